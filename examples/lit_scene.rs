@@ -0,0 +1,90 @@
+//! Visual check for the forward lit pass: a cube on a floor lit by a warm
+//! directional light casting shadows and two colored point lights.
+//!
+//! Run with `cargo run --example lit_scene`.
+
+use std::sync::Arc;
+use glam::{Mat4, Quat, Vec3};
+use log::LevelFilter;
+use mirage_engine::{DirectionalLight, LightManager, Mesh, Model, PointLight, Renderer, Transform};
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::WindowBuilder;
+
+const CAMERA_POSITION: Vec3 = Vec3::new(4.0, 3.5, 6.0);
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Info)
+        .init();
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("Mirage Engine - Lit Scene")
+        .with_inner_size(winit::dpi::LogicalSize::new(1280.0, 720.0))
+        .build(&event_loop)?;
+
+    let mut renderer = Renderer::new(&window).await?;
+    renderer.set_clear_color(0.02, 0.02, 0.03, 1.0);
+
+    let cube_mesh = Arc::new(Mesh::create_cube(renderer.device(), 1.0)?);
+    let material = Arc::new(renderer.create_material("Lit Scene Material", None));
+
+    let cube = Arc::new(Model::new_with_device(
+        renderer.device(),
+        cube_mesh.clone(),
+        material.clone(),
+        Transform {
+            position: Vec3::new(0.0, 0.5, 0.0),
+            rotation: Quat::from_rotation_y(30.0_f32.to_radians()),
+            scale: Vec3::ONE,
+        },
+    ));
+    let floor = Arc::new(Model::new_with_device(
+        renderer.device(),
+        cube_mesh,
+        material,
+        Transform::with_position_scale(Vec3::new(0.0, -0.1, 0.0), Vec3::new(10.0, 0.2, 10.0)),
+    ));
+
+    let mut lights = LightManager::new(8);
+    lights.add_light(Box::new(DirectionalLight::new(Vec3::new(-0.4, -1.0, -0.3), Vec3::new(1.0, 0.95, 0.85), 0.6)));
+    lights.add_light(Box::new(PointLight::new(Vec3::new(-1.5, 1.0, 1.5), Vec3::new(1.0, 0.2, 0.2), 6.0, 5.0)));
+    lights.add_light(Box::new(PointLight::new(Vec3::new(1.5, 1.0, -1.0), Vec3::new(0.2, 0.4, 1.0), 6.0, 5.0)));
+    renderer.set_light_manager(&lights);
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        match event {
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                *control_flow = ControlFlow::Exit;
+            }
+            Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
+                if let Err(e) = renderer.resize(size.width, size.height) {
+                    log::error!("Failed to resize renderer: {}", e);
+                }
+            }
+            Event::MainEventsCleared => {
+                window.request_redraw();
+            }
+            Event::RedrawRequested(_) => {
+                let (width, height) = renderer.size();
+                let aspect = width as f32 / height.max(1) as f32;
+                let view = Mat4::look_at_rh(CAMERA_POSITION, Vec3::ZERO, Vec3::Y);
+                let projection = Mat4::perspective_rh(60.0_f32.to_radians(), aspect, 0.1, 100.0);
+                renderer.set_camera(projection * view, CAMERA_POSITION);
+
+                renderer.draw_model(cube.clone(), true);
+                // The floor only receives shadows
+                renderer.draw_model(floor.clone(), false);
+
+                if let Err(e) = renderer.render_frame() {
+                    log::error!("Failed to render frame: {}", e);
+                }
+            }
+            _ => (),
+        }
+    });
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use crate::animation::{AnimationClip, TransformSample};
+
+/// Component that plays keyframed transform clips, see `transform_animation_system`
+///
+/// The time can also be set directly with `seek`, e.g. by a timeline scrubber.
+#[derive(Debug, Clone, Default)]
+pub struct Animator {
+    clips: HashMap<String, AnimationClip>,
+    /// Clip being played
+    current: Option<String>,
+    /// Seconds into the current clip
+    time: f32,
+    playing: bool,
+}
+
+impl Animator {
+    /// Create an animator without clips
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a clip, replacing any clip with the same name
+    pub fn with_clip(mut self, name: impl Into<String>, clip: AnimationClip) -> Self {
+        self.add_clip(name, clip);
+        self
+    }
+
+    /// Add a clip, replacing any clip with the same name
+    pub fn add_clip(&mut self, name: impl Into<String>, clip: AnimationClip) {
+        self.clips.insert(name.into(), clip);
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    /// Names of all clips
+    pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+        self.clips.keys().map(String::as_str)
+    }
+
+    /// Play a clip from its start, or resume it if it's the current clip
+    ///
+    /// A finished clip that doesn't loop starts over. Returns false if there's no such clip.
+    pub fn play(&mut self, name: &str) -> bool {
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+        if self.current.as_deref() != Some(name) || self.is_finished() {
+            self.current = Some(name.to_string());
+            self.time = 0.0;
+        }
+        self.playing = true;
+        true
+    }
+
+    /// Stop advancing, keeping the current time
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Stop and go back to the start of the current clip
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.time = 0.0;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Name of the clip being played
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Seconds into the current clip
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jump to `time` seconds into the current clip, clamped to its length
+    pub fn seek(&mut self, time: f32) {
+        let duration = self.current_clip_data().map_or(0.0, AnimationClip::duration);
+        self.time = time.clamp(0.0, duration);
+    }
+
+    /// Whether the current clip doesn't loop and has reached its end
+    pub fn is_finished(&self) -> bool {
+        self.current_clip_data().map_or(false, |clip| !clip.looping && self.time >= clip.duration())
+    }
+
+    /// Channel values of the current clip at the current time
+    pub fn sample(&self) -> Option<TransformSample> {
+        self.current_clip_data().map(|clip| clip.sample(self.time))
+    }
+
+    /// Advance by `delta_time` seconds, scaled by the clip's speed
+    ///
+    /// Returns true if a clip that doesn't loop finished during this call; it then
+    /// stays at its end and stops playing.
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let Some(clip) = self.current.as_ref().and_then(|name| self.clips.get(name)) else {
+            return false;
+        };
+
+        let duration = clip.duration();
+        self.time += delta_time * clip.speed;
+        if self.time < duration {
+            return false;
+        }
+        if clip.looping {
+            self.time = if duration > 0.0 { self.time % duration } else { 0.0 };
+            false
+        } else {
+            self.time = duration;
+            self.playing = false;
+            true
+        }
+    }
+
+    fn current_clip_data(&self) -> Option<&AnimationClip> {
+        self.clips.get(self.current.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3};
+    use crate::animation::TransformChannel;
+    use crate::ecs::{EcsManager, GlobalTransform, Transform3DComponent};
+
+    fn slide(looping: bool) -> AnimationClip {
+        AnimationClip::from_keyframes(TransformChannel::Position, vec![(0.0, Vec3::ZERO), (1.0, Vec3::new(10.0, 0.0, 0.0))])
+            .with_looping(looping)
+    }
+
+    #[test]
+    fn clip_that_does_not_loop_stops_at_its_end() {
+        let mut animator = Animator::new().with_clip("slide", slide(false));
+        animator.play("slide");
+        assert!(!animator.advance(0.5));
+        assert!(animator.advance(0.75));
+        assert_eq!(animator.time(), 1.0);
+        assert!(animator.is_finished());
+        assert!(!animator.is_playing());
+    }
+
+    #[test]
+    fn looping_clip_wraps_around() {
+        let mut animator = Animator::new().with_clip("slide", slide(true));
+        animator.play("slide");
+        assert!(!animator.advance(1.25));
+        assert!((animator.time() - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn run_systems_moves_the_world_matrix_in_the_same_frame() {
+        let mut ecs = EcsManager::new();
+        let mut animator = Animator::new().with_clip("slide", slide(false));
+        animator.play("slide");
+        let handle = ecs.create_entity((Transform3DComponent::new(Vec3::ZERO, Quat::IDENTITY, Vec3::ONE), animator));
+
+        ecs.run_systems(0.5);
+        let entity = ecs.get_entity(&handle).unwrap();
+        let position = ecs.world().get::<&Transform3DComponent>(entity).unwrap().position;
+        assert!(position.abs_diff_eq(Vec3::new(5.0, 0.0, 0.0), 1e-5));
+        let matrix = ecs.world().get::<&GlobalTransform>(entity).unwrap().matrix;
+        assert!(matrix.w_axis.truncate().abs_diff_eq(Vec3::new(5.0, 0.0, 0.0), 1e-5));
+    }
+}
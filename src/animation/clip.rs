@@ -0,0 +1,239 @@
+use glam::{EulerRot, Quat, Vec3};
+
+/// Part of a transform a keyframe track animates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransformChannel {
+    Position,
+    /// Keyframed as XYZ Euler angles in radians by `AnimationClip::from_keyframes`
+    Rotation,
+    Scale,
+}
+
+/// How values between two keyframes are computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Blend linearly, rotations along the shortest arc
+    #[default]
+    Linear,
+    /// Hold each keyframe's value until the next one
+    Step,
+}
+
+/// Values that can be blended between keyframes
+pub trait Keyframe: Copy {
+    /// Value at `t` between `self` (0) and `other` (1)
+    fn interpolate(self, other: Self, t: f32) -> Self;
+}
+
+impl Keyframe for Vec3 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Keyframe for Quat {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// Keyframes of one channel, sorted by time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<(f32, T)>,
+}
+
+impl<T: Keyframe> Track<T> {
+    /// Create a track, sorting the keyframes by time
+    ///
+    /// Keyframes with a time that isn't finite are dropped. Of several keyframes at
+    /// the same time, the last one given wins when sampling past it.
+    pub fn new(mut keyframes: Vec<(f32, T)>) -> Self {
+        keyframes.retain(|(time, _)| time.is_finite());
+        // Stable, so keyframes at the same time keep their order
+        keyframes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self { keyframes }
+    }
+
+    /// Keyframes as `(time, value)`, sorted by time
+    pub fn keyframes(&self) -> &[(f32, T)] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe, 0 without keyframes
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    /// Value at `time`, holding the first and last keyframes outside of them
+    pub fn sample(&self, time: f32, interpolation: Interpolation) -> Option<T> {
+        let (first, last) = (self.keyframes.first()?, self.keyframes.last()?);
+        if time <= first.0 {
+            return Some(first.1);
+        }
+        if time >= last.0 {
+            return Some(last.1);
+        }
+
+        // First keyframe after `time`, there is one as `time` is before the last
+        let next = self.keyframes.partition_point(|(key_time, _)| *key_time <= time);
+        let (start_time, start) = self.keyframes[next - 1];
+        let (end_time, end) = self.keyframes[next];
+        match interpolation {
+            Interpolation::Step => Some(start),
+            Interpolation::Linear => Some(start.interpolate(end, (time - start_time) / (end_time - start_time))),
+        }
+    }
+}
+
+/// Values of the animated channels at one point in time, `None` for channels the clip doesn't animate
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TransformSample {
+    pub position: Option<Vec3>,
+    pub rotation: Option<Quat>,
+    pub scale: Option<Vec3>,
+}
+
+/// Keyframed position, rotation and scale of a transform, played by an `Animator`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    pub position: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+    pub interpolation: Interpolation,
+    /// Playback rate, 2.0 plays the clip twice as fast
+    pub speed: f32,
+    /// Start over after the last keyframe; otherwise the clip stops on it
+    pub looping: bool,
+}
+
+impl Default for AnimationClip {
+    fn default() -> Self {
+        Self {
+            position: None,
+            rotation: None,
+            scale: None,
+            interpolation: Interpolation::Linear,
+            speed: 1.0,
+            looping: false,
+        }
+    }
+}
+
+impl AnimationClip {
+    /// A clip animating one channel through `keyframes` of `(seconds, value)`
+    ///
+    /// Keyframes don't need to be in time order. Rotation values are XYZ Euler angles
+    /// in radians, see `with_rotation_keyframes` for quaternions.
+    pub fn from_keyframes(channel: TransformChannel, keyframes: Vec<(f32, Vec3)>) -> Self {
+        Self::default().with_keyframes(channel, keyframes)
+    }
+
+    /// Animate another channel, replacing its keyframes if it already has some
+    pub fn with_keyframes(mut self, channel: TransformChannel, keyframes: Vec<(f32, Vec3)>) -> Self {
+        match channel {
+            TransformChannel::Position => self.position = Some(Track::new(keyframes)),
+            TransformChannel::Scale => self.scale = Some(Track::new(keyframes)),
+            TransformChannel::Rotation => {
+                let keyframes = keyframes.into_iter()
+                    .map(|(time, euler)| (time, Quat::from_euler(EulerRot::XYZ, euler.x, euler.y, euler.z)))
+                    .collect();
+                self = self.with_rotation_keyframes(keyframes);
+            }
+        }
+        self
+    }
+
+    /// Animate the rotation through quaternion keyframes
+    pub fn with_rotation_keyframes(mut self, keyframes: Vec<(f32, Quat)>) -> Self {
+        self.rotation = Some(Track::new(keyframes));
+        self
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Set the playback rate, negative speeds are treated as 0
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Time of the last keyframe of any channel, in clip seconds before `speed` is applied
+    pub fn duration(&self) -> f32 {
+        [
+            self.position.as_ref().map(Track::duration),
+            self.rotation.as_ref().map(Track::duration),
+            self.scale.as_ref().map(Track::duration),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f32::max)
+    }
+
+    /// Values of the animated channels at `time` seconds into the clip
+    pub fn sample(&self, time: f32) -> TransformSample {
+        TransformSample {
+            position: self.position.as_ref().and_then(|track| track.sample(time, self.interpolation)),
+            rotation: self.rotation.as_ref().and_then(|track| track.sample(time, self.interpolation)),
+            scale: self.scale.as_ref().and_then(|track| track.sample(time, self.interpolation)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn linear_sample_at_the_midpoint() {
+        let track = Track::new(vec![(0.0, Vec3::ZERO), (2.0, Vec3::new(4.0, -2.0, 0.0))]);
+        let value = track.sample(1.0, Interpolation::Linear).unwrap();
+        assert!(value.abs_diff_eq(Vec3::new(2.0, -1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn step_holds_the_previous_keyframe() {
+        let track = Track::new(vec![(0.0, Vec3::ZERO), (1.0, Vec3::ONE)]);
+        assert_eq!(track.sample(0.99, Interpolation::Step), Some(Vec3::ZERO));
+        assert_eq!(track.sample(1.0, Interpolation::Step), Some(Vec3::ONE));
+    }
+
+    #[test]
+    fn keyframes_are_sorted_and_held_outside_the_track() {
+        let track = Track::new(vec![(2.0, Vec3::ONE), (f32::NAN, Vec3::X), (1.0, Vec3::ZERO)]);
+        assert_eq!(track.keyframes().len(), 2);
+        assert_eq!(track.duration(), 2.0);
+        assert_eq!(track.sample(0.0, Interpolation::Linear), Some(Vec3::ZERO));
+        assert_eq!(track.sample(5.0, Interpolation::Linear), Some(Vec3::ONE));
+        assert_eq!(Track::<Vec3>::new(Vec::new()).sample(0.0, Interpolation::Linear), None);
+    }
+
+    #[test]
+    fn rotation_midpoint_is_half_the_angle() {
+        let clip = AnimationClip::from_keyframes(
+            TransformChannel::Rotation,
+            vec![(0.0, Vec3::ZERO), (1.0, Vec3::new(0.0, FRAC_PI_2, 0.0))],
+        );
+        let rotation = clip.sample(0.5).rotation.unwrap();
+        let expected = Quat::from_rotation_y(FRAC_PI_2 / 2.0);
+        assert!(rotation.angle_between(expected) < 1e-4);
+    }
+
+    #[test]
+    fn sample_leaves_unanimated_channels_out() {
+        let clip = AnimationClip::from_keyframes(TransformChannel::Scale, vec![(0.0, Vec3::ONE), (1.0, Vec3::splat(3.0))]);
+        let sample = clip.sample(0.5);
+        assert_eq!(sample.position, None);
+        assert_eq!(sample.rotation, None);
+        assert!(sample.scale.unwrap().abs_diff_eq(Vec3::splat(2.0), 1e-6));
+    }
+}
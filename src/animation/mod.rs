@@ -0,0 +1,6 @@
+// Keyframe animation of transforms
+mod clip;
+mod animator;
+
+pub use clip::{AnimationClip, Interpolation, Keyframe, Track, TransformChannel, TransformSample};
+pub use animator::Animator;
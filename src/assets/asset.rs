@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use wgpu::{Device, Queue};
+use crate::rendering::{Font, Model, Texture};
+
+/// Device and queue GPU assets are created with
+#[derive(Clone)]
+pub struct GpuContext {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+}
+
+/// Data an `AssetManager` can hold
+pub trait Asset: Send + Sync + 'static {
+    /// Approximate memory the asset takes up, in CPU or GPU memory
+    fn byte_size(&self) -> usize;
+}
+
+/// Assets an `AssetManager` can load from a file
+pub trait LoadAsset: Asset + Sized {
+    /// Load the asset, `gpu` is `None` for managers created without a device
+    fn load(path: &Path, gpu: Option<&GpuContext>) -> Result<Self>;
+}
+
+impl Asset for Texture {
+    fn byte_size(&self) -> usize {
+        let (width, height) = self.size;
+        let block_size = self.format.block_size(None).unwrap_or(4) as usize;
+        (0..self.texture.mip_level_count())
+            .map(|level| (width >> level).max(1) as usize * (height >> level).max(1) as usize)
+            .sum::<usize>() * block_size
+    }
+}
+
+impl LoadAsset for Texture {
+    fn load(path: &Path, gpu: Option<&GpuContext>) -> Result<Self> {
+        let gpu = gpu.ok_or_else(|| anyhow!("Loading texture {} needs a GPU device", path.display()))?;
+        Texture::from_file(&gpu.device, &gpu.queue, path, None)
+    }
+}
+
+impl Asset for Font {
+    fn byte_size(&self) -> usize {
+        self.data_size()
+    }
+}
+
+impl LoadAsset for Font {
+    fn load(path: &Path, _gpu: Option<&GpuContext>) -> Result<Self> {
+        Font::load(path)
+    }
+}
+
+/// Models are loaded through the renderer, see `AssetManager::insert`
+///
+/// Meshes shared with other models are counted for each of them.
+impl Asset for Model {
+    fn byte_size(&self) -> usize {
+        self.meshes()
+            .map(|(mesh, _)| (mesh.vertex_buffer().size() + mesh.index_buffer().size()) as usize)
+            .sum()
+    }
+}
@@ -0,0 +1,192 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, Weak};
+use anyhow::{anyhow, Result};
+use log::info;
+use wgpu::{Device, Queue};
+use crate::assets::{Asset, GpuContext, Handle, LoadAsset};
+use crate::assets::handle::AssetSlot;
+
+/// Type-erased view of an asset slot, for the cache and the stats
+trait ErasedSlot: Send + Sync {
+    fn byte_size(&self) -> usize;
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: Asset> ErasedSlot for AssetSlot<T> {
+    fn byte_size(&self) -> usize {
+        self.asset.read().unwrap().byte_size()
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// Loads assets by path and shares them through handles
+///
+/// Loading a path that's already loaded returns a handle to the same asset. The
+/// manager only keeps weak references, so an asset is freed as soon as its last
+/// handle is dropped and is read from disk again by the next `load`.
+pub struct AssetManager {
+    gpu: Option<GpuContext>,
+    /// Loaded assets by type and canonical path
+    assets: HashMap<(TypeId, PathBuf), Weak<dyn ErasedSlot>>,
+}
+
+impl AssetManager {
+    /// Create a manager for assets that don't need a GPU, e.g. fonts
+    pub fn new() -> Self {
+        Self {
+            gpu: None,
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Create a manager that can also load GPU assets such as textures
+    pub fn with_gpu(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self {
+            gpu: Some(GpuContext { device, queue }),
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Load an asset, or share the already loaded one
+    pub fn load<T: LoadAsset>(&mut self, path: impl AsRef<Path>) -> Result<Handle<T>> {
+        let path = canonical_path(path.as_ref());
+        if let Some(handle) = self.cached::<T>(&path) {
+            return Ok(handle);
+        }
+
+        let asset = T::load(&path, self.gpu.as_ref())?;
+        info!("Loaded asset {}", path.display());
+        Ok(self.store(path, asset))
+    }
+
+    /// Add an asset loaded some other way under `path`, e.g. a model from the renderer
+    ///
+    /// If an asset of the same type is already loaded from `path`, it is replaced
+    /// for all of its handles and the existing handle is returned.
+    pub fn insert<T: Asset>(&mut self, path: impl AsRef<Path>, asset: T) -> Handle<T> {
+        let path = canonical_path(path.as_ref());
+        match self.cached::<T>(&path) {
+            Some(handle) => {
+                handle.replace(asset);
+                handle
+            }
+            None => self.store(path, asset),
+        }
+    }
+
+    /// The asset behind a handle
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Arc<T> {
+        handle.get()
+    }
+
+    /// Load an asset from its file again, every handle to it sees the new version
+    ///
+    /// On failure the old version is kept.
+    pub fn reload<T: LoadAsset>(&mut self, handle: &Handle<T>) -> Result<()> {
+        let path = handle.path().ok_or_else(|| anyhow!("Asset wasn't loaded from a file"))?;
+        let asset = T::load(path, self.gpu.as_ref())?;
+        info!("Reloaded asset {}", path.display());
+        handle.replace(asset);
+        Ok(())
+    }
+
+    /// Number of assets that still have handles
+    pub fn loaded_count(&mut self) -> usize {
+        self.remove_released();
+        self.assets.len()
+    }
+
+    /// Approximate memory used by the assets that still have handles
+    pub fn bytes(&mut self) -> usize {
+        self.remove_released();
+        self.assets.values()
+            .filter_map(Weak::upgrade)
+            .map(|slot| slot.byte_size())
+            .sum()
+    }
+
+    /// Forget the assets whose last handle was dropped
+    fn remove_released(&mut self) {
+        self.assets.retain(|_, slot| slot.strong_count() > 0);
+    }
+
+    fn cached<T: Asset>(&self, path: &Path) -> Option<Handle<T>> {
+        let slot = self.assets.get(&(TypeId::of::<T>(), path.to_path_buf()))?.upgrade()?;
+        slot.into_any().downcast::<AssetSlot<T>>().ok().map(Handle::from_slot)
+    }
+
+    fn store<T: Asset>(&mut self, path: PathBuf, asset: T) -> Handle<T> {
+        let slot = Arc::new(AssetSlot {
+            path: Some(path.clone()),
+            asset: RwLock::new(Arc::new(asset)),
+        });
+        let erased: Arc<dyn ErasedSlot> = slot.clone();
+        self.assets.insert((TypeId::of::<T>(), path), Arc::downgrade(&erased));
+        Handle::from_slot(slot)
+    }
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The path with symlinks and `..` resolved, so one file is cached once
+///
+/// Paths that don't exist are kept as given, loading them fails anyway.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::Font;
+
+    fn font_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts/DejaVuSans.ttf")
+    }
+
+    #[test]
+    fn same_path_shares_one_handle() {
+        let mut assets = AssetManager::new();
+        let font = assets.load::<Font>(font_path()).unwrap();
+        let same = assets.load::<Font>(font_path()).unwrap();
+        let other_spelling = assets.load::<Font>(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts/../fonts/DejaVuSans.ttf")).unwrap();
+
+        assert_eq!(font, same);
+        assert_eq!(font, other_spelling);
+        assert!(Arc::ptr_eq(&font.get(), &same.get()));
+        assert_eq!(font.handle_count(), 3);
+        assert_eq!(assets.loaded_count(), 1);
+        assert_eq!(assets.bytes(), font.get().data_size());
+    }
+
+    #[test]
+    fn dropping_the_last_handle_frees_the_asset() {
+        let mut assets = AssetManager::new();
+        let font = assets.load::<Font>(font_path()).unwrap();
+        let clone = font.clone();
+        let asset = Arc::downgrade(&font.get());
+
+        drop(font);
+        assert_eq!(assets.loaded_count(), 1);
+        assert!(asset.upgrade().is_some());
+
+        drop(clone);
+        assert_eq!(assets.loaded_count(), 0);
+        assert_eq!(assets.bytes(), 0);
+        assert!(asset.upgrade().is_none());
+
+        let reloaded = assets.load::<Font>(font_path()).unwrap();
+        assert_eq!(reloaded.handle_count(), 1);
+        assert_eq!(assets.loaded_count(), 1);
+    }
+}
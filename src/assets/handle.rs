@@ -0,0 +1,84 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Shared storage behind the handles of one asset
+pub(crate) struct AssetSlot<T> {
+    /// File the asset was loaded from, `None` for assets created in code
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) asset: RwLock<Arc<T>>,
+}
+
+/// Cheap, clonable reference to an asset
+///
+/// Every clone sees the same asset, including after `AssetManager::reload`.
+/// The asset is freed once the last handle and every `Arc` taken from `get` is dropped.
+pub struct Handle<T> {
+    slot: Arc<AssetSlot<T>>,
+}
+
+impl<T> Handle<T> {
+    /// Wrap an asset that isn't managed by an `AssetManager`
+    pub fn new(asset: T) -> Self {
+        Self::from_arc(Arc::new(asset))
+    }
+
+    /// Wrap a shared asset that isn't managed by an `AssetManager`
+    pub fn from_arc(asset: Arc<T>) -> Self {
+        Self::from_slot(Arc::new(AssetSlot { path: None, asset: RwLock::new(asset) }))
+    }
+
+    pub(crate) fn from_slot(slot: Arc<AssetSlot<T>>) -> Self {
+        Self { slot }
+    }
+
+    /// The asset as it is now; a later reload doesn't change the returned `Arc`
+    pub fn get(&self) -> Arc<T> {
+        self.slot.asset.read().unwrap().clone()
+    }
+
+    /// File the asset was loaded from
+    pub fn path(&self) -> Option<&Path> {
+        self.slot.path.as_deref()
+    }
+
+    /// Number of handles to this asset, including this one
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.slot)
+    }
+
+    /// Swap in a new version of the asset for every handle
+    pub(crate) fn replace(&self, asset: T) {
+        *self.slot.asset.write().unwrap() = Arc::new(asset);
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self { slot: self.slot.clone() }
+    }
+}
+
+/// Handles are equal when they refer to the same asset
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.slot, &other.slot)
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> From<Arc<T>> for Handle<T> {
+    fn from(asset: Arc<T>) -> Self {
+        Self::from_arc(asset)
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("type", &std::any::type_name::<T>())
+            .field("path", &self.slot.path)
+            .finish()
+    }
+}
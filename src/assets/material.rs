@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use crate::assets::{Asset, GpuContext, LoadAsset};
+use crate::rendering::{BlendMode, MaterialParams};
+
+/// File extension used for material assets
+pub const MATERIAL_EXTENSION: &str = "mat";
+
+/// What a material is drawn on, which decides the settings it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MaterialKind {
+    /// Meshes drawn by the lit pass: base color, texture, metallic and roughness
+    #[default]
+    Lit,
+    /// Sprites: tint and texture
+    Sprite,
+}
+
+impl MaterialKind {
+    pub const ALL: [MaterialKind; 2] = [MaterialKind::Lit, MaterialKind::Sprite];
+
+    /// Name shown in the editor
+    pub fn label(self) -> &'static str {
+        match self {
+            MaterialKind::Lit => "Lit (3D)",
+            MaterialKind::Sprite => "Sprite (2D)",
+        }
+    }
+}
+
+/// Surface settings saved as a `.mat` file and shared by every entity using it
+///
+/// Entities reference materials through a `Handle`, so replacing the asset behind
+/// the handle updates all of them. Fields missing from a file take their default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialAsset {
+    pub kind: MaterialKind,
+    /// Color multiplied with the texture, the tint of sprites
+    pub base_color: [f32; 4],
+    /// Image file, relative to the material file's folder
+    ///
+    /// A texture that can't be loaded is drawn as a checkerboard, see `Texture::missing`.
+    pub texture: Option<PathBuf>,
+    /// Lit materials only, see `MaterialParams::metallic`
+    pub metallic: f32,
+    /// Lit materials only, see `MaterialParams::roughness`
+    pub roughness: f32,
+    /// How the surface is blended with what's already drawn
+    pub blend_mode: BlendMode,
+}
+
+impl Default for MaterialAsset {
+    fn default() -> Self {
+        let params = MaterialParams::default();
+        Self {
+            kind: MaterialKind::Lit,
+            base_color: params.base_color,
+            texture: None,
+            metallic: params.metallic,
+            roughness: params.roughness,
+            blend_mode: BlendMode::AlphaBlend,
+        }
+    }
+}
+
+impl MaterialAsset {
+    /// Surface values for the lit pass
+    pub fn params(&self) -> MaterialParams {
+        MaterialParams {
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+        }
+    }
+
+    /// Path of the texture, resolved against the folder of `material_path` if it's relative
+    pub fn texture_path(&self, material_path: Option<&Path>) -> Option<PathBuf> {
+        let texture = self.texture.as_ref()?;
+        match material_path.and_then(Path::parent) {
+            Some(folder) if texture.is_relative() => Some(folder.join(texture)),
+            _ => Some(texture.clone()),
+        }
+    }
+
+    /// Serialize the material to a RON string
+    pub fn to_ron(&self) -> Result<String> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Parse a material from a RON string
+    pub fn from_ron(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    /// Save the material to a RON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Load a material from a RON file
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+impl Asset for MaterialAsset {
+    fn byte_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.texture.as_ref().map_or(0, |path| path.as_os_str().len())
+    }
+}
+
+/// Only the settings are loaded, GPU materials are created from them by `material_asset_system`
+impl LoadAsset for MaterialAsset {
+    fn load(path: &Path, _gpu: Option<&GpuContext>) -> Result<Self> {
+        MaterialAsset::load(path)
+    }
+}
@@ -0,0 +1,10 @@
+// Asset module
+mod asset;
+mod handle;
+mod asset_manager;
+mod material;
+
+pub use asset::{Asset, LoadAsset, GpuContext};
+pub use handle::Handle;
+pub use asset_manager::AssetManager;
+pub use material::{MaterialAsset, MaterialKind, MATERIAL_EXTENSION};
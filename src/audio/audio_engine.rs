@@ -1,14 +1,17 @@
 use std::collections::HashMap;
-use std::io::BufReader;
-use std::fs::File;
 use std::path::Path;
-use std::sync::Arc;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use rodio::source::ChannelVolume;
 use glam::Vec3;
 use anyhow::{Result, anyhow};
 
-use super::audio_source::AudioSource;
-use super::audio_listener::AudioListener;
+use super::audio_source::{AudioData, AudioSource, MemoryPlayback, open_decoder, open_looped_decoder};
+use super::audio_listener::{AudioListener, stereo_volumes};
+
+/// How often playing spatial sounds pick up new channel volumes
+const PAN_UPDATE_PERIOD: Duration = Duration::from_millis(5);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AudioCategory {
@@ -25,14 +28,93 @@ pub enum PlaybackStatus {
     Stopped,
 }
 
+/// A gain ramp over wall-clock time
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: Duration,
+    /// Stop the sound once the ramp finishes
+    stop_when_done: bool,
+}
+
+impl Fade {
+    /// Gain at `now` and whether the ramp has finished
+    fn sample(&self, now: Instant) -> (f32, bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return (self.to, true);
+        }
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        (self.from + (self.to - self.from) * t, false)
+    }
+}
+
+/// A sound that is currently playing or paused
+///
+/// The sink owns the decoder, so a streamed file stays open while the sound exists.
+struct ActiveSound {
+    sink: Sink,
+    source: Arc<AudioSource>,
+    looping: bool,
+    /// Position the sound is played from, `None` for non-spatial playback
+    position: Option<Vec3>,
+    /// Distance attenuation from the last spatialization update
+    attenuation: f32,
+    /// Left and right channel volumes read by the playing stream
+    channel_volumes: Arc<Mutex<[f32; 2]>>,
+    /// Current gain of the fade, 1.0 when not fading
+    fade_gain: f32,
+    fade: Option<Fade>,
+}
+
+/// Master and category bus volumes, each clamped to `0.0..=1.0`
+#[derive(Debug, Clone)]
+struct Mixer {
+    master_volume: f32,
+    category_volumes: HashMap<AudioCategory, f32>,
+}
+
+impl Mixer {
+    fn new() -> Self {
+        Self {
+            master_volume: 1.0,
+            category_volumes: HashMap::from([
+                (AudioCategory::Music, 0.8),
+                (AudioCategory::SoundEffect, 1.0),
+                (AudioCategory::Ambient, 0.6),
+                (AudioCategory::Voice, 1.0),
+            ]),
+        }
+    }
+
+    fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+    }
+
+    fn set_category_volume(&mut self, category: AudioCategory, volume: f32) {
+        self.category_volumes.insert(category, volume.clamp(0.0, 1.0));
+    }
+
+    fn category_volume(&self, category: AudioCategory) -> f32 {
+        self.category_volumes.get(&category).copied().unwrap_or(1.0)
+    }
+
+    /// Volume the buses apply to a sound of `category`
+    fn gain(&self, category: AudioCategory) -> f32 {
+        self.category_volume(category) * self.master_volume
+    }
+}
+
 pub struct AudioEngine {
     _stream: OutputStream,
     stream_handle: OutputStreamHandle,
     sources: HashMap<String, Arc<AudioSource>>,
-    active_sinks: HashMap<String, Sink>,
-    master_volume: f32,
-    category_volumes: HashMap<AudioCategory, f32>,
-    listener: AudioListener,
+    active_sinks: HashMap<String, ActiveSound>,
+    mixer: Mixer,
+    /// `None` until a listener is set, spatial sounds play centered until then
+    listener: Option<AudioListener>,
 }
 
 impl AudioEngine {
@@ -40,23 +122,17 @@ impl AudioEngine {
         let (stream, stream_handle) = OutputStream::try_default()
             .map_err(|e| anyhow!("Failed to create audio stream: {}", e))?;
         
-        let mut category_volumes = HashMap::new();
-        category_volumes.insert(AudioCategory::Music, 0.8);
-        category_volumes.insert(AudioCategory::SoundEffect, 1.0);
-        category_volumes.insert(AudioCategory::Ambient, 0.6);
-        category_volumes.insert(AudioCategory::Voice, 1.0);
-        
         Ok(Self {
             _stream: stream,
             stream_handle,
             sources: HashMap::new(),
             active_sinks: HashMap::new(),
-            master_volume: 1.0,
-            category_volumes,
-            listener: AudioListener::new(Vec3::ZERO, Vec3::Z),
+            mixer: Mixer::new(),
+            listener: None,
         })
     }
     
+    /// Load a sound decoded into memory, for short effects that should play without delay
     pub fn load_audio(&mut self, id: &str, path: impl AsRef<Path>, category: AudioCategory) -> Result<()> {
         let path = path.as_ref().to_path_buf();
         let source = AudioSource::new(id.to_string(), path, category)?;
@@ -64,38 +140,39 @@ impl AudioEngine {
         Ok(())
     }
     
+    /// Load a sound that is decoded from disk while it plays, for long music tracks
+    pub fn load_audio_streaming(&mut self, id: &str, path: impl AsRef<Path>, category: AudioCategory) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        let source = AudioSource::from_file_streaming(id.to_string(), path, category)?;
+        self.sources.insert(id.to_string(), Arc::new(source));
+        Ok(())
+    }
+    
+    /// Get a loaded source for configuration
+    ///
+    /// Returns `None` if the source doesn't exist or is currently playing.
+    pub fn source_mut(&mut self, id: &str) -> Option<&mut AudioSource> {
+        if self.active_sinks.contains_key(id) {
+            return None;
+        }
+        self.sources.get_mut(id).and_then(Arc::get_mut)
+    }
+    
+    /// Play a source, positioned at the source's own position if it is spatial
     pub fn play(&mut self, id: &str, looping: bool) -> Result<()> {
         let source = self.sources.get(id)
-            .ok_or_else(|| anyhow!("Audio with ID '{}' not found", id))?
-            .clone();
-        
-        let sink = Sink::try_new(&self.stream_handle)
-            .map_err(|e| anyhow!("Failed to create audio sink: {}", e))?;
-        
-        let file = File::open(&source.path)
-            .map_err(|e| anyhow!("Failed to open audio file '{}': {}", source.path.display(), e))?;
-        
-        let reader = BufReader::new(file);
-        let decoder = Decoder::new(reader)
-            .map_err(|e| anyhow!("Failed to decode audio '{}': {}", source.path.display(), e))?;
-        
-        let category_volume = self.category_volumes.get(&source.category).unwrap_or(&1.0);
-        sink.set_volume(*category_volume * self.master_volume);
-        
-        if looping {
-            sink.append(decoder.repeat_infinite());
-        } else {
-            sink.append(decoder);
-        }
-        
-        sink.play();
-        
-        self.active_sinks.insert(id.to_string(), sink);
+            .ok_or_else(|| anyhow!("Audio with ID '{}' not found", id))?;
+        let position = source.spatial.then_some(source.position);
         
-        Ok(())
+        self.start(id, position, looping)
     }
     
+    /// Play a source at `position`, whether or not the source is spatial
     pub fn play_at_position(&mut self, id: &str, position: Vec3, looping: bool) -> Result<()> {
+        self.start(id, Some(position), looping)
+    }
+    
+    fn start(&mut self, id: &str, position: Option<Vec3>, looping: bool) -> Result<()> {
         let source = self.sources.get(id)
             .ok_or_else(|| anyhow!("Audio with ID '{}' not found", id))?
             .clone();
@@ -103,35 +180,53 @@ impl AudioEngine {
         let sink = Sink::try_new(&self.stream_handle)
             .map_err(|e| anyhow!("Failed to create audio sink: {}", e))?;
         
-        let file = File::open(&source.path)
-            .map_err(|e| anyhow!("Failed to open audio file '{}': {}", source.path.display(), e))?;
-        
-        let reader = BufReader::new(file);
-        let decoder = Decoder::new(reader)
-            .map_err(|e| anyhow!("Failed to decode audio '{}': {}", source.path.display(), e))?;
-        
-        let distance = position.distance(self.listener.position);
-        let distance_factor = (1.0 / (1.0 + distance * 0.1)).min(1.0);
+        let decoded: Box<dyn Source<Item = i16> + Send> = match (&source.data, looping) {
+            (AudioData::Memory { samples, channels, sample_rate }, looping) => {
+                Box::new(MemoryPlayback::new(samples.clone(), *channels, *sample_rate, looping))
+            }
+            // Repeating would keep every decoded sample around, rewind the file instead
+            (AudioData::Streaming, true) => Box::new(open_looped_decoder(&source.path)?),
+            (AudioData::Streaming, false) => Box::new(open_decoder(&source.path)?),
+        };
         
-        let category_volume = self.category_volumes.get(&source.category).unwrap_or(&1.0);
-        sink.set_volume(*category_volume * self.master_volume * distance_factor);
+        let channel_volumes = Arc::new(Mutex::new([1.0, 1.0]));
         
-        if looping {
-            sink.append(decoder.repeat_infinite());
+        if position.is_some() {
+            // Mix down to mono and re-pan into stereo, picking up volume changes while playing
+            let controls = channel_volumes.clone();
+            let panned = ChannelVolume::new(decoded, vec![1.0, 1.0])
+                .periodic_access(PAN_UPDATE_PERIOD, move |channels| {
+                    let [left, right] = *controls.lock().unwrap();
+                    channels.set_volume(0, left);
+                    channels.set_volume(1, right);
+                });
+            sink.append(panned);
         } else {
-            sink.append(decoder);
+            sink.append(decoded);
         }
         
-        sink.play();
+        let mut sound = ActiveSound {
+            sink,
+            source,
+            looping,
+            position,
+            attenuation: 1.0,
+            channel_volumes,
+            fade_gain: 1.0,
+            fade: None,
+        };
+        self.spatialize(&mut sound);
+        sound.sink.set_volume(self.volume_of(&sound));
+        sound.sink.play();
         
-        self.active_sinks.insert(id.to_string(), sink);
+        self.active_sinks.insert(id.to_string(), sound);
         
         Ok(())
     }
     
     pub fn stop(&mut self, id: &str) -> Result<()> {
-        if let Some(sink) = self.active_sinks.remove(id) {
-            sink.stop();
+        if let Some(sound) = self.active_sinks.remove(id) {
+            sound.sink.stop();
             Ok(())
         } else {
             Err(anyhow!("No active audio with ID '{}'", id))
@@ -139,8 +234,8 @@ impl AudioEngine {
     }
     
     pub fn pause(&mut self, id: &str) -> Result<()> {
-        if let Some(sink) = self.active_sinks.get(id) {
-            sink.pause();
+        if let Some(sound) = self.active_sinks.get(id) {
+            sound.sink.pause();
             Ok(())
         } else {
             Err(anyhow!("No active audio with ID '{}'", id))
@@ -148,41 +243,184 @@ impl AudioEngine {
     }
     
     pub fn resume(&mut self, id: &str) -> Result<()> {
-        if let Some(sink) = self.active_sinks.get(id) {
-            sink.play();
+        if let Some(sound) = self.active_sinks.get(id) {
+            sound.sink.play();
             Ok(())
         } else {
             Err(anyhow!("No active audio with ID '{}'", id))
         }
     }
     
+    /// Set the volume applied to every sound
+    ///
+    /// Clamped to `0.0..=1.0`, the mixer never amplifies.
     pub fn set_master_volume(&mut self, volume: f32) {
-        self.master_volume = volume.clamp(0.0, 1.0);
+        self.mixer.set_master_volume(volume);
         self.update_all_volumes();
     }
     
+    pub fn master_volume(&self) -> f32 {
+        self.mixer.master_volume
+    }
+    
+    /// Set the volume of a category bus, applied to playing and future sounds
+    ///
+    /// Clamped to `0.0..=1.0`, the mixer never amplifies.
     pub fn set_category_volume(&mut self, category: AudioCategory, volume: f32) {
-        self.category_volumes.insert(category, volume.clamp(0.0, 1.0));
+        self.mixer.set_category_volume(category, volume);
         self.update_all_volumes();
     }
     
+    pub fn category_volume(&self, category: AudioCategory) -> f32 {
+        self.mixer.category_volume(category)
+    }
+    
     fn update_all_volumes(&mut self) {
-        for (id, sink) in &self.active_sinks {
-            if let Some(source) = self.sources.get(id) {
-                let category_volume = self.category_volumes.get(&source.category).unwrap_or(&1.0);
-                sink.set_volume(*category_volume * self.master_volume);
+        for sound in self.active_sinks.values() {
+            sound.sink.set_volume(self.volume_of(sound));
+        }
+    }
+    
+    /// Sink volume for a sound
+    ///
+    /// Source gain * category volume * master volume, scaled by distance attenuation and fades.
+    fn volume_of(&self, sound: &ActiveSound) -> f32 {
+        sound.source.volume
+            * self.mixer.gain(sound.source.category)
+            * sound.attenuation
+            * sound.fade_gain
+    }
+    
+    /// Ramp a playing sound up from silence to full volume
+    pub fn fade_in(&mut self, id: &str, duration: Duration) -> Result<()> {
+        let sound = self.active_sinks.get_mut(id)
+            .ok_or_else(|| anyhow!("No active audio with ID '{}'", id))?;
+        
+        sound.fade_gain = 0.0;
+        sound.fade = Some(Fade {
+            from: 0.0,
+            to: 1.0,
+            start: Instant::now(),
+            duration,
+            stop_when_done: false,
+        });
+        
+        let volume = self.volume_of(&self.active_sinks[id]);
+        self.active_sinks[id].sink.set_volume(volume);
+        Ok(())
+    }
+    
+    /// Ramp a playing sound down to silence and stop it
+    ///
+    /// Does nothing if the sound isn't playing.
+    pub fn fade_out(&mut self, id: &str, duration: Duration) {
+        if let Some(sound) = self.active_sinks.get_mut(id) {
+            sound.fade = Some(Fade {
+                from: sound.fade_gain,
+                to: 0.0,
+                start: Instant::now(),
+                duration,
+                stop_when_done: true,
+            });
+        }
+    }
+    
+    /// Fade `from` out while fading `to` in over the same duration
+    ///
+    /// `to` is started if it isn't already playing, looping if `from` was looping.
+    pub fn crossfade(&mut self, from: &str, to: &str, duration: Duration) -> Result<()> {
+        if !self.active_sinks.contains_key(to) {
+            let looping = self.active_sinks.get(from).map_or(false, |sound| sound.looping);
+            self.play(to, looping)?;
+        }
+        
+        self.fade_out(from, duration);
+        self.fade_in(to, duration)
+    }
+    
+    /// Advance fades and spatialization, call once per frame
+    ///
+    /// Sounds that played to their end are dropped, closing streamed files.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let mut finished: Vec<String> = self.active_sinks.iter()
+            .filter(|(_, sound)| sound.sink.empty())
+            .map(|(id, _)| id.clone())
+            .collect();
+        
+        for (id, sound) in self.active_sinks.iter_mut() {
+            let Some(fade) = sound.fade else { continue };
+            let (gain, done) = fade.sample(now);
+            sound.fade_gain = gain;
+            
+            if done {
+                sound.fade = None;
+                if fade.stop_when_done {
+                    finished.push(id.clone());
+                }
+            }
+        }
+        
+        for id in finished {
+            if let Some(sound) = self.active_sinks.remove(&id) {
+                sound.sink.stop();
             }
         }
+        
+        self.update_spatialization();
+        self.update_all_volumes();
     }
     
     pub fn update_listener(&mut self, position: Vec3, forward: Vec3) {
-        self.listener.position = position;
-        self.listener.forward = forward;
+        self.listener = Some(AudioListener::new(position, forward));
+    }
+    
+    /// Move a playing sound, it picks up the new position on the next spatialization update
+    pub fn set_sound_position(&mut self, id: &str, position: Vec3) -> Result<()> {
+        match self.active_sinks.get_mut(id) {
+            Some(sound) if sound.position.is_some() => {
+                sound.position = Some(position);
+                Ok(())
+            }
+            Some(_) => Err(anyhow!("Audio with ID '{}' is not playing spatially", id)),
+            None => Err(anyhow!("No active audio with ID '{}'", id)),
+        }
+    }
+    
+    /// Recompute attenuation and stereo pan of all spatial sounds
+    ///
+    /// Called by `update`, call it directly only when not using fades.
+    pub fn update_spatialization(&mut self) {
+        let mut sounds = std::mem::take(&mut self.active_sinks);
+        for sound in sounds.values_mut() {
+            if sound.position.is_some() {
+                self.spatialize(sound);
+                sound.sink.set_volume(self.volume_of(sound));
+            }
+        }
+        self.active_sinks = sounds;
+    }
+    
+    /// Update the attenuation and channel volumes of a sound from the listener
+    fn spatialize(&self, sound: &mut ActiveSound) {
+        let (attenuation, pan) = match (sound.position, &self.listener) {
+            (Some(position), Some(listener)) => (
+                sound.source.attenuation(listener.distance_to(position)),
+                listener.pan(position),
+            ),
+            _ => (1.0, 0.0),
+        };
+        
+        sound.attenuation = attenuation;
+        *sound.channel_volumes.lock().unwrap() = stereo_volumes(pan);
     }
     
+    /// Whether a source is playing, paused or stopped, `None` if it isn't loaded
+    ///
+    /// A sound that played to its end counts as stopped, even before `update` drops it.
     pub fn get_playback_status(&self, id: &str) -> Option<PlaybackStatus> {
-        if let Some(sink) = self.active_sinks.get(id) {
-            if sink.is_paused() {
+        if let Some(sound) = self.active_sinks.get(id).filter(|sound| !sound.sink.empty()) {
+            if sound.sink.is_paused() {
                 Some(PlaybackStatus::Paused)
             } else {
                 Some(PlaybackStatus::Playing)
@@ -197,8 +435,132 @@ impl AudioEngine {
     }
     
     pub fn stop_all(&mut self) {
-        for (_, sink) in self.active_sinks.drain() {
-            sink.stop();
+        for (_, sound) in self.active_sinks.drain() {
+            sound.sink.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::audio_source::write_wav;
+
+    /// An engine on the default output device, `None` on machines without one
+    fn engine() -> Option<AudioEngine> {
+        match AudioEngine::new() {
+            Ok(engine) => Some(engine),
+            Err(e) => {
+                eprintln!("Skipping, no audio output: {}", e);
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn category_volume_only_affects_its_category() {
+        let mut mixer = Mixer::new();
+        mixer.set_category_volume(AudioCategory::Music, 1.0);
+        mixer.set_category_volume(AudioCategory::SoundEffect, 0.25);
+        assert_eq!(mixer.gain(AudioCategory::SoundEffect), 0.25);
+        assert_eq!(mixer.gain(AudioCategory::Music), 1.0);
+
+        mixer.set_category_volume(AudioCategory::Music, 0.5);
+        assert_eq!(mixer.gain(AudioCategory::SoundEffect), 0.25);
+        assert_eq!(mixer.gain(AudioCategory::Music), 0.5);
+    }
+
+    #[test]
+    fn master_volume_scales_every_category() {
+        let mut mixer = Mixer::new();
+        mixer.set_category_volume(AudioCategory::Music, 0.5);
+        mixer.set_category_volume(AudioCategory::SoundEffect, 1.0);
+        mixer.set_master_volume(0.5);
+        assert_eq!(mixer.gain(AudioCategory::Music), 0.25);
+        assert_eq!(mixer.gain(AudioCategory::SoundEffect), 0.5);
+    }
+
+    #[test]
+    fn volumes_are_clamped() {
+        let mut mixer = Mixer::new();
+        mixer.set_category_volume(AudioCategory::Voice, 3.0);
+        mixer.set_master_volume(-1.0);
+        assert_eq!(mixer.category_volume(AudioCategory::Voice), 1.0);
+        assert_eq!(mixer.master_volume, 0.0);
+        assert_eq!(mixer.gain(AudioCategory::Voice), 0.0);
+    }
+
+    #[test]
+    fn sfx_volume_changes_playing_sfx_and_leaves_music() {
+        let Some(mut engine) = engine() else {
+            return;
+        };
+        let path = write_wav(&[0; 8000]);
+        engine.load_audio("hit", &path, AudioCategory::SoundEffect).unwrap();
+        engine.load_audio_streaming("theme", &path, AudioCategory::Music).unwrap();
+        engine.source_mut("hit").unwrap().set_volume(0.5);
+        engine.play("hit", true).unwrap();
+        engine.play("theme", true).unwrap();
+
+        let sink_volume = |engine: &AudioEngine, id: &str| engine.active_sinks[id].sink.volume();
+        let music_before = sink_volume(&engine, "theme");
+        assert_eq!(music_before, engine.sources["theme"].volume * engine.mixer.gain(AudioCategory::Music));
+
+        engine.set_category_volume(AudioCategory::SoundEffect, 0.25);
+        assert_eq!(engine.category_volume(AudioCategory::SoundEffect), 0.25);
+        assert_eq!(sink_volume(&engine, "hit"), 0.5 * engine.mixer.gain(AudioCategory::SoundEffect));
+        assert_eq!(sink_volume(&engine, "hit"), 0.125);
+        assert_eq!(sink_volume(&engine, "theme"), music_before);
+
+        engine.stop_all();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn streamed_source_reports_playback_status() {
+        let Some(mut engine) = engine() else {
+            return;
+        };
+        // A tenth of a second at 8000 Hz
+        let path = write_wav(&[0; 800]);
+        engine.load_audio_streaming("music", &path, AudioCategory::Music).unwrap();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Stopped));
+        assert_eq!(engine.get_playback_status("missing"), None);
+
+        engine.play("music", false).unwrap();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Playing));
+        engine.pause("music").unwrap();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Paused));
+        engine.resume("music").unwrap();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Playing));
+        engine.stop("music").unwrap();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Stopped));
+
+        // Played to its end
+        engine.play("music", false).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while engine.get_playback_status("music") == Some(PlaybackStatus::Playing) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
         }
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Stopped));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn looping_streamed_source_keeps_playing_past_its_end() {
+        let Some(mut engine) = engine() else {
+            return;
+        };
+        let path = write_wav(&[0; 800]);
+        engine.load_audio_streaming("music", &path, AudioCategory::Music).unwrap();
+        engine.play("music", true).unwrap();
+
+        std::thread::sleep(Duration::from_millis(300));
+        engine.update();
+        assert_eq!(engine.get_playback_status("music"), Some(PlaybackStatus::Playing));
+
+        engine.stop_all();
+        std::fs::remove_file(&path).unwrap();
     }
-} 
\ No newline at end of file
+}
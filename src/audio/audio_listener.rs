@@ -33,4 +33,61 @@ impl AudioListener {
     pub fn distance_to(&self, position: Vec3) -> f32 {
         self.position.distance(position)
     }
-} 
\ No newline at end of file
+    
+    /// Stereo pan of a sound at `position`, from -1.0 (left) to 1.0 (right)
+    ///
+    /// Assumes Y is up. Sounds directly in front, behind, above or on top of the
+    /// listener are centered.
+    pub fn pan(&self, position: Vec3) -> f32 {
+        let right = self.forward.cross(Vec3::Y).normalize_or_zero();
+        let direction = (position - self.position).normalize_or_zero();
+        direction.dot(right).clamp(-1.0, 1.0)
+    }
+}
+
+/// Left and right channel volumes for a pan from -1.0 (left) to 1.0 (right)
+///
+/// A centered sound keeps both channels at full volume.
+pub fn stereo_volumes(pan: f32) -> [f32; 2] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_on_the_right_pans_right() {
+        let listener = AudioListener::new(Vec3::ZERO, Vec3::NEG_Z);
+        let pan = listener.pan(Vec3::new(5.0, 0.0, 0.0));
+        assert!((pan - 1.0).abs() < 1e-6);
+        let [left, right] = stereo_volumes(pan);
+        assert!(right > left);
+        assert_eq!(right, 1.0);
+    }
+
+    #[test]
+    fn pan_follows_the_listener_orientation() {
+        // Facing +X, the right hand points along +Z
+        let listener = AudioListener::new(Vec3::new(1.0, 0.0, 1.0), Vec3::X);
+        assert!(listener.pan(Vec3::new(1.0, 0.0, 4.0)) > 0.99);
+        assert!(listener.pan(Vec3::new(1.0, 0.0, -4.0)) < -0.99);
+    }
+
+    #[test]
+    fn centered_sounds_play_on_both_channels() {
+        let listener = AudioListener::new(Vec3::ZERO, Vec3::NEG_Z);
+        for position in [Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 2.0, 0.0), Vec3::ZERO] {
+            assert!(listener.pan(position).abs() < 1e-6, "{position} isn't centered");
+        }
+        assert_eq!(stereo_volumes(0.0), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn stereo_volumes_clamp_the_pan() {
+        assert_eq!(stereo_volumes(-1.0), [1.0, 0.0]);
+        assert_eq!(stereo_volumes(5.0), [0.0, 1.0]);
+        assert_eq!(stereo_volumes(0.5), [0.5, 1.0]);
+    }
+}
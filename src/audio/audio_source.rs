@@ -1,8 +1,104 @@
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use glam::Vec3;
 use anyhow::{Result, anyhow};
+use rodio::{Decoder, Source};
+use rodio::decoder::LoopedDecoder;
 
 use super::audio_engine::AudioCategory;
 
+/// How a spatial source gets quieter between its min and max distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RolloffMode {
+    /// Volume falls linearly to zero at the max distance
+    Linear,
+    /// Volume falls with the inverse of the distance and stays put past the max distance
+    Logarithmic,
+}
+
+/// Where the samples of a source come from when it plays
+#[derive(Clone)]
+pub enum AudioData {
+    /// Decoded into memory when loaded, so playback starts without touching the disk
+    Memory {
+        samples: Arc<[i16]>,
+        channels: u16,
+        sample_rate: u32,
+    },
+    /// Decoded from the file in chunks while playing, for long tracks like music
+    Streaming,
+}
+
+impl fmt::Debug for AudioData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioData::Memory { samples, channels, sample_rate } => f.debug_struct("Memory")
+                .field("samples", &samples.len())
+                .field("channels", channels)
+                .field("sample_rate", sample_rate)
+                .finish(),
+            AudioData::Streaming => write!(f, "Streaming"),
+        }
+    }
+}
+
+/// Playback of samples decoded into memory, sharing them with the source instead of copying
+#[derive(Clone)]
+pub(crate) struct MemoryPlayback {
+    samples: Arc<[i16]>,
+    channels: u16,
+    sample_rate: u32,
+    /// Index of the next sample
+    position: usize,
+    /// Start over after the last sample instead of ending
+    looping: bool,
+}
+
+impl MemoryPlayback {
+    pub(crate) fn new(samples: Arc<[i16]>, channels: u16, sample_rate: u32, looping: bool) -> Self {
+        Self { samples, channels, sample_rate, position: 0, looping }
+    }
+}
+
+impl Iterator for MemoryPlayback {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.position >= self.samples.len() && self.looping {
+            self.position = 0;
+        }
+        let sample = *self.samples.get(self.position)?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MemoryPlayback {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        if self.looping {
+            return None;
+        }
+        let frames = self.samples.len() as u64 / self.channels.max(1) as u64;
+        Some(Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64))
+    }
+}
+
 /// Structure representing an audio source
 #[derive(Debug)]
 pub struct AudioSource {
@@ -14,31 +110,241 @@ pub struct AudioSource {
     
     /// Audio category (music, sound effects, etc.)
     pub category: AudioCategory,
+    
+    /// Gain of the source before the category and master volumes
+    pub volume: f32,
+    
+    /// Whether the source is attenuated and panned relative to the listener
+    pub spatial: bool,
+    
+    /// Source position in 3D space
+    pub position: Vec3,
+    
+    /// Distance up to which the source plays at full volume
+    pub min_distance: f32,
+    
+    /// Distance at which attenuation stops
+    pub max_distance: f32,
+    
+    /// Attenuation curve between min and max distance
+    pub rolloff: RolloffMode,
+    
+    /// Decoded samples, or a note to stream them from `path`
+    pub data: AudioData,
 }
 
 impl AudioSource {
-    /// Create a new AudioSource instance
+    /// Create a source decoded into memory, for short sounds that should play without delay
     pub fn new(id: String, path: PathBuf, category: AudioCategory) -> Result<Self> {
-        // Validate that the file exists
-        if !path.exists() {
-            return Err(anyhow!("Audio file '{}' not found", path.display()));
-        }
+        validate_file(&path)?;
         
-        // Validate file extension
-        let extension = path.extension()
-            .and_then(|ext| ext.to_str())
-            .ok_or_else(|| anyhow!("Audio file doesn't have a valid extension"))?;
+        let decoder = open_decoder(&path)?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let samples: Arc<[i16]> = decoder.collect();
         
-        // Validate supported formats
-        match extension.to_lowercase().as_str() {
-            "mp3" | "wav" | "flac" | "ogg" => {},
-            _ => return Err(anyhow!("File format '{}' not supported", extension)),
-        }
+        Ok(Self::with_data(id, path, category, AudioData::Memory { samples, channels, sample_rate }))
+    }
+    
+    /// Create a source decoded from disk while it plays, for long tracks
+    ///
+    /// Only a small part of the file is in memory at a time, the file is opened
+    /// again for each playback.
+    pub fn from_file_streaming(id: String, path: PathBuf, category: AudioCategory) -> Result<Self> {
+        validate_file(&path)?;
+        // Fail now rather than on the first play if the file can't be decoded
+        open_decoder(&path)?;
         
-        Ok(Self {
+        Ok(Self::with_data(id, path, category, AudioData::Streaming))
+    }
+    
+    fn with_data(id: String, path: PathBuf, category: AudioCategory, data: AudioData) -> Self {
+        Self {
             id,
             path,
             category,
-        })
+            volume: 1.0,
+            spatial: false,
+            position: Vec3::ZERO,
+            min_distance: 1.0,
+            max_distance: 50.0,
+            rolloff: RolloffMode::Logarithmic,
+            data,
+        }
+    }
+    
+    /// Whether the source is decoded from disk while playing
+    pub fn is_streaming(&self) -> bool {
+        matches!(self.data, AudioData::Streaming)
+    }
+    
+    /// Set the source gain, clamped to `0.0..=1.0`
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
     }
-} 
\ No newline at end of file
+    
+    /// Enable or disable positional playback
+    pub fn set_spatial(&mut self, spatial: bool) {
+        self.spatial = spatial;
+    }
+    
+    /// Set the source position
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+    }
+    
+    /// Set the distance up to which the source plays at full volume
+    pub fn set_min_distance(&mut self, distance: f32) {
+        self.min_distance = distance.max(0.0);
+        self.max_distance = self.max_distance.max(self.min_distance);
+    }
+    
+    /// Set the distance at which attenuation stops
+    pub fn set_max_distance(&mut self, distance: f32) {
+        self.max_distance = distance.max(self.min_distance);
+    }
+    
+    /// Set the attenuation curve
+    pub fn set_rolloff(&mut self, rolloff: RolloffMode) {
+        self.rolloff = rolloff;
+    }
+    
+    /// Volume factor for a listener at `distance` from the source
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        if distance <= self.min_distance {
+            return 1.0;
+        }
+        
+        match self.rolloff {
+            RolloffMode::Linear => {
+                let range = self.max_distance - self.min_distance;
+                if range <= 0.0 {
+                    return 0.0;
+                }
+                (1.0 - (distance - self.min_distance) / range).clamp(0.0, 1.0)
+            }
+            RolloffMode::Logarithmic => {
+                // A zero min distance would silence the source everywhere
+                let min_distance = self.min_distance.max(f32::EPSILON);
+                min_distance / distance.min(self.max_distance)
+            }
+        }
+    }
+}
+
+/// Check that an audio file exists and has a supported extension
+fn validate_file(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(anyhow!("Audio file '{}' not found", path.display()));
+    }
+    
+    let extension = path.extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or_else(|| anyhow!("Audio file doesn't have a valid extension"))?;
+    
+    match extension.to_lowercase().as_str() {
+        "mp3" | "wav" | "flac" | "ogg" => Ok(()),
+        _ => Err(anyhow!("File format '{}' not supported", extension)),
+    }
+}
+
+/// Open a decoder reading the file as it goes
+pub(crate) fn open_decoder(path: &Path) -> Result<Decoder<BufReader<File>>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow!("Failed to open audio file '{}': {}", path.display(), e))?;
+    Decoder::new(BufReader::new(file))
+        .map_err(|e| anyhow!("Failed to decode audio '{}': {}", path.display(), e))
+}
+
+/// Open a decoder that rewinds the file whenever it reaches the end
+pub(crate) fn open_looped_decoder(path: &Path) -> Result<LoopedDecoder<BufReader<File>>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow!("Failed to open audio file '{}': {}", path.display(), e))?;
+    Decoder::new_looped(BufReader::new(file))
+        .map_err(|e| anyhow!("Failed to decode audio '{}': {}", path.display(), e))
+}
+
+/// Write a mono 16-bit WAV file of `samples` to its own file in the temporary directory
+#[cfg(test)]
+pub(crate) fn write_wav(samples: &[i16]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mirage-audio-{}.wav", uuid::Uuid::new_v4()));
+    let data_size = (samples.len() * 2) as u32;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel, 8000 Hz, 16000 bytes per second, 2 bytes per frame, 16 bits
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&8000u32.to_le_bytes());
+    bytes.extend_from_slice(&16000u32.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_sounds_are_decoded_into_memory() {
+        let path = write_wav(&[0, 100, -100, 200]);
+        let source = AudioSource::new("click".into(), path.clone(), AudioCategory::SoundEffect).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!source.is_streaming());
+        match &source.data {
+            AudioData::Memory { samples, channels, sample_rate } => {
+                assert_eq!(&samples[..], &[0, 100, -100, 200]);
+                assert_eq!((*channels, *sample_rate), (1, 8000));
+            }
+            AudioData::Streaming => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn streamed_sources_keep_no_samples() {
+        let path = write_wav(&[0; 64]);
+        let source = AudioSource::from_file_streaming("music".into(), path.clone(), AudioCategory::Music).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(source.is_streaming());
+        assert!(matches!(source.data, AudioData::Streaming));
+    }
+
+    #[test]
+    fn unsupported_and_missing_files_fail_to_load() {
+        let missing = std::env::temp_dir().join(format!("mirage-audio-{}.wav", uuid::Uuid::new_v4()));
+        assert!(AudioSource::new("missing".into(), missing, AudioCategory::SoundEffect).is_err());
+        assert!(AudioSource::from_file_streaming("readme".into(), PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("README.md"), AudioCategory::Music).is_err());
+    }
+
+    #[test]
+    fn memory_playback_shares_the_samples() {
+        let samples: Arc<[i16]> = Arc::from(vec![1, 2, 3]);
+        let playback = MemoryPlayback::new(samples.clone(), 1, 8000, false);
+        assert_eq!(Arc::strong_count(&samples), 2);
+
+        assert_eq!(playback.total_duration(), Some(Duration::from_secs_f64(3.0 / 8000.0)));
+        assert_eq!(playback.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn looping_memory_playback_starts_over() {
+        let samples: Arc<[i16]> = Arc::from(vec![1, 2, 3]);
+        let playback = MemoryPlayback::new(samples, 1, 8000, true);
+        assert_eq!(playback.total_duration(), None);
+        assert_eq!(playback.take(7).collect::<Vec<_>>(), vec![1, 2, 3, 1, 2, 3, 1]);
+
+        let silent = MemoryPlayback::new(Arc::from(Vec::new()), 1, 8000, true);
+        assert_eq!(silent.count(), 0);
+    }
+}
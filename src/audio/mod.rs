@@ -3,5 +3,5 @@ mod audio_source;
 mod audio_listener;
 
 pub use audio_engine::{AudioEngine, AudioCategory, PlaybackStatus};
-pub use audio_source::AudioSource;
-pub use audio_listener::AudioListener; 
\ No newline at end of file
+pub use audio_source::{AudioData, AudioSource, RolloffMode};
+pub use audio_listener::{AudioListener, stereo_volumes}; 
\ No newline at end of file
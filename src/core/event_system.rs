@@ -1,5 +1,6 @@
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
+use std::marker::PhantomData;
 
 pub trait Event: Any + Send + Sync {
     fn name(&self) -> &'static str;
@@ -7,45 +8,166 @@ pub trait Event: Any + Send + Sync {
 
 pub type EventHandlerFn = Box<dyn Fn(&dyn Any) + Send + Sync>;
 
+/// Events of one type, double buffered so they survive until the end of the next frame
+struct EventChannel<E> {
+    /// Events published during the previous frame
+    previous: Vec<E>,
+    /// Events published during the current frame
+    current: Vec<E>,
+    /// Index of the first event in `previous`, counting every event ever published
+    start: usize,
+}
+
+impl<E> EventChannel<E> {
+    fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start: 0,
+        }
+    }
+
+    /// Index the next published event will get
+    fn end(&self) -> usize {
+        self.start + self.previous.len() + self.current.len()
+    }
+}
+
+/// Type-erased channel so channels of every event type can share one map
+trait AnyChannel: Send + Sync {
+    /// Drop the previous frame's events and start a new frame
+    fn swap_buffers(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<E: Event> AnyChannel for EventChannel<E> {
+    fn swap_buffers(&mut self) {
+        self.start += self.previous.len();
+        self.previous.clear();
+        std::mem::swap(&mut self.previous, &mut self.current);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Cursor into the events of one type, created by `EventSystem::subscribe`
+///
+/// Each reader sees every event published after it was created exactly once,
+/// as long as it reads at least once every other frame.
+pub struct EventReader<E> {
+    next: usize,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<E: Event> EventReader<E> {
+    /// Events published since this reader last read
+    pub fn read<'a>(&mut self, events: &'a EventSystem) -> impl Iterator<Item = &'a E> + 'a {
+        let channel = events.channel::<E>();
+
+        // Events older than the previous frame are gone
+        let skip = channel.map_or(0, |channel| self.next.saturating_sub(channel.start));
+        if let Some(channel) = channel {
+            self.next = channel.end();
+        }
+
+        channel.into_iter()
+            .flat_map(|channel| channel.previous.iter().chain(channel.current.iter()))
+            .skip(skip)
+    }
+}
+
 pub struct EventSystem {
     handlers: HashMap<TypeId, Vec<EventHandlerFn>>,
+    channels: HashMap<TypeId, Box<dyn AnyChannel>>,
 }
 
 impl EventSystem {
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
+            channels: HashMap::new(),
         }
     }
 
-    pub fn subscribe<E: Event>(&mut self, handler: impl Fn(&E) + Send + Sync + 'static) {
+    /// Call `handler` immediately whenever an event of type `E` is published
+    pub fn add_handler<E: Event>(&mut self, handler: impl Fn(&E) + Send + Sync + 'static) {
         let type_id = TypeId::of::<E>();
-        
+
         let handler_wrapper: EventHandlerFn = Box::new(move |event: &dyn Any| {
             if let Some(event) = event.downcast_ref::<E>() {
                 handler(event);
             }
         });
-        
+
         self.handlers.entry(type_id).or_insert_with(Vec::new).push(handler_wrapper);
     }
 
-    pub fn publish<E: Event>(&self, event: E) {
+    /// Create a reader for the events of type `E` published from now on
+    pub fn subscribe<E: Event>(&mut self) -> EventReader<E> {
+        EventReader {
+            next: self.channel_mut::<E>().end(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Run the handlers for `event` and queue it for readers
+    pub fn publish<E: Event>(&mut self, event: E) {
         let type_id = TypeId::of::<E>();
-        
+
         if let Some(handlers) = self.handlers.get(&type_id) {
             for handler in handlers {
                 handler(&event);
             }
         }
+
+        self.channel_mut::<E>().current.push(event);
     }
 
+    /// End the frame, dropping the events published during the previous one
+    ///
+    /// Events published this frame stay readable until the end of the next frame.
+    pub fn update(&mut self) {
+        for channel in self.channels.values_mut() {
+            channel.swap_buffers();
+        }
+    }
+
+    /// Remove the handlers and queued events of type `E`
     pub fn clear<E: Event>(&mut self) {
         let type_id = TypeId::of::<E>();
         self.handlers.remove(&type_id);
+        if let Some(channel) = self.channels.get_mut(&type_id) {
+            // Swapping twice drops both buffers while keeping the indices of existing readers valid
+            channel.swap_buffers();
+            channel.swap_buffers();
+        }
     }
 
     pub fn clear_all(&mut self) {
         self.handlers.clear();
+        for channel in self.channels.values_mut() {
+            channel.swap_buffers();
+            channel.swap_buffers();
+        }
     }
-} 
\ No newline at end of file
+
+    fn channel<E: Event>(&self) -> Option<&EventChannel<E>> {
+        self.channels.get(&TypeId::of::<E>())
+            .and_then(|channel| channel.as_any().downcast_ref())
+    }
+
+    fn channel_mut<E: Event>(&mut self) -> &mut EventChannel<E> {
+        self.channels.entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(EventChannel::<E>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .expect("event channels are keyed by their event type")
+    }
+}
@@ -98,10 +98,36 @@ impl Event for UpdateEvent {
     }
 }
 
-pub struct RenderEvent;
+pub struct RenderEvent {
+    /// Interpolation factor between the last two fixed updates, 1.0 in variable mode
+    pub alpha: f32,
+}
 
 impl Event for RenderEvent {
     fn name(&self) -> &'static str {
         "RenderEvent"
     }
-} 
\ No newline at end of file
+} 
+/// A gamepad was connected, see `Input::publish_gamepad_events`
+pub struct GamepadConnectedEvent {
+    /// Index the gamepad is addressed by in `Input`
+    pub gamepad: usize,
+    pub name: String,
+}
+
+impl Event for GamepadConnectedEvent {
+    fn name(&self) -> &'static str {
+        "GamepadConnectedEvent"
+    }
+}
+
+/// A gamepad was disconnected, its index is free for the next one
+pub struct GamepadDisconnectedEvent {
+    pub gamepad: usize,
+}
+
+impl Event for GamepadDisconnectedEvent {
+    fn name(&self) -> &'static str {
+        "GamepadDisconnectedEvent"
+    }
+}
@@ -2,24 +2,44 @@ use eframe::{App, Frame};
 use egui::Context;
 use winit::event::Event as WinitEvent;
 
-use crate::core::{DeltaTime, EventSystem, UpdateEvent, RenderEvent};
+use crate::core::{DeltaTime, FixedTimestep, EventSystem, UpdateEvent, RenderEvent};
 use crate::scene::SceneManager;
 use crate::ui::WindowManager;
 
+pub type UpdateCallback = Box<dyn FnMut(&mut SceneManager, f32)>;
+pub type RenderCallback = Box<dyn FnMut(&mut SceneManager, f32)>;
+
 pub struct GameLoop {
     delta_time: DeltaTime,
+    fixed_timestep: Option<FixedTimestep>,
     scene_manager: SceneManager,
     event_system: EventSystem,
+    update_callback: Option<UpdateCallback>,
+    render_callback: Option<RenderCallback>,
     running: bool,
+    /// Whether to run one update step on the next tick while paused
+    step_requested: bool,
 }
 
 impl GameLoop {
     pub fn new() -> Self {
         Self {
             delta_time: DeltaTime::new(),
+            fixed_timestep: None,
             scene_manager: SceneManager::new(),
             event_system: EventSystem::new(),
+            update_callback: None,
+            render_callback: None,
             running: true,
+            step_requested: false,
+        }
+    }
+
+    /// Create a loop that updates in constant `dt` steps and renders at display rate
+    pub fn with_fixed_timestep(dt: f32) -> Self {
+        Self {
+            fixed_timestep: Some(FixedTimestep::new(dt)),
+            ..Self::new()
         }
     }
 
@@ -38,6 +58,20 @@ impl GameLoop {
         &mut self.event_system
     }
 
+    pub fn fixed_timestep(&self) -> Option<&FixedTimestep> {
+        self.fixed_timestep.as_ref()
+    }
+
+    /// Called once per update step with the step's delta time
+    pub fn set_update_callback(&mut self, callback: impl FnMut(&mut SceneManager, f32) + 'static) {
+        self.update_callback = Some(Box::new(callback));
+    }
+
+    /// Called once per frame with the interpolation alpha between fixed steps
+    pub fn set_render_callback(&mut self, callback: impl FnMut(&mut SceneManager, f32) + 'static) {
+        self.render_callback = Some(Box::new(callback));
+    }
+
     pub fn stop(&mut self) {
         self.running = false;
     }
@@ -46,25 +80,92 @@ impl GameLoop {
         self.running
     }
 
+    /// Real time of the last frame, unaffected by the time scale
     pub fn delta_seconds(&self) -> f32 {
         self.delta_time.delta_seconds()
     }
 
+    /// Time of the last frame as the game sees it, 0 while paused
+    pub fn scaled_delta_seconds(&self) -> f32 {
+        self.delta_time.scaled_delta_seconds()
+    }
+
+    /// Speed updates run at: 0 pauses them, 0.5 is half speed and 2 double speed
+    ///
+    /// Frames keep rendering at any scale. With a fixed timestep the steps keep
+    /// their size and run less or more often.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.delta_time.set_time_scale(time_scale);
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.delta_time.time_scale()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.delta_time.is_paused()
+    }
+
+    /// Run exactly one update step on the next tick, if paused
+    ///
+    /// The step is the fixed timestep, or the real frame time without one.
+    pub fn step_frame(&mut self) {
+        self.step_requested = true;
+    }
+
     pub fn fps(&self) -> f32 {
         self.delta_time.fps()
     }
+
+    /// Run one frame: as many update steps as are due, then render
+    ///
+    /// Returns the interpolation alpha passed to the render callback.
+    pub fn tick(&mut self) -> f32 {
+        let frame_dt = self.delta_time.update();
+
+        let (steps, dt) = match self.fixed_timestep.as_mut() {
+            Some(fixed) => (fixed.advance(frame_dt), fixed.step()),
+            // A paused frame has nothing to update
+            None => (u32::from(frame_dt > 0.0), frame_dt),
+        };
+        for _ in 0..steps {
+            self.step(dt);
+        }
+
+        if std::mem::take(&mut self.step_requested) && self.delta_time.is_paused() {
+            let dt = self.fixed_timestep.as_ref()
+                .map_or_else(|| self.delta_time.delta_seconds(), |fixed| fixed.step());
+            self.step(dt);
+        }
+
+        let alpha = self.fixed_timestep.as_ref().map_or(1.0, |fixed| fixed.alpha());
+
+        if let Some(render) = self.render_callback.as_mut() {
+            render(&mut self.scene_manager, alpha);
+        }
+        self.event_system.publish(RenderEvent { alpha });
+
+        // Events from this frame stay readable during the next one
+        self.event_system.update();
+
+        alpha
+    }
+
+    fn step(&mut self, dt: f32) {
+        self.event_system.publish(UpdateEvent { delta_time: dt });
+
+        self.scene_manager.update(dt);
+
+        if let Some(update) = self.update_callback.as_mut() {
+            update(&mut self.scene_manager, dt);
+        }
+    }
     
     pub fn run_with_window(mut self, window_manager: WindowManager) {
         window_manager.run(move |_window, event| {
             match event {
                 WinitEvent::MainEventsCleared => {
-                    let dt = self.delta_time.update();
-                    
-                    self.event_system.publish(UpdateEvent { delta_time: dt });
-                    
-                    self.scene_manager.update(dt);
-                    
-                    self.event_system.publish(RenderEvent);
+                    self.tick();
                 }
                 _ => {}
             }
@@ -74,16 +175,46 @@ impl GameLoop {
 
 impl App for GameLoop {
     fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
-        let dt = self.delta_time.update();
-        
-        self.event_system.publish(UpdateEvent { delta_time: dt });
-
-        self.scene_manager.update(dt);
-        
-        self.event_system.publish(RenderEvent);
+        self.tick();
 
         self.scene_manager.render(ctx);
 
         ctx.request_repaint();
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A fixed timestep loop that records the delta time of every update step
+    fn recording_loop() -> (GameLoop, Rc<RefCell<Vec<f32>>>) {
+        let steps = Rc::new(RefCell::new(Vec::new()));
+        let mut game_loop = GameLoop::with_fixed_timestep(1.0 / 60.0);
+        let recorded = steps.clone();
+        game_loop.set_update_callback(move |_, dt| recorded.borrow_mut().push(dt));
+        (game_loop, steps)
+    }
+
+    #[test]
+    fn paused_loop_only_runs_requested_steps() {
+        let (mut game_loop, steps) = recording_loop();
+        game_loop.set_time_scale(0.0);
+        assert!(game_loop.is_paused());
+
+        game_loop.tick();
+        game_loop.tick();
+        assert!(steps.borrow().is_empty());
+        assert_eq!(game_loop.scaled_delta_seconds(), 0.0);
+
+        game_loop.step_frame();
+        game_loop.tick();
+        assert_eq!(*steps.borrow(), vec![1.0 / 60.0]);
+
+        // A step is used up by the tick that ran it
+        game_loop.tick();
+        assert_eq!(steps.borrow().len(), 1);
+    }
+}
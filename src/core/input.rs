@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+use gilrs::{EventType, GamepadId, Gilrs};
+use glam::Vec2;
+use log::warn;
+use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use crate::core::{EventSystem, GamepadConnectedEvent, GamepadDisconnectedEvent};
+
+pub use winit::event::VirtualKeyCode as KeyCode;
+pub use gilrs::{Axis as GamepadAxis, Button as GamepadButton};
+
+/// Scroll distance of one wheel "line" when the platform reports pixels
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Axis values closer to zero than this read as zero, sticks rarely rest exactly at the center
+pub const DEFAULT_GAMEPAD_DEADZONE: f32 = 0.15;
+
+/// Buttons and axes of one connected gamepad
+struct GamepadState {
+    id: GamepadId,
+    name: String,
+    buttons_down: HashSet<GamepadButton>,
+    buttons_pressed: HashSet<GamepadButton>,
+    buttons_released: HashSet<GamepadButton>,
+    /// Raw axis values, before the deadzone
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadState {
+    fn new(id: GamepadId, name: String) -> Self {
+        Self {
+            id,
+            name,
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+            axes: HashMap::new(),
+        }
+    }
+}
+
+/// Gamepad connection changes not yet published
+enum GamepadConnection {
+    Connected(usize, String),
+    Disconnected(usize),
+}
+
+/// Keyboard, mouse and gamepad state, polled by game logic instead of handling winit events
+///
+/// Feed it every window and device event, and call `begin_frame` once at the
+/// start of each frame so the pressed/released sets only cover that frame.
+/// Gamepads are polled by `begin_frame` and addressed by index; a disconnected
+/// pad's index is reused by the next pad that connects.
+pub struct Input {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    mouse_position: Vec2,
+    mouse_delta: Vec2,
+    scroll_delta: Vec2,
+    raw_motion: bool,
+    /// `None` if the platform's gamepad backend failed to start
+    gilrs: Option<Gilrs>,
+    /// Gamepads by index, `None` for free indices
+    gamepads: Vec<Option<GamepadState>>,
+    gamepad_deadzone: f32,
+    /// Connection changes since the last `publish_gamepad_events`
+    gamepad_connections: Vec<GamepadConnection>,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        let mut input = Self {
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+            mouse_position: Vec2::ZERO,
+            mouse_delta: Vec2::ZERO,
+            scroll_delta: Vec2::ZERO,
+            raw_motion: false,
+            gilrs: None,
+            gamepads: Vec::new(),
+            gamepad_deadzone: DEFAULT_GAMEPAD_DEADZONE,
+            gamepad_connections: Vec::new(),
+        };
+
+        // Keyboard and mouse still work without gamepads
+        match Gilrs::new() {
+            Ok(gilrs) => {
+                // Pads plugged in before startup don't send a connection event
+                let connected: Vec<(GamepadId, String)> = gilrs.gamepads()
+                    .map(|(id, gamepad)| (id, gamepad.name().to_string()))
+                    .collect();
+                input.gilrs = Some(gilrs);
+                for (id, name) in connected {
+                    input.connect_gamepad(id, name);
+                }
+            }
+            Err(e) => warn!("Gamepad support disabled: {}", e),
+        }
+
+        input
+    }
+
+    /// Forget everything that only applies to the previous frame
+    pub fn begin_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.mouse_delta = Vec2::ZERO;
+        self.scroll_delta = Vec2::ZERO;
+
+        for gamepad in self.gamepads.iter_mut().flatten() {
+            gamepad.buttons_pressed.clear();
+            gamepad.buttons_released.clear();
+        }
+        self.poll_gamepads();
+    }
+
+    /// Publish a `GamepadConnectedEvent` or `GamepadDisconnectedEvent` for each
+    /// connection change since the last call
+    pub fn publish_gamepad_events(&mut self, event_system: &mut EventSystem) {
+        for connection in self.gamepad_connections.drain(..) {
+            match connection {
+                GamepadConnection::Connected(gamepad, name) => {
+                    event_system.publish(GamepadConnectedEvent { gamepad, name });
+                }
+                GamepadConnection::Disconnected(gamepad) => {
+                    event_system.publish(GamepadDisconnectedEvent { gamepad });
+                }
+            }
+        }
+    }
+
+    /// Apply the gamepad events that arrived since the last poll
+    fn poll_gamepads(&mut self) {
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return;
+        };
+
+        let mut events = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            let name = match event.event {
+                EventType::Connected => gilrs.gamepad(event.id).name().to_string(),
+                _ => String::new(),
+            };
+            events.push((event.id, event.event, name));
+        }
+
+        for (id, event, name) in events {
+            match event {
+                EventType::Connected => self.connect_gamepad(id, name),
+                EventType::Disconnected => self.disconnect_gamepad(id),
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(gamepad) = self.gamepad_by_id(id) {
+                        if gamepad.buttons_down.insert(button) {
+                            gamepad.buttons_pressed.insert(button);
+                        }
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(gamepad) = self.gamepad_by_id(id) {
+                        if gamepad.buttons_down.remove(&button) {
+                            gamepad.buttons_released.insert(button);
+                        }
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(gamepad) = self.gamepad_by_id(id) {
+                        gamepad.axes.insert(axis, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn connect_gamepad(&mut self, id: GamepadId, name: String) {
+        if self.gamepad_by_id(id).is_some() {
+            return;
+        }
+        let state = Some(GamepadState::new(id, name.clone()));
+        let index = match self.gamepads.iter().position(Option::is_none) {
+            Some(index) => {
+                self.gamepads[index] = state;
+                index
+            }
+            None => {
+                self.gamepads.push(state);
+                self.gamepads.len() - 1
+            }
+        };
+        self.gamepad_connections.push(GamepadConnection::Connected(index, name));
+    }
+
+    fn disconnect_gamepad(&mut self, id: GamepadId) {
+        let index = self.gamepads.iter()
+            .position(|gamepad| gamepad.as_ref().map_or(false, |gamepad| gamepad.id == id));
+        if let Some(index) = index {
+            self.gamepads[index] = None;
+            self.gamepad_connections.push(GamepadConnection::Disconnected(index));
+        }
+    }
+
+    fn gamepad_by_id(&mut self, id: GamepadId) -> Option<&mut GamepadState> {
+        self.gamepads.iter_mut().flatten().find(|gamepad| gamepad.id == id)
+    }
+
+    fn gamepad(&self, index: usize) -> Option<&GamepadState> {
+        self.gamepads.get(index)?.as_ref()
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    self.set_key(key, input.state == ElementState::Pressed);
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.set_mouse_button(*button, *state == ElementState::Pressed);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.set_mouse_position(Vec2::new(position.x as f32, position.y as f32));
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll_delta += match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                    MouseScrollDelta::PixelDelta(pos) => Vec2::new(pos.x as f32, pos.y as f32) / PIXELS_PER_LINE,
+                };
+            }
+            WindowEvent::Focused(false) => {
+                // Keys released while unfocused never reach us
+                self.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            self.raw_motion = true;
+            self.mouse_delta += Vec2::new(*x as f32, *y as f32);
+        }
+    }
+
+    /// Press or release a key, for input that doesn't come from winit events
+    ///
+    /// Pressing a key that is already down is ignored, like key repeat.
+    pub fn set_key(&mut self, key: KeyCode, down: bool) {
+        if down {
+            if self.keys_down.insert(key) {
+                self.keys_pressed.insert(key);
+            }
+        } else if self.keys_down.remove(&key) {
+            self.keys_released.insert(key);
+        }
+    }
+
+    /// Press or release a mouse button, for input that doesn't come from winit events
+    pub fn set_mouse_button(&mut self, button: MouseButton, down: bool) {
+        if down {
+            if self.buttons_down.insert(button) {
+                self.buttons_pressed.insert(button);
+            }
+        } else if self.buttons_down.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+    }
+
+    /// Move the cursor, in physical pixels relative to the window's top-left corner
+    pub fn set_mouse_position(&mut self, position: Vec2) {
+        // Raw device motion is more precise, only fall back to cursor movement without it
+        if !self.raw_motion {
+            self.mouse_delta += position - self.mouse_position;
+        }
+        self.mouse_position = position;
+    }
+
+    /// Release every held key and mouse button, e.g. when the window loses focus
+    pub fn release_all(&mut self) {
+        for key in self.keys_down.drain() {
+            self.keys_released.insert(key);
+        }
+        for button in self.buttons_down.drain() {
+            self.buttons_released.insert(button);
+        }
+    }
+
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Whether the key went down this frame
+    pub fn was_key_pressed(&self, key: KeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    /// Whether the key went up this frame
+    pub fn was_key_released(&self, key: KeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    /// All keys currently held down
+    pub fn keys_down(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys_down.iter().copied()
+    }
+
+    /// All keys that went down this frame
+    pub fn keys_pressed(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys_pressed.iter().copied()
+    }
+
+    /// All keys that went up this frame
+    pub fn keys_released(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys_released.iter().copied()
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    pub fn was_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    pub fn was_mouse_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// Cursor position in physical pixels, relative to the window's top-left corner
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// Mouse movement since the start of the frame
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Scroll since the start of the frame, in lines
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+
+    /// Indices of the connected gamepads
+    pub fn gamepads(&self) -> impl Iterator<Item = usize> + '_ {
+        self.gamepads.iter()
+            .enumerate()
+            .filter(|(_, gamepad)| gamepad.is_some())
+            .map(|(index, _)| index)
+    }
+
+    pub fn is_gamepad_connected(&self, gamepad: usize) -> bool {
+        self.gamepad(gamepad).is_some()
+    }
+
+    /// Name the gamepad reports, e.g. "Xbox Controller"
+    pub fn gamepad_name(&self, gamepad: usize) -> Option<&str> {
+        self.gamepad(gamepad).map(|gamepad| gamepad.name.as_str())
+    }
+
+    /// Whether a button is held on a gamepad, false if it isn't connected
+    pub fn gamepad_button_down(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad).map_or(false, |gamepad| gamepad.buttons_down.contains(&button))
+    }
+
+    /// Whether a button went down this frame
+    pub fn gamepad_button_pressed(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad).map_or(false, |gamepad| gamepad.buttons_pressed.contains(&button))
+    }
+
+    /// Whether a button went up this frame
+    pub fn gamepad_button_released(&self, gamepad: usize, button: GamepadButton) -> bool {
+        self.gamepad(gamepad).map_or(false, |gamepad| gamepad.buttons_released.contains(&button))
+    }
+
+    /// Axis value from -1 to 1 with the deadzone applied, 0 if the gamepad isn't connected
+    pub fn gamepad_axis(&self, gamepad: usize, axis: GamepadAxis) -> f32 {
+        apply_deadzone(self.gamepad_axis_raw(gamepad, axis), self.gamepad_deadzone)
+    }
+
+    /// Axis value from -1 to 1 as the gamepad reports it, without the deadzone
+    pub fn gamepad_axis_raw(&self, gamepad: usize, axis: GamepadAxis) -> f32 {
+        self.gamepad(gamepad)
+            .and_then(|gamepad| gamepad.axes.get(&axis).copied())
+            .unwrap_or(0.0)
+    }
+
+    pub fn gamepad_deadzone(&self) -> f32 {
+        self.gamepad_deadzone
+    }
+
+    /// Set the deadzone of every gamepad axis, clamped to 0..1
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) {
+        self.gamepad_deadzone = deadzone.clamp(0.0, 1.0);
+    }
+}
+
+/// Zero an axis value within `deadzone` of the center and rescale the rest
+///
+/// Values just outside the deadzone start near zero rather than jumping to it,
+/// and the result is clamped to -1..=1.
+pub fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= deadzone {
+        return 0.0;
+    }
+    if deadzone >= 1.0 {
+        return value.signum();
+    }
+    (value.signum() * (magnitude - deadzone) / (1.0 - deadzone)).clamp(-1.0, 1.0)
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_zeroes_small_values() {
+        assert_eq!(apply_deadzone(0.0, 0.2), 0.0);
+        assert_eq!(apply_deadzone(0.15, 0.2), 0.0);
+        assert_eq!(apply_deadzone(-0.2, 0.2), 0.0);
+    }
+
+    #[test]
+    fn deadzone_rescales_the_rest() {
+        assert!((apply_deadzone(0.6, 0.2) - 0.5).abs() < 1e-6);
+        assert!((apply_deadzone(-0.6, 0.2) + 0.5).abs() < 1e-6);
+        assert_eq!(apply_deadzone(1.0, 0.2), 1.0);
+        assert_eq!(apply_deadzone(-1.0, 0.2), -1.0);
+        // Just outside the deadzone starts near zero instead of jumping
+        assert!(apply_deadzone(0.21, 0.2) < 0.02);
+    }
+
+    #[test]
+    fn deadzone_edge_cases() {
+        assert_eq!(apply_deadzone(1.5, 0.2), 1.0);
+        assert_eq!(apply_deadzone(0.5, 0.0), 0.5);
+        assert_eq!(apply_deadzone(0.5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn key_state_follows_set_key() {
+        let mut input = Input::new();
+        input.set_key(KeyCode::Space, true);
+        assert!(input.is_key_down(KeyCode::Space));
+        assert!(input.was_key_pressed(KeyCode::Space));
+
+        input.begin_frame();
+        assert!(input.is_key_down(KeyCode::Space));
+        assert!(!input.was_key_pressed(KeyCode::Space));
+
+        input.release_all();
+        assert!(!input.is_key_down(KeyCode::Space));
+        assert!(input.was_key_released(KeyCode::Space));
+    }
+}
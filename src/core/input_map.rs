@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use winit::event::MouseButton;
+use crate::core::{Input, KeyCode, GamepadButton, GamepadAxis, apply_deadzone};
+
+/// A physical input that is either held or not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ButtonBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// The button on any connected gamepad
+    Gamepad(GamepadButton),
+}
+
+impl ButtonBinding {
+    fn is_down(&self, input: &Input) -> bool {
+        match *self {
+            ButtonBinding::Key(key) => input.is_key_down(key),
+            ButtonBinding::Mouse(button) => input.is_mouse_down(button),
+            ButtonBinding::Gamepad(button) => input.gamepads()
+                .any(|gamepad| input.gamepad_button_down(gamepad, button)),
+        }
+    }
+}
+
+/// A physical input that drives an axis from -1 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// -1 while `negative` is held, 1 while `positive` is, 0 for neither or both
+    Composite {
+        negative: ButtonBinding,
+        positive: ButtonBinding,
+    },
+    /// The axis on any connected gamepad, the one pushed furthest wins
+    Gamepad {
+        axis: GamepadAxis,
+        /// Overrides the deadzone set on `Input`
+        #[serde(default)]
+        deadzone: Option<f32>,
+        #[serde(default)]
+        inverted: bool,
+    },
+}
+
+impl AxisBinding {
+    /// Composite axis from two keys, e.g. A and D for moving left and right
+    pub fn keys(negative: KeyCode, positive: KeyCode) -> Self {
+        AxisBinding::Composite {
+            negative: ButtonBinding::Key(negative),
+            positive: ButtonBinding::Key(positive),
+        }
+    }
+
+    /// Gamepad axis using the deadzone set on `Input`
+    pub fn gamepad(axis: GamepadAxis) -> Self {
+        AxisBinding::Gamepad { axis, deadzone: None, inverted: false }
+    }
+
+    fn value(&self, input: &Input) -> f32 {
+        match *self {
+            AxisBinding::Composite { negative, positive } => {
+                let negative = if negative.is_down(input) { 1.0 } else { 0.0 };
+                let positive = if positive.is_down(input) { 1.0 } else { 0.0 };
+                positive - negative
+            }
+            AxisBinding::Gamepad { axis, deadzone, inverted } => {
+                let deadzone = deadzone.unwrap_or_else(|| input.gamepad_deadzone());
+                let value = input.gamepads()
+                    .map(|gamepad| apply_deadzone(input.gamepad_axis_raw(gamepad, axis), deadzone))
+                    .fold(0.0, strongest);
+                if inverted { -value } else { value }
+            }
+        }
+    }
+}
+
+/// State of one action during the current frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ActionState {
+    down: bool,
+    pressed: bool,
+    released: bool,
+}
+
+/// Named actions and axes bound to physical inputs
+///
+/// Game logic and scripts query actions like `"Jump"` or axes like `"Move"`
+/// instead of keys, so players can rebind them. Call `update` once per frame
+/// after `Input` has received the frame's events; the queries then answer for
+/// that frame. Only the bindings are serialized, e.g. to offer rebinding or to
+/// persist them from the editor.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<ButtonBinding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+    #[serde(skip)]
+    action_states: HashMap<String, ActionState>,
+    #[serde(skip)]
+    axis_values: HashMap<String, f32>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a binding to an action, creating the action if needed
+    pub fn bind_action(&mut self, action: &str, binding: ButtonBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Add a binding to an axis, creating the axis if needed
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replace every binding of an action, e.g. after the player rebinds it
+    pub fn set_action_bindings(&mut self, action: &str, bindings: Vec<ButtonBinding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+
+    /// Replace every binding of an axis
+    pub fn set_axis_bindings(&mut self, axis: &str, bindings: Vec<AxisBinding>) {
+        self.axes.insert(axis.to_string(), bindings);
+    }
+
+    pub fn action_bindings(&self, action: &str) -> &[ButtonBinding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Names of all actions, in no particular order
+    pub fn actions(&self) -> impl Iterator<Item = &str> + '_ {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Names of all axes, in no particular order
+    pub fn axes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.axes.keys().map(String::as_str)
+    }
+
+    /// Remove an action and its bindings
+    pub fn remove_action(&mut self, action: &str) {
+        self.actions.remove(action);
+        self.action_states.remove(action);
+    }
+
+    /// Remove an axis and its bindings
+    pub fn remove_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+        self.axis_values.remove(axis);
+    }
+
+    /// Resolve every action and axis against this frame's input
+    ///
+    /// An action goes down when its first binding does and up when its last
+    /// binding is released, so holding two of its keys doesn't press it twice.
+    pub fn update(&mut self, input: &Input) {
+        self.action_states.retain(|action, _| self.actions.contains_key(action));
+        for (action, bindings) in &self.actions {
+            let down = bindings.iter().any(|binding| binding.is_down(input));
+            let state = self.action_states.entry(action.clone()).or_default();
+            *state = ActionState {
+                down,
+                pressed: down && !state.down,
+                released: !down && state.down,
+            };
+        }
+
+        self.axis_values.clear();
+        for (axis, bindings) in &self.axes {
+            let value = bindings.iter()
+                .map(|binding| binding.value(input))
+                .fold(0.0, strongest);
+            self.axis_values.insert(axis.clone(), value);
+        }
+    }
+
+    /// Whether any binding of the action is held, false for unknown actions
+    pub fn is_action_down(&self, action: &str) -> bool {
+        self.action_state(action).down
+    }
+
+    /// Whether the action went down this frame
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.action_state(action).pressed
+    }
+
+    /// Whether the action went up this frame
+    pub fn is_action_released(&self, action: &str) -> bool {
+        self.action_state(action).released
+    }
+
+    /// Axis value from -1 to 1, 0 for unknown axes
+    ///
+    /// When several bindings are active, the one pushed furthest wins instead
+    /// of them adding up.
+    pub fn action_axis(&self, axis: &str) -> f32 {
+        self.axis_values.get(axis).copied().unwrap_or(0.0)
+    }
+
+    fn action_state(&self, action: &str) -> ActionState {
+        self.action_states.get(action).copied().unwrap_or_default()
+    }
+
+    /// Serialize the bindings to a RON string
+    pub fn to_ron(&self) -> Result<String> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Parse bindings from a RON string
+    pub fn from_ron(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    /// Save the bindings to a RON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Load bindings from a RON file
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+/// Whichever of two axis values is further from the center
+fn strongest(a: f32, b: f32) -> f32 {
+    if b.abs() > a.abs() { b } else { a }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_map() -> InputMap {
+        let mut map = InputMap::new();
+        map.bind_axis("Move", AxisBinding::keys(KeyCode::A, KeyCode::D));
+        map.bind_action("Jump", ButtonBinding::Key(KeyCode::Space));
+        map.bind_action("Jump", ButtonBinding::Key(KeyCode::W));
+        map
+    }
+
+    #[test]
+    fn two_key_composite_axis() {
+        let mut input = Input::new();
+        let mut map = move_map();
+
+        map.update(&input);
+        assert_eq!(map.action_axis("Move"), 0.0);
+
+        input.set_key(KeyCode::A, true);
+        map.update(&input);
+        assert_eq!(map.action_axis("Move"), -1.0);
+
+        input.set_key(KeyCode::A, false);
+        input.set_key(KeyCode::D, true);
+        map.update(&input);
+        assert_eq!(map.action_axis("Move"), 1.0);
+
+        input.set_key(KeyCode::A, true);
+        map.update(&input);
+        assert_eq!(map.action_axis("Move"), 0.0);
+    }
+
+    #[test]
+    fn action_is_pressed_once_across_its_bindings() {
+        let mut input = Input::new();
+        let mut map = move_map();
+
+        input.set_key(KeyCode::Space, true);
+        map.update(&input);
+        assert!(map.is_action_pressed("Jump"));
+
+        input.set_key(KeyCode::W, true);
+        map.update(&input);
+        assert!(map.is_action_down("Jump"));
+        assert!(!map.is_action_pressed("Jump"));
+
+        input.set_key(KeyCode::Space, false);
+        map.update(&input);
+        assert!(!map.is_action_released("Jump"));
+
+        input.set_key(KeyCode::W, false);
+        map.update(&input);
+        assert!(map.is_action_released("Jump"));
+        assert!(!map.is_action_down("Jump"));
+    }
+
+    #[test]
+    fn unknown_names_are_inactive() {
+        let map = move_map();
+        assert!(!map.is_action_down("Fire"));
+        assert_eq!(map.action_axis("Look"), 0.0);
+    }
+
+    #[test]
+    fn bindings_round_trip_through_ron() {
+        let map = move_map();
+        let loaded = InputMap::from_ron(&map.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.axis_bindings("Move"), map.axis_bindings("Move"));
+        assert_eq!(loaded.action_bindings("Jump"), map.action_bindings("Jump"));
+    }
+}
@@ -2,8 +2,12 @@ mod game_loop;
 mod timing;
 mod event_system;
 mod game_events;
+mod input;
+mod input_map;
 
 pub use game_loop::GameLoop;
-pub use timing::DeltaTime;
-pub use event_system::{EventSystem, Event};
-pub use game_events::*; 
\ No newline at end of file
+pub use timing::{DeltaTime, FixedTimestep, MAX_FIXED_STEPS_PER_FRAME};
+pub use event_system::{EventSystem, Event, EventReader};
+pub use game_events::*;
+pub use input::{Input, KeyCode, GamepadButton, GamepadAxis, DEFAULT_GAMEPAD_DEADZONE, apply_deadzone};
+pub use input_map::{InputMap, ButtonBinding, AxisBinding};
\ No newline at end of file
@@ -4,6 +4,8 @@ pub struct DeltaTime {
     last_update: Instant,
     delta: Duration,
     fixed_timestep: Option<Duration>,
+    /// Multiplier for the delta time passed to the game, see `set_time_scale`
+    time_scale: f32,
 }
 
 impl DeltaTime {
@@ -12,6 +14,7 @@ impl DeltaTime {
             last_update: Instant::now(),
             delta: Duration::from_secs(0),
             fixed_timestep: None,
+            time_scale: 1.0,
         }
     }
 
@@ -21,25 +24,67 @@ impl DeltaTime {
             last_update: Instant::now(),
             delta: fixed_dt,
             fixed_timestep: Some(fixed_dt),
+            time_scale: 1.0,
         }
     }
 
+    /// Measure the time since the last update and return the game's delta time
+    ///
+    /// The returned delta is scaled by the time scale, 0 while paused.
     pub fn update(&mut self) -> f32 {
         let now = Instant::now();
         self.delta = now - self.last_update;
         self.last_update = now;
         
-        if let Some(fixed) = self.fixed_timestep {
-            return fixed.as_secs_f32();
-        }
-        
-        self.delta.as_secs_f32()
+        let delta = match self.fixed_timestep {
+            Some(fixed) => fixed.as_secs_f32(),
+            None => self.delta.as_secs_f32(),
+        };
+        self.scale(delta)
     }
 
+    /// Record a frame time measured elsewhere, e.g. by egui, and return the game's delta time
+    ///
+    /// Like `update`, but the fixed timestep is ignored as the caller decides the frame length.
+    pub fn advance(&mut self, real_delta: f32) -> f32 {
+        self.last_update = Instant::now();
+        self.delta = Duration::try_from_secs_f32(real_delta).unwrap_or_default();
+        self.scaled_delta_seconds()
+    }
+
+    /// Real time between the last two updates, unaffected by the time scale
+    ///
+    /// For things that keep moving while the game is paused or slowed, such as the UI or an editor camera.
     pub fn delta_seconds(&self) -> f32 {
         self.delta.as_secs_f32()
     }
 
+    /// Real time between the last two updates multiplied by the time scale
+    pub fn scaled_delta_seconds(&self) -> f32 {
+        self.scale(self.delta_seconds())
+    }
+
+    /// A real duration in seconds as the game sees it
+    pub fn scale(&self, seconds: f32) -> f32 {
+        seconds * self.time_scale
+    }
+
+    /// Speed the game runs at: 0 pauses it, 0.5 is half speed and 2 double speed
+    ///
+    /// Negative and non-finite scales are treated as 0.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = if time_scale.is_finite() { time_scale.max(0.0) } else { 0.0 };
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Whether the time scale is 0
+    pub fn is_paused(&self) -> bool {
+        self.time_scale == 0.0
+    }
+
     pub fn delta_millis(&self) -> u128 {
         self.delta.as_millis()
     }
@@ -47,4 +92,98 @@ impl DeltaTime {
     pub fn fps(&self) -> f32 {
         1.0 / self.delta_seconds()
     }
-} 
\ No newline at end of file
+} 
+
+pub const MAX_FIXED_STEPS_PER_FRAME: u32 = 5;
+
+/// Accumulates frame time and splits it into constant-size simulation steps
+pub struct FixedTimestep {
+    step: f32,
+    accumulator: f32,
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> Self {
+        Self {
+            step,
+            accumulator: 0.0,
+            max_steps: MAX_FIXED_STEPS_PER_FRAME,
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Add a frame's worth of time and return how many fixed steps to run
+    ///
+    /// If the frame took longer than `max_steps` steps, the leftover time is
+    /// dropped so a slow frame can't snowball into ever slower ones.
+    pub fn advance(&mut self, frame_dt: f32) -> u32 {
+        self.accumulator += frame_dt;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if self.accumulator >= self.step {
+            self.accumulator %= self.step;
+        }
+
+        steps
+    }
+
+    /// How far between the last and the next fixed step we are, in `[0, 1)`
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn time_scale_scales_the_game_delta_only() {
+        let mut delta_time = DeltaTime::new();
+        delta_time.set_time_scale(0.5);
+
+        assert!(close(delta_time.advance(0.1), 0.05));
+        assert!(close(delta_time.delta_seconds(), 0.1));
+        assert!(close(delta_time.scaled_delta_seconds(), 0.05));
+
+        delta_time.set_time_scale(2.0);
+        assert!(close(delta_time.advance(0.1), 0.2));
+    }
+
+    #[test]
+    fn invalid_time_scales_pause() {
+        let mut delta_time = DeltaTime::new();
+        for time_scale in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            delta_time.set_time_scale(time_scale);
+            assert!(delta_time.is_paused(), "{}", time_scale);
+            assert_eq!(delta_time.advance(0.1), 0.0);
+        }
+    }
+
+    #[test]
+    fn fixed_timestep_drops_time_past_max_steps() {
+        let mut fixed = FixedTimestep::new(0.1).with_max_steps(3);
+        assert_eq!(fixed.advance(0.25), 2);
+        assert!((fixed.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(fixed.advance(1.0), 3);
+        assert!(fixed.alpha() < 1.0);
+    }
+}
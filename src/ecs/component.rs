@@ -1,9 +1,18 @@
 use glam::{Vec2, Vec3, Quat, Mat4};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use crate::rendering::{Mesh, Material, Model, Transform};
+use serde::{Serialize, Deserialize};
+use crate::rendering::{
+    ClearMode, DirectionalLight, Light, ShadowResolution, DEFAULT_SHADOW_BIAS, Mesh, Material, Model, PointLight, PrimitiveShape, RenderView, Renderer,
+    TextAlign, TextStyle, Transform,
+};
+use crate::assets::{Handle, MaterialAsset, MATERIAL_EXTENSION};
+use crate::ecs::EntityHandle;
+use crate::scripting::ScriptHandle;
+use crate::utils::math;
 
 /// Component that stores the name of an entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NameComponent {
     /// The name of the entity
     pub name: String,
@@ -16,8 +25,95 @@ impl NameComponent {
     }
 }
 
+/// Component that links an entity to its parent in the hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentComponent {
+    /// Handle of the parent entity
+    pub parent: EntityHandle,
+}
+
+impl ParentComponent {
+    /// Create a new parent component
+    pub fn new(parent: EntityHandle) -> Self {
+        Self { parent }
+    }
+}
+
+/// Component that stores the position of an entity among its siblings in the hierarchy
+///
+/// Siblings are listed by ascending order. Entities without one come after those that
+/// have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiblingOrderComponent {
+    /// Position among the siblings, lowest first
+    pub order: i32,
+}
+
+impl SiblingOrderComponent {
+    /// Create a new sibling order component
+    pub fn new(order: i32) -> Self {
+        Self { order }
+    }
+}
+
+/// Component that switches an entity and its descendants on or off
+///
+/// Inactive entities stay in the scene and the hierarchy but are skipped by the
+/// systems and not drawn. Entities without one are active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveComponent {
+    /// Whether the entity is active itself, its parents may still switch it off
+    pub active: bool,
+}
+
+impl ActiveComponent {
+    /// Create a new active component
+    pub fn new(active: bool) -> Self {
+        Self { active }
+    }
+}
+
+impl Default for ActiveComponent {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+/// Marker on entities that are inactive themselves or have an inactive parent
+///
+/// Maintained by `active_system`, systems leave out entities that have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InactiveInHierarchy;
+
+/// Number of layers an entity can be on
+pub const LAYER_COUNT: u8 = 32;
+
+/// Layer mask including every layer
+pub const ALL_LAYERS: u32 = u32::MAX;
+
+/// Component that puts an entity on a layer, e.g. to leave it out of raycasts
+///
+/// Entities without one are on layer 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LayerComponent {
+    /// Layer index, below `LAYER_COUNT`
+    pub layer: u8,
+}
+
+impl LayerComponent {
+    /// Create a new layer component, clamping the layer to the valid range
+    pub fn new(layer: u8) -> Self {
+        Self { layer: layer.min(LAYER_COUNT - 1) }
+    }
+    
+    /// Bit of this layer in a layer mask
+    pub fn mask(&self) -> u32 {
+        1 << self.layer.min(LAYER_COUNT - 1)
+    }
+}
+
 /// Component that stores the 2D transform of an entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transform2DComponent {
     /// Position in 2D space
     pub position: Vec2,
@@ -44,16 +140,12 @@ impl Transform2DComponent {
     
     /// Get the transformation matrix
     pub fn matrix(&self) -> Mat4 {
-        let translation = Mat4::from_translation(Vec3::new(self.position.x, self.position.y, 0.0));
-        let rotation = Mat4::from_rotation_z(self.rotation);
-        let scale = Mat4::from_scale(Vec3::new(self.scale.x, self.scale.y, 1.0));
-        
-        translation * rotation * scale
+        math::compose_2d(self.position, self.rotation, self.scale)
     }
 }
 
 /// Component that stores the 3D transform of an entity
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transform3DComponent {
     /// Position in 3D space
     pub position: Vec3,
@@ -80,17 +172,100 @@ impl Transform3DComponent {
     
     /// Get the transformation matrix
     pub fn matrix(&self) -> Mat4 {
-        let translation = Mat4::from_translation(self.position);
-        let rotation = Mat4::from_quat(self.rotation);
-        let scale = Mat4::from_scale(self.scale);
-        
-        translation * rotation * scale
+        math::compose(self.position, self.rotation, self.scale)
+    }
+    
+    /// Rotation as Euler angles in radians, applied in `math::EULER_ORDER`
+    pub fn euler(&self) -> Vec3 {
+        let (x, y, z) = self.rotation.to_euler(math::EULER_ORDER);
+        Vec3::new(x, y, z)
+    }
+    
+    /// Set the rotation from Euler angles in radians, applied in `math::EULER_ORDER`
+    pub fn set_euler(&mut self, euler: Vec3) {
+        self.rotation = Quat::from_euler(math::EULER_ORDER, euler.x, euler.y, euler.z);
+    }
+    
+    /// Rotate by `radians` around a world space axis
+    ///
+    /// Does nothing for a zero axis.
+    pub fn rotate_axis(&mut self, axis: Vec3, radians: f32) {
+        let Some(axis) = axis.try_normalize() else {
+            return;
+        };
+        self.rotation = (Quat::from_axis_angle(axis, radians) * self.rotation).normalize();
+    }
+    
+    /// Direction the entity faces, its local -Z axis like cameras
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+    
+    /// Turn so `forward` points at `target`, with the local Y axis as close to `up` as possible
+    ///
+    /// Does nothing if `target` is at the position or straight along `up` from it.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let forward = (target - self.position).normalize_or_zero();
+        let right = forward.cross(up).normalize_or_zero();
+        if forward == Vec3::ZERO || right == Vec3::ZERO {
+            return;
+        }
+        let up = right.cross(forward);
+        self.rotation = Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward));
     }
 }
 
+/// World matrix of an entity, computed by `transform_system` from its parent chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform {
+    /// Matrix from the entity's local space to world space
+    pub matrix: Mat4,
+}
+
+impl GlobalTransform {
+    /// Create a new global transform
+    pub fn new(matrix: Mat4) -> Self {
+        Self { matrix }
+    }
+    
+    /// Position in world space
+    pub fn position(&self) -> Vec3 {
+        self.matrix.w_axis.truncate()
+    }
+}
+
+/// World space bounding box of an entity's model, computed by `bounds_system`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundsComponent {
+    /// Box around the model's meshes in world space
+    pub aabb: math::Aabb,
+}
+
+impl BoundsComponent {
+    /// Create a new bounds component
+    pub fn new(aabb: math::Aabb) -> Self {
+        Self { aabb }
+    }
+}
+
+/// How a physics body moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BodyType {
+    /// Moved by velocity, gravity and collisions
+    #[default]
+    Dynamic,
+    /// Moved by velocity only, pushes dynamic bodies but is never pushed
+    Kinematic,
+    /// Never moves
+    Static,
+}
+
 /// Component for physics properties
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhysicsComponent {
+    /// Disabled bodies keep their velocity but aren't moved or pushed
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
     /// Velocity in 2D space
     pub velocity: Vec2,
     /// Angular velocity in radians per second
@@ -99,21 +274,198 @@ pub struct PhysicsComponent {
     pub mass: f32,
     /// Whether the entity is affected by gravity
     pub use_gravity: bool,
+    /// How the body moves
+    #[serde(default)]
+    pub body_type: BodyType,
+    /// Fraction of velocity lost per second
+    #[serde(default)]
+    pub linear_drag: f32,
+    /// Fraction of angular velocity lost per second
+    #[serde(default)]
+    pub angular_drag: f32,
+    /// Multiplier applied to gravity
+    #[serde(default = "default_gravity_scale")]
+    pub gravity_scale: f32,
+}
+
+fn default_gravity_scale() -> f32 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl PhysicsComponent {
     /// Create a new physics component
     pub fn new(velocity: Vec2, angular_velocity: f32, mass: f32, use_gravity: bool) -> Self {
-        Self { velocity, angular_velocity, mass, use_gravity }
+        Self {
+            enabled: true,
+            velocity,
+            angular_velocity,
+            mass,
+            use_gravity,
+            body_type: BodyType::Dynamic,
+            linear_drag: 0.0,
+            angular_drag: 0.0,
+            gravity_scale: 1.0,
+        }
     }
     
     /// Create a new physics component with default values
     pub fn default() -> Self {
+        Self::new(Vec2::ZERO, 0.0, 1.0, true)
+    }
+    
+    /// Set the body type
+    pub fn with_body_type(mut self, body_type: BodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+    
+    /// Inverse mass used when resolving collisions, zero for immovable bodies
+    pub fn inverse_mass(&self) -> f32 {
+        match self.body_type {
+            BodyType::Dynamic if self.mass > 0.0 => 1.0 / self.mass,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Shape of a 2D collider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ColliderShape {
+    /// Box, rotated with the entity
+    Box {
+        /// Half the width and height of the box
+        half_extents: Vec2,
+    },
+    /// Circle
+    Circle {
+        /// Radius of the circle
+        radius: f32,
+    },
+    /// Convex polygon, rotated with the entity
+    Polygon {
+        /// Corners around the entity's position, counter-clockwise
+        points: Vec<Vec2>,
+    },
+}
+
+impl ColliderShape {
+    /// Whether the physics system can use the shape
+    ///
+    /// Polygons need at least 3 points forming a convex, counter-clockwise outline,
+    /// see `math::convex_hull` to make one. Boxes and circles are always valid.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ColliderShape::Polygon { points } => math::is_convex_polygon(points),
+            _ => true,
+        }
+    }
+}
+
+/// A collider placed in world space, see `Collider2DComponent::world_shape`
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldShape {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// Convex polygon, boxes included
+    Polygon {
+        center: Vec2,
+        /// Corners in world space, counter-clockwise
+        vertices: Vec<Vec2>,
+    },
+}
+
+impl WorldShape {
+    pub fn center(&self) -> Vec2 {
+        match self {
+            WorldShape::Circle { center, .. } | WorldShape::Polygon { center, .. } => *center,
+        }
+    }
+    
+    /// Corners of the axis-aligned box around the shape, as `(min, max)`
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            WorldShape::Circle { center, radius } => (*center - Vec2::splat(*radius), *center + Vec2::splat(*radius)),
+            WorldShape::Polygon { center, vertices } => vertices.iter().fold((*center, *center), |(min, max), vertex| {
+                (min.min(*vertex), max.max(*vertex))
+            }),
+        }
+    }
+}
+
+/// Component for 2D collision shapes
+///
+/// Colliders on entities without a `PhysicsComponent` are treated as static.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collider2DComponent {
+    /// Disabled colliders don't collide or trigger
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Shape of the collider, scaled and rotated by the entity's transform
+    pub shape: ColliderShape,
+    /// Offset from the entity position, rotated with the entity
+    pub offset: Vec2,
+    /// Triggers report overlaps without pushing bodies apart
+    pub is_trigger: bool,
+}
+
+impl Collider2DComponent {
+    /// Create a box collider from its full size
+    pub fn new_box(size: Vec2) -> Self {
+        Self::from_shape(ColliderShape::Box { half_extents: size / 2.0 })
+    }
+    
+    /// Create a circle collider
+    pub fn new_circle(radius: f32) -> Self {
+        Self::from_shape(ColliderShape::Circle { radius })
+    }
+    
+    /// Create a polygon collider around the convex hull of `points`
+    ///
+    /// Returns `None` if fewer than 3 of the points are distinct or they all lie on one line.
+    pub fn new_polygon(points: &[Vec2]) -> Option<Self> {
+        let points = math::convex_hull(points);
+        if points.len() < 3 {
+            return None;
+        }
+        Some(Self::from_shape(ColliderShape::Polygon { points }))
+    }
+    
+    fn from_shape(shape: ColliderShape) -> Self {
         Self {
-            velocity: Vec2::ZERO,
-            angular_velocity: 0.0,
-            mass: 1.0,
-            use_gravity: true,
+            enabled: true,
+            shape,
+            offset: Vec2::ZERO,
+            is_trigger: false,
+        }
+    }
+    
+    /// Make the collider a trigger
+    pub fn as_trigger(mut self) -> Self {
+        self.is_trigger = true;
+        self
+    }
+    
+    /// The shape in world space, for an entity at `position` rotated by `rotation` radians with `scale`
+    ///
+    /// Circles are scaled by the larger scale axis.
+    pub fn world_shape(&self, position: Vec2, rotation: f32, scale: Vec2) -> WorldShape {
+        let scale = scale.abs();
+        let center = position + math::rotate_2d(self.offset, rotation);
+        let place = |point: Vec2| center + math::rotate_2d(point * scale, rotation);
+        match &self.shape {
+            ColliderShape::Box { half_extents } => {
+                let (x, y) = (half_extents.x, half_extents.y);
+                let corners = [Vec2::new(-x, -y), Vec2::new(x, -y), Vec2::new(x, y), Vec2::new(-x, y)];
+                WorldShape::Polygon { center, vertices: corners.into_iter().map(place).collect() }
+            }
+            ColliderShape::Circle { radius } => WorldShape::Circle { center, radius: radius * scale.max_element() },
+            ColliderShape::Polygon { points } => WorldShape::Polygon { center, vertices: points.iter().copied().map(place).collect() },
         }
     }
 }
@@ -122,35 +474,136 @@ impl PhysicsComponent {
 #[derive(Clone)]
 pub struct RenderableComponent {
     /// The model to render
-    pub model: Arc<Model>,
+    pub model: Handle<Model>,
     /// Whether the entity is visible
     pub visible: bool,
+    /// Whether the model is rendered into the directional light's shadow map
+    pub casts_shadows: bool,
+    /// Material file to draw the model with instead of its own materials
+    pub material_path: Option<PathBuf>,
+    /// The loaded material file, assigned by `material_asset_system`
+    pub material: Option<Handle<MaterialAsset>>,
+    /// Version of `material` the model was last rebuilt with
+    pub(crate) applied_material: Option<Arc<MaterialAsset>>,
 }
 
 impl RenderableComponent {
     /// Create a new renderable component
-    pub fn new(model: Arc<Model>) -> Self {
-        Self { model, visible: true }
+    pub fn new(model: impl Into<Handle<Model>>) -> Self {
+        Self {
+            model: model.into(),
+            visible: true,
+            casts_shadows: true,
+            material_path: None,
+            material: None,
+            applied_material: None,
+        }
+    }
+
+    /// Draw the model with a material file, see `material_asset_system`
+    pub fn with_material(mut self, path: impl Into<PathBuf>) -> Self {
+        self.set_material_path(Some(path.into()));
+        self
+    }
+
+    /// Switch to another material file, loaded by the next `material_asset_system` run
+    pub fn set_material_path(&mut self, path: Option<PathBuf>) {
+        self.material_path = path;
+        self.material = None;
     }
     
     /// Create a new renderable component with a mesh and material
     pub fn from_parts(device: &wgpu::Device, mesh: Arc<Mesh>, material: Arc<Material>) -> Self {
         let transform = Transform::default();
-        let model = Arc::new(Model::new_with_device(
+        let model = Handle::new(Model::new_with_device(
             device,
             mesh,
             material,
             transform,
         ));
         
-        Self { model, visible: true }
+        Self::new(model)
+    }
+}
+
+/// Name of the material primitives are created with
+pub const DEFAULT_MATERIAL: &str = "Default";
+
+/// Serializable reference to a renderable
+///
+/// Scenes store renderables by mesh and material name, since the GPU resources
+/// behind a `RenderableComponent` can only be recreated once a device is available.
+/// A material name ending in `.mat` is the path of a material file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderableDescriptor {
+    /// Name of the mesh to render
+    pub mesh: String,
+    /// Name of the material to render with
+    pub material: String,
+    /// Whether the entity is visible
+    pub visible: bool,
+    /// Whether the model casts shadows
+    #[serde(default = "default_casts_shadows")]
+    pub casts_shadows: bool,
+}
+
+fn default_casts_shadows() -> bool {
+    true
+}
+
+impl RenderableDescriptor {
+    /// Create a descriptor for an existing renderable component
+    pub fn from_renderable(renderable: &RenderableComponent) -> Self {
+        let model = renderable.model.get();
+        let material = match &renderable.material_path {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => model.material.name.clone(),
+        };
+        Self {
+            mesh: model.mesh.name.clone(),
+            material,
+            visible: renderable.visible,
+            casts_shadows: renderable.casts_shadows,
+        }
+    }
+    
+    /// Descriptor of a built-in primitive drawn with the default material
+    pub fn primitive(shape: PrimitiveShape) -> Self {
+        Self {
+            mesh: shape.name().to_string(),
+            material: DEFAULT_MATERIAL.to_string(),
+            visible: true,
+            casts_shadows: true,
+        }
     }
+    
+    /// The built-in primitive the mesh refers to, if any
+    pub fn primitive_shape(&self) -> Option<PrimitiveShape> {
+        PrimitiveShape::from_name(&self.mesh)
+    }
+    
+    /// The material file the material refers to, if any
+    pub fn material_file(&self) -> Option<&Path> {
+        let path = Path::new(&self.material);
+        (path.extension()? == MATERIAL_EXTENSION).then_some(path)
+    }
+}
+
+/// Background color of new cameras, cornflower blue
+pub const DEFAULT_CLEAR_COLOR: [f32; 4] = [0.392, 0.584, 0.929, 1.0];
+
+fn default_clear_color() -> [f32; 4] {
+    DEFAULT_CLEAR_COLOR
+}
+
+fn default_viewport() -> [f32; 4] {
+    RenderView::FULL_VIEWPORT
 }
 
 /// Component for camera properties
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CameraComponent {
-    /// Whether this is the active camera
+    /// Whether the camera renders, disabled cameras are skipped
     pub is_active: bool,
     /// Field of view in radians (for perspective cameras)
     pub fov: f32,
@@ -162,6 +615,18 @@ pub struct CameraComponent {
     pub is_orthographic: bool,
     /// Orthographic size (half height)
     pub ortho_size: f32,
+    /// How the target is cleared before this camera draws
+    #[serde(default)]
+    pub clear_mode: ClearMode,
+    /// Background color, linear RGBA
+    #[serde(default = "default_clear_color")]
+    pub clear_color: [f32; 4],
+    /// Part of the window drawn to as normalized `[x, y, width, height]`, from the bottom left
+    #[serde(default = "default_viewport")]
+    pub viewport: [f32; 4],
+    /// Cameras with a higher depth draw later, on top of the others
+    #[serde(default)]
+    pub depth: i32,
 }
 
 impl CameraComponent {
@@ -174,6 +639,10 @@ impl CameraComponent {
             far,
             is_orthographic: false,
             ortho_size: 5.0,
+            clear_mode: ClearMode::SolidColor,
+            clear_color: DEFAULT_CLEAR_COLOR,
+            viewport: RenderView::FULL_VIEWPORT,
+            depth: 0,
         }
     }
     
@@ -186,6 +655,377 @@ impl CameraComponent {
             far,
             is_orthographic: true,
             ortho_size,
+            clear_mode: ClearMode::SolidColor,
+            clear_color: DEFAULT_CLEAR_COLOR,
+            viewport: RenderView::FULL_VIEWPORT,
+            depth: 0,
+        }
+    }
+    
+    /// Make the renderer clear the next frames the way this camera does
+    pub fn apply_clear(&self, renderer: &mut Renderer) {
+        let [r, g, b, a] = self.clear_color.map(f64::from);
+        renderer.set_clear_color(r, g, b, a);
+        renderer.set_clear_mode(self.clear_mode);
+    }
+    
+    /// Projection matrix for a viewport with the given width / height ratio
+    pub fn projection(&self, aspect: f32) -> Mat4 {
+        if self.is_orthographic {
+            let half_height = self.ortho_size;
+            let half_width = half_height * aspect;
+            Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, self.near, self.far)
+        } else {
+            Mat4::perspective_rh(self.fov, aspect, self.near, self.far)
+        }
+    }
+    
+    /// The camera's view of a target of `size` pixels, seen from `world_matrix`
+    ///
+    /// The aspect ratio comes from the viewport's size in pixels, scale in the
+    /// matrix is ignored. Returns `None` if the viewport is empty.
+    pub fn render_view(&self, world_matrix: Mat4, size: (u32, u32)) -> Option<RenderView> {
+        let view_projection = self.view_projection(world_matrix, size)?;
+        let position = world_matrix.to_scale_rotation_translation().2;
+
+        let [r, g, b, a] = self.clear_color.map(f64::from);
+        Some(RenderView::new(view_projection, position)
+            .with_viewport(self.viewport)
+            .with_clear(self.clear_mode, wgpu::Color { r, g, b, a })
+            .with_sprite_view_projection(Some(view_projection)))
+    }
+    
+    /// View-projection of the camera for a target of `size` pixels, seen from `world_matrix`
+    ///
+    /// Like `render_view`, scale in the matrix is ignored. Returns `None` if the
+    /// viewport is empty.
+    pub fn view_projection(&self, world_matrix: Mat4, size: (u32, u32)) -> Option<Mat4> {
+        let (_, _, width, height) = crate::rendering::viewport_pixels(self.viewport, size)?;
+        let (_, rotation, position) = world_matrix.to_scale_rotation_translation();
+        let view = Mat4::from_rotation_translation(rotation, position).inverse();
+        Some(self.projection(width as f32 / height as f32) * view)
+    }
+    
+    /// World space corners of the volume the camera sees, near plane first
+    ///
+    /// Each plane's corners go bottom left, bottom right, top right, top left. For
+    /// orthographic cameras the near and far planes are the same size. Returns
+    /// `None` if the viewport is empty.
+    pub fn frustum_corners(&self, world_matrix: Mat4, size: (u32, u32)) -> Option<[Vec3; 8]> {
+        let inverse = self.view_projection(world_matrix, size)?.inverse();
+        // Clip space depth runs from 0 at the near plane to 1 at the far plane
+        let mut corners = [Vec3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let depth = if i < 4 { 0.0 } else { 1.0 };
+            let (x, y) = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)][i % 4];
+            *corner = inverse.project_point3(Vec3::new(x, y, depth));
         }
+        Some(corners)
     }
-} 
\ No newline at end of file
+
+    /// World space corners (min, max) of the part of the XY plane the camera sees
+    ///
+    /// Sprites and tilemaps lie in that plane, so this is the area to cull them
+    /// to, e.g. for `tilemap_render_system`. Returns `None` if the viewport is
+    /// empty or the camera doesn't see the plane.
+    pub fn visible_rect(&self, world_matrix: Mat4, size: (u32, u32)) -> Option<(Vec2, Vec2)> {
+        let corners = self.frustum_corners(world_matrix, size)?;
+        // The plane cuts the frustum along a polygon whose corners lie on the frustum's edges
+        const EDGES: [(usize, usize); 12] = [
+            (0, 1), (1, 2), (2, 3), (3, 0),
+            (4, 5), (5, 6), (6, 7), (7, 4),
+            (0, 4), (1, 5), (2, 6), (3, 7),
+        ];
+        let mut rect: Option<(Vec2, Vec2)> = None;
+        for (start, end) in EDGES {
+            let (start, end) = (corners[start], corners[end]);
+            if (start.z > 0.0 && end.z > 0.0) || (start.z < 0.0 && end.z < 0.0) {
+                continue;
+            }
+            let t = if start.z == end.z { 0.0 } else { start.z / (start.z - end.z) };
+            let point = start.lerp(end, t).truncate();
+            rect = Some(rect.map_or((point, point), |(min, max)| (min.min(point), max.max(point))));
+        }
+        rect
+    }
+}
+
+/// What kind of light a `LightComponent` casts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LightKind {
+    /// Parallel rays along the entity's forward axis, like the sun
+    #[default]
+    Directional,
+    /// Rays in every direction from the entity's position, fading out at the range
+    Point,
+}
+
+impl LightKind {
+    pub const ALL: [LightKind; 2] = [LightKind::Directional, LightKind::Point];
+
+    /// Name shown in the editor
+    pub fn label(self) -> &'static str {
+        match self {
+            LightKind::Directional => "Directional",
+            LightKind::Point => "Point",
+        }
+    }
+}
+
+/// Component that lights the scene from its entity
+///
+/// Directional lights shine along the entity's forward axis, its local -Z like
+/// cameras, and point lights from its position. `light_system` hands them to the
+/// renderer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightComponent {
+    /// Disabled lights don't light anything
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub kind: LightKind,
+    /// Linear RGB
+    pub color: Vec3,
+    pub intensity: f32,
+    /// Distance at which a point light has faded out, unused by directional lights
+    pub range: f32,
+    /// Size of a directional light's shadow map, unused by point lights
+    #[serde(default)]
+    pub shadow_resolution: ShadowResolution,
+    /// Depth offset that keeps surfaces from shadowing themselves, unused by point lights
+    #[serde(default = "default_shadow_bias")]
+    pub shadow_bias: f32,
+}
+
+fn default_shadow_bias() -> f32 {
+    DEFAULT_SHADOW_BIAS
+}
+
+impl LightComponent {
+    /// Create a white directional light
+    pub fn directional() -> Self {
+        Self {
+            enabled: true,
+            kind: LightKind::Directional,
+            color: Vec3::ONE,
+            intensity: 1.0,
+            range: 10.0,
+            shadow_resolution: ShadowResolution::default(),
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+        }
+    }
+
+    /// Create a white point light reaching `range` units
+    pub fn point(range: f32) -> Self {
+        Self {
+            kind: LightKind::Point,
+            range,
+            ..Self::directional()
+        }
+    }
+
+    /// The light placed by the entity's world matrix
+    pub fn light(&self, matrix: Mat4) -> Box<dyn Light> {
+        match self.kind {
+            LightKind::Directional => {
+                let direction = matrix.transform_vector3(Vec3::NEG_Z).try_normalize().unwrap_or(Vec3::NEG_Z);
+                let mut light = DirectionalLight::new(direction, self.color, self.intensity);
+                light.shadow_resolution = self.shadow_resolution;
+                light.shadow_bias = self.shadow_bias;
+                Box::new(light)
+            }
+            LightKind::Point => {
+                let position = matrix.w_axis.truncate();
+                Box::new(PointLight::new(position, self.color, self.intensity, self.range))
+            }
+        }
+    }
+}
+
+/// Component that gives its entity an audio clip to play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioSourceComponent {
+    /// Disabled sources don't play
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Path to the audio file, relative to the project root
+    pub clip: PathBuf,
+    /// Gain before the category and master volumes
+    pub volume: f32,
+    pub looping: bool,
+    /// Whether the clip starts playing when the scene starts
+    pub play_on_start: bool,
+    /// Whether the clip is attenuated and panned relative to the listener
+    pub spatial: bool,
+}
+
+impl AudioSourceComponent {
+    /// Create a source for `clip` at full volume, played once when the scene starts
+    pub fn new(clip: impl Into<PathBuf>) -> Self {
+        Self {
+            enabled: true,
+            clip: clip.into(),
+            volume: 1.0,
+            looping: false,
+            play_on_start: true,
+            spatial: false,
+        }
+    }
+}
+
+/// Where a `TextComponent` is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextSpace {
+    /// In the scene at the entity's transform, like a sprite
+    #[default]
+    World,
+    /// Over the rendered views, with the entity's position in pixels from the top left
+    Screen,
+}
+
+/// Component that draws a string, as a UI label or a label in the world
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextComponent {
+    /// Disabled text isn't drawn
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub text: String,
+    /// Height of the font in pixels, scaled by the entity's transform
+    pub font_size: f32,
+    /// Linear RGBA
+    pub color: [f32; 4],
+    pub align: TextAlign,
+    /// Width in pixels at which lines wrap between words, `None` only breaks at newlines
+    #[serde(default)]
+    pub max_width: Option<f32>,
+    #[serde(default)]
+    pub space: TextSpace,
+}
+
+impl TextComponent {
+    /// Create white, left-aligned world-space text
+    pub fn new(text: impl Into<String>) -> Self {
+        let style = TextStyle::default();
+        Self {
+            enabled: true,
+            text: text.into(),
+            font_size: style.font_size,
+            color: style.color,
+            align: style.align,
+            max_width: None,
+            space: TextSpace::World,
+        }
+    }
+    
+    /// How the renderer draws this text
+    pub fn style(&self) -> TextStyle {
+        TextStyle {
+            font_size: self.font_size,
+            color: self.color,
+            align: self.align,
+            max_width: self.max_width,
+        }
+    }
+}
+
+/// Component that runs a Lua script on its entity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LuaScriptComponent {
+    /// Disabled scripts aren't run, they keep their state until enabled again
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Path to the script file
+    pub path: PathBuf,
+    /// The loaded script, assigned by the script system on its first run
+    #[serde(skip)]
+    pub handle: Option<ScriptHandle>,
+}
+
+impl LuaScriptComponent {
+    /// Create a new script component
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { enabled: true, path: path.into(), handle: None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn assert_rect(rect: Option<(Vec2, Vec2)>, min: Vec2, max: Vec2) {
+        let (actual_min, actual_max) = rect.expect("the camera should see the plane");
+        assert!(actual_min.abs_diff_eq(min, 1e-3), "{actual_min} != {min}");
+        assert!(actual_max.abs_diff_eq(max, 1e-3), "{actual_max} != {max}");
+    }
+
+    #[test]
+    fn orthographic_camera_sees_its_size() {
+        let camera = CameraComponent::new_orthographic(5.0, 0.1, 100.0);
+        let matrix = Mat4::from_translation(Vec3::new(3.0, 0.0, 10.0));
+        assert_rect(camera.visible_rect(matrix, (200, 100)), Vec2::new(-7.0, -5.0), Vec2::new(13.0, 5.0));
+    }
+
+    #[test]
+    fn perspective_camera_sees_more_from_further_away() {
+        let camera = CameraComponent::new_perspective(FRAC_PI_2, 0.1, 100.0);
+        let near = Mat4::from_translation(Vec3::new(0.0, 0.0, 10.0));
+        let far = Mat4::from_translation(Vec3::new(0.0, 0.0, 20.0));
+        assert_rect(camera.visible_rect(near, (100, 100)), Vec2::splat(-10.0), Vec2::splat(10.0));
+        assert_rect(camera.visible_rect(far, (100, 100)), Vec2::splat(-20.0), Vec2::splat(20.0));
+    }
+
+    #[test]
+    fn camera_facing_away_sees_nothing() {
+        let camera = CameraComponent::new_orthographic(5.0, 0.1, 100.0);
+        let matrix = Mat4::from_rotation_translation(Quat::from_rotation_y(PI), Vec3::new(0.0, 0.0, 10.0));
+        assert_eq!(camera.visible_rect(matrix, (100, 100)), None);
+        assert_eq!(camera.visible_rect(Mat4::IDENTITY, (0, 100)), None);
+    }
+
+    #[test]
+    fn euler_round_trips_through_set_euler() {
+        let mut transform = Transform3DComponent::default();
+        let euler = Vec3::new(0.3, -0.6, 1.2);
+        transform.set_euler(euler);
+        assert!(transform.euler().abs_diff_eq(euler, 1e-5));
+        assert!(transform.rotation.abs_diff_eq(Quat::from_euler(math::EULER_ORDER, 0.3, -0.6, 1.2), 1e-6));
+    }
+
+    #[test]
+    fn rotate_axis_turns_around_the_world_axis() {
+        let mut transform = Transform3DComponent::default();
+        transform.rotate_axis(Vec3::Y * 3.0, FRAC_PI_2);
+        assert!(transform.forward().abs_diff_eq(Vec3::NEG_X, 1e-6));
+
+        // A world axis, so it applies after the existing rotation
+        let mut tilted = Transform3DComponent::new(Vec3::ZERO, Quat::from_rotation_x(FRAC_PI_2), Vec3::ONE);
+        tilted.rotate_axis(Vec3::Y, FRAC_PI_2);
+        let expected = Quat::from_rotation_y(FRAC_PI_2) * Quat::from_rotation_x(FRAC_PI_2);
+        assert!(tilted.rotation.abs_diff_eq(expected, 1e-6));
+
+        let before = tilted.rotation;
+        tilted.rotate_axis(Vec3::ZERO, 1.0);
+        assert_eq!(tilted.rotation, before);
+    }
+
+    #[test]
+    fn look_at_faces_the_target_upright() {
+        let mut transform = Transform3DComponent::new(Vec3::new(1.0, 2.0, 3.0), Quat::IDENTITY, Vec3::ONE);
+        let target = Vec3::new(4.0, -2.0, 3.0);
+        transform.look_at(target, Vec3::Y);
+        assert!(transform.forward().abs_diff_eq((target - transform.position).normalize(), 1e-5));
+        // The local X axis stays level and Y leans towards up
+        assert!((transform.rotation * Vec3::X).y.abs() < 1e-5);
+        assert!((transform.rotation * Vec3::Y).y > 0.0);
+        assert!(transform.rotation.is_normalized());
+    }
+
+    #[test]
+    fn look_at_ignores_degenerate_targets() {
+        let rotation = Quat::from_rotation_z(0.5);
+        let mut transform = Transform3DComponent::new(Vec3::ONE, rotation, Vec3::ONE);
+        transform.look_at(Vec3::ONE, Vec3::Y);
+        transform.look_at(Vec3::new(1.0, 5.0, 1.0), Vec3::Y);
+        assert_eq!(transform.rotation, rotation);
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+use hecs::Entity;
 use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use crate::ecs::EcsManager;
 
 /// A handle to an entity in the ECS
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EntityHandle {
     /// Unique identifier for the entity
     pub uuid: Uuid,
@@ -29,4 +33,100 @@ impl EntityHandle {
     pub fn short_id(&self) -> String {
         self.id()[..8].to_string()
     }
-} 
\ No newline at end of file
+} 
+/// A reference from a component to another entity that survives save and load
+///
+/// Serializes as the target's bare UUID, which scenes keep across save and load,
+/// and is looked up each time it's resolved rather than holding on to an `Entity`
+/// that may be despawned or reused. A reference to a deleted entity resolves to `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EntityRef {
+    uuid: Uuid,
+}
+
+impl EntityRef {
+    /// Create a reference to the entity with the given handle
+    pub fn new(handle: EntityHandle) -> Self {
+        Self { uuid: handle.uuid }
+    }
+    
+    /// Handle of the referenced entity, whether it still exists or not
+    pub fn handle(&self) -> EntityHandle {
+        EntityHandle::new(self.uuid)
+    }
+    
+    /// The referenced entity, `None` if it no longer exists
+    pub fn resolve(&self, ecs: &EcsManager) -> Option<Entity> {
+        ecs.get_entity(&self.handle())
+    }
+    
+    /// The referenced entity in a UUID to entity map, for systems given the map directly
+    pub fn resolve_in(&self, entity_map: &HashMap<Uuid, Entity>) -> Option<Entity> {
+        entity_map.get(&self.uuid).copied()
+    }
+    
+    /// Whether the referenced entity still exists
+    pub fn is_alive(&self, ecs: &EcsManager) -> bool {
+        self.resolve(ecs).is_some()
+    }
+}
+
+impl From<EntityHandle> for EntityRef {
+    fn from(handle: EntityHandle) -> Self {
+        Self::new(handle)
+    }
+}
+
+impl From<EntityRef> for EntityHandle {
+    fn from(reference: EntityRef) -> Self {
+        reference.handle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::NameComponent;
+    use crate::scene::SceneData;
+
+    fn name_of(ecs: &EcsManager, entity: Entity) -> String {
+        ecs.world().get::<&NameComponent>(entity).unwrap().name.clone()
+    }
+
+    #[test]
+    fn reference_survives_save_and_reload() {
+        let mut ecs = EcsManager::new();
+        ecs.create_entity((NameComponent::new("Player"),));
+        let target = ecs.create_entity((NameComponent::new("Target"),));
+        let reference = EntityRef::new(target);
+
+        let saved_reference = ron::to_string(&reference).unwrap();
+        let saved_scene = SceneData::from_world("Test", &ecs).to_ron().unwrap();
+
+        let mut reloaded = EcsManager::new();
+        SceneData::from_ron(&saved_scene).unwrap().apply_to_world(&mut reloaded);
+        let reference: EntityRef = ron::from_str(&saved_reference).unwrap();
+
+        let entity = reference.resolve(&reloaded).unwrap();
+        assert_eq!(name_of(&reloaded, entity), "Target");
+        assert_eq!(reference.handle(), target);
+        assert!(reference.is_alive(&reloaded));
+    }
+
+    #[test]
+    fn dangling_reference_resolves_to_none() {
+        let mut ecs = EcsManager::new();
+        let target = ecs.create_entity((NameComponent::new("Target"),));
+        let reference = EntityRef::new(target);
+        assert!(reference.is_alive(&ecs));
+
+        ecs.destroy_entity(&target);
+        // A new entity may reuse the despawned `Entity`, the reference must not follow it
+        ecs.create_entity((NameComponent::new("Newcomer"),));
+
+        assert_eq!(reference.resolve(&ecs), None);
+        assert!(!reference.is_alive(&ecs));
+        assert_eq!(reference.resolve_in(&HashMap::new()), None);
+    }
+}
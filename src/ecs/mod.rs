@@ -1,14 +1,28 @@
 mod entity;
 mod component;
 mod system;
+mod physics;
+mod prefab;
+mod query;
+mod tilemap;
+mod sprite;
 
 pub use entity::*;
 pub use component::*;
 pub use system::*;
+pub use physics::*;
+pub use prefab::*;
+pub use query::*;
+pub use tilemap::*;
+pub use sprite::*;
 
 use hecs::{World, Entity};
-use std::collections::HashMap;
+use glam::{Mat4, Vec3};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
+use crate::core::{EventSystem, Input, InputMap};
+use crate::utils::math::{Aabb, Ray};
+use crate::scripting::LuaEngine;
 
 /// The main ECS (Entity Component System) manager
 pub struct EcsManager {
@@ -16,6 +30,14 @@ pub struct EcsManager {
     world: World,
     /// Map from UUID to Entity for easier lookup
     entity_map: HashMap<Uuid, Entity>,
+    /// Map from Entity back to UUID
+    handle_map: HashMap<Entity, Uuid>,
+    /// Engine running `LuaScriptComponent`s, scripts are skipped without one
+    script_engine: Option<LuaEngine>,
+    /// Physics settings and collision state
+    physics: PhysicsState,
+    /// Events published by the systems, such as collisions and triggers
+    events: EventSystem,
 }
 
 impl EcsManager {
@@ -24,6 +46,10 @@ impl EcsManager {
         Self {
             world: World::new(),
             entity_map: HashMap::new(),
+            handle_map: HashMap::new(),
+            script_engine: None,
+            physics: PhysicsState::new(),
+            events: EventSystem::new(),
         }
     }
     
@@ -37,22 +63,426 @@ impl EcsManager {
         &mut self.world
     }
     
+    /// Set the engine that runs script components
+    pub fn set_script_engine(&mut self, engine: LuaEngine) {
+        self.script_engine = Some(engine);
+    }
+    
+    /// Get the script engine, if one is set
+    pub fn script_engine_mut(&mut self) -> Option<&mut LuaEngine> {
+        self.script_engine.as_mut()
+    }
+    
+    /// Update the input and actions scripts see, call it before `run_systems`
+    ///
+    /// `input_map` should already be updated from `input` for this frame.
+    pub fn set_script_input(&mut self, input: &Input, input_map: &InputMap) {
+        if let Some(engine) = &mut self.script_engine {
+            engine.set_input(input);
+            engine.set_input_map(input_map);
+        }
+    }
+    
+    /// Take the script errors reported since the last call
+    pub fn take_script_errors(&mut self) -> Vec<String> {
+        self.script_engine.as_mut().map(LuaEngine::take_errors).unwrap_or_default()
+    }
+    
+    /// Get the physics settings
+    pub fn physics_mut(&mut self) -> &mut PhysicsState {
+        &mut self.physics
+    }
+    
+    /// Events published by the systems, read them with an `EventReader`
+    pub fn events(&self) -> &EventSystem {
+        &self.events
+    }
+    
+    /// Events published by the systems, e.g. to subscribe or add handlers
+    pub fn events_mut(&mut self) -> &mut EventSystem {
+        &mut self.events
+    }
+    
     /// Create a new entity with the given components
     pub fn create_entity(&mut self, components: impl hecs::DynamicBundle) -> EntityHandle {
         let entity = self.world.spawn(components);
         let uuid = Uuid::new_v4();
         self.entity_map.insert(uuid, entity);
+        self.handle_map.insert(entity, uuid);
         EntityHandle::new(uuid)
     }
     
+    /// Create a new entity that keeps an existing handle (used when loading scenes)
+    pub fn create_entity_with_handle(&mut self, handle: EntityHandle, components: impl hecs::DynamicBundle) -> EntityHandle {
+        if let Some(existing) = self.entity_map.get(&handle.uuid).copied() {
+            self.handle_map.remove(&existing);
+            let _ = self.world.despawn(existing);
+        }
+        
+        let entity = self.world.spawn(components);
+        self.entity_map.insert(handle.uuid, entity);
+        self.handle_map.insert(entity, handle.uuid);
+        handle
+    }
+    
+    /// Spawn a copy of a prefab with fresh handles, returning the new root
+    ///
+    /// `position` overrides the root's position. Returns `None` for an empty prefab.
+    pub fn instantiate_prefab(&mut self, prefab: &Prefab, position: Option<Vec3>) -> Option<EntityHandle> {
+        let entities = prefab.instance(position);
+        for data in &entities {
+            data.spawn(self);
+        }
+        self.update_active();
+        entities.first().map(|root| root.handle)
+    }
+    
+    /// Remove all entities
+    pub fn clear(&mut self) {
+        self.world.clear();
+        self.entity_map.clear();
+        self.handle_map.clear();
+    }
+    
     /// Get an entity by its handle
     pub fn get_entity(&self, handle: &EntityHandle) -> Option<Entity> {
         self.entity_map.get(&handle.uuid).copied()
     }
     
+    /// Get the handle of an entity
+    pub fn get_handle(&self, entity: Entity) -> Option<EntityHandle> {
+        self.handle_map.get(&entity).map(|uuid| EntityHandle::new(*uuid))
+    }
+    
+    /// Query the entities that have handles, along with those handles
+    pub fn query<Q: hecs::Query>(&self) -> EcsQuery<'_, Q> {
+        EcsQuery::new(self.world.query::<Q>(), &self.handle_map)
+    }
+    
+    /// Query the entities that have handles, with mutable access to their components
+    pub fn query_mut<Q: hecs::Query>(&mut self) -> impl Iterator<Item = (EntityHandle, hecs::QueryItem<'_, Q>)> + '_ {
+        let handles = &self.handle_map;
+        self.world.query_mut::<Q>().into_iter().filter_map(move |(entity, item)| {
+            handles.get(&entity).map(|uuid| (EntityHandle::new(*uuid), item))
+        })
+    }
+    
+    /// Get a component of an entity
+    ///
+    /// The returned guard dereferences to the component.
+    pub fn get_component<T: hecs::Component>(&self, handle: &EntityHandle) -> Option<hecs::Ref<'_, T>> {
+        let entity = self.get_entity(handle)?;
+        self.world.get::<&T>(entity).ok()
+    }
+    
+    /// Get a component of an entity mutably
+    pub fn get_component_mut<T: hecs::Component>(&mut self, handle: &EntityHandle) -> Option<&mut T> {
+        let entity = self.get_entity(handle)?;
+        self.world.query_one_mut::<&mut T>(entity).ok()
+    }
+    
+    /// Add or replace a component of an entity
+    ///
+    /// Returns false if the entity doesn't exist.
+    pub fn insert_component<T: hecs::Component>(&mut self, handle: &EntityHandle, component: T) -> bool {
+        match self.get_entity(handle) {
+            Some(entity) => self.world.insert_one(entity, component).is_ok(),
+            None => false,
+        }
+    }
+    
+    /// Get all entity handles
+    pub fn handles(&self) -> Vec<EntityHandle> {
+        self.entity_map.keys().map(|uuid| EntityHandle::new(*uuid)).collect()
+    }
+    
+    /// Number of entities with handles
+    pub fn entity_count(&self) -> usize {
+        self.entity_map.len()
+    }
+    
+    /// Number of entities with each of the engine's component types, by component name
+    ///
+    /// Names match the ones the inspector shows. Types no entity has are counted as 0.
+    pub fn component_counts(&self) -> HashMap<&'static str, usize> {
+        HashMap::from([
+            ("Name", self.count::<NameComponent>()),
+            ("Parent", self.count::<ParentComponent>()),
+            ("Transform 2D", self.count::<Transform2DComponent>()),
+            ("Transform 3D", self.count::<Transform3DComponent>()),
+            ("Layer", self.count::<LayerComponent>()),
+            ("Rigidbody 2D", self.count::<PhysicsComponent>()),
+            ("Collider 2D", self.count::<Collider2DComponent>()),
+            ("Camera", self.count::<CameraComponent>()),
+            ("Light", self.count::<LightComponent>()),
+            ("Audio Source", self.count::<AudioSourceComponent>()),
+            ("Text", self.count::<TextComponent>()),
+            ("Renderable", self.count::<RenderableComponent>()),
+            ("Sprite", self.count::<SpriteComponent>()),
+            ("Sprite Animation", self.count::<SpriteAnimationComponent>()),
+            ("Tilemap", self.count::<TilemapComponent>()),
+            ("Lua Script", self.count::<LuaScriptComponent>()),
+        ])
+    }
+    
+    /// Number of entities with a component of type `T`
+    pub fn count<T: hecs::Component>(&self) -> usize {
+        self.world.query::<&T>().iter().count()
+    }
+    
+    /// Get the name of an entity
+    pub fn get_name(&self, handle: &EntityHandle) -> Option<String> {
+        self.get_component::<NameComponent>(handle).map(|name| name.name.clone())
+    }
+    
+    /// Get the parent of an entity
+    pub fn get_parent(&self, handle: &EntityHandle) -> Option<EntityHandle> {
+        self.get_component::<ParentComponent>(handle).map(|parent| parent.parent)
+    }
+    
+    /// World matrix of an entity, combining its transform with those of its parents
+    ///
+    /// Computed from the current transforms, so it doesn't wait for `transform_system`.
+    /// A parent chain that loops back on itself is cut where it repeats.
+    pub fn world_transform(&self, handle: &EntityHandle) -> Option<Mat4> {
+        let mut entity = self.get_entity(handle)?;
+        let mut visited = HashSet::from([entity]);
+        let mut matrix = local_matrix(&self.world, entity);
+        
+        while let Some(parent) = self.get_parent(&self.get_handle(entity)?).and_then(|p| self.get_entity(&p)) {
+            if !visited.insert(parent) {
+                break;
+            }
+            matrix = local_matrix(&self.world, parent) * matrix;
+            entity = parent;
+        }
+        
+        Some(matrix)
+    }
+    
+    /// Nearest visible renderable of an active entity hit by a ray, and the distance along the ray to the hit
+    ///
+    /// Rays are tested against each model's bounding box in world space, so hits are
+    /// approximate for rotated or non-box shapes. A ray starting inside a box hits it
+    /// at distance 0. Entities whose layer isn't in `layer_mask` are ignored; entities
+    /// without a `LayerComponent` are on layer 0.
+    pub fn raycast(&self, ray: &Ray, layer_mask: u32) -> Option<(EntityHandle, f32)> {
+        self.query::<(&RenderableComponent, Option<&LayerComponent>)>().iter()
+            .filter(|(handle, (renderable, layer))| {
+                renderable.visible && layer.copied().unwrap_or_default().mask() & layer_mask != 0
+                    && self.is_active_in_hierarchy(handle)
+            })
+            .filter_map(|(handle, (renderable, _))| {
+                let bounds = renderable.model.get().bounds().transformed(self.world_transform(&handle)?);
+                bounds.intersect_ray(ray).map(|distance| (handle, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+    
+    /// World space box around every visible renderable of an active entity, `None` if there are none
+    ///
+    /// Like `raycast`, boxes come from the current transforms rather than the last
+    /// `bounds_system` run, so they follow edits made since.
+    pub fn scene_bounds(&self) -> Option<Aabb> {
+        self.query::<&RenderableComponent>().iter()
+            .filter(|(handle, renderable)| renderable.visible && self.is_active_in_hierarchy(handle))
+            .filter_map(|(handle, renderable)| {
+                Some(renderable.model.get().bounds().transformed(self.world_transform(&handle)?))
+            })
+            .reduce(|bounds, other| bounds.union(&other))
+    }
+    
+    /// Set the parent of an entity, or clear it with `None`
+    ///
+    /// Returns false if either entity doesn't exist or the change would create a cycle
+    pub fn set_parent(&mut self, handle: &EntityHandle, parent: Option<EntityHandle>) -> bool {
+        let entity = match self.get_entity(handle) {
+            Some(entity) => entity,
+            None => return false,
+        };
+        
+        if self.get_parent(handle) == parent {
+            return true;
+        }
+        
+        match parent {
+            Some(parent) => {
+                if self.get_entity(&parent).is_none() || self.is_ancestor(handle, &parent) {
+                    return false;
+                }
+                if self.world.insert_one(entity, ParentComponent::new(parent)).is_err() {
+                    return false;
+                }
+            }
+            None => {
+                let _ = self.world.remove_one::<ParentComponent>(entity);
+            }
+        }
+        
+        // Moved entities go after their new siblings
+        let last = self.siblings(parent).iter()
+            .filter(|sibling| *sibling != handle)
+            .filter_map(|sibling| self.sibling_order(sibling))
+            .max();
+        let order = last.map_or(0, |last| last.saturating_add(1));
+        let ordered = self.world.insert_one(entity, SiblingOrderComponent::new(order)).is_ok();
+        
+        // The new parent may switch the entity on or off
+        self.update_active();
+        ordered
+    }
+    
+    /// Set the parent of an entity like `set_parent`, changing its transform so it stays put in the world
+    ///
+    /// Shear from a rotated parent with non-uniform scale can't be kept and is dropped.
+    pub fn set_parent_keep_world(&mut self, handle: &EntityHandle, parent: Option<EntityHandle>) -> bool {
+        let Some(world) = self.world_transform(handle) else {
+            return false;
+        };
+        if !self.set_parent(handle, parent) {
+            return false;
+        }
+        
+        let parent_world = parent.and_then(|parent| self.world_transform(&parent)).unwrap_or(Mat4::IDENTITY);
+        self.set_local_matrix(handle, parent_world.inverse() * world);
+        true
+    }
+    
+    /// Write a matrix into an entity's transform, its 2D transform if it only has that one
+    fn set_local_matrix(&mut self, handle: &EntityHandle, matrix: Mat4) {
+        let Some(entity) = self.get_entity(handle) else {
+            return;
+        };
+        let (scale, rotation, position) = matrix.to_scale_rotation_translation();
+        
+        if self.world.get::<&Transform3DComponent>(entity).is_err() {
+            if let Ok(mut transform) = self.world.get::<&mut Transform2DComponent>(entity) {
+                transform.position = position.truncate();
+                transform.rotation = rotation.to_euler(glam::EulerRot::ZYX).0;
+                transform.scale = scale.truncate();
+                return;
+            }
+        }
+        let _ = self.world.insert_one(entity, Transform3DComponent::new(position, rotation, scale));
+    }
+    
+    /// Get the direct children of an entity, in sibling order
+    pub fn children(&self, handle: &EntityHandle) -> Vec<EntityHandle> {
+        self.siblings(Some(*handle))
+    }
+    
+    /// Get the entities without a parent, in sibling order
+    pub fn root_entities(&self) -> Vec<EntityHandle> {
+        self.siblings(None)
+    }
+    
+    /// Get the children of `parent`, or the root entities for `None`, in sibling order
+    pub fn siblings(&self, parent: Option<EntityHandle>) -> Vec<EntityHandle> {
+        let mut siblings: Vec<EntityHandle> = self.handles().into_iter()
+            .filter(|handle| self.get_parent(handle) == parent)
+            .collect();
+        self.sort_siblings(&mut siblings);
+        siblings
+    }
+    
+    /// Sort entities by their sibling order
+    ///
+    /// Entities with the same order, or without one, are sorted by handle so the
+    /// result doesn't depend on storage order.
+    pub fn sort_siblings(&self, siblings: &mut [EntityHandle]) {
+        siblings.sort_by_cached_key(|handle| (self.sibling_order(handle).unwrap_or(i32::MAX), handle.uuid));
+    }
+    
+    /// Get the position of an entity among its siblings, if it has one
+    pub fn sibling_order(&self, handle: &EntityHandle) -> Option<i32> {
+        self.get_component::<SiblingOrderComponent>(handle).map(|order| order.order)
+    }
+    
+    /// Store `siblings` as the order of their entities, first to last
+    pub fn set_sibling_order(&mut self, siblings: &[EntityHandle]) {
+        for (index, handle) in siblings.iter().enumerate() {
+            self.insert_component(handle, SiblingOrderComponent::new(index as i32));
+        }
+    }
+    
+    /// The sibling order after moving an entity `offset` places, or `None` if it can't move
+    ///
+    /// Negative offsets move towards the first sibling.
+    pub fn moved_sibling_order(&self, handle: &EntityHandle, offset: isize) -> Option<Vec<EntityHandle>> {
+        self.get_entity(handle)?;
+        let mut siblings = self.siblings(self.get_parent(handle));
+        let index = siblings.iter().position(|sibling| sibling == handle)?;
+        let target = index.checked_add_signed(offset)?;
+        if target == index || target >= siblings.len() {
+            return None;
+        }
+        
+        let moved = siblings.remove(index);
+        siblings.insert(target, moved);
+        Some(siblings)
+    }
+    
+    /// Switch an entity, and with it its descendants, on or off
+    ///
+    /// Inactive entities are skipped by the systems until they're switched back on.
+    /// Returns false if the entity doesn't exist.
+    pub fn set_active(&mut self, handle: &EntityHandle, active: bool) -> bool {
+        if !self.insert_component(handle, ActiveComponent::new(active)) {
+            return false;
+        }
+        self.update_active();
+        true
+    }
+    
+    /// Whether an entity is switched on itself, regardless of its parents
+    ///
+    /// Entities without an `ActiveComponent` are active.
+    pub fn is_active(&self, handle: &EntityHandle) -> bool {
+        self.get_component::<ActiveComponent>(handle).map_or(true, |active| active.active)
+    }
+    
+    /// Whether an entity and all its parents are switched on
+    ///
+    /// Computed from the current flags, so it doesn't wait for `active_system`.
+    pub fn is_active_in_hierarchy(&self, handle: &EntityHandle) -> bool {
+        let mut visited = HashSet::new();
+        let mut current = Some(*handle);
+        while let Some(h) = current {
+            if !visited.insert(h) {
+                break;
+            }
+            if !self.is_active(&h) {
+                return false;
+            }
+            current = self.get_parent(&h);
+        }
+        true
+    }
+    
+    /// Update the `InactiveInHierarchy` markers after active flags or parents changed
+    ///
+    /// `run_systems` does this every frame, call it after changing the world
+    /// outside of it.
+    pub fn update_active(&mut self) {
+        active_system(&mut self.world, &self.entity_map);
+    }
+    
+    /// Check whether `ancestor` is `handle` or one of its parents
+    pub fn is_ancestor(&self, ancestor: &EntityHandle, handle: &EntityHandle) -> bool {
+        let mut current = Some(*handle);
+        while let Some(h) = current {
+            if h == *ancestor {
+                return true;
+            }
+            current = self.get_parent(&h);
+        }
+        false
+    }
+    
     /// Destroy an entity by its handle
     pub fn destroy_entity(&mut self, handle: &EntityHandle) -> bool {
         if let Some(entity) = self.entity_map.remove(&handle.uuid) {
+            self.handle_map.remove(&entity);
             self.world.despawn(entity).is_ok()
         } else {
             false
@@ -60,12 +490,37 @@ impl EcsManager {
     }
     
     /// Run all registered systems
+    ///
+    /// Inactive entities, and their descendants, are left out of every system.
+    /// Events the systems publish are readable from `events` until the end of
+    /// the next call.
     pub fn run_systems(&mut self, delta_time: f32) {
-        // Run transform system
-        transform_system(&mut self.world, delta_time);
+        // Events from two frames ago have been seen by every reader by now
+        self.events.update();
+        
+        // Find the inactive entities first so every system skips them
+        active_system(&mut self.world, &self.entity_map);
+        
+        // Run scripts first so physics sees their changes this frame
+        if let Some(engine) = &mut self.script_engine {
+            script_system(&mut self.world, engine, delta_time);
+        }
+        
+        // Sprites show this frame's animation frame
+        sprite_animation_system(&mut self.world, &self.handle_map, delta_time, &mut self.events);
         
         // Run physics system
-        physics_system(&mut self.world, delta_time);
+        physics_system(&mut self.world, &mut self.physics, delta_time);
+        self.physics.publish_events(&mut self.events);
+        
+        // Animated transforms, so world matrices follow them this frame
+        transform_animation_system(&mut self.world, &self.handle_map, delta_time, &mut self.events);
+        
+        // Run transform system
+        transform_system(&mut self.world, &self.entity_map, delta_time);
+        
+        // Bounds follow the transforms just computed
+        bounds_system(&mut self.world);
         
         // Run rendering system (prepare data for renderer)
         rendering_system(&mut self.world);
@@ -76,4 +531,171 @@ impl Default for EcsManager {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(ecs: &mut EcsManager, name: &str) -> EntityHandle {
+        ecs.create_entity((NameComponent::new(name),))
+    }
+
+    fn queried_names(ecs: &EcsManager) -> Vec<(EntityHandle, String)> {
+        let mut names: Vec<_> = ecs.query::<&NameComponent>().iter()
+            .map(|(handle, name)| (handle, name.name.clone()))
+            .collect();
+        names.sort_by(|a, b| a.1.cmp(&b.1));
+        names
+    }
+
+    #[test]
+    fn handles_and_entities_map_both_ways() {
+        let mut ecs = EcsManager::new();
+        let a = named(&mut ecs, "a");
+        let b = named(&mut ecs, "b");
+        assert_ne!(a, b);
+
+        for handle in [a, b] {
+            let entity = ecs.get_entity(&handle).unwrap();
+            assert_eq!(ecs.get_handle(entity), Some(handle));
+        }
+        assert_eq!(queried_names(&ecs), vec![(a, "a".to_string()), (b, "b".to_string())]);
+        assert_eq!(ecs.entity_count(), 2);
+    }
+
+    #[test]
+    fn destroyed_entities_are_gone_everywhere() {
+        let mut ecs = EcsManager::new();
+        let a = named(&mut ecs, "a");
+        let b = named(&mut ecs, "b");
+        let entity = ecs.get_entity(&a).unwrap();
+
+        assert!(ecs.destroy_entity(&a));
+        assert_eq!(ecs.get_entity(&a), None);
+        assert_eq!(ecs.get_handle(entity), None);
+        assert!(!ecs.world().contains(entity));
+        assert_eq!(queried_names(&ecs), vec![(b, "b".to_string())]);
+        assert_eq!(ecs.handles(), vec![b]);
+        assert!(!ecs.destroy_entity(&a));
+    }
+
+    #[test]
+    fn recycled_entity_ids_get_fresh_handles() {
+        let mut ecs = EcsManager::new();
+        let a = named(&mut ecs, "a");
+        ecs.destroy_entity(&a);
+        let b = named(&mut ecs, "b");
+        assert_ne!(a, b);
+        assert_eq!(ecs.get_entity(&a), None);
+        assert_eq!(queried_names(&ecs), vec![(b, "b".to_string())]);
+    }
+
+    #[test]
+    fn entities_without_handles_are_left_out_of_queries() {
+        let mut ecs = EcsManager::new();
+        let a = named(&mut ecs, "a");
+        let raw = ecs.world_mut().spawn((NameComponent::new("raw"),));
+        assert_eq!(ecs.get_handle(raw), None);
+        assert_eq!(queried_names(&ecs), vec![(a, "a".to_string())]);
+        assert_eq!(ecs.query_mut::<&mut NameComponent>().count(), 1);
+    }
+
+    #[test]
+    fn creating_with_an_existing_handle_replaces_the_entity() {
+        let mut ecs = EcsManager::new();
+        let a = named(&mut ecs, "a");
+        let old = ecs.get_entity(&a).unwrap();
+
+        assert_eq!(ecs.create_entity_with_handle(a, (NameComponent::new("new"),)), a);
+        assert!(!ecs.world().contains(old));
+        assert_eq!(ecs.get_handle(old), None);
+        assert_eq!(ecs.get_name(&a).as_deref(), Some("new"));
+        assert_eq!(ecs.entity_count(), 1);
+    }
+
+    /// `root` with a child, which has a child of its own
+    fn chain(ecs: &mut EcsManager) -> (EntityHandle, EntityHandle, EntityHandle) {
+        let root = named(ecs, "root");
+        let child = named(ecs, "child");
+        let grandchild = named(ecs, "grandchild");
+        assert!(ecs.set_parent(&child, Some(root)));
+        assert!(ecs.set_parent(&grandchild, Some(child)));
+        (root, child, grandchild)
+    }
+
+    #[test]
+    fn reparenting_onto_itself_is_rejected() {
+        let mut ecs = EcsManager::new();
+        let (root, child, _) = chain(&mut ecs);
+        assert!(!ecs.set_parent(&root, Some(root)));
+        assert!(!ecs.set_parent(&child, Some(child)));
+        assert_eq!(ecs.get_parent(&root), None);
+        assert_eq!(ecs.get_parent(&child), Some(root));
+    }
+
+    #[test]
+    fn reparenting_onto_a_descendant_is_rejected() {
+        let mut ecs = EcsManager::new();
+        let (root, child, grandchild) = chain(&mut ecs);
+        assert!(!ecs.set_parent(&root, Some(child)));
+        assert!(!ecs.set_parent(&root, Some(grandchild)));
+        assert!(!ecs.set_parent_keep_world(&root, Some(grandchild)));
+        assert_eq!(ecs.get_parent(&root), None);
+        assert_eq!(ecs.root_entities(), vec![root]);
+    }
+
+    #[test]
+    fn reparented_entity_goes_after_its_new_siblings() {
+        let mut ecs = EcsManager::new();
+        let (root, child, grandchild) = chain(&mut ecs);
+        let other = named(&mut ecs, "other");
+        assert!(ecs.set_parent(&other, Some(root)));
+        assert!(ecs.set_parent(&grandchild, Some(root)));
+        assert_eq!(ecs.children(&root), vec![child, other, grandchild]);
+        assert!(ecs.children(&child).is_empty());
+
+        // Moving up past the first sibling isn't possible
+        assert_eq!(ecs.moved_sibling_order(&child, -1), None);
+        let order = ecs.moved_sibling_order(&grandchild, -2).unwrap();
+        ecs.set_sibling_order(&order);
+        assert_eq!(ecs.children(&root), vec![grandchild, child, other]);
+    }
+
+    #[test]
+    fn scene_without_renderables_has_no_bounds() {
+        let mut ecs = EcsManager::new();
+        assert_eq!(ecs.scene_bounds(), None);
+        named(&mut ecs, "empty");
+        assert_eq!(ecs.scene_bounds(), None);
+    }
+
+    #[tokio::test]
+    async fn scene_bounds_unions_spread_apart_entities() {
+        use std::sync::Arc;
+        use glam::Quat;
+        use crate::rendering::{DepthFormat, Mesh, MeshData, Renderer};
+
+        let renderer = match Renderer::new_headless(4, 4, DepthFormat::default()).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                return;
+            }
+        };
+        let mesh = Arc::new(Mesh::from_data(renderer.device(), &MeshData::cube(1.0)).unwrap());
+        let material = Arc::new(renderer.create_material("Test", None));
+
+        let mut ecs = EcsManager::new();
+        for (position, scale) in [(Vec3::new(-10.0, 0.0, 0.0), 1.0), (Vec3::new(10.0, 5.0, 0.0), 2.0)] {
+            ecs.create_entity((
+                Transform3DComponent::new(position, Quat::IDENTITY, Vec3::splat(scale)),
+                RenderableComponent::from_parts(renderer.device(), mesh.clone(), material.clone()),
+            ));
+        }
+
+        let bounds = ecs.scene_bounds().unwrap();
+        assert!(bounds.min.abs_diff_eq(Vec3::new(-10.5, -0.5, -1.0), 1e-5), "{:?}", bounds);
+        assert!(bounds.max.abs_diff_eq(Vec3::new(11.0, 6.0, 1.0), 1e-5), "{:?}", bounds);
+    }
+}
@@ -0,0 +1,510 @@
+use std::collections::HashSet;
+use glam::Vec2;
+use hecs::{Entity, World};
+use crate::core::{Event, EventSystem};
+use crate::ecs::{BodyType, Collider2DComponent, InactiveInHierarchy, PhysicsComponent, Transform2DComponent, WorldShape};
+
+/// Default gravity, in units per second squared
+pub const DEFAULT_GRAVITY: Vec2 = Vec2::new(0.0, -9.81);
+
+/// Penetration left unresolved to keep resting contacts stable
+const PENETRATION_SLOP: f32 = 0.005;
+
+/// Fraction of the remaining penetration resolved each step
+const CORRECTION_PERCENT: f32 = 0.8;
+
+/// Two solid colliders touching
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    /// Contact normal pointing from `a` to `b`
+    pub normal: Vec2,
+    /// Overlap depth before resolution
+    pub penetration: f32,
+}
+
+impl Event for CollisionEvent {
+    fn name(&self) -> &'static str {
+        "CollisionEvent"
+    }
+}
+
+/// A collider started overlapping a trigger
+#[derive(Debug, Clone, Copy)]
+pub struct TriggerEnterEvent {
+    pub trigger: Entity,
+    pub other: Entity,
+}
+
+impl Event for TriggerEnterEvent {
+    fn name(&self) -> &'static str {
+        "TriggerEnterEvent"
+    }
+}
+
+/// A collider stopped overlapping a trigger
+#[derive(Debug, Clone, Copy)]
+pub struct TriggerExitEvent {
+    pub trigger: Entity,
+    pub other: Entity,
+}
+
+impl Event for TriggerExitEvent {
+    fn name(&self) -> &'static str {
+        "TriggerExitEvent"
+    }
+}
+
+/// An event produced by the physics system
+#[derive(Debug, Clone, Copy)]
+pub enum PhysicsEvent {
+    Collision(CollisionEvent),
+    TriggerEnter(TriggerEnterEvent),
+    TriggerExit(TriggerExitEvent),
+}
+
+/// Physics settings and the state kept between steps
+pub struct PhysicsState {
+    /// Gravity applied to dynamic bodies
+    pub gravity: Vec2,
+    /// Trigger overlaps from the previous step, as (trigger, other)
+    active_triggers: HashSet<(Entity, Entity)>,
+    /// Events since the last `drain_events`
+    events: Vec<PhysicsEvent>,
+}
+
+impl PhysicsState {
+    pub fn new() -> Self {
+        Self {
+            gravity: DEFAULT_GRAVITY,
+            active_triggers: HashSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Forget the trigger overlaps and pending events, keeping the settings
+    pub fn reset(&mut self) {
+        self.active_triggers.clear();
+        self.events.clear();
+    }
+
+    /// Take the events produced since the last call
+    pub fn drain_events(&mut self) -> Vec<PhysicsEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Publish and clear the pending events
+    pub fn publish_events(&mut self, event_system: &mut EventSystem) {
+        for event in self.drain_events() {
+            match event {
+                PhysicsEvent::Collision(event) => event_system.publish(event),
+                PhysicsEvent::TriggerEnter(event) => event_system.publish(event),
+                PhysicsEvent::TriggerExit(event) => event_system.publish(event),
+            }
+        }
+    }
+}
+
+impl Default for PhysicsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A collider in world space for one step
+struct ColliderProxy {
+    entity: Entity,
+    shape: WorldShape,
+    min: Vec2,
+    max: Vec2,
+    inverse_mass: f32,
+    is_trigger: bool,
+}
+
+/// Overlap between two colliders
+struct Contact {
+    /// Points from the first collider to the second
+    normal: Vec2,
+    penetration: f32,
+}
+
+/// System that integrates 2D bodies and resolves collisions between their colliders
+///
+/// Inactive entities and disabled colliders take no part. Disabled bodies aren't
+/// moved, and their colliders act like static ones.
+pub fn physics_system(world: &mut World, state: &mut PhysicsState, delta_time: f32) {
+    integrate(world, state.gravity, delta_time);
+
+    let mut proxies = collect_colliders(world);
+
+    // Sweep and prune along X
+    proxies.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+
+    let mut triggers = HashSet::new();
+    for i in 0..proxies.len() {
+        for j in (i + 1)..proxies.len() {
+            let (a, b) = (&proxies[i], &proxies[j]);
+            if b.min.x > a.max.x {
+                break;
+            }
+            if b.min.y > a.max.y || a.min.y > b.max.y {
+                continue;
+            }
+
+            let Some(contact) = contact(a, b) else { continue };
+
+            if a.is_trigger || b.is_trigger {
+                // Keep the pair order stable between steps when both are triggers
+                let a_is_trigger = a.is_trigger && (!b.is_trigger || a.entity.to_bits() < b.entity.to_bits());
+                let pair = if a_is_trigger { (a.entity, b.entity) } else { (b.entity, a.entity) };
+                triggers.insert(pair);
+                continue;
+            }
+
+            if a.inverse_mass + b.inverse_mass == 0.0 {
+                continue;
+            }
+
+            resolve(world, a, b, &contact);
+            state.events.push(PhysicsEvent::Collision(CollisionEvent {
+                a: a.entity,
+                b: b.entity,
+                normal: contact.normal,
+                penetration: contact.penetration,
+            }));
+        }
+    }
+
+    for &(trigger, other) in triggers.difference(&state.active_triggers) {
+        state.events.push(PhysicsEvent::TriggerEnter(TriggerEnterEvent { trigger, other }));
+    }
+    for &(trigger, other) in state.active_triggers.difference(&triggers) {
+        state.events.push(PhysicsEvent::TriggerExit(TriggerExitEvent { trigger, other }));
+    }
+    state.active_triggers = triggers;
+}
+
+/// Apply gravity and drag, then move bodies by their velocity
+fn integrate(world: &mut World, gravity: Vec2, delta_time: f32) {
+    for (_id, (transform, physics)) in world.query_mut::<(&mut Transform2DComponent, &mut PhysicsComponent)>().without::<&InactiveInHierarchy>() {
+        if !physics.enabled {
+            continue;
+        }
+        match physics.body_type {
+            BodyType::Static => continue,
+            BodyType::Kinematic => {}
+            BodyType::Dynamic => {
+                if physics.use_gravity {
+                    physics.velocity += gravity * physics.gravity_scale * delta_time;
+                }
+                physics.velocity /= 1.0 + physics.linear_drag * delta_time;
+                physics.angular_velocity /= 1.0 + physics.angular_drag * delta_time;
+            }
+        }
+
+        transform.position += physics.velocity * delta_time;
+        transform.rotation += physics.angular_velocity * delta_time;
+    }
+}
+
+/// Enabled colliders of the active entities with a 2D transform, invalid polygons are skipped
+fn collect_colliders(world: &World) -> Vec<ColliderProxy> {
+    let mut query = world.query::<(&Transform2DComponent, &Collider2DComponent, Option<&PhysicsComponent>)>()
+        .without::<&InactiveInHierarchy>();
+
+    query.iter()
+        .filter(|(_, (_, collider, _))| collider.enabled && collider.shape.is_valid())
+        .map(|(entity, (transform, collider, physics))| {
+            let shape = collider.world_shape(transform.position, transform.rotation, transform.scale);
+            let (min, max) = shape.bounds();
+
+            ColliderProxy {
+                entity,
+                shape,
+                min,
+                max,
+                inverse_mass: physics.filter(|p| p.enabled).map_or(0.0, |p| p.inverse_mass()),
+                is_trigger: collider.is_trigger,
+            }
+        })
+        .collect()
+}
+
+/// Push two overlapping bodies apart and stop them moving into each other
+fn resolve(world: &World, a: &ColliderProxy, b: &ColliderProxy, contact: &Contact) {
+    let total_inverse_mass = a.inverse_mass + b.inverse_mass;
+
+    // Positional correction, split by inverse mass
+    let correction = contact.normal
+        * ((contact.penetration - PENETRATION_SLOP).max(0.0) / total_inverse_mass)
+        * CORRECTION_PERCENT;
+    if let Ok(mut transform) = world.get::<&mut Transform2DComponent>(a.entity) {
+        transform.position -= correction * a.inverse_mass;
+    }
+    if let Ok(mut transform) = world.get::<&mut Transform2DComponent>(b.entity) {
+        transform.position += correction * b.inverse_mass;
+    }
+
+    // Cancel the relative velocity along the normal, without bounce
+    let velocity_a = world.get::<&PhysicsComponent>(a.entity).map_or(Vec2::ZERO, |p| p.velocity);
+    let velocity_b = world.get::<&PhysicsComponent>(b.entity).map_or(Vec2::ZERO, |p| p.velocity);
+    let normal_velocity = (velocity_b - velocity_a).dot(contact.normal);
+    if normal_velocity >= 0.0 {
+        return;
+    }
+
+    let impulse = contact.normal * (-normal_velocity / total_inverse_mass);
+    if let Ok(mut physics) = world.get::<&mut PhysicsComponent>(a.entity) {
+        physics.velocity -= impulse * a.inverse_mass;
+    }
+    if let Ok(mut physics) = world.get::<&mut PhysicsComponent>(b.entity) {
+        physics.velocity += impulse * b.inverse_mass;
+    }
+}
+
+fn contact(a: &ColliderProxy, b: &ColliderProxy) -> Option<Contact> {
+    match (&a.shape, &b.shape) {
+        (WorldShape::Circle { center: ca, radius: ra }, WorldShape::Circle { center: cb, radius: rb }) => {
+            circle_circle(*ca, *ra, *cb, *rb)
+        }
+        (WorldShape::Polygon { center: ca, vertices: va }, WorldShape::Polygon { center: cb, vertices: vb }) => {
+            polygon_polygon(*ca, va, *cb, vb)
+        }
+        (WorldShape::Polygon { center: polygon_center, vertices }, WorldShape::Circle { center, radius }) => {
+            polygon_circle(*polygon_center, vertices, *center, *radius)
+        }
+        (WorldShape::Circle { center, radius }, WorldShape::Polygon { center: polygon_center, vertices }) => {
+            polygon_circle(*polygon_center, vertices, *center, *radius).map(|c| Contact {
+                normal: -c.normal,
+                penetration: c.penetration,
+            })
+        }
+    }
+}
+
+fn circle_circle(ca: Vec2, ra: f32, cb: Vec2, rb: f32) -> Option<Contact> {
+    let d = cb - ca;
+    let distance = d.length();
+    let radii = ra + rb;
+    if distance >= radii {
+        return None;
+    }
+
+    let normal = if distance > 0.0 { d / distance } else { Vec2::Y };
+    Some(Contact { normal, penetration: radii - distance })
+}
+
+/// Separating axis test between two convex polygons, boxes included
+fn polygon_polygon(ca: Vec2, va: &[Vec2], cb: Vec2, vb: &[Vec2]) -> Option<Contact> {
+    let axes = edge_normals(va).chain(edge_normals(vb));
+    least_overlap(axes, |axis| project_polygon(va, axis), |axis| project_polygon(vb, axis))
+        .map(|contact| facing(contact, cb - ca))
+}
+
+/// Separating axis test with the normal pointing from the polygon to the circle
+///
+/// Besides the polygon's edge normals, the axis towards the polygon's corner
+/// nearest the circle is tested, which separates circles beyond a corner.
+fn polygon_circle(polygon_center: Vec2, vertices: &[Vec2], circle_center: Vec2, radius: f32) -> Option<Contact> {
+    let nearest = vertices.iter()
+        .copied()
+        .min_by(|a, b| a.distance_squared(circle_center).total_cmp(&b.distance_squared(circle_center)))?;
+    let corner_axis = (circle_center - nearest).normalize_or_zero();
+    let axes = edge_normals(vertices).chain(Some(corner_axis).filter(|axis| *axis != Vec2::ZERO));
+
+    let project_circle = |axis: Vec2| {
+        let center = circle_center.dot(axis);
+        (center - radius, center + radius)
+    };
+    least_overlap(axes, |axis| project_polygon(vertices, axis), project_circle)
+        .map(|contact| facing(contact, circle_center - polygon_center))
+}
+
+/// Axis of least overlap between two shapes' projections, `None` if any axis separates them
+fn least_overlap(
+    axes: impl Iterator<Item = Vec2>,
+    project_a: impl Fn(Vec2) -> (f32, f32),
+    project_b: impl Fn(Vec2) -> (f32, f32),
+) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    for axis in axes {
+        let (min_a, max_a) = project_a(axis);
+        let (min_b, max_b) = project_b(axis);
+        let overlap = max_a.min(max_b) - min_a.max(min_b);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if best.as_ref().map_or(true, |best| overlap < best.penetration) {
+            best = Some(Contact { normal: axis, penetration: overlap });
+        }
+    }
+    best
+}
+
+/// Flip the contact normal to point along `direction`, from the first shape to the second
+fn facing(contact: Contact, direction: Vec2) -> Contact {
+    if contact.normal.dot(direction) < 0.0 {
+        Contact { normal: -contact.normal, penetration: contact.penetration }
+    } else {
+        contact
+    }
+}
+
+/// Unit normals of a polygon's edges
+fn edge_normals(vertices: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    (0..vertices.len()).filter_map(move |i| {
+        let edge = vertices[(i + 1) % vertices.len()] - vertices[i];
+        let normal = Vec2::new(edge.y, -edge.x).normalize_or_zero();
+        (normal != Vec2::ZERO).then_some(normal)
+    })
+}
+
+/// Smallest and largest position of the vertices along an axis
+fn project_polygon(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    vertices.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), vertex| {
+        let position = vertex.dot(axis);
+        (min.min(position), max.max(position))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+    use crate::ecs::{ColliderShape, EcsManager};
+    use crate::utils::math;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    /// A static 10x1 floor with its top at y = 0.5 and a 1x1 dynamic box `height` above the origin
+    fn floor_and_box(world: &mut World, height: f32) -> Entity {
+        world.spawn((
+            Transform2DComponent::new(Vec2::ZERO, 0.0, Vec2::ONE),
+            Collider2DComponent::new_box(Vec2::new(10.0, 1.0)),
+        ));
+        world.spawn((
+            Transform2DComponent::new(Vec2::new(0.0, height), 0.0, Vec2::ONE),
+            Collider2DComponent::new_box(Vec2::ONE),
+            PhysicsComponent::default(),
+        ))
+    }
+
+    #[test]
+    fn falling_box_comes_to_rest_on_the_floor() {
+        let mut world = World::new();
+        let mut state = PhysicsState::new();
+        let falling = floor_and_box(&mut world, 3.0);
+
+        for _ in 0..300 {
+            physics_system(&mut world, &mut state, STEP);
+        }
+
+        let position = world.get::<&Transform2DComponent>(falling).unwrap().position;
+        let velocity = world.get::<&PhysicsComponent>(falling).unwrap().velocity;
+        assert!((position.y - 1.0).abs() < 0.05, "box rests at y = {}", position.y);
+        assert!(position.x.abs() < 1e-4);
+        assert!(velocity.y.abs() < 0.5, "box still moves at {}", velocity.y);
+    }
+
+    #[test]
+    fn collision_events_are_published_each_step_without_piling_up() {
+        let mut ecs = EcsManager::new();
+        floor_and_box(ecs.world_mut(), 0.9);
+        let mut reader = ecs.events_mut().subscribe::<CollisionEvent>();
+
+        ecs.run_systems(STEP);
+        assert!(reader.read(ecs.events()).count() > 0);
+
+        for _ in 0..100 {
+            ecs.run_systems(STEP);
+        }
+        assert!(ecs.physics_mut().drain_events().is_empty());
+        // Only this frame's and the previous frame's contacts are kept
+        assert!(reader.read(ecs.events()).count() <= 2);
+    }
+
+    /// A dynamic collider placed at `position`, rotated by `rotation` radians
+    fn proxy(collider: Collider2DComponent, position: Vec2, rotation: f32) -> ColliderProxy {
+        let shape = collider.world_shape(position, rotation, Vec2::ONE);
+        let (min, max) = shape.bounds();
+        ColliderProxy { entity: Entity::DANGLING, shape, min, max, inverse_mass: 1.0, is_trigger: false }
+    }
+
+    fn unit_box(position: Vec2, rotation: f32) -> ColliderProxy {
+        proxy(Collider2DComponent::new_box(Vec2::ONE), position, rotation)
+    }
+
+    fn assert_contact(contact: Option<Contact>, normal: Vec2, penetration: f32) {
+        let contact = contact.expect("shapes should overlap");
+        assert!(contact.normal.abs_diff_eq(normal, 1e-4), "normal {:?}, expected {:?}", contact.normal, normal);
+        assert!((contact.penetration - penetration).abs() < 1e-4, "penetration {}, expected {}", contact.penetration, penetration);
+    }
+
+    #[test]
+    fn rotated_boxes_overlap_along_their_own_axis() {
+        let a = unit_box(Vec2::ZERO, FRAC_PI_4);
+        let b = unit_box(Vec2::new(1.2, 0.0), FRAC_PI_4);
+        let hit = contact(&a, &b).expect("boxes should overlap");
+        // Either diagonal separates the boxes equally, both point away from `a`
+        assert!((hit.normal.x - FRAC_1_SQRT_2).abs() < 1e-4, "normal {:?}", hit.normal);
+        assert!((hit.normal.y.abs() - FRAC_1_SQRT_2).abs() < 1e-4, "normal {:?}", hit.normal);
+        assert!((hit.penetration - (1.0 - 1.2 * FRAC_1_SQRT_2)).abs() < 1e-4);
+
+        assert!(contact(&b, &a).unwrap().normal.x < 0.0);
+    }
+
+    #[test]
+    fn rotated_boxes_with_overlapping_bounds_can_be_separate() {
+        let a = unit_box(Vec2::ZERO, FRAC_PI_4);
+        let b = unit_box(Vec2::new(1.1, 1.1), FRAC_PI_4);
+        assert!(a.max.x > b.min.x && a.max.y > b.min.y);
+        assert!(contact(&a, &b).is_none());
+    }
+
+    #[test]
+    fn rotated_box_corner_pushes_into_a_face() {
+        let a = unit_box(Vec2::ZERO, 0.0);
+        let b = unit_box(Vec2::new(1.1, 0.0), FRAC_PI_4);
+        assert_contact(contact(&a, &b), Vec2::X, FRAC_1_SQRT_2 - 0.6);
+
+        let b = unit_box(Vec2::new(1.25, 0.0), FRAC_PI_4);
+        assert!(contact(&a, &b).is_none());
+    }
+
+    #[test]
+    fn polygon_and_circle_collide_across_an_edge_but_not_past_a_corner() {
+        let triangle = Collider2DComponent::new_polygon(&[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(0.0, 2.0)]).unwrap();
+        let polygon = proxy(triangle, Vec2::ZERO, 0.0);
+
+        let circle = proxy(Collider2DComponent::new_circle(0.5), Vec2::new(1.2, 1.2), 0.0);
+        // The hypotenuse is 0.4 / sqrt(2) away from the circle's center
+        assert_contact(contact(&polygon, &circle), Vec2::splat(FRAC_1_SQRT_2), 0.5 - 0.4 * FRAC_1_SQRT_2);
+        assert_contact(contact(&circle, &polygon), -Vec2::splat(FRAC_1_SQRT_2), 0.5 - 0.4 * FRAC_1_SQRT_2);
+
+        // Overlaps every edge axis, only the axis to the nearest corner separates
+        let beyond_corner = proxy(Collider2DComponent::new_circle(0.5), Vec2::new(2.4, -0.4), 0.0);
+        assert!(contact(&polygon, &beyond_corner).is_none());
+    }
+
+    #[test]
+    fn polygon_colliders_need_three_points_off_one_line() {
+        assert!(Collider2DComponent::new_polygon(&[]).is_none());
+        assert!(Collider2DComponent::new_polygon(&[Vec2::ZERO, Vec2::X]).is_none());
+        assert!(Collider2DComponent::new_polygon(&[Vec2::ZERO, Vec2::ZERO, Vec2::X, Vec2::X]).is_none());
+        assert!(Collider2DComponent::new_polygon(&[Vec2::ZERO, Vec2::ONE, Vec2::splat(2.0), Vec2::splat(3.0)]).is_none());
+    }
+
+    #[test]
+    fn polygon_collider_of_a_concave_outline_is_its_convex_hull() {
+        let dent = Vec2::new(1.0, 0.5);
+        let outline = [Vec2::ZERO, Vec2::new(2.0, 0.0), dent, Vec2::new(2.0, 2.0), Vec2::new(0.0, 2.0)];
+        let collider = Collider2DComponent::new_polygon(&outline).unwrap();
+        let ColliderShape::Polygon { points } = &collider.shape else {
+            panic!("expected a polygon, got {:?}", collider.shape);
+        };
+        assert_eq!(points.len(), 4);
+        assert!(!points.contains(&dent));
+        assert!(math::is_convex_polygon(points));
+    }
+}
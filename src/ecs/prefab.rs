@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use glam::Vec3;
+use serde::{Serialize, Deserialize};
+use crate::ecs::{EcsManager, EntityHandle, ParentComponent};
+use crate::scene::{migrate_to_current, EntityData, SceneError, SCENE_FORMAT_VERSION};
+
+/// File extension used for prefab assets
+pub const PREFAB_EXTENSION: &str = "prefab";
+
+/// A reusable template of an entity and its children
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prefab {
+    /// Format version, prefabs share the scene format and its migrations
+    pub version: u32,
+    /// Name of the prefab
+    pub name: String,
+    /// Captured entities, root first and parents before their children
+    pub entities: Vec<EntityData>,
+}
+
+impl Prefab {
+    /// Capture an entity, its children and all of their components
+    ///
+    /// Returns `None` if the entity doesn't exist.
+    pub fn from_entity(ecs: &EcsManager, handle: EntityHandle) -> Option<Self> {
+        let mut root = EntityData::capture(ecs, &handle)?;
+        // The root is placed wherever the prefab is instantiated
+        root.parent = None;
+
+        let name = root.name.as_ref().map_or_else(|| "Prefab".to_string(), |n| n.name.clone());
+
+        // Breadth first so parents come before their children
+        let mut entities = vec![root];
+        let mut i = 0;
+        while i < entities.len() {
+            let children = ecs.children(&entities[i].handle);
+            entities.extend(children.iter().filter_map(|child| EntityData::capture(ecs, child)));
+            i += 1;
+        }
+
+        Some(Self { version: SCENE_FORMAT_VERSION, name, entities })
+    }
+
+    /// Position of the root, from its 3D transform or else its 2D one
+    pub fn root_position(&self) -> Option<Vec3> {
+        let root = self.entities.first()?;
+        root.transform_3d.as_ref().map(|t| t.position)
+            .or_else(|| root.transform_2d.as_ref().map(|t| t.position.extend(0.0)))
+    }
+
+    /// Copy the entities with fresh handles, remapping parents to the new handles
+    ///
+    /// `position` overrides the position of the root's transforms.
+    pub fn instance(&self, position: Option<Vec3>) -> Vec<EntityData> {
+        let handles: HashMap<EntityHandle, EntityHandle> = self.entities.iter()
+            .map(|data| (data.handle, EntityHandle::generate()))
+            .collect();
+
+        let mut entities: Vec<EntityData> = self.entities.iter().map(|data| {
+            let mut data = data.clone();
+            data.handle = handles[&data.handle];
+            data.parent = data.parent
+                .and_then(|p| handles.get(&p.parent).copied())
+                .map(ParentComponent::new);
+            data
+        }).collect();
+
+        if let (Some(root), Some(position)) = (entities.first_mut(), position) {
+            if let Some(transform) = &mut root.transform_3d {
+                transform.position = position;
+            }
+            if let Some(transform) = &mut root.transform_2d {
+                transform.position = position.truncate();
+            }
+        }
+
+        entities
+    }
+
+    /// Serialize the prefab to a RON string
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Parse a prefab from a RON string, migrating older format versions
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        Ok(ron::from_str(&migrate_to_current(source)?)?)
+    }
+
+    /// Save the prefab to a RON file
+    pub fn save(&self, path: &Path) -> Result<(), SceneError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Load a prefab from a RON file
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+use hecs::{Entity, Query, QueryBorrow, QueryItem};
+use uuid::Uuid;
+use crate::ecs::EntityHandle;
+
+/// A borrowed query over entities that have handles
+///
+/// Created by `EcsManager::query`. Components stay borrowed until it's dropped.
+pub struct EcsQuery<'w, Q: Query> {
+    borrow: QueryBorrow<'w, Q>,
+    handles: &'w HashMap<Entity, Uuid>,
+}
+
+impl<'w, Q: Query> EcsQuery<'w, Q> {
+    pub(crate) fn new(borrow: QueryBorrow<'w, Q>, handles: &'w HashMap<Entity, Uuid>) -> Self {
+        Self { borrow, handles }
+    }
+
+    /// Iterate the matching entities with their handles
+    pub fn iter(&mut self) -> impl Iterator<Item = (EntityHandle, QueryItem<'_, Q>)> + '_ {
+        let handles = self.handles;
+        self.borrow.iter().filter_map(move |(entity, item)| {
+            handles.get(&entity).map(|uuid| (EntityHandle::new(*uuid), item))
+        })
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use glam::Vec2;
+use crate::assets::{Handle, MaterialAsset};
+use crate::core::Event;
+use crate::ecs::EntityHandle;
+use crate::rendering::{BlendMode, SortingLayers, SpriteInstance, SpriteUv, TextureAtlas, TextureHandle, DEFAULT_SORTING_LAYER};
+
+/// Component that draws a textured quad at the entity's 2D transform
+#[derive(Debug, Clone)]
+pub struct SpriteComponent {
+    /// Texture in the sprite renderer
+    pub texture: TextureHandle,
+    /// Width and height before the entity's scale
+    pub size: Vec2,
+    /// Part of the texture to draw, set by `SpriteAnimationComponent` while animating
+    pub uv: SpriteUv,
+    /// Color multiplied with the texture
+    pub tint: [f32; 4],
+    /// Name of the layer the sprite is sorted into, see `SortingLayers`
+    pub sorting_layer: String,
+    /// Draw order within the sorting layer, higher on top
+    pub order_in_layer: i32,
+    pub visible: bool,
+    /// Material file whose tint, texture and blend mode the sprite is drawn with
+    pub material_path: Option<PathBuf>,
+    /// The loaded material file, assigned by `material_asset_system`
+    pub material: Option<Handle<MaterialAsset>>,
+    /// Version of `material` whose texture the sprite last took
+    pub(crate) applied_material: Option<Arc<MaterialAsset>>,
+}
+
+impl SpriteComponent {
+    /// Create a sprite showing the whole texture
+    pub fn new(texture: TextureHandle, size: Vec2) -> Self {
+        Self {
+            texture,
+            size,
+            uv: SpriteUv::FULL,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            sorting_layer: DEFAULT_SORTING_LAYER.to_string(),
+            order_in_layer: 0,
+            visible: true,
+            material_path: None,
+            material: None,
+            applied_material: None,
+        }
+    }
+
+    /// Draw the sprite with a material file, see `material_asset_system`
+    pub fn with_material(mut self, path: impl Into<PathBuf>) -> Self {
+        self.set_material_path(Some(path.into()));
+        self
+    }
+
+    /// Switch to another material file, loaded by the next `material_asset_system` run
+    pub fn set_material_path(&mut self, path: Option<PathBuf>) {
+        self.material_path = path;
+        self.material = None;
+    }
+
+    /// Sort the sprite into a named layer, at an order within it
+    pub fn with_sorting(mut self, sorting_layer: impl Into<String>, order_in_layer: i32) -> Self {
+        self.sorting_layer = sorting_layer.into();
+        self.order_in_layer = order_in_layer;
+        self
+    }
+
+    /// The sprite at a position, rotation and scale, sorted with `layers`
+    ///
+    /// A material's tint is multiplied with the sprite's own and its blend mode is used.
+    pub fn instance(&self, position: Vec2, rotation: f32, scale: Vec2, layers: &SortingLayers) -> SpriteInstance {
+        let material = self.material.as_ref().map(Handle::get);
+        let mut tint = self.tint;
+        if let Some(material) = &material {
+            for (channel, material_channel) in tint.iter_mut().zip(material.base_color) {
+                *channel *= material_channel;
+            }
+        }
+        SpriteInstance::new(position, self.size * scale, self.texture)
+            .with_uv(self.uv)
+            .with_rotation(rotation)
+            .with_tint(tint)
+            .with_blend_mode(material.map_or(BlendMode::AlphaBlend, |material| material.blend_mode))
+            .with_sorting(layers.resolve(&self.sorting_layer), self.order_in_layer)
+    }
+}
+
+/// A sequence of frames played at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    /// Texture coordinates of each frame, in playback order
+    pub frames: Vec<SpriteUv>,
+    pub frames_per_second: f32,
+    /// Start over after the last frame; otherwise the clip stops on it
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<SpriteUv>, frames_per_second: f32, looping: bool) -> Self {
+        Self { frames, frames_per_second, looping }
+    }
+
+    /// Frames from named atlas regions, frame `i` is `regions[i]`
+    pub fn from_regions(atlas: &TextureAtlas, regions: &[&str], frames_per_second: f32, looping: bool) -> Result<Self> {
+        let frames = regions.iter()
+            .map(|name| atlas.region(name).ok_or_else(|| anyhow!("Atlas has no region '{}'", name)))
+            .collect::<Result<_>>()?;
+        Ok(Self::new(frames, frames_per_second, looping))
+    }
+
+    /// Seconds each frame is shown, infinite for clips that don't advance
+    pub fn frame_duration(&self) -> f32 {
+        if self.frames_per_second > 0.0 { 1.0 / self.frames_per_second } else { f32::INFINITY }
+    }
+}
+
+/// A clip that doesn't loop reached its last frame
+#[derive(Debug, Clone)]
+pub struct AnimationFinishedEvent {
+    pub entity: EntityHandle,
+    /// Name of the clip that finished
+    pub clip: String,
+}
+
+impl Event for AnimationFinishedEvent {
+    fn name(&self) -> &'static str {
+        "AnimationFinishedEvent"
+    }
+}
+
+/// Component that flips through the frames of named clips, see `sprite_animation_system`
+#[derive(Debug, Clone, Default)]
+pub struct SpriteAnimationComponent {
+    clips: HashMap<String, AnimationClip>,
+    /// Clip being shown
+    current: Option<String>,
+    /// Frame of the current clip being shown
+    frame: usize,
+    /// Seconds the current frame has been shown
+    elapsed: f32,
+    playing: bool,
+}
+
+impl SpriteAnimationComponent {
+    /// Create a component without clips
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a clip, replacing any clip with the same name
+    pub fn with_clip(mut self, name: impl Into<String>, clip: AnimationClip) -> Self {
+        self.add_clip(name, clip);
+        self
+    }
+
+    /// Add a clip, replacing any clip with the same name
+    pub fn add_clip(&mut self, name: impl Into<String>, clip: AnimationClip) {
+        self.clips.insert(name.into(), clip);
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    /// Names of all clips
+    pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+        self.clips.keys().map(String::as_str)
+    }
+
+    /// Play a clip from its first frame, or resume it if it's the current clip
+    ///
+    /// A finished clip that doesn't loop starts over. Returns false if there's no such clip.
+    pub fn play(&mut self, name: &str) -> bool {
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+        if self.current.as_deref() != Some(name) || self.is_finished() {
+            self.current = Some(name.to_string());
+            self.frame = 0;
+            self.elapsed = 0.0;
+        }
+        self.playing = true;
+        true
+    }
+
+    /// Stop advancing, keeping the current frame
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Name of the clip being shown
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Index of the frame being shown
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Whether the current clip doesn't loop and has reached its last frame
+    pub fn is_finished(&self) -> bool {
+        self.current_clip_data().map_or(false, |clip| {
+            !clip.looping && self.frame + 1 >= clip.frames.len() && self.elapsed >= clip.frame_duration()
+        })
+    }
+
+    /// Texture coordinates of the frame being shown
+    pub fn current_uv(&self) -> Option<SpriteUv> {
+        self.current_clip_data()?.frames.get(self.frame).copied()
+    }
+
+    /// Advance by `delta_time` seconds
+    ///
+    /// Returns true if a clip that doesn't loop finished during this call; it then
+    /// stays on its last frame and stops playing.
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let Some(clip) = self.current.as_ref().and_then(|name| self.clips.get(name)) else {
+            return false;
+        };
+        if clip.frames.is_empty() {
+            return false;
+        }
+
+        let frame_duration = clip.frame_duration();
+        let frame_count = clip.frames.len();
+        let looping = clip.looping;
+
+        self.elapsed += delta_time;
+        while self.elapsed >= frame_duration {
+            if self.frame + 1 < frame_count {
+                self.frame += 1;
+            } else if looping {
+                self.frame = 0;
+            } else {
+                // Hold the last frame, long enough that `is_finished` is true
+                self.elapsed = frame_duration;
+                self.playing = false;
+                return true;
+            }
+            self.elapsed -= frame_duration;
+        }
+        false
+    }
+
+    fn current_clip_data(&self) -> Option<&AnimationClip> {
+        self.clips.get(self.current.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::EcsManager;
+
+    /// Three frames at 4 frames per second, so each frame lasts exactly 0.25 seconds
+    fn clip(looping: bool) -> AnimationClip {
+        let frames = (0..3)
+            .map(|i| SpriteUv { min: Vec2::new(i as f32 / 3.0, 0.0), max: Vec2::new((i + 1) as f32 / 3.0, 1.0) })
+            .collect();
+        AnimationClip::new(frames, 4.0, looping)
+    }
+
+    fn playing(looping: bool) -> SpriteAnimationComponent {
+        let mut animation = SpriteAnimationComponent::new().with_clip("walk", clip(looping));
+        assert!(animation.play("walk"));
+        animation
+    }
+
+    #[test]
+    fn frame_changes_on_the_boundary() {
+        let mut animation = playing(true);
+        animation.advance(0.125);
+        assert_eq!(animation.frame(), 0);
+        animation.advance(0.125);
+        assert_eq!(animation.frame(), 1);
+        assert_eq!(animation.current_uv(), Some(clip(true).frames[1]));
+    }
+
+    #[test]
+    fn long_step_skips_frames_and_loops() {
+        let mut animation = playing(true);
+        assert!(!animation.advance(0.5));
+        assert_eq!(animation.frame(), 2);
+        assert!(!animation.advance(0.25));
+        assert_eq!(animation.frame(), 0);
+        assert!(animation.is_playing());
+    }
+
+    #[test]
+    fn clip_that_does_not_loop_finishes_once_on_its_last_frame() {
+        let mut animation = playing(false);
+        assert!(!animation.advance(0.5));
+        assert_eq!(animation.frame(), 2);
+        assert!(!animation.is_finished());
+
+        assert!(animation.advance(0.25));
+        assert_eq!(animation.frame(), 2);
+        assert!(animation.is_finished());
+        assert!(!animation.is_playing());
+
+        assert!(!animation.advance(1.0));
+        assert_eq!(animation.frame(), 2);
+
+        // Playing a finished clip starts it over
+        animation.play("walk");
+        assert_eq!(animation.frame(), 0);
+    }
+
+    #[test]
+    fn run_systems_animates_sprites_and_publishes_finished_clips() {
+        let mut ecs = EcsManager::new();
+        let mut reader = ecs.events_mut().subscribe::<AnimationFinishedEvent>();
+        let handle = ecs.create_entity((
+            SpriteComponent::new(TextureHandle::WHITE, Vec2::ONE),
+            playing(false),
+        ));
+
+        ecs.run_systems(0.25);
+        let entity = ecs.get_entity(&handle).unwrap();
+        let uv = ecs.world().get::<&SpriteComponent>(entity).unwrap().uv;
+        assert_eq!(uv, clip(false).frames[1]);
+        assert_eq!(reader.read(ecs.events()).count(), 0);
+
+        ecs.run_systems(0.5);
+        let finished: Vec<_> = reader.read(ecs.events()).cloned().collect();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].entity, handle);
+        assert_eq!(finished[0].clip, "walk");
+    }
+}
@@ -1,34 +1,159 @@
-use hecs::World;
-use crate::ecs::{Transform2DComponent, Transform3DComponent, PhysicsComponent, RenderableComponent};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use glam::{EulerRot, Mat4, Vec2};
+use hecs::{Entity, World};
+use log::error;
+use uuid::Uuid;
+use crate::ecs::{
+    Transform2DComponent, Transform3DComponent, RenderableComponent, LuaScriptComponent,
+    ParentComponent, GlobalTransform, TilemapComponent, CameraComponent, TextComponent, TextSpace,
+    SpriteComponent, SpriteAnimationComponent, AnimationFinishedEvent, RenderableDescriptor, BoundsComponent,
+    ActiveComponent, InactiveInHierarchy, LightComponent, EntityHandle, DEFAULT_MATERIAL,
+};
+use crate::core::EventSystem;
+use crate::animation::Animator;
+use crate::assets::{AssetManager, Handle, MaterialAsset};
+use crate::rendering::{InstanceGroups, Light, LightManager, Material, Mesh, Model, ModelPrimitive, PrimitiveShape, RenderView, Renderer, TextPlacement};
+use crate::scripting::LuaEngine;
+use crate::utils::math::Aabb;
 use wgpu::Queue;
 
-/// System that updates transforms based on physics
-pub fn transform_system(world: &mut World, delta_time: f32) {
-    // Update 2D transforms based on physics
-    for (_id, (transform, physics)) in world.query_mut::<(&mut Transform2DComponent, &PhysicsComponent)>() {
-        // Update position based on velocity
-        transform.position += physics.velocity * delta_time;
+/// System that marks the entities switched off by their own or a parent's `ActiveComponent`
+///
+/// Inactive entities get an `InactiveInHierarchy` marker, which the other systems
+/// check to leave them out; re-activated entities lose it. Run it first, so changes
+/// to the flags apply the same frame. A parent chain that loops back on itself is
+/// cut where it repeats.
+pub fn active_system(world: &mut World, entity_map: &HashMap<Uuid, Entity>) {
+    let switched_off: HashSet<Entity> = world.query::<&ActiveComponent>().iter()
+        .filter(|(_, active)| !active.active)
+        .map(|(entity, _)| entity)
+        .collect();
+    let parents: HashMap<Entity, Entity> = world.query::<&ParentComponent>().iter()
+        .filter_map(|(entity, parent)| entity_map.get(&parent.parent.uuid).map(|parent| (entity, *parent)))
+        .collect();
+    
+    let inactive: HashSet<Entity> = entity_map.values().copied()
+        .filter(|&entity| {
+            let mut visited = HashSet::from([entity]);
+            let mut current = entity;
+            loop {
+                if switched_off.contains(&current) {
+                    return true;
+                }
+                match parents.get(&current) {
+                    Some(&parent) if visited.insert(parent) => current = parent,
+                    _ => return false,
+                }
+            }
+        })
+        .collect();
+    
+    let marked: Vec<Entity> = world.query::<&InactiveInHierarchy>().iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in marked {
+        if !inactive.contains(&entity) {
+            let _ = world.remove_one::<InactiveInHierarchy>(entity);
+        }
+    }
+    for entity in inactive {
+        if world.get::<&InactiveInHierarchy>(entity).is_err() {
+            let _ = world.insert_one(entity, InactiveInHierarchy);
+        }
+    }
+}
+
+/// System that updates the `GlobalTransform` of every entity with a transform
+///
+/// World matrices are computed by walking the parent chain, each entity's matrix
+/// is computed once per run. Parents without a transform pass their parent's
+/// matrix through. An entity whose parent chain loops back on itself is treated
+/// as a root. Inactive entities keep their last `GlobalTransform`.
+pub fn transform_system(world: &mut World, entity_map: &HashMap<Uuid, Entity>, _delta_time: f32) {
+    // 2D transforms are integrated by the physics system
+    
+    let mut parents: HashMap<Entity, Entity> = HashMap::new();
+    for (entity, parent) in world.query::<&ParentComponent>().iter() {
+        if let Some(parent) = entity_map.get(&parent.parent.uuid) {
+            parents.insert(entity, *parent);
+        }
+    }
+    
+    let transformed: Vec<Entity> = world.query::<(Option<&Transform3DComponent>, Option<&Transform2DComponent>)>()
+        .without::<&InactiveInHierarchy>()
+        .iter()
+        .filter(|(_, (transform_3d, transform_2d))| transform_3d.is_some() || transform_2d.is_some())
+        .map(|(entity, _)| entity)
+        .collect();
+    
+    let mut matrices: HashMap<Entity, Mat4> = HashMap::new();
+    for &entity in &transformed {
+        let mut chain = vec![entity];
+        let mut in_chain: HashSet<Entity> = HashSet::from([entity]);
+        
+        // Walk up until a root or an entity computed earlier this run
+        let mut parent_matrix = Mat4::IDENTITY;
+        while let Some(&parent) = parents.get(chain.last().unwrap()) {
+            if let Some(matrix) = matrices.get(&parent) {
+                parent_matrix = *matrix;
+                break;
+            }
+            if !in_chain.insert(parent) {
+                error!("Parent cycle detected at entity {:?}, treating it as a root", chain.last().unwrap());
+                parents.remove(chain.last().unwrap());
+                break;
+            }
+            chain.push(parent);
+        }
         
-        // Update rotation based on angular velocity
-        transform.rotation += physics.angular_velocity * delta_time;
+        for &entity in chain.iter().rev() {
+            parent_matrix *= local_matrix(world, entity);
+            matrices.insert(entity, parent_matrix);
+        }
     }
     
-    // Update 3D transforms (if needed)
-    // This is a placeholder for future 3D physics
+    for entity in transformed {
+        let matrix = matrices[&entity];
+        let updated = match world.get::<&mut GlobalTransform>(entity) {
+            Ok(mut global) => {
+                global.matrix = matrix;
+                true
+            }
+            Err(_) => false,
+        };
+        if !updated {
+            let _ = world.insert_one(entity, GlobalTransform::new(matrix));
+        }
+    }
 }
 
-/// System that handles physics calculations
-pub fn physics_system(world: &mut World, delta_time: f32) {
-    // Apply gravity to physics components
-    let gravity = glam::Vec2::new(0.0, -9.81);
+/// Local matrix of an entity, identity for entities without a transform
+///
+/// The 3D transform is used when an entity has both.
+pub fn local_matrix(world: &World, entity: Entity) -> Mat4 {
+    if let Ok(transform) = world.get::<&Transform3DComponent>(entity) {
+        return transform.matrix();
+    }
+    if let Ok(transform) = world.get::<&Transform2DComponent>(entity) {
+        return transform.matrix();
+    }
+    Mat4::IDENTITY
+}
+
+/// System that runs the enabled Lua scripts attached to active entities
+pub fn script_system(world: &mut World, engine: &mut LuaEngine, delta_time: f32) {
+    engine.reload_changed();
     
-    for (_id, physics) in world.query_mut::<&mut PhysicsComponent>() {
-        if physics.use_gravity {
-            physics.velocity += gravity * delta_time;
+    let query = world.query_mut::<(&mut LuaScriptComponent, Option<&mut Transform3DComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (entity, (script, transform)) in query {
+        if !script.enabled {
+            continue;
         }
-        
-        // Add collision detection and resolution here
-        // ...
+        let handle = *script.handle.get_or_insert_with(|| engine.load(&script.path));
+        engine.run(handle, entity, transform, delta_time);
     }
 }
 
@@ -37,7 +162,7 @@ pub fn rendering_system(world: &mut World) {
     // Update model transforms from entity transforms
     
     // For 2D entities
-    for (_id, (transform, renderable)) in world.query_mut::<(&Transform2DComponent, &RenderableComponent)>() {
+    for (_id, (transform, renderable)) in world.query_mut::<(&Transform2DComponent, &RenderableComponent)>().without::<&InactiveInHierarchy>() {
         if renderable.visible {
             // Convert 2D transform to 3D transform for the model
             let position = glam::Vec3::new(transform.position.x, transform.position.y, 0.0);
@@ -53,13 +178,13 @@ pub fn rendering_system(world: &mut World) {
             
             // Update the model's transform if we have access to a queue
             if let Some(queue) = get_render_queue() {
-                renderable.model.update_transform(queue, &transform_3d);
+                renderable.model.get().update_transform(queue, &transform_3d);
             }
         }
     }
     
     // For 3D entities
-    for (_id, (transform, renderable)) in world.query_mut::<(&Transform3DComponent, &RenderableComponent)>() {
+    for (_id, (transform, renderable)) in world.query_mut::<(&Transform3DComponent, &RenderableComponent)>().without::<&InactiveInHierarchy>() {
         if renderable.visible {
             // Create a transform for the model
             let transform_3d = crate::rendering::Transform {
@@ -70,15 +195,529 @@ pub fn rendering_system(world: &mut World) {
             
             // Update the model's transform if we have access to a queue
             if let Some(queue) = get_render_queue() {
-                renderable.model.update_transform(queue, &transform_3d);
+                renderable.model.get().update_transform(queue, &transform_3d);
+            }
+        }
+    }
+}
+
+/// System that updates the `BoundsComponent` of every entity with a renderable
+///
+/// Run it after `transform_system`, bounds follow the `GlobalTransform`. Entities
+/// without one are bounded in model space. Bounds of entities that lost their
+/// renderable are removed. Inactive entities keep their last bounds.
+pub fn bounds_system(world: &mut World) {
+    let bounds: Vec<(Entity, Aabb)> = world.query::<(&RenderableComponent, Option<&GlobalTransform>)>()
+        .without::<&InactiveInHierarchy>()
+        .iter()
+        .map(|(entity, (renderable, global))| {
+            let matrix = global.map_or(Mat4::IDENTITY, |global| global.matrix);
+            (entity, renderable.model.get().bounds().transformed(matrix))
+        })
+        .collect();
+    
+    for (entity, aabb) in bounds {
+        let updated = match world.get::<&mut BoundsComponent>(entity) {
+            Ok(mut component) => {
+                component.aabb = aabb;
+                true
+            }
+            Err(_) => false,
+        };
+        if !updated {
+            let _ = world.insert_one(entity, BoundsComponent::new(aabb));
+        }
+    }
+    
+    let stale: Vec<Entity> = world.query::<&BoundsComponent>().without::<&RenderableComponent>().iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in stale {
+        let _ = world.remove_one::<BoundsComponent>(entity);
+    }
+}
+
+/// System that queues the visible renderables of active entities for the next frame
+///
+/// Renderables with a `GlobalTransform` are grouped by mesh and material, and each
+/// group is drawn with a single instanced draw call. The others are drawn one by one
+/// with their model's own transform. Renderables with a `BoundsComponent` are culled
+/// against each camera's frustum, an instanced group as a whole.
+pub fn render_queue_system(world: &World, renderer: &mut Renderer) {
+    let mut groups = InstanceGroups::new();
+    let mut query = world.query::<(&RenderableComponent, Option<&GlobalTransform>, Option<&BoundsComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (_id, (renderable, global, bounds)) in query.iter() {
+        if !renderable.visible {
+            continue;
+        }
+        let model = renderable.model.get();
+        match (global, bounds) {
+            (Some(global), _) => {
+                for (mesh, material) in model.meshes() {
+                    groups.push(mesh, material, renderable.casts_shadows, global.matrix, bounds.map(|bounds| bounds.aabb));
+                }
+            }
+            (None, Some(bounds)) => renderer.draw_model_with_bounds(model, renderable.casts_shadows, bounds.aabb),
+            (None, None) => renderer.draw_model(model, renderable.casts_shadows),
+        }
+    }
+    for draw in groups.into_draws() {
+        renderer.draw_instance_batch(draw);
+    }
+}
+
+/// System that creates the renderables of entities described as built-in primitives
+///
+/// Scenes and the editor only store a `RenderableDescriptor`, as GPU resources need
+/// the renderer. Each descriptor naming a `PrimitiveShape` is replaced by a
+/// `RenderableComponent` with the generated mesh; entities created in the same run
+/// share meshes and materials. A material file is drawn with the default material
+/// until `material_asset_system` loads it. Descriptors of other meshes are left alone.
+pub fn primitive_mesh_system(world: &mut World, renderer: &Renderer) {
+    let pending: Vec<(Entity, RenderableDescriptor)> = world
+        .query::<(&RenderableDescriptor, Option<&RenderableComponent>)>()
+        .iter()
+        .filter(|(_, (descriptor, renderable))| renderable.is_none() && descriptor.primitive_shape().is_some())
+        .map(|(entity, (descriptor, _))| (entity, descriptor.clone()))
+        .collect();
+    
+    let mut meshes: HashMap<PrimitiveShape, Arc<Mesh>> = HashMap::new();
+    let mut materials: HashMap<String, Arc<Material>> = HashMap::new();
+    for (entity, descriptor) in pending {
+        let Some(shape) = descriptor.primitive_shape() else { continue };
+        
+        let mesh = match meshes.get(&shape) {
+            Some(mesh) => mesh.clone(),
+            None => match Mesh::from_data(renderer.device(), &shape.mesh_data()) {
+                Ok(mesh) => meshes.entry(shape).or_insert(Arc::new(mesh)).clone(),
+                Err(e) => {
+                    error!("Failed to create {} mesh: {}", shape.name(), e);
+                    continue;
+                }
+            },
+        };
+        let material_file = descriptor.material_file().map(PathBuf::from);
+        let material_name = if material_file.is_some() { DEFAULT_MATERIAL } else { descriptor.material.as_str() };
+        let material = materials.entry(material_name.to_string())
+            .or_insert_with(|| Arc::new(renderer.create_material(material_name, None)))
+            .clone();
+        
+        let mut renderable = RenderableComponent::from_parts(renderer.device(), mesh, material);
+        renderable.visible = descriptor.visible;
+        renderable.casts_shadows = descriptor.casts_shadows;
+        renderable.set_material_path(material_file);
+        let _ = world.remove_one::<RenderableDescriptor>(entity);
+        let _ = world.insert_one(entity, renderable);
+    }
+}
+
+/// System that loads the material files of renderables and sprites and applies them
+///
+/// Files are loaded through `assets`, so entities using the same file share one
+/// material asset. Whenever the asset behind a handle is replaced, e.g. by editing it
+/// in the inspector, renderables get their model rebuilt with a new GPU material and
+/// sprites take its texture; its tint and blend mode are read while drawing. A file
+/// that can't be read is logged and replaced by the default material, and a missing
+/// texture is drawn as a checkerboard. Clearing a material path keeps the last one applied.
+pub fn material_asset_system(world: &mut World, renderer: &mut Renderer, assets: &mut AssetManager) {
+    // GPU materials built this run, by the asset version they were built from
+    let mut built: HashMap<*const MaterialAsset, Arc<Material>> = HashMap::new();
+    for (_id, renderable) in world.query_mut::<&mut RenderableComponent>() {
+        let Some(handle) = material_handle(renderable.material_path.as_ref(), &mut renderable.material, assets) else { continue };
+        let asset = handle.get();
+        if renderable.applied_material.as_ref().map_or(false, |applied| Arc::ptr_eq(applied, &asset)) {
+            continue;
+        }
+        
+        let material = built.entry(Arc::as_ptr(&asset))
+            .or_insert_with(|| {
+                let name = handle.path()
+                    .and_then(|path| path.file_stem())
+                    .map_or_else(|| DEFAULT_MATERIAL.to_string(), |stem| stem.to_string_lossy().into_owned());
+                Arc::new(renderer.create_material_from_asset(&name, &asset, handle.path()))
+            })
+            .clone();
+        let model = renderable.model.get();
+        let mut rebuilt = Model::new_with_device(renderer.device(), model.mesh.clone(), material.clone(), model.transform.clone());
+        rebuilt.primitives = model.primitives.iter()
+            .map(|primitive| ModelPrimitive { mesh: primitive.mesh.clone(), material: material.clone() })
+            .collect();
+        renderable.model = Handle::new(rebuilt);
+        renderable.applied_material = Some(asset);
+    }
+    
+    for (_id, sprite) in world.query_mut::<&mut SpriteComponent>() {
+        let Some(handle) = material_handle(sprite.material_path.as_ref(), &mut sprite.material, assets) else { continue };
+        let asset = handle.get();
+        if sprite.applied_material.as_ref().map_or(false, |applied| Arc::ptr_eq(applied, &asset)) {
+            continue;
+        }
+        if let Some(texture) = renderer.material_sprite_texture(&asset, handle.path()) {
+            sprite.texture = texture;
+        }
+        sprite.applied_material = Some(asset);
+    }
+}
+
+/// The handle of a component's material file, loading the file if it has no handle yet
+fn material_handle(path: Option<&PathBuf>, handle: &mut Option<Handle<MaterialAsset>>, assets: &mut AssetManager) -> Option<Handle<MaterialAsset>> {
+    let path = path?;
+    if let Some(handle) = handle {
+        return Some(handle.clone());
+    }
+    let loaded = assets.load::<MaterialAsset>(path).unwrap_or_else(|e| {
+        error!("Failed to load material {}: {}", path.display(), e);
+        Handle::new(MaterialAsset::default())
+    });
+    Some(handle.insert(loaded).clone())
+}
+
+/// System that queues the tiles of every tilemap that lie within the camera's view
+///
+/// `view_min` and `view_max` are the world space corners of the view, e.g. from
+/// `OrthographicCamera::visible_bounds`. Only visible tiles become sprites, so large
+/// maps cost no more than small ones. A tilemap's layer is its tiles' order in the
+/// Default sorting layer.
+pub fn tilemap_render_system(world: &World, renderer: &mut Renderer, view_min: Vec2, view_max: Vec2) {
+    let mut query = world.query::<(&TilemapComponent, Option<&Transform2DComponent>)>().without::<&InactiveInHierarchy>();
+    let mut tilemaps: Vec<_> = query.iter().map(|(_, components)| components).collect();
+    tilemaps.sort_by_key(|(tilemap, _)| tilemap.layer);
+    
+    for (tilemap, transform) in tilemaps {
+        let (origin, scale) = transform.map_or((Vec2::ZERO, Vec2::ONE), |t| (t.position, t.scale));
+        // Tilemap layers order the tiles within the Default sorting layer
+        for mut sprite in tilemap.visible_sprites(origin, scale, view_min, view_max) {
+            sprite.order_in_layer = tilemap.layer;
+            renderer.draw_sprite(sprite);
+        }
+    }
+}
+
+/// System that advances sprite animations and shows their current frame
+///
+/// The frame's texture coordinates are written to the entity's `SpriteComponent`.
+/// Clips that don't loop publish an `AnimationFinishedEvent` when they reach their
+/// last frame, entities without a handle in `handles` finish silently.
+pub fn sprite_animation_system(world: &mut World, handles: &HashMap<Entity, Uuid>, delta_time: f32, event_system: &mut EventSystem) {
+    let query = world.query_mut::<(&mut SpriteAnimationComponent, Option<&mut SpriteComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (entity, (animation, sprite)) in query {
+        if animation.advance(delta_time) {
+            if let Some(uuid) = handles.get(&entity) {
+                event_system.publish(AnimationFinishedEvent {
+                    entity: EntityHandle::new(*uuid),
+                    clip: animation.current_clip().unwrap_or_default().to_string(),
+                });
+            }
+        }
+        
+        if let (Some(sprite), Some(uv)) = (sprite, animation.current_uv()) {
+            sprite.uv = uv;
+        }
+    }
+}
+
+/// System that advances transform animations and writes the sampled channels to the entity's transform
+///
+/// 3D transforms take the channels as they are. 2D transforms take the X and Y of
+/// position and scale, and the rotation around Z. Run it before `transform_system`
+/// so world matrices follow the animation in the same frame. Clips that don't loop
+/// publish an `AnimationFinishedEvent` when they reach their end, as in
+/// `sprite_animation_system`.
+pub fn transform_animation_system(world: &mut World, handles: &HashMap<Entity, Uuid>, delta_time: f32, event_system: &mut EventSystem) {
+    let query = world.query_mut::<(&mut Animator, Option<&mut Transform3DComponent>, Option<&mut Transform2DComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (entity, (animator, transform_3d, transform_2d)) in query {
+        if animator.advance(delta_time) {
+            if let Some(uuid) = handles.get(&entity) {
+                event_system.publish(AnimationFinishedEvent {
+                    entity: EntityHandle::new(*uuid),
+                    clip: animator.current_clip().unwrap_or_default().to_string(),
+                });
+            }
+        }
+        let Some(sample) = animator.sample() else {
+            continue;
+        };
+        
+        if let Some(transform) = transform_3d {
+            if let Some(position) = sample.position {
+                transform.position = position;
+            }
+            if let Some(rotation) = sample.rotation {
+                transform.rotation = rotation;
+            }
+            if let Some(scale) = sample.scale {
+                transform.scale = scale;
+            }
+        } else if let Some(transform) = transform_2d {
+            if let Some(position) = sample.position {
+                transform.position = position.truncate();
+            }
+            if let Some(rotation) = sample.rotation {
+                transform.rotation = rotation.to_euler(EulerRot::ZYX).0;
+            }
+            if let Some(scale) = sample.scale {
+                transform.scale = scale.truncate();
             }
         }
     }
 }
 
+/// System that queues the visible sprites of active entities at their entity's transform
+///
+/// Sprites lie in the XY plane and follow their entity's scale and rotation around
+/// Z; the 2D transform wins if there are both. A 3D transform's Z only orders
+/// sprites of the same layer and order in layer.
+pub fn sprite_render_system(world: &World, renderer: &mut Renderer) {
+    let mut query = world.query::<(&SpriteComponent, Option<&Transform2DComponent>, Option<&Transform3DComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (_id, (sprite, transform_2d, transform_3d)) in query.iter() {
+        if !sprite.visible {
+            continue;
+        }
+        let layers = renderer.sorting_layers();
+        let instance = match (transform_2d, transform_3d) {
+            (Some(t), _) => sprite.instance(t.position, t.rotation, t.scale, layers),
+            (None, Some(t)) => sprite.instance(
+                t.position.truncate(),
+                t.rotation.to_euler(EulerRot::XYZ).2,
+                t.scale.truncate(),
+                layers,
+            ).with_depth(t.position.z),
+            (None, None) => sprite.instance(Vec2::ZERO, 0.0, Vec2::ONE, layers),
+        };
+        renderer.draw_sprite(instance);
+    }
+}
+
+/// System that queues the text of every enabled text component on an active entity
+///
+/// Text is anchored at the entity's position in the XY plane and follows its scale
+/// and rotation around Z; the 2D transform wins if there are both. Screen-space text
+/// takes the position as pixels from the top left of the window.
+pub fn text_render_system(world: &World, renderer: &mut Renderer) {
+    let mut query = world.query::<(&TextComponent, Option<&Transform2DComponent>, Option<&Transform3DComponent>)>()
+        .without::<&InactiveInHierarchy>();
+    for (_id, (text, transform_2d, transform_3d)) in query.iter() {
+        if !text.enabled || text.text.is_empty() {
+            continue;
+        }
+        
+        let placement = match (transform_2d, transform_3d) {
+            (Some(t), _) => TextPlacement::new(t.position).with_scale(t.scale).with_rotation(t.rotation),
+            (None, Some(t)) => TextPlacement::new(t.position.truncate())
+                .with_scale(t.scale.truncate())
+                .with_rotation(t.rotation.to_euler(EulerRot::XYZ).2),
+            (None, None) => TextPlacement::new(Vec2::ZERO),
+        };
+        match text.space {
+            TextSpace::World => renderer.draw_text(&text.text, &text.style(), placement),
+            TextSpace::Screen => renderer.draw_screen_text(&text.text, &text.style(), placement),
+        }
+    }
+}
+
+/// System that lights the following frames with the enabled lights of active entities
+///
+/// The lights come from `light_manager` and replace the lights of the last
+/// `Renderer::set_light_manager`.
+pub fn light_system(world: &World, renderer: &mut Renderer) {
+    renderer.set_light_manager(&light_manager(world));
+}
+
+/// Light manager holding the enabled lights of active entities, empty for a world without lights
+///
+/// Lights are placed by their entity's `GlobalTransform`, or its own transform if
+/// `transform_system` hasn't run, so a freshly loaded scene can be lit right away.
+pub fn light_manager(world: &World) -> LightManager {
+    let mut query = world.query::<(&LightComponent, Option<&GlobalTransform>)>().without::<&InactiveInHierarchy>();
+    let lights: Vec<Box<dyn Light>> = query.iter()
+        .filter(|(_, (light, _))| light.enabled)
+        .map(|(entity, (light, global))| {
+            let matrix = global.map_or_else(|| local_matrix(world, entity), |global| global.matrix);
+            light.light(matrix)
+        })
+        .collect();
+    
+    let mut manager = LightManager::new(lights.len());
+    for light in lights {
+        manager.add_light(light);
+    }
+    manager
+}
+
+/// Views of the enabled cameras on active entities for a target of `size` pixels, for `Renderer::render_views`
+///
+/// Views are ordered by camera depth, lowest first, so higher cameras draw on top.
+/// Cameras are placed by their `GlobalTransform`, or their own transform if
+/// `transform_system` hasn't run.
+pub fn camera_views(world: &World, size: (u32, u32)) -> Vec<RenderView> {
+    let mut cameras: Vec<(i32, RenderView)> = world.query::<(&CameraComponent, Option<&GlobalTransform>)>()
+        .without::<&InactiveInHierarchy>()
+        .iter()
+        .filter(|(_, (camera, _))| camera.is_active)
+        .filter_map(|(entity, (camera, global))| {
+            let matrix = global.map_or_else(|| local_matrix(world, entity), |global| global.matrix);
+            camera.render_view(matrix, size).map(|view| (camera.depth, view))
+        })
+        .collect();
+    
+    // Stable, so cameras at the same depth keep the query order
+    cameras.sort_by_key(|(depth, _)| *depth);
+    cameras.into_iter().map(|(_, view)| view).collect()
+}
+
+/// World space corners (min, max) of the XY plane area seen by any enabled camera, for `tilemap_render_system`
+///
+/// Cameras are placed as in `camera_views`. Returns `None` if no camera sees the plane.
+pub fn cameras_visible_rect(world: &World, size: (u32, u32)) -> Option<(Vec2, Vec2)> {
+    world.query::<(&CameraComponent, Option<&GlobalTransform>)>()
+        .without::<&InactiveInHierarchy>()
+        .iter()
+        .filter(|(_, (camera, _))| camera.is_active)
+        .filter_map(|(entity, (camera, global))| {
+            let matrix = global.map_or_else(|| local_matrix(world, entity), |global| global.matrix);
+            camera.visible_rect(matrix, size)
+        })
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))
+}
+
 /// Get the render queue for updating models
 /// This is a placeholder - in a real implementation, you would
 /// have a way to access the render queue from the ECS
 fn get_render_queue() -> Option<&'static Queue> {
     None
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+    use glam::{Quat, Vec3};
+
+    /// A world whose entities all have handles, as `EcsManager` keeps them
+    #[derive(Default)]
+    struct Scene {
+        world: World,
+        entity_map: HashMap<Uuid, Entity>,
+    }
+
+    impl Scene {
+        fn spawn(&mut self, components: impl hecs::DynamicBundle) -> (Entity, EntityHandle) {
+            let entity = self.world.spawn(components);
+            let uuid = Uuid::new_v4();
+            self.entity_map.insert(uuid, entity);
+            (entity, EntityHandle::new(uuid))
+        }
+
+        fn set_parent(&mut self, entity: Entity, parent: EntityHandle) {
+            self.world.insert_one(entity, ParentComponent::new(parent)).unwrap();
+        }
+
+        fn global(&self, entity: Entity) -> Mat4 {
+            self.world.get::<&GlobalTransform>(entity).unwrap().matrix
+        }
+
+        fn is_inactive(&self, entity: Entity) -> bool {
+            self.world.get::<&InactiveInHierarchy>(entity).is_ok()
+        }
+    }
+
+    fn transform(position: Vec3, rotation: Quat, scale: f32) -> Transform3DComponent {
+        Transform3DComponent::new(position, rotation, Vec3::splat(scale))
+    }
+
+    #[test]
+    fn transforms_combine_down_three_levels() {
+        let mut scene = Scene::default();
+        // Spawned leaf first, so children come before their parents in the query
+        let (grandchild, _) = scene.spawn((transform(Vec3::X, Quat::IDENTITY, 1.0),));
+        let (child, child_handle) = scene.spawn((transform(Vec3::Y, Quat::from_rotation_z(FRAC_PI_2), 1.0),));
+        let (root, root_handle) = scene.spawn((transform(Vec3::X, Quat::IDENTITY, 2.0),));
+        scene.set_parent(grandchild, child_handle);
+        scene.set_parent(child, root_handle);
+
+        transform_system(&mut scene.world, &scene.entity_map, 0.0);
+
+        let expected = local_matrix(&scene.world, root) * local_matrix(&scene.world, child) * local_matrix(&scene.world, grandchild);
+        assert!(scene.global(grandchild).abs_diff_eq(expected, 1e-5));
+        assert!(scene.global(grandchild).w_axis.truncate().abs_diff_eq(Vec3::new(1.0, 4.0, 0.0), 1e-5));
+        assert!(scene.global(root).abs_diff_eq(local_matrix(&scene.world, root), 1e-6));
+    }
+
+    #[test]
+    fn parent_without_a_transform_counts_as_identity() {
+        let mut scene = Scene::default();
+        let (group, group_handle) = scene.spawn((crate::ecs::NameComponent::new("Group"),));
+        let (child, _) = scene.spawn((transform(Vec3::Z, Quat::IDENTITY, 1.0),));
+        scene.set_parent(child, group_handle);
+
+        transform_system(&mut scene.world, &scene.entity_map, 0.0);
+
+        assert!(scene.global(child).abs_diff_eq(Mat4::from_translation(Vec3::Z), 1e-6));
+        assert!(scene.world.get::<&GlobalTransform>(group).is_err());
+    }
+
+    #[test]
+    fn parent_cycle_is_treated_as_a_root() {
+        let mut scene = Scene::default();
+        let (a, a_handle) = scene.spawn((transform(Vec3::X, Quat::IDENTITY, 1.0),));
+        let (b, b_handle) = scene.spawn((transform(Vec3::Y, Quat::IDENTITY, 1.0),));
+        let (c, _) = scene.spawn((transform(Vec3::Z, Quat::IDENTITY, 1.0),));
+        scene.set_parent(a, b_handle);
+        scene.set_parent(b, a_handle);
+        scene.set_parent(c, a_handle);
+
+        transform_system(&mut scene.world, &scene.entity_map, 0.0);
+
+        // Whichever of the two was cut loose sits at its own position, the other below it
+        let (a_world, b_world) = (scene.global(a).w_axis.truncate(), scene.global(b).w_axis.truncate());
+        let a_is_root = a_world.abs_diff_eq(Vec3::X, 1e-6) && b_world.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-6);
+        let b_is_root = b_world.abs_diff_eq(Vec3::Y, 1e-6) && a_world.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-6);
+        assert!(a_is_root != b_is_root, "a at {a_world}, b at {b_world}");
+        assert!(scene.global(c).w_axis.truncate().abs_diff_eq(a_world + Vec3::Z, 1e-6));
+    }
+
+    #[test]
+    fn disabled_entity_and_its_descendants_are_skipped() {
+        let mut scene = Scene::default();
+        let (root, root_handle) = scene.spawn((ActiveComponent::new(true),));
+        let (disabled, disabled_handle) = scene.spawn((ActiveComponent::new(false),));
+        let (grandchild, _) = scene.spawn((transform(Vec3::X, Quat::IDENTITY, 1.0),));
+        let (sibling, _) = scene.spawn((transform(Vec3::Y, Quat::IDENTITY, 1.0),));
+        scene.set_parent(disabled, root_handle);
+        scene.set_parent(grandchild, disabled_handle);
+        scene.set_parent(sibling, root_handle);
+
+        active_system(&mut scene.world, &scene.entity_map);
+        assert!(scene.is_inactive(disabled));
+        assert!(scene.is_inactive(grandchild));
+        assert!(!scene.is_inactive(root));
+        assert!(!scene.is_inactive(sibling));
+
+        // Systems leave the marked entities out
+        let active: Vec<Entity> = scene.world.query::<&Transform3DComponent>()
+            .without::<&InactiveInHierarchy>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(active, vec![sibling]);
+    }
+
+    #[test]
+    fn reenabled_entity_loses_the_marker() {
+        let mut scene = Scene::default();
+        let (parent, parent_handle) = scene.spawn((ActiveComponent::new(false),));
+        let (child, _) = scene.spawn((transform(Vec3::X, Quat::IDENTITY, 1.0),));
+        scene.set_parent(child, parent_handle);
+        active_system(&mut scene.world, &scene.entity_map);
+        assert!(scene.is_inactive(child));
+
+        scene.world.get::<&mut ActiveComponent>(parent).unwrap().active = true;
+        active_system(&mut scene.world, &scene.entity_map);
+        assert!(!scene.is_inactive(parent));
+        assert!(!scene.is_inactive(child));
+    }
+}
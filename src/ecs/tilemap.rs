@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use glam::Vec2;
+use serde::{Serialize, Deserialize};
+use crate::rendering::{SpriteInstance, SpriteUv, TextureAtlas, TextureHandle};
+use crate::scene::SceneError;
+
+/// Tile index of cells without a tile
+pub const EMPTY_TILE: u32 = u32::MAX;
+
+/// Tile images of a tilemap, registered with the sprite renderer
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    /// The atlas texture in the sprite renderer
+    pub texture: TextureHandle,
+    /// Texture coordinates of each tile, indexed by tile index
+    pub tiles: Vec<SpriteUv>,
+}
+
+impl Tileset {
+    /// Tiles from named atlas regions, tile `i` is `regions[i]`
+    pub fn from_regions(atlas: &TextureAtlas, texture: TextureHandle, regions: &[&str]) -> Result<Self> {
+        let tiles = regions.iter()
+            .map(|name| atlas.region(name).ok_or_else(|| anyhow!("Atlas has no region '{}'", name)))
+            .collect::<Result<_>>()?;
+        Ok(Self { texture, tiles })
+    }
+
+    /// Tiles from a grid of `tile_width` x `tile_height` pixel cells, row by row from the top left
+    pub fn from_grid(atlas: &TextureAtlas, texture: TextureHandle, tile_width: u32, tile_height: u32) -> Self {
+        let (width, height) = atlas.texture.size;
+        let columns = width / tile_width.max(1);
+        let rows = height / tile_height.max(1);
+        let cell = Vec2::new(tile_width as f32 / width as f32, tile_height as f32 / height as f32);
+
+        let tiles = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let min = Vec2::new(column as f32, row as f32) * cell;
+                SpriteUv { min, max: min + cell }
+            })
+            .collect();
+        Self { texture, tiles }
+    }
+}
+
+/// On-disk format read by `TilemapComponent::load`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TilemapFile {
+    /// Size of one tile in world units
+    tile_size: (f32, f32),
+    #[serde(default)]
+    layer: i32,
+    /// Atlas image the tiles come from
+    #[serde(default)]
+    atlas: Option<PathBuf>,
+    /// Tile indices from the top row down, -1 for empty cells
+    rows: Vec<Vec<i64>>,
+}
+
+/// Component that draws a grid of tiles from a tileset
+///
+/// Tile (0, 0) is the bottom left cell, with its bottom left corner at the entity's
+/// position. Tilemaps are axis aligned: the entity's scale resizes the tiles, its
+/// rotation is ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TilemapComponent {
+    /// Number of columns
+    width: u32,
+    /// Number of rows
+    height: u32,
+    /// Tile indices, row by row from the bottom
+    tiles: Vec<u32>,
+    /// Size of one tile in world units
+    pub tile_size: Vec2,
+    /// Tilemaps with a lower layer are drawn first
+    pub layer: i32,
+    /// Atlas image the tiles come from, for loading the tileset
+    pub atlas: Option<PathBuf>,
+    /// The tile images, assigned once the atlas is loaded; nothing is drawn without it
+    #[serde(skip)]
+    pub tileset: Option<Arc<Tileset>>,
+}
+
+impl TilemapComponent {
+    /// Create an empty tilemap
+    pub fn new(width: u32, height: u32, tile_size: Vec2) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![EMPTY_TILE; width as usize * height as usize],
+            tile_size,
+            layer: 0,
+            atlas: None,
+            tileset: None,
+        }
+    }
+
+    /// Load a tilemap from a RON file
+    ///
+    /// Rows are listed from the top down, so the file looks like the map. Shorter rows
+    /// are padded with empty cells.
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path)?;
+        let file: TilemapFile = ron::from_str(&source)?;
+
+        let height = file.rows.len() as u32;
+        let width = file.rows.iter().map(Vec::len).max().unwrap_or(0) as u32;
+        let mut tilemap = Self::new(width, height, Vec2::new(file.tile_size.0, file.tile_size.1));
+        tilemap.layer = file.layer;
+        tilemap.atlas = file.atlas;
+
+        for (row, tiles) in file.rows.iter().enumerate() {
+            let y = height - 1 - row as u32;
+            for (x, &tile) in tiles.iter().enumerate() {
+                let tile = u32::try_from(tile).unwrap_or(EMPTY_TILE);
+                tilemap.set_tile(x as u32, y, tile);
+            }
+        }
+
+        Ok(tilemap)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Tile index at a cell, `None` for empty cells and cells outside the map
+    pub fn get_tile(&self, x: u32, y: u32) -> Option<u32> {
+        self.index(x, y)
+            .map(|index| self.tiles[index])
+            .filter(|&tile| tile != EMPTY_TILE)
+    }
+
+    /// Set the tile index of a cell, `EMPTY_TILE` clears it
+    ///
+    /// Returns false if the cell is outside the map.
+    pub fn set_tile(&mut self, x: u32, y: u32, tile: u32) -> bool {
+        match self.index(x, y) {
+            Some(index) => {
+                self.tiles[index] = tile;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cells overlapping the world space rectangle from `view_min` to `view_max`
+    ///
+    /// `origin` is the world position of the map's bottom left corner and `scale`
+    /// multiplies the tile size. Only the overlapping range of cells is visited, so
+    /// the cost depends on the view, not the size of the map. Cells that only touch
+    /// the rectangle's edge aren't included.
+    pub fn visible_cells(&self, origin: Vec2, scale: Vec2, view_min: Vec2, view_max: Vec2) -> impl Iterator<Item = (u32, u32)> {
+        let tile_size = self.tile_size * scale;
+        let range = |min: f32, max: f32, origin: f32, size: f32, count: u32| {
+            if size <= 0.0 || min >= max {
+                return 0..0;
+            }
+            let first = ((min - origin) / size).floor().max(0.0);
+            let last = ((max - origin) / size).ceil().min(count as f32);
+            if first >= last {
+                0..0
+            } else {
+                first as u32..last as u32
+            }
+        };
+
+        let columns = range(view_min.x, view_max.x, origin.x, tile_size.x, self.width);
+        let rows = range(view_min.y, view_max.y, origin.y, tile_size.y, self.height);
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+    }
+
+    /// Sprites for the non-empty cells overlapping the view, see `visible_cells`
+    ///
+    /// Yields nothing until a tileset is assigned. Tiles missing from the tileset are skipped.
+    pub fn visible_sprites<'a>(&'a self, origin: Vec2, scale: Vec2, view_min: Vec2, view_max: Vec2) -> impl Iterator<Item = SpriteInstance> + 'a {
+        let tile_size = self.tile_size * scale;
+        let tileset = self.tileset.as_deref();
+
+        self.visible_cells(origin, scale, view_min, view_max)
+            .filter_map(move |(x, y)| {
+                let tileset = tileset?;
+                let uv = *tileset.tiles.get(self.get_tile(x, y)? as usize)?;
+                let center = origin + (Vec2::new(x as f32, y as f32) + 0.5) * tile_size;
+                Some(SpriteInstance::new(center, tile_size, tileset.texture).with_uv(uv))
+            })
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(tilemap: &TilemapComponent, origin: Vec2, scale: Vec2, view_min: Vec2, view_max: Vec2) -> Vec<(u32, u32)> {
+        tilemap.visible_cells(origin, scale, view_min, view_max).collect()
+    }
+
+    #[test]
+    fn cells_touching_the_view_edge_are_culled() {
+        let tilemap = TilemapComponent::new(10, 10, Vec2::ONE);
+        let visible = cells(&tilemap, Vec2::ZERO, Vec2::ONE, Vec2::new(2.0, 3.0), Vec2::new(4.0, 4.0));
+        assert_eq!(visible, vec![(2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn cells_partly_in_view_are_kept() {
+        let tilemap = TilemapComponent::new(10, 10, Vec2::ONE);
+        let visible = cells(&tilemap, Vec2::ZERO, Vec2::ONE, Vec2::new(1.5, 0.5), Vec2::new(2.5, 0.75));
+        assert_eq!(visible, vec![(1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn view_is_clipped_to_the_map() {
+        let tilemap = TilemapComponent::new(4, 2, Vec2::ONE);
+        let visible = cells(&tilemap, Vec2::ZERO, Vec2::ONE, Vec2::splat(-100.0), Vec2::splat(100.0));
+        assert_eq!(visible.len(), 8);
+        assert!(cells(&tilemap, Vec2::ZERO, Vec2::ONE, Vec2::new(4.0, 0.0), Vec2::new(8.0, 2.0)).is_empty());
+        assert!(cells(&tilemap, Vec2::ZERO, Vec2::ONE, Vec2::new(-3.0, -3.0), Vec2::ZERO).is_empty());
+    }
+
+    #[test]
+    fn origin_and_scale_move_the_cells() {
+        let tilemap = TilemapComponent::new(10, 10, Vec2::ONE);
+        // Tiles are 2 units wide starting at x = 10, so x = 14 is the edge between cells 1 and 2
+        let visible = cells(&tilemap, Vec2::new(10.0, 0.0), Vec2::splat(2.0), Vec2::new(14.0, 0.0), Vec2::new(15.0, 1.0));
+        assert_eq!(visible, vec![(2, 0)]);
+    }
+}
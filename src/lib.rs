@@ -5,6 +5,10 @@ mod rendering;
 mod utils;
 mod ecs;
 pub mod audio;
+mod scripting;
+mod assets;
+// Not glob exported, its AnimationClip would clash with the sprite one
+pub mod animation;
 
 pub use core::*;
 pub use scene::*;
@@ -13,16 +17,23 @@ pub use rendering::*;
 pub use utils::*;
 pub use ecs::*;
 pub use audio::*;
+pub use scripting::*;
+pub use assets::*;
 
 // Re-export common types
 pub use rendering::{
-    Renderer, Camera, OrthographicCamera, Mesh, Vertex, Texture, Shader, Material,
-    Model, Transform, Light, DirectionalLight, PointLight, LightManager
+    Renderer, RendererConfig, Camera, OrthographicCamera, ScreenAnchor, Camera2DController, Mesh, MeshData, PrimitiveShape, Vertex, Texture, TextureSampler, ColorSpace, TextureFormatPreference, DepthFormat, Shader, ShaderError, Material,
+    Model, Transform, FrameStats, Font, TextAlign, TextStyle, ClearMode, RenderView, CaptureError, SortingLayers, Light, DirectionalLight, PointLight, LightManager
 };
-pub use scene::{Scene, SceneManager, SceneState};
-pub use core::{GameLoop, DeltaTime, EventSystem};
+pub use scene::{Scene, SceneManager, SceneState, SceneError, SceneHandle};
+pub use core::{GameLoop, DeltaTime, FixedTimestep, EventSystem, EventReader, Input, KeyCode, InputMap};
 pub use ecs::{
-    EcsManager, EntityHandle, 
-    NameComponent, Transform2DComponent, Transform3DComponent, 
-    PhysicsComponent, RenderableComponent, CameraComponent
-}; 
\ No newline at end of file
+    EcsManager, EntityHandle, EntityRef,
+    NameComponent, Transform2DComponent, Transform3DComponent, GlobalTransform, BoundsComponent,
+    PhysicsComponent, RenderableComponent, RenderableDescriptor, CameraComponent, LightComponent, LightKind, AudioSourceComponent, ParentComponent,
+    SiblingOrderComponent, LayerComponent, ActiveComponent,
+    LuaScriptComponent, BodyType, Collider2DComponent, ColliderShape, WorldShape, Prefab,
+    TilemapComponent, Tileset, TextComponent, TextSpace,
+    SpriteComponent, SpriteAnimationComponent, AnimationClip, AnimationFinishedEvent,
+};
+pub use scripting::{LuaEngine, ScriptHandle}; 
\ No newline at end of file
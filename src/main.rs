@@ -5,22 +5,23 @@ mod rendering;
 mod utils;
 mod ecs;
 mod audio;
+mod scripting;
+mod assets;
+mod animation;
 
 use eframe::{NativeOptions, run_native};
-use ui::{MirageApp, EditorUI};
+use ui::{MirageApp, EditorUI, ConsoleLogger, SharedConsoleLogs};
 use log::LevelFilter;
 use std::env;
 use std::sync::Arc;
-use winit::window::WindowBuilder;
-use winit::event_loop::{EventLoop, ControlFlow};
-use winit::event::{Event, WindowEvent};
+use winit::event::Event;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logger
-    env_logger::Builder::new()
-        .filter_level(LevelFilter::Info)
-        .init();
+    // Initialize logger, records also show up in the editor console
+    let mut log_builder = env_logger::Builder::new();
+    log_builder.filter_level(LevelFilter::Info).parse_default_env();
+    let console_logs = ConsoleLogger::install(log_builder)?;
     
     // Check command line arguments
     let args: Vec<String> = env::args().collect();
@@ -37,6 +38,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             resizable: true,
             vsync: true,
             centered: true,
+            // The scene view renders through the same wgpu device as the UI
+            renderer: eframe::Renderer::Wgpu,
             ..Default::default()
         };
         
@@ -44,7 +47,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         run_native(
             "Mirage Engine Editor",
             options,
-            Box::new(|cc| Box::new(EditorApp::new(cc)))
+            Box::new(|cc| Box::new(EditorApp::new(cc, console_logs)))
         )?;
         
         Ok(())
@@ -55,18 +58,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 struct EditorApp {
     editor_ui: EditorUI,
     last_update_time: std::time::Instant,
+    /// Title last set on the window
+    window_title: String,
 }
 
 impl EditorApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, console_logs: SharedConsoleLogs) -> Self {
         // Set default egui style
         let mut style = (*cc.egui_ctx.style()).clone();
         style.visuals = egui::Visuals::dark();
         cc.egui_ctx.set_style(style);
         
+        let mut editor_ui = EditorUI::new();
+        editor_ui.console_panel.set_log_source(console_logs);
+        if let Some(render_state) = cc.wgpu_render_state.as_ref() {
+            editor_ui.scene_view_panel.renderer = Some(rendering::Renderer::from_render_state(render_state, rendering::DepthFormat::default()));
+        }
+        
         Self {
-            editor_ui: EditorUI::new(),
+            editor_ui,
             last_update_time: std::time::Instant::now(),
+            window_title: String::new(),
         }
     }
 }
@@ -81,6 +93,18 @@ impl eframe::App for EditorApp {
         // Update editor UI
         self.editor_ui.update(ctx, delta_time);
         
+        // Show the scene name and whether it has unsaved changes
+        let window_title = self.editor_ui.window_title();
+        if window_title != self.window_title {
+            frame.set_window_title(&window_title);
+            self.window_title = window_title;
+        }
+        
+        // The user chose to exit after being asked about unsaved changes
+        if self.editor_ui.exit_confirmed() {
+            frame.close();
+        }
+        
         // Maintain window size
         if let Some(original_size) = self.editor_ui.get_original_size() {
             let current_size = frame.info().window_info.size;
@@ -92,44 +116,81 @@ impl eframe::App for EditorApp {
         // Request continuous repainting
         ctx.request_repaint();
     }
+    
+    fn on_close_event(&mut self) -> bool {
+        self.editor_ui.request_exit()
+    }
 }
 
 async fn run_with_winit() -> Result<(), Box<dyn std::error::Error>> {
-    // Create event loop
-    let event_loop = EventLoop::new();
-    
-    // Create window
-    let window = WindowBuilder::new()
-        .with_title("Mirage Engine (winit)")
-        .with_inner_size(winit::dpi::LogicalSize::new(1280.0, 720.0))
-        .build(&event_loop)?;
+    // Create window, F11 toggles fullscreen
+    let window_manager = ui::WindowManager::new("Mirage Engine (winit)", 1280, 720);
+    let window = window_manager.window().ok_or("The window manager has no window")?;
     
     // Create renderer
-    let mut renderer = rendering::Renderer::new(&window).await?;
+    let renderer_config = rendering::RendererConfig {
+        vsync: window_manager.vsync(),
+        ..Default::default()
+    };
+    let mut renderer = rendering::Renderer::new(window, renderer_config).await?;
+    
+    // Keyboard/mouse state for game logic
+    let mut input = core::Input::new();
+    
+    // Engine events, gamepad connections are only logged for now
+    let mut event_system = core::EventSystem::new();
+    event_system.add_handler(|event: &core::GamepadConnectedEvent| {
+        log::info!("Gamepad {} connected: {}", event.gamepad, event.name);
+    });
+    event_system.add_handler(|event: &core::GamepadDisconnectedEvent| {
+        log::info!("Gamepad {} disconnected", event.gamepad);
+    });
+    
+    // Frames rendered, to log the averaged stats once per stats window
+    let mut frame_count: usize = 0;
     
     // Run event loop
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-        
+    window_manager.run(move |window_manager, event| {
         match event {
-            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
-                *control_flow = ControlFlow::Exit;
+            Event::NewEvents(_) => {
+                // Start of a new frame
+                event_system.update();
+                input.begin_frame();
+                input.publish_gamepad_events(&mut event_system);
             },
-            Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
-                // Resize renderer
-                renderer.resize(size.width, size.height);
+            Event::DeviceEvent { event, .. } => {
+                input.handle_device_event(event);
+            },
+            Event::WindowEvent { event, .. } => {
+                input.handle_window_event(event);
             },
             Event::MainEventsCleared => {
-                // Redraw the window
-                window.request_redraw();
+                // Follow resizes, fullscreen and vsync changes, then redraw the window
+                window_manager.apply_to_renderer(&mut renderer);
+                if let Some(window) = window_manager.window() {
+                    window.request_redraw();
+                }
             },
             Event::RedrawRequested(_) => {
                 // Render frame
-                if let Err(e) = renderer.render_frame() {
-                    log::error!("Failed to render frame: {}", e);
+                match renderer.render_frame() {
+                    Ok(()) => {}
+                    // Nothing more can be drawn
+                    Err(rendering::RenderError::OutOfMemory) => {
+                        log::error!("The GPU ran out of memory, exiting");
+                        window_manager.request_close();
+                    }
+                    Err(e) => log::error!("Failed to render frame: {}", e),
+                }
+                
+                frame_count += 1;
+                if frame_count % rendering::FRAME_STATS_HISTORY == 0 {
+                    log::debug!("{}", renderer.average_stats());
                 }
             },
             _ => (),
         }
     });
+    
+    Ok(())
 }
@@ -3,4 +3,7 @@ pub mod scene;
 pub mod ui;
 pub mod rendering;
 pub mod utils;
-pub mod audio; 
\ No newline at end of file
+pub mod audio;
+pub mod scripting;
+pub mod assets;
+pub mod animation;
\ No newline at end of file
@@ -0,0 +1,74 @@
+use serde::{Serialize, Deserialize};
+
+/// How a surface's color is combined with what's already drawn
+///
+/// Each mode has its own pipeline, so draws switching modes can't share a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// Replace what's below, ignoring alpha
+    Opaque,
+    /// Cover what's below by the surface's alpha
+    #[default]
+    AlphaBlend,
+    /// Add the color weighted by its alpha, e.g. for glows and particles
+    Additive,
+    /// Multiply what's below by the color, e.g. for shadow decals
+    Multiply,
+}
+
+impl BlendMode {
+    /// Every mode, in the order pipelines are kept in
+    pub const ALL: [BlendMode; 4] = [BlendMode::Opaque, BlendMode::AlphaBlend, BlendMode::Additive, BlendMode::Multiply];
+
+    /// Name shown in the editor
+    pub fn label(self) -> &'static str {
+        match self {
+            BlendMode::Opaque => "Opaque",
+            BlendMode::AlphaBlend => "Alpha Blend",
+            BlendMode::Additive => "Additive",
+            BlendMode::Multiply => "Multiply",
+        }
+    }
+
+    /// Index of the mode in `ALL`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Whether surfaces drawn with this mode hide what's behind them in the depth buffer
+    ///
+    /// Additive and multiplied surfaces only tint what's behind them, so they don't.
+    pub fn writes_depth(self) -> bool {
+        matches!(self, BlendMode::Opaque | BlendMode::AlphaBlend)
+    }
+
+    /// Blend state of the color target, `None` for opaque draws
+    pub fn blend_state(self) -> Option<wgpu::BlendState> {
+        // Coverage accumulates the same way in every mode
+        let alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        };
+        match self {
+            BlendMode::Opaque => None,
+            BlendMode::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            BlendMode::Additive => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha,
+            }),
+            BlendMode::Multiply => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha,
+            }),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use glam::{Mat4, Vec2, Vec3};
+use crate::utils::math::{Frustum, Ray};
 
 /// Base trait for all cameras
 pub trait Camera {
@@ -12,6 +13,69 @@ pub trait Camera {
     fn view_projection_matrix(&self) -> Mat4 {
         self.projection_matrix() * self.view_matrix()
     }
+    
+    /// Ray from the near plane through a pixel (origin top-left, Y down) of a viewport
+    fn screen_point_to_ray(&self, screen_pos: Vec2, viewport: Vec2) -> Ray {
+        screen_point_to_ray(self.view_projection_matrix(), screen_pos, viewport)
+    }
+    
+    /// The volume the camera sees, for culling
+    fn frustum(&self) -> Frustum {
+        Frustum::from_view_projection(self.view_projection_matrix())
+    }
+}
+
+/// Ray from the near plane through a pixel (origin top-left, Y down) of a viewport
+/// seen through `view_projection`
+///
+/// Works for perspective and orthographic projections with a 0 to 1 depth range.
+pub fn screen_point_to_ray(view_projection: Mat4, screen_pos: Vec2, viewport: Vec2) -> Ray {
+    let ndc = Vec2::new(
+        screen_pos.x / viewport.x.max(1.0) * 2.0 - 1.0,
+        1.0 - screen_pos.y / viewport.y.max(1.0) * 2.0,
+    );
+    let inverse = view_projection.inverse();
+    let near = inverse.project_point3(ndc.extend(0.0));
+    let far = inverse.project_point3(ndc.extend(1.0));
+    Ray::between(near, far)
+}
+
+/// Point of the viewport UI can be pinned to, kept across resizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenAnchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ScreenAnchor {
+    pub const ALL: [ScreenAnchor; 9] = [
+        ScreenAnchor::TopLeft, ScreenAnchor::Top, ScreenAnchor::TopRight,
+        ScreenAnchor::Left, ScreenAnchor::Center, ScreenAnchor::Right,
+        ScreenAnchor::BottomLeft, ScreenAnchor::Bottom, ScreenAnchor::BottomRight,
+    ];
+
+    /// Position in normalized viewport coordinates, 0 to 1 from the top-left corner with Y down
+    pub fn viewport_point(self) -> Vec2 {
+        let x = match self {
+            ScreenAnchor::TopLeft | ScreenAnchor::Left | ScreenAnchor::BottomLeft => 0.0,
+            ScreenAnchor::Top | ScreenAnchor::Center | ScreenAnchor::Bottom => 0.5,
+            ScreenAnchor::TopRight | ScreenAnchor::Right | ScreenAnchor::BottomRight => 1.0,
+        };
+        let y = match self {
+            ScreenAnchor::TopLeft | ScreenAnchor::Top | ScreenAnchor::TopRight => 0.0,
+            ScreenAnchor::Left | ScreenAnchor::Center | ScreenAnchor::Right => 0.5,
+            ScreenAnchor::BottomLeft | ScreenAnchor::Bottom | ScreenAnchor::BottomRight => 1.0,
+        };
+        Vec2::new(x, y)
+    }
 }
 
 /// 2D orthographic camera for rendering 2D scenes
@@ -113,6 +177,73 @@ impl OrthographicCamera {
         self.bottom = -half_height;
         self.top = half_height;
     }
+    
+    /// World space corners (min, max) of the axis aligned box around everything the camera sees
+    pub fn visible_bounds(&self) -> (Vec2, Vec2) {
+        let half_size = Vec2::new(self.right - self.left, self.top - self.bottom) / (2.0 * self.zoom);
+        let offset = Vec2::new(self.right + self.left, self.top + self.bottom) / (2.0 * self.zoom);
+        
+        // A rotated view covers a larger axis aligned area
+        let (sin, cos) = self.rotation.sin_cos();
+        let center = self.position + Vec2::new(offset.x * cos - offset.y * sin, offset.x * sin + offset.y * cos);
+        let extents = Vec2::new(
+            half_size.x * cos.abs() + half_size.y * sin.abs(),
+            half_size.x * sin.abs() + half_size.y * cos.abs(),
+        );
+        (center - extents, center + extents)
+    }
+    
+    /// Convert a pixel position (origin top-left, Y down) to world space
+    pub fn screen_to_world(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        if viewport.x <= 0.0 || viewport.y <= 0.0 {
+            return self.position;
+        }
+        
+        let ndc = Vec2::new(
+            position.x / viewport.x * 2.0 - 1.0,
+            1.0 - position.y / viewport.y * 2.0,
+        );
+        let world = self.view_projection_matrix().inverse().project_point3(Vec3::new(ndc.x, ndc.y, 0.0));
+        Vec2::new(world.x, world.y)
+    }
+    
+    /// Convert a world position to pixels (origin top-left, Y down)
+    pub fn world_to_screen(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        let ndc = self.view_projection_matrix().project_point3(Vec3::new(position.x, position.y, 0.0));
+        Vec2::new(
+            (ndc.x + 1.0) / 2.0 * viewport.x,
+            (1.0 - ndc.y) / 2.0 * viewport.y,
+        )
+    }
+    
+    /// Convert a pixel position to normalized viewport coordinates, 0 to 1 from the top-left corner with Y down
+    pub fn screen_to_viewport(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        position / viewport.max(Vec2::ONE)
+    }
+    
+    /// Convert normalized viewport coordinates to pixels (origin top-left, Y down)
+    pub fn viewport_to_screen(&self, point: Vec2, viewport: Vec2) -> Vec2 {
+        point * viewport
+    }
+    
+    /// Convert normalized viewport coordinates to world space
+    pub fn viewport_to_world(&self, point: Vec2, viewport: Vec2) -> Vec2 {
+        self.screen_to_world(self.viewport_to_screen(point, viewport), viewport)
+    }
+    
+    /// Convert a world position to normalized viewport coordinates
+    pub fn world_to_viewport(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        self.screen_to_viewport(self.world_to_screen(position, viewport), viewport)
+    }
+    
+    /// World position of a point pinned to `anchor`, `offset` pixels away from it (Y down)
+    ///
+    /// Call it with the current viewport size every frame and the point stays at the
+    /// same place on screen when the window is resized.
+    pub fn anchor_to_world(&self, anchor: ScreenAnchor, offset: Vec2, viewport: Vec2) -> Vec2 {
+        let screen = self.viewport_to_screen(anchor.viewport_point(), viewport) + offset;
+        self.screen_to_world(screen, viewport)
+    }
 }
 
 impl Camera for OrthographicCamera {
@@ -138,4 +269,38 @@ impl Camera for OrthographicCamera {
             self.far,
         )
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchored_point_keeps_its_place_on_screen_across_resizes() {
+        let mut camera = OrthographicCamera::new(800.0, 600.0, -1.0, 1.0);
+        camera.set_position(Vec2::new(100.0, -50.0));
+        let offset = Vec2::new(-10.0, 10.0);
+
+        for viewport in [Vec2::new(800.0, 600.0), Vec2::new(1024.0, 768.0)] {
+            camera.resize(viewport.x, viewport.y);
+            let world = camera.anchor_to_world(ScreenAnchor::TopRight, offset, viewport);
+
+            // One pixel is one world unit, the top right corner is half the viewport from the camera
+            let expected = camera.position() + Vec2::new(viewport.x / 2.0 - 10.0, viewport.y / 2.0 - 10.0);
+            assert!(world.abs_diff_eq(expected, 1e-3), "{world} at {viewport}");
+            assert!(camera.world_to_screen(world, viewport).abs_diff_eq(Vec2::new(viewport.x - 10.0, 10.0), 1e-3));
+        }
+    }
+
+    #[test]
+    fn anchors_map_to_their_viewport_points() {
+        let camera = OrthographicCamera::new(200.0, 100.0, -1.0, 1.0);
+        let viewport = Vec2::new(200.0, 100.0);
+
+        assert!(camera.anchor_to_world(ScreenAnchor::Center, Vec2::ZERO, viewport).abs_diff_eq(Vec2::ZERO, 1e-4));
+        assert!(camera.anchor_to_world(ScreenAnchor::BottomLeft, Vec2::ZERO, viewport).abs_diff_eq(Vec2::new(-100.0, -50.0), 1e-4));
+        for anchor in ScreenAnchor::ALL {
+            let world = camera.viewport_to_world(anchor.viewport_point(), viewport);
+            assert!(camera.world_to_viewport(world, viewport).abs_diff_eq(anchor.viewport_point(), 1e-5), "{:?}", anchor);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use glam::{Mat4, Vec2};
+use winit::event::MouseButton;
+use crate::core::{Input, KeyCode};
+use crate::rendering::{Camera, OrthographicCamera};
+
+/// Pans and zooms an `OrthographicCamera` from `Input`
+///
+/// WASD or the arrow keys pan, the scroll wheel zooms toward the cursor and
+/// dragging with the middle mouse button pans, like the editor scene view.
+pub struct Camera2DController {
+    camera: OrthographicCamera,
+    /// Keyboard pan speed in world units per second at zoom 1
+    pub pan_speed: f32,
+    /// Zoom change per scroll line, 0.1 zooms 10% per line
+    pub zoom_speed: f32,
+    /// Smallest allowed zoom
+    pub min_zoom: f32,
+    /// Largest allowed zoom
+    pub max_zoom: f32,
+}
+
+impl Camera2DController {
+    /// Create a controller for a camera
+    pub fn new(camera: OrthographicCamera) -> Self {
+        Self {
+            camera,
+            pan_speed: 10.0,
+            zoom_speed: 0.1,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+        }
+    }
+
+    /// Set the allowed zoom range
+    pub fn with_zoom_range(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom.max(min_zoom);
+        self.set_zoom(self.camera.zoom());
+        self
+    }
+
+    /// Get the controlled camera
+    pub fn camera(&self) -> &OrthographicCamera {
+        &self.camera
+    }
+
+    /// Get the controlled camera mutably
+    pub fn camera_mut(&mut self) -> &mut OrthographicCamera {
+        &mut self.camera
+    }
+
+    /// Set the zoom, clamped to the zoom range
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.camera.set_zoom(zoom.clamp(self.min_zoom, self.max_zoom));
+    }
+
+    /// Apply this frame's input to the camera
+    ///
+    /// `viewport` is the size of the view in pixels, matching `Input::mouse_position`.
+    pub fn update(&mut self, input: &Input, viewport: Vec2, delta_time: f32) {
+        // Keyboard pan
+        let mut direction = Vec2::ZERO;
+        if input.is_key_down(KeyCode::W) || input.is_key_down(KeyCode::Up) {
+            direction.y += 1.0;
+        }
+        if input.is_key_down(KeyCode::S) || input.is_key_down(KeyCode::Down) {
+            direction.y -= 1.0;
+        }
+        if input.is_key_down(KeyCode::A) || input.is_key_down(KeyCode::Left) {
+            direction.x -= 1.0;
+        }
+        if input.is_key_down(KeyCode::D) || input.is_key_down(KeyCode::Right) {
+            direction.x += 1.0;
+        }
+        if direction != Vec2::ZERO {
+            let offset = direction.normalize() * self.pan_speed * delta_time / self.camera.zoom();
+            self.camera.set_position(self.camera.position() + offset);
+        }
+
+        let cursor = input.mouse_position();
+
+        // Middle-drag pan, keeping the world point under the cursor fixed
+        if input.is_mouse_down(MouseButton::Middle) && input.mouse_delta() != Vec2::ZERO {
+            let from = self.screen_to_world(cursor - input.mouse_delta(), viewport);
+            let to = self.screen_to_world(cursor, viewport);
+            self.camera.set_position(self.camera.position() + from - to);
+        }
+
+        // Zoom toward the cursor
+        let scroll = input.scroll_delta().y;
+        if scroll != 0.0 {
+            let before = self.screen_to_world(cursor, viewport);
+            self.set_zoom(self.camera.zoom() * (1.0 + self.zoom_speed).powf(scroll));
+            let after = self.screen_to_world(cursor, viewport);
+            self.camera.set_position(self.camera.position() + before - after);
+        }
+    }
+
+    /// Get the view matrix
+    pub fn view_matrix(&self) -> Mat4 {
+        self.camera.view_matrix()
+    }
+
+    /// Get the projection matrix
+    pub fn projection_matrix(&self) -> Mat4 {
+        self.camera.projection_matrix()
+    }
+
+    /// Convert a pixel position (origin top-left, Y down) to world space
+    pub fn screen_to_world(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        self.camera.screen_to_world(position, viewport)
+    }
+
+    /// Convert a world position to pixels (origin top-left, Y down)
+    pub fn world_to_screen(&self, position: Vec2, viewport: Vec2) -> Vec2 {
+        self.camera.world_to_screen(position, viewport)
+    }
+}
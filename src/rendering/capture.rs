@@ -0,0 +1,131 @@
+use std::fmt;
+use std::sync::mpsc;
+use image::RgbaImage;
+use wgpu::{Device, Queue, Texture as WgpuTexture, TextureFormat};
+
+/// Errors that can occur while reading a frame back from the GPU
+#[derive(Debug)]
+pub enum CaptureError {
+    /// No frame has been rendered yet
+    NoFrame,
+    /// The frame went to a surface that doesn't allow copying from it
+    NotReadable,
+    /// Pixels of this format can't be converted to 8-bit RGBA
+    UnsupportedFormat(TextureFormat),
+    /// Mapping the readback buffer failed
+    Map(wgpu::BufferAsyncError),
+    /// Encoding or writing the image failed
+    Image(image::ImageError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::NoFrame => write!(f, "No frame has been rendered yet"),
+            CaptureError::NotReadable => write!(f, "The surface doesn't support reading frames back"),
+            CaptureError::UnsupportedFormat(format) => write!(f, "Can't capture frames of format {:?}", format),
+            CaptureError::Map(e) => write!(f, "Failed to read the frame back: {}", e),
+            CaptureError::Image(e) => write!(f, "Failed to save the frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CaptureError::Map(e) => Some(e),
+            CaptureError::Image(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for CaptureError {
+    fn from(e: image::ImageError) -> Self {
+        CaptureError::Image(e)
+    }
+}
+
+/// Bytes per row of a `width` pixel wide RGBA8 copy, padded as buffer copies require
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (unpadded + align - 1) / align * align
+}
+
+/// Copy a single-sampled texture into CPU memory, blocking until the copy is done
+///
+/// The texture needs `COPY_SRC` usage and a 4-byte RGBA or BGRA format. sRGB
+/// formats are returned as stored, i.e. already gamma encoded.
+pub fn read_texture(device: &Device, queue: &Queue, texture: &WgpuTexture, size: (u32, u32), format: TextureFormat) -> Result<RgbaImage, CaptureError> {
+    let swap_red_blue = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        other => return Err(CaptureError::UnsupportedFormat(other)),
+    };
+    let (width, height) = size;
+    let unpadded_bytes_per_row = width * 4;
+    let padded_bytes_per_row = padded_bytes_per_row(width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Frame Capture Buffer"),
+        size: padded_bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Frame Capture Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()
+        .unwrap_or(Err(wgpu::BufferAsyncError))
+        .map_err(CaptureError::Map)?;
+
+    // Drop the padding at the end of each row
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks_exact(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    // The buffer holds exactly width * height pixels
+    Ok(RgbaImage::from_raw(width, height, pixels).expect("capture buffer matches the image size"))
+}
@@ -0,0 +1,424 @@
+use std::f32::consts::TAU;
+use std::ops::Range;
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec2, Vec3};
+use log::warn;
+use wgpu::{
+    util::DeviceExt, BindGroup, Buffer, BufferUsages, Device, Queue, PipelineLayout, RenderPass,
+    RenderPipeline, TextureFormat, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
+};
+use crate::rendering::{FrameStats, Rect, Shader, TextStyle};
+use crate::rendering::shader::create_debug_line_shader;
+
+/// Lines a `DebugDraw` holds per frame unless created with another capacity
+pub const DEFAULT_DEBUG_LINE_CAPACITY: usize = 65536;
+
+/// Line segments a circle is drawn with
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// Lines the GPU buffer is sized for before it first grows
+const INITIAL_LINE_CAPACITY: usize = 1024;
+
+/// End of a debug line
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct LineVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl LineVertex {
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x3,
+                },
+                // Color
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Debug text queued for the frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugText {
+    pub position: Vec2,
+    pub text: String,
+    pub style: TextStyle,
+    pub scale: f32,
+}
+
+/// Lines and text drawn over the rendered view for one frame, e.g. AI paths or raycasts
+///
+/// Shapes are in world space and drawn with the sprites' view-projection, so they
+/// line up with sprites and, under a camera, with models. The renderer clears them
+/// once a frame is drawn, so they are queued again every frame. Get the renderer's
+/// with `Renderer::debug_draw`.
+///
+/// At most `capacity` lines are kept per frame. Lines queued past that are dropped,
+/// `dropped` counts them and the first overflow is logged. Shapes count as the lines
+/// they are made of, a circle as `32`. Text isn't capped.
+#[derive(Debug, Clone)]
+pub struct DebugDraw {
+    /// Line list of the depth tested lines
+    depth_tested: Vec<LineVertex>,
+    /// Line list of the lines drawn on top of everything
+    on_top: Vec<LineVertex>,
+    texts: Vec<DebugText>,
+    capacity: usize,
+    dropped: usize,
+    /// Whether an overflow was logged already
+    warned: bool,
+    depth_test: bool,
+    text_style: TextStyle,
+    text_scale: f32,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_DEBUG_LINE_CAPACITY)
+    }
+}
+
+impl DebugDraw {
+    /// Create an empty queue holding `DEFAULT_DEBUG_LINE_CAPACITY` lines
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty queue holding at most `capacity` lines per frame
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            depth_tested: Vec::new(),
+            on_top: Vec::new(),
+            texts: Vec::new(),
+            capacity,
+            dropped: 0,
+            warned: false,
+            depth_test: false,
+            text_style: TextStyle {
+                font_size: 16.0,
+                ..TextStyle::default()
+            },
+            text_scale: 1.0,
+        }
+    }
+
+    /// Lines kept per frame
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change how many lines are kept per frame, lines already queued are kept
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+    }
+
+    /// Lines queued this frame
+    pub fn line_count(&self) -> usize {
+        (self.depth_tested.len() + self.on_top.len()) / 2
+    }
+
+    /// Lines dropped this frame for being past the capacity
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.on_top.is_empty() && self.texts.is_empty()
+    }
+
+    /// Whether the lines queued from now on are hidden by models in front of them
+    ///
+    /// Depth tested lines are drawn with the models, below sprites. The default
+    /// draws lines on top of everything in the view.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    /// Style of the text queued from now on, and world units per pixel of its glyphs
+    ///
+    /// The scale keeps text readable under cameras that don't draw in pixels.
+    pub fn set_text_style(&mut self, style: TextStyle, scale: f32) {
+        self.text_style = style;
+        self.text_scale = scale;
+    }
+
+    /// Queue a line in the 2D plane
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: [f32; 4]) {
+        self.line_3d(a.extend(0.0), b.extend(0.0), color);
+    }
+
+    /// Queue a line in 3D space
+    pub fn line_3d(&mut self, a: Vec3, b: Vec3, color: [f32; 4]) {
+        if self.line_count() >= self.capacity {
+            self.dropped += 1;
+            if !self.warned {
+                warn!("Debug draw capacity of {} lines reached, further lines are dropped", self.capacity);
+                self.warned = true;
+            }
+            return;
+        }
+        let lines = if self.depth_test { &mut self.depth_tested } else { &mut self.on_top };
+        lines.push(LineVertex { position: a.to_array(), color });
+        lines.push(LineVertex { position: b.to_array(), color });
+    }
+
+    /// Queue the outline of the rectangle from `(x, y)` to `(x + width, y + height)`
+    pub fn rect(&mut self, rect: Rect, color: [f32; 4]) {
+        let min = Vec2::new(rect.x, rect.y);
+        let max = min + Vec2::new(rect.width, rect.height);
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        for i in 0..4 {
+            self.line(corners[i], corners[(i + 1) % 4], color);
+        }
+    }
+
+    /// Queue the outline of a circle in the 2D plane
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: [f32; 4]) {
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    /// Queue text anchored at `position`, in the style set by `set_text_style`
+    pub fn text(&mut self, position: Vec2, text: &str) {
+        self.texts.push(DebugText {
+            position,
+            text: text.to_string(),
+            style: self.text_style,
+            scale: self.text_scale,
+        });
+    }
+
+    /// Take the queued text, for drawing it with the sprites
+    pub fn take_texts(&mut self) -> Vec<DebugText> {
+        std::mem::take(&mut self.texts)
+    }
+
+    /// Remove everything queued, done by the renderer after each frame
+    pub fn clear(&mut self) {
+        self.depth_tested.clear();
+        self.on_top.clear();
+        self.texts.clear();
+        self.dropped = 0;
+    }
+}
+
+/// GPU side of `DebugDraw`: a line pipeline with and one without depth testing
+pub struct DebugLineRenderer {
+    depth_tested_pipeline: RenderPipeline,
+    on_top_pipeline: RenderPipeline,
+    pipeline_layout: PipelineLayout,
+    shader: Shader,
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    vertex_buffer: Buffer,
+    /// Lines `vertex_buffer` has room for
+    capacity: usize,
+    /// Vertices of the depth tested lines in `vertex_buffer`
+    depth_tested: Range<u32>,
+    /// Vertices of the lines drawn on top
+    on_top: Range<u32>,
+}
+
+impl DebugLineRenderer {
+    /// Create the line pipelines for the given color and depth target formats and sample count
+    pub fn new(device: &Device, format: TextureFormat, depth_format: TextureFormat, sample_count: u32) -> Self {
+        let shader = create_debug_line_shader(device);
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Debug Line Camera Bind Group Layout"),
+        });
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Debug Line Camera Buffer"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("Debug Line Camera Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Line Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let depth_tested_pipeline = create_pipeline(device, &pipeline_layout, &shader, format, sample_count, Some(depth_format));
+        let on_top_pipeline = create_pipeline(device, &pipeline_layout, &shader, format, sample_count, None);
+
+        Self {
+            depth_tested_pipeline,
+            on_top_pipeline,
+            pipeline_layout,
+            shader,
+            format,
+            depth_format,
+            camera_buffer,
+            camera_bind_group,
+            vertex_buffer: create_vertex_buffer(device, INITIAL_LINE_CAPACITY),
+            capacity: INITIAL_LINE_CAPACITY,
+            depth_tested: 0..0,
+            on_top: 0..0,
+        }
+    }
+
+    /// Rebuild the pipelines for targets with `sample_count` samples
+    pub fn set_sample_count(&mut self, device: &Device, sample_count: u32) {
+        self.depth_tested_pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, sample_count, Some(self.depth_format));
+        self.on_top_pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, sample_count, None);
+    }
+
+    /// Upload the queued lines, growing the vertex buffer if needed
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, debug_draw: &DebugDraw) {
+        let line_count = debug_draw.line_count();
+        if line_count > self.capacity {
+            self.capacity = line_count.next_power_of_two();
+            self.vertex_buffer = create_vertex_buffer(device, self.capacity);
+        }
+
+        let depth_tested = debug_draw.depth_tested.len() as u32;
+        let on_top = debug_draw.on_top.len() as u32;
+        if depth_tested > 0 {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&debug_draw.depth_tested));
+        }
+        if on_top > 0 {
+            let offset = (depth_tested as usize * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress;
+            queue.write_buffer(&self.vertex_buffer, offset, bytemuck::cast_slice(&debug_draw.on_top));
+        }
+        self.depth_tested = 0..depth_tested;
+        self.on_top = depth_tested..depth_tested + on_top;
+    }
+
+    /// Set the view-projection the next submitted lines are drawn with
+    pub fn set_view_projection(&self, queue: &Queue, view_projection: Mat4) {
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&view_projection.to_cols_array()));
+    }
+
+    /// Draw the prepared depth tested lines, into a pass with the depth attachment
+    pub fn render_depth_tested<'a>(&'a self, render_pass: &mut RenderPass<'a>, stats: &mut FrameStats) {
+        self.render(render_pass, &self.depth_tested_pipeline, self.depth_tested.clone(), stats);
+    }
+
+    /// Draw the prepared lines that go on top, into a pass without a depth attachment
+    pub fn render_on_top<'a>(&'a self, render_pass: &mut RenderPass<'a>, stats: &mut FrameStats) {
+        self.render(render_pass, &self.on_top_pipeline, self.on_top.clone(), stats);
+    }
+
+    fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, pipeline: &'a RenderPipeline, vertices: Range<u32>, stats: &mut FrameStats) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(vertices, 0..1);
+        // Lines have no triangles
+        stats.record_draw(0);
+    }
+}
+
+/// Create a vertex buffer for `capacity` lines
+fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Debug Line Vertex Buffer"),
+        size: (capacity * 2 * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Line pipeline for color targets of `format` with `sample_count` samples
+///
+/// Lines are depth tested against a depth target of `depth_format` if there is one.
+/// They don't write depth, so they never hide each other.
+fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    format: TextureFormat,
+    sample_count: u32,
+    depth_format: Option<TextureFormat>,
+) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(if depth_format.is_some() { "Depth Tested Debug Line Pipeline" } else { "Debug Line Pipeline" }),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers: &[LineVertex::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader.module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
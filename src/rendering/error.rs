@@ -0,0 +1,167 @@
+use std::fmt;
+use std::path::PathBuf;
+use crate::rendering::ShaderError;
+
+/// Errors that can occur while setting up the renderer or drawing a frame
+#[derive(Debug)]
+pub enum RenderError {
+    /// The window surface couldn't be created
+    CreateSurface(wgpu::CreateSurfaceError),
+    /// No graphics adapter can draw to the window
+    AdapterNotFound,
+    /// The adapter reports no format or alpha mode the surface can be configured with
+    UnsupportedSurface,
+    /// The adapter refused to create a device
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The renderer draws offscreen and has no window surface
+    NoSurface,
+    /// The surface stayed lost or outdated after reconfiguring it
+    SurfaceLost,
+    /// Getting the next frame from the surface took too long
+    SurfaceTimeout,
+    /// The GPU ran out of memory
+    OutOfMemory,
+    /// The surface can't be resized to this size
+    InvalidSize {
+        width: u32,
+        height: u32,
+        /// Largest width and height the device supports
+        max: u32,
+    },
+    /// A shader failed to compile or lacks entry points
+    ShaderCompile(ShaderError),
+    /// An image couldn't be read or uploaded as a texture
+    TextureLoad(anyhow::Error),
+    /// A file couldn't be read
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::CreateSurface(e) => write!(f, "Failed to create the window surface: {}", e),
+            RenderError::AdapterNotFound => write!(f, "Failed to find an appropriate adapter"),
+            RenderError::UnsupportedSurface => write!(f, "The adapter can't draw to the window surface"),
+            RenderError::RequestDevice(e) => write!(f, "Failed to create the device: {}", e),
+            RenderError::NoSurface => write!(f, "Renderer has no window surface"),
+            RenderError::SurfaceLost => write!(f, "The window surface was lost and couldn't be reconfigured"),
+            RenderError::SurfaceTimeout => write!(f, "Timed out waiting for the next frame"),
+            RenderError::OutOfMemory => write!(f, "The GPU ran out of memory"),
+            RenderError::InvalidSize { width, height, max } => {
+                write!(f, "Can't resize to {}x{}, the largest supported size is {}x{}", width, height, max, max)
+            }
+            RenderError::ShaderCompile(e) => write!(f, "{}", e),
+            RenderError::TextureLoad(e) => write!(f, "Failed to load texture {:#}", e),
+            RenderError::Io { path, source } => write!(f, "Failed to read {}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::CreateSurface(e) => Some(e),
+            RenderError::RequestDevice(e) => Some(e),
+            RenderError::ShaderCompile(e) => Some(e),
+            RenderError::TextureLoad(e) => Some(e.as_ref()),
+            RenderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::SurfaceError> for RenderError {
+    fn from(e: wgpu::SurfaceError) -> Self {
+        match e {
+            wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => RenderError::SurfaceLost,
+            wgpu::SurfaceError::OutOfMemory => RenderError::OutOfMemory,
+            wgpu::SurfaceError::Timeout => RenderError::SurfaceTimeout,
+        }
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for RenderError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        RenderError::CreateSurface(e)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for RenderError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        RenderError::RequestDevice(e)
+    }
+}
+
+impl From<ShaderError> for RenderError {
+    fn from(e: ShaderError) -> Self {
+        RenderError::ShaderCompile(e)
+    }
+}
+
+/// Get a frame with `acquire`, reconfiguring the surface and trying once more if it was lost or outdated
+///
+/// Surfaces go out of date when the window is resized or restored, and are lost
+/// when the GPU resets; both recover once the surface is configured again.
+pub(crate) fn acquire_with_retry<T>(
+    mut acquire: impl FnMut() -> Result<T, wgpu::SurfaceError>,
+    mut reconfigure: impl FnMut(),
+) -> Result<T, RenderError> {
+    match acquire() {
+        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+            reconfigure();
+            Ok(acquire()?)
+        }
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Acquire frames from a scripted list of results, counting reconfigures
+    fn acquire_from(results: Vec<Result<u32, wgpu::SurfaceError>>) -> (Result<u32, RenderError>, usize, u32) {
+        let mut results = results.into_iter();
+        let attempts = Cell::new(0);
+        let mut reconfigures = 0;
+        let result = acquire_with_retry(
+            || {
+                attempts.set(attempts.get() + 1);
+                results.next().expect("acquired more often than scripted")
+            },
+            || reconfigures += 1,
+        );
+        (result, attempts.get(), reconfigures)
+    }
+
+    #[test]
+    fn lost_surface_is_reconfigured_and_acquired_again() {
+        for lost in [wgpu::SurfaceError::Lost, wgpu::SurfaceError::Outdated] {
+            let (result, attempts, reconfigures) = acquire_from(vec![Err(lost), Ok(7)]);
+            assert_eq!(result.unwrap(), 7);
+            assert_eq!((attempts, reconfigures), (2, 1));
+        }
+    }
+
+    #[test]
+    fn surface_lost_twice_is_an_error() {
+        let (result, attempts, reconfigures) = acquire_from(vec![Err(wgpu::SurfaceError::Lost), Err(wgpu::SurfaceError::Lost)]);
+        assert!(matches!(result, Err(RenderError::SurfaceLost)));
+        assert_eq!((attempts, reconfigures), (2, 1));
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let (result, attempts, reconfigures) = acquire_from(vec![Err(wgpu::SurfaceError::Timeout)]);
+        assert!(matches!(result, Err(RenderError::SurfaceTimeout)));
+        assert_eq!((attempts, reconfigures), (1, 0));
+
+        let (result, attempts, reconfigures) = acquire_from(vec![Ok(3)]);
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!((attempts, reconfigures), (1, 0));
+    }
+}
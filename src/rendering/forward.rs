@@ -0,0 +1,530 @@
+use std::ops::Range;
+use std::sync::Arc;
+use glam::{Mat4, Vec3};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, BufferUsages, CommandEncoder, Device, PipelineLayout, Queue, RenderPass, RenderPipeline, TextureFormat};
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use crate::rendering::{BlendMode, FrameStats, InstanceData, InstancedDraw, LightData, LightType, Model, ShadowPass, ShadowSettings, Shader, Texture, Vertex};
+use crate::rendering::instancing::identity_model_bind_group;
+use crate::rendering::material::material_bind_group_layout;
+use crate::rendering::shader::{create_instanced_lit_shader, create_lit_shader};
+use crate::rendering::shadow::light_view_projection;
+use crate::utils::math::{Aabb, Frustum};
+
+/// Point lights the lit shader supports unless configured otherwise
+pub const DEFAULT_MAX_POINT_LIGHTS: usize = 16;
+
+/// Ambient light used until one is set
+const DEFAULT_AMBIENT: Vec3 = Vec3::splat(0.05);
+
+/// A model queued for the lit pass
+#[derive(Clone)]
+pub struct ModelDraw {
+    /// The model to draw
+    pub model: Arc<Model>,
+    /// Whether the model is rendered into the shadow map
+    pub casts_shadows: bool,
+    /// World space bounds the model is culled with, `None` is never culled
+    pub bounds: Option<Aabb>,
+}
+
+impl ModelDraw {
+    /// Whether any of the model may be inside `frustum`
+    pub fn is_visible(&self, frustum: &Frustum) -> bool {
+        self.bounds.map_or(true, |bounds| frustum.intersects_aabb(&bounds))
+    }
+}
+
+/// Fixed part of the light uniform, followed by the point light array
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct LightsHeader {
+    camera_position: [f32; 3],
+    point_light_count: u32,
+    ambient: [f32; 3],
+    has_directional: u32,
+    light_view_projection: [f32; 16],
+    shadow_bias: f32,
+    has_shadow: u32,
+    shadow_texel_size: f32,
+    _padding: u32,
+    directional: LightData,
+}
+
+/// GPU side of the forward lit pass: pipeline, camera, lights and depth buffer
+pub struct ForwardRenderer {
+    /// One pipeline per blend mode, in the order of `BlendMode::ALL`
+    pipelines: Vec<RenderPipeline>,
+    pipeline_layout: PipelineLayout,
+    shader: Shader,
+    /// Pipelines for instanced draws, taking model matrices from the instance buffer
+    instanced_pipelines: Vec<RenderPipeline>,
+    instanced_shader: Shader,
+    /// Bound as the model of instanced draws
+    identity_model_bind_group: BindGroup,
+    /// Instances of every instanced draw of the frame, `None` until there are any
+    instance_buffer: Option<Buffer>,
+    /// Instances `instance_buffer` has room for
+    instance_capacity: usize,
+    /// Part of `instance_buffer` holding each draw passed to the last `prepare_instances`
+    instance_ranges: Vec<Range<u32>>,
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    sample_count: u32,
+    camera_bind_group_layout: BindGroupLayout,
+    model_bind_group_layout: BindGroupLayout,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    lights_buffer: Buffer,
+    lights_bind_group_layout: BindGroupLayout,
+    lights_bind_group: BindGroup,
+    shadow_pass: ShadowPass,
+    depth_texture: Option<Texture>,
+    max_point_lights: usize,
+    ambient: Vec3,
+}
+
+impl ForwardRenderer {
+    /// Create the lit pipeline for the given color and depth target formats and sample count
+    pub fn new(device: &Device, format: TextureFormat, depth_format: TextureFormat, max_point_lights: usize, sample_count: u32) -> Self {
+        let max_point_lights = max_point_lights.max(1);
+        let shader = create_lit_shader(device, max_point_lights);
+
+        let camera_bind_group_layout = uniform_bind_group_layout(device, wgpu::ShaderStages::VERTEX, "Lit Camera Bind Group Layout");
+        // Same layout as the one models create their bind groups with
+        let model_bind_group_layout = uniform_bind_group_layout(device, wgpu::ShaderStages::VERTEX, "Lit Model Bind Group Layout");
+        let lights_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // Lights
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Shadow map
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Shadow comparison sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+            label: Some("Lit Lights Bind Group Layout"),
+        });
+
+        // Same layout as material bind groups
+        let material_bind_group_layout = material_bind_group_layout(device, "Lit Material Bind Group Layout");
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Lit Camera Buffer"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("Lit Camera Bind Group"),
+        });
+
+        let lights_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lit Lights Buffer"),
+            size: (std::mem::size_of::<LightsHeader>() + max_point_lights * std::mem::size_of::<LightData>()) as wgpu::BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shadow_pass = ShadowPass::new(device, &model_bind_group_layout);
+        let lights_bind_group = create_lights_bind_group(device, &lights_bind_group_layout, &lights_buffer, &shadow_pass);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Lit Pipeline Layout"),
+            bind_group_layouts: &[
+                &camera_bind_group_layout,
+                &model_bind_group_layout,
+                &material_bind_group_layout,
+                &lights_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipelines = create_pipelines(device, &pipeline_layout, &shader, &[Vertex::desc()], format, depth_format, sample_count);
+        let instanced_shader = create_instanced_lit_shader(device, max_point_lights);
+        let instanced_pipelines = create_pipelines(device, &pipeline_layout, &instanced_shader, &[Vertex::desc(), InstanceData::desc()], format, depth_format, sample_count);
+        let identity_model_bind_group = identity_model_bind_group(device, &model_bind_group_layout);
+
+        Self {
+            pipelines,
+            pipeline_layout,
+            shader,
+            instanced_pipelines,
+            instanced_shader,
+            identity_model_bind_group,
+            instance_buffer: None,
+            instance_capacity: 0,
+            instance_ranges: Vec::new(),
+            format,
+            depth_format,
+            sample_count,
+            camera_bind_group_layout,
+            model_bind_group_layout,
+            camera_buffer,
+            camera_bind_group,
+            lights_buffer,
+            lights_bind_group_layout,
+            lights_bind_group,
+            shadow_pass,
+            depth_texture: None,
+            max_point_lights,
+            ambient: DEFAULT_AMBIENT,
+        }
+    }
+
+    /// Maximum number of point lights uploaded per frame
+    pub fn max_point_lights(&self) -> usize {
+        self.max_point_lights
+    }
+
+    /// Set the light applied to every surface regardless of direction
+    pub fn set_ambient(&mut self, ambient: Vec3) {
+        self.ambient = ambient;
+    }
+
+    /// Light applied to every surface regardless of direction
+    pub fn ambient(&self) -> Vec3 {
+        self.ambient
+    }
+
+    /// Rebuild the pipeline for targets with `sample_count` samples
+    ///
+    /// The depth buffer is recreated with the new sample count by the next `prepare`.
+    pub fn set_sample_count(&mut self, device: &Device, sample_count: u32) {
+        if sample_count != self.sample_count {
+            self.pipelines = create_pipelines(device, &self.pipeline_layout, &self.shader, &[Vertex::desc()], self.format, self.depth_format, sample_count);
+            self.instanced_pipelines = create_pipelines(
+                device,
+                &self.pipeline_layout,
+                &self.instanced_shader,
+                &[Vertex::desc(), InstanceData::desc()],
+                self.format,
+                self.depth_format,
+                sample_count,
+            );
+            self.sample_count = sample_count;
+            self.depth_texture = None;
+        }
+    }
+
+    /// Camera bind group layout, for creating materials that are compatible with the lit pass
+    pub fn camera_bind_group_layout(&self) -> &BindGroupLayout {
+        &self.camera_bind_group_layout
+    }
+
+    /// Model bind group layout, for creating materials that are compatible with the lit pass
+    pub fn model_bind_group_layout(&self) -> &BindGroupLayout {
+        &self.model_bind_group_layout
+    }
+
+    /// Upload the camera and lights and make sure the depth buffer matches `size`
+    ///
+    /// The first directional light is used, further ones are ignored. When there are
+    /// more point lights than the shader supports, the ones closest to the camera are kept.
+    /// The shadow map is only kept while there is a directional light with `shadow` settings.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, view_projection: Mat4, camera_position: Vec3, lights: &[LightData], shadow: Option<ShadowSettings>, size: (u32, u32)) {
+        self.resize_depth(device, size);
+
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&view_projection.to_cols_array()));
+
+        let directional = lights.iter().find(|light| light.light_type == LightType::Directional as u32);
+        let mut point_lights: Vec<LightData> = lights.iter()
+            .filter(|light| light.light_type == LightType::Point as u32)
+            .copied()
+            .collect();
+        if point_lights.len() > self.max_point_lights {
+            point_lights.sort_by(|a, b| {
+                let a = Vec3::from(a.position).distance_squared(camera_position);
+                let b = Vec3::from(b.position).distance_squared(camera_position);
+                a.total_cmp(&b)
+            });
+            point_lights.truncate(self.max_point_lights);
+        }
+
+        let shadow = shadow.filter(|_| directional.is_some());
+        let light_view_projection = directional
+            .map(|light| light_view_projection(Vec3::from(light.direction), camera_position))
+            .unwrap_or(Mat4::IDENTITY);
+        if self.shadow_pass.update(device, queue, shadow.map(|shadow| shadow.resolution), light_view_projection) {
+            self.lights_bind_group = create_lights_bind_group(device, &self.lights_bind_group_layout, &self.lights_buffer, &self.shadow_pass);
+        }
+
+        let header = LightsHeader {
+            camera_position: camera_position.to_array(),
+            point_light_count: point_lights.len() as u32,
+            ambient: self.ambient.to_array(),
+            has_directional: directional.is_some() as u32,
+            light_view_projection: light_view_projection.to_cols_array(),
+            shadow_bias: shadow.map_or(0.0, |shadow| shadow.bias),
+            has_shadow: shadow.is_some() as u32,
+            shadow_texel_size: self.shadow_pass.texel_size(),
+            _padding: 0,
+            directional: directional.copied().unwrap_or_else(LightData::zeroed),
+        };
+        queue.write_buffer(&self.lights_buffer, 0, bytemuck::bytes_of(&header));
+        if !point_lights.is_empty() {
+            queue.write_buffer(&self.lights_buffer, std::mem::size_of::<LightsHeader>() as wgpu::BufferAddress, bytemuck::cast_slice(&point_lights));
+        }
+    }
+
+    /// Upload the instances of every instanced draw of the frame into one buffer
+    ///
+    /// The buffer grows to fit and is kept for the following frames. Call it once per
+    /// frame, before rendering the same `draws` in any view.
+    pub fn prepare_instances(&mut self, device: &Device, queue: &Queue, draws: &[InstancedDraw]) {
+        self.instance_ranges.clear();
+        let mut instances: Vec<InstanceData> = Vec::new();
+        for draw in draws {
+            let start = instances.len() as u32;
+            instances.extend_from_slice(&draw.instances);
+            self.instance_ranges.push(start..instances.len() as u32);
+        }
+        if instances.is_empty() {
+            return;
+        }
+
+        if instances.len() > self.instance_capacity {
+            let capacity = instances.len().next_power_of_two();
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Lit Instance Buffer"),
+                size: (capacity * std::mem::size_of::<InstanceData>()) as wgpu::BufferAddress,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            self.instance_capacity = capacity;
+        }
+        if let Some(buffer) = &self.instance_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+    }
+
+    /// Reallocate the depth buffer if it doesn't match `size`
+    pub fn resize_depth(&mut self, device: &Device, size: (u32, u32)) {
+        let needs_depth = match &self.depth_texture {
+            Some(depth) => depth.size != size,
+            None => true,
+        };
+        if needs_depth {
+            self.depth_texture = Some(Texture::create_depth_texture(device, size.0, size.1, self.depth_format, self.sample_count, "Lit Depth Texture"));
+        }
+    }
+
+    /// Depth buffer created by the last `prepare` or `resize_depth`
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth_texture.as_ref().map(|depth| &depth.view)
+    }
+
+    /// Render the shadow casters into the shadow map, skipped while there is none
+    ///
+    /// `instanced` are the draws passed to the last `prepare_instances`.
+    pub fn render_shadows(&self, encoder: &mut CommandEncoder, models: &[ModelDraw], instanced: &[InstancedDraw], stats: &mut FrameStats) {
+        self.shadow_pass.render(encoder, models, instanced, self.instance_buffer.as_ref(), &self.instance_ranges, stats);
+    }
+
+    /// Record draws for every mesh of the models into a render pass
+    ///
+    /// Models are shaded by the lit pipeline using their material's texture and normal map,
+    /// switching pipelines when the material's blend mode changes. Models created
+    /// without a device have no bind group and are skipped.
+    pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, models: &'a [ModelDraw], stats: &mut FrameStats) {
+        if models.is_empty() {
+            return;
+        }
+
+        let mut blend_mode = None;
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_bind_group(3, &self.lights_bind_group, &[]);
+
+        for ModelDraw { model, .. } in models {
+            let Some(model_bind_group) = &model.model_bind_group else {
+                continue;
+            };
+            render_pass.set_bind_group(1, model_bind_group, &[]);
+
+            for (mesh, material) in model.meshes() {
+                if blend_mode != Some(material.blend_mode) {
+                    render_pass.set_pipeline(&self.pipelines[material.blend_mode.index()]);
+                    blend_mode = Some(material.blend_mode);
+                }
+                render_pass.set_bind_group(2, &material.bind_group, &[]);
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.num_indices(), 0, 0..1);
+                stats.record_draw(mesh.num_indices() / 3);
+            }
+        }
+    }
+
+    /// Record one draw per instanced draw into a render pass
+    ///
+    /// `instanced` are the draws passed to the last `prepare_instances`, and `visible`
+    /// tells which of them to draw, e.g. the ones inside the view's frustum.
+    pub fn render_instanced<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        instanced: &'a [InstancedDraw],
+        visible: impl Fn(&InstancedDraw) -> bool,
+        stats: &mut FrameStats,
+    ) {
+        let Some(instance_buffer) = &self.instance_buffer else {
+            return;
+        };
+
+        let mut blend_mode = None;
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.identity_model_bind_group, &[]);
+        render_pass.set_bind_group(3, &self.lights_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+
+        for (draw, range) in instanced.iter().zip(&self.instance_ranges) {
+            if !visible(draw) {
+                stats.culled += range.len() as u32;
+                continue;
+            }
+            if blend_mode != Some(draw.material.blend_mode) {
+                render_pass.set_pipeline(&self.instanced_pipelines[draw.material.blend_mode.index()]);
+                blend_mode = Some(draw.material.blend_mode);
+            }
+            render_pass.set_bind_group(2, &draw.material.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, draw.mesh.vertex_buffer().slice(..));
+            render_pass.set_index_buffer(draw.mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..draw.mesh.num_indices(), 0, range.clone());
+            stats.record_instanced_draw(draw.mesh.num_indices() / 3, range.len() as u32);
+        }
+    }
+}
+
+/// Bind group layout with a single uniform buffer
+fn uniform_bind_group_layout(device: &Device, visibility: wgpu::ShaderStages, label: &str) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some(label),
+    })
+}
+
+/// Bind the light uniform and the current shadow map
+fn create_lights_bind_group(device: &Device, layout: &BindGroupLayout, lights_buffer: &Buffer, shadow_pass: &ShadowPass) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: lights_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(shadow_pass.view()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(shadow_pass.sampler()),
+            },
+        ],
+        label: Some("Lit Lights Bind Group"),
+    })
+}
+
+/// Lit pipelines of every blend mode, in the order of `BlendMode::ALL`
+fn create_pipelines(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    buffers: &[wgpu::VertexBufferLayout],
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    sample_count: u32,
+) -> Vec<RenderPipeline> {
+    BlendMode::ALL.into_iter()
+        .map(|blend_mode| create_pipeline(device, layout, shader, buffers, format, depth_format, sample_count, blend_mode))
+        .collect()
+}
+
+/// Lit pipeline for vertices laid out as `buffers` and color targets of `format` with `sample_count` samples
+#[allow(clippy::too_many_arguments)]
+fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    buffers: &[wgpu::VertexBufferLayout],
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    sample_count: u32,
+    blend_mode: BlendMode,
+) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("Lit Pipeline ({})", blend_mode.label())),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader.module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: blend_mode.blend_state(),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: blend_mode.writes_depth(),
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
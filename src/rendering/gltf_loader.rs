@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use image::{DynamicImage, GrayAlphaImage, GrayImage, RgbImage, RgbaImage};
+use wgpu::{BindGroupLayout, Device, Queue};
+use log::warn;
+use crate::rendering::{Material, Mesh, Model, ModelPrimitive, Renderer, Texture, TextureSampler, Transform, Vertex, compute_tangents};
+use crate::rendering::shader::create_unlit_shader;
+
+/// Errors that can occur while loading a model
+#[derive(Debug)]
+pub enum ModelError {
+    /// The file could not be read or parsed
+    Gltf(gltf::Error),
+    /// The file contains no triangle meshes
+    NoMeshes,
+    /// A primitive references a vertex that doesn't exist
+    InvalidIndex {
+        /// Name of the mesh
+        mesh: String,
+        /// The out-of-range index
+        index: u32,
+    },
+    /// Creating GPU resources failed
+    Gpu(anyhow::Error),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Gltf(e) => write!(f, "Failed to load glTF: {}", e),
+            ModelError::NoMeshes => write!(f, "Model contains no triangle meshes"),
+            ModelError::InvalidIndex { mesh, index } => {
+                write!(f, "Mesh '{}' references missing vertex {}", mesh, index)
+            }
+            ModelError::Gpu(e) => write!(f, "Failed to create model resources: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelError::Gltf(e) => Some(e),
+            ModelError::Gpu(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<gltf::Error> for ModelError {
+    fn from(e: gltf::Error) -> Self {
+        ModelError::Gltf(e)
+    }
+}
+
+impl Model {
+    /// Load a model from a .gltf or .glb file
+    ///
+    /// Every triangle primitive becomes its own mesh, with node transforms baked
+    /// into the vertices. Base color factors are baked into the vertex colors and
+    /// base color and normal textures are bound to the materials. Tangents are read
+    /// from the file, or generated where it has none. Skins and animations are
+    /// ignored with a warning.
+    pub fn load_gltf(path: &Path, renderer: &mut Renderer) -> Result<Model, ModelError> {
+        let (document, buffers, images) = gltf::import(path)?;
+        let file_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("glTF");
+
+        if document.skins().next().is_some() {
+            warn!("{}: skins are not supported, loading the bind pose", path.display());
+        }
+        if document.animations().next().is_some() {
+            warn!("{}: animations are not supported and were skipped", path.display());
+        }
+
+        let device = renderer.device();
+        let queue = renderer.queue();
+        let format = renderer.config().format;
+
+        let textures: Vec<Option<Texture>> = images.iter().enumerate()
+            .map(|(i, data)| load_image(device, queue, data, &format!("{} Texture {}", file_name, i), false))
+            .collect();
+
+        let shader = create_unlit_shader(device);
+        let camera_layout = uniform_bind_group_layout(device, "glTF Camera Bind Group Layout");
+        let model_layout = uniform_bind_group_layout(device, "glTF Model Bind Group Layout");
+
+        // Collect meshes with their world transforms
+        let mut meshes = Vec::new();
+        match document.default_scene().or_else(|| document.scenes().next()) {
+            Some(scene) => {
+                let mut stack: Vec<(gltf::Node, Mat4)> = scene.nodes().map(|node| (node, Mat4::IDENTITY)).collect();
+                while let Some((node, parent)) = stack.pop() {
+                    let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+                    if let Some(mesh) = node.mesh() {
+                        meshes.push((mesh, transform));
+                    }
+                    stack.extend(node.children().map(|child| (child, transform)));
+                }
+            }
+            None => meshes.extend(document.meshes().map(|mesh| (mesh, Mat4::IDENTITY))),
+        }
+
+        let mut materials: HashMap<Option<usize>, Arc<Material>> = HashMap::new();
+        let mut primitives = Vec::new();
+
+        for (mesh, transform) in meshes {
+            let tangent_matrix = Mat3::from_mat4(transform);
+            let normal_matrix = tangent_matrix.inverse().transpose();
+
+            for primitive in mesh.primitives() {
+                let mesh_name = format!("{}/{}", mesh.name().unwrap_or(file_name), primitive.index());
+
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    warn!("{}: {:?} primitives are not supported, skipping {}", path.display(), primitive.mode(), mesh_name);
+                    continue;
+                }
+
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let Some(positions) = reader.read_positions() else {
+                    warn!("{}: {} has no positions, skipping", path.display(), mesh_name);
+                    continue;
+                };
+                let positions: Vec<Vec3> = positions
+                    .map(|p| transform.transform_point3(Vec3::from(p)))
+                    .collect();
+
+                let normals: Option<Vec<Vec3>> = reader.read_normals().map(|normals| {
+                    normals.map(|n| (normal_matrix * Vec3::from(n)).normalize_or_zero()).collect()
+                });
+                let tangents: Option<Vec<Vec4>> = reader.read_tangents().map(|tangents| {
+                    tangents.map(|t| (tangent_matrix * Vec3::new(t[0], t[1], t[2])).normalize_or_zero().extend(t[3])).collect()
+                });
+                let uvs: Vec<Vec2> = reader.read_tex_coords(0)
+                    .map(|uvs| uvs.into_f32().map(Vec2::from).collect())
+                    .unwrap_or_default();
+
+                let gltf_material = primitive.material();
+                let base_color = Vec4::from(gltf_material.pbr_metallic_roughness().base_color_factor());
+                let colors: Vec<Vec4> = reader.read_colors(0)
+                    .map(|colors| colors.into_rgba_f32().map(|c| Vec4::from(c) * base_color).collect())
+                    .unwrap_or_default();
+
+                let indices: Vec<u32> = reader.read_indices()
+                    .map(|indices| indices.into_u32().collect())
+                    .unwrap_or_else(|| (0..positions.len() as u32).collect());
+
+                if let Some(&index) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+                    return Err(ModelError::InvalidIndex { mesh: mesh_name, index });
+                }
+
+                let vertex = |i: usize, normal: Vec3| Vertex::new(
+                    positions[i],
+                    normal,
+                    uvs.get(i).copied().unwrap_or(Vec2::ZERO),
+                    colors.get(i).copied().unwrap_or(base_color).to_array(),
+                );
+
+                let (mut vertices, indices) = match &normals {
+                    Some(normals) => {
+                        let vertices = (0..positions.len())
+                            .map(|i| vertex(i, normals.get(i).copied().unwrap_or(Vec3::Z)))
+                            .collect::<Vec<_>>();
+                        (vertices, indices)
+                    }
+                    None => {
+                        // Flat normals need a separate vertex per triangle corner
+                        let mut vertices = Vec::with_capacity(indices.len());
+                        for triangle in indices.chunks_exact(3) {
+                            let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+                            let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]).normalize_or_zero();
+                            vertices.extend([vertex(a, normal), vertex(b, normal), vertex(c, normal)]);
+                        }
+                        let indices = (0..vertices.len() as u32).collect();
+                        (vertices, indices)
+                    }
+                };
+
+                // The file's tangents only fit the vertices they came with, not flat shaded copies
+                match tangents.filter(|tangents| normals.is_some() && tangents.len() == vertices.len()) {
+                    Some(tangents) => {
+                        for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+                            vertex.tangent = tangent.to_array();
+                        }
+                    }
+                    None => compute_tangents(&mut vertices, &indices),
+                }
+
+                let gpu_mesh = Mesh::new(device, &mesh_name, &vertices, &indices).map_err(ModelError::Gpu)?;
+
+                let material = materials.entry(gltf_material.index()).or_insert_with(|| {
+                    let name = gltf_material.name().unwrap_or("glTF Material");
+                    let texture = gltf_material.pbr_metallic_roughness().base_color_texture()
+                        .and_then(|info| textures.get(info.texture().source().index())?.as_ref());
+                    // Normals are data rather than colors, so the image is uploaded again without sRGB
+                    let normal_map = gltf_material.normal_texture().and_then(|info| {
+                        let data = images.get(info.texture().source().index())?;
+                        load_image(device, queue, data, &format!("{} Normal Map", name), true)
+                    });
+                    Arc::new(Material::new_with_normal_map(
+                        device,
+                        name,
+                        &shader,
+                        &camera_layout,
+                        &model_layout,
+                        texture,
+                        normal_map.as_ref(),
+                        format,
+                    ))
+                }).clone();
+
+                primitives.push(ModelPrimitive { mesh: Arc::new(gpu_mesh), material });
+            }
+        }
+
+        if primitives.is_empty() {
+            return Err(ModelError::NoMeshes);
+        }
+
+        let first = primitives.remove(0);
+        let mut model = Model::new_with_device(device, first.mesh, first.material, Transform::default());
+        model.primitives = primitives;
+
+        Ok(model)
+    }
+}
+
+/// Bind group layout with a single vertex uniform buffer, as used for camera and model data
+fn uniform_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some(label),
+    })
+}
+
+/// Upload a decoded glTF image, or `None` with a warning if its format isn't supported
+///
+/// `linear` images hold data like normals and skip sRGB decoding.
+fn load_image(device: &Device, queue: &Queue, data: &gltf::image::Data, label: &str, linear: bool) -> Option<Texture> {
+    use gltf::image::Format;
+
+    let (width, height, pixels) = (data.width, data.height, data.pixels.clone());
+    let image = match data.format {
+        Format::R8G8B8A8 => RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8),
+        Format::R8G8B8 => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8 => GrayAlphaImage::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8 => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        other => {
+            warn!("{}: {:?} images are not supported", label, other);
+            return None;
+        }
+    };
+
+    let Some(image) = image else {
+        warn!("{}: image data doesn't match its size", label);
+        return None;
+    };
+
+    let texture = if linear {
+        Texture::linear_from_image(device, queue, &image, TextureSampler::default(), Some(label))
+    } else {
+        Texture::from_image(device, queue, &image, Some(label))
+    };
+    match texture {
+        Ok(texture) => Some(texture),
+        Err(e) => {
+            warn!("{}: {}", label, e);
+            None
+        }
+    }
+}
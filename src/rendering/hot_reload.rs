@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use log::warn;
+
+/// Time a file must go without changes before it's reported, editors often write twice
+pub const HOT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches asset files and reports the ones that changed on disk
+///
+/// The directories containing the files are watched rather than the files
+/// themselves, so files replaced by a rename (as many editors save) are still seen.
+pub struct AssetWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// Watched files, canonicalized
+    files: HashSet<PathBuf>,
+    /// Watched directories and the number of watched files in each
+    directories: HashMap<PathBuf, usize>,
+    /// Changed files and when they last changed
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl AssetWatcher {
+    /// Create a watcher that isn't watching anything yet
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        Ok(Self {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            files: HashSet::new(),
+            directories: HashMap::new(),
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Start watching a file
+    pub fn watch(&mut self, path: &Path) -> notify::Result<()> {
+        let path = fs::canonicalize(path)?;
+        if self.files.contains(&path) {
+            return Ok(());
+        }
+
+        let directory = path.parent().unwrap_or(&path).to_path_buf();
+        if !self.directories.contains_key(&directory) {
+            self.watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+        }
+        *self.directories.entry(directory).or_insert(0) += 1;
+        self.files.insert(path);
+        Ok(())
+    }
+
+    /// Stop watching a file
+    pub fn unwatch(&mut self, path: &Path) {
+        let Ok(path) = fs::canonicalize(path) else {
+            return;
+        };
+        if !self.files.remove(&path) {
+            return;
+        }
+        self.pending.remove(&path);
+
+        let directory = path.parent().unwrap_or(&path).to_path_buf();
+        if let Some(count) = self.directories.get_mut(&directory) {
+            *count -= 1;
+            if *count == 0 {
+                self.directories.remove(&directory);
+                let _ = self.watcher.unwatch(&directory);
+            }
+        }
+    }
+
+    /// Whether a file is being watched
+    pub fn is_watching(&self, path: &Path) -> bool {
+        fs::canonicalize(path).map_or(false, |path| self.files.contains(&path))
+    }
+
+    /// The watched files that changed and then stayed unchanged for `HOT_RELOAD_DEBOUNCE`
+    ///
+    /// Each change is reported once. The paths are canonicalized.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    for path in event.paths {
+                        if self.files.contains(&path) {
+                            self.pending.insert(path, now);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Asset watcher error: {}", e),
+            }
+        }
+
+        let settled: Vec<PathBuf> = self.pending.iter()
+            .filter(|(_, changed)| now.duration_since(**changed) >= HOT_RELOAD_DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &settled {
+            self.pending.remove(path);
+        }
+        settled
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use glam::Mat4;
+use wgpu::{BindGroup, BindGroupLayout, Device, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+use wgpu::util::DeviceExt;
+use bytemuck::{Pod, Zeroable};
+use crate::rendering::{Material, Mesh};
+use crate::rendering::model::ModelUniform;
+use crate::utils::math::{Aabb, Frustum};
+
+/// Per-instance data of an instanced draw, read by the vertex shader as attributes 5 to 8
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct InstanceData {
+    /// Model matrix of the instance, by columns
+    pub model: [[f32; 4]; 4],
+}
+
+impl InstanceData {
+    /// Instance drawn with the model matrix `model`
+    pub fn new(model: Mat4) -> Self {
+        Self { model: model.to_cols_array_2d() }
+    }
+
+    /// Get the vertex buffer layout for the instance buffer, stepped once per instance
+    pub fn desc() -> VertexBufferLayout<'static> {
+        const COLUMN: wgpu::BufferAddress = std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress;
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            // A matrix takes one attribute per column, after the `Vertex` attributes
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: COLUMN,
+                    shader_location: 6,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: COLUMN * 2,
+                    shader_location: 7,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: COLUMN * 3,
+                    shader_location: 8,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// A mesh queued for the lit pass once per instance, in a single draw call
+#[derive(Clone)]
+pub struct InstancedDraw {
+    /// The mesh to draw
+    pub mesh: Arc<Mesh>,
+    /// The material every instance is drawn with
+    pub material: Arc<Material>,
+    /// One entry per copy of the mesh
+    pub instances: Vec<InstanceData>,
+    /// Whether the instances are rendered into the shadow map
+    pub casts_shadows: bool,
+    /// World space bounds of all instances, `None` is never culled
+    ///
+    /// The instances are culled together, a view seeing any of them draws them all.
+    pub bounds: Option<Aabb>,
+}
+
+impl InstancedDraw {
+    /// Draw `mesh` with `material` once for each of `instances`, casting shadows
+    pub fn new(mesh: Arc<Mesh>, material: Arc<Material>, instances: Vec<InstanceData>) -> Self {
+        Self { mesh, material, instances, casts_shadows: true, bounds: None }
+    }
+
+    /// Whether any of the instances may be inside `frustum`
+    pub fn is_visible(&self, frustum: &Frustum) -> bool {
+        self.bounds.map_or(true, |bounds| frustum.intersects_aabb(&bounds))
+    }
+}
+
+/// Groups copies of the same mesh and material into instanced draws
+///
+/// Meshes and materials are matched by identity, i.e. the same `Arc`. Copies that
+/// differ in whether they cast shadows go into separate draws.
+#[derive(Default)]
+pub struct InstanceGroups {
+    draws: Vec<InstancedDraw>,
+    /// Index in `draws` of each mesh, material and shadow casting combination
+    lookup: HashMap<(*const Mesh, *const Material, bool), usize>,
+}
+
+impl InstanceGroups {
+    /// Create an empty set of groups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a copy of `mesh` drawn with `material` at `matrix`
+    ///
+    /// `bounds` are in world space, a copy without bounds keeps its whole group from being culled.
+    pub fn push(&mut self, mesh: &Arc<Mesh>, material: &Arc<Material>, casts_shadows: bool, matrix: Mat4, bounds: Option<Aabb>) {
+        let key = (Arc::as_ptr(mesh), Arc::as_ptr(material), casts_shadows);
+        let instance = InstanceData::new(matrix);
+        match self.lookup.get(&key) {
+            Some(&index) => {
+                let draw = &mut self.draws[index];
+                draw.instances.push(instance);
+                draw.bounds = match (draw.bounds, bounds) {
+                    (Some(group), Some(bounds)) => Some(group.union(&bounds)),
+                    _ => None,
+                };
+            }
+            None => {
+                self.lookup.insert(key, self.draws.len());
+                self.draws.push(InstancedDraw {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    instances: vec![instance],
+                    casts_shadows,
+                    bounds,
+                });
+            }
+        }
+    }
+
+    /// Number of groups, each drawn with one draw call
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    /// Whether nothing was added
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    /// The groups, in the order their first copy was added
+    pub fn into_draws(self) -> Vec<InstancedDraw> {
+        self.draws
+    }
+}
+
+/// Model bind group holding the identity matrix, bound by instanced draws whose matrices come from the instance buffer
+pub(crate) fn identity_model_bind_group(device: &Device, layout: &BindGroupLayout) -> BindGroup {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Identity Model Buffer"),
+        contents: bytemuck::bytes_of(&ModelUniform { model: Mat4::IDENTITY.to_cols_array_2d() }),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Identity Model Bind Group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::rendering::{DepthFormat, MeshData, Renderer};
+
+    /// A headless renderer and a cube mesh on it, `None` on machines without any adapter
+    async fn renderer_and_cube() -> Option<(Renderer, Arc<Mesh>)> {
+        let renderer = match Renderer::new_headless(4, 4, DepthFormat::default()).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                return None;
+            }
+        };
+        let mesh = Mesh::from_data(renderer.device(), &MeshData::cube(1.0)).unwrap();
+        Some((renderer, Arc::new(mesh)))
+    }
+
+    fn unit_bounds(position: Vec3) -> Option<Aabb> {
+        Some(Aabb::new(position - Vec3::splat(0.5), position + Vec3::splat(0.5)))
+    }
+
+    #[tokio::test]
+    async fn thousand_copies_make_one_instanced_draw() {
+        let Some((renderer, mesh)) = renderer_and_cube().await else {
+            return;
+        };
+        let material = Arc::new(renderer.create_material("Test", None));
+        let mut groups = InstanceGroups::new();
+        for i in 0..1000 {
+            let position = Vec3::new(i as f32, 0.0, 0.0);
+            groups.push(&mesh, &material, true, Mat4::from_translation(position), unit_bounds(position));
+        }
+
+        assert_eq!(groups.len(), 1);
+        let draws = groups.into_draws();
+        assert_eq!(draws[0].instances.len(), 1000);
+        assert_eq!(draws[0].instances[999], InstanceData::new(Mat4::from_translation(Vec3::new(999.0, 0.0, 0.0))));
+        assert_eq!(draws[0].bounds, Some(Aabb::new(Vec3::splat(-0.5), Vec3::new(999.5, 0.5, 0.5))));
+    }
+
+    #[tokio::test]
+    async fn copies_split_by_material_and_shadow_casting() {
+        let Some((renderer, mesh)) = renderer_and_cube().await else {
+            return;
+        };
+        let material = Arc::new(renderer.create_material("Test", None));
+        let other_material = Arc::new(renderer.create_material("Other", None));
+        let mut groups = InstanceGroups::new();
+        groups.push(&mesh, &material, true, Mat4::IDENTITY, unit_bounds(Vec3::ZERO));
+        groups.push(&mesh, &material, false, Mat4::IDENTITY, unit_bounds(Vec3::ZERO));
+        groups.push(&mesh, &other_material, true, Mat4::IDENTITY, unit_bounds(Vec3::ZERO));
+        // A copy without bounds keeps its group from being culled
+        groups.push(&mesh, &material, true, Mat4::IDENTITY, None);
+
+        assert_eq!(groups.len(), 3);
+        let draws = groups.into_draws();
+        assert_eq!(draws[0].instances.len(), 2);
+        assert_eq!(draws[0].bounds, None);
+        assert_eq!(draws[1].bounds, unit_bounds(Vec3::ZERO));
+    }
+}
@@ -2,6 +2,7 @@ use glam::{Vec3, Vec4};
 use wgpu::{Device, Queue, Buffer, BindGroup, BindGroupLayout, BufferUsages};
 use wgpu::util::DeviceExt;
 use bytemuck::{Pod, Zeroable};
+use serde::{Serialize, Deserialize};
 
 /// Base trait for all light types
 pub trait Light {
@@ -10,6 +11,58 @@ pub trait Light {
     
     /// Get the light type
     fn get_type(&self) -> LightType;
+    
+    /// Shadow settings, `None` for lights that don't cast shadows
+    fn shadow_settings(&self) -> Option<ShadowSettings> {
+        None
+    }
+}
+
+/// Shadow bias used by new directional lights
+pub const DEFAULT_SHADOW_BIAS: f32 = 0.005;
+
+/// Size of a shadow map
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShadowResolution {
+    Low,
+    #[default]
+    Medium,
+    High,
+    VeryHigh,
+}
+
+impl ShadowResolution {
+    /// All resolutions, from lowest to highest
+    pub const ALL: [ShadowResolution; 4] = [Self::Low, Self::Medium, Self::High, Self::VeryHigh];
+    
+    /// Width and height of the shadow map in texels
+    pub fn size(self) -> u32 {
+        match self {
+            Self::Low => 512,
+            Self::Medium => 1024,
+            Self::High => 2048,
+            Self::VeryHigh => 4096,
+        }
+    }
+    
+    /// Name shown in the editor
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::VeryHigh => "Very High",
+        }
+    }
+}
+
+/// How a light renders its shadow map
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// Size of the shadow map
+    pub resolution: ShadowResolution,
+    /// Depth offset that keeps surfaces from shadowing themselves
+    pub bias: f32,
 }
 
 /// Types of lights supported
@@ -36,6 +89,10 @@ pub struct DirectionalLight {
     pub direction: Vec3,
     pub color: Vec3,
     pub intensity: f32,
+    /// Size of the shadow map
+    pub shadow_resolution: ShadowResolution,
+    /// Depth offset that keeps surfaces from shadowing themselves
+    pub shadow_bias: f32,
 }
 
 impl DirectionalLight {
@@ -45,6 +102,8 @@ impl DirectionalLight {
             direction: direction.normalize(),
             color,
             intensity,
+            shadow_resolution: ShadowResolution::default(),
+            shadow_bias: DEFAULT_SHADOW_BIAS,
         }
     }
 }
@@ -64,6 +123,13 @@ impl Light for DirectionalLight {
     fn get_type(&self) -> LightType {
         LightType::Directional
     }
+    
+    fn shadow_settings(&self) -> Option<ShadowSettings> {
+        Some(ShadowSettings {
+            resolution: self.shadow_resolution,
+            bias: self.shadow_bias,
+        })
+    }
 }
 
 /// Point light (omni-directional)
@@ -138,6 +204,18 @@ impl LightManager {
         self.lights.len()
     }
     
+    /// Shader data of every light, in the order they were added
+    pub fn light_data(&self) -> Vec<LightData> {
+        self.lights.iter().map(|light| light.get_light_data()).collect()
+    }
+    
+    /// Shadow settings of the first directional light, the only one that is shaded
+    pub fn shadow_settings(&self) -> Option<ShadowSettings> {
+        self.lights.iter()
+            .find(|light| light.get_type() == LightType::Directional)
+            .and_then(|light| light.shadow_settings())
+    }
+    
     /// Initialize the light buffer and bind group
     pub fn initialize(&mut self, device: &Device) {
         // Create light buffer with space for max_lights
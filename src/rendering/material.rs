@@ -1,12 +1,46 @@
 use wgpu::{
-    Device, RenderPipeline, BindGroup, BindGroupLayout, Buffer,
+    util::DeviceExt, Device, RenderPipeline, BindGroup, BindGroupLayout, Buffer,
     ColorTargetState, BlendState, VertexState, FragmentState,
     PrimitiveState, MultisampleState, DepthStencilState, CompareFunction,
     StencilState, BindGroupLayoutEntry, ShaderStages, BindingType,
     TextureSampleType, SamplerBindingType, TextureViewDimension,
     TextureFormat,
 };
-use crate::rendering::{Shader, Texture, Vertex};
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+use crate::rendering::{BlendMode, Shader, Texture, Vertex, Rect, SpriteUv};
+
+/// Material settings read by the lit shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct MaterialUniform {
+    base_color: [f32; 4],
+    metallic: f32,
+    roughness: f32,
+    has_normal_map: u32,
+    _padding: u32,
+}
+
+/// Surface values of a lit material, applied on top of its textures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialParams {
+    /// Color multiplied with the base color texture
+    pub base_color: [f32; 4],
+    /// 0 for dielectrics, 1 for metals, which tint their highlights and lose their diffuse color
+    pub metallic: f32,
+    /// 0 for sharp highlights, 1 for broad dull ones
+    pub roughness: f32,
+}
+
+impl Default for MaterialParams {
+    fn default() -> Self {
+        Self {
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            metallic: 0.0,
+            roughness: 0.5,
+        }
+    }
+}
 
 /// A material for rendering objects
 pub struct Material {
@@ -20,6 +54,14 @@ pub struct Material {
     pub bind_group_layout: BindGroupLayout,
     /// The model bind group layout
     pub model_bind_group_layout: BindGroupLayout,
+    /// Normalized part of the texture to sample, the whole texture if `None`
+    pub uv_window: Option<Rect>,
+    /// Whether the lit pass perturbs normals with a normal map
+    pub has_normal_map: bool,
+    /// Base color, metallic and roughness the material was created with
+    pub params: MaterialParams,
+    /// How the lit pass blends the material with what's already drawn
+    pub blend_mode: BlendMode,
 }
 
 impl Material {
@@ -33,30 +75,41 @@ impl Material {
         texture: Option<&Texture>,
         format: TextureFormat,
     ) -> Self {
-        // Create material bind group layout
-        let material_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                // Texture
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Sampler
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-            label: Some(&format!("{} Material Bind Group Layout", name)),
-        });
+        Self::new_with_normal_map(device, name, shader, camera_bind_group_layout, model_bind_group_layout, texture, None, format)
+    }
+    
+    /// Create a new material with the given shader, texture and tangent space normal map
+    ///
+    /// The normal map should be linear, see `Texture::linear_from_image`, with
+    /// green pointing up the texture. Meshes without tangents ignore it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_normal_map(
+        device: &Device,
+        name: &str,
+        shader: &Shader,
+        camera_bind_group_layout: &BindGroupLayout,
+        model_bind_group_layout: &BindGroupLayout,
+        texture: Option<&Texture>,
+        normal_map: Option<&Texture>,
+        format: TextureFormat,
+    ) -> Self {
+        Self::new_with_params(device, name, shader, camera_bind_group_layout, model_bind_group_layout, texture, normal_map, MaterialParams::default(), format)
+    }
+
+    /// Create a new material with the given textures and surface values
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_params(
+        device: &Device,
+        name: &str,
+        shader: &Shader,
+        camera_bind_group_layout: &BindGroupLayout,
+        model_bind_group_layout: &BindGroupLayout,
+        texture: Option<&Texture>,
+        normal_map: Option<&Texture>,
+        params: MaterialParams,
+        format: TextureFormat,
+    ) -> Self {
+        let material_bind_group_layout = material_bind_group_layout(device, &format!("{} Material Bind Group Layout", name));
         
         // Create pipeline layout
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -122,6 +175,29 @@ impl Material {
             (&owned_white_texture.view, &owned_white_texture.sampler)
         };
         
+        // The shader doesn't sample the normal map without one, any texture fills the slot
+        let has_normal_map = normal_map.is_some();
+        let owned_normal_placeholder;
+        let normal_map = match normal_map {
+            Some(normal_map) => normal_map,
+            None => {
+                owned_normal_placeholder = create_white_texture(device);
+                &owned_normal_placeholder
+            }
+        };
+        let uniform = MaterialUniform {
+            base_color: params.base_color,
+            metallic: params.metallic.clamp(0.0, 1.0),
+            roughness: params.roughness.clamp(0.0, 1.0),
+            has_normal_map: has_normal_map as u32,
+            _padding: 0,
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Material Buffer", name)),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        
         // Create material bind group
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &material_bind_group_layout,
@@ -134,6 +210,18 @@ impl Material {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&normal_map.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&normal_map.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
             ],
             label: Some(&format!("{} Material Bind Group", name)),
         });
@@ -163,6 +251,30 @@ impl Material {
             bind_group,
             bind_group_layout: material_bind_group_layout,
             model_bind_group_layout: model_bind_group_layout_owned,
+            uv_window: None,
+            has_normal_map,
+            params,
+            blend_mode: BlendMode::AlphaBlend,
+        }
+    }
+    
+    /// Restrict the material to part of its texture, e.g. one sprite of an atlas
+    pub fn with_uv_window(mut self, uv_window: Option<Rect>) -> Self {
+        self.uv_window = uv_window;
+        self
+    }
+    
+    /// Blend the material with what's below it as `blend_mode` says, alpha blended by default
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+    
+    /// Map a texture coordinate across the whole mesh into the material's UV window
+    pub fn map_uv(&self, uv: Vec2) -> Vec2 {
+        match self.uv_window {
+            Some(window) => SpriteUv::from(window).map(uv),
+            None => uv,
         }
     }
     
@@ -192,6 +304,47 @@ impl Material {
     }
 }
 
+/// Layout of material bind groups: base color texture and sampler, normal map and
+/// sampler, then the material uniform
+pub fn material_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    let texture = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
+    let sampler = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        count: None,
+    };
+    
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            texture(0),
+            sampler(1),
+            texture(2),
+            sampler(3),
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+        label: Some(label),
+    })
+}
+
 /// Create a 1x1 white texture
 fn create_white_texture(device: &Device) -> Texture {
     let size = 1u32;
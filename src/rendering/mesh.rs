@@ -3,10 +3,16 @@ use wgpu::{
     VertexFormat, VertexStepMode,
 };
 use bytemuck::{Pod, Zeroable};
-use glam::{Vec2, Vec3};
+use std::f32::consts::{PI, TAU};
+use glam::{Vec2, Vec3, Vec4};
 use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use crate::utils::math::Aabb;
 
-/// A vertex with position, normal, texture coordinates, and color
+/// Vertex color of generated meshes
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// A vertex with position, normal, texture coordinates, color and tangent
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
@@ -14,19 +20,30 @@ pub struct Vertex {
     pub normal: [f32; 3],
     pub tex_coords: [f32; 2],
     pub color: [f32; 4],
+    /// Direction of increasing u, with the handedness in `w`, see `compute_tangents`
+    ///
+    /// Zero when the mesh has no tangents, normal maps are then ignored.
+    pub tangent: [f32; 4],
 }
 
 impl Vertex {
-    /// Create a new vertex
+    /// Create a new vertex without a tangent
     pub fn new(position: Vec3, normal: Vec3, tex_coords: Vec2, color: [f32; 4]) -> Self {
         Self {
             position: position.to_array(),
             normal: normal.to_array(),
             tex_coords: tex_coords.to_array(),
             color,
+            tangent: [0.0; 4],
         }
     }
 
+    /// Set the tangent, `w` being the handedness
+    pub fn with_tangent(mut self, tangent: Vec4) -> Self {
+        self.tangent = tangent.to_array();
+        self
+    }
+
     /// Get the vertex buffer layout for this vertex type
     pub fn desc() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
@@ -57,6 +74,12 @@ impl Vertex {
                     shader_location: 3,
                     format: VertexFormat::Float32x4,
                 },
+                // Tangent
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -67,6 +90,8 @@ pub struct Mesh {
     vertex_buffer: Buffer,
     index_buffer: Buffer,
     num_indices: u32,
+    /// Bounds of the vertex positions
+    bounds: Aabb,
     pub name: String,
 }
 
@@ -90,10 +115,14 @@ impl Mesh {
             usage: BufferUsages::INDEX,
         });
 
+        let bounds = Aabb::from_points(vertices.iter().map(|vertex| Vec3::from(vertex.position)))
+            .unwrap_or(Aabb::new(Vec3::ZERO, Vec3::ZERO));
+
         Ok(Self {
             vertex_buffer,
             index_buffer,
             num_indices: indices.len() as u32,
+            bounds,
             name: name.to_string(),
         })
     }
@@ -113,47 +142,423 @@ impl Mesh {
         self.num_indices
     }
 
+    /// Bounding box of the vertices in model space
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    /// Upload generated mesh data
+    pub fn from_data(device: &Device, data: &MeshData) -> Result<Self> {
+        Self::new(device, &data.name, &data.vertices, &data.indices)
+    }
+
     /// Create a quad mesh (rectangle)
     pub fn create_quad(device: &Device, width: f32, height: f32) -> Result<Self> {
+        Self::from_data(device, &MeshData::rectangle(width, height))
+    }
+
+    /// Create a cube mesh centered on the origin, with flat normals per face
+    pub fn create_cube(device: &Device, size: f32) -> Result<Self> {
+        Self::from_data(device, &MeshData::cube(size))
+    }
+}
+
+/// Fill in the tangent of every vertex from the texture coordinates of its triangles
+///
+/// Tangents point along increasing u and are averaged over the triangles sharing
+/// a vertex, then made perpendicular to its normal. The handedness in `w` makes
+/// the bitangent `cross(normal, tangent) * w` point up the texture, towards
+/// decreasing v, as normal maps with green pointing up expect. Vertices without
+/// usable texture coordinates get any tangent perpendicular to their normal.
+/// Triangles with indices past the end of `vertices` are skipped.
+pub fn compute_tangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut tangents = vec![Vec3::ZERO; vertices.len()];
+    let mut bitangents = vec![Vec3::ZERO; vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+        if a.max(b).max(c) >= vertices.len() {
+            continue;
+        }
+        let position = |i: usize| Vec3::from(vertices[i].position);
+        let uv = |i: usize| Vec2::from(vertices[i].tex_coords);
+
+        let (edge_1, edge_2) = (position(b) - position(a), position(c) - position(a));
+        let (delta_1, delta_2) = (uv(b) - uv(a), uv(c) - uv(a));
+        let determinant = delta_1.x * delta_2.y - delta_2.x * delta_1.y;
+        // All corners share a u or a v, the texture doesn't orient the triangle
+        if determinant.abs() <= f32::EPSILON {
+            continue;
+        }
+
+        // Solve edge = delta.x * dP/du + delta.y * dP/dv for both edges
+        let tangent = (edge_1 * delta_2.y - edge_2 * delta_1.y) / determinant;
+        let bitangent = (edge_2 * delta_1.x - edge_1 * delta_2.x) / determinant;
+        for i in [a, b, c] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+
+    for (i, vertex) in vertices.iter_mut().enumerate() {
+        let normal = Vec3::from(vertex.normal).normalize_or_zero();
+        let tangent = (tangents[i] - normal * normal.dot(tangents[i]))
+            .try_normalize()
+            .unwrap_or_else(|| if normal == Vec3::ZERO { Vec3::X } else { normal.any_orthonormal_vector() });
+        // `bitangents` run down the texture, along increasing v
+        let handedness = if normal.cross(tangent).dot(-bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+        vertex.tangent = tangent.extend(handedness).to_array();
+    }
+}
+
+/// Vertices and indices of a mesh, before it is uploaded to the GPU
+///
+/// The generators build white, counter-clockwise wound triangle lists centered on
+/// the origin, with unit normals, tangents and texture coordinates from the top left.
+#[derive(Debug, Clone)]
+pub struct MeshData {
+    pub name: String,
+    pub vertices: Vec<Vertex>,
+    /// Triangle list indices
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// A cube with sides of `size` and flat normals, each face textured with the whole image
+    pub fn cube(size: f32) -> Self {
+        let half = size / 2.0;
+        // Normal, then the axes spanning the face so that corners wind counter-clockwise
+        let faces = [
+            (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+            (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+            (Vec3::Z, Vec3::X, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+        ];
+
+        let mut vertices = Vec::with_capacity(24);
+        let mut indices = Vec::with_capacity(36);
+        for (normal, right, up) in faces {
+            let base = vertices.len() as u32;
+            for (u, v) in [(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)] {
+                let position = (normal + right * (u * 2.0 - 1.0) + up * (1.0 - v * 2.0)) * half;
+                vertices.push(Vertex::new(position, normal, Vec2::new(u, v), WHITE));
+            }
+            indices.extend([base, base + 1, base + 2, base + 2, base + 3, base]);
+        }
+
+        Self::named("Cube", vertices, indices)
+    }
+
+    /// A UV sphere around the Y axis
+    ///
+    /// `segments` (at least 3) divide it around the equator and `rings` (at least 2)
+    /// from pole to pole. Texture coordinates are spherical: u goes around the
+    /// equator, v from the top pole to the bottom one. The seam and the poles repeat
+    /// their vertices so each can have its own texture coordinates, giving
+    /// `(segments + 1) * (rings + 1)` vertices and `segments * (rings - 1) * 6`
+    /// indices, as the triangles touching a pole are single triangles.
+    pub fn sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+
+        let mut vertices = Vec::with_capacity(((segments + 1) * (rings + 1)) as usize);
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let (sin_phi, cos_phi) = (v * PI).sin_cos();
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+                let normal = Vec3::new(sin_phi * cos_theta, cos_phi, sin_phi * sin_theta);
+                vertices.push(Vertex::new(normal * radius, normal, Vec2::new(u, v), WHITE));
+            }
+        }
+
+        let mut indices = Vec::with_capacity((segments * (rings - 1) * 6) as usize);
+        let stride = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let top = ring * stride + segment;
+                let bottom = top + stride;
+                // Skip the triangles that collapse into a pole
+                if ring != 0 {
+                    indices.extend([top, top + 1, bottom]);
+                }
+                if ring != rings - 1 {
+                    indices.extend([top + 1, bottom + 1, bottom]);
+                }
+            }
+        }
+
+        Self::named("Sphere", vertices, indices)
+    }
+
+    /// A plane in XZ facing up, split into `subdivisions` x `subdivisions` quads
+    ///
+    /// Has `(subdivisions + 1)²` vertices and `subdivisions² * 6` indices. The image
+    /// is stretched over the whole plane, its top at -Z.
+    pub fn plane(width: f32, height: f32, subdivisions: u32) -> Self {
+        let cells = subdivisions.max(1);
+
+        let mut vertices = Vec::with_capacity(((cells + 1) * (cells + 1)) as usize);
+        for row in 0..=cells {
+            let v = row as f32 / cells as f32;
+            for column in 0..=cells {
+                let u = column as f32 / cells as f32;
+                let position = Vec3::new((u - 0.5) * width, 0.0, (v - 0.5) * height);
+                vertices.push(Vertex::new(position, Vec3::Y, Vec2::new(u, v), WHITE));
+            }
+        }
+
+        let mut indices = Vec::with_capacity((cells * cells * 6) as usize);
+        let stride = cells + 1;
+        for row in 0..cells {
+            for column in 0..cells {
+                let top = row * stride + column;
+                let bottom = top + stride;
+                indices.extend([top, bottom, top + 1, top + 1, bottom, bottom + 1]);
+            }
+        }
+
+        Self::named("Plane", vertices, indices)
+    }
+
+    /// A 1 x 1 quad in XY facing +Z
+    pub fn quad() -> Self {
+        Self::rectangle(1.0, 1.0)
+    }
+
+    /// A cylinder along the Y axis with capped ends
+    ///
+    /// `segments` (at least 3) divide it around its axis. The side has smooth
+    /// normals and wraps the image once around; the caps have flat normals and
+    /// their own vertices, with the image mapped onto the circle. Has
+    /// `(segments + 1) * 4 + 2` vertices and `segments * 12` indices.
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let half = height / 2.0;
+        let directions: Vec<(f32, Vec3)> = (0..=segments)
+            .map(|segment| {
+                let u = segment as f32 / segments as f32;
+                let (sin, cos) = (u * TAU).sin_cos();
+                (u, Vec3::new(cos, 0.0, sin))
+            })
+            .collect();
+
+        let mut vertices = Vec::with_capacity(((segments + 1) * 4 + 2) as usize);
+        let mut indices = Vec::with_capacity((segments * 12) as usize);
+
+        // Side, the top row followed by the bottom row
+        for (y, v) in [(half, 0.0), (-half, 1.0)] {
+            for &(u, direction) in &directions {
+                let position = direction * radius + Vec3::Y * y;
+                vertices.push(Vertex::new(position, direction, Vec2::new(u, v), WHITE));
+            }
+        }
+        let stride = segments + 1;
+        for segment in 0..segments {
+            let top = segment;
+            let bottom = top + stride;
+            indices.extend([top, top + 1, bottom, top + 1, bottom + 1, bottom]);
+        }
+
+        // Caps, each a fan around its center
+        for normal in [Vec3::Y, Vec3::NEG_Y] {
+            let center = vertices.len() as u32;
+            vertices.push(Vertex::new(normal * half, normal, Vec2::new(0.5, 0.5), WHITE));
+            for &(_, direction) in &directions {
+                let position = direction * radius + normal * half;
+                let uv = Vec2::new(0.5 + direction.x * 0.5, 0.5 + direction.z * 0.5);
+                vertices.push(Vertex::new(position, normal, uv, WHITE));
+            }
+            for segment in 0..segments {
+                let current = center + 1 + segment;
+                // Seen from outside, the top cap runs the ring the other way than the bottom one
+                if normal == Vec3::Y {
+                    indices.extend([center, current + 1, current]);
+                } else {
+                    indices.extend([center, current, current + 1]);
+                }
+            }
+        }
+
+        Self::named("Cylinder", vertices, indices)
+    }
+
+    /// A `width` x `height` quad in XY facing +Z
+    fn rectangle(width: f32, height: f32) -> Self {
         let half_width = width / 2.0;
         let half_height = height / 2.0;
 
-        let vertices = [
+        let vertices = vec![
             // Bottom left
-            Vertex::new(
-                Vec3::new(-half_width, -half_height, 0.0),
-                Vec3::new(0.0, 0.0, 1.0),
-                Vec2::new(0.0, 1.0),
-                [1.0, 1.0, 1.0, 1.0],
-            ),
+            Vertex::new(Vec3::new(-half_width, -half_height, 0.0), Vec3::Z, Vec2::new(0.0, 1.0), WHITE),
             // Bottom right
-            Vertex::new(
-                Vec3::new(half_width, -half_height, 0.0),
-                Vec3::new(0.0, 0.0, 1.0),
-                Vec2::new(1.0, 1.0),
-                [1.0, 1.0, 1.0, 1.0],
-            ),
+            Vertex::new(Vec3::new(half_width, -half_height, 0.0), Vec3::Z, Vec2::new(1.0, 1.0), WHITE),
             // Top right
-            Vertex::new(
-                Vec3::new(half_width, half_height, 0.0),
-                Vec3::new(0.0, 0.0, 1.0),
-                Vec2::new(1.0, 0.0),
-                [1.0, 1.0, 1.0, 1.0],
-            ),
+            Vertex::new(Vec3::new(half_width, half_height, 0.0), Vec3::Z, Vec2::new(1.0, 0.0), WHITE),
             // Top left
-            Vertex::new(
-                Vec3::new(-half_width, half_height, 0.0),
-                Vec3::new(0.0, 0.0, 1.0),
-                Vec2::new(0.0, 0.0),
-                [1.0, 1.0, 1.0, 1.0],
-            ),
+            Vertex::new(Vec3::new(-half_width, half_height, 0.0), Vec3::Z, Vec2::new(0.0, 0.0), WHITE),
         ];
 
-        let indices = [
+        let indices = vec![
             0, 1, 2, // First triangle
             2, 3, 0, // Second triangle
         ];
 
-        Self::new(device, "Quad", &vertices, &indices)
+        Self::named("Quad", vertices, indices)
+    }
+
+    fn named(name: &str, mut vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        compute_tangents(&mut vertices, &indices);
+        Self { name: name.to_string(), vertices, indices }
+    }
+}
+
+/// Built-in meshes the editor can create, stored in scenes by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrimitiveShape {
+    Cube,
+    Sphere,
+    Plane,
+    Quad,
+    Cylinder,
+}
+
+impl PrimitiveShape {
+    pub const ALL: [PrimitiveShape; 5] = [
+        PrimitiveShape::Cube,
+        PrimitiveShape::Sphere,
+        PrimitiveShape::Plane,
+        PrimitiveShape::Quad,
+        PrimitiveShape::Cylinder,
+    ];
+
+    /// Name of the shape, also the name of its mesh
+    pub fn name(self) -> &'static str {
+        match self {
+            PrimitiveShape::Cube => "Cube",
+            PrimitiveShape::Sphere => "Sphere",
+            PrimitiveShape::Plane => "Plane",
+            PrimitiveShape::Quad => "Quad",
+            PrimitiveShape::Cylinder => "Cylinder",
+        }
     }
-} 
\ No newline at end of file
+
+    /// The shape whose mesh has this name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| shape.name() == name)
+    }
+
+    /// The shape's mesh at its default size
+    ///
+    /// Cubes, spheres and cylinders are one unit across (cylinders two high),
+    /// planes are 10 x 10 units, as in most editors.
+    pub fn mesh_data(self) -> MeshData {
+        match self {
+            PrimitiveShape::Cube => MeshData::cube(1.0),
+            PrimitiveShape::Sphere => MeshData::sphere(0.5, 32, 16),
+            PrimitiveShape::Plane => MeshData::plane(10.0, 10.0, 10),
+            PrimitiveShape::Quad => MeshData::quad(),
+            PrimitiveShape::Cylinder => MeshData::cylinder(0.5, 2.0, 32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every generator at a small size, with the vertex and index counts they document
+    fn generated() -> Vec<(MeshData, usize, usize)> {
+        vec![
+            (MeshData::cube(2.0), 24, 36),
+            (MeshData::sphere(0.5, 8, 4), 9 * 5, 8 * 3 * 6),
+            (MeshData::plane(10.0, 10.0, 3), 4 * 4, 3 * 3 * 6),
+            (MeshData::quad(), 4, 6),
+            (MeshData::cylinder(0.5, 2.0, 6), 7 * 4 + 2, 6 * 12),
+        ]
+    }
+
+    #[test]
+    fn generators_have_the_documented_counts() {
+        for (mesh, vertices, indices) in generated() {
+            assert_eq!(mesh.vertices.len(), vertices, "{} vertices", mesh.name);
+            assert_eq!(mesh.indices.len(), indices, "{} indices", mesh.name);
+            assert!(mesh.indices.iter().all(|&index| (index as usize) < vertices), "{} index out of range", mesh.name);
+        }
+    }
+
+    #[test]
+    fn generators_have_unit_normals() {
+        for (mesh, _, _) in generated() {
+            for vertex in &mesh.vertices {
+                let length = Vec3::from(vertex.normal).length();
+                assert!((length - 1.0).abs() < 1e-5, "{} normal {:?}", mesh.name, vertex.normal);
+            }
+        }
+    }
+
+    #[test]
+    fn triangles_wind_counter_clockwise_seen_from_outside() {
+        for (mesh, _, _) in generated() {
+            for triangle in mesh.indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+                let [pa, pb, pc] = [a, b, c].map(|vertex| Vec3::from(vertex.position));
+                let face_normal = (pb - pa).cross(pc - pa);
+                let normal = Vec3::from(a.normal) + Vec3::from(b.normal) + Vec3::from(c.normal);
+                assert!(face_normal.dot(normal) > 0.0, "{} triangle {:?}", mesh.name, triangle);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_meshes_are_centered() {
+        let cube = MeshData::cube(2.0);
+        let bounds = Aabb::from_points(cube.vertices.iter().map(|vertex| Vec3::from(vertex.position))).unwrap();
+        assert_eq!(bounds, Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0)));
+
+        let sphere = MeshData::sphere(0.5, 8, 4);
+        for vertex in &sphere.vertices {
+            assert!((Vec3::from(vertex.position).length() - 0.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn tangents_are_orthonormal_to_normals() {
+        for (mesh, _, _) in generated() {
+            for vertex in &mesh.vertices {
+                let normal = Vec3::from(vertex.normal);
+                let tangent = Vec4::from(vertex.tangent);
+                assert!((tangent.truncate().length() - 1.0).abs() < 1e-4, "{} tangent {:?}", mesh.name, tangent);
+                assert!(tangent.truncate().dot(normal).abs() < 1e-4, "{} tangent {:?} normal {:?}", mesh.name, tangent, normal);
+                assert!(tangent.w == 1.0 || tangent.w == -1.0, "{} handedness {}", mesh.name, tangent.w);
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_follow_the_texture() {
+        // u increases to the right and v downwards, so the bitangent points up
+        let quad = MeshData::quad();
+        for vertex in &quad.vertices {
+            let tangent = Vec4::from(vertex.tangent);
+            assert!(tangent.truncate().abs_diff_eq(Vec3::X, 1e-5));
+            let bitangent = Vec3::from(vertex.normal).cross(tangent.truncate()) * tangent.w;
+            assert!(bitangent.abs_diff_eq(Vec3::Y, 1e-5));
+        }
+    }
+
+    #[test]
+    fn degenerate_texture_coordinates_still_get_a_tangent() {
+        let mut vertices = [Vec3::ZERO, Vec3::X, Vec3::Y]
+            .map(|position| Vertex::new(position, Vec3::Z, Vec2::ZERO, WHITE));
+        compute_tangents(&mut vertices, &[0, 1, 2, 0, 1, 7]);
+        for vertex in &vertices {
+            let tangent = Vec4::from(vertex.tangent).truncate();
+            assert!((tangent.length() - 1.0).abs() < 1e-5);
+            assert!(tangent.dot(Vec3::Z).abs() < 1e-5);
+        }
+    }
+}
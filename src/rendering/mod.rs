@@ -1,19 +1,63 @@
 // Rendering module
 mod renderer;
 mod camera;
+mod camera_controller;
 mod mesh;
 mod texture;
+mod texture_compression;
 mod shader;
 mod material;
 mod model;
 mod light;
+mod forward;
+mod shadow;
+mod sprite_batch;
+mod texture_atlas;
+mod text;
+mod render_target;
+mod stats;
+mod skybox;
+mod render_view;
+mod hot_reload;
+mod gltf_loader;
+mod obj_loader;
+mod capture;
+mod sorting_layer;
+mod debug_draw;
+mod error;
+mod instancing;
+mod blend;
 
 // Re-export for public use
-pub use renderer::Renderer;
-pub use camera::{Camera, OrthographicCamera};
-pub use mesh::{Mesh, Vertex};
-pub use texture::Texture;
-pub use shader::Shader;
-pub use material::Material;
-pub use model::{Model, Transform};
-pub use light::{Light, DirectionalLight, PointLight, LightManager};
\ No newline at end of file
+pub use renderer::{Renderer, RendererConfig, MSAA_SAMPLE_COUNTS};
+pub use error::RenderError;
+pub use render_target::RenderTarget;
+pub use capture::{CaptureError, read_texture, padded_bytes_per_row};
+pub use stats::{FrameStats, FrameStatsHistory, FRAME_STATS_HISTORY};
+pub use skybox::{ClearMode, SkyboxRenderer};
+pub use render_view::{RenderView, ViewportClearRenderer, viewport_pixels};
+pub use hot_reload::{AssetWatcher, HOT_RELOAD_DEBOUNCE};
+pub use camera::{Camera, OrthographicCamera, ScreenAnchor, screen_point_to_ray};
+pub use camera_controller::Camera2DController;
+pub use mesh::{Mesh, MeshData, PrimitiveShape, Vertex, compute_tangents};
+pub use texture::{Texture, TextureSampler, ColorSpace, DepthFormat, TextureFormatPreference};
+pub use texture_atlas::{TextureAtlas, Rect, SpriteUv};
+pub use text::{
+    Font, TextAlign, TextStyle, TextLayout, PositionedGlyph, TextPlacement, TextRenderer, GLYPH_ATLAS_SIZE,
+};
+pub use shader::{Shader, ShaderError};
+pub use material::{Material, MaterialParams};
+pub use model::{Model, ModelPrimitive, Transform};
+pub use gltf_loader::ModelError;
+pub use obj_loader::{MeshError, ObjMesh, ObjMaterial};
+pub use forward::{ForwardRenderer, ModelDraw, DEFAULT_MAX_POINT_LIGHTS};
+pub use instancing::{InstanceData, InstanceGroups, InstancedDraw};
+pub use shadow::{ShadowPass, SHADOW_DISTANCE};
+pub use light::{
+    Light, LightData, LightType, DirectionalLight, PointLight, LightManager,
+    ShadowResolution, ShadowSettings, DEFAULT_SHADOW_BIAS,
+};
+pub use blend::BlendMode;
+pub use sprite_batch::{SpriteBatch, SpriteInstance, SpriteDrawCall, SpriteRenderer, TextureHandle};
+pub use sorting_layer::{SortingLayers, DEFAULT_SORTING_LAYER};
+pub use debug_draw::{DebugDraw, DebugLineRenderer, DebugText, DEFAULT_DEBUG_LINE_CAPACITY};
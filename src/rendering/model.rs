@@ -1,9 +1,9 @@
-use std::{path::Path, sync::Arc};
-use anyhow::Result;
+use std::sync::Arc;
 use glam::{Mat4, Vec3, Quat};
 use wgpu::{Device, Queue, BindGroup, Buffer, BufferUsages};
 use wgpu::util::DeviceExt;
 use crate::rendering::{Mesh, Material};
+use crate::utils::math;
 use bytemuck::{Pod, Zeroable};
 
 /// A 3D model with mesh, material, and transform
@@ -14,12 +14,23 @@ pub struct Model {
     pub material: Arc<Material>,
     /// The transform for this model
     pub transform: ModelTransform,
+    /// Further meshes drawn with this model, e.g. the other primitives of a glTF mesh
+    pub primitives: Vec<ModelPrimitive>,
     /// The model bind group
     pub model_bind_group: Option<BindGroup>,
     /// The model uniform buffer
     model_buffer: Option<Buffer>,
 }
 
+/// A mesh and the material it is drawn with
+#[derive(Clone)]
+pub struct ModelPrimitive {
+    /// The mesh to draw
+    pub mesh: Arc<Mesh>,
+    /// The material to draw it with
+    pub material: Arc<Material>,
+}
+
 /// The transform of an object in 3D space
 #[derive(Debug, Clone)]
 pub struct ModelTransform {
@@ -67,11 +78,7 @@ impl ModelTransform {
     
     /// Get the model matrix for this transform
     pub fn model_matrix(&self) -> Mat4 {
-        let translation = Mat4::from_translation(self.position);
-        let rotation = Mat4::from_quat(self.rotation);
-        let scale = Mat4::from_scale(self.scale);
-        
-        translation * rotation * scale
+        math::compose(self.position, self.rotation, self.scale)
     }
 }
 
@@ -94,6 +101,7 @@ impl Model {
             mesh,
             material,
             transform,
+            primitives: Vec::new(),
             model_bind_group: None,
             model_buffer: None,
         }
@@ -147,11 +155,24 @@ impl Model {
             mesh,
             material,
             transform,
+            primitives: Vec::new(),
             model_bind_group: Some(model_bind_group),
             model_buffer: Some(model_buffer),
         }
     }
     
+    /// All meshes of this model with their materials, starting with `mesh`
+    pub fn meshes(&self) -> impl Iterator<Item = (&Arc<Mesh>, &Arc<Material>)> {
+        std::iter::once((&self.mesh, &self.material))
+            .chain(self.primitives.iter().map(|p| (&p.mesh, &p.material)))
+    }
+    
+    /// Bounding box of all meshes in model space
+    pub fn bounds(&self) -> math::Aabb {
+        self.primitives.iter()
+            .fold(self.mesh.bounds(), |bounds, primitive| bounds.union(&primitive.mesh.bounds()))
+    }
+    
     /// Update the transform of this model
     pub fn update_transform(&self, queue: &Queue, transform: &ModelTransform) {
         if let Some(buffer) = &self.model_buffer {
@@ -163,19 +184,7 @@ impl Model {
             queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[model_uniform]));
         }
     }
-    
-    /// Load a model from an OBJ file
-    pub async fn load_obj<P: AsRef<Path> + Clone>(
-        _device: &Device,
-        _queue: &Queue,
-        obj_path: P,
-    ) -> Result<Self> {
-        let _obj_file = std::fs::read_to_string(&obj_path)?;
-        
-        // TODO: Implement OBJ loading
-        
-        Err(anyhow::anyhow!("OBJ loading not implemented yet"))
-    }
+
 }
 
 // Re-export Transform as ModelTransform for compatibility
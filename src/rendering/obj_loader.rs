@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use glam::{Vec2, Vec3};
+use wgpu::Device;
+use log::warn;
+use crate::rendering::{
+    ColorSpace, Material, Mesh, Model, ModelPrimitive, Renderer, Texture, TextureSampler, Transform, Vertex,
+    compute_tangents,
+};
+
+/// Errors that can occur while loading an OBJ file
+#[derive(Debug)]
+pub enum MeshError {
+    /// The file could not be read
+    Io {
+        /// The file being read
+        path: PathBuf,
+        /// The underlying error
+        source: std::io::Error,
+    },
+    /// A line could not be parsed
+    Parse {
+        /// 1-based line number
+        line: usize,
+        /// What was wrong with the line
+        message: String,
+    },
+    /// A face references a vertex, normal or texture coordinate that doesn't exist
+    InvalidIndex {
+        /// 1-based line number
+        line: usize,
+        /// "position", "normal" or "texture coordinate"
+        kind: &'static str,
+        /// The index as written in the file
+        index: i64,
+    },
+    /// The file contains no faces
+    NoMeshes,
+    /// Creating GPU resources failed
+    Gpu(anyhow::Error),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io { path, source } => write!(f, "Failed to read {}: {}", path.display(), source),
+            MeshError::Parse { line, message } => write!(f, "Line {}: {}", line, message),
+            MeshError::InvalidIndex { line, kind, index } => {
+                write!(f, "Line {}: face references missing {} {}", line, kind, index)
+            }
+            MeshError::NoMeshes => write!(f, "File contains no faces"),
+            MeshError::Gpu(e) => write!(f, "Failed to create mesh: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MeshError::Io { source, .. } => Some(source),
+            MeshError::Gpu(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// A material from an .mtl file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    /// Name of the material
+    pub name: String,
+    /// Diffuse color (`Kd`) and opacity (`d`)
+    pub diffuse: [f32; 4],
+    /// Diffuse texture (`map_Kd`), relative paths resolved against the .mtl file
+    pub diffuse_texture: Option<PathBuf>,
+    /// Tangent space normal map (`norm`, `map_Bump` or `bump`), resolved like `diffuse_texture`
+    pub normal_texture: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: [1.0, 1.0, 1.0, 1.0],
+            diffuse_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+/// One mesh of an OBJ file, before it is uploaded to the GPU
+#[derive(Debug, Clone)]
+pub struct ObjMesh {
+    /// Object or group name, with the material name appended if the group uses several
+    pub name: String,
+    /// Deduplicated vertices, colored with the material's diffuse color
+    pub vertices: Vec<Vertex>,
+    /// Triangle list indices
+    pub indices: Vec<u32>,
+    /// The material the faces use
+    pub material: Option<ObjMaterial>,
+}
+
+/// Mesh being built while parsing
+struct MeshBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: Vec<Vertex>,
+    /// Whether the file gave a normal for each vertex
+    has_normal: Vec<bool>,
+    indices: Vec<u32>,
+    /// Vertex for each (position, normal, uv) index tuple
+    lookup: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+impl MeshBuilder {
+    fn new(name: &str, material: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            material,
+            vertices: Vec::new(),
+            has_normal: Vec::new(),
+            indices: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+
+    /// Fill in normals the file left out by averaging the faces around each vertex,
+    /// then generate tangents from the normals and texture coordinates
+    fn finish(mut self, materials: &HashMap<String, ObjMaterial>) -> ObjMesh {
+        if self.has_normal.iter().any(|has_normal| !has_normal) {
+            let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+            for triangle in self.indices.chunks_exact(3) {
+                let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+                let pa = Vec3::from(self.vertices[a].position);
+                let face_normal = (Vec3::from(self.vertices[b].position) - pa)
+                    .cross(Vec3::from(self.vertices[c].position) - pa);
+                for i in [a, b, c] {
+                    normals[i] += face_normal;
+                }
+            }
+            for (i, vertex) in self.vertices.iter_mut().enumerate() {
+                if !self.has_normal[i] {
+                    vertex.normal = normals[i].normalize_or_zero().to_array();
+                }
+            }
+        }
+        compute_tangents(&mut self.vertices, &self.indices);
+
+        let material = self.material.and_then(|name| {
+            let material = materials.get(&name).cloned();
+            if material.is_none() {
+                warn!("OBJ mesh '{}' uses unknown material '{}'", self.name, name);
+            }
+            material
+        });
+        if let Some(material) = &material {
+            for vertex in &mut self.vertices {
+                vertex.color = material.diffuse;
+            }
+        }
+
+        ObjMesh {
+            name: self.name,
+            vertices: self.vertices,
+            indices: self.indices,
+            material,
+        }
+    }
+}
+
+impl ObjMesh {
+    /// Parse an OBJ file and the .mtl files it references
+    ///
+    /// Polygons are triangulated as fans and vertices are shared between faces
+    /// that use the same position, normal and texture coordinate. A new mesh
+    /// starts at each object, group or material change. Missing .mtl files are
+    /// reported as warnings.
+    pub fn parse(path: &Path) -> Result<Vec<ObjMesh>, MeshError> {
+        let source = read(path)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+
+        let mut positions: Vec<Vec3> = Vec::new();
+        let mut normals: Vec<Vec3> = Vec::new();
+        let mut tex_coords: Vec<Vec2> = Vec::new();
+        let mut materials: HashMap<String, ObjMaterial> = HashMap::new();
+
+        let default_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("OBJ");
+        let mut group_name = default_name.to_string();
+        let mut current = MeshBuilder::new(&group_name, None);
+        let mut meshes = Vec::new();
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else { continue };
+            let args: Vec<&str> = tokens.collect();
+
+            match keyword {
+                "v" => positions.push(parse_vec3(&args, line_number)?),
+                "vn" => normals.push(parse_vec3(&args, line_number)?),
+                "vt" => {
+                    let u = parse_float(args.first(), line_number)?;
+                    let v = args.get(1).map_or(Ok(0.0), |v| parse_float(Some(v), line_number))?;
+                    // OBJ puts the origin at the bottom left, textures are sampled from the top left
+                    tex_coords.push(Vec2::new(u, 1.0 - v));
+                }
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(MeshError::Parse {
+                            line: line_number,
+                            message: format!("face needs at least 3 vertices, got {}", args.len()),
+                        });
+                    }
+
+                    let mut face = Vec::with_capacity(args.len());
+                    for arg in &args {
+                        let mut parts = arg.split('/');
+                        let position = resolve_index(parts.next(), positions.len(), line_number, "position")?
+                            .ok_or_else(|| MeshError::Parse {
+                                line: line_number,
+                                message: format!("face vertex '{}' has no position", arg),
+                            })?;
+                        let tex_coord = resolve_index(parts.next(), tex_coords.len(), line_number, "texture coordinate")?;
+                        let normal = resolve_index(parts.next(), normals.len(), line_number, "normal")?;
+
+                        let key = (position, normal, tex_coord);
+                        let index = match current.lookup.get(&key) {
+                            Some(&index) => index,
+                            None => {
+                                let index = current.vertices.len() as u32;
+                                current.vertices.push(Vertex::new(
+                                    positions[position],
+                                    normal.map_or(Vec3::ZERO, |n| normals[n]),
+                                    tex_coord.map_or(Vec2::ZERO, |t| tex_coords[t]),
+                                    [1.0, 1.0, 1.0, 1.0],
+                                ));
+                                current.has_normal.push(normal.is_some());
+                                current.lookup.insert(key, index);
+                                index
+                            }
+                        };
+                        face.push(index);
+                    }
+
+                    for i in 1..face.len() - 1 {
+                        current.indices.extend([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                "o" | "g" => {
+                    group_name = if args.is_empty() { default_name.to_string() } else { args.join(" ") };
+                    let material = current.material.clone();
+                    start_mesh(&mut meshes, &mut current, MeshBuilder::new(&group_name, material), &materials);
+                }
+                "usemtl" => {
+                    let material = args.join(" ");
+                    let name = format!("{}/{}", group_name, material);
+                    start_mesh(&mut meshes, &mut current, MeshBuilder::new(&name, Some(material)), &materials);
+                }
+                "mtllib" => {
+                    for file in &args {
+                        let mtl_path = directory.join(file);
+                        match parse_mtl(&mtl_path) {
+                            Ok(loaded) => materials.extend(loaded),
+                            Err(e) => warn!("{}: {}", path.display(), e),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let last = std::mem::replace(&mut current, MeshBuilder::new("", None));
+        if !last.indices.is_empty() {
+            meshes.push(last.finish(&materials));
+        }
+
+        Ok(meshes)
+    }
+}
+
+impl Mesh {
+    /// Load the meshes of a Wavefront OBJ file
+    ///
+    /// Material colors are baked into the vertex colors. Use `Model::load_obj`
+    /// to also bind the materials' textures.
+    pub fn load_obj(device: &Device, path: &Path) -> Result<Vec<Mesh>, MeshError> {
+        ObjMesh::parse(path)?
+            .iter()
+            .map(|mesh| Mesh::new(device, &mesh.name, &mesh.vertices, &mesh.indices).map_err(MeshError::Gpu))
+            .collect()
+    }
+}
+
+impl Model {
+    /// Load a model from a Wavefront OBJ file
+    ///
+    /// Every object or group becomes its own primitive. Each .mtl material is
+    /// created once, with its diffuse texture and normal map bound; textures that
+    /// fail to load are replaced by the missing texture. Faces without a material
+    /// share a plain white one.
+    pub fn load_obj(path: &Path, renderer: &mut Renderer) -> Result<Model, MeshError> {
+        let meshes = ObjMesh::parse(path)?;
+        let device = renderer.device();
+
+        let mut materials: HashMap<Option<String>, Arc<Material>> = HashMap::new();
+        let mut primitives = Vec::with_capacity(meshes.len());
+        for mesh in &meshes {
+            let gpu_mesh = Mesh::new(device, &mesh.name, &mesh.vertices, &mesh.indices).map_err(MeshError::Gpu)?;
+            let material = materials.entry(mesh.material.as_ref().map(|m| m.name.clone()))
+                .or_insert_with(|| Arc::new(match &mesh.material {
+                    Some(material) => {
+                        let texture = material.diffuse_texture.as_ref()
+                            .map(|path| load_texture(renderer, path, ColorSpace::Srgb));
+                        let normal_map = material.normal_texture.as_ref()
+                            .map(|path| load_texture(renderer, path, ColorSpace::Linear));
+                        renderer.create_normal_mapped_material(&material.name, texture.as_ref(), normal_map.as_ref())
+                    }
+                    None => renderer.create_material("OBJ Material", None),
+                }))
+                .clone();
+            primitives.push(ModelPrimitive { mesh: Arc::new(gpu_mesh), material });
+        }
+
+        if primitives.is_empty() {
+            return Err(MeshError::NoMeshes);
+        }
+
+        let first = primitives.remove(0);
+        let mut model = Model::new_with_device(renderer.device(), first.mesh, first.material, Transform::default());
+        model.primitives = primitives;
+
+        Ok(model)
+    }
+}
+
+/// Load a texture referenced by an .mtl file, falling back to the missing texture
+fn load_texture(renderer: &Renderer, path: &Path, color_space: ColorSpace) -> Texture {
+    Texture::load(renderer.device(), renderer.queue(), path, TextureSampler::default(), color_space, renderer.texture_format())
+        .unwrap_or_else(|e| {
+            warn!("Can't load texture {}: {}", path.display(), e);
+            Texture::missing(renderer.device(), renderer.queue())
+        })
+}
+
+fn read(path: &Path) -> Result<String, MeshError> {
+    fs::read_to_string(path).map_err(|source| MeshError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Finish the current mesh if it has faces and continue with `next`
+fn start_mesh(meshes: &mut Vec<ObjMesh>, current: &mut MeshBuilder, next: MeshBuilder, materials: &HashMap<String, ObjMaterial>) {
+    let finished = std::mem::replace(current, next);
+    if !finished.indices.is_empty() {
+        meshes.push(finished.finish(materials));
+    }
+}
+
+fn parse_float(token: Option<&&str>, line: usize) -> Result<f32, MeshError> {
+    let token = token.ok_or_else(|| MeshError::Parse {
+        line,
+        message: "missing number".to_string(),
+    })?;
+    token.parse().map_err(|_| MeshError::Parse {
+        line,
+        message: format!("'{}' is not a number", token),
+    })
+}
+
+fn parse_vec3(args: &[&str], line: usize) -> Result<Vec3, MeshError> {
+    Ok(Vec3::new(
+        parse_float(args.first(), line)?,
+        parse_float(args.get(1), line)?,
+        parse_float(args.get(2), line)?,
+    ))
+}
+
+/// Turn a 1-based (or negative, relative) OBJ index into a 0-based one
+///
+/// Returns `None` for an empty index, e.g. the texture coordinate in `1//1`.
+fn resolve_index(token: Option<&str>, count: usize, line: usize, kind: &'static str) -> Result<Option<usize>, MeshError> {
+    let token = match token {
+        Some(token) if !token.is_empty() => token,
+        _ => return Ok(None),
+    };
+
+    let index: i64 = token.parse().map_err(|_| MeshError::Parse {
+        line,
+        message: format!("'{}' is not a valid {} index", token, kind),
+    })?;
+
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(MeshError::InvalidIndex { line, kind, index });
+    }
+
+    Ok(Some(resolved as usize))
+}
+
+/// Parse the materials of an .mtl file
+fn parse_mtl(path: &Path) -> Result<HashMap<String, ObjMaterial>, MeshError> {
+    let source = read(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<ObjMaterial> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            current = Some(ObjMaterial::new(&args.join(" ")));
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else { continue };
+        match keyword {
+            "Kd" => {
+                let color = parse_vec3(&args, line_number)?;
+                material.diffuse = [color.x, color.y, color.z, material.diffuse[3]];
+            }
+            "d" => material.diffuse[3] = parse_float(args.first(), line_number)?,
+            "Tr" => material.diffuse[3] = 1.0 - parse_float(args.first(), line_number)?,
+            // Options like `-s 1 1 1` come before the file name
+            "map_Kd" => material.diffuse_texture = args.last().map(|file| directory.join(file)),
+            "norm" | "map_Bump" | "bump" => material.normal_texture = args.last().map(|file| directory.join(file)),
+            _ => {}
+        }
+    }
+
+    if let Some(material) = current {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::DepthFormat;
+
+    /// Write `files` into their own directory under the temporary directory
+    fn write_files(files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("mirage-obj-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        for (name, contents) in files {
+            fs::write(directory.join(name), contents).unwrap();
+        }
+        directory
+    }
+
+    const TRIANGLES: &str = "mtllib colors.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+g a
+usemtl red
+f 1 2 3
+g b
+usemtl blue
+f 1 3 2
+g c
+usemtl red
+f 3 2 1
+";
+
+    const MATERIALS: &str = "newmtl red
+Kd 1 0 0
+map_Kd missing.png
+newmtl blue
+Kd 0 0 1
+";
+
+    #[tokio::test]
+    async fn obj_model_shares_materials_between_groups() {
+        let mut renderer = match Renderer::new_headless(4, 4, DepthFormat::default()).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                return;
+            }
+        };
+        let directory = write_files(&[("triangles.obj", TRIANGLES), ("colors.mtl", MATERIALS), ("empty.obj", "v 0 0 0\n")]);
+
+        let model = Model::load_obj(&directory.join("triangles.obj"), &mut renderer).unwrap();
+        let meshes: Vec<_> = model.meshes().collect();
+        assert_eq!(meshes.len(), 3);
+        assert!(Arc::ptr_eq(meshes[0].1, meshes[2].1));
+        assert!(!Arc::ptr_eq(meshes[0].1, meshes[1].1));
+
+        assert!(matches!(Model::load_obj(&directory.join("empty.obj"), &mut renderer), Err(MeshError::NoMeshes)));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
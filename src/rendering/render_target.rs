@@ -0,0 +1,78 @@
+use wgpu::{
+    Device, Texture as WgpuTexture, TextureView, TextureDescriptor, TextureFormat,
+    TextureUsages, Extent3d, TextureDimension, TextureViewDescriptor,
+};
+
+/// An offscreen color target that can be rendered into and then sampled or read back
+pub struct RenderTarget {
+    /// The underlying WGPU texture
+    pub texture: WgpuTexture,
+    /// The texture view
+    pub view: TextureView,
+    /// The size of the target (width, height)
+    pub size: (u32, u32),
+    /// The format of the target
+    pub format: TextureFormat,
+    /// Samples per pixel, 1 unless the target is multisampled
+    pub sample_count: u32,
+}
+
+impl RenderTarget {
+    /// Create a new render target
+    ///
+    /// Returns `None` if either dimension is zero, since WGPU can't create empty textures.
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat, label: &str) -> Option<Self> {
+        let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC;
+        Self::create(device, width, height, format, 1, usage, label)
+    }
+
+    /// Create a target other textures are copied into, to be read back later
+    ///
+    /// Returns `None` if either dimension is zero.
+    pub fn copy_target(device: &Device, width: u32, height: u32, format: TextureFormat, label: &str) -> Option<Self> {
+        Self::create(device, width, height, format, 1, TextureUsages::COPY_DST | TextureUsages::COPY_SRC, label)
+    }
+
+    /// Create a multisampled target, which can only be rendered into and resolved
+    ///
+    /// Returns `None` if either dimension is zero.
+    pub fn multisampled(device: &Device, width: u32, height: u32, format: TextureFormat, sample_count: u32, label: &str) -> Option<Self> {
+        Self::create(device, width, height, format, sample_count, TextureUsages::RENDER_ATTACHMENT, label)
+    }
+
+    fn create(device: &Device, width: u32, height: u32, format: TextureFormat, sample_count: u32, usage: TextureUsages, label: &str) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Some(Self {
+            texture,
+            view,
+            size: (width, height),
+            format,
+            sample_count,
+        })
+    }
+
+    /// Whether the target already has the given size
+    pub fn matches_size(&self, width: u32, height: u32) -> bool {
+        self.size == (width, height)
+    }
+}
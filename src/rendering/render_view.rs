@@ -0,0 +1,238 @@
+use glam::{Mat4, Vec3};
+use wgpu::{
+    util::DeviceExt, BindGroup, Buffer, BufferUsages, Device, Queue,
+    PipelineLayout, RenderPass, RenderPipeline, TextureFormat,
+};
+use crate::rendering::{ClearMode, FrameStats, Shader};
+use crate::rendering::shader::create_viewport_clear_shader;
+
+/// One camera's view of a frame, see `Renderer::render_views`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderView {
+    /// View-projection models are drawn with
+    pub view_projection: Mat4,
+    /// Camera position, for specular highlights and picking point lights
+    pub position: Vec3,
+    /// View-projection sprites are drawn with; `None` positions them in pixels of the viewport
+    pub sprite_view_projection: Option<Mat4>,
+    /// Part of the target drawn to as normalized `[x, y, width, height]`, from the bottom left
+    pub viewport: [f32; 4],
+    /// How the viewport is cleared before drawing
+    pub clear_mode: ClearMode,
+    pub clear_color: wgpu::Color,
+}
+
+impl RenderView {
+    /// Viewport covering the whole target
+    pub const FULL_VIEWPORT: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+    /// A view of the whole target, cleared to black
+    pub fn new(view_projection: Mat4, position: Vec3) -> Self {
+        Self {
+            view_projection,
+            position,
+            sprite_view_projection: None,
+            viewport: Self::FULL_VIEWPORT,
+            clear_mode: ClearMode::SolidColor,
+            clear_color: wgpu::Color::BLACK,
+        }
+    }
+
+    /// Draw into part of the target, see `viewport`
+    pub fn with_viewport(mut self, viewport: [f32; 4]) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Set how the viewport is cleared
+    pub fn with_clear(mut self, clear_mode: ClearMode, clear_color: wgpu::Color) -> Self {
+        self.clear_mode = clear_mode;
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// Draw sprites with their own view-projection, e.g. the camera's
+    pub fn with_sprite_view_projection(mut self, view_projection: Option<Mat4>) -> Self {
+        self.sprite_view_projection = view_projection;
+        self
+    }
+
+    /// Viewport as `(x, y, width, height)` pixels from the top left of a target of `size`
+    ///
+    /// The viewport is clipped to the target. Returns `None` if nothing of it is left.
+    pub fn pixel_viewport(&self, size: (u32, u32)) -> Option<(u32, u32, u32, u32)> {
+        viewport_pixels(self.viewport, size)
+    }
+
+    /// Whether the viewport covers the whole target
+    pub fn covers_target(&self) -> bool {
+        let [x, y, width, height] = self.viewport;
+        x <= 0.0 && y <= 0.0 && x + width >= 1.0 && y + height >= 1.0
+    }
+}
+
+/// Normalized bottom-left `[x, y, width, height]` to clipped top-left pixels
+pub fn viewport_pixels(viewport: [f32; 4], size: (u32, u32)) -> Option<(u32, u32, u32, u32)> {
+    let [x, y, width, height] = viewport;
+    let (target_width, target_height) = (size.0 as f32, size.1 as f32);
+
+    let left = (x * target_width).round().clamp(0.0, target_width);
+    let right = ((x + width) * target_width).round().clamp(0.0, target_width);
+    // Flip to the top-left origin render passes use
+    let top = ((1.0 - y - height) * target_height).round().clamp(0.0, target_height);
+    let bottom = ((1.0 - y) * target_height).round().clamp(0.0, target_height);
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((left as u32, top as u32, (right - left) as u32, (bottom - top) as u32))
+}
+
+/// Clears the viewport of a render pass, which load operations can't do for part of a target
+///
+/// Draws a triangle at the far plane that writes depth without testing it, with
+/// the clear color or, for `ClearMode::DepthOnly`, without touching color.
+pub struct ViewportClearRenderer {
+    color_pipeline: RenderPipeline,
+    depth_pipeline: RenderPipeline,
+    pipeline_layout: PipelineLayout,
+    shader: Shader,
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    color_buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl ViewportClearRenderer {
+    /// Create the clear pipelines for the given color and depth target formats and sample count
+    pub fn new(device: &Device, format: TextureFormat, depth_format: TextureFormat, sample_count: u32) -> Self {
+        let shader = create_viewport_clear_shader(device);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Viewport Clear Bind Group Layout"),
+        });
+
+        let color_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Viewport Clear Color Buffer"),
+            contents: bytemuck::cast_slice(&[0.0f32; 4]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: color_buffer.as_entire_binding(),
+            }],
+            label: Some("Viewport Clear Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Viewport Clear Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let color_pipeline = create_pipeline(device, &pipeline_layout, &shader, format, depth_format, sample_count, wgpu::ColorWrites::ALL);
+        let depth_pipeline = create_pipeline(device, &pipeline_layout, &shader, format, depth_format, sample_count, wgpu::ColorWrites::empty());
+
+        Self {
+            color_pipeline,
+            depth_pipeline,
+            pipeline_layout,
+            shader,
+            format,
+            depth_format,
+            color_buffer,
+            bind_group,
+        }
+    }
+
+    /// Rebuild the pipelines for targets with `sample_count` samples
+    pub fn set_sample_count(&mut self, device: &Device, sample_count: u32) {
+        self.color_pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, self.depth_format, sample_count, wgpu::ColorWrites::ALL);
+        self.depth_pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, self.depth_format, sample_count, wgpu::ColorWrites::empty());
+    }
+
+    /// Upload the color the next submitted clear uses
+    pub fn prepare(&self, queue: &Queue, color: wgpu::Color) {
+        let color = [color.r as f32, color.g as f32, color.b as f32, color.a as f32];
+        queue.write_buffer(&self.color_buffer, 0, bytemuck::cast_slice(&color));
+    }
+
+    /// Clear the pass's viewport as `clear_mode` says, `ClearMode::Skybox` clears to the color
+    pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, clear_mode: ClearMode, stats: &mut FrameStats) {
+        let pipeline = match clear_mode {
+            ClearMode::Skybox | ClearMode::SolidColor => &self.color_pipeline,
+            ClearMode::DepthOnly => &self.depth_pipeline,
+            ClearMode::DontClear => return,
+        };
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        stats.record_draw(1);
+    }
+}
+
+/// Clear pipeline writing `write_mask` of color targets of `format` with `sample_count` samples
+fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    sample_count: u32,
+    write_mask: wgpu::ColorWrites,
+) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Viewport Clear Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader.module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
@@ -1,26 +1,148 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 use wgpu::{
     Device, Queue, Surface, SurfaceConfiguration, Adapter,
-    TextureFormat, TextureUsages, PresentMode, ShaderModule,
-    RenderPipeline, CommandEncoder, TextureView,
+    TextureFormat, TextureUsages, PresentMode,
+    RenderPipeline, CommandEncoder, TextureView, SurfaceTexture,
 };
 use winit::window::Window;
-use anyhow::Result;
-use log::{info, warn};
+use glam::{Mat4, Vec2, Vec3};
+use image::RgbaImage;
+use egui::mutex::RwLock;
+use crate::rendering::{
+    AssetWatcher, ClearMode, ForwardRenderer, FrameStats, FrameStatsHistory, LightData, LightManager, Material, Model, ModelDraw,
+    CaptureError, RenderTarget, RenderView, Shader, ShaderError, ShadowSettings, SkyboxRenderer, SpriteBatch, SpriteInstance, SpriteRenderer, Texture, TextureHandle,
+    TextPlacement, TextRenderer, TextStyle, TextureSampler, TextureFormatPreference, ColorSpace, DepthFormat, ViewportClearRenderer, Font, DEFAULT_MAX_POINT_LIGHTS, read_texture, SortingLayers, DEFAULT_SORTING_LAYER,
+    DebugDraw, DebugLineRenderer, RenderError, InstanceData, InstancedDraw, Mesh,
+};
+use crate::rendering::error::acquire_with_retry;
+use crate::rendering::shader::create_unlit_shader;
+use crate::assets::{AssetManager, MaterialAsset};
+use crate::utils::math::{Aabb, Frustum};
+use log::{error, info, warn};
+
+/// Target of a rendered frame, see `Renderer::capture_frame`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameTarget {
+    Surface,
+    Offscreen,
+    Headless,
+}
+
+/// Sample counts MSAA can use, other requests fall back to the nearest supported one
+pub const MSAA_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// Options for creating a `Renderer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RendererConfig {
+    /// Samples per pixel for antialiasing, 1 disables MSAA
+    pub msaa_samples: u32,
+    /// Wait for the display's vertical blank before presenting
+    pub vsync: bool,
+    /// Format sprite and material textures are stored in, see `Renderer::set_texture_format`
+    pub texture_format: TextureFormatPreference,
+    /// Format of the depth buffer
+    pub depth_format: DepthFormat,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 4,
+            vsync: true,
+            texture_format: TextureFormatPreference::Uncompressed,
+            depth_format: DepthFormat::Depth32Float,
+        }
+    }
+}
 
 /// Main renderer that handles the GPU device and rendering pipeline
 pub struct Renderer {
-    surface: Surface,
+    /// Window surface, `None` when rendering only offscreen (e.g. inside the editor)
+    surface: Option<Surface>,
     device: Arc<Device>,
     queue: Arc<Queue>,
     config: SurfaceConfiguration,
     size: (u32, u32),
+    /// Set while the window is minimized, frames are skipped until it's resized again
+    minimized: bool,
     clear_color: wgpu::Color,
+    /// How the frame is cleared before drawing
+    clear_mode: ClearMode,
+    skybox: SkyboxRenderer,
+    /// Clears viewports that don't cover the whole target
+    viewport_clear: ViewportClearRenderer,
+    sprite_batch: SpriteBatch,
+    /// Layers sprites are sorted into
+    sorting_layers: SortingLayers,
+    sprite_renderer: SpriteRenderer,
+    sprite_view_projection: Option<Mat4>,
+    text_renderer: TextRenderer,
+    /// Glyph atlas in `sprite_renderer`
+    text_texture: TextureHandle,
+    /// Screen-space sprites, drawn over every view in pixels from the top left
+    overlay_batch: SpriteBatch,
+    overlay_renderer: SpriteRenderer,
+    /// Glyph atlas in `overlay_renderer`
+    overlay_text_texture: TextureHandle,
+    forward_renderer: ForwardRenderer,
+    /// Debug lines and text queued for the current frame
+    debug_draw: DebugDraw,
+    debug_renderer: DebugLineRenderer,
+    /// Models queued for the current frame
+    models: Vec<ModelDraw>,
+    /// Instanced meshes queued for the current frame
+    instanced: Vec<InstancedDraw>,
+    /// Lights copied from the last `set_light_manager`
+    lights: Vec<LightData>,
+    /// Shadow settings of the directional light, if it has one
+    shadow_settings: Option<ShadowSettings>,
+    /// View-projection and position of the 3D camera
+    camera: (Mat4, Vec3),
+    /// The egui renderer offscreen targets are registered with
+    egui_renderer: Option<Arc<RwLock<egui_wgpu::Renderer>>>,
+    /// Offscreen target and the egui texture showing it
+    offscreen: Option<(RenderTarget, egui::TextureId)>,
+    /// Target every frame is drawn into by a renderer created with `new_headless`
+    headless_target: Option<RenderTarget>,
+    /// Copy of the last frame presented to the surface, for `capture_frame`
+    surface_frame: Option<RenderTarget>,
+    /// Where the last frame was drawn, `None` before the first one
+    last_frame: Option<FrameTarget>,
+    /// Samples per pixel actually in use
+    msaa_samples: u32,
+    /// Format of the depth buffer models and sprites are tested against
+    depth_format: DepthFormat,
+    /// Sample counts the adapter supports for the color and depth formats
+    supported_sample_counts: Vec<u32>,
+    /// Multisampled color target, resolved into the frame; `None` without MSAA
+    msaa_target: Option<RenderTarget>,
+    /// Stats of the frame being recorded
+    frame_stats: FrameStats,
+    /// Stats of the last finished frames
+    stats_history: FrameStatsHistory,
+    /// When the previous frame finished
+    last_frame_end: Option<Instant>,
+    /// Watches the asset files below for changes, `None` while hot reloading is off
+    asset_watcher: Option<AssetWatcher>,
+    /// Sprite textures loaded from files, with the sampler they were loaded with
+    sprite_texture_files: HashMap<PathBuf, (TextureHandle, TextureSampler)>,
+    /// File the sprite shader was loaded from, if not the built-in one
+    sprite_shader_file: Option<PathBuf>,
+    /// Checkerboard drawn for sprite materials whose texture can't be loaded, registered on first use
+    missing_sprite_texture: Option<TextureHandle>,
+    /// Format sprite and material textures loaded from files are stored in
+    texture_format: TextureFormatPreference,
+    /// Drawn by lit materials created without a texture, so they show their plain colors
+    white_texture: Texture,
 }
 
 impl Renderer {
     /// Create a new renderer with the given window
-    pub async fn new(window: &Window) -> Result<Self> {
+    pub async fn new(window: &Window, renderer_config: RendererConfig) -> Result<Self, RenderError> {
         let size = window.inner_size();
         let size = (size.width, size.height);
 
@@ -41,7 +163,7 @@ impl Renderer {
                 force_fallback_adapter: false,
             })
             .await
-            .ok_or_else(|| anyhow::anyhow!("Failed to find an appropriate adapter"))?;
+            .ok_or(RenderError::AdapterNotFound)?;
 
         // Log adapter info
         log_adapter_info(&adapter);
@@ -50,7 +172,10 @@ impl Renderer {
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
+                    // Lets MSAA use every sample count the adapter supports, not just the guaranteed ones,
+                    // and textures be stored compressed where the adapter can sample BC formats
+                    features: adapter.features()
+                        & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::TEXTURE_COMPRESSION_BC),
                     limits: if cfg!(target_arch = "wasm32") {
                         wgpu::Limits::downlevel_webgl2_defaults()
                     } else {
@@ -72,23 +197,155 @@ impl Renderer {
             .iter()
             .copied()
             .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+            .or_else(|| surface_caps.formats.first().copied())
+            .ok_or(RenderError::UnsupportedSurface)?;
+        let alpha_mode = *surface_caps.alpha_modes.first().ok_or(RenderError::UnsupportedSurface)?;
 
-        // Configure surface
+        // Configure surface, copyable if possible so frames can be captured
+        let usage = TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & TextureUsages::COPY_SRC);
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.0,
             height: size.1,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
+            present_mode: if renderer_config.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync },
+            alpha_mode,
             view_formats: vec![],
         };
-        surface.configure(&device, &config);
+        // A window created minimized is configured once it gets a size
+        if size.0 > 0 && size.1 > 0 {
+            surface.configure(&device, &config);
+        }
 
         info!("Renderer initialized with surface format: {:?}", surface_format);
 
-        Ok(Self {
+        let depth_format = renderer_config.depth_format;
+        let supported_sample_counts = supported_sample_counts(&adapter, &device, surface_format, depth_format);
+        let msaa_samples = select_sample_count(renderer_config.msaa_samples, &supported_sample_counts);
+        let mut renderer = Self::with_device(Some(surface), device, queue, config, depth_format, supported_sample_counts, msaa_samples);
+        renderer.texture_format = renderer_config.texture_format;
+        Ok(renderer)
+    }
+
+    /// Create a renderer without a window, drawing every frame into an offscreen target
+    ///
+    /// Frames go through the same draw paths as a windowed renderer and are read back
+    /// with `capture_frame` instead of being presented, e.g. for tests and CI. MSAA is
+    /// disabled so pixels are exact. Machines without a GPU need a software adapter,
+    /// e.g. Mesa's llvmpipe or WARP, which is requested when no hardware adapter is found.
+    pub async fn new_headless(width: u32, height: u32, depth_format: DepthFormat) -> Result<Self, RenderError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            dx12_shader_compiler: Default::default(),
+        });
+
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(RenderError::AdapterNotFound)?;
+        log_adapter_info(&adapter);
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: wgpu::Features::empty(),
+                    limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                    label: Some("Headless Device"),
+                },
+                None,
+            )
+            .await?;
+        let device = Arc::new(device);
+        let queue = Arc::new(queue);
+
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let max = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(RenderError::InvalidSize { width, height, max });
+        }
+        let config = wgpu::SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        info!("Headless renderer initialized at {}x{} with {:?} depth", width, height, depth_format);
+
+        let supported_sample_counts = supported_sample_counts(&adapter, &device, format, depth_format);
+        let mut renderer = Self::with_device(None, device, queue, config, depth_format, supported_sample_counts, 1);
+        renderer.headless_target = RenderTarget::new(&renderer.device, width, height, format, "Headless Render Target");
+        Ok(renderer)
+    }
+
+    /// Create an offscreen renderer that shares eframe's device
+    ///
+    /// Frames are only produced through `render_to_texture`, MSAA starts disabled.
+    pub fn from_render_state(render_state: &egui_wgpu::RenderState, depth_format: DepthFormat) -> Self {
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let config = wgpu::SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            format,
+            width: 0,
+            height: 0,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        let supported_sample_counts = supported_sample_counts(&render_state.adapter, &render_state.device, format, depth_format);
+
+        info!("Offscreen renderer initialized with format: {:?}", format);
+
+        let mut renderer = Self::with_device(None, render_state.device.clone(), render_state.queue.clone(), config, depth_format, supported_sample_counts, 1);
+        renderer.egui_renderer = Some(render_state.renderer.clone());
+        renderer
+    }
+
+    /// Create the draw passes for targets of `config`'s format and size
+    fn with_device(
+        surface: Option<Surface>,
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        config: SurfaceConfiguration,
+        depth_format: DepthFormat,
+        supported_sample_counts: Vec<u32>,
+        msaa_samples: u32,
+    ) -> Self {
+        let format = config.format;
+        let depth_texture_format = depth_format.texture_format();
+        let size = (config.width, config.height);
+        let text_renderer = TextRenderer::new(&device, &queue);
+        // Registered first in both sprite renderers, so it's `TextureHandle::WHITE`
+        let white_texture = Texture::white(&device, &queue);
+        let mut sprite_renderer = SpriteRenderer::new(&device, format, Some(depth_texture_format), msaa_samples);
+        sprite_renderer.register_texture(&device, &white_texture);
+        let text_texture = sprite_renderer.register_texture(&device, text_renderer.texture());
+        let mut overlay_renderer = SpriteRenderer::new(&device, format, None, msaa_samples);
+        overlay_renderer.register_texture(&device, &white_texture);
+        let overlay_text_texture = overlay_renderer.register_texture(&device, text_renderer.texture());
+        let forward_renderer = ForwardRenderer::new(&device, format, depth_texture_format, DEFAULT_MAX_POINT_LIGHTS, msaa_samples);
+        let skybox = SkyboxRenderer::new(&device, format, depth_texture_format, msaa_samples);
+        let viewport_clear = ViewportClearRenderer::new(&device, format, depth_texture_format, msaa_samples);
+        let debug_renderer = DebugLineRenderer::new(&device, format, depth_texture_format, msaa_samples);
+        let msaa_target = create_msaa_target(&device, size, format, msaa_samples);
+
+        Self {
+            // Only a window can be minimized
+            minimized: surface.is_some() && (size.0 == 0 || size.1 == 0),
             surface,
             device,
             queue,
@@ -100,7 +357,50 @@ impl Renderer {
                 b: 0.3,
                 a: 1.0,
             },
-        })
+            clear_mode: ClearMode::SolidColor,
+            skybox,
+            viewport_clear,
+            sprite_batch: SpriteBatch::new(),
+            sorting_layers: SortingLayers::new(),
+            sprite_renderer,
+            sprite_view_projection: None,
+            text_renderer,
+            text_texture,
+            overlay_batch: SpriteBatch::new(),
+            overlay_renderer,
+            overlay_text_texture,
+            forward_renderer,
+            debug_draw: DebugDraw::new(),
+            debug_renderer,
+            models: Vec::new(),
+            instanced: Vec::new(),
+            lights: Vec::new(),
+            shadow_settings: None,
+            camera: (Mat4::IDENTITY, Vec3::ZERO),
+            egui_renderer: None,
+            offscreen: None,
+            headless_target: None,
+            surface_frame: None,
+            last_frame: None,
+            msaa_samples,
+            depth_format,
+            supported_sample_counts,
+            msaa_target,
+            frame_stats: FrameStats::default(),
+            stats_history: FrameStatsHistory::new(),
+            last_frame_end: None,
+            asset_watcher: None,
+            sprite_texture_files: HashMap::new(),
+            sprite_shader_file: None,
+            missing_sprite_texture: None,
+            texture_format: TextureFormatPreference::Uncompressed,
+            white_texture,
+        }
+    }
+
+    /// Register offscreen targets with this egui renderer so they can be shown in the UI
+    pub fn set_egui_renderer(&mut self, egui_renderer: Arc<RwLock<egui_wgpu::Renderer>>) {
+        self.egui_renderer = Some(egui_renderer);
     }
 
     /// Get a reference to the device
@@ -113,6 +413,23 @@ impl Renderer {
         &self.queue
     }
 
+    /// Store the sprite and material textures loaded from now on in the format closest to `preference`
+    ///
+    /// Falls back to 8-bit RGBA where the device can't sample compressed formats.
+    pub fn set_texture_format(&mut self, preference: TextureFormatPreference) {
+        self.texture_format = preference;
+    }
+
+    /// Format preference for textures loaded from files
+    pub fn texture_format(&self) -> TextureFormatPreference {
+        self.texture_format
+    }
+
+    /// Create an asset manager that loads GPU assets with this renderer's device
+    pub fn create_asset_manager(&self) -> AssetManager {
+        AssetManager::with_gpu(self.device.clone(), self.queue.clone())
+    }
+
     /// Get the current surface configuration
     pub fn config(&self) -> &SurfaceConfiguration {
         &self.config
@@ -123,27 +440,144 @@ impl Renderer {
         self.size
     }
 
+    /// Stats of the last finished frame
+    pub fn stats(&self) -> FrameStats {
+        self.stats_history.latest()
+    }
+
+    /// Stats averaged over the last `FRAME_STATS_HISTORY` frames
+    pub fn average_stats(&self) -> FrameStats {
+        self.stats_history.average()
+    }
+
+    /// Samples per pixel in use, which may differ from the requested count
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /// Format of the depth buffer, see `RendererConfig::depth_format`
+    pub fn depth_format(&self) -> DepthFormat {
+        self.depth_format
+    }
+
+    /// Set the MSAA sample count, recreating the pipelines and the multisampled target
+    ///
+    /// Falls back to the nearest count the adapter supports, 1 disables MSAA.
+    pub fn set_msaa(&mut self, samples: u32) {
+        let msaa_samples = select_sample_count(samples, &self.supported_sample_counts);
+        if msaa_samples == self.msaa_samples {
+            return;
+        }
+
+        self.msaa_samples = msaa_samples;
+        self.sprite_renderer.set_sample_count(&self.device, msaa_samples);
+        self.overlay_renderer.set_sample_count(&self.device, msaa_samples);
+        self.forward_renderer.set_sample_count(&self.device, msaa_samples);
+        self.skybox.set_sample_count(&self.device, msaa_samples);
+        self.viewport_clear.set_sample_count(&self.device, msaa_samples);
+        self.debug_renderer.set_sample_count(&self.device, msaa_samples);
+        self.msaa_target = create_msaa_target(&self.device, self.size, self.config.format, msaa_samples);
+    }
+
     /// Set the clear color
     pub fn set_clear_color(&mut self, r: f64, g: f64, b: f64, a: f64) {
         self.clear_color = wgpu::Color { r, g, b, a };
     }
 
+    /// Set how the following frames are cleared
+    ///
+    /// Applies to `render_frame`, views passed to `render_views` carry their own.
+    pub fn set_clear_mode(&mut self, clear_mode: ClearMode) {
+        self.clear_mode = clear_mode;
+    }
+
+    pub fn clear_mode(&self) -> ClearMode {
+        self.clear_mode
+    }
+
+    /// Set the cubemap drawn by `ClearMode::Skybox`, `None` falls back to the clear color
+    pub fn set_skybox(&mut self, cubemap: Option<&Texture>) {
+        self.skybox.set_cubemap(&self.device, cubemap);
+    }
+
+    /// Load six cubemap faces, see `Texture::load_cubemap`, and use them as the skybox
+    pub fn load_skybox<P: AsRef<Path>>(&mut self, faces: [P; 6]) -> Result<(), RenderError> {
+        let cubemap = Texture::load_cubemap(&self.device, &self.queue, faces, Some("Skybox Cubemap"))
+            .map_err(RenderError::TextureLoad)?;
+        self.set_skybox(Some(&cubemap));
+        Ok(())
+    }
+
     /// Resize the renderer surface
-    pub fn resize(&mut self, width: u32, height: u32) {
-        if width > 0 && height > 0 {
-            self.size = (width, height);
-            self.config.width = width;
-            self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
-            info!("Renderer resized to {}x{}", width, height);
-        } else {
-            warn!("Attempted to resize renderer to invalid dimensions: {}x{}", width, height);
+    ///
+    /// A zero width or height means the window was minimized: frames are skipped
+    /// until it's resized to a real size again.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), RenderError> {
+        if width == 0 || height == 0 {
+            self.minimized = true;
+            return Ok(());
+        }
+        let max = self.device.limits().max_texture_dimension_2d;
+        if width > max || height > max {
+            return Err(RenderError::InvalidSize { width, height, max });
+        }
+
+        self.minimized = false;
+        self.size = (width, height);
+        self.config.width = width;
+        self.config.height = height;
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+        if self.headless_target.is_some() {
+            self.headless_target = RenderTarget::new(&self.device, width, height, self.config.format, "Headless Render Target");
+        }
+        self.msaa_target = create_msaa_target(&self.device, self.size, self.config.format, self.msaa_samples);
+        self.forward_renderer.resize_depth(&self.device, self.size);
+        info!("Renderer resized to {}x{}", width, height);
+        Ok(())
+    }
+
+    /// Whether the window is minimized, see `resize`
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Whether frames wait for the display's vertical blank before presenting
+    pub fn vsync(&self) -> bool {
+        self.config.present_mode == PresentMode::AutoVsync
+    }
+
+    /// Wait for the display's vertical blank before presenting, reconfiguring the surface
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let present_mode = if vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+        if present_mode == self.config.present_mode {
+            return;
         }
+        self.config.present_mode = present_mode;
+        if let (Some(surface), false) = (&self.surface, self.minimized) {
+            surface.configure(&self.device, &self.config);
+        }
+        info!("Vsync {}", if vsync { "enabled" } else { "disabled" });
+    }
+
+    /// Get the next frame of the window surface
+    ///
+    /// A lost or outdated surface is reconfigured and asked again before giving up.
+    fn current_texture(&self) -> Result<SurfaceTexture, RenderError> {
+        let surface = self.surface.as_ref().ok_or(RenderError::NoSurface)?;
+        acquire_with_retry(
+            || surface.get_current_texture(),
+            || {
+                warn!("Window surface lost or outdated, reconfiguring it");
+                surface.configure(&self.device, &self.config);
+            },
+        )
     }
 
     /// Begin a new render pass
-    pub fn begin_frame(&self) -> Result<(CommandEncoder, TextureView)> {
-        let output = self.surface.get_current_texture()?;
+    pub fn begin_frame(&self) -> Result<(CommandEncoder, TextureView), RenderError> {
+        let output = self.current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
         
         let encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -154,60 +588,884 @@ impl Renderer {
     }
 
     /// End the current frame and submit the command buffer
-    pub fn end_frame(&self, encoder: CommandEncoder) {
+    pub fn end_frame(&mut self, encoder: CommandEncoder) {
         self.queue.submit(std::iter::once(encoder.finish()));
+        self.frame_stats.submits += 1;
+        self.finish_frame_stats();
     }
     
-    /// Render a simple frame with the clear color
-    pub fn render_frame(&self) -> Result<()> {
-        // Get output texture
-        let output = self.surface.get_current_texture()?;
+    /// Render a frame cleared as set by `set_clear_mode`, with the queued models lit by the current lights, and any queued sprites
+    ///
+    /// A lost or outdated surface is reconfigured and the frame drawn anyway. While
+    /// the window is minimized the queued drawing is dropped without presenting.
+    pub fn render_frame(&mut self) -> Result<(), RenderError> {
+        self.flush_sprites().map(|_| ())
+    }
+
+    /// Use the lights of `light_manager` for the following frames
+    ///
+    /// The lights are copied, so this has to be called again after they change.
+    pub fn set_light_manager(&mut self, light_manager: &LightManager) {
+        self.lights = light_manager.light_data();
+        self.shadow_settings = light_manager.shadow_settings();
+    }
+
+    /// Set how many point lights are shaded per frame, rebuilding the lit pipeline
+    pub fn set_max_point_lights(&mut self, max_point_lights: usize) {
+        if max_point_lights != self.forward_renderer.max_point_lights() {
+            let ambient = self.forward_renderer.ambient();
+            self.forward_renderer = ForwardRenderer::new(&self.device, self.config.format, self.depth_format.texture_format(), max_point_lights, self.msaa_samples);
+            self.forward_renderer.set_ambient(ambient);
+        }
+    }
+
+    /// Set the light applied to every surface regardless of direction
+    pub fn set_ambient_light(&mut self, ambient: Vec3) {
+        self.forward_renderer.set_ambient(ambient);
+    }
+
+    /// Set the camera models are drawn with
+    ///
+    /// `position` is used for specular highlights and for picking the point
+    /// lights closest to the camera.
+    pub fn set_camera(&mut self, view_projection: Mat4, position: Vec3) {
+        self.camera = (view_projection, position);
+    }
+
+    /// Queue a model for the current frame
+    pub fn draw_model(&mut self, model: Arc<Model>, casts_shadows: bool) {
+        self.models.push(ModelDraw { model, casts_shadows, bounds: None });
+    }
+
+    /// Queue a model that is skipped in views whose frustum doesn't reach its world space `bounds`
+    ///
+    /// Culled models still cast shadows into the views that see them.
+    pub fn draw_model_with_bounds(&mut self, model: Arc<Model>, casts_shadows: bool, bounds: Aabb) {
+        self.models.push(ModelDraw { model, casts_shadows, bounds: Some(bounds) });
+    }
+
+    /// Queue `mesh` to be drawn with `material` once for each of `instances`, in a single draw call
+    ///
+    /// The instances cast shadows and are never culled, see `draw_instance_batch` for control over both.
+    pub fn draw_instanced(&mut self, mesh: Arc<Mesh>, material: Arc<Material>, instances: &[InstanceData]) {
+        self.draw_instance_batch(InstancedDraw::new(mesh, material, instances.to_vec()));
+    }
+
+    /// Queue an instanced draw, e.g. one of the groups built by `InstanceGroups`
+    pub fn draw_instance_batch(&mut self, draw: InstancedDraw) {
+        if !draw.instances.is_empty() {
+            self.instanced.push(draw);
+        }
+    }
+
+    /// Create a material that can be drawn by the lit pass
+    ///
+    /// Without a texture the material is drawn in its vertex colors alone.
+    pub fn create_material(&self, name: &str, texture: Option<&Texture>) -> Material {
+        self.create_normal_mapped_material(name, texture, None)
+    }
+
+    /// Create a material for the lit pass whose surfaces are perturbed by a normal map
+    ///
+    /// See `Material::new_with_normal_map` for what the normal map should hold.
+    pub fn create_normal_mapped_material(&self, name: &str, texture: Option<&Texture>, normal_map: Option<&Texture>) -> Material {
+        let shader = create_unlit_shader(&self.device);
+        Material::new_with_normal_map(
+            &self.device,
+            name,
+            &shader,
+            self.forward_renderer.camera_bind_group_layout(),
+            self.forward_renderer.model_bind_group_layout(),
+            Some(texture.unwrap_or(&self.white_texture)),
+            normal_map,
+            self.config.format,
+        )
+    }
+
+    /// Create a lit material from a material asset loaded from `material_path`
+    ///
+    /// A texture that can't be loaded is logged and replaced by `Texture::missing`,
+    /// so a broken reference shows up in the scene instead of failing.
+    pub fn create_material_from_asset(&self, name: &str, asset: &MaterialAsset, material_path: Option<&Path>) -> Material {
+        let texture = asset.texture_path(material_path).map(|path| {
+            Texture::load(&self.device, &self.queue, &path, TextureSampler::default(), ColorSpace::Srgb, self.texture_format).unwrap_or_else(|e| {
+                warn!("Material {} can't load texture {}: {}", name, path.display(), e);
+                Texture::missing(&self.device, &self.queue)
+            })
+        });
+        let shader = create_unlit_shader(&self.device);
+        Material::new_with_params(
+            &self.device,
+            name,
+            &shader,
+            self.forward_renderer.camera_bind_group_layout(),
+            self.forward_renderer.model_bind_group_layout(),
+            Some(texture.as_ref().unwrap_or(&self.white_texture)),
+            None,
+            asset.params(),
+            self.config.format,
+        )
+        .with_blend_mode(asset.blend_mode)
+    }
+
+    /// Sprite texture of a sprite material loaded from `material_path`, `None` if it has none
+    ///
+    /// Textures are loaded once like `load_sprite_texture`. One that can't be loaded
+    /// is logged and drawn as the checkerboard of `Texture::missing`.
+    pub fn material_sprite_texture(&mut self, asset: &MaterialAsset, material_path: Option<&Path>) -> Option<TextureHandle> {
+        let path = asset.texture_path(material_path)?;
+        match self.load_sprite_texture(&path, TextureSampler::default()) {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!("Sprite material can't load texture {}: {}", path.display(), e);
+                Some(self.missing_sprite_texture())
+            }
+        }
+    }
+
+    fn missing_sprite_texture(&mut self) -> TextureHandle {
+        if let Some(handle) = self.missing_sprite_texture {
+            return handle;
+        }
+        let texture = Texture::missing(&self.device, &self.queue);
+        let handle = self.sprite_renderer.register_texture(&self.device, &texture);
+        self.missing_sprite_texture = Some(handle);
+        handle
+    }
+
+    /// Make a texture available for sprite drawing
+    pub fn register_sprite_texture(&mut self, texture: &Texture) -> TextureHandle {
+        self.sprite_renderer.register_texture(&self.device, texture)
+    }
+
+    /// Load an image file as a sprite texture
+    ///
+    /// Loading a file again returns the handle it already has. With hot reloading on,
+    /// the texture is reloaded when the file changes and the handle keeps working.
+    pub fn load_sprite_texture(&mut self, path: impl AsRef<Path>, sampler: TextureSampler) -> Result<TextureHandle, RenderError> {
+        let path = canonicalize(path.as_ref())?;
+        if let Some((handle, _)) = self.sprite_texture_files.get(&path) {
+            return Ok(*handle);
+        }
+
+        let texture = load_texture(&self.device, &self.queue, &path, sampler, self.texture_format)?;
+        let handle = self.register_sprite_texture(&texture);
+        self.watch_asset(&path);
+        self.sprite_texture_files.insert(path, (handle, sampler));
+        Ok(handle)
+    }
+
+    /// Draw sprites with a WGSL shader file instead of the built-in sprite shader
+    ///
+    /// The shader needs `vs_main` and `fs_main` entry points and the built-in shader's
+    /// bindings. An invalid shader is returned as an error and the current one is kept.
+    pub fn load_sprite_shader(&mut self, path: impl AsRef<Path>) -> Result<(), RenderError> {
+        let path = canonicalize(path.as_ref())?;
+        let shader = load_sprite_shader(&self.device, &path)?;
+        self.sprite_renderer.set_shader(&self.device, shader);
+
+        if let Some(previous) = self.sprite_shader_file.take() {
+            if let Some(watcher) = &mut self.asset_watcher {
+                watcher.unwatch(&previous);
+            }
+        }
+        self.watch_asset(&path);
+        self.sprite_shader_file = Some(path);
+        Ok(())
+    }
+
+    /// Turn reloading of the textures and shaders loaded from files on or off
+    ///
+    /// While on, changed files are reloaded at the start of the next frame, once
+    /// they stopped changing for `HOT_RELOAD_DEBOUNCE`. Files that fail to load are
+    /// logged and the previous version is kept.
+    pub fn enable_hot_reload(&mut self, enabled: bool) {
+        if !enabled {
+            if self.asset_watcher.take().is_some() {
+                info!("Asset hot reloading disabled");
+            }
+            return;
+        }
+        if self.asset_watcher.is_some() {
+            return;
+        }
+
+        match AssetWatcher::new() {
+            Ok(watcher) => {
+                self.asset_watcher = Some(watcher);
+                let paths: Vec<PathBuf> = self.sprite_texture_files.keys()
+                    .chain(&self.sprite_shader_file)
+                    .cloned()
+                    .collect();
+                for path in paths {
+                    self.watch_asset(&path);
+                }
+                info!("Asset hot reloading enabled");
+            }
+            Err(e) => warn!("Failed to start asset hot reloading: {}", e),
+        }
+    }
+
+    /// Whether asset files are reloaded when they change
+    pub fn hot_reload_enabled(&self) -> bool {
+        self.asset_watcher.is_some()
+    }
+
+    /// Watch an asset file if hot reloading is on
+    fn watch_asset(&mut self, path: &Path) {
+        if let Some(watcher) = &mut self.asset_watcher {
+            if let Err(e) = watcher.watch(path) {
+                warn!("Failed to watch {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Reload the asset files that changed since the last frame
+    fn reload_changed_assets(&mut self) {
+        let changed = match &mut self.asset_watcher {
+            Some(watcher) => watcher.poll(),
+            None => return,
+        };
+
+        for path in changed {
+            if let Some(&(handle, sampler)) = self.sprite_texture_files.get(&path) {
+                match load_texture(&self.device, &self.queue, &path, sampler, self.texture_format) {
+                    Ok(texture) => {
+                        self.sprite_renderer.replace_texture(&self.device, handle, &texture);
+                        info!("Reloaded texture {}", path.display());
+                    }
+                    Err(e) => warn!("Failed to reload texture {}: {}", path.display(), e),
+                }
+            }
+
+            if self.sprite_shader_file.as_ref() == Some(&path) {
+                match load_sprite_shader(&self.device, &path) {
+                    Ok(shader) => {
+                        self.sprite_renderer.set_shader(&self.device, shader);
+                        info!("Reloaded shader {}", path.display());
+                    }
+                    Err(e) => error!("Failed to reload shader {}, keeping the previous one: {}", path.display(), e),
+                }
+            }
+        }
+    }
+
+    /// Set the view-projection used for sprites
+    ///
+    /// Without one, sprites are positioned in pixels with the origin at the
+    /// center of the surface and Y pointing up.
+    pub fn set_sprite_view_projection(&mut self, view_projection: Option<Mat4>) {
+        self.sprite_view_projection = view_projection;
+    }
+
+    /// Layers sprites are sorted into, back to front
+    pub fn sorting_layers(&self) -> &SortingLayers {
+        &self.sorting_layers
+    }
+
+    /// Change the sorting layers, e.g. to add a layer; applies from the next frame
+    pub fn sorting_layers_mut(&mut self) -> &mut SortingLayers {
+        &mut self.sorting_layers
+    }
+
+    /// Queue a sprite for the current frame
+    pub fn draw_sprite(&mut self, sprite: SpriteInstance) {
+        self.sprite_batch.push(sprite);
+    }
+
+    /// Queue text in world space, drawn with the sprites
+    ///
+    /// Glyphs are laid out in pixels, so with the default sprite view-projection
+    /// text at scale 1 is `style.font_size` pixels high.
+    pub fn draw_text(&mut self, text: &str, style: &TextStyle, placement: TextPlacement) {
+        for sprite in self.text_renderer.sprites(text, style, placement, self.text_texture) {
+            self.sprite_batch.push(sprite);
+        }
+    }
+
+    /// Queue text in screen space, drawn over every view
+    ///
+    /// `placement.position` is in pixels from the top left of the target.
+    pub fn draw_screen_text(&mut self, text: &str, style: &TextStyle, placement: TextPlacement) {
+        // The overlay's y axis points up from the top edge, see `draw_frame`
+        let position = Vec2::new(placement.position.x, -placement.position.y).round();
+        let placement = TextPlacement { position, ..placement };
+        for sprite in self.text_renderer.sprites(text, style, placement, self.overlay_text_texture) {
+            self.overlay_batch.push(sprite);
+        }
+    }
+
+    /// Debug lines and text for the current frame, cleared once it's drawn
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    /// Draw text with another font instead of the embedded one
+    pub fn set_font(&mut self, font: Font) {
+        self.text_renderer.set_font(font);
+    }
+
+    /// The font text is drawn with
+    pub fn font(&self) -> &Font {
+        self.text_renderer.font()
+    }
+
+    /// Draw all queued models and sprites and present the frame
+    ///
+    /// Sprites sharing a texture are drawn in a single call. Returns the number
+    /// of draw calls issued.
+    pub fn flush_sprites(&mut self) -> Result<usize, RenderError> {
+        let render_view = self.default_view();
+        self.present_views(&[render_view])
+    }
+
+    /// Draw the queued models and sprites once for each view and present the frame
+    ///
+    /// Views are drawn in order into their viewports, so later views end up on top,
+    /// e.g. a minimap over the main camera. Parts of the window no view covers are black.
+    pub fn render_views(&mut self, render_views: &[RenderView]) -> Result<(), RenderError> {
+        self.present_views(render_views).map(|_| ())
+    }
+
+    /// The single view set by `set_camera`, `set_clear_mode` and `set_sprite_view_projection`
+    fn default_view(&self) -> RenderView {
+        let (view_projection, position) = self.camera;
+        RenderView::new(view_projection, position)
+            .with_clear(self.clear_mode, self.clear_color)
+            .with_sprite_view_projection(self.sprite_view_projection)
+    }
+
+    fn present_views(&mut self, render_views: &[RenderView]) -> Result<usize, RenderError> {
+        if self.minimized {
+            self.discard_queued();
+            return Ok(0);
+        }
+        // Headless frames stay in their target until read back with `capture_frame`
+        if let Some(target) = self.headless_target.take() {
+            let draw_calls = self.draw_frame(&target.view, target.size, render_views);
+            self.headless_target = Some(target);
+            self.last_frame = Some(FrameTarget::Headless);
+            return Ok(draw_calls);
+        }
+        let output = self.current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
         
-        // Create command encoder
+        let draw_calls = self.draw_frame(&view, self.size, render_views);
+        self.keep_surface_frame(&output.texture);
+        
+        // Present the frame
+        output.present();
+        
+        Ok(draw_calls)
+    }
+
+    /// Copy a presented frame aside, as the surface texture is gone once presented
+    fn keep_surface_frame(&mut self, texture: &wgpu::Texture) {
+        if !self.config.usage.contains(TextureUsages::COPY_SRC) {
+            self.last_frame = None;
+            return;
+        }
+        
+        let (width, height) = self.size;
+        let needs_realloc = self.surface_frame.as_ref().map_or(true, |frame| !frame.matches_size(width, height));
+        if needs_realloc {
+            self.surface_frame = RenderTarget::copy_target(&self.device, width, height, self.config.format, "Surface Frame Copy");
+        }
+        let Some(frame) = &self.surface_frame else {
+            return;
+        };
+        
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
+            label: Some("Surface Frame Copy Encoder"),
         });
+        encoder.copy_texture_to_texture(
+            texture.as_image_copy(),
+            frame.texture.as_image_copy(),
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+        self.last_frame = Some(FrameTarget::Surface);
+    }
+    
+    /// Read the last rendered frame back into CPU memory
+    ///
+    /// Reads the window's last presented frame, or the last `render_to_texture`
+    /// frame, whichever was drawn last. Blocks until the GPU has finished it.
+    pub fn capture_frame(&mut self) -> Result<RgbaImage, CaptureError> {
+        let target = match self.last_frame {
+            None => return Err(CaptureError::NoFrame),
+            Some(FrameTarget::Surface) => self.surface_frame.as_ref().ok_or(CaptureError::NotReadable)?,
+            Some(FrameTarget::Offscreen) => self.offscreen.as_ref().map(|(target, _)| target).ok_or(CaptureError::NoFrame)?,
+            Some(FrameTarget::Headless) => self.headless_target.as_ref().ok_or(CaptureError::NoFrame)?,
+        };
+        read_texture(&self.device, &self.queue, &target.texture, target.size, target.format)
+    }
+    
+    /// Capture the last rendered frame and save it, the image format follows the extension
+    pub fn save_screenshot(&mut self, path: impl AsRef<Path>) -> Result<(), CaptureError> {
+        let image = self.capture_frame()?;
+        image.save(path)?;
+        Ok(())
+    }
+
+    /// Render the queued models and sprites into an offscreen target and return its egui texture
+    ///
+    /// The target is reallocated when the size changes. Returns `None` if either
+    /// dimension is zero (e.g. a collapsed panel) or no egui renderer is set; the
+    /// queued models and sprites are kept for the next frame in that case.
+    pub fn render_to_texture(&mut self, width: u32, height: u32) -> Option<egui::TextureId> {
+        let render_view = self.default_view();
+        self.render_views_to_texture(width, height, &[render_view])
+    }
+
+    /// Like `render_to_texture`, drawing the queued models and sprites once for each view
+    ///
+    /// Views are drawn in order into their viewports, as with `render_views`.
+    pub fn render_views_to_texture(&mut self, width: u32, height: u32, render_views: &[RenderView]) -> Option<egui::TextureId> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let egui_renderer = self.egui_renderer.clone()?;
+
+        let needs_realloc = match &self.offscreen {
+            Some((target, _)) => !target.matches_size(width, height),
+            None => true,
+        };
+
+        if needs_realloc {
+            let target = RenderTarget::new(&self.device, width, height, self.config.format, "Offscreen Render Target")?;
+            let mut egui_renderer = egui_renderer.write();
+            
+            // Keep the same texture id so the UI doesn't have to track reallocations
+            let texture_id = match self.offscreen.take() {
+                Some((_, texture_id)) => {
+                    egui_renderer.update_egui_texture_from_wgpu_texture(&self.device, &target.view, wgpu::FilterMode::Linear, texture_id);
+                    texture_id
+                }
+                None => egui_renderer.register_native_texture(&self.device, &target.view, wgpu::FilterMode::Linear),
+            };
+            
+            info!("Offscreen render target allocated at {}x{}", width, height);
+            self.offscreen = Some((target, texture_id));
+        }
+
+        let (target, texture_id) = self.offscreen.take()?;
+        self.draw_frame(&target.view, target.size, render_views);
+        self.offscreen = Some((target, texture_id));
+        self.last_frame = Some(FrameTarget::Offscreen);
+
+        Some(texture_id)
+    }
+
+    /// Draw the queued models and sprites into `view` once for each render view
+    ///
+    /// With MSAA all passes draw into the multisampled target, which is resolved into `view`.
+    /// Returns the number of sprite draw calls per render view.
+    fn draw_frame(&mut self, view: &TextureView, size: (u32, u32), render_views: &[RenderView]) -> usize {
+        self.reload_changed_assets();
+        
+        // Offscreen targets can have a different size than the surface
+        let needs_msaa_target = match &self.msaa_target {
+            Some(target) => !target.matches_size(size.0, size.1),
+            None => self.msaa_samples > 1,
+        };
+        if needs_msaa_target {
+            self.msaa_target = create_msaa_target(&self.device, size, self.config.format, self.msaa_samples);
+        }
+
+        for text in self.debug_draw.take_texts() {
+            let placement = TextPlacement::new(text.position).with_scale(Vec2::splat(text.scale));
+            self.draw_text(&text.text, &text.style, placement);
+        }
+        self.debug_renderer.prepare(&self.device, &self.queue, &self.debug_draw);
+        self.debug_draw.clear();
+        self.text_renderer.upload(&self.queue);
+        self.sprite_batch.default_layer = self.sorting_layers.resolve(DEFAULT_SORTING_LAYER);
+        self.sprite_batch.sort_by_y = self.sorting_layers.sort_by_y;
+        let draw_calls = self.sprite_renderer.prepare(&self.device, &self.queue, Mat4::IDENTITY, &mut self.sprite_batch);
+        // Pixels from the top left with y up, so glyphs aren't drawn upside down
+        let overlay_view_projection = Mat4::orthographic_rh(0.0, size.0 as f32, -(size.1 as f32), 0.0, -1.0, 1.0);
+        let overlay_draw_calls = self.overlay_renderer.prepare(&self.device, &self.queue, overlay_view_projection, &mut self.overlay_batch);
+        let models = std::mem::take(&mut self.models);
+        let instanced = std::mem::take(&mut self.instanced);
+        self.forward_renderer.prepare_instances(&self.device, &self.queue, &instanced);
         
-        // Create render pass
-        {
-            let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Simple Render Pass"),
+        let (color_view, resolve_target) = match &self.msaa_target {
+            Some(target) => (&target.view, Some(view)),
+            None => (view, None),
+        };
+        
+        // A view covering the whole target clears it with load operations. Otherwise the
+        // target starts out black, and each view clears its own viewport by drawing.
+        let mut first_pass = true;
+        for render_view in render_views {
+            let Some((x, y, width, height)) = render_view.pixel_viewport(size) else {
+                continue;
+            };
+            let clear_mode = render_view.clear_mode;
+            let (color_load, depth_load, clear_viewport) = match (first_pass, render_view.covers_target()) {
+                (true, true) => (clear_mode.color_load(render_view.clear_color), clear_mode.depth_load(), false),
+                (true, false) => (wgpu::LoadOp::Clear(wgpu::Color::BLACK), wgpu::LoadOp::Clear(1.0), true),
+                (false, _) => (wgpu::LoadOp::Load, wgpu::LoadOp::Load, true),
+            };
+            first_pass = false;
+            
+            let sprite_view_projection = render_view.sprite_view_projection.unwrap_or_else(|| {
+                let half_width = width as f32 / 2.0;
+                let half_height = height as f32 / 2.0;
+                Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, -1.0, 1.0)
+            });
+            self.sprite_renderer.set_view_projection(&self.queue, sprite_view_projection);
+            self.debug_renderer.set_view_projection(&self.queue, sprite_view_projection);
+            self.forward_renderer.prepare(&self.device, &self.queue, render_view.view_projection, render_view.position, &self.lights, self.shadow_settings, size);
+            
+            let draw_skybox = clear_mode == ClearMode::Skybox && self.skybox.has_cubemap();
+            if draw_skybox {
+                self.skybox.prepare(&self.queue, render_view.view_projection, render_view.position);
+            } else if clear_viewport {
+                self.viewport_clear.prepare(&self.queue, render_view.clear_color);
+            }
+            
+            // Views are submitted one by one, so each sees its own camera uniforms
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+            
+            self.forward_renderer.render_shadows(&mut encoder, &models, &instanced, &mut self.frame_stats);
+            
+            let frustum = Frustum::from_view_projection(render_view.view_projection);
+            let visible_models: Vec<ModelDraw> = models.iter()
+                .filter(|draw| draw.is_visible(&frustum))
+                .cloned()
+                .collect();
+            self.frame_stats.culled += (models.len() - visible_models.len()) as u32;
+            
+            // Skybox, lit models, sprites and depth tested debug lines; the other lines go on top without depth testing
+            {
+                let depth_view = self.forward_renderer.depth_view()
+                    .expect("depth texture is created by prepare");
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Lit Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: color_load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: depth_load,
+                            store: true,
+                        }),
+                        stencil_ops: None,
+                    }),
+                });
+                set_viewport(&mut render_pass, (x, y, width, height));
+                
+                if draw_skybox {
+                    self.skybox.render(&mut render_pass, &mut self.frame_stats);
+                } else if clear_viewport {
+                    self.viewport_clear.render(&mut render_pass, clear_mode, &mut self.frame_stats);
+                }
+                self.forward_renderer.render(&mut render_pass, &visible_models, &mut self.frame_stats);
+                self.forward_renderer.render_instanced(&mut render_pass, &instanced, |draw| draw.is_visible(&frustum), &mut self.frame_stats);
+                // Sprites seen through the camera share its depth, ones placed in pixels stay on top
+                if render_view.sprite_view_projection.is_some() {
+                    self.sprite_renderer.render_depth_tested(&mut render_pass, &draw_calls, &mut self.frame_stats);
+                } else {
+                    self.sprite_renderer.render(&mut render_pass, &draw_calls, &mut self.frame_stats);
+                }
+                self.debug_renderer.render_depth_tested(&mut render_pass, &mut self.frame_stats);
+            }
+            
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("On Top Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                set_viewport(&mut render_pass, (x, y, width, height));
+                
+                self.debug_renderer.render_on_top(&mut render_pass, &mut self.frame_stats);
+            }
+            
+            self.queue.submit(std::iter::once(encoder.finish()));
+            self.frame_stats.submits += 1;
+        }
+        
+        // Without any visible view the target would keep undefined contents
+        if first_pass {
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Clear Encoder"),
+            });
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Clear Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: color_view,
+                    resolve_target,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                         store: true,
                     },
                 })],
                 depth_stencil_attachment: None,
             });
-            // Render pass automatically dropped here
+            self.queue.submit(std::iter::once(encoder.finish()));
+            self.frame_stats.submits += 1;
         }
         
-        // Submit command buffer
-        self.queue.submit(std::iter::once(encoder.finish()));
-        
-        // Present the frame
-        output.present();
+        if !overlay_draw_calls.is_empty() {
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Overlay Encoder"),
+            });
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Overlay Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                self.overlay_renderer.render(&mut render_pass, &overlay_draw_calls, &mut self.frame_stats);
+            }
+            self.queue.submit(std::iter::once(encoder.finish()));
+            self.frame_stats.submits += 1;
+        }
+        self.finish_frame_stats();
         
-        Ok(())
+        draw_calls.len()
+    }
+
+    /// Time the frame and move its stats into the history
+    fn finish_frame_stats(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame_end) = self.last_frame_end {
+            self.frame_stats.frame_time = now - last_frame_end;
+        }
+        self.last_frame_end = Some(now);
+
+        self.stats_history.push(std::mem::take(&mut self.frame_stats));
     }
 
-    /// Create a shader module from WGSL source
-    pub fn create_shader(&self, source: &str, label: Option<&str>) -> ShaderModule {
-        self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label,
-            source: wgpu::ShaderSource::Wgsl(source.into()),
-        })
+    /// Drop the models, sprites and debug drawing queued for a frame that isn't drawn
+    fn discard_queued(&mut self) {
+        self.models.clear();
+        self.instanced.clear();
+        self.sprite_batch.clear();
+        self.overlay_batch.clear();
+        self.debug_draw.clear();
+    }
+
+    /// Create a shader from WGSL source, see `Shader::from_wgsl`
+    pub fn create_shader(&self, source: &str, label: Option<&str>) -> Result<Shader, ShaderError> {
+        Shader::from_wgsl(&self.device, source, label.unwrap_or("Shader"))
     }
 }
 
+/// Restrict drawing to a viewport in pixels from the top left
+fn set_viewport(render_pass: &mut wgpu::RenderPass, (x, y, width, height): (u32, u32, u32, u32)) {
+    render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+    render_pass.set_scissor_rect(x, y, width, height);
+}
+
+/// Absolute path of an asset file, which also checks that it exists
+fn canonicalize(path: &Path) -> Result<PathBuf, RenderError> {
+    fs::canonicalize(path).map_err(|source| RenderError::Io { path: path.to_path_buf(), source })
+}
+
+/// Load an image file into a texture
+fn load_texture(
+    device: &Device,
+    queue: &Queue,
+    path: &Path,
+    sampler: TextureSampler,
+    preference: TextureFormatPreference,
+) -> Result<Texture, RenderError> {
+    Texture::load(device, queue, path, sampler, ColorSpace::Srgb, preference)
+        .map_err(|e| RenderError::TextureLoad(e.context(path.display().to_string())))
+}
+
+/// Load and validate a sprite shader file
+fn load_sprite_shader(device: &Device, path: &Path) -> Result<Shader, RenderError> {
+    let source = fs::read_to_string(path).map_err(|source| RenderError::Io { path: path.to_path_buf(), source })?;
+    let shader = Shader::from_wgsl(device, &source, &path.to_string_lossy())?;
+    shader.require_entry_points(&["vs_main", "fs_main"])?;
+    Ok(shader)
+}
+
 /// Log information about the graphics adapter
 fn log_adapter_info(adapter: &Adapter) {
     let info = adapter.get_info();
     info!("Graphics adapter: {} ({:?})", info.name, info.backend);
     info!("Device type: {:?}", info.device_type);
     info!("Driver info: {:?}", info.driver);
-} 
\ No newline at end of file
+} 
+
+/// Sample counts from `MSAA_SAMPLE_COUNTS` usable with `format` and a depth buffer of `depth_format`
+fn supported_sample_counts(adapter: &Adapter, device: &Device, format: TextureFormat, depth_format: DepthFormat) -> Vec<u32> {
+    let format_flags = |format: TextureFormat| {
+        if device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            adapter.get_texture_format_features(format).flags
+        } else {
+            format.guaranteed_format_features(device.features()).flags
+        }
+    };
+    let color = format_flags(format);
+    let depth = format_flags(depth_format.texture_format());
+
+    MSAA_SAMPLE_COUNTS.into_iter()
+        .filter(|&count| count == 1 || (
+            color.sample_count_supported(count)
+                && color.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                && depth.sample_count_supported(count)
+        ))
+        .collect()
+}
+
+/// The supported sample count nearest to `requested`, preferring the lower one on ties
+///
+/// Logs the selected count since it may differ from the request.
+fn select_sample_count(requested: u32, supported: &[u32]) -> u32 {
+    let selected = supported.iter()
+        .copied()
+        .min_by_key(|&count| (count.abs_diff(requested), count))
+        .unwrap_or(1);
+
+    if selected == requested {
+        info!("MSAA sample count: {}", selected);
+    } else {
+        warn!("MSAA sample count {} is not supported, using {}", requested, selected);
+    }
+    selected
+}
+
+/// Multisampled color target of `size`, `None` when MSAA is disabled
+fn create_msaa_target(device: &Device, size: (u32, u32), format: TextureFormat, sample_count: u32) -> Option<RenderTarget> {
+    if sample_count <= 1 {
+        return None;
+    }
+    RenderTarget::multisampled(device, size.0, size.1, format, sample_count, "MSAA Color Target")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::{MeshData, Transform};
+
+    /// A headless renderer with a black background, lit only by full ambient light
+    /// so models show their plain colors, looking down -Z at a 2 x 2 square
+    async fn flat_shaded_headless(width: u32, height: u32, depth_format: DepthFormat) -> Option<Renderer> {
+        let mut renderer = match Renderer::new_headless(width, height, depth_format).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                return None;
+            }
+        };
+        renderer.set_light_manager(&LightManager::new(0));
+        renderer.set_ambient_light(Vec3::ONE);
+        renderer.set_clear_mode(ClearMode::SolidColor);
+        renderer.set_clear_color(0.0, 0.0, 0.0, 1.0);
+        let eye = Vec3::new(0.0, 0.0, 5.0);
+        let view = Mat4::look_at_rh(eye, Vec3::ZERO, Vec3::Y);
+        renderer.set_camera(Mat4::orthographic_rh(-1.0, 1.0, -1.0, 1.0, 0.1, 10.0) * view, eye);
+        Some(renderer)
+    }
+
+    /// A square of `size` at depth `z` facing the camera, drawn in `color`
+    fn solid_quad(renderer: &Renderer, size: f32, z: f32, color: [f32; 4]) -> Arc<Model> {
+        let mut data = MeshData::quad();
+        for vertex in &mut data.vertices {
+            vertex.color = color;
+        }
+        let mesh = Arc::new(Mesh::from_data(renderer.device(), &data).unwrap());
+        let material = Arc::new(renderer.create_material("Solid", None));
+        let transform = Transform::with_position_scale(Vec3::new(0.0, 0.0, z), Vec3::new(size, size, 1.0));
+        Arc::new(Model::new_with_device(renderer.device(), mesh, material, transform))
+    }
+
+    #[tokio::test]
+    async fn split_screen_views_clear_their_own_half() {
+        let mut renderer = match Renderer::new_headless(64, 32, DepthFormat::default()).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                return;
+            }
+        };
+        let red = wgpu::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+        let blue = wgpu::Color { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+        let views = [
+            RenderView::new(Mat4::IDENTITY, Vec3::ZERO)
+                .with_viewport([0.0, 0.0, 0.5, 1.0])
+                .with_clear(ClearMode::SolidColor, red),
+            RenderView::new(Mat4::IDENTITY, Vec3::ZERO)
+                .with_viewport([0.5, 0.0, 0.5, 1.0])
+                .with_clear(ClearMode::SolidColor, blue),
+        ];
+
+        renderer.render_views(&views).unwrap();
+        let frame = renderer.capture_frame().unwrap();
+
+        assert_eq!(frame.dimensions(), (64, 32));
+        for y in [0, 16, 31] {
+            assert_eq!(frame.get_pixel(8, y).0, [255, 0, 0, 255]);
+            assert_eq!(frame.get_pixel(31, y).0, [255, 0, 0, 255]);
+            assert_eq!(frame.get_pixel(32, y).0, [0, 0, 255, 255]);
+            assert_eq!(frame.get_pixel(56, y).0, [0, 0, 255, 255]);
+        }
+    }
+
+    #[tokio::test]
+    async fn solid_quad_covers_the_center_pixel() {
+        let Some(mut renderer) = flat_shaded_headless(32, 32, DepthFormat::default()).await else {
+            return;
+        };
+        renderer.draw_model(solid_quad(&renderer, 1.0, 0.0, [1.0, 0.0, 0.0, 1.0]), false);
+        renderer.render_frame().unwrap();
+        let frame = renderer.capture_frame().unwrap();
+
+        assert_eq!(frame.get_pixel(16, 16).0, [255, 0, 0, 255]);
+        // The quad spans the middle half of the frame
+        assert_eq!(frame.get_pixel(2, 16).0, [0, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(16, 29).0, [0, 0, 0, 255]);
+    }
+
+    #[tokio::test]
+    async fn near_quad_covers_far_quad_in_either_order() {
+        for depth_format in [DepthFormat::Depth24Plus, DepthFormat::Depth32Float] {
+            let Some(mut renderer) = flat_shaded_headless(32, 32, depth_format).await else {
+                return;
+            };
+            assert_eq!(renderer.depth_format(), depth_format);
+            let near = solid_quad(&renderer, 1.0, 1.0, [0.0, 1.0, 0.0, 1.0]);
+            let far = solid_quad(&renderer, 1.5, -1.0, [1.0, 0.0, 0.0, 1.0]);
+
+            for order in [[&far, &near], [&near, &far]] {
+                for model in order {
+                    renderer.draw_model(model.clone(), false);
+                }
+                renderer.render_frame().unwrap();
+                let frame = renderer.capture_frame().unwrap();
+                assert_eq!(frame.get_pixel(16, 16).0, [0, 255, 0, 255], "{:?}", depth_format);
+                // Only the far quad reaches this far out
+                assert_eq!(frame.get_pixel(16, 6).0, [255, 0, 0, 255], "{:?}", depth_format);
+            }
+        }
+    }
+}
@@ -1,25 +1,67 @@
+use std::fmt;
 use wgpu::{Device, ShaderModule};
 
+/// Lines of source shown before and after the offending line in a `ShaderError`
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
+/// A shader that failed to compile, with where in the source it went wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderError {
+    /// Name of the shader
+    pub name: String,
+    /// What went wrong
+    pub message: String,
+    /// 1-based line and column of the error, if it points into the source
+    pub location: Option<(u32, u32)>,
+    /// The source around the error, with line numbers and the column marked
+    pub snippet: String,
+}
+
+impl ShaderError {
+    /// Error at an offset into `source`, or at no particular place for `None`
+    fn new(name: &str, message: String, source: &str, location: Option<naga::SourceLocation>) -> Self {
+        let location = location.map(|location| (location.line_number, location.line_position));
+        Self {
+            name: name.to_string(),
+            message,
+            location,
+            snippet: location.map(|(line, column)| source_snippet(source, line, column)).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{}:{}:{}: {}", self.name, line, column, self.message)?,
+            None => write!(f, "{}: {}", self.name, self.message)?,
+        }
+        if !self.snippet.is_empty() {
+            write!(f, "\n{}", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
 /// A shader module that can be used for rendering
 pub struct Shader {
     pub module: ShaderModule,
     pub name: String,
     pub entry_point: String,
+    /// Entry points defined by the source
+    pub entry_points: Vec<String>,
 }
 
 impl Shader {
     /// Create a new shader from WGSL source code
-    pub fn from_wgsl(device: &Device, source: &str, name: &str) -> Self {
-        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some(name),
-            source: wgpu::ShaderSource::Wgsl(source.into()),
-        });
-
-        Self {
-            module,
-            name: name.to_string(),
-            entry_point: "main".to_string(),
-        }
+    ///
+    /// The source is parsed and validated before the device sees it, so mistakes are
+    /// returned as errors pointing at the offending line instead of bringing down the
+    /// renderer.
+    pub fn from_wgsl(device: &Device, source: &str, name: &str) -> Result<Self, ShaderError> {
+        Self::from_wgsl_with_entry_point(device, source, name, "main")
     }
 
     /// Create a new shader from WGSL source code with a specific entry point
@@ -28,18 +70,66 @@ impl Shader {
         source: &str,
         name: &str,
         entry_point: &str,
-    ) -> Self {
+    ) -> Result<Self, ShaderError> {
+        let entry_points = validate_wgsl(source, name)?;
         let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some(name),
             source: wgpu::ShaderSource::Wgsl(source.into()),
         });
 
-        Self {
+        Ok(Self {
             module,
             name: name.to_string(),
             entry_point: entry_point.to_string(),
+            entry_points,
+        })
+    }
+
+    /// Fail unless the shader defines every one of `entry_points`
+    pub fn require_entry_points(&self, entry_points: &[&str]) -> Result<(), ShaderError> {
+        match entry_points.iter().find(|name| !self.entry_points.iter().any(|e| e == *name)) {
+            Some(missing) => Err(ShaderError {
+                name: self.name.clone(),
+                message: format!("no entry point named '{}'", missing),
+                location: None,
+                snippet: String::new(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse and validate WGSL source, returning the names of its entry points
+pub fn validate_wgsl(source: &str, name: &str) -> Result<Vec<String>, ShaderError> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| ShaderError::new(name, e.message().to_string(), source, e.location(source)))?;
+
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|e| ShaderError::new(name, e.as_inner().to_string(), source, e.location(source)))?;
+
+    Ok(module.entry_points.iter().map(|entry_point| entry_point.name.clone()).collect())
+}
+
+/// Source lines around `line`, numbered, with a caret under `column`
+fn source_snippet(source: &str, line: u32, column: u32) -> String {
+    let line = line.max(1) as usize;
+    let first = line.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+    let last = line + SNIPPET_CONTEXT_LINES;
+    let width = last.to_string().len();
+
+    let mut snippet = String::new();
+    for (number, text) in source.lines().enumerate().map(|(i, text)| (i + 1, text)) {
+        if number < first || number > last {
+            continue;
+        }
+        snippet.push_str(&format!("{:>width$} | {}\n", number, text, width = width));
+        if number == line {
+            let indent = " ".repeat(column.saturating_sub(1) as usize);
+            snippet.push_str(&format!("{:>width$} | {}^\n", "", indent, width = width));
         }
     }
+    snippet.trim_end().to_string()
 }
 
 /// Default 2D sprite shader
@@ -88,6 +178,7 @@ pub fn create_sprite_shader(device: &Device) -> Shader {
     "#;
 
     Shader::from_wgsl(device, shader_src, "Sprite Shader")
+        .expect("built-in shader is valid WGSL")
 }
 
 /// Default 2D unlit shader
@@ -130,4 +221,452 @@ pub fn create_unlit_shader(device: &Device) -> Shader {
     "#;
 
     Shader::from_wgsl(device, shader_src, "Unlit Shader")
-} 
\ No newline at end of file
+        .expect("built-in shader is valid WGSL")
+} 
+/// Batched 2D sprite shader, vertices are already in world space
+pub fn create_sprite_batch_shader(device: &Device) -> Shader {
+    let shader_src = r#"
+    struct VertexInput {
+        @location(0) position: vec3<f32>,
+        @location(1) normal: vec3<f32>,
+        @location(2) tex_coords: vec2<f32>,
+        @location(3) color: vec4<f32>,
+    };
+
+    struct VertexOutput {
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) tex_coords: vec2<f32>,
+        @location(1) color: vec4<f32>,
+    };
+
+    struct CameraUniform {
+        view_proj: mat4x4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+    @vertex
+    fn vs_main(in: VertexInput) -> VertexOutput {
+        var out: VertexOutput;
+        out.clip_position = camera.view_proj * vec4<f32>(in.position, 1.0);
+        out.tex_coords = in.tex_coords;
+        out.color = in.color;
+        return out;
+    }
+
+    @group(1) @binding(0) var t_diffuse: texture_2d<f32>;
+    @group(1) @binding(1) var s_diffuse: sampler;
+
+    @fragment
+    fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+        let tex_color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+        return tex_color * in.color;
+    }
+    "#;
+
+    Shader::from_wgsl(device, shader_src, "Sprite Batch Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// Forward lit shader with one directional light and up to `max_point_lights` point lights
+///
+/// Uses Blinn-Phong shading. Point lights fade out smoothly at their range, the
+/// directional light is shadowed with 3x3 PCF when a shadow map is bound.
+pub fn create_lit_shader(device: &Device, max_point_lights: usize) -> Shader {
+    Shader::from_wgsl(device, &lit_shader_source(max_point_lights, false), "Lit Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// Lit shader taking the model matrix from the instance buffer, see `InstanceData`
+pub fn create_instanced_lit_shader(device: &Device, max_point_lights: usize) -> Shader {
+    Shader::from_wgsl(device, &lit_shader_source(max_point_lights, true), "Instanced Lit Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// WGSL of the lit shader, with the model matrix from the model uniform or per instance
+fn lit_shader_source(max_point_lights: usize, instanced: bool) -> String {
+    let shader_src = r#"
+    const MAX_POINT_LIGHTS: u32 = {MAX_POINT_LIGHTS}u;
+    // Highlight exponent of a perfectly smooth surface, about 32 at the default roughness of 0.5
+    const MAX_SHININESS: f32 = 122.0;
+    const SPECULAR_STRENGTH: f32 = 0.5;
+
+    struct VertexInput {
+        @location(0) position: vec3<f32>,
+        @location(1) normal: vec3<f32>,
+        @location(2) tex_coords: vec2<f32>,
+        @location(3) color: vec4<f32>,
+        @location(4) tangent: vec4<f32>,{INSTANCE_INPUT}
+    };
+
+    struct VertexOutput {
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) world_position: vec3<f32>,
+        @location(1) world_normal: vec3<f32>,
+        @location(2) tex_coords: vec2<f32>,
+        @location(3) color: vec4<f32>,
+        @location(4) world_tangent: vec4<f32>,
+    };
+
+    struct CameraUniform {
+        view_proj: mat4x4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+    struct ModelUniform {
+        model: mat4x4<f32>,
+    };
+    @group(1) @binding(0) var<uniform> model: ModelUniform;
+
+    @group(2) @binding(0) var t_diffuse: texture_2d<f32>;
+    @group(2) @binding(1) var s_diffuse: sampler;
+    @group(2) @binding(2) var t_normal: texture_2d<f32>;
+    @group(2) @binding(3) var s_normal: sampler;
+
+    struct MaterialUniform {
+        base_color: vec4<f32>,
+        metallic: f32,
+        roughness: f32,
+        has_normal_map: u32,
+    };
+    @group(2) @binding(4) var<uniform> material: MaterialUniform;
+
+    struct Light {
+        position: vec3<f32>,
+        light_type: u32,
+        color: vec3<f32>,
+        intensity: f32,
+        direction: vec3<f32>,
+        range: f32,
+    };
+
+    struct LightsUniform {
+        camera_position: vec3<f32>,
+        point_light_count: u32,
+        ambient: vec3<f32>,
+        has_directional: u32,
+        light_view_proj: mat4x4<f32>,
+        shadow_bias: f32,
+        has_shadow: u32,
+        shadow_texel_size: f32,
+        directional: Light,
+        point_lights: array<Light, MAX_POINT_LIGHTS>,
+    };
+    @group(3) @binding(0) var<uniform> lights: LightsUniform;
+    @group(3) @binding(1) var t_shadow: texture_depth_2d;
+    @group(3) @binding(2) var s_shadow: sampler_comparison;
+
+    @vertex
+    fn vs_main(in: VertexInput) -> VertexOutput {
+        var out: VertexOutput;
+        let model_matrix = {MODEL_MATRIX};
+        let world_position = model_matrix * vec4<f32>(in.position, 1.0);
+        out.clip_position = camera.view_proj * world_position;
+        out.world_position = world_position.xyz;
+        // Ignores non-uniform scale, which is close enough for Blinn-Phong
+        out.world_normal = (model_matrix * vec4<f32>(in.normal, 0.0)).xyz;
+        out.world_tangent = vec4<f32>((model_matrix * vec4<f32>(in.tangent.xyz, 0.0)).xyz, in.tangent.w);
+        out.tex_coords = in.tex_coords;
+        out.color = in.color;
+        return out;
+    }
+
+    // Geometric normal perturbed by the normal map `sampled`, unchanged without a map or tangents
+    fn surface_normal(in: VertexOutput, sampled: vec3<f32>) -> vec3<f32> {
+        let normal = normalize(in.world_normal);
+        if material.has_normal_map == 0u || dot(in.world_tangent.xyz, in.world_tangent.xyz) < 1e-8 {
+            return normal;
+        }
+        // Interpolation leaves the tangent slightly off perpendicular
+        let tangent = normalize(in.world_tangent.xyz - normal * dot(normal, in.world_tangent.xyz));
+        let bitangent = cross(normal, tangent) * in.world_tangent.w;
+        let tangent_normal = sampled * 2.0 - vec3<f32>(1.0);
+        return normalize(tangent * tangent_normal.x + bitangent * tangent_normal.y + normal * tangent_normal.z);
+    }
+
+    // Diffuse plus specular contribution of light arriving from `to_light`
+    //
+    // Rough surfaces spread a dimmer highlight; metals tint it with the albedo and
+    // lose their diffuse color.
+    fn shade(normal: vec3<f32>, to_view: vec3<f32>, to_light: vec3<f32>, radiance: vec3<f32>, albedo: vec3<f32>) -> vec3<f32> {
+        let diffuse = max(dot(normal, to_light), 0.0);
+        let half_dir = normalize(to_light + to_view);
+        let smoothness = 1.0 - material.roughness;
+        let shininess = mix(2.0, MAX_SHININESS, smoothness * smoothness);
+        let specular_color = mix(vec3<f32>(SPECULAR_STRENGTH), albedo, material.metallic);
+        var specular = vec3<f32>(0.0);
+        if diffuse > 0.0 {
+            // Twice the smoothness keeps the default roughness as bright as a fixed highlight
+            specular = specular_color * pow(max(dot(normal, half_dir), 0.0), shininess) * smoothness * 2.0;
+        }
+        return (albedo * diffuse * (1.0 - material.metallic) + specular) * radiance;
+    }
+
+    // Fraction of the directional light reaching `world_position`, averaged over 3x3 shadow map texels
+    fn directional_shadow(world_position: vec3<f32>, normal: vec3<f32>, to_light: vec3<f32>) -> f32 {
+        let light_clip = lights.light_view_proj * vec4<f32>(world_position, 1.0);
+        let ndc = light_clip.xyz / light_clip.w;
+        let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+        // Outside the shadow map nothing is known to be in shadow
+        if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+            return 1.0;
+        }
+
+        // Surfaces at grazing angles need more bias to avoid acne
+        let bias = max(lights.shadow_bias * (1.0 - dot(normal, to_light)), lights.shadow_bias * 0.1);
+        let depth = ndc.z - bias;
+
+        var lit = 0.0;
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let offset = vec2<f32>(f32(x), f32(y)) * lights.shadow_texel_size;
+                lit += textureSampleCompareLevel(t_shadow, s_shadow, uv + offset, depth);
+            }
+        }
+        return lit / 9.0;
+    }
+
+    @fragment
+    fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+        let base = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.color * material.base_color;
+        // Sampled up front, textureSample needs uniform control flow
+        let normal = surface_normal(in, textureSample(t_normal, s_normal, in.tex_coords).xyz);
+        let to_view = normalize(lights.camera_position - in.world_position);
+
+        var color = base.rgb * lights.ambient;
+
+        if lights.has_directional != 0u {
+            let light = lights.directional;
+            let to_light = normalize(-light.direction);
+            var visibility = 1.0;
+            if lights.has_shadow != 0u {
+                visibility = directional_shadow(in.world_position, normal, to_light);
+            }
+            color += shade(normal, to_view, to_light, light.color * light.intensity * visibility, base.rgb);
+        }
+
+        for (var i = 0u; i < min(lights.point_light_count, MAX_POINT_LIGHTS); i++) {
+            let light = lights.point_lights[i];
+            let offset = light.position - in.world_position;
+            let distance = length(offset);
+            if distance >= light.range {
+                continue;
+            }
+            // Inverse square falloff windowed to reach zero at the range
+            let window = clamp(1.0 - pow(distance / light.range, 4.0), 0.0, 1.0);
+            let attenuation = window * window / (distance * distance + 1.0);
+            let radiance = light.color * light.intensity * attenuation;
+            color += shade(normal, to_view, offset / max(distance, 0.0001), radiance, base.rgb);
+        }
+
+        return vec4<f32>(color, base.a);
+    }
+    "#.replace("{MAX_POINT_LIGHTS}", &max_point_lights.max(1).to_string());
+
+    let (instance_input, model_matrix) = model_matrix_source(instanced);
+    shader_src
+        .replace("{INSTANCE_INPUT}", instance_input)
+        .replace("{MODEL_MATRIX}", model_matrix)
+}
+
+/// Extra vertex inputs and the expression for the model matrix, from the model uniform or per instance
+fn model_matrix_source(instanced: bool) -> (&'static str, &'static str) {
+    if instanced {
+        (
+            r#"
+        @location(5) model_0: vec4<f32>,
+        @location(6) model_1: vec4<f32>,
+        @location(7) model_2: vec4<f32>,
+        @location(8) model_3: vec4<f32>,"#,
+            "mat4x4<f32>(in.model_0, in.model_1, in.model_2, in.model_3)",
+        )
+    } else {
+        ("", "model.model")
+    }
+}
+
+/// Depth-only shader rendering geometry from a light's point of view
+pub fn create_shadow_shader(device: &Device) -> Shader {
+    Shader::from_wgsl(device, &shadow_shader_source(false), "Shadow Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// Shadow shader taking the model matrix from the instance buffer, see `InstanceData`
+pub fn create_instanced_shadow_shader(device: &Device) -> Shader {
+    Shader::from_wgsl(device, &shadow_shader_source(true), "Instanced Shadow Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// WGSL of the shadow shader, with the model matrix from the model uniform or per instance
+fn shadow_shader_source(instanced: bool) -> String {
+    let shader_src = r#"
+    struct VertexInput {
+        @location(0) position: vec3<f32>,
+        @location(1) normal: vec3<f32>,
+        @location(2) tex_coords: vec2<f32>,
+        @location(3) color: vec4<f32>,{INSTANCE_INPUT}
+    };
+
+    struct CameraUniform {
+        view_proj: mat4x4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> light_camera: CameraUniform;
+
+    struct ModelUniform {
+        model: mat4x4<f32>,
+    };
+    @group(1) @binding(0) var<uniform> model: ModelUniform;
+
+    @vertex
+    fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32> {
+        return light_camera.view_proj * {MODEL_MATRIX} * vec4<f32>(in.position, 1.0);
+    }
+    "#;
+
+    let (instance_input, model_matrix) = model_matrix_source(instanced);
+    shader_src
+        .replace("{INSTANCE_INPUT}", instance_input)
+        .replace("{MODEL_MATRIX}", model_matrix)
+}
+
+/// Skybox shader, a fullscreen triangle sampling a cubemap by view direction
+pub fn create_skybox_shader(device: &Device) -> Shader {
+    let shader_src = r#"
+    struct SkyboxUniform {
+        inverse_view_proj: mat4x4<f32>,
+        camera_position: vec4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> skybox: SkyboxUniform;
+    @group(0) @binding(1) var t_cubemap: texture_cube<f32>;
+    @group(0) @binding(2) var s_cubemap: sampler;
+
+    struct VertexOutput {
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) ndc: vec2<f32>,
+    };
+
+    // One triangle covering the whole viewport, at depth 1 so it also resets depth
+    @vertex
+    fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+        let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+        let ndc = uv * 2.0 - vec2<f32>(1.0, 1.0);
+        var out: VertexOutput;
+        out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+        out.ndc = ndc;
+        return out;
+    }
+
+    @fragment
+    fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+        // The point on the far plane behind this pixel gives the view direction
+        let far = skybox.inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+        let direction = far.xyz / far.w - skybox.camera_position.xyz;
+        return textureSample(t_cubemap, s_cubemap, direction);
+    }
+    "#;
+
+    Shader::from_wgsl(device, shader_src, "Skybox Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// Fills the viewport with a color at the far plane, for clearing part of a target
+pub fn create_viewport_clear_shader(device: &Device) -> Shader {
+    let shader_src = r#"
+    struct ClearUniform {
+        color: vec4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> clear: ClearUniform;
+
+    // One triangle covering the whole viewport, at depth 1 so it also resets depth
+    @vertex
+    fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+        let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+        return vec4<f32>(uv * 2.0 - vec2<f32>(1.0, 1.0), 1.0, 1.0);
+    }
+
+    @fragment
+    fn fs_main() -> @location(0) vec4<f32> {
+        return clear.color;
+    }
+    "#;
+
+    Shader::from_wgsl(device, shader_src, "Viewport Clear Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+/// Shader of debug lines, vertices are in world space with their own color
+pub fn create_debug_line_shader(device: &Device) -> Shader {
+    let shader_src = r#"
+    struct VertexInput {
+        @location(0) position: vec3<f32>,
+        @location(1) color: vec4<f32>,
+    };
+
+    struct VertexOutput {
+        @builtin(position) clip_position: vec4<f32>,
+        @location(0) color: vec4<f32>,
+    };
+
+    struct CameraUniform {
+        view_proj: mat4x4<f32>,
+    };
+    @group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+    @vertex
+    fn vs_main(in: VertexInput) -> VertexOutput {
+        var out: VertexOutput;
+        out.clip_position = camera.view_proj * vec4<f32>(in.position, 1.0);
+        out.color = in.color;
+        return out;
+    }
+
+    @fragment
+    fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+        return in.color;
+    }
+    "#;
+
+    Shader::from_wgsl(device, shader_src, "Debug Line Shader")
+        .expect("built-in shader is valid WGSL")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "\
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+
+    #[test]
+    fn valid_shader_lists_its_entry_points() {
+        let entry_points = validate_wgsl(VALID, "valid.wgsl").unwrap();
+        assert_eq!(entry_points, vec!["vs_main".to_string(), "fs_main".to_string()]);
+    }
+
+    #[test]
+    fn syntax_error_reports_its_line() {
+        let source = VALID.replace("return vec4<f32>(1.0);", "return vec4<f32>(1.0;");
+        let error = validate_wgsl(&source, "broken.wgsl").unwrap_err();
+
+        assert_eq!(error.name, "broken.wgsl");
+        let (line, column) = error.location.expect("a syntax error points into the source");
+        assert_eq!(line, 8);
+        assert!(column > 0);
+        assert!(error.to_string().starts_with("broken.wgsl:8:"), "{}", error);
+        assert!(error.snippet.contains("vec4<f32>(1.0;"), "{}", error.snippet);
+    }
+
+    #[test]
+    fn type_error_is_rejected() {
+        let source = VALID.replace("return vec4<f32>(1.0);", "return 1.0;");
+        assert!(validate_wgsl(&source, "mistyped.wgsl").is_err());
+    }
+}
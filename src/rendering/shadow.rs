@@ -0,0 +1,244 @@
+use std::ops::Range;
+use glam::{Mat4, Vec3};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, BufferUsages, CommandEncoder, Device, PipelineLayout, Queue, RenderPipeline, Sampler, TextureView};
+use wgpu::util::DeviceExt;
+use crate::rendering::{FrameStats, InstanceData, InstancedDraw, ModelDraw, Shader, ShadowResolution, Texture, Vertex};
+use crate::rendering::instancing::identity_model_bind_group;
+use crate::rendering::shader::{create_instanced_shadow_shader, create_shadow_shader};
+
+/// Distance around the camera that receives directional shadows
+pub const SHADOW_DISTANCE: f32 = 20.0;
+
+/// Depth-only pass that renders shadow casters from the directional light
+pub struct ShadowPass {
+    pipeline: RenderPipeline,
+    /// Pipeline for instanced draws, taking model matrices from the instance buffer
+    instanced_pipeline: RenderPipeline,
+    /// Bound as the model of instanced draws
+    identity_model_bind_group: BindGroup,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    /// The shadow map, `None` while no light casts shadows
+    map: Option<(ShadowResolution, Texture)>,
+    /// Bound in place of the shadow map while there is none
+    placeholder: Texture,
+}
+
+impl ShadowPass {
+    /// Create the shadow pipeline, `model_bind_group_layout` must match the models' bind groups
+    pub fn new(device: &Device, model_bind_group_layout: &BindGroupLayout) -> Self {
+        let shader = create_shadow_shader(device);
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Shadow Camera Bind Group Layout"),
+        });
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Camera Buffer"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("Shadow Camera Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout, model_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, &[Vertex::desc()]);
+        let instanced_shader = create_instanced_shadow_shader(device);
+        let instanced_pipeline = create_pipeline(device, &pipeline_layout, &instanced_shader, &[Vertex::desc(), InstanceData::desc()]);
+
+        Self {
+            pipeline,
+            instanced_pipeline,
+            identity_model_bind_group: identity_model_bind_group(device, model_bind_group_layout),
+            camera_buffer,
+            camera_bind_group,
+            map: None,
+            placeholder: Texture::create_depth_texture(device, 1, 1, Texture::DEPTH_FORMAT, 1, "Shadow Map Placeholder"),
+        }
+    }
+
+    /// Allocate the shadow map for `resolution`, or drop it when `None`, and upload the light's view-projection
+    ///
+    /// Returns true if the shadow map texture changed, so bind groups using it must be recreated.
+    pub fn update(&mut self, device: &Device, queue: &Queue, resolution: Option<ShadowResolution>, view_projection: Mat4) -> bool {
+        let current = self.map.as_ref().map(|(resolution, _)| *resolution);
+        let changed = current != resolution;
+
+        if changed {
+            self.map = resolution.map(|resolution| {
+                let size = resolution.size();
+                (resolution, Texture::create_depth_texture(device, size, size, Texture::DEPTH_FORMAT, 1, "Shadow Map"))
+            });
+        }
+
+        if self.map.is_some() {
+            queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&view_projection.to_cols_array()));
+        }
+
+        changed
+    }
+
+    /// Whether a shadow map is allocated and rendered
+    pub fn is_active(&self) -> bool {
+        self.map.is_some()
+    }
+
+    /// Size of one shadow map texel in UV space
+    pub fn texel_size(&self) -> f32 {
+        self.map.as_ref().map_or(1.0, |(resolution, _)| 1.0 / resolution.size() as f32)
+    }
+
+    /// The shadow map, or a placeholder while there is none
+    pub fn view(&self) -> &TextureView {
+        match &self.map {
+            Some((_, texture)) => &texture.view,
+            None => &self.placeholder.view,
+        }
+    }
+
+    /// Comparison sampler for the shadow map
+    pub fn sampler(&self) -> &Sampler {
+        match &self.map {
+            Some((_, texture)) => &texture.sampler,
+            None => &self.placeholder.sampler,
+        }
+    }
+
+    /// Render the models and instances that cast shadows into the shadow map, if there is one
+    ///
+    /// `instance_ranges` are the parts of `instance_buffer` holding each of the `instanced` draws.
+    pub fn render(
+        &self,
+        encoder: &mut CommandEncoder,
+        models: &[ModelDraw],
+        instanced: &[InstancedDraw],
+        instance_buffer: Option<&Buffer>,
+        instance_ranges: &[Range<u32>],
+        stats: &mut FrameStats,
+    ) {
+        let Some((_, texture)) = &self.map else {
+            return;
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Render Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+
+        for draw in models.iter().filter(|draw| draw.casts_shadows) {
+            let Some(model_bind_group) = &draw.model.model_bind_group else {
+                continue;
+            };
+            render_pass.set_bind_group(1, model_bind_group, &[]);
+
+            for (mesh, _) in draw.model.meshes() {
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.num_indices(), 0, 0..1);
+                stats.record_draw(mesh.num_indices() / 3);
+            }
+        }
+
+        let Some(instance_buffer) = instance_buffer else {
+            return;
+        };
+        render_pass.set_pipeline(&self.instanced_pipeline);
+        render_pass.set_bind_group(1, &self.identity_model_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        for (draw, range) in instanced.iter().zip(instance_ranges) {
+            if !draw.casts_shadows {
+                continue;
+            }
+            render_pass.set_vertex_buffer(0, draw.mesh.vertex_buffer().slice(..));
+            render_pass.set_index_buffer(draw.mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..draw.mesh.num_indices(), 0, range.clone());
+            stats.record_instanced_draw(draw.mesh.num_indices() / 3, range.len() as u32);
+        }
+    }
+}
+
+/// Depth-only pipeline drawing vertices laid out as `buffers`
+fn create_pipeline(device: &Device, layout: &PipelineLayout, shader: &Shader, buffers: &[wgpu::VertexBufferLayout]) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers,
+        },
+        fragment: None,
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// Orthographic view-projection of a directional light covering `SHADOW_DISTANCE` around `center`
+pub fn light_view_projection(direction: Vec3, center: Vec3) -> Mat4 {
+    let direction = direction.normalize_or_zero();
+    let direction = if direction == Vec3::ZERO { Vec3::NEG_Y } else { direction };
+    // Any up vector works as long as it isn't parallel to the light
+    let up = if direction.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+
+    let eye = center - direction * SHADOW_DISTANCE * 2.0;
+    let view = Mat4::look_at_rh(eye, center, up);
+    let projection = Mat4::orthographic_rh(
+        -SHADOW_DISTANCE, SHADOW_DISTANCE,
+        -SHADOW_DISTANCE, SHADOW_DISTANCE,
+        0.0, SHADOW_DISTANCE * 4.0,
+    );
+    projection * view
+}
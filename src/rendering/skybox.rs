@@ -0,0 +1,242 @@
+use glam::{Mat4, Vec3};
+use serde::{Serialize, Deserialize};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, BufferUsages, Device, Queue,
+    PipelineLayout, RenderPass, RenderPipeline, TextureFormat,
+};
+use crate::rendering::{FrameStats, Shader, Texture};
+use crate::rendering::shader::create_skybox_shader;
+
+/// What a camera does with the target before drawing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClearMode {
+    /// Draw the renderer's skybox cubemap, or clear to the color without one
+    Skybox,
+    /// Clear to the clear color
+    #[default]
+    SolidColor,
+    /// Keep the color, clear only depth, for drawing on top of another camera
+    DepthOnly,
+    /// Keep both color and depth
+    ///
+    /// Only offscreen targets keep their contents between frames, a window surface
+    /// hands out a new texture every frame.
+    DontClear,
+}
+
+impl ClearMode {
+    /// Every mode, in the order the inspector lists them
+    pub const ALL: [ClearMode; 4] = [ClearMode::Skybox, ClearMode::SolidColor, ClearMode::DepthOnly, ClearMode::DontClear];
+
+    /// Name shown in the inspector
+    pub fn label(self) -> &'static str {
+        match self {
+            ClearMode::Skybox => "Skybox",
+            ClearMode::SolidColor => "Solid Color",
+            ClearMode::DepthOnly => "Depth Only",
+            ClearMode::DontClear => "Don't Clear",
+        }
+    }
+
+    /// Load operation for the color target
+    pub fn color_load(self, color: wgpu::Color) -> wgpu::LoadOp<wgpu::Color> {
+        match self {
+            ClearMode::Skybox | ClearMode::SolidColor => wgpu::LoadOp::Clear(color),
+            ClearMode::DepthOnly | ClearMode::DontClear => wgpu::LoadOp::Load,
+        }
+    }
+
+    /// Load operation for the depth target
+    pub fn depth_load(self) -> wgpu::LoadOp<f32> {
+        match self {
+            ClearMode::DontClear => wgpu::LoadOp::Load,
+            _ => wgpu::LoadOp::Clear(1.0),
+        }
+    }
+}
+
+/// GPU side of the skybox: pipeline, camera uniform and the bound cubemap
+pub struct SkyboxRenderer {
+    pipeline: RenderPipeline,
+    pipeline_layout: PipelineLayout,
+    shader: Shader,
+    format: TextureFormat,
+    depth_format: TextureFormat,
+    uniform_buffer: Buffer,
+    bind_group_layout: BindGroupLayout,
+    /// Bind group of the cubemap, nothing is drawn without one
+    bind_group: Option<BindGroup>,
+}
+
+impl SkyboxRenderer {
+    /// Create the skybox pipeline for the given color and depth target formats and sample count
+    pub fn new(device: &Device, format: TextureFormat, depth_format: TextureFormat, sample_count: u32) -> Self {
+        let shader = create_skybox_shader(device);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // Camera
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Cubemap
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Skybox Bind Group Layout"),
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Skybox Uniform Buffer"),
+            contents: bytemuck::cast_slice(&skybox_uniform(Mat4::IDENTITY, Vec3::ZERO)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, format, depth_format, sample_count);
+
+        Self {
+            pipeline,
+            pipeline_layout,
+            shader,
+            format,
+            depth_format,
+            uniform_buffer,
+            bind_group_layout,
+            bind_group: None,
+        }
+    }
+
+    /// Rebuild the pipeline for targets with `sample_count` samples, the cubemap is kept
+    pub fn set_sample_count(&mut self, device: &Device, sample_count: u32) {
+        self.pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, self.depth_format, sample_count);
+    }
+
+    /// Draw this cubemap, e.g. from `Texture::load_cubemap`; `None` removes the skybox
+    pub fn set_cubemap(&mut self, device: &Device, cubemap: Option<&Texture>) {
+        self.bind_group = cubemap.map(|cubemap| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&cubemap.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&cubemap.sampler),
+                    },
+                ],
+                label: Some("Skybox Bind Group"),
+            })
+        });
+    }
+
+    /// Whether a cubemap is bound
+    pub fn has_cubemap(&self) -> bool {
+        self.bind_group.is_some()
+    }
+
+    /// Upload the camera the skybox is seen from
+    pub fn prepare(&self, queue: &Queue, view_projection: Mat4, camera_position: Vec3) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&skybox_uniform(view_projection, camera_position)));
+    }
+
+    /// Fill the viewport with the skybox, does nothing without a cubemap
+    pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, stats: &mut FrameStats) {
+        let Some(bind_group) = &self.bind_group else {
+            return;
+        };
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        stats.record_draw(1);
+    }
+}
+
+/// Uniform contents: the inverse view-projection followed by the camera position
+fn skybox_uniform(view_projection: Mat4, camera_position: Vec3) -> [f32; 20] {
+    let mut uniform = [0.0; 20];
+    uniform[..16].copy_from_slice(&view_projection.inverse().to_cols_array());
+    uniform[16..19].copy_from_slice(&camera_position.to_array());
+    uniform
+}
+
+/// Skybox pipeline for color targets of `format` with `sample_count` samples
+///
+/// The skybox is drawn first in the lit pass at the far plane. It writes depth without
+/// testing it, so it also clears depth within the viewport.
+fn create_pipeline(device: &Device, layout: &PipelineLayout, shader: &Shader, format: TextureFormat, depth_format: TextureFormat, sample_count: u32) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Skybox Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader.module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
@@ -0,0 +1,76 @@
+/// Layer sprites are drawn in unless they name another one
+pub const DEFAULT_SORTING_LAYER: &str = "Default";
+
+/// Ordered names of the layers sprites are sorted into, later layers are drawn on top
+///
+/// Starts out as Background, Default, Foreground and UI. Sprites in a layer that
+/// doesn't exist are drawn in the Default layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortingLayers {
+    names: Vec<String>,
+    /// Sort sprites of the same layer and order by height, lower sprites on top,
+    /// as top-down games do to draw characters in front of what's behind them
+    pub sort_by_y: bool,
+}
+
+impl SortingLayers {
+    pub fn new() -> Self {
+        Self {
+            names: ["Background", DEFAULT_SORTING_LAYER, "Foreground", "UI"].map(String::from).to_vec(),
+            sort_by_y: false,
+        }
+    }
+
+    /// Layer names from back to front
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Position of a layer from the back
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.names.iter().position(|layer| layer == name).map(|index| index as u32)
+    }
+
+    /// Position of a layer, or of the Default layer if there's no layer called `name`
+    pub fn resolve(&self, name: &str) -> u32 {
+        self.index_of(name)
+            .or_else(|| self.index_of(DEFAULT_SORTING_LAYER))
+            .unwrap_or(0)
+    }
+
+    /// Add a layer in front of all others, returns false if it already exists
+    pub fn add(&mut self, name: impl Into<String>) -> bool {
+        let index = self.names.len();
+        self.insert(index, name)
+    }
+
+    /// Add a layer at a position from the back, returns false if it already exists
+    ///
+    /// Positions past the front add the layer in front.
+    pub fn insert(&mut self, index: usize, name: impl Into<String>) -> bool {
+        let name = name.into();
+        if self.names.contains(&name) {
+            return false;
+        }
+        self.names.insert(index.min(self.names.len()), name);
+        true
+    }
+
+    /// Remove a layer, its sprites move to the Default layer
+    ///
+    /// Returns false if there's no such layer. The Default layer can't be removed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        if name == DEFAULT_SORTING_LAYER {
+            return false;
+        }
+        let count = self.names.len();
+        self.names.retain(|layer| layer != name);
+        self.names.len() != count
+    }
+}
+
+impl Default for SortingLayers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
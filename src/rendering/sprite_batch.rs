@@ -0,0 +1,669 @@
+use std::ops::Range;
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, BufferUsages, Device, Queue,
+    PipelineLayout, RenderPass, RenderPipeline, TextureFormat,
+};
+use glam::{Mat4, Vec2, Vec3};
+use crate::rendering::{BlendMode, FrameStats, Shader, SpriteUv, Texture, Vertex};
+use crate::rendering::shader::create_sprite_batch_shader;
+
+/// Number of sprites the GPU buffers are sized for before they first grow
+const INITIAL_SPRITE_CAPACITY: usize = 1024;
+
+/// Handle to a texture registered with the sprite renderer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureHandle(pub u32);
+
+impl TextureHandle {
+    /// Plain white texture registered before any other, for sprites drawn in their tint alone
+    pub const WHITE: TextureHandle = TextureHandle(0);
+}
+
+/// A single textured quad to draw
+#[derive(Debug, Clone, Copy)]
+pub struct SpriteInstance {
+    /// Center of the sprite in world space
+    pub position: Vec2,
+    /// Width and height of the sprite
+    pub size: Vec2,
+    /// Rotation around the center in radians
+    pub rotation: f32,
+    /// Texture to sample
+    pub texture: TextureHandle,
+    /// Part of the texture to draw, e.g. an atlas region
+    pub uv: SpriteUv,
+    /// Color multiplied with the texture
+    pub tint: [f32; 4],
+    /// Index of the sorting layer, see `SortingLayers`; `None` is the Default layer
+    pub sorting_layer: Option<u32>,
+    /// Draw order within the sorting layer, higher on top
+    pub order_in_layer: i32,
+    /// Z of the sprite, sprites of the same layer and order are drawn from low to high Z
+    pub depth: f32,
+    /// How the sprite is blended with what's below it
+    pub blend_mode: BlendMode,
+}
+
+impl SpriteInstance {
+    /// Create an untinted, unrotated sprite
+    pub fn new(position: Vec2, size: Vec2, texture: TextureHandle) -> Self {
+        Self {
+            position,
+            size,
+            rotation: 0.0,
+            texture,
+            uv: SpriteUv::FULL,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            sorting_layer: None,
+            order_in_layer: 0,
+            depth: 0.0,
+            blend_mode: BlendMode::AlphaBlend,
+        }
+    }
+
+    /// Draw only part of the texture
+    pub fn with_uv(mut self, uv: SpriteUv) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    /// Set the rotation in radians
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set the tint color
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Set how the sprite is blended with what's below it
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Set the sorting layer index and the order within it
+    pub fn with_sorting(mut self, sorting_layer: u32, order_in_layer: i32) -> Self {
+        self.sorting_layer = Some(sorting_layer);
+        self.order_in_layer = order_in_layer;
+        self
+    }
+
+    /// Set the Z used to sort sprites that share a layer and order
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// The four corners of the sprite as vertices, counter-clockwise from bottom left
+    fn vertices(&self) -> [Vertex; 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let half = self.size * 0.5;
+        let corner = |x: f32, y: f32, u: f32, v: f32| {
+            let local = Vec2::new(x * half.x, y * half.y);
+            let rotated = Vec2::new(local.x * cos - local.y * sin, local.x * sin + local.y * cos);
+            Vertex::new((self.position + rotated).extend(0.0), Vec3::Z, self.uv.map(Vec2::new(u, v)), self.tint)
+        };
+
+        [
+            corner(-1.0, -1.0, 0.0, 1.0),
+            corner(1.0, -1.0, 1.0, 1.0),
+            corner(1.0, 1.0, 1.0, 0.0),
+            corner(-1.0, 1.0, 0.0, 0.0),
+        ]
+    }
+}
+
+/// A range of indices drawn with a single texture and blend mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteDrawCall {
+    /// Texture bound for the draw
+    pub texture: TextureHandle,
+    /// Blend mode of the pipeline the draw uses
+    pub blend_mode: BlendMode,
+    /// Indices to draw
+    pub indices: Range<u32>,
+}
+
+/// Collects sprites over a frame and turns them into as few draw calls as possible
+///
+/// Sprites are drawn back to front by sorting layer, then order in layer, then Z,
+/// then optionally height. Sprites that tie are grouped by texture so they share draw
+/// calls, and keep their submission order within a texture. Overlapping sprites with
+/// different textures therefore only stack the way they were pushed if they differ in
+/// order in layer or Z; give them one when the stacking matters.
+pub struct SpriteBatch {
+    sprites: Vec<SpriteInstance>,
+    /// Layer index of sprites without a sorting layer
+    pub default_layer: u32,
+    /// Sort ties by height, lower sprites on top, see `SortingLayers::sort_by_y`
+    pub sort_by_y: bool,
+}
+
+impl SpriteBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self {
+            sprites: Vec::new(),
+            default_layer: 0,
+            sort_by_y: false,
+        }
+    }
+
+    /// Queue a sprite
+    pub fn push(&mut self, sprite: SpriteInstance) {
+        self.sprites.push(sprite);
+    }
+
+    /// Number of queued sprites
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    /// Whether no sprites are queued
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Drop all queued sprites
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    /// Build the vertices and draw calls for the queued sprites and empty the batch
+    ///
+    /// Indices follow the fixed quad pattern from `quad_indices`.
+    pub fn build(&mut self) -> (Vec<Vertex>, Vec<SpriteDrawCall>) {
+        // Stable, so sprites that tie on texture too keep their submission order
+        let (default_layer, sort_by_y) = (self.default_layer, self.sort_by_y);
+        let layer = |sprite: &SpriteInstance| sprite.sorting_layer.unwrap_or(default_layer);
+        self.sprites.sort_by(|a, b| {
+            layer(a).cmp(&layer(b))
+                .then(a.order_in_layer.cmp(&b.order_in_layer))
+                .then(a.depth.total_cmp(&b.depth))
+                .then_with(|| if sort_by_y { b.position.y.total_cmp(&a.position.y) } else { std::cmp::Ordering::Equal })
+                .then(a.texture.cmp(&b.texture))
+        });
+
+        let mut vertices = Vec::with_capacity(self.sprites.len() * 4);
+        let mut draw_calls: Vec<SpriteDrawCall> = Vec::new();
+
+        for (i, sprite) in self.sprites.iter().enumerate() {
+            vertices.extend_from_slice(&sprite.vertices());
+
+            let end = (i as u32 + 1) * 6;
+            match draw_calls.last_mut() {
+                Some(call) if call.texture == sprite.texture && call.blend_mode == sprite.blend_mode => call.indices.end = end,
+                // Switching textures or blend modes closes the current draw call
+                _ => draw_calls.push(SpriteDrawCall {
+                    texture: sprite.texture,
+                    blend_mode: sprite.blend_mode,
+                    indices: end - 6..end,
+                }),
+            }
+        }
+
+        self.sprites.clear();
+        (vertices, draw_calls)
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Indices for `sprite_count` quads laid out as in `SpriteInstance::vertices`
+fn quad_indices(sprite_count: usize) -> Vec<u32> {
+    (0..sprite_count as u32)
+        .flat_map(|i| {
+            let base = i * 4;
+            [base, base + 1, base + 2, base, base + 2, base + 3]
+        })
+        .collect()
+}
+
+/// GPU side of sprite batching: pipeline, textures and dynamic buffers
+pub struct SpriteRenderer {
+    /// One pipeline per blend mode, in the order of `BlendMode::ALL`
+    pipelines: Vec<RenderPipeline>,
+    /// Like `pipelines`, tested against the depth target; empty without a depth format
+    depth_tested_pipelines: Vec<RenderPipeline>,
+    pipeline_layout: PipelineLayout,
+    shader: Shader,
+    format: TextureFormat,
+    /// Format of the depth target of the passes sprites are drawn in, if they have one
+    depth_format: Option<TextureFormat>,
+    sample_count: u32,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    texture_bind_group_layout: BindGroupLayout,
+    textures: Vec<BindGroup>,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    capacity: usize,
+}
+
+impl SpriteRenderer {
+    /// Create the sprite pipelines for the given color target format and sample count
+    ///
+    /// With a `depth_format` sprites are drawn into passes with a depth target of that
+    /// format, see `render_depth_tested`.
+    pub fn new(device: &Device, format: TextureFormat, depth_format: Option<TextureFormat>, sample_count: u32) -> Self {
+        let shader = create_sprite_batch_shader(device);
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Sprite Camera Bind Group Layout"),
+        });
+
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // Texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Sprite Texture Bind Group Layout"),
+        });
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sprite Camera Buffer"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("Sprite Camera Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let (pipelines, depth_tested_pipelines) = create_pipelines(device, &pipeline_layout, &shader, format, depth_format, sample_count);
+
+        let (vertex_buffer, index_buffer) = create_buffers(device, INITIAL_SPRITE_CAPACITY);
+
+        Self {
+            pipelines,
+            depth_tested_pipelines,
+            pipeline_layout,
+            shader,
+            format,
+            depth_format,
+            sample_count,
+            camera_buffer,
+            camera_bind_group,
+            texture_bind_group_layout,
+            textures: Vec::new(),
+            vertex_buffer,
+            index_buffer,
+            capacity: INITIAL_SPRITE_CAPACITY,
+        }
+    }
+
+    /// Rebuild the pipeline for targets with `sample_count` samples, registered textures are kept
+    pub fn set_sample_count(&mut self, device: &Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        (self.pipelines, self.depth_tested_pipelines) = create_pipelines(device, &self.pipeline_layout, &self.shader, self.format, self.depth_format, sample_count);
+    }
+
+    /// Draw sprites with another shader
+    ///
+    /// The shader needs `vs_main` and `fs_main` entry points and the bindings of the
+    /// built-in sprite shader.
+    pub fn set_shader(&mut self, device: &Device, shader: Shader) {
+        (self.pipelines, self.depth_tested_pipelines) = create_pipelines(device, &self.pipeline_layout, &shader, self.format, self.depth_format, self.sample_count);
+        self.shader = shader;
+    }
+
+    /// Make a texture available to sprites
+    pub fn register_texture(&mut self, device: &Device, texture: &Texture) -> TextureHandle {
+        let handle = TextureHandle(self.textures.len() as u32);
+        let bind_group = self.create_texture_bind_group(device, texture, handle);
+        self.textures.push(bind_group);
+        handle
+    }
+
+    /// Swap the texture behind a handle, sprites using the handle draw the new one
+    ///
+    /// Returns false if the handle was never registered.
+    pub fn replace_texture(&mut self, device: &Device, handle: TextureHandle, texture: &Texture) -> bool {
+        if handle.0 as usize >= self.textures.len() {
+            return false;
+        }
+        self.textures[handle.0 as usize] = self.create_texture_bind_group(device, texture, handle);
+        true
+    }
+
+    fn create_texture_bind_group(&self, device: &Device, texture: &Texture, handle: TextureHandle) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some(&format!("Sprite Texture Bind Group {}", handle.0)),
+        })
+    }
+
+    /// Upload the batch to the GPU and return the draw calls to issue
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, view_projection: Mat4, batch: &mut SpriteBatch) -> Vec<SpriteDrawCall> {
+        let sprite_count = batch.len();
+        let (vertices, draw_calls) = batch.build();
+
+        if sprite_count > self.capacity {
+            self.capacity = sprite_count.next_power_of_two();
+            let (vertex_buffer, index_buffer) = create_buffers(device, self.capacity);
+            self.vertex_buffer = vertex_buffer;
+            self.index_buffer = index_buffer;
+        }
+
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&view_projection.to_cols_array()));
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+
+        // Sprites with textures that were never registered are dropped
+        draw_calls.into_iter()
+            .filter(|call| (call.texture.0 as usize) < self.textures.len())
+            .collect()
+    }
+
+    /// Replace the view-projection uploaded by `prepare`, for drawing the same batch from another view
+    pub fn set_view_projection(&self, queue: &Queue, view_projection: Mat4) {
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&view_projection.to_cols_array()));
+    }
+
+    /// Record the prepared draw calls into a render pass, on top of what's already drawn
+    pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, draw_calls: &[SpriteDrawCall], stats: &mut FrameStats) {
+        self.render_with(render_pass, &self.pipelines, draw_calls, stats);
+    }
+
+    /// Record the prepared draw calls, hidden by what's in front of them in the depth target
+    ///
+    /// Sprites don't write depth, so they never hide each other or what's drawn after them.
+    /// Without a depth format this is the same as `render`.
+    pub fn render_depth_tested<'a>(&'a self, render_pass: &mut RenderPass<'a>, draw_calls: &[SpriteDrawCall], stats: &mut FrameStats) {
+        let pipelines = if self.depth_tested_pipelines.is_empty() { &self.pipelines } else { &self.depth_tested_pipelines };
+        self.render_with(render_pass, pipelines, draw_calls, stats);
+    }
+
+    fn render_with<'a>(&'a self, render_pass: &mut RenderPass<'a>, pipelines: &'a [RenderPipeline], draw_calls: &[SpriteDrawCall], stats: &mut FrameStats) {
+        if draw_calls.is_empty() {
+            return;
+        }
+
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        let mut blend_mode = None;
+        for call in draw_calls {
+            if blend_mode != Some(call.blend_mode) {
+                render_pass.set_pipeline(&pipelines[call.blend_mode.index()]);
+                blend_mode = Some(call.blend_mode);
+            }
+            render_pass.set_bind_group(1, &self.textures[call.texture.0 as usize], &[]);
+            render_pass.draw_indexed(call.indices.clone(), 0, 0..1);
+            // Two triangles, six indices per sprite
+            let triangles = call.indices.len() as u32 / 3;
+            stats.record_draw(triangles);
+            stats.sprites += triangles / 2;
+        }
+    }
+}
+
+/// Create vertex and index buffers for `capacity` sprites
+fn create_buffers(device: &Device, capacity: usize) -> (Buffer, Buffer) {
+    let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sprite Vertex Buffer"),
+        size: (capacity * 4 * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    // The index pattern never changes, so it only has to be written once per allocation
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Sprite Index Buffer"),
+        contents: bytemuck::cast_slice(&quad_indices(capacity)),
+        usage: BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, index_buffer)
+}
+
+/// Sprite pipelines of every blend mode, in the order of `BlendMode::ALL`
+///
+/// Returns the pipelines drawing on top and, with a `depth_format`, the depth tested ones.
+fn create_pipelines(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    format: TextureFormat,
+    depth_format: Option<TextureFormat>,
+    sample_count: u32,
+) -> (Vec<RenderPipeline>, Vec<RenderPipeline>) {
+    let create = |depth_compare| BlendMode::ALL.into_iter()
+        .map(|blend_mode| {
+            // Sprites are see-through at their edges, so they never write depth
+            let depth_stencil = depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: false,
+                depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            });
+            create_pipeline(device, layout, shader, format, sample_count, blend_mode, depth_stencil)
+        })
+        .collect::<Vec<_>>();
+
+    let on_top = create(wgpu::CompareFunction::Always);
+    let depth_tested = match depth_format {
+        Some(_) => create(wgpu::CompareFunction::LessEqual),
+        None => Vec::new(),
+    };
+    (on_top, depth_tested)
+}
+
+/// Sprite pipeline for color targets of `format` with `sample_count` samples
+fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &Shader,
+    format: TextureFormat,
+    sample_count: u32,
+    blend_mode: BlendMode,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("Sprite Pipeline ({})", blend_mode.label())),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader.module,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader.module,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: blend_mode.blend_state(),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            // Negative scales flip the winding, sprites are visible from both sides
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprite(x: f32, texture: u32) -> SpriteInstance {
+        SpriteInstance::new(Vec2::new(x, 0.0), Vec2::ONE, TextureHandle(texture))
+    }
+
+    /// X position of each sprite in the order it's drawn, from its bottom left vertex
+    fn drawn_order(vertices: &[Vertex]) -> Vec<f32> {
+        vertices.chunks(4).map(|quad| quad[0].position[0] + 0.5).collect()
+    }
+
+    #[test]
+    fn ten_thousand_sprites_over_four_textures_take_four_draw_calls() {
+        let mut batch = SpriteBatch::new();
+        // Interleaved, so the batcher has to do the grouping
+        for i in 0..10_000 {
+            batch.push(sprite(i as f32, i % 4));
+        }
+        let (vertices, draw_calls) = batch.build();
+        assert_eq!(vertices.len(), 10_000 * 4);
+        assert_eq!(draw_calls.len(), 4);
+        assert!(draw_calls.iter().all(|call| call.indices.len() == 2500 * 6));
+        assert_eq!(draw_calls.last().unwrap().indices.end, 10_000 * 6);
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn ties_group_by_texture_and_keep_submission_order_within_one() {
+        let mut batch = SpriteBatch::new();
+        batch.push(sprite(0.0, 2));
+        batch.push(sprite(1.0, 1));
+        batch.push(sprite(2.0, 2));
+        batch.push(sprite(3.0, 1));
+        let (vertices, draw_calls) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![1.0, 3.0, 0.0, 2.0]);
+        let textures: Vec<_> = draw_calls.iter().map(|call| call.texture).collect();
+        assert_eq!(textures, vec![TextureHandle(1), TextureHandle(2)]);
+    }
+
+    #[test]
+    fn order_in_layer_stacks_textures_over_grouping() {
+        let mut batch = SpriteBatch::new();
+        batch.push(sprite(0.0, 1).with_sorting(0, 0));
+        batch.push(sprite(1.0, 2).with_sorting(0, 1));
+        batch.push(sprite(2.0, 1).with_sorting(0, 2));
+        let (vertices, draw_calls) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![0.0, 1.0, 2.0]);
+        let textures: Vec<_> = draw_calls.iter().map(|call| call.texture).collect();
+        assert_eq!(textures, vec![TextureHandle(1), TextureHandle(2), TextureHandle(1)]);
+    }
+
+    #[test]
+    fn layers_and_orders_sort_before_submission_order() {
+        let mut batch = SpriteBatch::new();
+        batch.default_layer = 1;
+        batch.push(sprite(0.0, 0).with_sorting(2, 0));
+        batch.push(sprite(1.0, 0).with_sorting(1, 5));
+        batch.push(sprite(2.0, 0));
+        batch.push(sprite(3.0, 0).with_sorting(0, 9));
+        let (vertices, draw_calls) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(draw_calls.len(), 1);
+    }
+
+    #[test]
+    fn sort_by_y_draws_lower_sprites_on_top() {
+        let mut batch = SpriteBatch::new();
+        batch.sort_by_y = true;
+        batch.push(SpriteInstance::new(Vec2::new(0.0, -1.0), Vec2::ONE, TextureHandle::WHITE));
+        batch.push(SpriteInstance::new(Vec2::new(1.0, 3.0), Vec2::ONE, TextureHandle::WHITE));
+        let (vertices, _) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn higher_order_in_layer_draws_on_top() {
+        let mut batch = SpriteBatch::new();
+        batch.push(sprite(0.0, 0).with_sorting(0, 3));
+        batch.push(sprite(1.0, 0).with_sorting(0, -2));
+        batch.push(sprite(2.0, 0).with_sorting(0, 0));
+        let (vertices, _) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![1.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn depth_breaks_ties_before_submission_order() {
+        let mut batch = SpriteBatch::new();
+        batch.sort_by_y = true;
+        batch.push(sprite(0.0, 0).with_depth(2.0));
+        batch.push(sprite(1.0, 0).with_depth(-1.0));
+        batch.push(sprite(2.0, 0).with_depth(0.5));
+        // Order in layer still wins over depth
+        batch.push(sprite(3.0, 0).with_sorting(0, -1).with_depth(10.0));
+        let (vertices, _) = batch.build();
+        assert_eq!(drawn_order(&vertices), vec![3.0, 1.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn blend_modes_split_draw_calls() {
+        let mut batch = SpriteBatch::new();
+        batch.push(sprite(0.0, 0));
+        batch.push(sprite(1.0, 0));
+        batch.push(sprite(2.0, 0).with_blend_mode(BlendMode::Additive));
+        batch.push(sprite(3.0, 0).with_blend_mode(BlendMode::Additive));
+        let (vertices, draw_calls) = batch.build();
+
+        assert_eq!(drawn_order(&vertices), vec![0.0, 1.0, 2.0, 3.0]);
+        let calls: Vec<_> = draw_calls.iter().map(|call| (call.blend_mode, call.indices.clone())).collect();
+        assert_eq!(calls, vec![(BlendMode::AlphaBlend, 0..12), (BlendMode::Additive, 12..24)]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Number of frames `FrameStatsHistory` averages over
+pub const FRAME_STATS_HISTORY: usize = 120;
+
+/// Work done by the renderer during one frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    /// CPU time since the previous frame ended
+    pub frame_time: Duration,
+    /// Command buffers submitted to the GPU
+    pub submits: u32,
+    /// Draw calls issued for meshes, shadows and sprites
+    pub draw_calls: u32,
+    /// Triangles drawn by those draw calls
+    pub triangles: u32,
+    /// Sprites flushed from the sprite batch
+    pub sprites: u32,
+    /// Models and instances skipped for being outside a view's frustum, counted once per view
+    pub culled: u32,
+    /// Copies of meshes drawn by instanced draw calls
+    pub instances: u32,
+}
+
+impl FrameStats {
+    /// Frames per second at this frame time, 0 before the first full frame
+    pub fn fps(&self) -> f32 {
+        let seconds = self.frame_time.as_secs_f32();
+        if seconds > 0.0 { 1.0 / seconds } else { 0.0 }
+    }
+
+    /// Count a draw call of `triangles` triangles
+    pub fn record_draw(&mut self, triangles: u32) {
+        self.draw_calls += 1;
+        self.triangles += triangles;
+    }
+
+    /// Count an instanced draw call of `instances` copies of a `triangles` triangle mesh
+    pub fn record_instanced_draw(&mut self, triangles: u32, instances: u32) {
+        self.draw_calls += 1;
+        self.triangles += triangles * instances;
+        self.instances += instances;
+    }
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "FPS: {:.1} | Frame: {:.2} ms | Draw calls: {} | Triangles: {} | Instances: {} | Sprites: {} | Culled: {} | Submits: {}",
+            self.fps(),
+            self.frame_time.as_secs_f64() * 1000.0,
+            self.draw_calls,
+            self.triangles,
+            self.instances,
+            self.sprites,
+            self.culled,
+            self.submits,
+        )
+    }
+}
+
+/// Ring buffer of the stats of the last `FRAME_STATS_HISTORY` frames
+pub struct FrameStatsHistory {
+    frames: [FrameStats; FRAME_STATS_HISTORY],
+    /// Slot the next frame is written to
+    next: usize,
+    /// Number of slots holding a frame
+    len: usize,
+}
+
+impl FrameStatsHistory {
+    pub fn new() -> Self {
+        Self {
+            frames: [FrameStats::default(); FRAME_STATS_HISTORY],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a frame, replacing the oldest one once the buffer is full
+    pub fn push(&mut self, stats: FrameStats) {
+        self.frames[self.next] = stats;
+        self.next = (self.next + 1) % FRAME_STATS_HISTORY;
+        self.len = (self.len + 1).min(FRAME_STATS_HISTORY);
+    }
+
+    /// The most recent frame, or empty stats before the first one
+    pub fn latest(&self) -> FrameStats {
+        if self.len == 0 {
+            return FrameStats::default();
+        }
+        self.frames[(self.next + FRAME_STATS_HISTORY - 1) % FRAME_STATS_HISTORY]
+    }
+
+    /// Mean of every field over the recorded frames
+    pub fn average(&self) -> FrameStats {
+        if self.len == 0 {
+            return FrameStats::default();
+        }
+
+        let frames = &self.frames[..self.len];
+        let count = self.len as u32;
+        let mean = |field: fn(&FrameStats) -> u32| {
+            (frames.iter().map(|frame| field(frame) as u64).sum::<u64>() / self.len as u64) as u32
+        };
+
+        FrameStats {
+            frame_time: frames.iter().map(|frame| frame.frame_time).sum::<Duration>() / count,
+            submits: mean(|frame| frame.submits),
+            draw_calls: mean(|frame| frame.draw_calls),
+            triangles: mean(|frame| frame.triangles),
+            sprites: mean(|frame| frame.sprites),
+            culled: mean(|frame| frame.culled),
+            instances: mean(|frame| frame.instances),
+        }
+    }
+}
+
+impl Default for FrameStatsHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use anyhow::{anyhow, Context, Result};
+use glam::Vec2;
+use image::{DynamicImage, Rgba, RgbaImage};
+use log::warn;
+use serde::{Serialize, Deserialize};
+use wgpu::{Device, FilterMode, Queue};
+use crate::rendering::{SpriteInstance, SpriteUv, Texture, TextureHandle, TextureSampler};
+
+/// Width and height of the glyph atlas texture in pixels
+pub const GLYPH_ATLAS_SIZE: u32 = 1024;
+
+/// Empty pixels around each glyph in the atlas, so filtering doesn't bleed between glyphs
+const GLYPH_PADDING: u32 = 1;
+
+/// Font used when no other font is set, DejaVu Sans
+static EMBEDDED_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// A TrueType or OpenType font, cheap to clone
+#[derive(Clone)]
+pub struct Font {
+    inner: Arc<fontdue::Font>,
+    /// Size of the font file in bytes
+    data_size: usize,
+}
+
+impl Font {
+    /// Load a font file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read font {}", path.display()))?;
+        Self::from_bytes(&bytes)
+            .with_context(|| format!("Failed to parse font {}", path.display()))
+    }
+
+    /// Parse a font from the contents of a font file
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(|e| anyhow!(e))?;
+        Ok(Self { inner: Arc::new(font), data_size: bytes.len() })
+    }
+
+    /// The font built into the engine, so text works without any assets
+    pub fn embedded() -> Self {
+        static FONT: OnceLock<Font> = OnceLock::new();
+        FONT.get_or_init(|| Self::from_bytes(EMBEDDED_FONT).expect("embedded font is valid"))
+            .clone()
+    }
+
+    /// Size of the font file the font was parsed from
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+
+    /// Distance between the baselines of two lines at `size` pixels
+    pub fn line_height(&self, size: f32) -> f32 {
+        self.inner.horizontal_line_metrics(size)
+            .map_or(size * 1.2, |metrics| metrics.new_line_size)
+    }
+
+    /// Distance from the top of a line to its baseline at `size` pixels
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.inner.horizontal_line_metrics(size)
+            .map_or(size * 0.8, |metrics| metrics.ascent)
+    }
+
+    /// Pixel width of a single line of text, including kerning
+    pub fn line_width(&self, line: &str, size: f32) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for character in line.chars() {
+            if let Some(previous) = previous {
+                width += self.inner.horizontal_kern(previous, character, size).unwrap_or(0.0);
+            }
+            width += self.inner.metrics(character, size).advance_width;
+            previous = Some(character);
+        }
+        width
+    }
+
+    /// Pixel size of the block `text` takes up, see `layout`
+    pub fn measure(&self, text: &str, size: f32, max_width: Option<f32>) -> Vec2 {
+        let lines = self.wrap(text, size, max_width);
+        let width = lines.iter().map(|line| self.line_width(line, size)).fold(0.0, f32::max);
+        Vec2::new(width, lines.len() as f32 * self.line_height(size))
+    }
+
+    /// Split text into lines at newlines and, with a `max_width`, between words
+    ///
+    /// A word wider than `max_width` gets a line of its own rather than being split.
+    pub fn wrap<'a>(&self, text: &'a str, size: f32, max_width: Option<f32>) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+            let Some(max_width) = max_width else {
+                lines.push(paragraph);
+                continue;
+            };
+
+            // `end` is where the words that fit on the current line end
+            let mut start = 0;
+            let mut end = 0;
+            let breaks = paragraph.match_indices(' ').map(|(index, _)| index).chain([paragraph.len()]);
+            for index in breaks {
+                if end > start && self.line_width(&paragraph[start..index], size) > max_width {
+                    lines.push(&paragraph[start..end]);
+                    // The space the line broke at belongs to neither line
+                    start = end + 1;
+                }
+                end = index;
+            }
+            lines.push(&paragraph[start..]);
+        }
+        lines
+    }
+
+    /// Place the glyphs of `text` as described by `style`
+    ///
+    /// Positions are in pixels with y pointing up, relative to the anchor at the top
+    /// of the first line. The anchor is the left end, center or right end of each
+    /// line depending on the alignment.
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        let size = style.font_size;
+        let line_height = self.line_height(size);
+        let lines = self.wrap(text, size, style.max_width);
+
+        let mut glyphs = Vec::new();
+        let mut width: f32 = 0.0;
+        for (index, line) in lines.iter().enumerate() {
+            let line_width = self.line_width(line, size);
+            width = width.max(line_width);
+
+            let baseline = -self.ascent(size) - index as f32 * line_height;
+            let mut pen = -line_width * style.align.offset();
+            let mut previous = None;
+            for character in line.chars() {
+                if let Some(previous) = previous {
+                    pen += self.inner.horizontal_kern(previous, character, size).unwrap_or(0.0);
+                }
+                let metrics = self.inner.metrics(character, size);
+                // Spaces and control characters only move the pen
+                if metrics.width > 0 && metrics.height > 0 {
+                    glyphs.push(PositionedGlyph {
+                        character,
+                        min: Vec2::new(pen + metrics.xmin as f32, baseline + metrics.ymin as f32),
+                        size: Vec2::new(metrics.width as f32, metrics.height as f32),
+                    });
+                }
+                pen += metrics.advance_width;
+                previous = Some(character);
+            }
+        }
+
+        TextLayout {
+            glyphs,
+            size: Vec2::new(width, lines.len() as f32 * line_height),
+        }
+    }
+}
+
+/// Horizontal alignment of each line relative to the text's anchor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextAlign {
+    /// Lines start at the anchor
+    #[default]
+    Left,
+    /// Lines are centered on the anchor
+    Center,
+    /// Lines end at the anchor
+    Right,
+}
+
+impl TextAlign {
+    pub const ALL: [TextAlign; 3] = [TextAlign::Left, TextAlign::Center, TextAlign::Right];
+
+    /// Name shown in the inspector
+    pub fn label(self) -> &'static str {
+        match self {
+            TextAlign::Left => "Left",
+            TextAlign::Center => "Center",
+            TextAlign::Right => "Right",
+        }
+    }
+
+    /// Fraction of the line's width that lies left of the anchor
+    fn offset(self) -> f32 {
+        match self {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => 0.5,
+            TextAlign::Right => 1.0,
+        }
+    }
+}
+
+/// How a piece of text is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// Height of the font in pixels
+    pub font_size: f32,
+    /// Color of the glyphs
+    pub color: [f32; 4],
+    pub align: TextAlign,
+    /// Width in pixels at which lines wrap between words, `None` only breaks at newlines
+    pub max_width: Option<f32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 32.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: TextAlign::Left,
+            max_width: None,
+        }
+    }
+}
+
+/// A glyph placed by `Font::layout`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub character: char,
+    /// Bottom-left corner of the glyph's bitmap
+    pub min: Vec2,
+    /// Size of the glyph's bitmap in pixels
+    pub size: Vec2,
+}
+
+/// Glyphs of a piece of text placed by `Font::layout`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    /// Width of the widest line and height of all lines
+    pub size: Vec2,
+}
+
+/// Where text is drawn: the anchor's position, and the scale and rotation around it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextPlacement {
+    pub position: Vec2,
+    pub scale: Vec2,
+    /// Rotation around the anchor in radians
+    pub rotation: f32,
+}
+
+impl TextPlacement {
+    /// Unscaled, unrotated text anchored at `position`
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            scale: Vec2::ONE,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+}
+
+/// Rasterized glyphs packed into rows of an image
+///
+/// Glyphs are rasterized the first time they're drawn at a size and kept until the
+/// font changes. Once the atlas is full, new glyphs aren't drawn.
+struct GlyphAtlas {
+    pixels: RgbaImage,
+    /// Texture coordinates by character and font size bits, `None` if the glyph didn't fit
+    glyphs: HashMap<(char, u32), Option<SpriteUv>>,
+    /// Top-left corner of the next glyph in the current row
+    cursor: (u32, u32),
+    /// Height of the tallest glyph in the current row
+    row_height: u32,
+    /// Whether glyphs were added since the last upload
+    dirty: bool,
+}
+
+impl GlyphAtlas {
+    fn new() -> Self {
+        Self {
+            pixels: RgbaImage::from_pixel(GLYPH_ATLAS_SIZE, GLYPH_ATLAS_SIZE, Rgba([255, 255, 255, 0])),
+            glyphs: HashMap::new(),
+            cursor: (GLYPH_PADDING, GLYPH_PADDING),
+            row_height: 0,
+            dirty: true,
+        }
+    }
+
+    /// Texture coordinates of a glyph, rasterizing it if it isn't in the atlas yet
+    fn glyph(&mut self, font: &Font, character: char, size: f32) -> Option<SpriteUv> {
+        if let Some(uv) = self.glyphs.get(&(character, size.to_bits())) {
+            return *uv;
+        }
+
+        let (metrics, coverage) = font.inner.rasterize(character, size);
+        let uv = self.insert(metrics.width as u32, metrics.height as u32, &coverage);
+        if uv.is_none() {
+            warn!("Glyph atlas is full, '{}' at {}px is not drawn", character, size);
+        }
+        self.glyphs.insert((character, size.to_bits()), uv);
+        uv
+    }
+
+    /// Copy a coverage bitmap into the alpha channel of a free spot
+    fn insert(&mut self, width: u32, height: u32, coverage: &[u8]) -> Option<SpriteUv> {
+        if self.cursor.0 + width + GLYPH_PADDING > GLYPH_ATLAS_SIZE {
+            self.cursor = (GLYPH_PADDING, self.cursor.1 + self.row_height + GLYPH_PADDING);
+            self.row_height = 0;
+        }
+        if self.cursor.0 + width + GLYPH_PADDING > GLYPH_ATLAS_SIZE
+            || self.cursor.1 + height + GLYPH_PADDING > GLYPH_ATLAS_SIZE {
+            return None;
+        }
+
+        let (x, y) = self.cursor;
+        for row in 0..height {
+            for column in 0..width {
+                let alpha = coverage[(row * width + column) as usize];
+                self.pixels.put_pixel(x + column, y + row, Rgba([255, 255, 255, alpha]));
+            }
+        }
+        self.cursor.0 += width + GLYPH_PADDING;
+        self.row_height = self.row_height.max(height);
+        self.dirty = true;
+
+        let atlas_size = GLYPH_ATLAS_SIZE as f32;
+        Some(SpriteUv {
+            min: Vec2::new(x as f32, y as f32) / atlas_size,
+            max: Vec2::new((x + width) as f32, (y + height) as f32) / atlas_size,
+        })
+    }
+}
+
+/// Turns text into sprites of glyphs from a glyph atlas texture
+pub struct TextRenderer {
+    font: Font,
+    atlas: GlyphAtlas,
+    texture: Texture,
+}
+
+impl TextRenderer {
+    /// Create a text renderer using the embedded font
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let atlas = GlyphAtlas::new();
+        let sampler = TextureSampler {
+            filter: FilterMode::Linear,
+            ..Default::default()
+        };
+        let texture = Texture::with_sampler(device, queue, &DynamicImage::ImageRgba8(atlas.pixels.clone()), sampler, Some("Glyph Atlas"))
+            .expect("glyph atlas texture is valid");
+
+        Self {
+            font: Font::embedded(),
+            atlas,
+            texture,
+        }
+    }
+
+    /// The glyph atlas, register it with the sprite renderers that draw text
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Draw text with another font, this empties the glyph atlas
+    pub fn set_font(&mut self, font: Font) {
+        self.font = font;
+        self.atlas = GlyphAtlas::new();
+    }
+
+    /// Sprites for the glyphs of `text`, sampling the atlas registered as `texture`
+    ///
+    /// Glyphs are laid out in pixels, which `placement` scales, rotates and moves.
+    pub fn sprites(&mut self, text: &str, style: &TextStyle, placement: TextPlacement, texture: TextureHandle) -> Vec<SpriteInstance> {
+        let layout = self.font.layout(text, style);
+        let (sin, cos) = placement.rotation.sin_cos();
+
+        layout.glyphs.iter()
+            .filter_map(|glyph| {
+                let uv = self.atlas.glyph(&self.font, glyph.character, style.font_size)?;
+                let local = (glyph.min + glyph.size * 0.5) * placement.scale;
+                let rotated = Vec2::new(local.x * cos - local.y * sin, local.x * sin + local.y * cos);
+                Some(
+                    SpriteInstance::new(placement.position + rotated, glyph.size * placement.scale, texture)
+                        .with_uv(uv)
+                        .with_rotation(placement.rotation)
+                        .with_tint(style.color)
+                )
+            })
+            .collect()
+    }
+
+    /// Upload the glyphs rasterized since the last upload
+    pub fn upload(&mut self, queue: &Queue) {
+        if self.atlas.dirty {
+            self.texture.write_pixels(queue, &self.atlas.pixels);
+            self.atlas.dirty = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 20.0;
+
+    #[test]
+    fn width_grows_with_the_text() {
+        let font = Font::embedded();
+        assert_eq!(font.line_width("", SIZE), 0.0);
+
+        let a = font.line_width("Hello", SIZE);
+        let b = font.line_width("Hello, world", SIZE);
+        assert!(a > 0.0 && b > a);
+        assert!((font.line_width("Hello", 2.0 * SIZE) - 2.0 * a).abs() < 0.5);
+
+        let measured = font.measure("Hello\nHello, world", SIZE, None);
+        assert_eq!(measured, Vec2::new(b, 2.0 * font.line_height(SIZE)));
+    }
+
+    #[test]
+    fn wraps_between_words_that_fit() {
+        let font = Font::embedded();
+        let text = "ab ab ab ab ab";
+        let max_width = font.line_width("ab ab", SIZE) + 1.0;
+
+        assert_eq!(font.wrap(text, SIZE, None), vec![text]);
+        assert_eq!(font.wrap(text, SIZE, Some(max_width)), vec!["ab ab", "ab ab", "ab"]);
+        for line in font.wrap(text, SIZE, Some(max_width)) {
+            assert!(font.line_width(line, SIZE) <= max_width);
+        }
+    }
+
+    #[test]
+    fn long_words_and_newlines_get_their_own_lines() {
+        let font = Font::embedded();
+        let max_width = font.line_width("tiny", SIZE);
+
+        assert_eq!(font.wrap("a enormousword b", SIZE, Some(max_width)), vec!["a", "enormousword", "b"]);
+        assert_eq!(font.wrap("one\r\ntwo\n", SIZE, Some(1000.0)), vec!["one", "two", ""]);
+    }
+
+    #[test]
+    fn layout_matches_measure() {
+        let font = Font::embedded();
+        let style = TextStyle { font_size: SIZE, max_width: Some(60.0), ..Default::default() };
+        let text = "some wrapped text here";
+
+        let layout = font.layout(text, &style);
+        assert_eq!(layout.size, font.measure(text, SIZE, style.max_width));
+        // Spaces move the pen without a glyph
+        assert_eq!(layout.glyphs.len(), text.chars().filter(|c| *c != ' ').count());
+    }
+}
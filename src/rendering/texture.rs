@@ -1,11 +1,125 @@
 use std::path::Path;
-use anyhow::Result;
-use image::{GenericImageView, DynamicImage};
+use anyhow::{anyhow, Result};
+use image::{GenericImageView, DynamicImage, RgbaImage};
+use image::imageops::FilterType;
+use log::info;
 use wgpu::{
     Device, Queue, Texture as WgpuTexture, TextureView, Sampler, TextureUsages,
     TextureDescriptor, Extent3d, TextureDimension, TextureViewDescriptor,
-    SamplerDescriptor, FilterMode, AddressMode, CompareFunction, TextureFormat,
+    SamplerDescriptor, FilterMode, AddressMode, CompareFunction, TextureFormat, Features,
 };
+use crate::rendering::texture_compression::{self, BLOCK_BYTES, BLOCK_SIZE};
+
+/// Whether a texture's pixels are colors or data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Colors, decoded from sRGB when sampled
+    #[default]
+    Srgb,
+    /// Data such as normal maps and masks, sampled as stored
+    Linear,
+}
+
+/// Format `Texture::load` stores images in, if the device supports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureFormatPreference {
+    /// 8 bits per channel, supported everywhere
+    #[default]
+    Uncompressed,
+    /// BC3, a quarter of the memory, quick to encode
+    Bc3,
+    /// BC7, a quarter of the memory, better quality than BC3
+    Bc7,
+}
+
+impl TextureFormatPreference {
+    /// The format to store `img` in on a device with `features`
+    ///
+    /// Single-channel data images, like masks, are stored as R8. Compressed
+    /// formats need `TEXTURE_COMPRESSION_BC` and a size in whole 4x4 blocks,
+    /// otherwise images are stored as 8-bit RGBA.
+    pub fn select(self, img: &DynamicImage, color_space: ColorSpace, features: Features) -> TextureFormat {
+        let single_channel = matches!(img, DynamicImage::ImageLuma8(_) | DynamicImage::ImageLuma16(_));
+        if single_channel && color_space == ColorSpace::Linear {
+            return TextureFormat::R8Unorm;
+        }
+
+        let (width, height) = img.dimensions();
+        let compressible = features.contains(Features::TEXTURE_COMPRESSION_BC)
+            && width % BLOCK_SIZE == 0
+            && height % BLOCK_SIZE == 0;
+        match (self, compressible, color_space) {
+            (TextureFormatPreference::Bc3, true, ColorSpace::Srgb) => TextureFormat::Bc3RgbaUnormSrgb,
+            (TextureFormatPreference::Bc3, true, ColorSpace::Linear) => TextureFormat::Bc3RgbaUnorm,
+            (TextureFormatPreference::Bc7, true, ColorSpace::Srgb) => TextureFormat::Bc7RgbaUnormSrgb,
+            (TextureFormatPreference::Bc7, true, ColorSpace::Linear) => TextureFormat::Bc7RgbaUnorm,
+            (_, _, ColorSpace::Srgb) => TextureFormat::Rgba8UnormSrgb,
+            (_, _, ColorSpace::Linear) => TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+/// How a texture is sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureSampler {
+    /// Filtering when magnified, minified and between mip levels; `Nearest` keeps pixel art sharp
+    pub filter: FilterMode,
+    /// Horizontal wrapping; `Repeat` tiles the texture
+    pub wrap_u: AddressMode,
+    /// Vertical wrapping
+    pub wrap_v: AddressMode,
+    /// Generate the mip chain when loading
+    pub mipmaps: bool,
+}
+
+impl Default for TextureSampler {
+    fn default() -> Self {
+        Self {
+            filter: FilterMode::Linear,
+            wrap_u: AddressMode::ClampToEdge,
+            wrap_v: AddressMode::ClampToEdge,
+            mipmaps: false,
+        }
+    }
+}
+
+impl TextureSampler {
+    /// Create the WGPU sampler
+    pub fn create_sampler(&self, device: &Device) -> Sampler {
+        device.create_sampler(&SamplerDescriptor {
+            address_mode_u: self.wrap_u,
+            address_mode_v: self.wrap_v,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: self.filter,
+            ..Default::default()
+        })
+    }
+}
+
+/// Format of the depth buffer models and sprites are tested against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthFormat {
+    /// 16-bit, half the memory, but distant surfaces start to fight
+    Depth16Unorm,
+    /// At least 24 bits, the backend picks the exact format
+    Depth24Plus,
+    /// 32-bit float, the most precise
+    #[default]
+    Depth32Float,
+}
+
+impl DepthFormat {
+    /// The texture format of the depth buffer
+    pub const fn texture_format(self) -> TextureFormat {
+        match self {
+            DepthFormat::Depth16Unorm => TextureFormat::Depth16Unorm,
+            DepthFormat::Depth24Plus => TextureFormat::Depth24Plus,
+            DepthFormat::Depth32Float => TextureFormat::Depth32Float,
+        }
+    }
+}
 
 /// A texture with a view and sampler
 pub struct Texture {
@@ -22,8 +136,8 @@ pub struct Texture {
 }
 
 impl Texture {
-    /// The format to use for depth textures
-    pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+    /// The format of shadow maps, and of the depth buffer unless `RendererConfig` picks another
+    pub const DEPTH_FORMAT: TextureFormat = DepthFormat::Depth32Float.texture_format();
 
     /// Create a new texture from raw data
     pub fn from_bytes(
@@ -49,27 +163,113 @@ impl Texture {
         Self::from_image(device, queue, &img, Some(label_str))
     }
 
-    /// Create a new texture from an image
+    /// Load an image file in the format closest to `preference` the device supports
+    ///
+    /// Colors are stored as sRGB and data like normal maps as linear, see
+    /// `TextureFormatPreference::select`. The chosen format is logged and kept in `format`.
+    pub fn load(
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+        sampler: TextureSampler,
+        color_space: ColorSpace,
+        preference: TextureFormatPreference,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let img = image::open(path)?;
+        let format = preference.select(&img, color_space, device.features());
+        let texture = Self::with_format(device, queue, &img, sampler, format, Some(path.to_string_lossy().as_ref()))?;
+        info!("Loaded texture {} as {:?}", path.display(), format);
+        Ok(texture)
+    }
+
+    /// Create a new texture from an image, with linear filtering and clamped edges
     pub fn from_image(
         device: &Device,
         queue: &Queue,
         img: &DynamicImage,
         label: Option<&str>,
+    ) -> Result<Self> {
+        Self::with_sampler(device, queue, img, TextureSampler::default(), label)
+    }
+
+    /// Create a new texture from an image, sampled as described by `sampler`
+    ///
+    /// With `sampler.mipmaps` the mip chain is generated on the CPU, which works for
+    /// any size, not just powers of two.
+    pub fn with_sampler(
+        device: &Device,
+        queue: &Queue,
+        img: &DynamicImage,
+        sampler: TextureSampler,
+        label: Option<&str>,
+    ) -> Result<Self> {
+        Self::with_format(device, queue, img, sampler, TextureFormat::Rgba8UnormSrgb, label)
+    }
+
+    /// Create the magenta and black checkerboard drawn in place of a texture that failed to load
+    pub fn missing(device: &Device, queue: &Queue) -> Self {
+        const CHECKERS: u32 = 8;
+        const MAGENTA: image::Rgba<u8> = image::Rgba([255, 0, 255, 255]);
+        const BLACK: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+        let pixels = RgbaImage::from_fn(CHECKERS, CHECKERS, |x, y| if (x + y) % 2 == 0 { MAGENTA } else { BLACK });
+        let sampler = TextureSampler {
+            filter: FilterMode::Nearest,
+            wrap_u: AddressMode::Repeat,
+            wrap_v: AddressMode::Repeat,
+            mipmaps: false,
+        };
+        Self::with_sampler(device, queue, &DynamicImage::ImageRgba8(pixels), sampler, Some("Missing Texture"))
+            .expect("placeholder texture is valid")
+    }
+
+    /// Create a single white pixel, for sprites drawn in their tint alone
+    pub fn white(device: &Device, queue: &Queue) -> Self {
+        let pixels = RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+        Self::with_sampler(device, queue, &DynamicImage::ImageRgba8(pixels), TextureSampler::default(), Some("White Texture"))
+            .expect("white texture is valid")
+    }
+
+    /// Create a texture whose pixels are data rather than colors, like a normal map
+    ///
+    /// The pixels are stored as they are, without the sRGB decoding colors go through.
+    pub fn linear_from_image(
+        device: &Device,
+        queue: &Queue,
+        img: &DynamicImage,
+        sampler: TextureSampler,
+        label: Option<&str>,
+    ) -> Result<Self> {
+        Self::with_format(device, queue, img, sampler, TextureFormat::Rgba8Unorm, label)
+    }
+
+    /// Upload an image as `format`, one of the formats `TextureFormatPreference::select` picks
+    fn with_format(
+        device: &Device,
+        queue: &Queue,
+        img: &DynamicImage,
+        sampler: TextureSampler,
+        format: TextureFormat,
+        label: Option<&str>,
     ) -> Result<Self> {
         let rgba = img.to_rgba8();
         let dimensions = img.dimensions();
+        let mip_level_count = if sampler.mipmaps {
+            mip_level_count(dimensions.0, dimensions.1)
+        } else {
+            1
+        };
 
         let size = wgpu::Extent3d {
             width: dimensions.0,
             height: dimensions.1,
             depth_or_array_layers: 1,
         };
-        let format = TextureFormat::Rgba8UnormSrgb;
 
         let texture = device.create_texture(&TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: TextureDimension::D2,
             format,
@@ -77,32 +277,73 @@ impl Texture {
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
+        write_encoded_level(queue, &texture, format, 0, &rgba);
+        for level in 1..mip_level_count {
+            let width = (dimensions.0 >> level).max(1);
+            let height = (dimensions.1 >> level).max(1);
+            let mip = image::imageops::resize(&rgba, width, height, FilterType::Triangle);
+            write_encoded_level(queue, &texture, format, level, &mip);
+        }
 
         let view = texture.create_view(&TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&SamplerDescriptor {
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            mipmap_filter: FilterMode::Linear,
+        let sampler = sampler.create_sampler(device);
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            size: dimensions,
+            format,
+        })
+    }
+
+    /// Load six square images of the same size into a cube texture
+    ///
+    /// Faces are in the order +X, -X, +Y, -Y, +Z, -Z. The view is a cube view, for
+    /// sampling by direction.
+    pub fn load_cubemap<P: AsRef<Path>>(
+        device: &Device,
+        queue: &Queue,
+        faces: [P; 6],
+        label: Option<&str>,
+    ) -> Result<Self> {
+        let images = faces.iter()
+            .map(|path| image::open(path).map(|img| img.to_rgba8()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let dimensions = images[0].dimensions();
+        if dimensions.0 != dimensions.1 {
+            return Err(anyhow!("Cubemap faces must be square, got {}x{}", dimensions.0, dimensions.1));
+        }
+        if let Some((i, _)) = images.iter().enumerate().find(|(_, face)| face.dimensions() != dimensions) {
+            return Err(anyhow!("Cubemap face {} is not {}x{} like the first face", i, dimensions.0, dimensions.1));
+        }
+
+        let format = TextureFormat::Rgba8UnormSrgb;
+        let texture = device.create_texture(&TextureDescriptor {
+            label,
+            size: Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, face) in images.iter().enumerate() {
+            write_layer(queue, &texture, layer as u32, face);
+        }
+
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
             ..Default::default()
         });
+        let sampler = TextureSampler::default().create_sampler(device);
 
         Ok(Self {
             texture,
@@ -113,11 +354,15 @@ impl Texture {
         })
     }
 
-    /// Create a new depth texture
+    /// Create a new depth texture of a depth `format`
+    ///
+    /// Multisampled depth textures can only be used as render attachments.
     pub fn create_depth_texture(
         device: &Device,
         width: u32,
         height: u32,
+        format: TextureFormat,
+        sample_count: u32,
         label: &str,
     ) -> Self {
         let size = Extent3d {
@@ -129,10 +374,14 @@ impl Texture {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: TextureDimension::D2,
-            format: Self::DEPTH_FORMAT,
-            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            format,
+            usage: if sample_count > 1 {
+                TextureUsages::RENDER_ATTACHMENT
+            } else {
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+            },
             view_formats: &[],
         });
 
@@ -155,7 +404,188 @@ impl Texture {
             view,
             sampler,
             size: (width, height),
-            format: Self::DEPTH_FORMAT,
+            format,
+        }
+    }
+
+    /// Overwrite the top mip level of an 8-bit RGBA texture with an image of the texture's size
+    pub fn write_pixels(&self, queue: &Queue, pixels: &RgbaImage) {
+        write_mip_level(queue, &self.texture, 0, pixels);
+    }
+
+    /// Whether sampling decodes the pixels from sRGB, as for colors
+    pub fn is_srgb(&self) -> bool {
+        self.format.is_srgb()
+    }
+} 
+
+/// Number of mip levels down to 1x1 for a texture of the given size
+fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Upload one mip level of an RGBA8 texture
+fn write_mip_level(queue: &Queue, texture: &WgpuTexture, level: u32, pixels: &RgbaImage) {
+    write_image(queue, texture, level, wgpu::Origin3d::ZERO, pixels);
+}
+
+/// Upload one mip level of a texture, converting the pixels to its format
+fn write_encoded_level(queue: &Queue, texture: &WgpuTexture, format: TextureFormat, level: u32, pixels: &RgbaImage) {
+    let (width, height) = pixels.dimensions();
+    let (data, bytes_per_row, rows, extent) = match format {
+        TextureFormat::R8Unorm => {
+            let red: Vec<u8> = pixels.pixels().map(|pixel| pixel[0]).collect();
+            (red, width, height, (width, height))
+        }
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb
+        | TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => {
+            let blocks = if matches!(format, TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb) {
+                texture_compression::encode_bc3(pixels)
+            } else {
+                texture_compression::encode_bc7(pixels)
+            };
+            // Small mip levels are still stored as whole blocks
+            let (blocks_x, blocks_y) = (texture_compression::block_count(width), texture_compression::block_count(height));
+            (blocks, blocks_x * BLOCK_BYTES, blocks_y, (blocks_x * BLOCK_SIZE, blocks_y * BLOCK_SIZE))
+        }
+        _ => {
+            write_mip_level(queue, texture, level, pixels);
+            return;
+        }
+    };
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: level,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: Some(rows),
+        },
+        Extent3d {
+            width: extent.0,
+            height: extent.1,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Upload one array layer, e.g. a cubemap face, of an RGBA8 texture
+fn write_layer(queue: &Queue, texture: &WgpuTexture, layer: u32, pixels: &RgbaImage) {
+    write_image(queue, texture, 0, wgpu::Origin3d { x: 0, y: 0, z: layer }, pixels);
+}
+
+fn write_image(queue: &Queue, texture: &WgpuTexture, level: u32, origin: wgpu::Origin3d, pixels: &RgbaImage) {
+    let (width, height) = pixels.dimensions();
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: level,
+            origin,
+            aspect: wgpu::TextureAspect::All,
+        },
+        pixels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::Renderer;
+
+    /// A headless renderer, `None` on machines without any adapter
+    async fn headless() -> Option<Renderer> {
+        match Renderer::new_headless(4, 4, DepthFormat::default()).await {
+            Ok(renderer) => Some(renderer),
+            Err(e) => {
+                eprintln!("Skipping, no headless renderer: {}", e);
+                None
+            }
         }
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn mip_chain_goes_down_to_one_pixel() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(100, 37), 7);
+        assert_eq!(mip_level_count(1, 300), 9);
+        assert_eq!(mip_level_count(0, 0), 1);
+    }
+
+    #[tokio::test]
+    async fn npot_texture_with_mipmaps_loads() {
+        let Some(renderer) = headless().await else {
+            return;
+        };
+        let (device, queue) = (renderer.device(), renderer.queue());
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(100, 37, image::Rgba([200, 100, 50, 255])));
+        let sampler = TextureSampler { mipmaps: true, ..Default::default() };
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let texture = Texture::with_sampler(device, queue, &image, sampler, Some("NPOT Texture")).unwrap();
+        let error = device.pop_error_scope().await;
+
+        assert!(error.is_none(), "{:?}", error);
+        assert_eq!(texture.size, (100, 37));
+        assert_eq!(texture.texture.mip_level_count(), 7);
+    }
+
+    #[test]
+    fn color_space_picks_srgb_or_linear_formats() {
+        let color = DynamicImage::ImageRgba8(RgbaImage::new(8, 8));
+        let mask = DynamicImage::ImageLuma8(image::GrayImage::new(8, 8));
+        let uncompressed = TextureFormatPreference::Uncompressed;
+
+        assert_eq!(uncompressed.select(&color, ColorSpace::Srgb, Features::empty()), TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(uncompressed.select(&color, ColorSpace::Linear, Features::empty()), TextureFormat::Rgba8Unorm);
+        assert_eq!(uncompressed.select(&mask, ColorSpace::Linear, Features::empty()), TextureFormat::R8Unorm);
+        // A grayscale image of colors still needs the sRGB decode
+        assert_eq!(uncompressed.select(&mask, ColorSpace::Srgb, Features::empty()), TextureFormat::Rgba8UnormSrgb);
+    }
+
+    #[test]
+    fn compressed_formats_keep_the_color_space() {
+        let color = DynamicImage::ImageRgba8(RgbaImage::new(8, 8));
+        let bc = Features::TEXTURE_COMPRESSION_BC;
+
+        assert_eq!(TextureFormatPreference::Bc7.select(&color, ColorSpace::Srgb, bc), TextureFormat::Bc7RgbaUnormSrgb);
+        assert_eq!(TextureFormatPreference::Bc7.select(&color, ColorSpace::Linear, bc), TextureFormat::Bc7RgbaUnorm);
+        assert_eq!(TextureFormatPreference::Bc3.select(&color, ColorSpace::Srgb, bc), TextureFormat::Bc3RgbaUnormSrgb);
+        assert_eq!(TextureFormatPreference::Bc3.select(&color, ColorSpace::Linear, bc), TextureFormat::Bc3RgbaUnorm);
+
+        // Without the feature, or with a size that isn't whole blocks, the image stays uncompressed
+        assert_eq!(TextureFormatPreference::Bc7.select(&color, ColorSpace::Linear, Features::empty()), TextureFormat::Rgba8Unorm);
+        let odd = DynamicImage::ImageRgba8(RgbaImage::new(10, 8));
+        assert_eq!(TextureFormatPreference::Bc7.select(&odd, ColorSpace::Srgb, bc), TextureFormat::Rgba8UnormSrgb);
+    }
+
+    #[tokio::test]
+    async fn linear_textures_are_not_srgb() {
+        let Some(renderer) = headless().await else {
+            return;
+        };
+        let (device, queue) = (renderer.device(), renderer.queue());
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, image::Rgba([128, 128, 255, 255])));
+
+        let color = Texture::from_image(device, queue, &image, Some("Color")).unwrap();
+        let normal_map = Texture::linear_from_image(device, queue, &image, TextureSampler::default(), Some("Normal Map")).unwrap();
+        assert!(color.is_srgb());
+        assert!(!normal_map.is_srgb());
+    }
+}
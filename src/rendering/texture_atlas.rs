@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use glam::Vec2;
+use serde::{Serialize, Deserialize};
+use wgpu::{Device, Queue};
+use crate::rendering::Texture;
+
+/// An axis-aligned rectangle
+///
+/// Atlas regions use pixel coordinates, UV windows use normalized coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    /// Left edge
+    pub x: f32,
+    /// Top edge
+    pub y: f32,
+    /// Width of the rectangle
+    pub width: f32,
+    /// Height of the rectangle
+    pub height: f32,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+/// Normalized texture coordinates of a sprite within its texture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteUv {
+    /// Top-left texture coordinate
+    pub min: Vec2,
+    /// Bottom-right texture coordinate
+    pub max: Vec2,
+}
+
+impl SpriteUv {
+    /// The whole texture
+    pub const FULL: Self = Self {
+        min: Vec2::ZERO,
+        max: Vec2::ONE,
+    };
+
+    /// Map a coordinate in `[0, 1]` across the whole sprite into this window
+    pub fn map(&self, uv: Vec2) -> Vec2 {
+        self.min + (self.max - self.min) * uv
+    }
+}
+
+impl Default for SpriteUv {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl From<Rect> for SpriteUv {
+    fn from(rect: Rect) -> Self {
+        Self {
+            min: Vec2::new(rect.x, rect.y),
+            max: Vec2::new(rect.x + rect.width, rect.y + rect.height),
+        }
+    }
+}
+
+impl From<SpriteUv> for Rect {
+    fn from(uv: SpriteUv) -> Self {
+        Rect::new(uv.min.x, uv.min.y, uv.max.x - uv.min.x, uv.max.y - uv.min.y)
+    }
+}
+
+/// On-disk format of the JSON sidecar next to an atlas image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AtlasSidecar {
+    /// Named regions in pixel coordinates
+    regions: HashMap<String, Rect>,
+}
+
+/// A texture divided into named sprite regions
+pub struct TextureAtlas {
+    /// The atlas texture
+    pub texture: Texture,
+    /// Named regions in pixel coordinates
+    regions: HashMap<String, Rect>,
+}
+
+impl TextureAtlas {
+    /// Create an atlas from a texture and pixel regions
+    ///
+    /// Fails if any region lies outside the texture.
+    pub fn new(texture: Texture, regions: HashMap<String, Rect>) -> Result<Self> {
+        let (width, height) = (texture.size.0 as f32, texture.size.1 as f32);
+
+        for (name, rect) in &regions {
+            if rect.x < 0.0 || rect.y < 0.0 || rect.width <= 0.0 || rect.height <= 0.0
+                || rect.x + rect.width > width || rect.y + rect.height > height {
+                bail!(
+                    "Atlas region '{}' ({}, {}, {}x{}) is outside the {}x{} texture",
+                    name, rect.x, rect.y, rect.width, rect.height, width, height
+                );
+            }
+        }
+
+        Ok(Self { texture, regions })
+    }
+
+    /// Load an atlas image and its regions from the JSON sidecar with the same name
+    ///
+    /// `sprites.png` reads its regions from `sprites.json`.
+    pub fn from_file<P: AsRef<Path>>(device: &Device, queue: &Queue, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let sidecar_path = path.with_extension("json");
+
+        let sidecar = fs::read_to_string(&sidecar_path)
+            .with_context(|| format!("Failed to read atlas sidecar {}", sidecar_path.display()))?;
+        let sidecar: AtlasSidecar = serde_json::from_str(&sidecar)
+            .with_context(|| format!("Failed to parse atlas sidecar {}", sidecar_path.display()))?;
+
+        let texture = Texture::from_file(device, queue, path, None)?;
+        Self::new(texture, sidecar.regions)
+    }
+
+    /// Get the pixel rectangle of a region
+    pub fn pixel_rect(&self, name: &str) -> Option<Rect> {
+        self.regions.get(name).copied()
+    }
+
+    /// Get the texture coordinates of a region
+    pub fn region(&self, name: &str) -> Option<SpriteUv> {
+        let rect = self.regions.get(name)?;
+        let size = Vec2::new(self.texture.size.0 as f32, self.texture.size.1 as f32);
+
+        Some(SpriteUv {
+            min: Vec2::new(rect.x, rect.y) / size,
+            max: Vec2::new(rect.x + rect.width, rect.y + rect.height) / size,
+        })
+    }
+
+    /// Names of all regions
+    pub fn region_names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(|name| name.as_str())
+    }
+}
@@ -0,0 +1,193 @@
+use image::RgbaImage;
+
+/// Side of the square pixel blocks BC formats compress, in pixels
+pub const BLOCK_SIZE: u32 = 4;
+
+/// Size of one compressed BC3 or BC7 block, in bytes
+pub const BLOCK_BYTES: u32 = 16;
+
+/// Interpolation weights of BC7's 4-bit indices, out of 64
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Number of blocks covering a row or column of `pixels` pixels
+pub fn block_count(pixels: u32) -> u32 {
+    (pixels + BLOCK_SIZE - 1) / BLOCK_SIZE
+}
+
+/// Compress an image to BC3, blocks row by row
+///
+/// Colors use the corners of each block's color bounding box as endpoints,
+/// alpha its smallest and largest values.
+pub fn encode_bc3(image: &RgbaImage) -> Vec<u8> {
+    encode_blocks(image, bc3_block)
+}
+
+/// Compress an image to BC7, blocks row by row
+///
+/// Every block is stored in mode 6, one RGBA line with 16 steps, between the
+/// corners of the block's bounding box.
+pub fn encode_bc7(image: &RgbaImage) -> Vec<u8> {
+    encode_blocks(image, bc7_block)
+}
+
+fn encode_blocks(image: &RgbaImage, encode_block: fn(&[[u8; 4]; 16]) -> [u8; 16]) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let (blocks_x, blocks_y) = (block_count(width), block_count(height));
+    let mut data = Vec::with_capacity((blocks_x * blocks_y * BLOCK_BYTES) as usize);
+    for block_y in 0..blocks_y {
+        for block_x in 0..blocks_x {
+            data.extend_from_slice(&encode_block(&block_pixels(image, block_x, block_y)));
+        }
+    }
+    data
+}
+
+/// The pixels of a block row by row, repeating the edge pixels past the image's border
+fn block_pixels(image: &RgbaImage, block_x: u32, block_y: u32) -> [[u8; 4]; 16] {
+    let (width, height) = image.dimensions();
+    let mut pixels = [[0; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let x = (block_x * BLOCK_SIZE + i as u32 % BLOCK_SIZE).min(width - 1);
+        let y = (block_y * BLOCK_SIZE + i as u32 / BLOCK_SIZE).min(height - 1);
+        *pixel = image.get_pixel(x, y).0;
+    }
+    pixels
+}
+
+/// Smallest and largest value of each channel
+fn bounding_box(pixels: &[[u8; 4]; 16]) -> ([u8; 4], [u8; 4]) {
+    let mut min = [u8::MAX; 4];
+    let mut max = [u8::MIN; 4];
+    for pixel in pixels {
+        for channel in 0..4 {
+            min[channel] = min[channel].min(pixel[channel]);
+            max[channel] = max[channel].max(pixel[channel]);
+        }
+    }
+    (min, max)
+}
+
+/// Index of the palette entry closest to `pixel`, comparing the first `channels` channels
+fn nearest<const N: usize>(palette: &[[u8; 4]; N], pixel: [u8; 4], channels: usize) -> usize {
+    let distance = |color: &[u8; 4]| -> u32 {
+        (0..channels).map(|channel| {
+            let difference = color[channel] as i32 - pixel[channel] as i32;
+            (difference * difference) as u32
+        }).sum()
+    };
+    (0..N).min_by_key(|&i| distance(&palette[i])).unwrap_or(0)
+}
+
+fn bc3_block(pixels: &[[u8; 4]; 16]) -> [u8; 16] {
+    let (min, max) = bounding_box(pixels);
+    let mut block = [0; 16];
+
+    // Alpha, with the largest value first to select eight steps between the two
+    let (a0, a1) = (max[3], min[3]);
+    let mut alpha_palette = [[0; 4]; 8];
+    alpha_palette[0][3] = a0;
+    alpha_palette[1][3] = a1;
+    for step in 1..7 {
+        alpha_palette[step as usize + 1][3] = (((7 - step) * a0 as u32 + step * a1 as u32 + 3) / 7) as u8;
+    }
+    let mut alpha_indices = 0u64;
+    for (i, &pixel) in pixels.iter().enumerate() {
+        let index = if a0 == a1 { 0 } else { nearest(&alpha_palette, [0, 0, 0, pixel[3]], 4) };
+        alpha_indices |= (index as u64) << (3 * i);
+    }
+    block[0] = a0;
+    block[1] = a1;
+    block[2..8].copy_from_slice(&alpha_indices.to_le_bytes()[..6]);
+
+    // Color, always four steps between the endpoints in BC3
+    let (c0, c1) = (to_rgb565(max), to_rgb565(min));
+    let (e0, e1) = (from_rgb565(c0), from_rgb565(c1));
+    let lerp = |w: u32| -> [u8; 4] {
+        let mut color = [0; 4];
+        for channel in 0..3 {
+            color[channel] = ((e0[channel] as u32 * (3 - w) + e1[channel] as u32 * w + 1) / 3) as u8;
+        }
+        color
+    };
+    let color_palette = [e0, e1, lerp(1), lerp(2)];
+    let mut color_indices = 0u32;
+    for (i, &pixel) in pixels.iter().enumerate() {
+        color_indices |= (nearest(&color_palette, pixel, 3) as u32) << (2 * i);
+    }
+    block[8..10].copy_from_slice(&c0.to_le_bytes());
+    block[10..12].copy_from_slice(&c1.to_le_bytes());
+    block[12..16].copy_from_slice(&color_indices.to_le_bytes());
+    block
+}
+
+fn to_rgb565(color: [u8; 4]) -> u16 {
+    let quantize = |value: u8, max: u32| (value as u32 * max + 127) / 255;
+    ((quantize(color[0], 31) << 11) | (quantize(color[1], 63) << 5) | quantize(color[2], 31)) as u16
+}
+
+fn from_rgb565(color: u16) -> [u8; 4] {
+    let (r, g, b) = ((color >> 11) as u8 & 31, (color >> 5) as u8 & 63, color as u8 & 31);
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2), 255]
+}
+
+fn bc7_block(pixels: &[[u8; 4]; 16]) -> [u8; 16] {
+    let (min, max) = bounding_box(pixels);
+    let (mut q0, mut p0) = quantize_bc7_endpoint(min);
+    let (mut q1, mut p1) = quantize_bc7_endpoint(max);
+
+    let (e0, e1) = (bc7_endpoint(q0, p0), bc7_endpoint(q1, p1));
+    let mut palette = [[0; 4]; 16];
+    for (color, weight) in palette.iter_mut().zip(BC7_WEIGHTS) {
+        for channel in 0..4 {
+            color[channel] = (((64 - weight) * e0[channel] as u32 + weight * e1[channel] as u32 + 32) >> 6) as u8;
+        }
+    }
+    let mut indices = pixels.map(|pixel| nearest(&palette, pixel, 4) as u32);
+
+    // The first index is stored without its top bit, swapping the endpoints clears it
+    if indices[0] >= 8 {
+        std::mem::swap(&mut q0, &mut q1);
+        std::mem::swap(&mut p0, &mut p1);
+        for index in &mut indices {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut bits = 0u128;
+    let mut length = 0;
+    let mut write = |value: u32, count: u32| {
+        bits |= ((value & ((1 << count) - 1)) as u128) << length;
+        length += count;
+    };
+    // Mode 6 is six 0 bits followed by a 1
+    write(1 << 6, 7);
+    for channel in 0..4 {
+        write(q0[channel] as u32, 7);
+        write(q1[channel] as u32, 7);
+    }
+    write(p0 as u32, 1);
+    write(p1 as u32, 1);
+    write(indices[0], 3);
+    for &index in &indices[1..] {
+        write(index, 4);
+    }
+    bits.to_le_bytes()
+}
+
+/// 7-bit channels and the shared lowest bit closest to an RGBA color
+fn quantize_bc7_endpoint(color: [u8; 4]) -> ([u8; 4], u8) {
+    let [even, odd] = [0, 1].map(|p_bit: u8| {
+        let quantized = color.map(|value| ((value.saturating_sub(p_bit) as u32 + 1) / 2).min(127) as u8);
+        let error: u32 = bc7_endpoint(quantized, p_bit).iter().zip(color).map(|(&decoded, value)| {
+            let difference = decoded as i32 - value as i32;
+            (difference * difference) as u32
+        }).sum();
+        (quantized, p_bit, error)
+    });
+    let (quantized, p_bit, _) = if odd.2 < even.2 { odd } else { even };
+    (quantized, p_bit)
+}
+
+fn bc7_endpoint(quantized: [u8; 4], p_bit: u8) -> [u8; 4] {
+    quantized.map(|value| (value << 1) | p_bit)
+}
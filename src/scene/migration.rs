@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+use log::info;
+use serde::{Serialize, Deserialize};
+use crate::ecs::{
+    EntityHandle, NameComponent, ParentComponent, Transform2DComponent, Transform3DComponent,
+    PhysicsComponent, RenderableDescriptor, CameraComponent, LuaScriptComponent, Collider2DComponent,
+    TilemapComponent, SiblingOrderComponent,
+};
+use crate::scene::serialization::{EntityData, SceneError};
+
+/// Format version written by this build
+pub const SCENE_FORMAT_VERSION: u32 = 2;
+
+/// Version of files written before the version field existed
+pub const FIRST_SCENE_FORMAT_VERSION: u32 = 1;
+
+/// Upgrades scene and prefab files from one format version to the next
+pub struct SceneMigration {
+    /// Version this migration reads, it writes `from_version + 1`
+    pub from_version: u32,
+    /// What changed in the next version
+    pub description: &'static str,
+    /// Convert RON of `from_version` to RON of the next version
+    pub migrate: fn(&str) -> Result<String, SceneError>,
+}
+
+/// Every migration in version order, one for each version below `SCENE_FORMAT_VERSION`
+///
+/// Migrations write the current component types, so when a later version changes a
+/// component, the migrations before it need a copy of the old one.
+pub const SCENE_MIGRATIONS: &[SceneMigration] = &[
+    SceneMigration {
+        from_version: 1,
+        description: "entity `order` renamed to `sibling_order`",
+        migrate: migrate_v1_to_v2,
+    },
+];
+
+fn first_version() -> u32 {
+    FIRST_SCENE_FORMAT_VERSION
+}
+
+/// The version field of a file, the other fields are skipped
+#[derive(Deserialize)]
+struct VersionHeader {
+    #[serde(default = "first_version")]
+    version: u32,
+}
+
+/// Format version of a scene or prefab file
+pub fn format_version(source: &str) -> Result<u32, SceneError> {
+    Ok(ron::from_str::<VersionHeader>(source)?.version)
+}
+
+/// Bring RON of any supported version up to `SCENE_FORMAT_VERSION`
+///
+/// Current files are returned as they are. Versions newer than this build fail with
+/// `SceneError::UnsupportedVersion` instead of being misread.
+pub fn migrate_to_current(source: &str) -> Result<Cow<'_, str>, SceneError> {
+    let version = format_version(source)?;
+    if !(FIRST_SCENE_FORMAT_VERSION..=SCENE_FORMAT_VERSION).contains(&version) {
+        return Err(SceneError::UnsupportedVersion(version));
+    }
+
+    let mut source = Cow::Borrowed(source);
+    for migration in SCENE_MIGRATIONS.iter().filter(|migration| migration.from_version >= version) {
+        info!("Migrating scene from version {} to {}: {}", migration.from_version, migration.from_version + 1, migration.description);
+        source = Cow::Owned((migration.migrate)(&source)?);
+    }
+    Ok(source)
+}
+
+/// Scene or prefab file, both have a name and a list of entities
+#[derive(Deserialize)]
+struct DocumentV1 {
+    name: String,
+    entities: Vec<EntityDataV1>,
+}
+
+/// Version 1 entity, the sibling order was stored as `order`
+#[derive(Deserialize)]
+struct EntityDataV1 {
+    handle: EntityHandle,
+    #[serde(default)]
+    name: Option<NameComponent>,
+    #[serde(default)]
+    parent: Option<ParentComponent>,
+    #[serde(default)]
+    order: Option<SiblingOrderComponent>,
+    #[serde(default)]
+    transform_2d: Option<Transform2DComponent>,
+    #[serde(default)]
+    transform_3d: Option<Transform3DComponent>,
+    #[serde(default)]
+    physics: Option<PhysicsComponent>,
+    #[serde(default)]
+    collider_2d: Option<Collider2DComponent>,
+    #[serde(default)]
+    renderable: Option<RenderableDescriptor>,
+    #[serde(default)]
+    camera: Option<CameraComponent>,
+    #[serde(default)]
+    script: Option<LuaScriptComponent>,
+    #[serde(default)]
+    tilemap: Option<TilemapComponent>,
+}
+
+/// Version 2 scene or prefab file
+#[derive(Serialize)]
+struct DocumentV2 {
+    version: u32,
+    name: String,
+    entities: Vec<EntityData>,
+}
+
+fn migrate_v1_to_v2(source: &str) -> Result<String, SceneError> {
+    let document: DocumentV1 = ron::from_str(source)?;
+    let entities = document.entities.into_iter()
+        .map(|entity| EntityData {
+            handle: entity.handle,
+            name: entity.name,
+            parent: entity.parent,
+            sibling_order: entity.order,
+            layer: None,
+            active: None,
+            transform_2d: entity.transform_2d,
+            transform_3d: entity.transform_3d,
+            physics: entity.physics,
+            collider_2d: entity.collider_2d,
+            renderable: entity.renderable,
+            camera: entity.camera,
+            light: None,
+            audio_source: None,
+            script: entity.script,
+            tilemap: entity.tilemap,
+            text: None,
+        })
+        .collect();
+
+    let document = DocumentV2 {
+        version: 2,
+        name: document.name,
+        entities,
+    };
+    Ok(ron::ser::to_string_pretty(&document, ron::ser::PrettyConfig::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneData;
+
+    const V1_SCENE: &str = include_str!("fixtures/scene_v1.ron");
+
+    #[test]
+    fn v1_fixture_loads_into_the_current_version() {
+        assert_eq!(format_version(V1_SCENE).unwrap(), 1);
+
+        let scene = SceneData::from_ron(V1_SCENE).unwrap();
+        assert_eq!(scene.version, SCENE_FORMAT_VERSION);
+        assert_eq!(scene.name, "Version 1");
+        assert_eq!(scene.entities.len(), 2);
+
+        let root = &scene.entities[0];
+        assert_eq!(root.name.as_ref().unwrap().name, "Root");
+        assert_eq!(root.transform_3d.as_ref().unwrap().position, glam::Vec3::new(1.0, 2.0, 3.0));
+        assert!(root.sibling_order.is_none());
+
+        let child = &scene.entities[1];
+        assert_eq!(child.parent, Some(ParentComponent::new(root.handle)));
+        assert_eq!(child.sibling_order, Some(SiblingOrderComponent::new(4)));
+    }
+
+    #[test]
+    fn current_files_are_not_migrated() {
+        let current = SceneData::from_ron(V1_SCENE).unwrap().to_ron().unwrap();
+        assert_eq!(format_version(&current).unwrap(), SCENE_FORMAT_VERSION);
+        assert!(matches!(migrate_to_current(&current).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn future_versions_are_rejected() {
+        let future = format!("(version: {}, name: \"Future\", entities: [])", SCENE_FORMAT_VERSION + 1);
+        assert!(matches!(
+            SceneData::from_ron(&future),
+            Err(SceneError::UnsupportedVersion(version)) if version == SCENE_FORMAT_VERSION + 1
+        ));
+    }
+}
@@ -1,5 +1,15 @@
 mod scene;
 mod scene_manager;
+mod serialization;
+mod play_mode;
+mod migration;
+mod recovery;
 
 pub use scene::{Scene, SceneState};
-pub use scene_manager::SceneManager; 
\ No newline at end of file
+pub use scene_manager::SceneManager;
+pub use serialization::{SceneError, SceneHandle, SceneData, EntityData, save_world, load_world};
+pub use play_mode::{PlayMode, WorldSnapshot, PLAY_MODE_STEP};
+pub use recovery::{Autosaver, RecoveryData, RECOVERY_FILE_NAME, remove_recovery_file};
+pub use migration::{
+    SceneMigration, SCENE_MIGRATIONS, SCENE_FORMAT_VERSION, FIRST_SCENE_FORMAT_VERSION, format_version, migrate_to_current,
+};
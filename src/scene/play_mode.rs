@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use crate::core::DeltaTime;
+use crate::ecs::{EcsManager, EntityHandle, RenderableComponent, RenderableDescriptor};
+use crate::scene::SceneState;
+use crate::scene::serialization::{SceneData, SceneError};
+
+/// Copy of a world, serialized in memory, that can be restored later
+///
+/// Renderables keep their GPU resources instead of going through their descriptors,
+/// so restoring doesn't need a renderer.
+pub struct WorldSnapshot {
+    /// The world as a RON scene
+    scene: String,
+    /// Renderable components by entity
+    renderables: HashMap<EntityHandle, RenderableComponent>,
+}
+
+impl WorldSnapshot {
+    /// Capture every entity in the world
+    pub fn capture(ecs: &EcsManager) -> Result<Self, SceneError> {
+        let scene = SceneData::from_world("Snapshot", ecs).to_ron()?;
+        let renderables = ecs.query::<&RenderableComponent>().iter()
+            .map(|(handle, renderable)| (handle, renderable.clone()))
+            .collect();
+
+        Ok(Self { scene, renderables })
+    }
+
+    /// Replace the contents of the world with the snapshot
+    ///
+    /// Entities keep their handles, entities spawned since the capture are removed.
+    pub fn restore(&self, ecs: &mut EcsManager) -> Result<(), SceneError> {
+        let data = SceneData::from_ron(&self.scene)?;
+        data.apply_to_world(ecs);
+
+        // Put the live renderables back in place of the descriptors spawned from the scene
+        for (handle, renderable) in &self.renderables {
+            if let Some(entity) = ecs.get_entity(handle) {
+                let _ = ecs.world_mut().remove_one::<RenderableDescriptor>(entity);
+            }
+            ecs.insert_component(handle, renderable.clone());
+        }
+
+        // Trigger overlaps from the play session refer to entities that no longer exist
+        ecs.physics_mut().reset();
+        Ok(())
+    }
+}
+
+/// Delta time of a single step taken with `PlayMode::step` while paused
+pub const PLAY_MODE_STEP: f32 = 1.0 / 60.0;
+
+/// Editor play mode: snapshots the world on Play and restores it on Stop
+///
+/// The state is `SceneState::Editing`, `SceneState::Playing` or `SceneState::Paused`.
+pub struct PlayMode {
+    state: SceneState,
+    /// The world as it was when Play was pressed
+    snapshot: Option<WorldSnapshot>,
+    /// Scales the editor's frame time into the delta time systems run with
+    delta_time: DeltaTime,
+    /// Whether to run one `PLAY_MODE_STEP` on the next update while paused
+    step_requested: bool,
+}
+
+impl PlayMode {
+    pub fn new() -> Self {
+        Self {
+            state: SceneState::Editing,
+            snapshot: None,
+            delta_time: DeltaTime::new(),
+            step_requested: false,
+        }
+    }
+
+    pub fn state(&self) -> SceneState {
+        self.state
+    }
+
+    /// Whether the game is running or paused, as opposed to being edited
+    pub fn is_playing(&self) -> bool {
+        matches!(self.state, SceneState::Playing | SceneState::Paused)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == SceneState::Paused
+    }
+
+    /// Snapshot the world and start running its systems
+    ///
+    /// Resumes instead if the game is paused. The world is left untouched if the snapshot fails.
+    pub fn play(&mut self, ecs: &EcsManager) -> Result<(), SceneError> {
+        match self.state {
+            SceneState::Paused => self.state = SceneState::Playing,
+            SceneState::Playing => {}
+            _ => {
+                self.snapshot = Some(WorldSnapshot::capture(ecs)?);
+                self.state = SceneState::Playing;
+            }
+        }
+        Ok(())
+    }
+
+    /// Toggle between playing and paused, does nothing while editing
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            SceneState::Playing => SceneState::Paused,
+            SceneState::Paused => SceneState::Playing,
+            state => state,
+        };
+    }
+
+    /// Advance a paused game by one `PLAY_MODE_STEP` on the next update
+    pub fn step(&mut self) {
+        if self.is_paused() {
+            self.step_requested = true;
+        }
+    }
+
+    /// Speed the game runs at: 0 freezes it, 0.5 is half speed and 2 double speed
+    ///
+    /// Negative and non-finite scales are treated as 0. The scale is kept across Play and Stop.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.delta_time.set_time_scale(time_scale);
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.delta_time.time_scale()
+    }
+
+    /// Stop playing and restore the world from the snapshot taken by `play`
+    pub fn stop(&mut self, ecs: &mut EcsManager) -> Result<(), SceneError> {
+        if !self.is_playing() {
+            return Ok(());
+        }
+
+        self.state = SceneState::Editing;
+        self.step_requested = false;
+        match self.snapshot.take() {
+            Some(snapshot) => snapshot.restore(ecs),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the world's systems with the scaled delta time, unless editing, paused or at a time scale of 0
+    ///
+    /// A step requested while paused runs the systems once with `PLAY_MODE_STEP`.
+    pub fn update(&mut self, ecs: &mut EcsManager, delta_time: f32) {
+        let delta_time = self.delta_time.advance(delta_time);
+        match self.state {
+            SceneState::Playing if !self.delta_time.is_paused() => ecs.run_systems(delta_time),
+            SceneState::Paused if std::mem::take(&mut self.step_requested) => ecs.run_systems(PLAY_MODE_STEP),
+            _ => {}
+        }
+    }
+}
+
+impl Default for PlayMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use log::warn;
+use serde::{Serialize, Deserialize};
+use crate::scene::serialization::{SceneData, SceneError};
+use crate::utils::config::config_path;
+
+/// File the editor autosaves the scene to, in the editor's config directory
+pub const RECOVERY_FILE_NAME: &str = "recovery.ron";
+
+/// An autosaved scene, along with the file it was being edited from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryData {
+    /// File the scene was opened from or last saved to, `None` for a scene never saved
+    pub scene_path: Option<PathBuf>,
+    pub scene: SceneData,
+}
+
+impl RecoveryData {
+    /// Where the editor keeps its recovery file, `None` if the OS has no config directory
+    pub fn default_path() -> Option<PathBuf> {
+        config_path(RECOVERY_FILE_NAME)
+    }
+
+    /// Write the recovery file to `path`
+    ///
+    /// The file is written next to `path` first and then moved over it, so a crash
+    /// while writing leaves the previous recovery file intact.
+    pub fn write(&self, path: &Path) -> Result<(), SceneError> {
+        let source = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, source)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Read a recovery file
+    pub fn read(path: &Path) -> Result<Self, SceneError> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The recovery file at `path` if it holds changes the scene file doesn't have
+    ///
+    /// That is the case when it was written after the scene file was last modified,
+    /// or when the scene was never saved. Returns `None` if there's no recovery file.
+    /// Recovery files older than their scene and unreadable ones are removed.
+    pub fn find_unsaved(path: &Path) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+        let data = match Self::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Discarding unreadable recovery file {}: {}", path.display(), e);
+                remove_recovery_file(path);
+                return None;
+            }
+        };
+
+        let recovery_time = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        let scene_time = data.scene_path.as_ref()
+            .and_then(|scene_path| fs::metadata(scene_path).and_then(|metadata| metadata.modified()).ok());
+        match (recovery_time, scene_time) {
+            (Some(recovery_time), Some(scene_time)) if recovery_time <= scene_time => {
+                remove_recovery_file(path);
+                None
+            }
+            // Never saved, or the scene file is gone
+            _ => Some(data),
+        }
+    }
+}
+
+/// Remove a recovery file, logging failures other than it not existing
+pub fn remove_recovery_file(path: &Path) {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove recovery file {}: {}", path.display(), e),
+    }
+}
+
+/// Writes recovery files on a background thread
+///
+/// The scene is captured by the caller, serializing and writing it happens off the
+/// UI thread. Only one save runs at a time.
+#[derive(Default)]
+pub struct Autosaver {
+    task: Option<JoinHandle<Result<(), SceneError>>>,
+    /// Seconds of unsaved changes since the last save was started
+    timer: f32,
+}
+
+impl Autosaver {
+    /// Create an autosaver with no save running
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a save is still running
+    pub fn is_saving(&self) -> bool {
+        self.task.as_ref().map_or(false, |task| !task.is_finished())
+    }
+
+    /// Count `delta_time` towards the next save and return whether one is due
+    ///
+    /// Time only counts while `has_changes` is true; once it's false the count starts
+    /// over, so a scene without unsaved changes is never written. An `interval` of 0
+    /// or less turns autosaving off. A due save stays due until `start` succeeds.
+    pub fn is_due(&mut self, delta_time: f32, interval: f32, has_changes: bool) -> bool {
+        if interval <= 0.0 || !has_changes {
+            self.timer = 0.0;
+            return false;
+        }
+        self.timer += delta_time;
+        self.timer >= interval
+    }
+
+    /// Start writing `data` to `path` in the background
+    ///
+    /// Returns false and does nothing if the previous save hasn't finished.
+    pub fn start(&mut self, data: RecoveryData, path: PathBuf) -> bool {
+        if self.is_saving() {
+            return false;
+        }
+        self.task = Some(std::thread::spawn(move || data.write(&path)));
+        self.timer = 0.0;
+        true
+    }
+
+    /// Result of the last save, once it finished and only the first time it's asked for
+    pub fn poll(&mut self) -> Option<Result<(), SceneError>> {
+        if self.is_saving() {
+            return None;
+        }
+        self.task.take().map(join)
+    }
+
+    /// Wait for a running save, then remove the recovery file at `path`
+    pub fn discard(&mut self, path: &Path) {
+        if let Some(Err(e)) = self.task.take().map(join) {
+            warn!("Autosave failed: {}", e);
+        }
+        remove_recovery_file(path);
+        self.timer = 0.0;
+    }
+}
+
+/// Result of a save task, a panic in it counts as an IO error
+fn join(task: JoinHandle<Result<(), SceneError>>) -> Result<(), SceneError> {
+    task.join().unwrap_or_else(|_| {
+        Err(SceneError::Io(std::io::Error::new(std::io::ErrorKind::Other, "the autosave thread panicked")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::EcsManager;
+
+    /// A recovery file path of its own in the temporary directory
+    fn recovery_path() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("mirage-recovery-{}", uuid::Uuid::new_v4()))
+            .join(RECOVERY_FILE_NAME)
+    }
+
+    fn recovery_data() -> RecoveryData {
+        RecoveryData {
+            scene_path: None,
+            scene: SceneData::from_world("Autosaved", &EcsManager::new()),
+        }
+    }
+
+    /// Run the autosaver for a frame, writing to `path` if a save is due
+    fn frame(autosaver: &mut Autosaver, path: &Path, delta_time: f32, has_changes: bool) {
+        if autosaver.is_due(delta_time, 1.0, has_changes) {
+            assert!(autosaver.start(recovery_data(), path.to_path_buf()));
+        }
+        while autosaver.is_saving() {
+            std::thread::yield_now();
+        }
+        if let Some(result) = autosaver.poll() {
+            result.unwrap();
+        }
+    }
+
+    #[test]
+    fn clean_scene_is_never_written() {
+        let path = recovery_path();
+        let mut autosaver = Autosaver::new();
+        for _ in 0..10 {
+            frame(&mut autosaver, &path, 0.5, false);
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn dirty_scene_is_written_after_the_interval() {
+        let path = recovery_path();
+        let mut autosaver = Autosaver::new();
+
+        frame(&mut autosaver, &path, 0.6, true);
+        assert!(!path.exists());
+        frame(&mut autosaver, &path, 0.6, true);
+        assert_eq!(RecoveryData::read(&path).unwrap().scene.name, "Autosaved");
+
+        autosaver.discard(&path);
+        assert!(!path.exists());
+        let _ = fs::remove_dir(path.parent().unwrap());
+    }
+
+    #[test]
+    fn saving_the_scene_restarts_the_count() {
+        let mut autosaver = Autosaver::new();
+        assert!(!autosaver.is_due(0.75, 1.0, true));
+        assert!(!autosaver.is_due(0.25, 1.0, false));
+        assert!(!autosaver.is_due(0.75, 1.0, true));
+        assert!(autosaver.is_due(0.25, 1.0, true));
+        assert!(!autosaver.is_due(10.0, 0.0, true));
+    }
+}
@@ -6,6 +6,10 @@ pub enum SceneState {
     Paused,
     Inactive,
     Destroyed,
+    /// Edited in the editor, systems don't run
+    Editing,
+    /// Running in the editor's play mode
+    Playing,
 }
 
 pub trait Scene {
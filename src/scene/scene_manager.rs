@@ -1,10 +1,14 @@
 use std::collections::HashMap;
+use std::path::Path;
 use egui::Context;
+use crate::ecs::EcsManager;
 use crate::scene::{Scene, SceneState};
+use crate::scene::serialization::{self, SceneError, SceneHandle};
 
 pub struct SceneManager {
     scenes: HashMap<String, Box<dyn Scene>>,
     active_scene: Option<String>,
+    world: EcsManager,
 }
 
 impl SceneManager {
@@ -12,9 +16,27 @@ impl SceneManager {
         Self {
             scenes: HashMap::new(),
             active_scene: None,
+            world: EcsManager::new(),
         }
     }
 
+    pub fn world(&self) -> &EcsManager {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut EcsManager {
+        &mut self.world
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), SceneError> {
+        let name = self.active_scene.as_deref().unwrap_or("Untitled");
+        serialization::save_world(name, &self.world, path)
+    }
+
+    pub fn load_from_file(&mut self, path: &Path) -> Result<SceneHandle, SceneError> {
+        serialization::load_world(&mut self.world, path)
+    }
+
     pub fn add_scene(&mut self, scene: Box<dyn Scene>) {
         let name = scene.name().to_string();
         self.scenes.insert(name, scene);
@@ -0,0 +1,293 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::ecs::{
+    EcsManager, EntityHandle, NameComponent, ParentComponent, Transform2DComponent,
+    Transform3DComponent, PhysicsComponent, RenderableComponent, RenderableDescriptor,
+    CameraComponent, LightComponent, LuaScriptComponent, Collider2DComponent, TilemapComponent, SiblingOrderComponent, LayerComponent, ActiveComponent, TextComponent, AudioSourceComponent,
+};
+use crate::scene::migration::{self, SCENE_FORMAT_VERSION};
+
+/// Errors that can occur while saving or loading a scene
+#[derive(Debug)]
+pub enum SceneError {
+    /// Reading or writing the scene file failed
+    Io(std::io::Error),
+    /// The scene file could not be parsed
+    Parse(ron::error::SpannedError),
+    /// The scene could not be serialized
+    Serialize(ron::Error),
+    /// The file has a format version this build can't read, e.g. from a newer engine
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "Scene IO error: {}", e),
+            SceneError::Parse(e) => write!(f, "Failed to parse scene: {}", e),
+            SceneError::Serialize(e) => write!(f, "Failed to serialize scene: {}", e),
+            SceneError::UnsupportedVersion(version) => write!(
+                f,
+                "Scene format version {} is not supported, this build reads versions {} to {}",
+                version,
+                migration::FIRST_SCENE_FORMAT_VERSION,
+                SCENE_FORMAT_VERSION,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(e) => Some(e),
+            SceneError::Parse(e) => Some(e),
+            SceneError::Serialize(e) => Some(e),
+            SceneError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SceneError::Parse(e)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(e: ron::Error) -> Self {
+        SceneError::Serialize(e)
+    }
+}
+
+/// Handle to a scene loaded from disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SceneHandle {
+    /// Name of the scene
+    pub name: String,
+}
+
+/// On-disk representation of a scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneData {
+    /// Format version, see `SCENE_FORMAT_VERSION`
+    pub version: u32,
+    /// Name of the scene
+    pub name: String,
+    /// All entities in the scene
+    pub entities: Vec<EntityData>,
+}
+
+/// On-disk representation of a single entity and its components
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityData {
+    /// Handle of the entity, preserved across save/load
+    pub handle: EntityHandle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<NameComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<ParentComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sibling_order: Option<SiblingOrderComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<LayerComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<ActiveComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform_2d: Option<Transform2DComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform_3d: Option<Transform3DComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physics: Option<PhysicsComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collider_2d: Option<Collider2DComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renderable: Option<RenderableDescriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub light: Option<LightComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_source: Option<AudioSourceComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<LuaScriptComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilemap: Option<TilemapComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextComponent>,
+}
+
+impl EntityData {
+    /// Capture a single entity and its components
+    pub fn capture(ecs: &EcsManager, handle: &EntityHandle) -> Option<Self> {
+        let world = ecs.world();
+        let entity = ecs.get_entity(handle)?;
+
+        // Entities loaded from disk keep their descriptor until a renderer resolves them
+        let renderable = world.get::<&RenderableComponent>(entity).ok()
+            .map(|r| RenderableDescriptor::from_renderable(&r))
+            .or_else(|| world.get::<&RenderableDescriptor>(entity).ok().map(|r| (*r).clone()));
+
+        Some(Self {
+            handle: *handle,
+            name: world.get::<&NameComponent>(entity).ok().map(|c| (*c).clone()),
+            parent: world.get::<&ParentComponent>(entity).ok().map(|c| *c),
+            sibling_order: world.get::<&SiblingOrderComponent>(entity).ok().map(|c| *c),
+            layer: world.get::<&LayerComponent>(entity).ok().map(|c| *c),
+            active: world.get::<&ActiveComponent>(entity).ok().map(|c| *c),
+            transform_2d: world.get::<&Transform2DComponent>(entity).ok().map(|c| (*c).clone()),
+            transform_3d: world.get::<&Transform3DComponent>(entity).ok().map(|c| (*c).clone()),
+            physics: world.get::<&PhysicsComponent>(entity).ok().map(|c| (*c).clone()),
+            collider_2d: world.get::<&Collider2DComponent>(entity).ok().map(|c| (*c).clone()),
+            renderable,
+            camera: world.get::<&CameraComponent>(entity).ok().map(|c| (*c).clone()),
+            light: world.get::<&LightComponent>(entity).ok().map(|c| (*c).clone()),
+            audio_source: world.get::<&AudioSourceComponent>(entity).ok().map(|c| (*c).clone()),
+            script: world.get::<&LuaScriptComponent>(entity).ok().map(|c| (*c).clone()),
+            tilemap: world.get::<&TilemapComponent>(entity).ok().map(|c| (*c).clone()),
+            text: world.get::<&TextComponent>(entity).ok().map(|c| (*c).clone()),
+        })
+    }
+
+    /// Spawn the entity into the world, keeping its handle
+    pub fn spawn(&self, ecs: &mut EcsManager) -> EntityHandle {
+        let mut builder = hecs::EntityBuilder::new();
+        if let Some(c) = &self.name { builder.add(c.clone()); }
+        if let Some(c) = &self.parent { builder.add(*c); }
+        if let Some(c) = &self.sibling_order { builder.add(*c); }
+        if let Some(c) = &self.layer { builder.add(*c); }
+        if let Some(c) = &self.active { builder.add(*c); }
+        if let Some(c) = &self.transform_2d { builder.add(c.clone()); }
+        if let Some(c) = &self.transform_3d { builder.add(c.clone()); }
+        if let Some(c) = &self.physics { builder.add(c.clone()); }
+        if let Some(c) = &self.collider_2d { builder.add(c.clone()); }
+        if let Some(c) = &self.renderable { builder.add(c.clone()); }
+        if let Some(c) = &self.camera { builder.add(c.clone()); }
+        if let Some(c) = &self.light { builder.add(c.clone()); }
+        if let Some(c) = &self.audio_source { builder.add(c.clone()); }
+        if let Some(c) = &self.script { builder.add(c.clone()); }
+        if let Some(c) = &self.tilemap { builder.add(c.clone()); }
+        if let Some(c) = &self.text { builder.add(c.clone()); }
+
+        ecs.create_entity_with_handle(self.handle, builder.build())
+    }
+}
+
+impl SceneData {
+    /// Capture all entities in the world
+    pub fn from_world(name: &str, ecs: &EcsManager) -> Self {
+        let mut entities: Vec<EntityData> = ecs.handles().iter()
+            .filter_map(|handle| EntityData::capture(ecs, handle))
+            .collect();
+
+        // Keep the file stable between saves
+        entities.sort_by_key(|e| e.handle.uuid);
+
+        Self {
+            version: SCENE_FORMAT_VERSION,
+            name: name.to_string(),
+            entities,
+        }
+    }
+
+    /// Replace the contents of the world with this scene
+    pub fn apply_to_world(&self, ecs: &mut EcsManager) {
+        ecs.clear();
+
+        for data in &self.entities {
+            data.spawn(ecs);
+        }
+        ecs.update_active();
+    }
+
+    /// Serialize the scene to a RON string
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Parse a scene from a RON string, migrating older format versions
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        Ok(ron::from_str(&migration::migrate_to_current(source)?)?)
+    }
+}
+
+/// Save the world to a RON scene file
+pub fn save_world(name: &str, ecs: &EcsManager, path: &Path) -> Result<(), SceneError> {
+    let ron = SceneData::from_world(name, ecs).to_ron()?;
+    fs::write(path, ron)?;
+    Ok(())
+}
+
+/// Load a RON scene file into the world, replacing its contents
+pub fn load_world(ecs: &mut EcsManager, path: &Path) -> Result<SceneHandle, SceneError> {
+    let source = fs::read_to_string(path)?;
+    let data = SceneData::from_ron(&source)?;
+    data.apply_to_world(ecs);
+    Ok(SceneHandle { name: data.name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3};
+    use crate::ecs::light_manager;
+    use crate::rendering::{ShadowResolution, ShadowSettings};
+
+    fn spawn_light(ecs: &mut EcsManager, name: &str, position: Vec3, light: LightComponent) -> EntityHandle {
+        ecs.create_entity((
+            NameComponent::new(name),
+            Transform3DComponent::new(position, Quat::from_rotation_x(-0.8), Vec3::ONE),
+            light,
+        ))
+    }
+
+    /// Save the world to a RON string and load it into a fresh world
+    fn reload(ecs: &EcsManager) -> EcsManager {
+        let ron = SceneData::from_world("Lights", ecs).to_ron().unwrap();
+        let mut loaded = EcsManager::new();
+        SceneData::from_ron(&ron).unwrap().apply_to_world(&mut loaded);
+        loaded
+    }
+
+    #[test]
+    fn lights_survive_save_and_reload() {
+        let mut directional = LightComponent::directional();
+        directional.color = Vec3::new(1.0, 0.9, 0.7);
+        directional.intensity = 2.5;
+        directional.shadow_resolution = ShadowResolution::High;
+        directional.shadow_bias = 0.01;
+        let mut point = LightComponent::point(7.5);
+        point.color = Vec3::new(0.2, 0.4, 1.0);
+        point.intensity = 3.0;
+
+        let mut ecs = EcsManager::new();
+        let sun = spawn_light(&mut ecs, "Sun", Vec3::new(0.0, 10.0, 0.0), directional.clone());
+        let lamp = spawn_light(&mut ecs, "Lamp", Vec3::new(2.0, 1.0, -3.0), point.clone());
+
+        let loaded = reload(&ecs);
+        assert_eq!(loaded.get_component::<LightComponent>(&sun).as_deref(), Some(&directional));
+        assert_eq!(loaded.get_component::<LightComponent>(&lamp).as_deref(), Some(&point));
+
+        let manager = light_manager(loaded.world());
+        assert_eq!(manager.light_count(), 2);
+        assert_eq!(manager.shadow_settings(), Some(ShadowSettings { resolution: ShadowResolution::High, bias: 0.01 }));
+    }
+
+    #[test]
+    fn scene_without_lights_loads_an_empty_light_manager() {
+        let mut ecs = EcsManager::new();
+        ecs.create_entity((NameComponent::new("Empty"),));
+
+        let loaded = reload(&ecs);
+        assert_eq!(loaded.entity_count(), 1);
+        assert_eq!(light_manager(loaded.world()).light_count(), 0);
+    }
+}
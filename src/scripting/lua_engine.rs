@@ -0,0 +1,352 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use glam::{Quat, Vec2, Vec3};
+use hecs::Entity;
+use mlua::{Function, Lua, RegistryKey, Table};
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use log::{error, info};
+use crate::core::{Input, InputMap, KeyCode};
+use crate::ecs::Transform3DComponent;
+
+/// Handle to a script loaded into a `LuaEngine`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScriptHandle(pub u32);
+
+/// A loaded script and its private global environment
+struct LoadedScript {
+    path: PathBuf,
+    /// Modification time of the file when it was last loaded
+    modified: Option<SystemTime>,
+    /// Script environment, `None` while the script has an error
+    env: Option<RegistryKey>,
+    /// Entities whose `on_start` has already run
+    started: HashSet<Entity>,
+}
+
+/// Input state as seen by scripts during a frame
+#[derive(Default)]
+struct InputSnapshot {
+    keys_down: HashSet<String>,
+    keys_pressed: HashSet<String>,
+    keys_released: HashSet<String>,
+    mouse_position: Vec2,
+    /// Actions and axes resolved for this frame
+    actions: InputMap,
+}
+
+/// Runs Lua scripts attached to entities
+///
+/// Each script gets its own global environment and may define
+/// `on_start(entity)` and `on_update(entity, dt)`. The `entity` table has an
+/// `id` and, for entities with a 3D transform, `position`, `rotation` and
+/// `scale` tables that are written back after the call. Scripts query input
+/// through the global `input` table, e.g. `input.is_key_down("W")`, or through
+/// the actions of an `InputMap`, e.g. `input.is_action_pressed("Jump")`.
+pub struct LuaEngine {
+    lua: Lua,
+    scripts: HashMap<ScriptHandle, LoadedScript>,
+    next_handle: u32,
+    input: Rc<RefCell<InputSnapshot>>,
+    /// Errors since the last `take_errors`
+    errors: Vec<String>,
+}
+
+impl LuaEngine {
+    /// Create a new Lua engine with the engine bindings registered
+    pub fn new() -> Result<Self> {
+        let lua = Lua::new();
+        let input = Rc::new(RefCell::new(InputSnapshot::default()));
+        register_input(&lua, &input)
+            .map_err(|e| anyhow!("Failed to register Lua input bindings: {}", e))?;
+
+        Ok(Self {
+            lua,
+            scripts: HashMap::new(),
+            next_handle: 0,
+            input,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Load a script file
+    ///
+    /// Always returns a handle. If the script fails to load, the error is
+    /// reported and the script is retried once the file changes.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> ScriptHandle {
+        let handle = ScriptHandle(self.next_handle);
+        self.next_handle += 1;
+
+        let mut script = LoadedScript {
+            path: path.as_ref().to_path_buf(),
+            modified: None,
+            env: None,
+            started: HashSet::new(),
+        };
+        self.compile(&mut script);
+        self.scripts.insert(handle, script);
+
+        handle
+    }
+
+    /// Unload a script
+    pub fn unload(&mut self, handle: ScriptHandle) {
+        if let Some(script) = self.scripts.remove(&handle) {
+            if let Some(env) = script.env {
+                let _ = self.lua.remove_registry_value(env);
+            }
+        }
+    }
+
+    /// Reload every script whose file changed since it was loaded
+    ///
+    /// Reloaded scripts run `on_start` again for each entity.
+    pub fn reload_changed(&mut self) {
+        let mut scripts = std::mem::take(&mut self.scripts);
+        for script in scripts.values_mut() {
+            if modified_time(&script.path) != script.modified {
+                info!("Reloading script {}", script.path.display());
+                self.compile(script);
+            }
+        }
+        self.scripts = scripts;
+    }
+
+    /// Update the input state visible to scripts
+    pub fn set_input(&mut self, input: &Input) {
+        let mut snapshot = self.input.borrow_mut();
+        snapshot.keys_down = input.keys_down().map(key_name).collect();
+        snapshot.keys_pressed = input.keys_pressed().map(key_name).collect();
+        snapshot.keys_released = input.keys_released().map(key_name).collect();
+        snapshot.mouse_position = input.mouse_position();
+    }
+
+    /// Update the actions and axes visible to scripts, after `InputMap::update`
+    pub fn set_input_map(&mut self, input_map: &InputMap) {
+        self.input.borrow_mut().actions = input_map.clone();
+    }
+
+    /// Run a script for an entity
+    ///
+    /// Calls `on_start` the first time the entity runs the script, then
+    /// `on_update`. A script that errors is disabled until its file changes.
+    pub fn run(&mut self, handle: ScriptHandle, entity: Entity, transform: Option<&mut Transform3DComponent>, delta_time: f32) {
+        let Some(script) = self.scripts.get_mut(&handle) else { return };
+        let Some(env) = &script.env else { return };
+
+        let first_run = script.started.insert(entity);
+        if let Err(e) = call_script(&self.lua, env, entity, transform, delta_time, first_run) {
+            let path = script.path.clone();
+            if let Some(env) = script.env.take() {
+                let _ = self.lua.remove_registry_value(env);
+            }
+            self.report_error(&path, e);
+        }
+    }
+
+    /// Take the script errors reported since the last call
+    pub fn take_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// (Re)load a script file into a fresh environment
+    fn compile(&mut self, script: &mut LoadedScript) {
+        script.modified = modified_time(&script.path);
+        script.started.clear();
+        if let Some(env) = script.env.take() {
+            let _ = self.lua.remove_registry_value(env);
+        }
+
+        match self.create_environment(&script.path) {
+            Ok(env) => script.env = Some(env),
+            Err(e) => self.report_error(&script.path, e),
+        }
+    }
+
+    /// Execute a script file in a new environment that falls back to the globals
+    fn create_environment(&self, path: &Path) -> mlua::Result<RegistryKey> {
+        let source = fs::read_to_string(path).map_err(mlua::Error::external)?;
+
+        let env = self.lua.create_table()?;
+        let meta = self.lua.create_table()?;
+        meta.set("__index", self.lua.globals())?;
+        env.set_metatable(Some(meta));
+
+        self.lua.load(&source)
+            .set_name(path.display().to_string())
+            .set_environment(env.clone())
+            .exec()?;
+
+        self.lua.create_registry_value(env)
+    }
+
+    fn report_error(&mut self, path: &Path, err: impl Display) {
+        let message = format!("Script error in {}: {}", path.display(), err);
+        error!("{}", message);
+        self.errors.push(message);
+    }
+}
+
+/// A syntax error found by `check_syntax`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptSyntaxError {
+    /// Line of the error, starting at 1, if Lua reported one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Compile Lua source without running it, reporting the first syntax error
+///
+/// `name` is used as the chunk name in the message, usually the script's file name.
+pub fn check_syntax(source: &str, name: &str) -> Result<(), ScriptSyntaxError> {
+    let lua = Lua::new();
+    let result = lua.load(source)
+        .set_name(format!("={}", name))
+        .into_function();
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(mlua::Error::SyntaxError { message, .. }) => Err(ScriptSyntaxError {
+            line: error_line(&message, name),
+            message,
+        }),
+        Err(e) => Err(ScriptSyntaxError { line: None, message: e.to_string() }),
+    }
+}
+
+/// Line number from a Lua error message of the form `name:line: message`
+fn error_line(message: &str, name: &str) -> Option<usize> {
+    let rest = message.strip_prefix(name)?.strip_prefix(':')?;
+    rest.split(':').next()?.parse().ok()
+}
+
+/// Name scripts use for a key, e.g. `"W"`, `"Space"` or `"Left"`
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Register the global `input` table
+fn register_input(lua: &Lua, input: &Rc<RefCell<InputSnapshot>>) -> mlua::Result<()> {
+    let table = lua.create_table()?;
+
+    let state = input.clone();
+    table.set("is_key_down", lua.create_function(move |_, key: String| {
+        Ok(state.borrow().keys_down.contains(&key))
+    })?)?;
+
+    let state = input.clone();
+    table.set("was_key_pressed", lua.create_function(move |_, key: String| {
+        Ok(state.borrow().keys_pressed.contains(&key))
+    })?)?;
+
+    let state = input.clone();
+    table.set("was_key_released", lua.create_function(move |_, key: String| {
+        Ok(state.borrow().keys_released.contains(&key))
+    })?)?;
+
+    let state = input.clone();
+    table.set("mouse_position", lua.create_function(move |_, ()| {
+        let position = state.borrow().mouse_position;
+        Ok((position.x, position.y))
+    })?)?;
+
+    let state = input.clone();
+    table.set("is_action_down", lua.create_function(move |_, action: String| {
+        Ok(state.borrow().actions.is_action_down(&action))
+    })?)?;
+
+    let state = input.clone();
+    table.set("is_action_pressed", lua.create_function(move |_, action: String| {
+        Ok(state.borrow().actions.is_action_pressed(&action))
+    })?)?;
+
+    let state = input.clone();
+    table.set("is_action_released", lua.create_function(move |_, action: String| {
+        Ok(state.borrow().actions.is_action_released(&action))
+    })?)?;
+
+    let state = input.clone();
+    table.set("action_axis", lua.create_function(move |_, axis: String| {
+        Ok(state.borrow().actions.action_axis(&axis))
+    })?)?;
+
+    lua.globals().set("input", table)
+}
+
+/// Call the script callbacks for one entity and write its transform back
+fn call_script(
+    lua: &Lua,
+    env: &RegistryKey,
+    entity: Entity,
+    transform: Option<&mut Transform3DComponent>,
+    delta_time: f32,
+    first_run: bool,
+) -> mlua::Result<()> {
+    let env: Table = lua.registry_value(env)?;
+
+    let entity_table = lua.create_table()?;
+    entity_table.set("id", entity.to_bits().get())?;
+    if let Some(transform) = &transform {
+        entity_table.set("position", vec3_table(lua, transform.position)?)?;
+        entity_table.set("rotation", quat_table(lua, transform.rotation)?)?;
+        entity_table.set("scale", vec3_table(lua, transform.scale)?)?;
+    }
+
+    if first_run {
+        if let Some(on_start) = env.raw_get::<_, Option<Function>>("on_start")? {
+            on_start.call::<_, ()>(entity_table.clone())?;
+        }
+    }
+
+    if let Some(on_update) = env.raw_get::<_, Option<Function>>("on_update")? {
+        on_update.call::<_, ()>((entity_table.clone(), delta_time))?;
+    }
+
+    if let Some(transform) = transform {
+        transform.position = read_vec3(&entity_table.get("position")?)?;
+        transform.rotation = read_quat(&entity_table.get("rotation")?)?;
+        transform.scale = read_vec3(&entity_table.get("scale")?)?;
+    }
+
+    Ok(())
+}
+
+fn vec3_table(lua: &Lua, v: Vec3) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+    table.set("x", v.x)?;
+    table.set("y", v.y)?;
+    table.set("z", v.z)?;
+    Ok(table)
+}
+
+fn quat_table(lua: &Lua, q: Quat) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+    table.set("x", q.x)?;
+    table.set("y", q.y)?;
+    table.set("z", q.z)?;
+    table.set("w", q.w)?;
+    Ok(table)
+}
+
+fn read_vec3(table: &Table) -> mlua::Result<Vec3> {
+    Ok(Vec3::new(table.get("x")?, table.get("y")?, table.get("z")?))
+}
+
+fn read_quat(table: &Table) -> mlua::Result<Quat> {
+    let q = Quat::from_xyzw(table.get("x")?, table.get("y")?, table.get("z")?, table.get("w")?);
+    // Scripts may write any four numbers
+    if q.length_squared() > 0.0 {
+        Ok(q.normalize())
+    } else {
+        Ok(Quat::IDENTITY)
+    }
+}
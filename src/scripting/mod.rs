@@ -0,0 +1,3 @@
+mod lua_engine;
+
+pub use lua_engine::{LuaEngine, ScriptHandle, ScriptSyntaxError, check_syntax, key_name};
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+use egui::{Color32, Context, CursorIcon, Id, Rect, Stroke, Ui};
+use glam::Vec3;
+use crate::ecs::{EcsManager, EntityHandle, RenderableComponent, SpriteComponent};
+use crate::ui::editor::ui_components::ProjectFileType;
+
+/// Outline of drop targets that take the dragged asset
+const ACCEPT_COLOR: Color32 = Color32::from_rgb(80, 200, 120);
+
+/// Outline of drop targets that don't
+const REJECT_COLOR: Color32 = Color32::from_rgb(220, 70, 70);
+
+/// A project file being dragged from the project panel
+///
+/// It lives in egui's temporary memory from the drag's start until the pointer is
+/// released, so any panel can be a drop target without knowing the project panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DraggedAsset {
+    /// Path relative to the project root, starting with `/`
+    pub path: String,
+    pub file_type: ProjectFileType,
+}
+
+impl DraggedAsset {
+    /// Start dragging a project file
+    pub fn start(ctx: &Context, asset: DraggedAsset) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), asset));
+    }
+
+    /// The asset being dragged, if any
+    pub fn current(ctx: &Context) -> Option<DraggedAsset> {
+        ctx.data(|data| data.get_temp::<DraggedAsset>(Self::id()))
+    }
+
+    /// Stop dragging, call it after every drop target had its chance
+    pub fn clear(ctx: &Context) {
+        ctx.data_mut(|data| data.remove::<DraggedAsset>(Self::id()));
+    }
+
+    fn id() -> Id {
+        Id::new("dragged_project_asset")
+    }
+
+    /// Path relative to the project root, the form components store
+    pub fn relative_path(&self) -> PathBuf {
+        PathBuf::from(self.path.trim_start_matches('/'))
+    }
+
+    /// File name shown while dragging
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// A project asset released over a panel, applied by the editor
+#[derive(Debug, Clone)]
+pub struct AssetDrop {
+    pub asset: DraggedAsset,
+    /// Entity it was dropped on, `None` for empty space
+    pub target: Option<EntityHandle>,
+    /// Scene position it was dropped at, where dropped prefabs are placed
+    pub position: Option<Vec3>,
+}
+
+/// Drop target covering `rect`, for assets `accepts` returns true for
+///
+/// While an asset is dragged over it the target is outlined in green if it takes
+/// the asset, and in red with a "no drop" cursor if it doesn't. Returns the asset
+/// when it's released over the target and accepted.
+pub fn drop_target(ui: &Ui, rect: Rect, accepts: impl FnOnce(&ProjectFileType) -> bool) -> Option<DraggedAsset> {
+    let asset = DraggedAsset::current(ui.ctx())?;
+    let hovered = ui.input(|i| i.pointer.hover_pos()).map_or(false, |pos| rect.contains(pos));
+    if !hovered {
+        return None;
+    }
+
+    let accepted = accepts(&asset.file_type);
+    let color = if accepted { ACCEPT_COLOR } else { REJECT_COLOR };
+    ui.painter().rect_stroke(rect, 2.0, Stroke::new(1.5, color));
+    if !accepted {
+        ui.ctx().set_cursor_icon(CursorIcon::NoDrop);
+    }
+
+    let released = ui.input(|i| i.pointer.any_released());
+    (released && accepted).then_some(asset)
+}
+
+/// Drop target over the widgets added to `ui` so far, for files of `file_type`
+///
+/// Returns the dropped file's path relative to the project root, the form path fields hold.
+pub fn path_drop_target(ui: &Ui, file_type: ProjectFileType) -> Option<PathBuf> {
+    drop_target(ui, ui.min_rect(), |dragged| *dragged == file_type).map(|asset| asset.relative_path())
+}
+
+/// Whether an asset of `file_type` can be dropped on `target`, or on no entity for `None`
+///
+/// Prefabs are instantiated wherever they're dropped. Textures go to sprites,
+/// materials to sprites and renderables, and scripts and audio clips to any entity.
+pub fn entity_accepts(ecs: &EcsManager, target: Option<EntityHandle>, file_type: &ProjectFileType) -> bool {
+    let has_sprite = target.map_or(false, |handle| ecs.get_component::<SpriteComponent>(&handle).is_some());
+    let has_renderable = target.map_or(false, |handle| ecs.get_component::<RenderableComponent>(&handle).is_some());
+    match file_type {
+        ProjectFileType::Prefab => true,
+        ProjectFileType::Texture => has_sprite,
+        ProjectFileType::Material => has_sprite || has_renderable,
+        ProjectFileType::Script | ProjectFileType::Audio => target.is_some(),
+        ProjectFileType::Folder | ProjectFileType::Scene | ProjectFileType::Other => false,
+    }
+}
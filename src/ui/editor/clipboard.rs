@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use glam::Vec3;
+use crate::ecs::{EcsManager, EntityHandle, ParentComponent, Prefab};
+use crate::scene::EntityData;
+use crate::ui::editor::hierarchy::EntityType;
+use crate::ui::editor::history::{SpawnEntitiesCommand, default_entity_type};
+use crate::ui::editor::ui_components::Selection;
+
+/// How far pasted copies are moved from the originals
+pub const PASTE_OFFSET: Vec3 = Vec3::new(0.5, -0.5, 0.0);
+
+/// Values of one component, copied in the inspector
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopiedComponent {
+    /// Name of the component type, as shown in the inspector
+    pub name: String,
+    /// The component's values as RON
+    pub values: String,
+}
+
+/// One copied entity subtree
+#[derive(Clone)]
+struct ClipboardEntry {
+    /// Snapshot of the subtree, root first
+    prefab: Prefab,
+    /// Parent of the original root
+    parent: Option<EntityHandle>,
+    /// Editor type of each entity in the snapshot
+    entity_types: Vec<EntityType>,
+}
+
+/// Entity snapshots copied in the editor
+#[derive(Clone, Default)]
+pub struct Clipboard {
+    entries: Vec<ClipboardEntry>,
+}
+
+impl Clipboard {
+    /// Create an empty clipboard
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether nothing has been copied
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replace the contents with the selected entities and their children
+    ///
+    /// Returns the number of copied subtrees.
+    pub fn copy(&mut self, ecs: &EcsManager, entity_types: &HashMap<EntityHandle, EntityType>, selection: &Selection) -> usize {
+        self.entries = selection_roots(ecs, selection).into_iter()
+            .filter_map(|root| {
+                let prefab = Prefab::from_entity(ecs, root)?;
+                let entity_types = prefab.entities.iter()
+                    .map(|data| entity_types.get(&data.handle).copied().unwrap_or_else(|| default_entity_type(data)))
+                    .collect();
+                Some(ClipboardEntry {
+                    prefab,
+                    parent: ecs.get_parent(&root),
+                    entity_types,
+                })
+            })
+            .collect();
+        self.entries.len()
+    }
+
+    /// The copied snapshots as RON, for the system clipboard
+    pub fn to_ron(&self) -> Option<String> {
+        let prefabs: Vec<&Prefab> = self.entries.iter().map(|entry| &entry.prefab).collect();
+        ron::ser::to_string_pretty(&prefabs, ron::ser::PrettyConfig::default()).ok()
+    }
+
+    /// Command that spawns copies with fresh handles, or `None` if the clipboard is empty
+    ///
+    /// Copies are parented like the originals, or under `parent` when it's given.
+    pub fn paste_command(&self, ecs: &EcsManager, parent: Option<EntityHandle>) -> Option<SpawnEntitiesCommand> {
+        self.spawn_command("Paste", ecs, parent)
+    }
+
+    fn spawn_command(&self, description: &str, ecs: &EcsManager, parent: Option<EntityHandle>) -> Option<SpawnEntitiesCommand> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let subtrees: Vec<Vec<(EntityData, EntityType)>> = self.entries.iter().map(|entry| {
+            let position = entry.prefab.root_position().map(|position| position + PASTE_OFFSET);
+            let mut entities = entry.prefab.instance(position);
+
+            // The original parent may have been deleted since copying
+            let parent = parent.or(entry.parent).filter(|p| ecs.get_entity(p).is_some());
+            if let Some(root) = entities.first_mut() {
+                root.parent = parent.map(ParentComponent::new);
+            }
+
+            entities.into_iter().zip(entry.entity_types.iter().copied()).collect::<Vec<_>>()
+        }).collect();
+
+        Some(SpawnEntitiesCommand::new(description, subtrees))
+    }
+}
+
+/// Selected entities that don't have a selected ancestor, in a stable order
+pub fn selection_roots(ecs: &EcsManager, selection: &Selection) -> Vec<EntityHandle> {
+    let mut roots: Vec<EntityHandle> = selection.iter()
+        .filter(|handle| ecs.get_entity(handle).is_some())
+        .filter(|handle| !selection.iter().any(|other| other != *handle && ecs.is_ancestor(other, handle)))
+        .copied()
+        .collect();
+    roots.sort_by_key(|handle| handle.uuid);
+    roots
+}
+
+/// Command that duplicates the selected entities without touching the clipboard
+pub fn duplicate_command(ecs: &EcsManager, entity_types: &HashMap<EntityHandle, EntityType>, selection: &Selection) -> Option<SpawnEntitiesCommand> {
+    let mut clipboard = Clipboard::new();
+    clipboard.copy(ecs, entity_types, selection);
+    clipboard.spawn_command("Duplicate", ecs, None)
+}
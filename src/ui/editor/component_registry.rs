@@ -0,0 +1,673 @@
+use egui::Ui;
+use glam::{Vec2, Vec3};
+use hecs::{Component, Entity, World};
+use serde::{de::DeserializeOwned, Serialize};
+use crate::ecs::{
+    AudioSourceComponent, BodyType, CameraComponent, Collider2DComponent, ColliderShape, LayerComponent, LightComponent, LightKind, LuaScriptComponent,
+    PhysicsComponent, RenderableComponent, SpriteComponent, TextComponent, TextSpace, Transform2DComponent, LAYER_COUNT,
+};
+use crate::rendering::{ClearMode, ShadowResolution, TextAlign};
+use crate::ui::editor::material_editor::material_fields;
+use crate::ui::editor::script_editor::ScriptEditorPanel;
+use crate::ui::editor::asset_drag;
+use crate::ui::editor::ui_components::ProjectFileType;
+use crate::utils::math;
+
+/// Draws the inspector fields of a component, reading and writing it in the world
+pub type DrawComponentFn = Box<dyn Fn(&mut Ui, &mut World, Entity)>;
+
+/// Reads a component's values as RON
+type CopyComponentFn = Box<dyn Fn(&World, Entity) -> Option<String>>;
+
+/// Writes RON values into a component, adding it if needed
+type PasteComponentFn = Box<dyn Fn(&mut World, Entity, &str) -> Result<(), ron::error::SpannedError>>;
+
+/// Reads, and with `Some` writes, the flag that switches a component on and off
+type EnabledFlagFn = Box<dyn Fn(&World, Entity, Option<bool>) -> Option<bool>>;
+
+/// Width of the label column in component fields
+const LABEL_WIDTH: f32 = 120.0;
+
+/// How the inspector finds, creates, removes and draws one component type
+pub struct ComponentRegistration {
+    /// Name shown in the inspector
+    pub name: &'static str,
+    /// Section of the "Add Component" menu
+    pub category: &'static str,
+    has: Box<dyn Fn(&World, Entity) -> bool>,
+    add: Option<Box<dyn Fn(&mut World, Entity)>>,
+    remove: Box<dyn Fn(&mut World, Entity)>,
+    draw: DrawComponentFn,
+    copy: Option<CopyComponentFn>,
+    paste: Option<PasteComponentFn>,
+    enabled: Option<EnabledFlagFn>,
+}
+
+impl ComponentRegistration {
+    /// Whether the entity has this component
+    pub fn is_present(&self, world: &World, entity: Entity) -> bool {
+        (self.has)(world, entity)
+    }
+
+    /// Whether the component can be added from the editor
+    pub fn can_add(&self) -> bool {
+        self.add.is_some()
+    }
+
+    /// Add a default instance of the component, returns false if it can't be created
+    pub fn add(&self, world: &mut World, entity: Entity) -> bool {
+        match &self.add {
+            Some(add) => {
+                add(world, entity);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the component from the entity
+    pub fn remove(&self, world: &mut World, entity: Entity) {
+        (self.remove)(world, entity);
+    }
+
+    /// Draw the component's fields
+    pub fn draw(&self, ui: &mut Ui, world: &mut World, entity: Entity) {
+        (self.draw)(ui, world, entity);
+    }
+
+    /// Whether the entity's component is switched on, `None` if it has none or can't be switched
+    pub fn is_enabled(&self, world: &World, entity: Entity) -> Option<bool> {
+        self.enabled.as_ref().and_then(|enabled| enabled(world, entity, None))
+    }
+
+    /// Switch the entity's component on or off, does nothing for components that can't be switched
+    pub fn set_enabled(&self, world: &World, entity: Entity, value: bool) {
+        if let Some(enabled) = &self.enabled {
+            enabled(world, entity, Some(value));
+        }
+    }
+
+    /// Whether the component's values can be copied and pasted
+    pub fn can_copy(&self) -> bool {
+        self.copy.is_some() && self.paste.is_some()
+    }
+
+    /// The entity's component values as RON, `None` if it has none or they can't be copied
+    pub fn copy(&self, world: &World, entity: Entity) -> Option<String> {
+        self.copy.as_ref().and_then(|copy| copy(world, entity))
+    }
+
+    /// Replace the entity's component values with copied ones, adding the component if it's missing
+    ///
+    /// Does nothing for components that can't be copied.
+    pub fn paste(&self, world: &mut World, entity: Entity, values: &str) -> Result<(), ron::error::SpannedError> {
+        match &self.paste {
+            Some(paste) => paste(world, entity, values),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Component types the inspector knows how to show
+#[derive(Default)]
+pub struct ComponentRegistry {
+    registrations: Vec<ComponentRegistration>,
+}
+
+impl ComponentRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the engine's built-in components
+    ///
+    /// Names, parents and 3D transforms are edited by the inspector header,
+    /// the hierarchy and the transform section, so they aren't registered.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register_copyable("Transform 2D", "Common", Some(Transform2DComponent::default), draw_transform_2d);
+        registry.register_copyable("Layer", "Common", Some(LayerComponent::default), draw_layer);
+        registry.register_copyable("Rigidbody 2D", "Physics", Some(PhysicsComponent::default), draw_physics);
+        registry.register_copyable("Collider 2D", "Physics", Some(|| Collider2DComponent::new_box(Vec2::ONE)), draw_collider_2d);
+        registry.register_copyable("Camera", "Rendering", Some(|| CameraComponent::new_perspective(60.0_f32.to_radians(), 0.1, 1000.0)), draw_camera);
+        registry.register_copyable("Light", "Rendering", Some(LightComponent::directional), draw_light);
+        registry.register_copyable("Text", "Rendering", Some(|| TextComponent::new("New Text")), draw_text);
+        registry.register_copyable("Audio Source", "Audio", Some(|| AudioSourceComponent::new("")), draw_audio_source);
+        // Renderables need GPU resources, so they can't be created or pasted from the inspector
+        registry.register::<RenderableComponent>("Renderable", "Rendering", None, draw_renderable);
+        // Sprites need a registered texture, so the same goes for them
+        registry.register::<SpriteComponent>("Sprite Renderer", "Rendering", None, draw_sprite);
+        registry.register_copyable("Lua Script", "Scripts", Some(|| LuaScriptComponent::new("")), draw_lua_script);
+
+        // Switched on and off by the checkbox in their header
+        registry.set_enabled_flag::<PhysicsComponent>("Rigidbody 2D", |physics| &mut physics.enabled);
+        registry.set_enabled_flag::<Collider2DComponent>("Collider 2D", |collider| &mut collider.enabled);
+        registry.set_enabled_flag::<CameraComponent>("Camera", |camera| &mut camera.is_active);
+        registry.set_enabled_flag::<LightComponent>("Light", |light| &mut light.enabled);
+        registry.set_enabled_flag::<TextComponent>("Text", |text| &mut text.enabled);
+        registry.set_enabled_flag::<AudioSourceComponent>("Audio Source", |source| &mut source.enabled);
+        registry.set_enabled_flag::<RenderableComponent>("Renderable", |renderable| &mut renderable.visible);
+        registry.set_enabled_flag::<SpriteComponent>("Sprite Renderer", |sprite| &mut sprite.visible);
+        registry.set_enabled_flag::<LuaScriptComponent>("Lua Script", |script| &mut script.enabled);
+        registry
+    }
+
+    /// Register a component type
+    ///
+    /// `factory` creates the instance added by the "Add Component" menu, without
+    /// one the component can only be added from code.
+    pub fn register<T: Component>(
+        &mut self,
+        name: &'static str,
+        category: &'static str,
+        factory: Option<fn() -> T>,
+        draw: fn(&mut Ui, &mut T),
+    ) {
+        self.registrations.push(ComponentRegistration {
+            name,
+            category,
+            has: Box::new(|world, entity| world.get::<&T>(entity).is_ok()),
+            add: factory.map(|factory| -> Box<dyn Fn(&mut World, Entity)> {
+                Box::new(move |world, entity| {
+                    let _ = world.insert_one(entity, factory());
+                })
+            }),
+            remove: Box::new(|world, entity| {
+                let _ = world.remove_one::<T>(entity);
+            }),
+            draw: Box::new(move |ui, world, entity| {
+                if let Ok(mut component) = world.get::<&mut T>(entity) {
+                    draw(ui, &mut component);
+                }
+            }),
+            copy: None,
+            paste: None,
+            enabled: None,
+        });
+    }
+
+    /// Register a component type whose values can be copied and pasted in the inspector
+    ///
+    /// Values go through the component's serialized form, so fields skipped by serde
+    /// get their defaults when pasted.
+    pub fn register_copyable<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &'static str,
+        category: &'static str,
+        factory: Option<fn() -> T>,
+        draw: fn(&mut Ui, &mut T),
+    ) {
+        self.register(name, category, factory, draw);
+        let registration = self.registrations.last_mut().expect("component was just registered");
+        registration.copy = Some(Box::new(|world, entity| {
+            let component = world.get::<&T>(entity).ok()?;
+            ron::to_string(&*component).ok()
+        }));
+        registration.paste = Some(Box::new(|world, entity, values| {
+            let component: T = ron::from_str(values)?;
+            let _ = world.insert_one(entity, component);
+            Ok(())
+        }));
+    }
+
+    /// Let the named component be switched on and off with the flag `flag` returns
+    ///
+    /// Does nothing if no component has that name.
+    pub fn set_enabled_flag<T: Component>(&mut self, name: &str, flag: fn(&mut T) -> &mut bool) {
+        let Some(registration) = self.registrations.iter_mut().find(|registration| registration.name == name) else {
+            return;
+        };
+        registration.enabled = Some(Box::new(move |world, entity, value| {
+            let mut component = world.get::<&mut T>(entity).ok()?;
+            let enabled = flag(&mut *component);
+            if let Some(value) = value {
+                *enabled = value;
+            }
+            Some(*enabled)
+        }));
+    }
+
+    /// Registration with the given name
+    pub fn find(&self, name: &str) -> Option<&ComponentRegistration> {
+        self.registrations.iter().find(|registration| registration.name == name)
+    }
+
+    /// All registrations, in registration order
+    pub fn registrations(&self) -> &[ComponentRegistration] {
+        &self.registrations
+    }
+
+    /// Menu categories, in the order they were first registered
+    pub fn categories(&self) -> Vec<&'static str> {
+        let mut categories = Vec::new();
+        for registration in &self.registrations {
+            if !categories.contains(&registration.category) {
+                categories.push(registration.category);
+            }
+        }
+        categories
+    }
+}
+
+/// Labelled row with the label in a fixed-width column
+pub(crate) fn field(ui: &mut Ui, label: &str, add_contents: impl FnOnce(&mut Ui)) {
+    ui.horizontal(|ui| {
+        ui.add_sized([LABEL_WIDTH, 20.0], egui::Label::new(label));
+        add_contents(ui);
+    });
+}
+
+/// Drag values for the components of a 2D vector
+fn vec2_field(ui: &mut Ui, label: &str, value: &mut Vec2, speed: f32) {
+    field(ui, label, |ui| {
+        ui.label("X");
+        ui.add(egui::DragValue::new(&mut value.x).speed(speed).fixed_decimals(2));
+        ui.label("Y");
+        ui.add(egui::DragValue::new(&mut value.y).speed(speed).fixed_decimals(2));
+    });
+}
+
+fn draw_transform_2d(ui: &mut Ui, transform: &mut Transform2DComponent) {
+    vec2_field(ui, "Position", &mut transform.position, 0.1);
+
+    let mut degrees = transform.rotation.to_degrees();
+    field(ui, "Rotation", |ui| {
+        if ui.add(egui::DragValue::new(&mut degrees).speed(1.0).suffix("°").fixed_decimals(1)).changed() {
+            transform.rotation = degrees.to_radians();
+        }
+    });
+
+    vec2_field(ui, "Scale", &mut transform.scale, 0.01);
+}
+
+fn draw_layer(ui: &mut Ui, layer: &mut LayerComponent) {
+    field(ui, "Layer", |ui| {
+        egui::ComboBox::from_id_source("layer")
+            .selected_text(layer.layer.to_string())
+            .show_ui(ui, |ui| {
+                for index in 0..LAYER_COUNT {
+                    ui.selectable_value(&mut layer.layer, index, index.to_string());
+                }
+            });
+    });
+}
+
+fn draw_physics(ui: &mut Ui, physics: &mut PhysicsComponent) {
+    field(ui, "Body Type", |ui| {
+        egui::ComboBox::from_id_source("body_type")
+            .selected_text(format!("{:?}", physics.body_type))
+            .show_ui(ui, |ui| {
+                for body_type in [BodyType::Dynamic, BodyType::Kinematic, BodyType::Static] {
+                    ui.selectable_value(&mut physics.body_type, body_type, format!("{:?}", body_type));
+                }
+            });
+    });
+
+    field(ui, "Mass", |ui| {
+        ui.add(egui::DragValue::new(&mut physics.mass).speed(0.1).clamp_range(0.0001..=1000.0));
+    });
+
+    field(ui, "Linear Drag", |ui| {
+        ui.add(egui::DragValue::new(&mut physics.linear_drag).speed(0.01).clamp_range(0.0..=1000.0));
+    });
+
+    field(ui, "Angular Drag", |ui| {
+        ui.add(egui::DragValue::new(&mut physics.angular_drag).speed(0.01).clamp_range(0.0..=1000.0));
+    });
+
+    field(ui, "Use Gravity", |ui| {
+        ui.checkbox(&mut physics.use_gravity, "");
+    });
+
+    field(ui, "Gravity Scale", |ui| {
+        ui.add(egui::DragValue::new(&mut physics.gravity_scale).speed(0.1));
+    });
+
+    vec2_field(ui, "Velocity", &mut physics.velocity, 0.1);
+
+    field(ui, "Angular Velocity", |ui| {
+        ui.add(egui::DragValue::new(&mut physics.angular_velocity).speed(0.1));
+    });
+}
+
+fn draw_collider_2d(ui: &mut Ui, collider: &mut Collider2DComponent) {
+    let shape_name = match collider.shape {
+        ColliderShape::Box { .. } => "Box",
+        ColliderShape::Circle { .. } => "Circle",
+        ColliderShape::Polygon { .. } => "Polygon",
+    };
+    field(ui, "Shape", |ui| {
+        egui::ComboBox::from_id_source("collider_shape")
+            .selected_text(shape_name)
+            .show_ui(ui, |ui| {
+                // Keep roughly the same size when switching shapes
+                let half_extents = collider_half_extents(&collider.shape);
+                if ui.selectable_label(shape_name == "Box", "Box").clicked() && shape_name != "Box" {
+                    collider.shape = ColliderShape::Box { half_extents };
+                }
+                if ui.selectable_label(shape_name == "Circle", "Circle").clicked() && shape_name != "Circle" {
+                    collider.shape = ColliderShape::Circle { radius: half_extents.max_element() };
+                }
+                if ui.selectable_label(shape_name == "Polygon", "Polygon").clicked() && shape_name != "Polygon" {
+                    let (x, y) = (half_extents.x, half_extents.y);
+                    let points = vec![Vec2::new(-x, -y), Vec2::new(x, -y), Vec2::new(x, y), Vec2::new(-x, y)];
+                    collider.shape = ColliderShape::Polygon { points };
+                }
+            });
+    });
+
+    let valid = collider.shape.is_valid();
+    match &mut collider.shape {
+        ColliderShape::Box { half_extents } => {
+            let mut size = *half_extents * 2.0;
+            vec2_field(ui, "Size", &mut size, 0.1);
+            *half_extents = size.max(Vec2::splat(0.001)) / 2.0;
+        }
+        ColliderShape::Circle { radius } => {
+            field(ui, "Radius", |ui| {
+                ui.add(egui::DragValue::new(radius).speed(0.05).clamp_range(0.001..=1000.0));
+            });
+        }
+        ColliderShape::Polygon { points } => {
+            let can_remove = points.len() > 3;
+            let mut removed = None;
+            for (i, point) in points.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    vec2_field(ui, &format!("Point {}", i), point, 0.05);
+                    if ui.add_enabled(can_remove, egui::Button::new("✖").small()).clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                points.remove(i);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Add Point").clicked() {
+                    // Halfway along the closing edge, so the outline stays convex
+                    let point = match (points.first(), points.last()) {
+                        (Some(first), Some(last)) => (*first + *last) / 2.0,
+                        _ => Vec2::ZERO,
+                    };
+                    points.push(point);
+                }
+                if !valid && ui.button("Make Convex").clicked() {
+                    let hull = math::convex_hull(points);
+                    if hull.len() >= 3 {
+                        *points = hull;
+                    }
+                }
+            });
+            if !valid {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 200, 0),
+                    "⚠ Needs at least 3 points in a convex, counter-clockwise outline; ignored by physics until fixed",
+                );
+            }
+        }
+    }
+
+    vec2_field(ui, "Offset", &mut collider.offset, 0.1);
+
+    field(ui, "Is Trigger", |ui| {
+        ui.checkbox(&mut collider.is_trigger, "");
+    });
+}
+
+/// Half size of the box around a collider shape, in the collider's own space
+fn collider_half_extents(shape: &ColliderShape) -> Vec2 {
+    match shape {
+        ColliderShape::Box { half_extents } => *half_extents,
+        ColliderShape::Circle { radius } => Vec2::splat(*radius),
+        ColliderShape::Polygon { points } => points.iter()
+            .fold(Vec2::splat(0.001), |extents, point| extents.max(point.abs())),
+    }
+}
+
+fn draw_light(ui: &mut Ui, light: &mut LightComponent) {
+    field(ui, "Type", |ui| {
+        egui::ComboBox::from_id_source("light_kind")
+            .selected_text(light.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in LightKind::ALL {
+                    ui.selectable_value(&mut light.kind, kind, kind.label());
+                }
+            });
+    });
+
+    let mut color = light.color.to_array();
+    field(ui, "Color", |ui| {
+        if ui.color_edit_button_rgb(&mut color).changed() {
+            light.color = Vec3::from(color);
+        }
+    });
+
+    field(ui, "Intensity", |ui| {
+        ui.add(egui::DragValue::new(&mut light.intensity).speed(0.05).fixed_decimals(2).clamp_range(0.0..=f32::MAX));
+    });
+
+    match light.kind {
+        LightKind::Point => {
+            field(ui, "Range", |ui| {
+                ui.add(egui::DragValue::new(&mut light.range).speed(0.1).fixed_decimals(2).clamp_range(0.01..=f32::MAX));
+            });
+        }
+        LightKind::Directional => {
+            field(ui, "Shadow Resolution", |ui| {
+                egui::ComboBox::from_id_source("shadow_resolution")
+                    .selected_text(light.shadow_resolution.name())
+                    .show_ui(ui, |ui| {
+                        for resolution in ShadowResolution::ALL {
+                            ui.selectable_value(&mut light.shadow_resolution, resolution, resolution.name());
+                        }
+                    });
+            });
+            field(ui, "Shadow Bias", |ui| {
+                ui.add(egui::DragValue::new(&mut light.shadow_bias).speed(0.0005).fixed_decimals(4).clamp_range(0.0..=1.0));
+            });
+        }
+    }
+}
+
+fn draw_camera(ui: &mut Ui, camera: &mut CameraComponent) {
+    field(ui, "Projection", |ui| {
+        egui::ComboBox::from_id_source("projection")
+            .selected_text(if camera.is_orthographic { "Orthographic" } else { "Perspective" })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut camera.is_orthographic, false, "Perspective");
+                ui.selectable_value(&mut camera.is_orthographic, true, "Orthographic");
+            });
+    });
+
+    if camera.is_orthographic {
+        field(ui, "Size", |ui| {
+            ui.add(egui::DragValue::new(&mut camera.ortho_size).speed(0.1).fixed_decimals(2).clamp_range(0.01..=10000.0));
+        });
+    } else {
+        let mut degrees = camera.fov.to_degrees();
+        field(ui, "Field of View", |ui| {
+            if ui.add(egui::Slider::new(&mut degrees, 1.0..=179.0).suffix("°").fixed_decimals(1)).changed() {
+                camera.fov = degrees.to_radians();
+            }
+        });
+    }
+
+    field(ui, "Clipping Planes", |ui| {
+        ui.label("Near");
+        ui.add(egui::DragValue::new(&mut camera.near).speed(0.01).fixed_decimals(2).clamp_range(0.001..=camera.far));
+        ui.label("Far");
+        ui.add(egui::DragValue::new(&mut camera.far).speed(1.0).fixed_decimals(1).clamp_range(camera.near..=f32::MAX));
+    });
+
+    field(ui, "Clear Flags", |ui| {
+        egui::ComboBox::from_id_source("clear_flags")
+            .selected_text(camera.clear_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in ClearMode::ALL {
+                    ui.selectable_value(&mut camera.clear_mode, mode, mode.label());
+                }
+            });
+    });
+
+    // Skyboxes fall back to the color when no cubemap is loaded
+    if matches!(camera.clear_mode, ClearMode::SolidColor | ClearMode::Skybox) {
+        field(ui, "Background", |ui| {
+            ui.color_edit_button_rgba_unmultiplied(&mut camera.clear_color);
+        });
+    }
+
+    let [x, y, width, height] = &mut camera.viewport;
+    field(ui, "Viewport Rect", |ui| {
+        ui.label("X");
+        ui.add(egui::DragValue::new(x).speed(0.01).fixed_decimals(2).clamp_range(0.0..=1.0));
+        ui.label("Y");
+        ui.add(egui::DragValue::new(y).speed(0.01).fixed_decimals(2).clamp_range(0.0..=1.0));
+    });
+    field(ui, "", |ui| {
+        ui.label("W");
+        ui.add(egui::DragValue::new(width).speed(0.01).fixed_decimals(2).clamp_range(0.0..=1.0));
+        ui.label("H");
+        ui.add(egui::DragValue::new(height).speed(0.01).fixed_decimals(2).clamp_range(0.0..=1.0));
+    });
+
+    field(ui, "Depth", |ui| {
+        ui.add(egui::DragValue::new(&mut camera.depth));
+    });
+}
+
+fn draw_text(ui: &mut Ui, text: &mut TextComponent) {
+    field(ui, "Text", |ui| {
+        ui.add(egui::TextEdit::multiline(&mut text.text).desired_rows(2));
+    });
+
+    field(ui, "Font Size", |ui| {
+        ui.add(egui::DragValue::new(&mut text.font_size).speed(0.5).fixed_decimals(1).clamp_range(1.0..=512.0));
+    });
+
+    field(ui, "Color", |ui| {
+        ui.color_edit_button_rgba_unmultiplied(&mut text.color);
+    });
+
+    field(ui, "Alignment", |ui| {
+        egui::ComboBox::from_id_source("text_align")
+            .selected_text(text.align.label())
+            .show_ui(ui, |ui| {
+                for align in TextAlign::ALL {
+                    ui.selectable_value(&mut text.align, align, align.label());
+                }
+            });
+    });
+
+    let mut wrap = text.max_width.is_some();
+    field(ui, "Wrap", |ui| {
+        if ui.checkbox(&mut wrap, "").changed() {
+            text.max_width = wrap.then_some(text.font_size * 10.0);
+        }
+    });
+    if let Some(max_width) = &mut text.max_width {
+        field(ui, "Max Width", |ui| {
+            ui.add(egui::DragValue::new(max_width).speed(1.0).fixed_decimals(0).clamp_range(1.0..=f32::MAX));
+        });
+    }
+
+    field(ui, "Space", |ui| {
+        egui::ComboBox::from_id_source("text_space")
+            .selected_text(match text.space {
+                TextSpace::World => "World",
+                TextSpace::Screen => "Screen",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut text.space, TextSpace::World, "World");
+                ui.selectable_value(&mut text.space, TextSpace::Screen, "Screen");
+            });
+    });
+}
+
+fn draw_renderable(ui: &mut Ui, renderable: &mut RenderableComponent) {
+    field(ui, "Cast Shadows", |ui| {
+        ui.checkbox(&mut renderable.casts_shadows, "");
+    });
+
+    let model = renderable.model.get();
+    field(ui, "Mesh", |ui| {
+        ui.label(&model.mesh.name);
+    });
+
+    // Without a material file the model's own materials are used
+    if renderable.material_path.is_none() {
+        field(ui, "Model Material", |ui| {
+            ui.label(&model.material.name);
+        });
+    }
+    if let Some(path) = material_fields(ui, "renderable_material", renderable.material_path.as_ref(), renderable.material.as_ref()) {
+        renderable.set_material_path(path);
+    }
+}
+
+fn draw_sprite(ui: &mut Ui, sprite: &mut SpriteComponent) {
+    vec2_field(ui, "Size", &mut sprite.size, 0.1);
+
+    field(ui, "Color", |ui| {
+        ui.color_edit_button_rgba_unmultiplied(&mut sprite.tint);
+    });
+
+    field(ui, "Sorting Layer", |ui| {
+        ui.text_edit_singleline(&mut sprite.sorting_layer);
+    });
+
+    field(ui, "Order in Layer", |ui| {
+        ui.add(egui::DragValue::new(&mut sprite.order_in_layer));
+    });
+
+    if let Some(path) = material_fields(ui, "sprite_material", sprite.material_path.as_ref(), sprite.material.as_ref()) {
+        sprite.set_material_path(path);
+    }
+}
+
+fn draw_audio_source(ui: &mut Ui, source: &mut AudioSourceComponent) {
+    let mut clip = source.clip.to_string_lossy().into_owned();
+    field(ui, "Clip", |ui| {
+        if ui.add(egui::TextEdit::singleline(&mut clip).hint_text("Audio/clip.ogg")).changed() {
+            source.clip = clip.into();
+        }
+        if let Some(dropped) = asset_drag::path_drop_target(ui, ProjectFileType::Audio) {
+            source.clip = dropped;
+        }
+    });
+
+    field(ui, "Volume", |ui| {
+        ui.add(egui::Slider::new(&mut source.volume, 0.0..=1.0));
+    });
+
+    field(ui, "Loop", |ui| {
+        ui.checkbox(&mut source.looping, "");
+    });
+
+    field(ui, "Play On Start", |ui| {
+        ui.checkbox(&mut source.play_on_start, "");
+    });
+
+    field(ui, "Spatial", |ui| {
+        ui.checkbox(&mut source.spatial, "");
+    });
+}
+
+fn draw_lua_script(ui: &mut Ui, script: &mut LuaScriptComponent) {
+    let mut path = script.path.to_string_lossy().into_owned();
+    field(ui, "Script", |ui| {
+        let response = ui.add(egui::TextEdit::singleline(&mut path).hint_text("Scripts/script.lua"));
+        if response.changed() {
+            script.path = path.into();
+            // Reload the script on the next run
+            script.handle = None;
+        }
+        if let Some(dropped) = asset_drag::path_drop_target(ui, ProjectFileType::Script) {
+            script.path = dropped;
+            script.handle = None;
+        }
+    });
+    if ui.add_enabled(!script.path.as_os_str().is_empty(), egui::Button::new("Edit Script")).clicked() {
+        ScriptEditorPanel::request_open(ui.ctx(), &script.path);
+    }
+}
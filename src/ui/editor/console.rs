@@ -1,53 +1,143 @@
-use egui::{Context, Ui, ScrollArea};
+use std::sync::{Arc, Mutex};
+use egui::{Context, Key, Modifiers, Ui, ScrollArea};
 use chrono::Local;
+use log::{Level, Log, Metadata, Record, SetLoggerError};
 use crate::ui::editor::ui_components::{ConsoleLog, LogLevel, helpers};
 
+/// Maximum number of log messages kept by the console
+pub const MAX_CONSOLE_LOGS: usize = 5000;
+
+/// Maximum number of typed commands the console remembers
+const MAX_COMMAND_HISTORY: usize = 100;
+
+/// Log records waiting to be picked up by the console
+pub type SharedConsoleLogs = Arc<Mutex<Vec<ConsoleLog>>>;
+
+/// `log` implementation that prints through `env_logger` and forwards the records to the console
+pub struct ConsoleLogger {
+    inner: env_logger::Logger,
+    logs: SharedConsoleLogs,
+}
+
+impl ConsoleLogger {
+    /// Install a logger built from `builder` as the global logger
+    ///
+    /// Records that pass the builder's filters are also collected in the returned
+    /// logs, to be shown by `ConsolePanel::set_log_source`.
+    pub fn install(mut builder: env_logger::Builder) -> Result<SharedConsoleLogs, SetLoggerError> {
+        let inner = builder.build();
+        let max_level = inner.filter();
+        let logs = SharedConsoleLogs::default();
+
+        log::set_boxed_logger(Box::new(Self {
+            inner,
+            logs: logs.clone(),
+        }))?;
+        log::set_max_level(max_level);
+
+        Ok(logs)
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+
+        let level = match record.level() {
+            Level::Error => LogLevel::Error,
+            Level::Warn => LogLevel::Warning,
+            Level::Info | Level::Debug | Level::Trace => LogLevel::Info,
+        };
+
+        // A poisoned lock only means a panic while holding it, the logs are still usable
+        let mut logs = self.logs.lock().unwrap_or_else(|e| e.into_inner());
+        logs.push(ConsoleLog {
+            timestamp: Local::now().format("%H:%M:%S").to_string(),
+            level,
+            target: Some(record.target().to_string()),
+            message: record.args().to_string(),
+        });
+        // Nobody may be reading the logs, e.g. when running without the editor
+        truncate_logs(&mut logs);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
 /// Console panel for displaying logs
 pub struct ConsolePanel {
     /// Whether to show console
     pub show_console: bool,
-    /// Log messages for console
+    /// Log messages for console, oldest first
     pub console_logs: Vec<ConsoleLog>,
+    /// Show info messages
+    pub show_info: bool,
+    /// Show warnings
+    pub show_warning: bool,
+    /// Show errors
+    pub show_error: bool,
+    /// Only show messages containing this text
+    pub filter: String,
+    /// Records from the `log` crate, moved into `console_logs` every frame
+    log_source: Option<SharedConsoleLogs>,
+    /// Text of the command line
+    command_line: String,
+    /// Commands entered so far, oldest first
+    command_history: Vec<String>,
+    /// Entry of `command_history` shown in the command line while browsing it with the arrow keys
+    history_position: Option<usize>,
+    /// Command entered this frame, run by the editor
+    entered_command: Option<String>,
 }
 
 impl ConsolePanel {
     /// Create a new console panel
     pub fn new() -> Self {
-        // Create sample console logs
-        let console_logs = vec![
-            ConsoleLog {
-                timestamp: "12:34:56".to_string(),
-                level: LogLevel::Info,
-                message: "Editor initialized".to_string(),
-            },
-            ConsoleLog {
-                timestamp: "12:34:57".to_string(),
-                level: LogLevel::Info,
-                message: "Scene loaded".to_string(),
-            },
-            ConsoleLog {
-                timestamp: "12:34:58".to_string(),
-                level: LogLevel::Warning,
-                message: "Missing sprite reference".to_string(),
-            },
-            ConsoleLog {
-                timestamp: "12:34:59".to_string(),
-                level: LogLevel::Info,
-                message: "Project assets loaded".to_string(),
-            },
-            ConsoleLog {
-                timestamp: "12:35:00".to_string(),
-                level: LogLevel::Error,
-                message: "Failed to load audio file".to_string(),
-            },
-        ];
-        
         Self {
             show_console: false,
-            console_logs,
+            console_logs: Vec::new(),
+            show_info: true,
+            show_warning: true,
+            show_error: true,
+            filter: String::new(),
+            log_source: None,
+            command_line: String::new(),
+            command_history: Vec::new(),
+            history_position: None,
+            entered_command: None,
+        }
+    }
+
+    /// Show the records collected by a `ConsoleLogger`
+    pub fn set_log_source(&mut self, logs: SharedConsoleLogs) {
+        self.log_source = Some(logs);
+    }
+
+    /// Move the records logged since the last call into the console
+    pub fn collect_logs(&mut self) {
+        let Some(source) = &self.log_source else {
+            return;
+        };
+
+        let mut source = source.lock().unwrap_or_else(|e| e.into_inner());
+        if source.is_empty() {
+            return;
         }
+        self.console_logs.append(&mut *source);
+        drop(source);
+
+        truncate_logs(&mut self.console_logs);
     }
-    
+
     /// Render console window
     pub fn render(&mut self, ctx: &Context) {
         if self.show_console {
@@ -67,18 +157,16 @@ impl ConsolePanel {
                             }
                         });
                     });
-                    
+
                     ui.separator();
-                    
-                    ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
-                        for log in &self.console_logs {
-                            ui.label(helpers::get_log_text(log));
-                        }
-                    });
+
+                    self.render_filters(ui);
+                    self.render_logs(ui);
+                    self.render_command_line(ui);
                 });
         }
     }
-    
+
     /// Render project console tab
     pub fn render_project_console(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
@@ -92,45 +180,154 @@ impl ConsolePanel {
                 }
             });
         });
-        
+
         ui.separator();
-        
-        ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
-            for log in &self.console_logs {
-                ui.label(helpers::get_log_text(log));
+
+        self.render_filters(ui);
+        self.render_logs(ui);
+        self.render_command_line(ui);
+    }
+
+    /// Level checkboxes and the text filter
+    fn render_filters(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_info, "Info");
+            ui.checkbox(&mut self.show_warning, "Warning");
+            ui.checkbox(&mut self.show_error, "Error");
+            ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter"));
+        });
+    }
+
+    /// The messages that pass the filters
+    fn render_logs(&self, ui: &mut Ui) {
+        let filter = self.filter.to_lowercase();
+        // Leave room for the command line below
+        let command_line_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+
+        ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .max_height((ui.available_height() - command_line_height).max(0.0))
+            .show(ui, |ui| {
+                for log in self.console_logs.iter().filter(|log| self.is_visible(log, &filter)) {
+                    ui.label(helpers::get_log_text(log));
+                }
+            });
+    }
+
+    /// Input line for console commands, Up and Down browse the ones entered before
+    fn render_command_line(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(">");
+            let response = ui.add(egui::TextEdit::singleline(&mut self.command_line)
+                .hint_text("Type a command, 'help' lists them")
+                .desired_width(f32::INFINITY));
+
+            if response.has_focus() {
+                if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::ArrowUp)) {
+                    self.browse_history(true);
+                }
+                if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::ArrowDown)) {
+                    self.browse_history(false);
+                }
+            }
+
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                self.enter_command();
+                // Keep typing commands without clicking back into the line
+                response.request_focus();
             }
         });
     }
-    
+
+    /// Show the previous or next entered command in the command line
+    fn browse_history(&mut self, older: bool) {
+        let Some(last) = self.command_history.len().checked_sub(1) else {
+            return;
+        };
+
+        self.history_position = match (self.history_position, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(position), true) => Some(position.saturating_sub(1)),
+            (Some(position), false) => (position < last).then_some(position + 1),
+        };
+        self.command_line = self.history_position
+            .map_or_else(String::new, |position| self.command_history[position].clone());
+    }
+
+    /// Hand the command line to the editor and remember it
+    fn enter_command(&mut self) {
+        let command = self.command_line.trim().to_string();
+        self.command_line.clear();
+        self.history_position = None;
+        if command.is_empty() {
+            return;
+        }
+
+        if self.command_history.last() != Some(&command) {
+            self.command_history.push(command.clone());
+            if self.command_history.len() > MAX_COMMAND_HISTORY {
+                self.command_history.remove(0);
+            }
+        }
+        self.entered_command = Some(command);
+    }
+
+    /// The command entered this frame, if any
+    pub fn take_command(&mut self) -> Option<String> {
+        self.entered_command.take()
+    }
+
+    /// Whether a message passes the level filters and contains `filter`, which must be lowercase
+    fn is_visible(&self, log: &ConsoleLog, filter: &str) -> bool {
+        let level_shown = match log.level {
+            LogLevel::Info => self.show_info,
+            LogLevel::Warning => self.show_warning,
+            LogLevel::Error => self.show_error,
+        };
+
+        level_shown && (filter.is_empty()
+            || log.message.to_lowercase().contains(filter)
+            || log.target.as_ref().map_or(false, |target| target.to_lowercase().contains(filter)))
+    }
+
     /// Log info message
     pub fn log_info(&mut self, message: &str) {
         self.add_log(LogLevel::Info, message);
     }
-    
+
     /// Log warning message
     pub fn log_warning(&mut self, message: &str) {
         self.add_log(LogLevel::Warning, message);
     }
-    
+
     /// Log error message
     pub fn log_error(&mut self, message: &str) {
         self.add_log(LogLevel::Error, message);
     }
-    
+
     /// Add log message
     pub fn add_log(&mut self, level: LogLevel, message: &str) {
+        // Keep the order with records logged earlier in the frame
+        self.collect_logs();
+
         let now = Local::now();
         let timestamp = now.format("%H:%M:%S").to_string();
-        
+
         self.console_logs.push(ConsoleLog {
             timestamp,
             level,
+            target: None,
             message: message.to_string(),
         });
-        
-        // Limit log size
-        if self.console_logs.len() > 100 {
-            self.console_logs.remove(0);
-        }
+
+        truncate_logs(&mut self.console_logs);
     }
-} 
\ No newline at end of file
+}
+
+/// Drop the oldest messages beyond `MAX_CONSOLE_LOGS`
+fn truncate_logs(logs: &mut Vec<ConsoleLog>) {
+    if logs.len() > MAX_CONSOLE_LOGS {
+        logs.drain(..logs.len() - MAX_CONSOLE_LOGS);
+    }
+}
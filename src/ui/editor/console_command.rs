@@ -0,0 +1,195 @@
+use crate::rendering::PrimitiveShape;
+use crate::ecs::LightKind;
+use crate::ui::editor::editor_ui::{EditorUI, SceneAction};
+use crate::ui::editor::hierarchy::EntityType;
+
+/// Runs a console command with the words typed after its name
+///
+/// The returned text is printed to the console, as an error for `Err`.
+pub type ConsoleCommandFn = fn(&mut EditorUI, &[&str]) -> Result<String, String>;
+
+/// A command that can be typed into the console
+#[derive(Clone, Copy)]
+pub struct ConsoleCommand {
+    pub name: &'static str,
+    /// Arguments shown by `help`, e.g. `<type> [name]`
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub run: ConsoleCommandFn,
+}
+
+/// Commands the console can run, looked up by name
+#[derive(Default)]
+pub struct ConsoleCommandRegistry {
+    commands: Vec<ConsoleCommand>,
+}
+
+impl ConsoleCommandRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the editor's built-in commands
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register("help", "[command]", "List the commands, or describe one", help);
+        registry.register("clear", "", "Clear the console", clear);
+        registry.register("fps", "", "Print the scene view's frame rate", fps);
+        registry.register("spawn", "<type> [name]", "Create an entity, e.g. `spawn cube` or `spawn point_light Lamp`", spawn);
+        registry.register("undo", "", "Undo the last edit", undo);
+        registry.register("redo", "", "Redo the last undone edit", redo);
+        registry.register("play", "", "Enter play mode", play);
+        registry.register("stop", "", "Leave play mode", stop);
+        registry.register("scene.new", "", "Start a new scene", scene_new);
+        registry.register("scene.save", "", "Save the scene", scene_save);
+        registry
+    }
+
+    /// Register a command, replacing any command with the same name
+    pub fn register(&mut self, name: &'static str, usage: &'static str, description: &'static str, run: ConsoleCommandFn) {
+        let command = ConsoleCommand { name, usage, description, run };
+        match self.commands.iter_mut().find(|existing| existing.name == name) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// The command called `name`
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    /// All commands, in the order they were registered
+    pub fn commands(&self) -> impl Iterator<Item = &ConsoleCommand> {
+        self.commands.iter()
+    }
+
+    /// The command whose name is closest to a mistyped `name`, if any is close enough
+    pub fn suggest(&self, name: &str) -> Option<&'static str> {
+        let max_distance = (name.chars().count() / 3).max(2);
+        self.commands.iter()
+            .map(|command| (command.name, edit_distance(name, command.name)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(name, _)| name)
+    }
+
+    /// Error for a name no command has, suggesting the closest one
+    pub fn unknown_command(&self, name: &str) -> String {
+        match self.suggest(name) {
+            Some(suggestion) => format!("Unknown command '{}', did you mean '{}'?", name, suggestion),
+            None => format!("Unknown command '{}', type 'help' to list the commands", name),
+        }
+    }
+}
+
+/// Number of single character insertions, deletions and substitutions turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Run an editor operation that reports through a log callback, returning what it logged
+fn logged(editor: &mut EditorUI, operation: impl FnOnce(&mut EditorUI, &mut dyn FnMut(&str))) -> String {
+    let mut messages = Vec::new();
+    operation(editor, &mut |message: &str| messages.push(message.to_string()));
+    messages.join("\n")
+}
+
+fn help(editor: &mut EditorUI, args: &[&str]) -> Result<String, String> {
+    let registry = &editor.console_commands;
+    if let Some(&name) = args.first() {
+        let command = registry.get(name).ok_or_else(|| registry.unknown_command(name))?;
+        return Ok(format!("{} {}\n  {}", command.name, command.usage, command.description));
+    }
+
+    let lines: Vec<String> = registry.commands()
+        .map(|command| format!("{} {} - {}", command.name, command.usage, command.description))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn clear(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    editor.console_panel.console_logs.clear();
+    Ok(String::new())
+}
+
+fn fps(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    let renderer = editor.scene_view_panel.renderer.as_ref().ok_or("No renderer to measure")?;
+    let frame_time = renderer.average_stats().frame_time.as_secs_f32();
+    if frame_time <= 0.0 {
+        return Err("No frames rendered yet".to_string());
+    }
+    Ok(format!("{:.1} FPS ({:.2} ms)", 1.0 / frame_time, frame_time * 1000.0))
+}
+
+fn spawn(editor: &mut EditorUI, args: &[&str]) -> Result<String, String> {
+    let Some((&kind, name)) = args.split_first() else {
+        return Err("Usage: spawn <type> [name]".to_string());
+    };
+
+    let (entity_type, default_name) = match kind.to_lowercase().as_str() {
+        "empty" => (EntityType::GameObject, "GameObject"),
+        "camera" => (EntityType::Camera, "Camera"),
+        "light" | "directional_light" => (EntityType::Light(LightKind::Directional), "Directional Light"),
+        "point_light" => (EntityType::Light(LightKind::Point), "Point Light"),
+        "sprite" => (EntityType::Sprite, "Sprite"),
+        "text" => (EntityType::Text, "Text"),
+        "audio" | "audio_source" => (EntityType::AudioSource, "Audio Source"),
+        other => match PrimitiveShape::ALL.into_iter().find(|shape| shape.name().eq_ignore_ascii_case(other)) {
+            Some(shape) => (EntityType::Primitive(shape), shape.name()),
+            None => return Err(format!(
+                "Unknown entity type '{}', expected empty, camera, light, point_light, sprite, text, audio or a primitive such as cube",
+                kind,
+            )),
+        },
+    };
+
+    let name = if name.is_empty() { default_name.to_string() } else { name.join(" ") };
+    editor.hierarchy_panel.create_entity(&name, entity_type);
+    Ok(format!("Spawned {}", name))
+}
+
+fn undo(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    let mut undone = false;
+    let message = logged(editor, |editor, log_info| undone = editor.undo(log_info));
+    if undone { Ok(message) } else { Err("Nothing to undo".to_string()) }
+}
+
+fn redo(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    let mut redone = false;
+    let message = logged(editor, |editor, log_info| redone = editor.redo(log_info));
+    if redone { Ok(message) } else { Err("Nothing to redo".to_string()) }
+}
+
+fn play(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    if editor.play_mode.is_playing() {
+        return Err("Already playing".to_string());
+    }
+    Ok(logged(editor, |editor, log_info| editor.start_playing(log_info)))
+}
+
+fn stop(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    if !editor.play_mode.is_playing() {
+        return Err("Not playing".to_string());
+    }
+    Ok(logged(editor, |editor, log_info| editor.stop_playing(log_info)))
+}
+
+fn scene_new(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    Ok(logged(editor, |editor, log_info| editor.request_scene_action(SceneAction::NewScene, log_info)))
+}
+
+fn scene_save(editor: &mut EditorUI, _args: &[&str]) -> Result<String, String> {
+    Ok(logged(editor, |editor, log_info| editor.save_scene(log_info)))
+}
@@ -1,22 +1,58 @@
-use egui::{Context, RichText, Ui, Window, SidePanel, TopBottomPanel, CentralPanel, Style, Visuals, Color32, Stroke, Rect};
+use egui::{Context, RichText, Ui, Window, SidePanel, TopBottomPanel, CentralPanel, Style, Visuals, Color32, Stroke};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use glam::Vec3;
 use crate::ui::editor::{
-    ui_components::{ProjectTab, LogLevel, ConsoleLog, ProjectFile, ProjectFileType, 
-    EntityComponent, ComponentType, EntityTransform, HierarchyItem, 
-    AudioFile, SceneViewTool},
-    hierarchy::HierarchyPanel,
+    ui_components::{ProjectTab, LogLevel, ConsoleLog, 
+    EntityTransform, HierarchyItem, 
+    AudioFile, SceneViewTool, Selection, ProjectFileType},
+    hierarchy::{HierarchyPanel, EntityType, spawn_entity},
     inspector::InspectorPanel,
     project::ProjectPanel,
-    scene_view::SceneViewPanel,
+    scene_view::{SceneViewPanel, PhysicsDebugInfo, CameraGizmo, LightGizmo},
     game_view::GameViewPanel,
     audio_panel::AudioPanel,
     console::ConsolePanel,
+    console_command::ConsoleCommandRegistry,
+    script_editor::ScriptEditorPanel,
+    history::{CommandHistory, EditorState, DeleteEntityCommand, GroupEntitiesCommand, SpawnEntitiesCommand, UngroupEntitiesCommand},
+    clipboard::{Clipboard, duplicate_command},
+    asset_drag::{AssetDrop, DraggedAsset},
+    game_input::feed_egui_input,
+    layout::EditorLayout,
 };
 use crate::audio::AudioCategory;
+use crate::ecs::{
+    AudioSourceComponent, BodyType, CameraComponent, Collider2DComponent, EcsManager, EntityHandle, LightComponent, LightKind,
+    LuaScriptComponent, PhysicsComponent, Prefab, RenderableComponent, SpriteComponent, Transform2DComponent, Transform3DComponent,
+    PREFAB_EXTENSION, primitive_mesh_system, material_asset_system,
+};
+use crate::assets::AssetManager;
+use crate::rendering::TextureSampler;
+use crate::scene::{Autosaver, PlayMode, RecoveryData, SceneData, SceneError, load_world, save_world};
+use crate::core::{Input, InputMap};
+use crate::scripting::LuaEngine;
+use crate::utils::config::{EditorSettings, ThemeMode};
+
+/// Directory prefabs are saved to, relative to the working directory
+pub const PREFAB_DIRECTORY: &str = "Prefabs";
+/// Directory screenshots are saved to, relative to the project or working directory
+pub const SCREENSHOT_DIRECTORY: &str = "Screenshots";
+
+/// Time scales offered by the toolbar, 0 freezes the game
+const TIME_SCALES: [f32; 6] = [0.0, 0.1, 0.25, 0.5, 1.0, 2.0];
+
+/// Extension of scene files
+pub const SCENE_EXTENSION: &str = "scene";
+
+/// Title of the editor window, followed by the scene name
+pub const WINDOW_TITLE: &str = "Mirage Engine Editor";
 
 /// The main editor UI for the engine
 pub struct EditorUI {
+    /// The ECS world being edited
+    pub ecs: EcsManager,
     /// Hierarchy panel
     pub hierarchy_panel: HierarchyPanel,
     /// Inspector panel
@@ -31,14 +67,78 @@ pub struct EditorUI {
     pub audio_panel: AudioPanel,
     /// Console panel
     pub console_panel: ConsolePanel,
+    /// Commands that can be typed into the console
+    pub console_commands: ConsoleCommandRegistry,
+    /// Lua script editor window
+    pub script_editor: ScriptEditorPanel,
     /// Current active view (Scene/Game)
     pub active_view: ActiveView,
-    /// Show grid in scene view
-    pub show_grid: bool,
     /// Editor toolbar state
     pub toolbar: ToolbarState,
     /// Current editor theme
     pub theme: EditorTheme,
+    /// Undo/redo history of editor operations
+    pub history: CommandHistory,
+    /// Entities copied with Ctrl+C or Ctrl+X
+    pub clipboard: Clipboard,
+    /// File the scene was opened from or last saved to
+    pub current_scene_path: Option<PathBuf>,
+    /// Editing, playing or paused
+    pub play_mode: PlayMode,
+    /// Keyboard and mouse as the game sees them while playing
+    pub game_input: Input,
+    /// Actions and axes the game and its scripts query instead of keys
+    pub input_map: InputMap,
+    /// Material files used by the scene, shared between the entities using them
+    pub assets: AssetManager,
+    /// Editor state put aside while playing
+    edit_session: Option<EditSession>,
+    /// Whether the scene changed in ways the undo history doesn't track since it was
+    /// last saved, e.g. component fields edited in the inspector
+    pub dirty: bool,
+    /// Action waiting for the user to save or discard unsaved changes
+    pending_scene_action: Option<SceneAction>,
+    /// Whether the user agreed to exit, see `request_exit`
+    exit_confirmed: bool,
+    /// Panel visibility and sizes, saved when they change
+    pub layout: EditorLayout,
+    /// Layout as last saved, to notice changes
+    saved_layout: EditorLayout,
+    /// Part of the panel ids, changed to make egui forget the panels' sizes
+    layout_generation: u32,
+    /// User preferences, saved when they change
+    pub settings: EditorSettings,
+    /// Settings as last saved, to notice changes
+    saved_settings: EditorSettings,
+    /// Whether the Preferences window is shown
+    pub show_preferences: bool,
+    /// Whether the Scene Stats window is shown
+    pub show_scene_stats: bool,
+    /// Writes the recovery file in the background
+    autosaver: Autosaver,
+    /// Autosave left by a session that ended with unsaved changes, waiting for the user to recover or discard it
+    recovery_prompt: Option<RecoveryData>,
+}
+
+/// Editor actions that throw away the current scene
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneAction {
+    NewScene,
+    /// Pick a scene file and open it
+    OpenScene,
+    /// Open a known scene file, e.g. from File > Open Recent
+    OpenSceneFile(PathBuf),
+    Exit,
+}
+
+/// Editor state from before Play, restored on Stop along with the world
+struct EditSession {
+    /// Undo history of the edits, play mode records into a fresh one
+    history: CommandHistory,
+    /// Entity types of the edited entities
+    entity_types: HashMap<EntityHandle, EntityType>,
+    /// Selection when Play was pressed
+    selection: Selection,
 }
 
 /// Active view in the editor
@@ -54,13 +154,10 @@ pub enum ActiveView {
 pub struct ToolbarState {
     /// Current transform tool
     pub transform_tool: SceneViewTool,
-    /// Toggle for play/pause
-    pub play_mode: bool,
-    /// Toggle for pause
-    pub paused: bool,
 }
 
 /// Editor theme
+#[derive(Debug, Clone, Copy)]
 pub struct EditorTheme {
     /// Background color
     pub background: Color32,
@@ -78,25 +175,84 @@ pub struct EditorTheme {
     pub component_header: Color32,
 }
 
+impl EditorTheme {
+    /// Colors of the given color scheme
+    pub fn new(mode: ThemeMode) -> Self {
+        match mode {
+            ThemeMode::Dark => Self {
+                background: Color32::from_rgb(56, 56, 56),
+                panel_background: Color32::from_rgb(42, 42, 42),
+                text: Color32::from_rgb(220, 220, 220),
+                selected: Color32::from_rgb(44, 93, 135),
+                header: Color32::from_rgb(37, 37, 37),
+                grid: Color32::from_rgb(60, 60, 60),
+                component_header: Color32::from_rgb(65, 65, 65),
+            },
+            ThemeMode::Light => Self {
+                background: Color32::from_rgb(200, 200, 200),
+                panel_background: Color32::from_rgb(222, 222, 222),
+                text: Color32::from_rgb(20, 20, 20),
+                selected: Color32::from_rgb(58, 114, 176),
+                header: Color32::from_rgb(203, 203, 203),
+                grid: Color32::from_rgb(170, 170, 170),
+                component_header: Color32::from_rgb(190, 190, 190),
+            },
+        }
+    }
+}
+
 impl EditorUI {
     /// Create a new editor UI
     pub fn new() -> Self {
-        let hierarchy_panel = HierarchyPanel::new();
+        let mut ecs = EcsManager::new();
+        let mut hierarchy_panel = HierarchyPanel::new();
         let mut inspector_panel = InspectorPanel::new();
         let mut scene_view_panel = SceneViewPanel::new();
-        let project_panel = ProjectPanel::new();
+        let mut project_panel = ProjectPanel::new();
         let game_view_panel = GameViewPanel::new();
         let audio_panel = AudioPanel::new();
-        let console_panel = ConsolePanel::new();
+        let mut console_panel = ConsolePanel::new();
+        let layout = EditorLayout::load();
+        let mut settings = EditorSettings::load();
+        // Saved on the first frame if opening the project changes the recent projects
+        let saved_settings = settings.clone();
+        scene_view_panel.apply_settings(&settings);
         
-        // Share entity names with scene view
-        scene_view_panel.set_entity_names(hierarchy_panel.entity_names.clone());
+        match LuaEngine::new() {
+            Ok(engine) => ecs.set_script_engine(engine),
+            Err(e) => console_panel.log_error(&format!("Scripting disabled: {}", e)),
+        }
         
-        // Share entity transforms between inspector and scene view using the same Arc<Mutex<>>
-        let entity_transforms = scene_view_panel.get_entity_transforms();
-        inspector_panel.set_entity_transforms(entity_transforms);
+        // The working directory is the project until projects can be opened from the editor
+        match std::env::current_dir() {
+            Ok(root) => {
+                match project_panel.open_project(root.clone()) {
+                    Ok(()) => settings.add_recent_project(&root),
+                    Err(e) => console_panel.log_error(&format!("Failed to open project {}: {}", root.display(), e)),
+                }
+            }
+            Err(e) => console_panel.log_error(&format!("Failed to open project: {}", e)),
+        }
+        
+        // Populate the default scene
+        let default_entities = [
+            ("Main Camera", EntityType::Camera, EntityTransform::new([0.0, 0.0, -10.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0])),
+            ("Directional Light", EntityType::Light(LightKind::Directional), EntityTransform::new([5.0, 10.0, 0.0], [45.0, 0.0, 0.0], [1.0, 1.0, 1.0])),
+            ("Player", EntityType::GameObject, EntityTransform::default()),
+            ("Background", EntityType::Sprite, EntityTransform::new([0.0, -2.0, 0.0], [0.0, 0.0, 0.0], [20.0, 1.0, 1.0])),
+            ("UI Canvas", EntityType::UI, EntityTransform::default()),
+        ];
+        for (name, entity_type, transform) in default_entities {
+            let handle = spawn_entity(&mut ecs, name, entity_type, transform.to_component());
+            hierarchy_panel.entity_types.insert(handle, entity_type);
+        }
+        
+        // Share entity names with scene view
+        scene_view_panel.set_entity_names(hierarchy_panel.entity_names(&ecs));
+        scene_view_panel.set_entity_types(hierarchy_panel.entity_types.clone());
         
         Self {
+            ecs,
             hierarchy_panel,
             inspector_panel,
             project_panel,
@@ -104,22 +260,33 @@ impl EditorUI {
             game_view_panel,
             audio_panel,
             console_panel,
-            active_view: ActiveView::Scene,
-            show_grid: true,
+            console_commands: ConsoleCommandRegistry::with_builtin(),
+            script_editor: ScriptEditorPanel::new(),
+            active_view: if layout.show_game_view { ActiveView::Game } else { ActiveView::Scene },
             toolbar: ToolbarState {
                 transform_tool: SceneViewTool::Select,
-                play_mode: false,
-                paused: false,
-            },
-            theme: EditorTheme {
-                background: Color32::from_rgb(56, 56, 56),
-                panel_background: Color32::from_rgb(42, 42, 42),
-                text: Color32::from_rgb(220, 220, 220),
-                selected: Color32::from_rgb(44, 93, 135),
-                header: Color32::from_rgb(37, 37, 37),
-                grid: Color32::from_rgb(60, 60, 60),
-                component_header: Color32::from_rgb(65, 65, 65),
             },
+            theme: EditorTheme::new(settings.theme),
+            history: CommandHistory::default(),
+            clipboard: Clipboard::new(),
+            current_scene_path: None,
+            play_mode: PlayMode::new(),
+            game_input: Input::new(),
+            input_map: InputMap::new(),
+            assets: AssetManager::new(),
+            edit_session: None,
+            dirty: false,
+            pending_scene_action: None,
+            exit_confirmed: false,
+            saved_layout: layout.clone(),
+            layout,
+            layout_generation: 0,
+            saved_settings,
+            settings,
+            show_preferences: false,
+            show_scene_stats: false,
+            autosaver: Autosaver::new(),
+            recovery_prompt: RecoveryData::default_path().and_then(|path| RecoveryData::find_unsaved(&path)),
         }
     }
     
@@ -131,7 +298,10 @@ impl EditorUI {
     /// Set editor theme
     pub fn set_theme(&self, ctx: &Context) {
         let mut style = (*ctx.style()).clone();
-        style.visuals = Visuals::dark();
+        style.visuals = match self.settings.theme {
+            ThemeMode::Dark => Visuals::dark(),
+            ThemeMode::Light => Visuals::light(),
+        };
         
         // Customize Unity-like theme
         style.visuals.widgets.noninteractive.bg_fill = self.theme.panel_background;
@@ -171,94 +341,264 @@ impl EditorUI {
         self.set_theme(ctx);
         
         // Sinkronisasi entity names dari hierarchy ke scene view
-        self.scene_view_panel.set_entity_names(self.hierarchy_panel.entity_names.clone());
-        
-        // Sinkronisasi dua arah antara hierarchy dan scene_view
-        // Jika scene_view memiliki entity yang dipilih, update hierarchy_panel
-        if self.scene_view_panel.selected_entity.is_some() && 
-           self.scene_view_panel.selected_entity != self.hierarchy_panel.selected_entity {
-            self.hierarchy_panel.selected_entity = self.scene_view_panel.selected_entity;
-            ctx.request_repaint(); // Force repaint untuk mengupdate UI segera
-        }
-        // Jika hierarchy_panel memiliki entity yang dipilih, update scene_view
-        else if self.hierarchy_panel.selected_entity.is_some() && 
-                self.hierarchy_panel.selected_entity != self.scene_view_panel.selected_entity {
-            self.scene_view_panel.set_selected_entity(self.hierarchy_panel.selected_entity);
-            ctx.request_repaint(); // Force repaint untuk mengupdate UI segera
+        let entity_names = self.hierarchy_panel.entity_names(&self.ecs);
+        self.scene_view_panel.set_entity_names(entity_names.clone());
+        self.scene_view_panel.set_entity_types(self.hierarchy_panel.entity_types.clone());
+        let inactive = self.ecs.handles().into_iter()
+            .filter(|handle| !self.ecs.is_active_in_hierarchy(handle))
+            .collect();
+        self.scene_view_panel.set_inactive_entities(inactive);
+        if self.scene_view_panel.show_physics_debug {
+            let physics_debug = self.collect_physics_debug();
+            self.scene_view_panel.set_physics_debug(physics_debug);
         }
+        let camera_gizmos = self.collect_camera_gizmos(ctx);
+        self.scene_view_panel.set_camera_gizmos(camera_gizmos);
+        let light_gizmos = self.collect_light_gizmos();
+        self.scene_view_panel.set_light_gizmos(light_gizmos);
+        
+        // Settings edited last frame, in the Preferences window or the snap menu
+        self.scene_view_panel.apply_settings(&self.settings);
         
         // Update scene view tool dari toolbar
         self.scene_view_panel.scene_view_tool = self.toolbar.transform_tool.clone();
         
+        // The game only sees the keyboard and mouse while playing, and not while typing into the editor
+        self.game_input.begin_frame();
+        self.game_input.publish_gamepad_events(self.ecs.events_mut());
+        if self.play_mode.is_playing() && !ctx.wants_keyboard_input() {
+            feed_egui_input(&mut self.game_input, ctx);
+        } else {
+            self.game_input.release_all();
+        }
+        self.input_map.update(&self.game_input);
+        self.ecs.set_script_input(&self.game_input, &self.input_map);
+        
+        // Run the game's systems while playing
+        self.play_mode.update(&mut self.ecs, delta_time);
+        
+        // Primitives created in the editor or loaded with a scene get their meshes once there's a device,
+        // and material edits from the inspector are applied
+        if let Some(renderer) = &mut self.scene_view_panel.renderer {
+            primitive_mesh_system(self.ecs.world_mut(), renderer);
+            material_asset_system(self.ecs.world_mut(), renderer, &mut self.assets);
+        }
+        
+        // Pick up files changed outside the editor
+        self.project_panel.poll_changes();
+        
+        // Show what was logged through the `log` crate since the last frame
+        self.console_panel.collect_logs();
+        
         // Collect log messages first
         let mut messages = Vec::new();
         let mut log_info = |message: &str| {
             messages.push(message.to_string());
         };
         
+        // Editing shortcuts, unless a text field wants the keys
+        if !ctx.wants_keyboard_input() {
+            let (undo, redo, delete, duplicate, focus) = ctx.input_mut(|i| (
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Delete),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::D),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::F),
+            ));
+            // The platform integration may turn Ctrl+C/X/V into clipboard events instead of key presses
+            let (copy, cut, paste, paste_under_selection) = ctx.input_mut(|i| {
+                let copy = i.events.iter().any(|e| matches!(e, egui::Event::Copy))
+                    || i.consume_key(egui::Modifiers::COMMAND, egui::Key::C);
+                let cut = i.events.iter().any(|e| matches!(e, egui::Event::Cut))
+                    || i.consume_key(egui::Modifiers::COMMAND, egui::Key::X);
+                let paste = i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))
+                    || i.consume_key(egui::Modifiers::COMMAND, egui::Key::V)
+                    || i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::V);
+                (copy, cut, paste, i.modifiers.shift)
+            });
+            if undo {
+                self.undo(&mut log_info);
+            }
+            if redo {
+                self.redo(&mut log_info);
+            }
+            if delete {
+                self.delete_selected(&mut log_info);
+            }
+            if copy {
+                self.copy_selected(ctx, &mut log_info);
+            }
+            if cut {
+                self.cut_selected(ctx, &mut log_info);
+            }
+            if paste {
+                // Ctrl+Shift+V pastes under the selected entity
+                self.paste(paste_under_selection, &mut log_info);
+            }
+            if duplicate {
+                self.duplicate_selected(&mut log_info);
+            }
+            if focus {
+                self.scene_view_panel.focus_selection(&self.ecs);
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::A)) {
+                self.frame_all(&mut log_info);
+            }
+            
+            // Ctrl+Shift+G first, so Ctrl+G doesn't swallow it
+            let (ungroup, group) = ctx.input_mut(|i| (
+                i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::G),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::G),
+            ));
+            if ungroup {
+                self.ungroup_selected(&mut log_info);
+            } else if group {
+                self.group_selected(&mut log_info);
+            }
+            
+            // Escape deselects
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.hierarchy_panel.selection.clear();
+            }
+            
+            // Transform tool shortcuts, only while working in the scene view
+            if self.scene_view_panel.has_focus() {
+                let tool = ctx.input_mut(|i| {
+                    [
+                        (egui::Key::Q, SceneViewTool::Select),
+                        (egui::Key::W, SceneViewTool::Move),
+                        (egui::Key::E, SceneViewTool::Rotate),
+                        (egui::Key::R, SceneViewTool::Scale),
+                    ]
+                    .into_iter()
+                    .find(|(key, _)| i.consume_key(egui::Modifiers::NONE, *key))
+                    .map(|(_, tool)| tool)
+                });
+                if let Some(tool) = tool {
+                    self.scene_view_panel.scene_view_tool = tool.clone();
+                    self.toolbar.transform_tool = tool;
+                }
+            }
+            
+            // Ctrl+Shift+S first, so Ctrl+S doesn't swallow it
+            let (new_scene, open_scene, save_scene_as, save_scene) = ctx.input_mut(|i| (
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::N),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::O),
+                i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::S),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::S),
+            ));
+            if new_scene {
+                self.request_scene_action(SceneAction::NewScene, &mut log_info);
+            }
+            if open_scene {
+                self.request_scene_action(SceneAction::OpenScene, &mut log_info);
+            }
+            if save_scene_as {
+                self.save_scene_as(&mut log_info);
+            } else if save_scene {
+                self.save_scene(&mut log_info);
+            }
+        }
+        
+        self.autosave(delta_time);
+        
         // Draw toolbar at top
         self.render_toolbar(ctx, &mut log_info);
         
         // Left side with hierarchy panel
-        egui::SidePanel::left("hierarchy_panel_container")
-            .resizable(true)
-            .default_width(300.0)
-            .min_width(200.0)
-            .frame(egui::Frame::default().fill(self.theme.panel_background))
-            .show(ctx, |ui| {
-                self.hierarchy_panel.render(ui, &mut log_info);
-            });
+        if self.layout.show_hierarchy {
+            let hierarchy_rect = egui::SidePanel::left(self.panel_id("hierarchy_panel_container"))
+                .resizable(true)
+                .default_width(self.layout.hierarchy_width)
+                .min_width(200.0)
+                .frame(egui::Frame::default().fill(self.theme.panel_background))
+                .show(ctx, |ui| {
+                    self.hierarchy_panel.render(ui, &mut self.ecs, &self.inspector_panel.component_registry, &mut log_info);
+                }).response.rect;
+            self.layout.hierarchy_width = hierarchy_rect.width();
+        }
         
-        // Right side with inspector panel
-        egui::SidePanel::right("inspector_panel_container")
-            .resizable(true)
-            .default_width(300.0)
-            .min_width(200.0)
-            .frame(egui::Frame::default().fill(self.theme.panel_background))
-            .show(ctx, |ui| {
-                self.inspector_panel.render(ui, self.hierarchy_panel.selected_entity, 
-                                          &self.hierarchy_panel.entity_names, &mut log_info);
-                
-                // Jika perubahan dilakukan di inspector, request repaint
-                if self.inspector_panel.dirty {
-                    ctx.request_repaint();
-                    self.inspector_panel.dirty = false;
-                }
-            });
+        // The hierarchy and scene view share one selection, whichever panel changed it last wins
+        if self.hierarchy_panel.selection != self.scene_view_panel.selection {
+            self.scene_view_panel.set_selection(&self.hierarchy_panel.selection);
+            ctx.request_repaint();
+        }
+        if std::mem::take(&mut self.hierarchy_panel.focus_requested) {
+            self.scene_view_panel.focus_selection(&self.ecs);
+        }
         
-        // Bottom with project panel
-        egui::TopBottomPanel::bottom("project_console_panel")
-            .resizable(true)
-            .default_height(200.0)
-            .min_height(100.0)
-            .frame(egui::Frame::default().fill(self.theme.panel_background))
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    // Project/Console Tabs
-                    if ui.selectable_label(self.project_panel.project_active_tab == ProjectTab::Files, 
-                                          "Project").clicked() {
-                        self.project_panel.project_active_tab = ProjectTab::Files;
+        // Keep repainting until the focus animation finishes
+        if self.scene_view_panel.animate_camera(delta_time) {
+            ctx.request_repaint();
+        }
+        
+        // Right side with inspector panel
+        if self.layout.show_inspector {
+            let inspector_rect = egui::SidePanel::right(self.panel_id("inspector_panel_container"))
+                .resizable(true)
+                .default_width(self.layout.inspector_width)
+                .min_width(200.0)
+                .frame(egui::Frame::default().fill(self.theme.panel_background))
+                .show(ctx, |ui| {
+                    self.inspector_panel.render(ui, &mut self.ecs, &self.hierarchy_panel.selection, 
+                                              &entity_names, &mut log_info);
+                    
+                    // Jika perubahan dilakukan di inspector, request repaint
+                    if self.inspector_panel.dirty {
+                        // Edits made while playing are thrown away on Stop
+                        self.dirty |= !self.play_mode.is_playing();
+                        ctx.request_repaint();
+                        self.inspector_panel.dirty = false;
                     }
-                    if ui.selectable_label(self.project_panel.project_active_tab == ProjectTab::Console, 
-                                          "Console").clicked() {
-                        self.project_panel.project_active_tab = ProjectTab::Console;
+                }).response.rect;
+            self.layout.inspector_width = inspector_rect.width();
+        }
+        
+        // Bottom with project panel, showing whichever of its tabs are enabled
+        if self.layout.show_project || self.layout.show_console {
+            let active_tab_hidden = match self.project_panel.project_active_tab {
+                ProjectTab::Files => !self.layout.show_project,
+                ProjectTab::Console => !self.layout.show_console,
+                ProjectTab::Audio => false,
+            };
+            if active_tab_hidden {
+                self.project_panel.project_active_tab = if self.layout.show_project { ProjectTab::Files } else { ProjectTab::Console };
+            }
+            
+            let bottom_rect = egui::TopBottomPanel::bottom(self.panel_id("project_console_panel"))
+                .resizable(true)
+                .default_height(self.layout.bottom_height)
+                .min_height(100.0)
+                .frame(egui::Frame::default().fill(self.theme.panel_background))
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        // Project/Console Tabs
+                        if self.layout.show_project && ui.selectable_label(self.project_panel.project_active_tab == ProjectTab::Files, 
+                                              "Project").clicked() {
+                            self.project_panel.project_active_tab = ProjectTab::Files;
+                        }
+                        if self.layout.show_console && ui.selectable_label(self.project_panel.project_active_tab == ProjectTab::Console, 
+                                              "Console").clicked() {
+                            self.project_panel.project_active_tab = ProjectTab::Console;
+                        }
+                    });
+                    
+                    ui.separator();
+                    
+                    match self.project_panel.project_active_tab {
+                        ProjectTab::Files => {
+                            self.project_panel.render_project_files(ui, &mut log_info);
+                        },
+                        ProjectTab::Console => {
+                            self.console_panel.render_project_console(ui);
+                        },
+                        ProjectTab::Audio => {
+                            self.audio_panel.render_project_audio(ui, &mut log_info);
+                        },
                     }
-                });
-                
-                ui.separator();
-                
-                match self.project_panel.project_active_tab {
-                    ProjectTab::Files => {
-                        self.project_panel.render_project_files(ui, &mut log_info);
-                    },
-                    ProjectTab::Console => {
-                        self.console_panel.render_project_console(ui);
-                    },
-                    ProjectTab::Audio => {
-                        self.audio_panel.render_project_audio(ui, &mut log_info);
-                    },
-                }
-            });
+                }).response.rect;
+            self.layout.bottom_height = bottom_rect.height();
+        }
         
         // Central panel with Scene/Game view
         egui::CentralPanel::default()
@@ -276,21 +616,51 @@ impl EditorUI {
                     ui.separator();
                     
                     // Additional view options
-                    ui.checkbox(&mut self.show_grid, "Grid");
+                    ui.checkbox(&mut self.settings.show_grid, "Grid");
+                    ui.add(egui::DragValue::new(&mut self.settings.grid_size).speed(0.05).clamp_range(0.1..=100.0))
+                        .on_hover_text("World units between grid lines");
+                    ui.checkbox(&mut self.settings.snap_to_grid, "Snap to Grid")
+                        .on_hover_text("Move entities to the nearest grid cell and snap rotate and scale drags, without holding Ctrl");
+                    ui.checkbox(&mut self.scene_view_panel.show_physics_debug, "Physics")
+                        .on_hover_text("Draw colliders and velocities");
+                    ui.menu_button("Snap Settings", |ui| {
+                        let settings = &mut self.settings;
+                        ui.horizontal(|ui| {
+                            ui.label("Grid");
+                            ui.add(egui::DragValue::new(&mut settings.grid_snap).speed(0.05).clamp_range(0.01..=100.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Angle");
+                            ui.add(egui::DragValue::new(&mut settings.angle_snap).speed(1.0).suffix("°").clamp_range(1.0..=180.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Scale");
+                            ui.add(egui::DragValue::new(&mut settings.scale_snap).speed(0.01).clamp_range(0.01..=10.0));
+                        });
+                    });
                 });
                 
                 ui.separator();
                 
                 match self.active_view {
                     ActiveView::Scene => {
-                        // Pastikan pengaturan grid diterapkan ke scene view
-                        self.scene_view_panel.show_grid = self.show_grid;
-                        
                         // Render scene view
-                        self.scene_view_panel.render(ui, &mut log_info);
+                        self.scene_view_panel.render(ui, &mut self.ecs, &mut log_info);
+                        
+                        if let Some((handle, range)) = self.scene_view_panel.light_range_edit.take() {
+                            if let Some(light) = self.ecs.get_component_mut::<LightComponent>(&handle) {
+                                light.range = range;
+                            }
+                        }
+                        
+                        if self.scene_view_panel.selection != self.hierarchy_panel.selection {
+                            self.hierarchy_panel.selection = self.scene_view_panel.selection.clone();
+                            ctx.request_repaint();
+                        }
                         
                         // Jika perubahan dilakukan di scene view, force refresh inspector
                         if self.scene_view_panel.dirty {
+                            self.dirty |= !self.play_mode.is_playing();
                             // Reset dirty flag
                             self.scene_view_panel.dirty = false;
                             
@@ -299,15 +669,1065 @@ impl EditorUI {
                         }
                     },
                     ActiveView::Game => {
-                        self.game_view_panel.render(ui, &mut log_info);
+                        self.game_view_panel.render(ui, &self.ecs, self.scene_view_panel.renderer.as_mut(), &mut log_info);
                     },
                 }
             });
         
+        // Execute everything the panels queued this frame
+        self.execute_pending_commands();
+        
+        self.handle_asset_drag(ctx, &mut log_info);
+        if let Some(handle) = self.hierarchy_panel.prefab_to_save.take() {
+            self.save_prefab(handle, &mut log_info);
+        }
+        
+        // Scripts opened from the Files tab or the inspector
+        let opened_script = self.project_panel.opened_script.take()
+            .or_else(|| ScriptEditorPanel::take_open_request(ctx));
+        if let Some(path) = opened_script {
+            self.script_editor.open_script(path, &mut log_info);
+        }
+        self.script_editor.render(ctx, &mut log_info);
+        
         // Add log messages
         for message in messages {
             self.console_panel.log_info(&message);
         }
+        
+        for error in self.ecs.take_script_errors() {
+            self.console_panel.log_error(&error);
+        }
+        
+        for error in self.project_panel.take_errors() {
+            self.console_panel.log_error(&error);
+        }
+        
+        for error in self.script_editor.take_errors() {
+            self.console_panel.log_error(&error);
+        }
+        
+        // Rename dialog opened from the Files tab
+        let mut rename_log = Vec::new();
+        self.project_panel.render_rename_dialog(ctx, &mut |message: &str| rename_log.push(message.to_string()));
+        for message in rename_log {
+            self.console_panel.log_info(&message);
+        }
+        
+        // Floating console window, opened from the Console tab
+        self.console_panel.render(ctx);
+        
+        // Commands typed into the console window or tab
+        if let Some(command) = self.console_panel.take_command() {
+            self.run_console_command(&command);
+        }
+        
+        self.render_preferences(ctx);
+        self.render_scene_stats(ctx);
+        
+        let mut prompt_log = Vec::new();
+        self.render_unsaved_changes_prompt(ctx, &mut |message: &str| prompt_log.push(message.to_string()));
+        self.render_recovery_prompt(ctx, &mut |message: &str| prompt_log.push(message.to_string()));
+        for message in prompt_log {
+            self.console_panel.log_info(&message);
+        }
+        
+        self.layout.show_game_view = self.active_view == ActiveView::Game;
+        // Wait for panel resizes to finish rather than writing the file every frame of a drag
+        if self.layout != self.saved_layout && !ctx.input(|i| i.pointer.any_down()) {
+            self.save_layout();
+        }
+        // Likewise while dragging a value in the Preferences window
+        if self.settings != self.saved_settings && !ctx.input(|i| i.pointer.any_down()) {
+            self.save_settings();
+        }
+    }
+    
+    /// Save the settings so the next editor session starts with them
+    pub fn save_settings(&mut self) {
+        if let Err(e) = self.settings.save() {
+            self.console_panel.log_error(&format!("Failed to save the editor settings: {}", e));
+        }
+        // Don't retry a failed save every frame
+        self.saved_settings = self.settings.clone();
+    }
+    
+    /// Write the scene to the recovery file every `autosave_interval` seconds while it has unsaved changes
+    ///
+    /// The scene is captured here and written on a background thread. Autosaving
+    /// leaves the scene file, `current_scene_path` and the unsaved changes alone,
+    /// and doesn't run while playing or before the user answered the recovery prompt.
+    fn autosave(&mut self, delta_time: f32) {
+        if let Some(Err(e)) = self.autosaver.poll() {
+            self.console_panel.log_error(&format!("Autosave failed: {}", e));
+        }
+        
+        // Nothing counts towards an autosave while playing or before the recovery prompt is answered
+        let has_changes = self.has_unsaved_changes()
+            && !self.play_mode.is_playing()
+            && self.recovery_prompt.is_none();
+        if !self.autosaver.is_due(delta_time, self.settings.autosave_interval, has_changes) {
+            return;
+        }
+        let Some(path) = RecoveryData::default_path() else {
+            return;
+        };
+        let data = RecoveryData {
+            scene_path: self.current_scene_path.clone(),
+            scene: SceneData::from_world(&self.scene_name(), &self.ecs),
+        };
+        // Stays due, so this is tried again next frame if the last autosave is still being written
+        self.autosaver.start(data, path);
+    }
+    
+    /// Remove the recovery file, once its changes are saved or thrown away
+    fn discard_recovery(&mut self) {
+        if let Some(path) = RecoveryData::default_path() {
+            self.autosaver.discard(&path);
+        }
+    }
+    
+    /// Modal asking whether to recover the autosave of a session that ended with unsaved changes
+    fn render_recovery_prompt(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        let Some(recovery) = &self.recovery_prompt else {
+            return;
+        };
+        
+        block_editor(ctx, "recovery_blocker");
+        
+        let scene_name = recovery.scene_path.as_ref()
+            .map_or_else(|| recovery.scene.name.clone(), |path| path.display().to_string());
+        let mut answer = None;
+        let dialog = egui::Area::new("recovery_prompt")
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Frame::window(ui.style()).show(ui, |ui| {
+                    ui.heading("Recover Unsaved Changes");
+                    ui.label(format!("The last session ended with unsaved changes to {}.", scene_name));
+                    ui.label("Recover them from the autosave?");
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Recover").clicked() {
+                            answer = Some(true);
+                        }
+                        if ui.button("Discard").clicked() {
+                            answer = Some(false);
+                        }
+                    });
+                });
+            });
+        ctx.move_to_top(dialog.response.layer_id);
+        
+        let Some(recover) = answer else {
+            return;
+        };
+        let Some(recovery) = self.recovery_prompt.take() else {
+            return;
+        };
+        if recover {
+            self.recover_scene(recovery, log_info);
+        } else {
+            self.discard_recovery();
+            log_info("Discarded the autosaved changes");
+        }
+    }
+    
+    /// Replace the edited world with an autosaved scene
+    ///
+    /// The recovery file is kept until the scene is saved, in case the editor
+    /// closes again first.
+    fn recover_scene(&mut self, recovery: RecoveryData, log_info: &mut dyn FnMut(&str)) {
+        if self.play_mode.is_playing() {
+            self.stop_playing(log_info);
+        }
+        
+        recovery.scene.apply_to_world(&mut self.ecs);
+        self.reset_scene_state();
+        self.current_scene_path = recovery.scene_path;
+        // The recovered changes aren't in the scene file yet
+        self.dirty = true;
+        log_info(&format!("Recovered unsaved changes to {}", self.scene_name()));
+    }
+    
+    /// Window with entity and component counts and the renderer's load, opened from the Window menu
+    fn render_scene_stats(&mut self, ctx: &Context) {
+        if !self.show_scene_stats {
+            return;
+        }
+        
+        let mut open = self.show_scene_stats;
+        let mut component_counts: Vec<(&'static str, usize)> = self.ecs.component_counts().into_iter()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        // Most common first, then by name so equal counts don't shuffle between frames
+        component_counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let stats = self.scene_view_panel.renderer.as_ref().map(|renderer| renderer.average_stats());
+        
+        Window::new("Scene Stats")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(220.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("scene_stats_grid").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                    ui.label(RichText::new("Entities").strong());
+                    ui.label(self.ecs.entity_count().to_string());
+                    ui.end_row();
+                });
+                
+                egui::CollapsingHeader::new("Components").default_open(true).show(ui, |ui| {
+                    if component_counts.is_empty() {
+                        ui.label(RichText::new("None").weak());
+                    }
+                    egui::Grid::new("scene_stats_components").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                        for (name, count) in &component_counts {
+                            ui.label(*name);
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+                
+                egui::CollapsingHeader::new("Rendering").default_open(true).show(ui, |ui| {
+                    let Some(stats) = stats else {
+                        ui.label(RichText::new("No renderer").weak());
+                        return;
+                    };
+                    // Averaged like the scene view's frame stats, so the numbers are readable
+                    egui::Grid::new("scene_stats_rendering").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                        for (label, value) in [
+                            ("Draw Calls", stats.draw_calls),
+                            ("Triangles", stats.triangles),
+                            ("Instances", stats.instances),
+                            ("Sprites", stats.sprites),
+                            ("Culled", stats.culled),
+                        ] {
+                            ui.label(label);
+                            ui.label(value.to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        self.show_scene_stats = open;
+    }
+    
+    /// Window for editing the editor settings, opened from the Edit menu
+    fn render_preferences(&mut self, ctx: &Context) {
+        let mut open = self.show_preferences;
+        let mut theme_changed = false;
+        Window::new("Preferences")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let settings = &mut self.settings;
+                egui::Grid::new("preferences_grid").num_columns(2).spacing([20.0, 6.0]).show(ui, |ui| {
+                    ui.label("Theme");
+                    egui::ComboBox::from_id_source("preferences_theme")
+                        .selected_text(format!("{:?}", settings.theme))
+                        .show_ui(ui, |ui| {
+                            for mode in [ThemeMode::Dark, ThemeMode::Light] {
+                                theme_changed |= ui.selectable_value(&mut settings.theme, mode, format!("{:?}", mode)).changed();
+                            }
+                        });
+                    ui.end_row();
+                    
+                    ui.label("Grid Size");
+                    ui.add(egui::DragValue::new(&mut settings.grid_size).speed(0.05).clamp_range(0.1..=100.0));
+                    ui.end_row();
+                    
+                    ui.label("Position Snap");
+                    ui.add(egui::DragValue::new(&mut settings.grid_snap).speed(0.05).clamp_range(0.01..=100.0));
+                    ui.end_row();
+                    
+                    ui.label("Rotation Snap");
+                    ui.add(egui::DragValue::new(&mut settings.angle_snap).speed(1.0).suffix("°").clamp_range(1.0..=180.0));
+                    ui.end_row();
+                    
+                    ui.label("Scale Snap");
+                    ui.add(egui::DragValue::new(&mut settings.scale_snap).speed(0.01).clamp_range(0.01..=10.0));
+                    ui.end_row();
+                    
+                    ui.label("Zoom Range");
+                    ui.horizontal(|ui| {
+                        let max_zoom = settings.max_zoom;
+                        ui.add(egui::DragValue::new(&mut settings.min_zoom).speed(0.01).clamp_range(0.01..=max_zoom));
+                        ui.label("to");
+                        let min_zoom = settings.min_zoom;
+                        ui.add(egui::DragValue::new(&mut settings.max_zoom).speed(0.1).clamp_range(min_zoom..=100.0));
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Autosave");
+                    ui.add(egui::DragValue::new(&mut settings.autosave_interval).speed(1.0).suffix(" s").clamp_range(0.0..=3600.0))
+                        .on_hover_text("Seconds between autosaves of unsaved changes for recovery, 0 turns autosave off");
+                    ui.end_row();
+                });
+                
+                ui.separator();
+                
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Recent Projects").strong());
+                    if ui.add_enabled(!settings.recent_projects.is_empty(), egui::Button::new("Clear")).clicked() {
+                        settings.recent_projects.clear();
+                    }
+                });
+                if settings.recent_projects.is_empty() {
+                    ui.label(RichText::new("None").weak());
+                }
+                for project in &settings.recent_projects {
+                    ui.label(project.display().to_string());
+                }
+                
+                ui.separator();
+                
+                if ui.button("Restore Defaults").clicked() {
+                    let recent_projects = std::mem::take(&mut settings.recent_projects);
+                    let recent_scenes = std::mem::take(&mut settings.recent_scenes);
+                    *settings = EditorSettings { recent_projects, recent_scenes, ..EditorSettings::default() };
+                    theme_changed = true;
+                }
+            });
+        self.show_preferences = open;
+        
+        if theme_changed {
+            self.theme = EditorTheme::new(self.settings.theme);
+        }
+    }
+    
+    /// Show every panel at its default size again
+    pub fn reset_layout(&mut self) {
+        self.layout = EditorLayout::default();
+        self.active_view = ActiveView::Scene;
+        // Panels with new ids start out at their default sizes
+        self.layout_generation += 1;
+    }
+    
+    /// Save the layout so the next editor session starts with it
+    pub fn save_layout(&mut self) {
+        if let Err(e) = self.layout.save() {
+            self.console_panel.log_error(&format!("Failed to save the editor layout: {}", e));
+        }
+        // Don't retry a failed save every frame
+        self.saved_layout = self.layout.clone();
+    }
+    
+    /// Id of a panel that changes on `reset_layout`
+    fn panel_id(&self, name: &str) -> egui::Id {
+        egui::Id::new((name, self.layout_generation))
+    }
+    
+    /// Undo the most recent editor operation
+    pub fn undo(&mut self, log_info: &mut dyn FnMut(&str)) -> bool {
+        let description = self.history.undo_description();
+        let undone = self.with_history(|history, state| history.undo(state));
+        if let (true, Some(description)) = (undone, description) {
+            log_info(&format!("Undo {}", description));
+        }
+        undone
+    }
+    
+    /// Redo the most recently undone editor operation
+    pub fn redo(&mut self, log_info: &mut dyn FnMut(&str)) -> bool {
+        let description = self.history.redo_description();
+        let redone = self.with_history(|history, state| history.redo(state));
+        if let (true, Some(description)) = (redone, description) {
+            log_info(&format!("Redo {}", description));
+        }
+        redone
+    }
+    
+    /// Delete the selected entities and their children
+    pub fn delete_selected(&mut self, log_info: &mut dyn FnMut(&str)) {
+        let handles: Vec<_> = self.hierarchy_panel.selection.iter().copied().collect();
+        match handles.as_slice() {
+            [] => {}
+            [handle] => {
+                let name = self.ecs.get_name(handle).unwrap_or_default();
+                self.with_history(|history, state| history.execute(Box::new(DeleteEntityCommand::new(*handle)), state));
+                log_info(&format!("Deleted entity: {}", name));
+            }
+            _ => {
+                let count = handles.len();
+                self.with_history(|history, state| history.execute(Box::new(DeleteEntityCommand::new_many(handles)), state));
+                log_info(&format!("Deleted {} entities", count));
+            }
+        }
+    }
+    
+    /// Put the selected entities under a new empty entity at their center
+    pub fn group_selected(&mut self, log_info: &mut dyn FnMut(&str)) {
+        let handles: Vec<_> = self.hierarchy_panel.selection.iter().copied().collect();
+        if let Some(command) = GroupEntitiesCommand::new(&self.ecs, &handles) {
+            let count = command.members.len();
+            self.with_history(|history, state| history.execute(Box::new(command), state));
+            log_info(&format!("Grouped {} entities", count));
+        }
+    }
+    
+    /// Move the children of the selected entities up to their parents and delete the selected entities
+    pub fn ungroup_selected(&mut self, log_info: &mut dyn FnMut(&str)) {
+        let handles: Vec<_> = self.hierarchy_panel.selection.iter().copied().collect();
+        if let Some(command) = UngroupEntitiesCommand::new(&self.ecs, &handles) {
+            let count = command.groups.len();
+            self.with_history(|history, state| history.execute(Box::new(command), state));
+            log_info(&format!("Ungrouped {} entities", count));
+        }
+    }
+    
+    /// Copy the selected entities and their children to the clipboard
+    pub fn copy_selected(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        let count = self.clipboard.copy(&self.ecs, &self.hierarchy_panel.entity_types, &self.hierarchy_panel.selection);
+        if count == 0 {
+            return;
+        }
+        
+        // Also put the snapshots on the system clipboard, so Ctrl+V produces a paste event
+        if let Some(ron) = self.clipboard.to_ron() {
+            ctx.output_mut(|o| o.copied_text = ron);
+        }
+        log_info(&format!("Copied {} entities", count));
+    }
+    
+    /// Copy the selected entities to the clipboard, then delete them
+    pub fn cut_selected(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        self.copy_selected(ctx, log_info);
+        if !self.clipboard.is_empty() {
+            self.delete_selected(log_info);
+        }
+    }
+    
+    /// Paste copies of the clipboard with fresh handles, does nothing when it's empty
+    ///
+    /// Copies keep the parents of the originals, or go under the selected entity with `under_selection`.
+    pub fn paste(&mut self, under_selection: bool, log_info: &mut dyn FnMut(&str)) {
+        let parent = if under_selection { self.hierarchy_panel.selection.primary() } else { None };
+        if let Some(command) = self.clipboard.paste_command(&self.ecs, parent) {
+            let count = command.roots().len();
+            self.with_history(|history, state| history.execute(Box::new(command), state));
+            log_info(&format!("Pasted {} entities", count));
+        }
+    }
+    
+    /// Duplicate the selected entities without touching the clipboard
+    pub fn duplicate_selected(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if let Some(command) = duplicate_command(&self.ecs, &self.hierarchy_panel.entity_types, &self.hierarchy_panel.selection) {
+            let count = command.roots().len();
+            self.with_history(|history, state| history.execute(Box::new(command), state));
+            log_info(&format!("Duplicated {} entities", count));
+        }
+    }
+    
+    /// Move the scene view camera to show the whole scene, does nothing for an empty scene
+    pub fn frame_all(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if self.scene_view_panel.frame_all(&self.ecs) {
+            log_info("Framed the whole scene");
+        }
+    }
+    
+    /// Name of the open scene, taken from its file name
+    pub fn scene_name(&self) -> String {
+        self.current_scene_path.as_ref()
+            .and_then(|path| path.file_stem())
+            .map_or_else(|| "Untitled".to_string(), |stem| stem.to_string_lossy().into_owned())
+    }
+    
+    /// Window title with the scene name, marked with `*` if it has unsaved changes
+    pub fn window_title(&self) -> String {
+        let modified = if self.has_unsaved_changes() { "*" } else { "" };
+        format!("{} - {}{}", WINDOW_TITLE, self.scene_name(), modified)
+    }
+    
+    /// Whether the scene changed since it was opened or last saved
+    ///
+    /// Undoing back to the saved state only counts as unchanged if nothing outside
+    /// the undo history, see `dirty`, changed too.
+    pub fn has_unsaved_changes(&self) -> bool {
+        // While playing, the edits are in the history put aside at Play
+        let history = self.edit_session.as_ref().map_or(&self.history, |session| &session.history);
+        self.dirty || history.is_modified()
+    }
+    
+    /// Do an action that throws away the scene, asking first if it has unsaved changes
+    pub fn request_scene_action(&mut self, action: SceneAction, log_info: &mut dyn FnMut(&str)) {
+        if self.has_unsaved_changes() {
+            self.pending_scene_action = Some(action);
+        } else {
+            self.run_scene_action(action, log_info);
+        }
+    }
+    
+    /// Called when the window is asked to close, returns whether it may
+    ///
+    /// With unsaved changes the user is asked first; once they agree,
+    /// `exit_confirmed` tells the application to close the window.
+    pub fn request_exit(&mut self) -> bool {
+        if self.exit_confirmed || !self.has_unsaved_changes() {
+            return true;
+        }
+        self.pending_scene_action = Some(SceneAction::Exit);
+        false
+    }
+    
+    /// Whether the user chose to exit after being asked about unsaved changes
+    pub fn exit_confirmed(&self) -> bool {
+        self.exit_confirmed
+    }
+    
+    fn run_scene_action(&mut self, action: SceneAction, log_info: &mut dyn FnMut(&str)) {
+        match action {
+            SceneAction::NewScene => self.new_scene(log_info),
+            SceneAction::OpenScene => self.open_scene_dialog(log_info),
+            SceneAction::OpenSceneFile(path) => self.open_scene(&path, log_info),
+            SceneAction::Exit => {
+                // The user saved or chose to throw the changes away
+                self.discard_recovery();
+                self.exit_confirmed = true;
+            }
+        }
+    }
+    
+    /// Modal asking whether to save before the pending scene action
+    fn render_unsaved_changes_prompt(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        let Some(action) = self.pending_scene_action.clone() else {
+            return;
+        };
+        
+        block_editor(ctx, "unsaved_changes_blocker");
+        
+        let scene_name = self.scene_name();
+        let mut answer = None;
+        let dialog = egui::Area::new("unsaved_changes_prompt")
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Frame::window(ui.style()).show(ui, |ui| {
+                    ui.heading("Unsaved Changes");
+                    ui.label(format!("Save changes to {} before continuing?", scene_name));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            answer = Some(true);
+                        }
+                        if ui.button("Don't Save").clicked() {
+                            answer = Some(false);
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                            self.pending_scene_action = None;
+                        }
+                    });
+                });
+            });
+        ctx.move_to_top(dialog.response.layer_id);
+        
+        let Some(save) = answer else {
+            return;
+        };
+        self.pending_scene_action = None;
+        if save {
+            self.save_scene(log_info);
+            // The save dialog was cancelled or saving failed
+            if self.has_unsaved_changes() {
+                return;
+            }
+        }
+        self.run_scene_action(action, log_info);
+    }
+    
+    /// Replace the edited world with an empty, untitled scene
+    pub fn new_scene(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if self.play_mode.is_playing() {
+            self.stop_playing(log_info);
+        }
+        
+        self.ecs.clear();
+        self.history.clear();
+        self.hierarchy_panel.selection.clear();
+        self.hierarchy_panel.entity_types.clear();
+        self.current_scene_path = None;
+        self.dirty = false;
+        self.discard_recovery();
+        log_info("Created new scene");
+    }
+    
+    /// Pick a scene file and open it, does nothing if the dialog is cancelled
+    pub fn open_scene_dialog(&mut self, log_info: &mut dyn FnMut(&str)) {
+        let Some(path) = self.scene_file_dialog().pick_file() else {
+            return;
+        };
+        self.open_scene(&path, log_info);
+    }
+    
+    /// Replace the edited world with the scene in `path`
+    pub fn open_scene(&mut self, path: &Path, log_info: &mut dyn FnMut(&str)) {
+        if self.play_mode.is_playing() {
+            self.stop_playing(log_info);
+        }
+        
+        if let Err(e) = load_world(&mut self.ecs, path) {
+            self.console_panel.log_error(&format!("Failed to open scene {}: {}", path.display(), e));
+            return;
+        }
+        
+        self.reset_scene_state();
+        self.current_scene_path = Some(path.to_path_buf());
+        self.dirty = false;
+        self.discard_recovery();
+        self.settings.add_recent_scene(path);
+        log_info(&format!("Opened scene: {}", path.display()));
+    }
+    
+    /// Forget editor state of the previous scene after the world was replaced
+    fn reset_scene_state(&mut self) {
+        // It refers to entities that are gone
+        self.history.clear();
+        self.hierarchy_panel.selection.clear();
+        self.hierarchy_panel.entity_types.clear();
+        for handle in self.ecs.handles() {
+            self.hierarchy_panel.entity_types.insert(handle, EntityType::GameObject);
+        }
+        for (handle, _) in self.ecs.query::<&CameraComponent>().iter() {
+            self.hierarchy_panel.entity_types.insert(handle, EntityType::Camera);
+        }
+        for (handle, light) in self.ecs.query::<&LightComponent>().iter() {
+            self.hierarchy_panel.entity_types.insert(handle, EntityType::Light(light.kind));
+        }
+        for (handle, _) in self.ecs.query::<&AudioSourceComponent>().iter() {
+            self.hierarchy_panel.entity_types.insert(handle, EntityType::AudioSource);
+        }
+    }
+    
+    /// Save the scene to its current file, asking for one if it has none
+    pub fn save_scene(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if !self.can_save_scene() {
+            return;
+        }
+        
+        match self.current_scene_path.clone() {
+            Some(path) => self.save_scene_to(&path, log_info),
+            None => self.save_scene_as(log_info),
+        }
+    }
+    
+    /// Pick a file and save the scene to it, does nothing if the dialog is cancelled
+    pub fn save_scene_as(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if !self.can_save_scene() {
+            return;
+        }
+        
+        let file_name = format!("{}.{}", self.scene_name(), SCENE_EXTENSION);
+        let Some(mut path) = self.scene_file_dialog().set_file_name(&file_name).save_file() else {
+            return;
+        };
+        if path.extension().is_none() {
+            path.set_extension(SCENE_EXTENSION);
+        }
+        self.save_scene_to(&path, log_info);
+    }
+    
+    /// Whether the scene can be saved, warns if it can't
+    fn can_save_scene(&mut self) -> bool {
+        // The world holds the play session's changes, which are thrown away on Stop
+        if self.play_mode.is_playing() {
+            self.console_panel.log_warning("Stop play mode before saving the scene");
+            return false;
+        }
+        true
+    }
+    
+    /// Save the scene to `path` and make it the current scene file
+    fn save_scene_to(&mut self, path: &Path, log_info: &mut dyn FnMut(&str)) {
+        self.current_scene_path = Some(path.to_path_buf());
+        
+        match save_world(&self.scene_name(), &self.ecs, path) {
+            Ok(()) => {
+                self.history.mark_saved();
+                self.dirty = false;
+                self.discard_recovery();
+                self.settings.add_recent_scene(path);
+                log_info(&format!("Saved scene: {}", path.display()));
+            }
+            Err(e) => self.console_panel.log_error(&format!("Failed to save scene {}: {}", path.display(), e)),
+        }
+    }
+    
+    /// File dialog for scene files, starting in the current scene's folder or the project
+    fn scene_file_dialog(&self) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new().add_filter("Scene", &[SCENE_EXTENSION]);
+        let directory = self.current_scene_path.as_ref()
+            .and_then(|path| path.parent())
+            .or_else(|| self.project_panel.project_root());
+        match directory {
+            Some(directory) => dialog.set_directory(directory),
+            None => dialog,
+        }
+    }
+    
+    /// Make `root` the project shown in the project panel
+    pub fn open_project(&mut self, root: PathBuf, log_info: &mut dyn FnMut(&str)) {
+        match self.project_panel.open_project(root.clone()) {
+            Ok(()) => {
+                self.settings.add_recent_project(&root);
+                log_info(&format!("Opened project: {}", root.display()));
+            }
+            Err(e) => self.console_panel.log_error(&format!("Failed to open project {}: {}", root.display(), e)),
+        }
+    }
+    
+    /// Entries of File > Open Recent, scenes first and then projects
+    ///
+    /// Files that no longer exist are greyed out with a button to forget them.
+    fn render_open_recent_menu(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
+        enum RecentAction {
+            OpenScene(PathBuf),
+            OpenProject(PathBuf),
+            RemoveScene(PathBuf),
+            RemoveProject(PathBuf),
+        }
+        
+        let mut action = None;
+        let sections = [
+            ("Scenes", &self.settings.recent_scenes, true),
+            ("Projects", &self.settings.recent_projects, false),
+        ];
+        for (title, paths, is_scene) in sections {
+            ui.label(RichText::new(title).weak());
+            if paths.is_empty() {
+                ui.add_enabled(false, egui::Button::new("None"));
+            }
+            for path in paths {
+                let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                let exists = path.exists();
+                ui.horizontal(|ui| {
+                    let entry = ui.add_enabled(exists, egui::Button::new(name))
+                        .on_hover_text(path.display().to_string())
+                        .on_disabled_hover_text(format!("{} no longer exists", path.display()));
+                    if entry.clicked() {
+                        action = Some(if is_scene { RecentAction::OpenScene(path.clone()) } else { RecentAction::OpenProject(path.clone()) });
+                    }
+                    if !exists && ui.small_button("Remove").clicked() {
+                        action = Some(if is_scene { RecentAction::RemoveScene(path.clone()) } else { RecentAction::RemoveProject(path.clone()) });
+                    }
+                });
+            }
+            ui.separator();
+        }
+        
+        let has_recent = !self.settings.recent_scenes.is_empty() || !self.settings.recent_projects.is_empty();
+        if ui.add_enabled(has_recent, egui::Button::new("Clear Recent")).clicked() {
+            ui.close_menu();
+            self.settings.clear_recent();
+        }
+        
+        match action {
+            Some(RecentAction::OpenScene(path)) => {
+                ui.close_menu();
+                self.request_scene_action(SceneAction::OpenSceneFile(path), log_info);
+            }
+            Some(RecentAction::OpenProject(path)) => {
+                ui.close_menu();
+                self.open_project(path, log_info);
+            }
+            // The menu stays open to remove more
+            Some(RecentAction::RemoveScene(path)) => self.settings.recent_scenes.retain(|scene| *scene != path),
+            Some(RecentAction::RemoveProject(path)) => self.settings.recent_projects.retain(|project| *project != path),
+            None => {}
+        }
+    }
+    
+    /// Snapshot the world and start running its systems
+    pub fn start_playing(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if let Err(e) = self.play_mode.play(&self.ecs) {
+            self.console_panel.log_error(&format!("Failed to start play mode: {}", e));
+            return;
+        }
+        
+        self.edit_session = Some(EditSession {
+            history: std::mem::take(&mut self.history),
+            entity_types: self.hierarchy_panel.entity_types.clone(),
+            selection: self.hierarchy_panel.selection.clone(),
+        });
+        
+        self.active_view = ActiveView::Game;
+        self.game_view_panel.play_mode = true;
+        log_info("Starting play mode");
+    }
+    
+    /// Stop playing and restore the world and editor state from before Play
+    pub fn stop_playing(&mut self, log_info: &mut dyn FnMut(&str)) {
+        if let Err(e) = self.play_mode.stop(&mut self.ecs) {
+            self.console_panel.log_error(&format!("Failed to restore the scene after play mode: {}", e));
+        }
+        
+        if let Some(session) = self.edit_session.take() {
+            self.history = session.history;
+            self.hierarchy_panel.entity_types = session.entity_types;
+            self.hierarchy_panel.selection = session.selection;
+        }
+        self.game_view_panel.play_mode = false;
+        log_info("Stopping play mode");
+    }
+    
+    /// Load a prefab file and instantiate it as an undoable operation
+    pub fn instantiate_prefab(&mut self, path: &Path, position: Option<Vec3>, log_info: &mut dyn FnMut(&str)) {
+        match Prefab::load(path) {
+            Ok(prefab) => {
+                let command = SpawnEntitiesCommand::instantiate(&prefab, position);
+                self.with_history(|history, state| history.execute(Box::new(command), state));
+                log_info(&format!("Instantiated prefab: {}", prefab.name));
+            }
+            Err(e) => self.console_panel.log_error(&format!("Failed to load prefab {}: {}", path.display(), e)),
+        }
+    }
+    
+    /// Save an entity and its children as a prefab in the project's `PREFAB_DIRECTORY`
+    pub fn save_prefab(&mut self, handle: EntityHandle, log_info: &mut dyn FnMut(&str)) {
+        let Some(prefab) = Prefab::from_entity(&self.ecs, handle) else {
+            return;
+        };
+        
+        let folder = self.project_panel.full_path(&format!("/{}", PREFAB_DIRECTORY))
+            .unwrap_or_else(|| PREFAB_DIRECTORY.into());
+        let path = folder.join(format!("{}.{}", prefab.name, PREFAB_EXTENSION));
+        let result = fs::create_dir_all(&folder)
+            .map_err(SceneError::from)
+            .and_then(|_| prefab.save(&path));
+        
+        match result {
+            Ok(()) => {
+                // Show the new file without waiting for the watcher
+                self.project_panel.refresh();
+                log_info(&format!("Saved prefab: {}", path.display()));
+            }
+            Err(e) => self.console_panel.log_error(&format!("Failed to save prefab {}: {}", path.display(), e)),
+        }
+    }
+    
+    /// Save the scene view's last frame as a PNG in the project's `SCREENSHOT_DIRECTORY`
+    pub fn capture_screenshot(&mut self, log_info: &mut dyn FnMut(&str)) {
+        let Some(renderer) = self.scene_view_panel.renderer.as_mut() else {
+            self.console_panel.log_error("Can't capture a screenshot without a renderer");
+            return;
+        };
+        
+        let folder = self.project_panel.full_path(&format!("/{}", SCREENSHOT_DIRECTORY))
+            .unwrap_or_else(|| SCREENSHOT_DIRECTORY.into());
+        let file_name = format!("screenshot_{}.png", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let path = folder.join(file_name);
+        
+        let result = fs::create_dir_all(&folder)
+            .map_err(|e| e.to_string())
+            .and_then(|_| renderer.save_screenshot(&path).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => {
+                self.project_panel.refresh();
+                log_info(&format!("Saved screenshot: {}", path.display()));
+            }
+            Err(e) => self.console_panel.log_error(&format!("Failed to save screenshot {}: {}", path.display(), e)),
+        }
+    }
+    
+    /// Run a command typed into the console and print its result there
+    pub fn run_console_command(&mut self, line: &str) {
+        self.console_panel.log_info(&format!("> {}", line));
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return;
+        };
+        
+        let result = match self.console_commands.get(name).map(|command| command.run) {
+            Some(run) => run(self, args),
+            None => Err(self.console_commands.unknown_command(name)),
+        };
+        match result {
+            Ok(message) if message.is_empty() => {}
+            Ok(message) => self.console_panel.log_info(&message),
+            Err(error) => self.console_panel.log_error(&error),
+        }
+    }
+    
+    /// Follow an asset dragged from the project panel and apply it where it's dropped
+    ///
+    /// Inspector fields take their drops while rendering, the hierarchy and the scene
+    /// view leave theirs to `drop_asset`.
+    fn handle_asset_drag(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        let Some(dragged) = DraggedAsset::current(ctx) else {
+            return;
+        };
+        
+        egui::show_tooltip_at_pointer(ctx, egui::Id::new("asset_drag"), |ui| {
+            ui.label(dragged.name());
+        });
+        
+        if !ctx.input(|i| i.pointer.any_released()) {
+            return;
+        }
+        
+        DraggedAsset::clear(ctx);
+        let dropped = self.hierarchy_panel.asset_drop.take()
+            .or_else(|| self.scene_view_panel.asset_drop.take());
+        if let Some(dropped) = dropped {
+            self.drop_asset(dropped, log_info);
+        }
+    }
+    
+    /// Apply a project asset dropped on the hierarchy or the scene view
+    ///
+    /// Prefabs are instantiated, textures assigned to the target's sprite, materials
+    /// to its sprite or renderable, and scripts and audio clips added to it.
+    fn drop_asset(&mut self, dropped: AssetDrop, log_info: &mut dyn FnMut(&str)) {
+        let AssetDrop { asset, target, position } = dropped;
+        let full_path = self.project_panel.full_path(&asset.path).unwrap_or_else(|| asset.relative_path());
+        if asset.file_type == ProjectFileType::Prefab {
+            self.instantiate_prefab(&full_path, position, log_info);
+            return;
+        }
+        
+        let Some(handle) = target else {
+            return;
+        };
+        let relative = asset.relative_path();
+        match asset.file_type {
+            ProjectFileType::Texture => {
+                let Some(renderer) = self.scene_view_panel.renderer.as_mut() else {
+                    self.console_panel.log_error("Can't load textures without a renderer");
+                    return;
+                };
+                let texture = match renderer.load_sprite_texture(&full_path, TextureSampler::default()) {
+                    Ok(texture) => texture,
+                    Err(e) => {
+                        self.console_panel.log_error(&format!("Failed to load texture {}: {}", full_path.display(), e));
+                        return;
+                    }
+                };
+                if let Some(sprite) = self.ecs.get_component_mut::<SpriteComponent>(&handle) {
+                    sprite.texture = texture;
+                }
+            }
+            ProjectFileType::Material => {
+                if let Some(sprite) = self.ecs.get_component_mut::<SpriteComponent>(&handle) {
+                    sprite.set_material_path(Some(relative));
+                } else if let Some(renderable) = self.ecs.get_component_mut::<RenderableComponent>(&handle) {
+                    renderable.set_material_path(Some(relative));
+                }
+            }
+            ProjectFileType::Script => match self.ecs.get_component_mut::<LuaScriptComponent>(&handle) {
+                Some(script) => {
+                    script.path = relative;
+                    script.handle = None;
+                }
+                None => {
+                    self.ecs.insert_component(&handle, LuaScriptComponent::new(relative));
+                }
+            },
+            ProjectFileType::Audio => match self.ecs.get_component_mut::<AudioSourceComponent>(&handle) {
+                Some(source) => source.clip = relative,
+                None => {
+                    self.ecs.insert_component(&handle, AudioSourceComponent::new(relative));
+                }
+            },
+            _ => return,
+        }
+        
+        self.dirty |= !self.play_mode.is_playing();
+        let name = self.ecs.get_name(&handle).unwrap_or_default();
+        log_info(&format!("Dropped {} on {}", asset.name(), name));
+    }
+    
+    /// Execute the commands queued by the panels this frame
+    fn execute_pending_commands(&mut self) {
+        let mut commands: Vec<_> = self.hierarchy_panel.pending_commands.drain(..).collect();
+        commands.extend(self.inspector_panel.pending_commands.drain(..));
+        commands.extend(self.scene_view_panel.pending_commands.drain(..));
+        
+        if commands.is_empty() {
+            return;
+        }
+        
+        self.with_history(|history, state| {
+            for command in commands {
+                history.execute(command, state);
+            }
+        });
+    }
+    
+    /// Run `f` with the history and the editor state it operates on
+    fn with_history<R>(&mut self, f: impl FnOnce(&mut CommandHistory, &mut EditorState) -> R) -> R {
+        let mut state = EditorState {
+            ecs: &mut self.ecs,
+            entity_types: &mut self.hierarchy_panel.entity_types,
+            selection: &mut self.hierarchy_panel.selection,
+        };
+        let result = f(&mut self.history, &mut state);
+        
+        // Commands may change the selection
+        self.scene_view_panel.set_selection(&self.hierarchy_panel.selection);
+        result
+    }
+    
+    /// Colliders and velocities of the entities, for the scene view's physics overlay
+    ///
+    /// The physics system moves `Transform2DComponent`s, entities with only a 3D
+    /// transform use its X and Y.
+    fn collect_physics_debug(&self) -> Vec<PhysicsDebugInfo> {
+        let mut query = self.ecs.query::<(
+            Option<&Transform2DComponent>,
+            Option<&Transform3DComponent>,
+            Option<&Collider2DComponent>,
+            Option<&PhysicsComponent>,
+        )>();
+        
+        query.iter()
+            .filter(|(_, (_, _, collider, physics))| collider.is_some() || physics.is_some())
+            .filter_map(|(_, (transform_2d, transform_3d, collider, physics))| {
+                let (position, rotation, scale) = match (transform_2d, transform_3d) {
+                    (Some(t), _) => (t.position, t.rotation, t.scale),
+                    (None, Some(t)) => (t.position.truncate(), t.rotation.to_euler(glam::EulerRot::ZYX).0, t.scale.truncate()),
+                    (None, None) => return None,
+                };
+                
+                Some(PhysicsDebugInfo {
+                    position,
+                    collider: collider.map(|c| c.world_shape(position, rotation, scale)),
+                    is_trigger: collider.map_or(false, |c| c.is_trigger),
+                    velocity: physics.filter(|p| p.body_type == BodyType::Dynamic).map(|p| p.velocity),
+                })
+            })
+            .collect()
+    }
+    
+    /// View volumes of the selected cameras, for the scene view
+    ///
+    /// The game renders to the whole window, so its size gives the cameras'
+    /// aspect ratio.
+    fn collect_camera_gizmos(&self, ctx: &Context) -> Vec<CameraGizmo> {
+        let window = ctx.screen_rect().size() * ctx.pixels_per_point();
+        let size = (window.x.round() as u32, window.y.round() as u32);
+        
+        self.scene_view_panel.selection.iter()
+            .filter_map(|handle| {
+                let camera = self.ecs.get_component::<CameraComponent>(handle)?;
+                let corners = camera.frustum_corners(self.ecs.world_transform(handle)?, size)?;
+                Some(CameraGizmo { corners, is_orthographic: camera.is_orthographic })
+            })
+            .collect()
+    }
+    
+    /// Ranges of the selected point lights, for the scene view
+    ///
+    /// Directional lights reach everywhere and only show their direction.
+    fn collect_light_gizmos(&self) -> Vec<LightGizmo> {
+        self.scene_view_panel.selection.iter()
+            .filter_map(|handle| {
+                let light = self.ecs.get_component::<LightComponent>(handle)?;
+                if light.kind != LightKind::Point {
+                    return None;
+                }
+                let position = self.ecs.world_transform(handle)?.w_axis.truncate().truncate();
+                Some(LightGizmo { handle: *handle, position, range: light.range })
+            })
+            .collect()
     }
     
     /// Render the Unity-like toolbar
@@ -316,33 +1736,198 @@ impl EditorUI {
             .frame(egui::Frame::default().fill(self.theme.header))
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
+                    // File menu
+                    ui.menu_button("File", |ui| {
+                        if ui.add(egui::Button::new("New Scene").shortcut_text("Ctrl+N")).clicked() {
+                            ui.close_menu();
+                            self.request_scene_action(SceneAction::NewScene, log_info);
+                        }
+                        if ui.add(egui::Button::new("Open Scene...").shortcut_text("Ctrl+O")).clicked() {
+                            ui.close_menu();
+                            self.request_scene_action(SceneAction::OpenScene, log_info);
+                        }
+                        ui.menu_button("Open Recent", |ui| self.render_open_recent_menu(ui, log_info));
+                        
+                        ui.separator();
+                        
+                        if ui.add(egui::Button::new("Save Scene").shortcut_text("Ctrl+S")).clicked() {
+                            ui.close_menu();
+                            self.save_scene(log_info);
+                        }
+                        if ui.add(egui::Button::new("Save Scene As...").shortcut_text("Ctrl+Shift+S")).clicked() {
+                            ui.close_menu();
+                            self.save_scene_as(log_info);
+                        }
+                        
+                        ui.separator();
+                        
+                        if ui.button("Capture Screenshot").clicked() {
+                            ui.close_menu();
+                            self.capture_screenshot(log_info);
+                        }
+                        
+                        ui.separator();
+                        
+                        if ui.button("Exit").clicked() {
+                            ui.close_menu();
+                            self.request_scene_action(SceneAction::Exit, log_info);
+                        }
+                    });
+                    
+                    // Edit menu
+                    ui.menu_button("Edit", |ui| {
+                        let undo_text = match self.history.undo_description() {
+                            Some(description) => format!("Undo {}", description),
+                            None => "Undo".to_string(),
+                        };
+                        let undo_button = egui::Button::new(undo_text).shortcut_text("Ctrl+Z");
+                        if ui.add_enabled(self.history.can_undo(), undo_button).clicked() {
+                            self.undo(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        let redo_text = match self.history.redo_description() {
+                            Some(description) => format!("Redo {}", description),
+                            None => "Redo".to_string(),
+                        };
+                        let redo_button = egui::Button::new(redo_text).shortcut_text("Ctrl+Y");
+                        if ui.add_enabled(self.history.can_redo(), redo_button).clicked() {
+                            self.redo(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        ui.separator();
+                        
+                        let has_selection = !self.hierarchy_panel.selection.is_empty();
+                        if ui.add_enabled(has_selection, egui::Button::new("Cut").shortcut_text("Ctrl+X")).clicked() {
+                            self.cut_selected(ui.ctx(), log_info);
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(has_selection, egui::Button::new("Copy").shortcut_text("Ctrl+C")).clicked() {
+                            self.copy_selected(ui.ctx(), log_info);
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(!self.clipboard.is_empty(), egui::Button::new("Paste").shortcut_text("Ctrl+V")).clicked() {
+                            self.paste(false, log_info);
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(has_selection, egui::Button::new("Duplicate").shortcut_text("Ctrl+D")).clicked() {
+                            self.duplicate_selected(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        ui.separator();
+                        
+                        if ui.add_enabled(has_selection, egui::Button::new("Group").shortcut_text("Ctrl+G")).clicked() {
+                            self.group_selected(log_info);
+                            ui.close_menu();
+                        }
+                        let has_children = self.hierarchy_panel.selection.iter().any(|handle| !self.ecs.children(handle).is_empty());
+                        if ui.add_enabled(has_children, egui::Button::new("Ungroup").shortcut_text("Ctrl+Shift+G")).clicked() {
+                            self.ungroup_selected(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        ui.separator();
+                        
+                        let delete_button = egui::Button::new("Delete").shortcut_text("Del");
+                        if ui.add_enabled(!self.hierarchy_panel.selection.is_empty(), delete_button).clicked() {
+                            self.delete_selected(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        ui.separator();
+                        
+                        if ui.add_enabled(has_selection, egui::Button::new("Frame Selected").shortcut_text("F")).clicked() {
+                            self.scene_view_panel.focus_selection(&self.ecs);
+                            ui.close_menu();
+                        }
+                        if ui.add(egui::Button::new("Frame All").shortcut_text("A")).clicked() {
+                            self.frame_all(log_info);
+                            ui.close_menu();
+                        }
+                        
+                        ui.separator();
+                        
+                        if ui.button("Preferences...").clicked() {
+                            self.show_preferences = true;
+                            ui.close_menu();
+                        }
+                    });
+                    
+                    // Window menu
+                    ui.menu_button("Window", |ui| {
+                        ui.checkbox(&mut self.layout.show_hierarchy, "Hierarchy");
+                        ui.checkbox(&mut self.layout.show_inspector, "Inspector");
+                        ui.checkbox(&mut self.layout.show_project, "Project");
+                        ui.checkbox(&mut self.layout.show_console, "Console");
+                        
+                        ui.separator();
+                        
+                        ui.checkbox(&mut self.console_panel.show_console, "Console Window");
+                        ui.checkbox(&mut self.scene_view_panel.show_stats, "Frame Stats");
+                        ui.checkbox(&mut self.show_scene_stats, "Scene Stats");
+                        
+                        ui.separator();
+                        
+                        if ui.button("Reset Layout").clicked() {
+                            ui.close_menu();
+                            self.reset_layout();
+                            log_info("Layout reset");
+                        }
+                    });
+                    
+                    ui.separator();
+                    
                     // Transform tools
-                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Select, "Select");
-                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Move, "Move");
-                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Rotate, "Rotate");
-                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Scale, "Scale");
+                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Select, "Select")
+                        .on_hover_text("Select and box select (Q), pan with the middle mouse button");
+                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Move, "Move")
+                        .on_hover_text("Move (W)");
+                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Rotate, "Rotate")
+                        .on_hover_text("Rotate (E)");
+                    ui.selectable_value(&mut self.toolbar.transform_tool, SceneViewTool::Scale, "Scale")
+                        .on_hover_text("Scale (R)");
                     
                     ui.separator();
                     
                     // Play controls
-                    let play_text = if self.toolbar.play_mode { "Stop" } else { "Play" };
+                    let play_text = if self.play_mode.is_playing() { "Stop" } else { "Play" };
                     if ui.button(play_text).clicked() {
-                        self.toolbar.play_mode = !self.toolbar.play_mode;
-                        if self.toolbar.play_mode {
-                            log_info("Starting play mode");
-                            self.active_view = ActiveView::Game;
-                            self.game_view_panel.play_mode = true;
+                        if self.play_mode.is_playing() {
+                            self.stop_playing(log_info);
                         } else {
-                            log_info("Stopping play mode");
-                            self.game_view_panel.play_mode = false;
+                            self.start_playing(log_info);
                         }
                     }
                     
-                    if ui.button("Pause").clicked() {
-                        self.toolbar.paused = !self.toolbar.paused;
-                        log_info(if self.toolbar.paused { "Game paused" } else { "Game resumed" });
+                    let pause_button = egui::SelectableLabel::new(self.play_mode.is_paused(), "Pause");
+                    if ui.add_enabled(self.play_mode.is_playing(), pause_button).clicked() {
+                        self.play_mode.toggle_pause();
+                        log_info(if self.play_mode.is_paused() { "Game paused" } else { "Game resumed" });
+                    }
+                    
+                    let step_button = egui::Button::new("Step");
+                    if ui.add_enabled(self.play_mode.is_paused(), step_button).on_hover_text("Advance the paused game by one frame").clicked() {
+                        self.play_mode.step();
                     }
                     
+                    // Game speed, the UI and editor camera keep running at real time
+                    let time_scale = self.play_mode.time_scale();
+                    egui::ComboBox::from_id_source("time_scale")
+                        .selected_text(format!("{}x", time_scale))
+                        .width(60.0)
+                        .show_ui(ui, |ui| {
+                            for scale in TIME_SCALES {
+                                if ui.selectable_label(time_scale == scale, format!("{}x", scale)).clicked() {
+                                    self.play_mode.set_time_scale(scale);
+                                    log_info(&format!("Time scale set to {}x", scale));
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Time scale");
+                    
                     ui.separator();
                     
                     // Layers dropdown
@@ -361,4 +1946,15 @@ impl EditorUI {
                 });
             });
     }
-} 
\ No newline at end of file
+} 
+/// Dim the editor and swallow clicks on it, below a modal dialog
+fn block_editor(ctx: &Context, id: &str) {
+    let screen = ctx.screen_rect();
+    egui::Area::new(id)
+        .order(egui::Order::Foreground)
+        .fixed_pos(screen.min)
+        .show(ctx, |ui| {
+            ui.allocate_rect(screen, egui::Sense::click_and_drag());
+            ui.painter().rect_filled(screen, 0.0, Color32::from_black_alpha(120));
+        });
+}
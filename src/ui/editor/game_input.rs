@@ -0,0 +1,122 @@
+use egui::{Context, Event, Key, PointerButton};
+use glam::Vec2;
+use winit::event::MouseButton;
+use crate::core::{Input, KeyCode};
+
+/// Feed this frame's egui keyboard and mouse events to the `Input` the game reads
+///
+/// The editor runs inside egui rather than a winit event loop, so play mode gets
+/// its input from egui's events. Shift, Ctrl and Alt only reach egui as modifier
+/// state and are reported as their left-hand keys.
+pub fn feed_egui_input(input: &mut Input, ctx: &Context) {
+    ctx.input(|i| {
+        let pixels_per_point = i.pixels_per_point();
+        for event in &i.events {
+            match event {
+                Event::Key { key, pressed, .. } => {
+                    input.set_key(key_code(*key), *pressed);
+                }
+                Event::PointerButton { button, pressed, .. } => {
+                    input.set_mouse_button(mouse_button(*button), *pressed);
+                }
+                Event::PointerMoved(position) => {
+                    input.set_mouse_position(Vec2::new(position.x, position.y) * pixels_per_point);
+                }
+                _ => {}
+            }
+        }
+
+        input.set_key(KeyCode::LShift, i.modifiers.shift);
+        input.set_key(KeyCode::LControl, i.modifiers.ctrl);
+        input.set_key(KeyCode::LAlt, i.modifiers.alt);
+    });
+}
+
+fn mouse_button(button: PointerButton) -> MouseButton {
+    match button {
+        PointerButton::Primary => MouseButton::Left,
+        PointerButton::Secondary => MouseButton::Right,
+        PointerButton::Middle => MouseButton::Middle,
+        PointerButton::Extra1 => MouseButton::Other(3),
+        PointerButton::Extra2 => MouseButton::Other(4),
+    }
+}
+
+/// The winit key for an egui key
+fn key_code(key: Key) -> KeyCode {
+    match key {
+        Key::ArrowDown => KeyCode::Down,
+        Key::ArrowLeft => KeyCode::Left,
+        Key::ArrowRight => KeyCode::Right,
+        Key::ArrowUp => KeyCode::Up,
+        Key::Escape => KeyCode::Escape,
+        Key::Tab => KeyCode::Tab,
+        Key::Backspace => KeyCode::Back,
+        Key::Enter => KeyCode::Return,
+        Key::Space => KeyCode::Space,
+        Key::Insert => KeyCode::Insert,
+        Key::Delete => KeyCode::Delete,
+        Key::Home => KeyCode::Home,
+        Key::End => KeyCode::End,
+        Key::PageUp => KeyCode::PageUp,
+        Key::PageDown => KeyCode::PageDown,
+        Key::Minus => KeyCode::Minus,
+        Key::PlusEquals => KeyCode::Equals,
+        Key::Num0 => KeyCode::Key0,
+        Key::Num1 => KeyCode::Key1,
+        Key::Num2 => KeyCode::Key2,
+        Key::Num3 => KeyCode::Key3,
+        Key::Num4 => KeyCode::Key4,
+        Key::Num5 => KeyCode::Key5,
+        Key::Num6 => KeyCode::Key6,
+        Key::Num7 => KeyCode::Key7,
+        Key::Num8 => KeyCode::Key8,
+        Key::Num9 => KeyCode::Key9,
+        Key::A => KeyCode::A,
+        Key::B => KeyCode::B,
+        Key::C => KeyCode::C,
+        Key::D => KeyCode::D,
+        Key::E => KeyCode::E,
+        Key::F => KeyCode::F,
+        Key::G => KeyCode::G,
+        Key::H => KeyCode::H,
+        Key::I => KeyCode::I,
+        Key::J => KeyCode::J,
+        Key::K => KeyCode::K,
+        Key::L => KeyCode::L,
+        Key::M => KeyCode::M,
+        Key::N => KeyCode::N,
+        Key::O => KeyCode::O,
+        Key::P => KeyCode::P,
+        Key::Q => KeyCode::Q,
+        Key::R => KeyCode::R,
+        Key::S => KeyCode::S,
+        Key::T => KeyCode::T,
+        Key::U => KeyCode::U,
+        Key::V => KeyCode::V,
+        Key::W => KeyCode::W,
+        Key::X => KeyCode::X,
+        Key::Y => KeyCode::Y,
+        Key::Z => KeyCode::Z,
+        Key::F1 => KeyCode::F1,
+        Key::F2 => KeyCode::F2,
+        Key::F3 => KeyCode::F3,
+        Key::F4 => KeyCode::F4,
+        Key::F5 => KeyCode::F5,
+        Key::F6 => KeyCode::F6,
+        Key::F7 => KeyCode::F7,
+        Key::F8 => KeyCode::F8,
+        Key::F9 => KeyCode::F9,
+        Key::F10 => KeyCode::F10,
+        Key::F11 => KeyCode::F11,
+        Key::F12 => KeyCode::F12,
+        Key::F13 => KeyCode::F13,
+        Key::F14 => KeyCode::F14,
+        Key::F15 => KeyCode::F15,
+        Key::F16 => KeyCode::F16,
+        Key::F17 => KeyCode::F17,
+        Key::F18 => KeyCode::F18,
+        Key::F19 => KeyCode::F19,
+        Key::F20 => KeyCode::F20,
+    }
+}
@@ -1,4 +1,9 @@
 use egui::{Context, Ui, Color32, Vec2, Rect, Pos2, Stroke};
+use crate::ecs::{
+    EcsManager, camera_views, cameras_visible_rect, light_system, render_queue_system, sprite_render_system,
+    text_render_system, tilemap_render_system,
+};
+use crate::rendering::Renderer;
 
 /// Game view panel for previewing the game
 pub struct GameViewPanel {
@@ -29,7 +34,10 @@ impl GameViewPanel {
     }
     
     /// Render the game view
-    pub fn render(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
+    ///
+    /// The scene is drawn through its enabled cameras, ordered by depth, into a
+    /// target the size of the panel, without gizmos or grid.
+    pub fn render(&mut self, ui: &mut Ui, ecs: &EcsManager, renderer: Option<&mut Renderer>, log_info: &mut dyn FnMut(&str)) {
         let available_size = ui.available_size();
         
         // Store the original window size
@@ -45,64 +53,49 @@ impl GameViewPanel {
         
         let rect = response.rect;
         
-        // Draw mock game screen
-        painter.rect_filled(rect, 0.0, Color32::from_rgb(20, 20, 20));
-        
-        // Draw player if in play mode
         if self.play_mode {
             self.frame_counter += 1;
-            
-            // Simple moving player
-            let player_size = 30.0;
-            let t = self.frame_counter as f32 * 0.02;
-            let player_x = rect.center().x + (t.sin() * 100.0);
-            let player_y = rect.center().y;
-            
-            painter.rect_filled(
-                Rect::from_center_size(
-                    Pos2::new(player_x, player_y),
-                    Vec2::new(player_size, player_size),
-                ),
-                0.0,
-                Color32::from_rgb(0, 200, 0),
-            );
-            
-            // Draw collectibles
-            for i in 0..5 {
-                let angle = i as f32 * std::f32::consts::PI * 0.4 + t * 0.5;
-                let x = rect.center().x + angle.cos() * 150.0;
-                let y = rect.center().y + angle.sin() * 100.0;
-                let size = 15.0 + (t + i as f32 * 0.5).sin().abs() * 5.0;
+        }
+        
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let size = (
+            (rect.width() * pixels_per_point).round() as u32,
+            (rect.height() * pixels_per_point).round() as u32,
+        );
+        // Cameras take their aspect ratio from the target, so resizing the panel reshapes them
+        let render_views = camera_views(ecs.world(), size);
+        
+        let message = match renderer {
+            None => Some("No renderer"),
+            Some(_) if render_views.is_empty() => Some("No cameras rendering"),
+            Some(renderer) => {
+                let world = ecs.world();
+                light_system(world, renderer);
+                render_queue_system(world, renderer);
+                // Only the tiles the cameras see become sprites
+                if let Some((view_min, view_max)) = cameras_visible_rect(world, size) {
+                    tilemap_render_system(world, renderer, view_min, view_max);
+                }
+                sprite_render_system(world, renderer);
+                text_render_system(world, renderer);
                 
-                painter.circle_filled(
-                    Pos2::new(x, y),
-                    size,
-                    Color32::from_rgb(255, 200, 0),
-                );
+                match renderer.render_views_to_texture(size.0, size.1, &render_views) {
+                    Some(texture_id) => {
+                        let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                        painter.image(texture_id, rect, uv, Color32::WHITE);
+                        None
+                    }
+                    None => Some("No renderer"),
+                }
             }
-            
-            // Draw stats
-            painter.text(
-                Pos2::new(rect.left() + 10.0, rect.top() + 20.0),
-                egui::Align2::LEFT_TOP,
-                format!("Frame: {}", self.frame_counter),
-                egui::FontId::default(),
-                Color32::WHITE,
-            );
-            
-            painter.text(
-                Pos2::new(rect.left() + 10.0, rect.top() + 40.0),
-                egui::Align2::LEFT_TOP,
-                if self.frame_counter % 60 < 30 { "PLAYING" } else { "" },
-                egui::FontId::proportional(16.0),
-                Color32::from_rgb(255, 50, 50),
-            );
-        } else {
-            // Show "Play" message when not in play mode
+        };
+        
+        if let Some(message) = message {
+            painter.rect_filled(rect, 0.0, Color32::BLACK);
             painter.text(
                 rect.center(),
                 egui::Align2::CENTER_CENTER,
-                "Click Play button to start the game",
+                message,
                 egui::FontId::proportional(18.0),
                 Color32::WHITE,
             );
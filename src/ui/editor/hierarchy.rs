@@ -1,29 +1,139 @@
-use egui::{Context, Ui, RichText, Color32, ScrollArea, Sense};
-use std::collections::HashMap;
-use crate::ui::editor::ui_components::{HierarchyItem};
+use egui::{Context, Ui, RichText, Color32, ScrollArea, Sense, Stroke};
+use std::collections::{HashMap, HashSet};
+use glam::Vec2;
+use crate::ecs::{
+    EcsManager, EntityHandle, NameComponent, Transform3DComponent, CameraComponent, TextComponent, RenderableDescriptor,
+    LightComponent, LightKind, SpriteComponent, AudioSourceComponent,
+};
+use crate::rendering::{PrimitiveShape, TextureHandle};
+use crate::ui::editor::ui_components::{HierarchyItem, EntityTransform, Selection};
+use crate::ui::editor::history::{
+    EditorCommand, CreateEntityCommand, DeleteEntityCommand, ReparentEntityCommand, ReorderSiblingsCommand,
+};
+use crate::ui::editor::clipboard::duplicate_command;
+use crate::ui::editor::asset_drag::{self, AssetDrop};
+use crate::ui::editor::component_registry::ComponentRegistry;
 
 /// Functions for managing and rendering the hierarchy panel
 pub struct HierarchyPanel {
-    /// The currently selected entity
-    pub selected_entity: Option<u32>,
+    /// The selected entities
+    pub selection: Selection,
+    /// Entities in the order their rows are shown, used for Shift+click ranges
+    visible_rows: Vec<EntityHandle>,
     /// The hierarchy expanded state
-    pub hierarchy_expanded: HashMap<u32, bool>,
-    /// Entity names
-    pub entity_names: HashMap<u32, String>,
-    /// Entity parent map
-    pub entity_parent_map: HashMap<u32, u32>,
+    pub hierarchy_expanded: HashMap<EntityHandle, bool>,
     /// Drag entity ID
-    pub drag_entity_id: Option<u32>,
+    pub drag_entity_id: Option<EntityHandle>,
+    /// Where the dragged entity would be dropped, found while rendering the rows
+    drop_target: Option<DropTarget>,
     /// Show hierarchy search
     pub show_hierarchy_search: bool,
     /// Hierarchy search text
     pub hierarchy_search_text: String,
+    /// Entities shown while searching, the matches and their ancestors; `None` without a search
+    search_results: Option<HashSet<EntityHandle>>,
     /// Show create entity menu
     pub show_create_entity_menu: bool,
     /// Entity types (for icons)
-    pub entity_types: HashMap<u32, EntityType>,
+    pub entity_types: HashMap<EntityHandle, EntityType>,
+    /// Commands queued this frame, executed by the editor
+    pub pending_commands: Vec<Box<dyn EditorCommand>>,
+    /// Entity to save as a prefab, handled by the editor
+    pub prefab_to_save: Option<EntityHandle>,
+    /// Set when an entity was double-clicked, the editor frames the selection in the scene view
+    pub focus_requested: bool,
+    /// Project asset dropped this frame and the entity it was dropped on, handled by the editor
+    pub asset_drop: Option<AssetDrop>,
 }
 
+/// Where a dragged entity can be dropped in the hierarchy
+#[derive(Clone, Copy, PartialEq)]
+enum DropTarget {
+    /// Becomes a child of this entity
+    Entity(EntityHandle),
+    /// Becomes a root entity
+    Root,
+}
+
+/// Hierarchy search, e.g. `t:Rigidbody2D name:player`
+///
+/// `t:` (or `type:`) terms match entities with a registered component whose name
+/// contains the term, ignoring case and spaces. `name:` terms and plain text match
+/// entity names. An entity must match every term.
+struct SearchQuery {
+    names: Vec<String>,
+    types: Vec<String>,
+}
+
+impl SearchQuery {
+    /// Parse the search box text, `None` when it's empty
+    fn parse(text: &str) -> Option<Self> {
+        let mut names = Vec::new();
+        let mut types = Vec::new();
+        let mut plain = Vec::new();
+        for token in text.split_whitespace() {
+            let token = token.to_lowercase();
+            if let Some(component) = token.strip_prefix("t:").or_else(|| token.strip_prefix("type:")) {
+                types.push(component.to_string());
+            } else if let Some(name) = token.strip_prefix("name:") {
+                names.push(name.to_string());
+            } else {
+                plain.push(token);
+            }
+        }
+        // Plain words are matched together, so "main camera" still finds "Main Camera"
+        if !plain.is_empty() {
+            names.push(plain.join(" "));
+        }
+
+        (!names.is_empty() || !types.is_empty()).then_some(Self { names, types })
+    }
+
+    fn matches(&self, ecs: &EcsManager, registry: &ComponentRegistry, item: &HierarchyItem) -> bool {
+        let name = item.name.to_lowercase();
+        if !self.names.iter().all(|term| name.contains(term.as_str())) {
+            return false;
+        }
+        if self.types.is_empty() {
+            return true;
+        }
+
+        let Some(entity) = ecs.get_entity(&item.id) else {
+            return false;
+        };
+        let present: Vec<String> = registry.registrations().iter()
+            .filter(|registration| registration.is_present(ecs.world(), entity))
+            .map(|registration| registration.name.to_lowercase().replace(' ', ""))
+            .collect();
+        self.types.iter().all(|term| present.iter().any(|component| component.contains(term.as_str())))
+    }
+}
+
+/// Add the entities matching `query` and their ancestors to `results`, returning whether any of `items` was added
+fn collect_search_results(
+    items: &[HierarchyItem],
+    query: &SearchQuery,
+    ecs: &EcsManager,
+    registry: &ComponentRegistry,
+    results: &mut HashSet<EntityHandle>,
+) -> bool {
+    let mut any = false;
+    for item in items {
+        let descendant_matches = collect_search_results(&item.children, query, ecs, registry, results);
+        if descendant_matches || query.matches(ecs, registry, item) {
+            results.insert(item.id);
+            any = true;
+        }
+    }
+    any
+}
+
+/// Explanation of the search syntax, shown by the hierarchy's "?" button
+const SEARCH_HELP: &str = "Plain text matches entity names.\n\
+    t:Light - entities with a Light component\n\
+    t:Rigidbody2D name:player - both at once\n\
+    Component names ignore case and spaces, and ancestors of matches stay visible.";
+
 /// Entity type for hierarchy display
 #[derive(Clone, Copy, PartialEq)]
 pub enum EntityType {
@@ -31,8 +141,8 @@ pub enum EntityType {
     GameObject,
     /// Camera entity
     Camera,
-    /// Light entity
-    Light,
+    /// Directional or point light
+    Light(LightKind),
     /// UI element
     UI,
     /// Sprite renderer
@@ -41,41 +151,44 @@ pub enum EntityType {
     ParticleSystem,
     /// Audio source
     AudioSource,
+    /// Text label
+    Text,
+    /// Built-in mesh such as a cube or sphere
+    Primitive(PrimitiveShape),
 }
 
 impl HierarchyPanel {
     /// Create a new hierarchy panel
     pub fn new() -> Self {
-        let mut entity_names = HashMap::new();
-        entity_names.insert(1, "Main Camera".to_string());
-        entity_names.insert(2, "Directional Light".to_string());
-        entity_names.insert(3, "Player".to_string());
-        entity_names.insert(4, "Background".to_string());
-        entity_names.insert(5, "UI Canvas".to_string());
-        
-        // Entity types for icons
-        let mut entity_types = HashMap::new();
-        entity_types.insert(1, EntityType::Camera);
-        entity_types.insert(2, EntityType::Light);
-        entity_types.insert(3, EntityType::GameObject);
-        entity_types.insert(4, EntityType::Sprite);
-        entity_types.insert(5, EntityType::UI);
-        
         Self {
-            selected_entity: None,
+            selection: Selection::new(),
+            visible_rows: Vec::new(),
             hierarchy_expanded: HashMap::new(),
-            entity_names,
-            entity_parent_map: HashMap::new(),
             drag_entity_id: None,
+            drop_target: None,
             show_hierarchy_search: false,
             hierarchy_search_text: String::new(),
+            search_results: None,
             show_create_entity_menu: false,
-            entity_types,
+            entity_types: HashMap::new(),
+            pending_commands: Vec::new(),
+            prefab_to_save: None,
+            focus_requested: false,
+            asset_drop: None,
         }
     }
     
+    /// Get the names of all entities in the world
+    pub fn entity_names(&self, ecs: &EcsManager) -> HashMap<EntityHandle, String> {
+        let mut names = HashMap::new();
+        for (handle, name) in ecs.query::<&NameComponent>().iter() {
+            names.insert(handle, name.name.clone());
+        }
+        names
+    }
+    
     /// Render the hierarchy panel
-    pub fn render(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
+    pub fn render(&mut self, ui: &mut Ui, ecs: &mut EcsManager, registry: &ComponentRegistry, log_info: &mut dyn FnMut(&str)) {
         // Unity-like header with dark background
         ui.horizontal(|ui| {
             ui.heading("Hierarchy");
@@ -98,12 +211,17 @@ impl HierarchyPanel {
         if self.show_hierarchy_search {
             ui.horizontal(|ui| {
                 ui.label("🔍");
-                ui.add(egui::TextEdit::singleline(&mut self.hierarchy_search_text)
-                    .hint_text("Search...")
-                    .desired_width(f32::INFINITY));
-                if ui.button("✖").clicked() {
-                    self.hierarchy_search_text.clear();
-                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("✖").clicked() {
+                        self.hierarchy_search_text.clear();
+                    }
+                    ui.menu_button("?", |ui| {
+                        ui.label(SEARCH_HELP);
+                    });
+                    ui.add(egui::TextEdit::singleline(&mut self.hierarchy_search_text)
+                        .hint_text("Search, t:Light for components")
+                        .desired_width(f32::INFINITY));
+                });
             });
         }
         
@@ -112,12 +230,88 @@ impl HierarchyPanel {
             self.render_create_menu(ui, log_info);
         }
         
+        self.prune_state(ecs);
+        
         ScrollArea::vertical().show(ui, |ui| {
-            let items = self.build_entity_hierarchy();
-            self.render_entity_hierarchy(ui, &items, 0, log_info);
+            let items = self.build_entity_hierarchy(ecs);
+            self.search_results = SearchQuery::parse(&self.hierarchy_search_text).map(|query| {
+                let mut results = HashSet::new();
+                collect_search_results(&items, &query, ecs, registry, &mut results);
+                results
+            });
+            let mut rows = Vec::new();
+            self.collect_visible_rows(&items, &mut rows);
+            self.visible_rows = rows;
+            
+            self.drop_target = None;
+            self.asset_drop = None;
+            self.render_entity_hierarchy(ui, ecs, &items, 0, log_info);
+            self.render_root_drop_zone(ui, ecs);
+            
+            // Drop once every row had the chance to become the target
+            if self.drag_entity_id.is_some() && ui.input(|i| i.pointer.any_released()) {
+                if let (Some(dragged), Some(target)) = (self.drag_entity_id.take(), self.drop_target) {
+                    self.drop_entity(ecs, dragged, target, log_info);
+                }
+            }
         });
     }
     
+    /// Empty space below the rows
+    ///
+    /// Dropping an entity there makes it a root entity, and dropping a prefab there
+    /// instantiates it at the root.
+    fn render_root_drop_zone(&mut self, ui: &mut Ui, ecs: &EcsManager) {
+        let height = (ui.clip_rect().bottom() - ui.cursor().top()).max(24.0);
+        let response = ui.allocate_response(egui::vec2(ui.available_width(), height), Sense::hover());
+        if self.drag_entity_id.is_some() && ui.rect_contains_pointer(response.rect) {
+            self.drop_target = Some(DropTarget::Root);
+            ui.painter().rect_stroke(response.rect, 2.0, Stroke::new(1.0, Color32::from_rgb(90, 160, 230)));
+        }
+        if let Some(asset) = asset_drag::drop_target(ui, response.rect, |file_type| asset_drag::entity_accepts(ecs, None, file_type)) {
+            self.asset_drop = Some(AssetDrop { asset, target: None, position: None });
+        }
+    }
+    
+    /// Queue moving a dragged entity to where it was dropped
+    ///
+    /// Dropping an entity onto itself or one of its children is rejected.
+    fn drop_entity(&mut self, ecs: &EcsManager, dragged: EntityHandle, target: DropTarget, log_info: &mut dyn FnMut(&str)) {
+        let name = ecs.get_name(&dragged).unwrap_or_default();
+        let parent = match target {
+            DropTarget::Entity(parent) => Some(parent),
+            DropTarget::Root => None,
+        };
+        
+        if parent.map_or(false, |parent| ecs.is_ancestor(&dragged, &parent)) {
+            log_info(&format!("Can't move '{}' into itself or one of its children", name));
+            return;
+        }
+        
+        if let Some(command) = ReparentEntityCommand::new(ecs, dragged, parent) {
+            self.pending_commands.push(Box::new(command));
+            
+            // Keep the moved entity in view under its new parent
+            if let Some(parent) = parent {
+                self.hierarchy_expanded.insert(parent, true);
+                self.reveal(ecs, &parent);
+            }
+            match parent.and_then(|parent| ecs.get_name(&parent)) {
+                Some(parent_name) => log_info(&format!("Moved '{}' to be a child of '{}'", name, parent_name)),
+                None => log_info(&format!("Moved '{}' to the root", name)),
+            }
+        }
+    }
+    
+    /// Queue moving an entity `offset` places among its siblings
+    fn move_entity(&mut self, ecs: &EcsManager, handle: EntityHandle, offset: isize, log_info: &mut dyn FnMut(&str)) {
+        if let Some(command) = ReorderSiblingsCommand::new(ecs, handle, offset) {
+            self.pending_commands.push(Box::new(command));
+            let direction = if offset < 0 { "up" } else { "down" };
+            log_info(&format!("Moved '{}' {}", ecs.get_name(&handle).unwrap_or_default(), direction));
+        }
+    }
+    
     /// Render the create entity menu
     fn render_create_menu(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
         // Unity-like dropdown menu
@@ -127,45 +321,69 @@ impl HierarchyPanel {
                 ui.set_width(ui.available_width());
                 
                 if ui.selectable_label(false, "Create Empty").clicked() {
-                    self.create_entity("New GameObject".to_string(), EntityType::GameObject);
+                    self.create_empty_entity("New GameObject");
                     self.show_create_entity_menu = false;
                     log_info("Created empty GameObject");
                 }
                 
-                if ui.selectable_label(false, "3D Object").clicked() {
-                    log_info("3D Object submenu clicked");
-                }
+                ui.menu_button("3D Object", |ui| {
+                    for shape in PrimitiveShape::ALL {
+                        if ui.button(shape.name()).clicked() {
+                            self.create_entity(shape.name(), EntityType::Primitive(shape));
+                            self.show_create_entity_menu = false;
+                            log_info(&format!("Created {}", shape.name()));
+                            ui.close_menu();
+                        }
+                    }
+                });
                 
-                if ui.selectable_label(false, "2D Object").clicked() {
-                    log_info("2D Object submenu clicked");
-                }
+                ui.menu_button("2D Object", |ui| {
+                    if ui.button("Sprite").clicked() {
+                        self.create_sprite_entity("Sprite");
+                        self.show_create_entity_menu = false;
+                        log_info("Created Sprite");
+                        ui.close_menu();
+                    }
+                });
                 
-                if ui.selectable_label(false, "Light").clicked() {
-                    self.create_entity("New Light".to_string(), EntityType::Light);
-                    self.show_create_entity_menu = false;
-                    log_info("Created Light");
-                }
+                ui.menu_button("Light", |ui| {
+                    for kind in LightKind::ALL {
+                        let name = format!("{} Light", kind.label());
+                        if ui.button(&name).clicked() {
+                            self.create_entity(&name, EntityType::Light(kind));
+                            self.show_create_entity_menu = false;
+                            log_info(&format!("Created {}", name));
+                            ui.close_menu();
+                        }
+                    }
+                });
                 
                 if ui.selectable_label(false, "Camera").clicked() {
-                    self.create_entity("New Camera".to_string(), EntityType::Camera);
+                    self.create_entity("Camera", EntityType::Camera);
                     self.show_create_entity_menu = false;
                     log_info("Created Camera");
                 }
                 
                 if ui.selectable_label(false, "UI").clicked() {
-                    self.create_entity("UI Element".to_string(), EntityType::UI);
+                    self.create_entity("UI Element", EntityType::UI);
                     self.show_create_entity_menu = false;
                     log_info("Created UI Element");
                 }
                 
+                if ui.selectable_label(false, "Text").clicked() {
+                    self.create_entity("Text", EntityType::Text);
+                    self.show_create_entity_menu = false;
+                    log_info("Created Text");
+                }
+                
                 if ui.selectable_label(false, "Audio").clicked() {
-                    self.create_entity("Audio Source".to_string(), EntityType::AudioSource);
+                    self.create_entity("Audio Source", EntityType::AudioSource);
                     self.show_create_entity_menu = false;
                     log_info("Created Audio Source");
                 }
                 
                 if ui.selectable_label(false, "Particle System").clicked() {
-                    self.create_entity("Particle System".to_string(), EntityType::ParticleSystem);
+                    self.create_entity("Particle System", EntityType::ParticleSystem);
                     self.show_create_entity_menu = false;
                     log_info("Created Particle System");
                 }
@@ -178,90 +396,135 @@ impl HierarchyPanel {
             });
     }
     
-    /// Create a new entity
-    fn create_entity(&mut self, name: String, entity_type: EntityType) {
-        let new_id = self.entity_names.keys().max().map_or(1, |max| max + 1);
-        self.entity_names.insert(new_id, name);
-        self.entity_types.insert(new_id, entity_type);
-        
-        // If an entity is selected, make this a child of it
-        if let Some(parent_id) = self.selected_entity {
-            self.entity_parent_map.insert(new_id, parent_id);
-        }
+    /// Queue creation of an empty game object
+    pub fn create_empty_entity(&mut self, name: &str) -> EntityHandle {
+        self.create_entity(name, EntityType::GameObject)
+    }
+    
+    /// Queue creation of a sprite entity
+    pub fn create_sprite_entity(&mut self, name: &str) -> EntityHandle {
+        self.create_entity(name, EntityType::Sprite)
+    }
+    
+    /// Queue creation of a new entity
+    ///
+    /// If an entity is selected the new one becomes its child. The entity is
+    /// spawned when the editor executes the queued command.
+    pub fn create_entity(&mut self, name: &str, entity_type: EntityType) -> EntityHandle {
+        let command = CreateEntityCommand::new(name, entity_type, EntityTransform::default(), self.selection.primary());
+        let handle = command.handle;
+        self.pending_commands.push(Box::new(command));
         
         // Select the new entity
-        self.selected_entity = Some(new_id);
+        self.selection.set(Some(handle));
+        
+        handle
     }
     
     /// Build the entity hierarchy
-    pub fn build_entity_hierarchy(&self) -> Vec<HierarchyItem> {
-        let mut root_items = Vec::new();
-        let mut used_ids = std::collections::HashSet::new();
+    ///
+    /// Siblings are listed in their stored order, falling back to their handles, so
+    /// the tree is the same every frame. Entities whose parent is gone or unnamed are
+    /// shown as roots.
+    pub fn build_entity_hierarchy(&self, ecs: &EcsManager) -> Vec<HierarchyItem> {
+        let groups = group_by_parent(ecs);
+        hierarchy_items(&groups, None)
+    }
+    
+    /// Add children to a hierarchy item
+    pub fn add_children_to_hierarchy(&self, ecs: &EcsManager, parent: &mut HierarchyItem) {
+        let groups = group_by_parent(ecs);
+        parent.children.extend(hierarchy_items(&groups, Some(parent.id)));
+    }
+    
+    /// Forget the expanded state and selection of entities that no longer exist
+    fn prune_state(&mut self, ecs: &EcsManager) {
+        self.hierarchy_expanded.retain(|handle, _| ecs.get_entity(handle).is_some());
         
-        // First, collect all entities that have parents
-        for (&id, _) in &self.entity_names {
-            if let Some(_parent_id) = self.entity_parent_map.get(&id) {
-                used_ids.insert(id);
+        let stale: Vec<EntityHandle> = self.selection.iter()
+            .filter(|handle| ecs.get_entity(handle).is_none())
+            .copied()
+            .collect();
+        for handle in stale {
+            self.selection.remove(&handle);
+        }
+    }
+    
+    /// Expand the ancestors of an entity so its row is visible
+    fn reveal(&mut self, ecs: &EcsManager, handle: &EntityHandle) {
+        let mut current = ecs.get_parent(handle);
+        while let Some(parent) = current {
+            let expanded = self.hierarchy_expanded.entry(parent).or_insert(false);
+            if *expanded {
+                break;
             }
+            *expanded = true;
+            current = ecs.get_parent(&parent);
         }
-        
-        // Then, create hierarchy items for all root entities (those without parents)
-        for (&id, name) in &self.entity_names {
-            if !used_ids.contains(&id) {
-                let mut item = HierarchyItem {
-                    id,
-                    name: name.clone(),
-                    children: Vec::new(),
-                };
-                
-                self.add_children_to_hierarchy(&mut item);
-                root_items.push(item);
+    }
+    
+    /// Whether an item is shown with the current search, as a match or an ancestor of one
+    fn matches_search(&self, item: &HierarchyItem) -> bool {
+        self.search_results.as_ref().map_or(true, |results| results.contains(&item.id))
+    }
+    
+    /// Whether an item's children are shown, searching expands the ancestors of matches
+    fn shows_children(&self, item: &HierarchyItem) -> bool {
+        !item.children.is_empty()
+            && (self.search_results.is_some() || *self.hierarchy_expanded.get(&item.id).unwrap_or(&false))
+    }
+    
+    /// Collect the entities whose rows are shown, top to bottom
+    fn collect_visible_rows(&self, items: &[HierarchyItem], rows: &mut Vec<EntityHandle>) {
+        for item in items {
+            if !self.matches_search(item) {
+                continue;
+            }
+            
+            rows.push(item.id);
+            if self.shows_children(item) {
+                self.collect_visible_rows(&item.children, rows);
             }
         }
-        
-        root_items
     }
     
-    /// Add children to a hierarchy item
-    pub fn add_children_to_hierarchy(&self, parent: &mut HierarchyItem) {
-        for (&id, &parent_id) in &self.entity_parent_map {
-            if parent_id == parent.id {
-                if let Some(name) = self.entity_names.get(&id) {
-                    let mut child = HierarchyItem {
-                        id,
-                        name: name.clone(),
-                        children: Vec::new(),
-                    };
-                    
-                    self.add_children_to_hierarchy(&mut child);
-                    parent.children.push(child);
-                }
+    /// Select every row between the primary selection and `to`
+    fn select_range(&mut self, to: EntityHandle) {
+        let anchor = self.selection.primary();
+        let from = anchor.and_then(|anchor| self.visible_rows.iter().position(|row| *row == anchor));
+        let to_index = self.visible_rows.iter().position(|row| *row == to);
+        
+        match (from, to_index) {
+            (Some(from), Some(to_index)) => {
+                let range = self.visible_rows[from.min(to_index)..=from.max(to_index)].to_vec();
+                self.selection.set_many(range, anchor);
             }
+            _ => self.selection.set(Some(to)),
         }
     }
     
     /// Render the entity hierarchy
-    pub fn render_entity_hierarchy(&mut self, ui: &mut Ui, items: &[HierarchyItem], depth: usize, log_info: &mut dyn FnMut(&str)) {
+    pub fn render_entity_hierarchy(&mut self, ui: &mut Ui, ecs: &mut EcsManager, items: &[HierarchyItem], depth: usize, log_info: &mut dyn FnMut(&str)) {
         for item in items {
             // Skip items that don't match the search
-            if !self.hierarchy_search_text.is_empty() && 
-               !item.name.to_lowercase().contains(&self.hierarchy_search_text.to_lowercase()) {
+            if !self.matches_search(item) {
                 continue;
             }
             
             // Convert depth to float for UI spacing
             let indent = (depth as f32) * 20.0;
             let has_children = !item.children.is_empty();
+            let searching = self.search_results.is_some();
             
             // Entity row frame for hover effect and selection
-            let is_selected = Some(item.id) == self.selected_entity;
+            let is_selected = self.selection.contains(&item.id);
             let row_color = if is_selected {
                 Color32::from_rgb(44, 93, 135) // Unity's selection blue
             } else {
                 Color32::TRANSPARENT
             };
             
-            egui::Frame::none()
+            let row = egui::Frame::none()
                 .fill(row_color)
                 .show(ui, |ui| {
                     ui.horizontal(|ui| {
@@ -269,7 +532,7 @@ impl HierarchyPanel {
                         
                         if has_children {
                             let expanded = self.hierarchy_expanded.entry(item.id).or_insert(false);
-                            let text = if *expanded { "▼" } else { "►" };
+                            let text = if *expanded || searching { "▼" } else { "►" };
                             if ui.button(text).clicked() {
                                 *expanded = !*expanded;
                             }
@@ -282,58 +545,259 @@ impl HierarchyPanel {
                             let icon = match entity_type {
                                 EntityType::GameObject => "⬚",
                                 EntityType::Camera => "📷",
-                                EntityType::Light => "💡",
+                                EntityType::Light(_) => "💡",
                                 EntityType::UI => "🖼",
                                 EntityType::Sprite => "🎨",
                                 EntityType::ParticleSystem => "✨",
                                 EntityType::AudioSource => "🔊",
+                                EntityType::Text => "🔤",
+                                EntityType::Primitive(_) => "🧊",
                             };
                             ui.label(icon);
                         }
                         
-                        // Entity name with proper styling
+                        // Entity name with proper styling, inactive entities are greyed out
                         let mut text = RichText::new(&item.name);
-                        if is_selected {
+                        if !ecs.is_active_in_hierarchy(&item.id) {
+                            text = text.color(Color32::from_gray(110));
+                        } else if is_selected {
                             text = text.color(Color32::WHITE);
                         }
                         
-                        let response = ui.add(egui::Label::new(text).sense(Sense::click()));
+                        let response = ui.add(egui::Label::new(text).sense(Sense::click_and_drag()));
                         
                         if response.clicked() {
-                            self.selected_entity = Some(item.id);
-                            // Log selection
+                            // Ctrl+click toggles, Shift+click selects a range
+                            let modifiers = ui.input(|i| i.modifiers);
+                            if modifiers.command {
+                                self.selection.toggle(item.id);
+                            } else if modifiers.shift {
+                                self.select_range(item.id);
+                            } else {
+                                self.selection.set(Some(item.id));
+                            }
                             log_info(&format!("Selected entity: {}", item.name));
                         }
                         
-                        // Handle drag & drop for hierarchy reordering
-                        if response.dragged() {
-                            self.drag_entity_id = Some(item.id);
+                        if response.double_clicked() {
+                            self.selection.set(Some(item.id));
+                            self.focus_requested = true;
+                            log_info(&format!("Focusing on entity: {}", item.name));
                         }
                         
-                        if response.drag_released() {
-                            self.drag_entity_id = None;
-                        }
-                        
-                        if let Some(drag_id) = self.drag_entity_id {
-                            if drag_id != item.id && response.hovered() {
-                                // Handle parenting logic here
-                                self.entity_parent_map.insert(drag_id, item.id);
-                                
-                                // We need to rebuild the hierarchy
-                                self.drag_entity_id = None;
-                                
-                                if let Some(dragged_name) = self.entity_names.get(&drag_id) {
-                                    log_info(&format!("Moved '{}' to be a child of '{}'", 
-                                                   dragged_name, item.name));
+                        let response = response.context_menu(|ui| {
+                            if ui.button("Duplicate").clicked() {
+                                // Duplicate the whole selection when the item is part of it
+                                if !self.selection.contains(&item.id) {
+                                    self.selection.set(Some(item.id));
+                                }
+                                if let Some(command) = duplicate_command(ecs, &self.entity_types, &self.selection) {
+                                    self.pending_commands.push(Box::new(command));
+                                    log_info(&format!("Duplicated entity: {}", item.name));
                                 }
+                                ui.close_menu();
+                            }
+                            if ui.button("Move Up").clicked() {
+                                self.move_entity(ecs, item.id, -1, log_info);
+                                ui.close_menu();
+                            }
+                            if ui.button("Move Down").clicked() {
+                                self.move_entity(ecs, item.id, 1, log_info);
+                                ui.close_menu();
+                            }
+                            if ui.button("Save as Prefab").clicked() {
+                                self.prefab_to_save = Some(item.id);
+                                ui.close_menu();
                             }
+                            if ui.button("Delete").clicked() {
+                                // Deleting part of a multi-selection deletes all of it
+                                let command = if self.selection.contains(&item.id) && self.selection.len() > 1 {
+                                    DeleteEntityCommand::new_many(self.selection.iter().copied().collect())
+                                } else {
+                                    DeleteEntityCommand::new(item.id)
+                                };
+                                self.pending_commands.push(Box::new(command));
+                                log_info(&format!("Deleted entity: {}", item.name));
+                                ui.close_menu();
+                            }
+                        });
+                        
+                        // Drag an entity onto another row to reparent it
+                        if response.drag_started() {
+                            self.drag_entity_id = Some(item.id);
                         }
                     });
                 });
             
-            if has_children && *self.hierarchy_expanded.get(&item.id).unwrap_or(&false) {
-                self.render_entity_hierarchy(ui, &item.children, depth + 1, log_info);
+            if let Some(dragged) = self.drag_entity_id {
+                if dragged != item.id && ui.rect_contains_pointer(row.response.rect) {
+                    self.drop_target = Some(DropTarget::Entity(item.id));
+                    
+                    // Red when dropping here would create a cycle
+                    let color = if ecs.is_ancestor(&dragged, &item.id) {
+                        Color32::from_rgb(200, 70, 70)
+                    } else {
+                        Color32::from_rgb(90, 160, 230)
+                    };
+                    ui.painter().rect_stroke(row.response.rect, 2.0, Stroke::new(1.0, color));
+                }
+            }
+            
+            // Project assets dropped on the row go to its entity
+            if let Some(asset) = asset_drag::drop_target(ui, row.response.rect, |file_type| asset_drag::entity_accepts(ecs, Some(item.id), file_type)) {
+                self.asset_drop = Some(AssetDrop { asset, target: Some(item.id), position: None });
+            }
+            
+            if self.shows_children(item) {
+                self.render_entity_hierarchy(ui, ecs, &item.children, depth + 1, log_info);
+            }
+        }
+    }
+}
+
+/// Named entities and their names, grouped by the parent they're shown under and in sibling order
+fn group_by_parent(ecs: &EcsManager) -> HashMap<Option<EntityHandle>, Vec<(EntityHandle, String)>> {
+    let names = HashMap::<EntityHandle, String>::from_iter(
+        ecs.query::<&NameComponent>().iter().map(|(handle, name)| (handle, name.name.clone())),
+    );
+    
+    let mut handles: Vec<EntityHandle> = names.keys().copied().collect();
+    ecs.sort_siblings(&mut handles);
+    
+    let mut groups: HashMap<Option<EntityHandle>, Vec<(EntityHandle, String)>> = HashMap::new();
+    for handle in handles {
+        let parent = ecs.get_parent(&handle).filter(|parent| names.contains_key(parent));
+        groups.entry(parent).or_default().push((handle, names[&handle].clone()));
+    }
+    groups
+}
+
+/// Hierarchy items for the children of `parent`, or the roots for `None`
+fn hierarchy_items(groups: &HashMap<Option<EntityHandle>, Vec<(EntityHandle, String)>>, parent: Option<EntityHandle>) -> Vec<HierarchyItem> {
+    groups.get(&parent).map_or_else(Vec::new, |children| {
+        children.iter()
+            .map(|(handle, name)| HierarchyItem {
+                id: *handle,
+                name: name.clone(),
+                children: hierarchy_items(groups, Some(*handle)),
+            })
+            .collect()
+    })
+}
+
+/// Spawn an entity with the components that match its editor type
+///
+/// Sprites start out as a white square, cameras, lights, audio sources and text with their
+/// components' defaults. Types the engine has no components for yet only get a
+/// name and transform.
+pub fn spawn_entity(ecs: &mut EcsManager, name: &str, entity_type: EntityType, transform: Transform3DComponent) -> EntityHandle {
+    spawn_entity_with_handle(ecs, EntityHandle::generate(), name, entity_type, transform)
+}
+
+/// Spawn an entity that keeps an existing handle (used when redoing a creation)
+pub fn spawn_entity_with_handle(ecs: &mut EcsManager, handle: EntityHandle, name: &str, entity_type: EntityType, transform: Transform3DComponent) -> EntityHandle {
+    match entity_type {
+        EntityType::Camera => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            CameraComponent::new_perspective(60.0_f32.to_radians(), 0.3, 1000.0),
+        )),
+        EntityType::Light(kind) => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            match kind {
+                LightKind::Directional => LightComponent::directional(),
+                LightKind::Point => LightComponent::point(10.0),
+            },
+        )),
+        EntityType::Sprite => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            SpriteComponent::new(TextureHandle::WHITE, Vec2::ONE),
+        )),
+        EntityType::Text => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            TextComponent::new("New Text"),
+        )),
+        EntityType::AudioSource => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            AudioSourceComponent::new(""),
+        )),
+        EntityType::Primitive(shape) => ecs.create_entity_with_handle(handle, (
+            NameComponent::new(name),
+            transform,
+            RenderableDescriptor::primitive(shape),
+        )),
+        _ => ecs.create_entity_with_handle(handle, (NameComponent::new(name), transform)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Handles of the items and their descendants, depth first
+    fn flatten(items: &[HierarchyItem]) -> Vec<(EntityHandle, usize)> {
+        fn visit(items: &[HierarchyItem], depth: usize, out: &mut Vec<(EntityHandle, usize)>) {
+            for item in items {
+                out.push((item.id, depth));
+                visit(&item.children, depth + 1, out);
             }
         }
+        let mut out = Vec::new();
+        visit(items, 0, &mut out);
+        out
     }
-} 
\ No newline at end of file
+
+    /// A few roots, one of them with children, created in `handles` order
+    fn scene(handles: &[EntityHandle]) -> EcsManager {
+        let mut ecs = EcsManager::new();
+        for (i, handle) in handles.iter().enumerate() {
+            ecs.create_entity_with_handle(*handle, (NameComponent::new(format!("Entity {i}")),));
+        }
+        for child in &handles[3..] {
+            ecs.insert_component(child, crate::ecs::ParentComponent::new(handles[0]));
+        }
+        ecs
+    }
+
+    #[test]
+    fn building_twice_gives_the_same_order() {
+        let handles: Vec<EntityHandle> = (0..8).map(|_| EntityHandle::generate()).collect();
+        let ecs = scene(&handles);
+        let panel = HierarchyPanel::new();
+
+        let first = flatten(&panel.build_entity_hierarchy(&ecs));
+        let second = flatten(&panel.build_entity_hierarchy(&ecs));
+        assert_eq!(first.len(), handles.len());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn order_does_not_depend_on_creation_order() {
+        let handles: Vec<EntityHandle> = (0..8).map(|_| EntityHandle::generate()).collect();
+        let mut reversed = handles.clone();
+        reversed[1..3].reverse();
+        reversed[3..].reverse();
+        let panel = HierarchyPanel::new();
+
+        let built = flatten(&panel.build_entity_hierarchy(&scene(&handles)));
+        let built_reversed = flatten(&panel.build_entity_hierarchy(&scene(&reversed)));
+        assert_eq!(built, built_reversed);
+    }
+
+    #[test]
+    fn stored_sibling_order_comes_first() {
+        let handles: Vec<EntityHandle> = (0..8).map(|_| EntityHandle::generate()).collect();
+        let mut ecs = scene(&handles);
+        let children: Vec<EntityHandle> = handles[3..].iter().rev().copied().collect();
+        ecs.set_sibling_order(&children);
+
+        let items = HierarchyPanel::new().build_entity_hierarchy(&ecs);
+        let root = items.iter().find(|item| item.id == handles[0]).unwrap();
+        let shown: Vec<EntityHandle> = root.children.iter().map(|item| item.id).collect();
+        assert_eq!(shown, children);
+    }
+}
@@ -0,0 +1,969 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use glam::Vec3;
+use crate::ecs::{EcsManager, EntityHandle, NameComponent, Prefab, RenderableDescriptor};
+use crate::scene::EntityData;
+use crate::ui::editor::hierarchy::{EntityType, spawn_entity_with_handle};
+use crate::ui::editor::ui_components::{EntityTransform, Selection};
+
+/// Default number of undo steps kept by the editor
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// Consecutive edits of the same field within this window become one undo step
+pub const COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// The parts of the editor that commands are allowed to change
+pub struct EditorState<'a> {
+    /// The ECS world being edited
+    pub ecs: &'a mut EcsManager,
+    /// Entity types (for icons and scene view drawing)
+    pub entity_types: &'a mut HashMap<EntityHandle, EntityType>,
+    /// The selected entities
+    pub selection: &'a mut Selection,
+}
+
+impl<'a> EditorState<'a> {
+    /// Write an editor transform to an entity's `Transform3DComponent`
+    pub fn set_transform(&mut self, handle: EntityHandle, transform: &EntityTransform) {
+        transform.write_to(self.ecs, &handle);
+    }
+
+    /// Move an entity under a new parent without moving it in the world
+    pub fn set_parent_keep_world(&mut self, handle: EntityHandle, parent: Option<EntityHandle>) {
+        self.ecs.set_parent_keep_world(&handle, parent);
+    }
+}
+
+/// An undoable editor operation
+pub trait EditorCommand {
+    /// Short human readable description, shown in the Edit menu
+    fn description(&self) -> String;
+
+    /// Perform (or redo) the operation
+    fn apply(&mut self, state: &mut EditorState);
+
+    /// Undo the operation
+    fn revert(&mut self, state: &mut EditorState);
+
+    /// Try to fold a newer command into this one, returning true on success
+    fn merge(&mut self, _next: &dyn EditorCommand) -> bool {
+        false
+    }
+
+    /// Access the concrete command, used when merging
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Bounded undo/redo stack of editor commands
+pub struct CommandHistory {
+    /// Recorded commands, oldest first
+    commands: Vec<Box<dyn EditorCommand>>,
+    /// Number of commands currently applied; everything past it can be redone
+    pointer: usize,
+    /// Maximum number of commands kept
+    capacity: usize,
+    /// Prevents the next command from merging into one that was undone or redone
+    merge_barrier: bool,
+    /// Value of `pointer` when the scene was last saved, `None` if that state can't be reached any more
+    saved_pointer: Option<usize>,
+}
+
+impl CommandHistory {
+    /// Create a new history that keeps at most `capacity` commands
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            commands: Vec::new(),
+            pointer: 0,
+            capacity: capacity.max(1),
+            merge_barrier: false,
+            saved_pointer: Some(0),
+        }
+    }
+
+    /// Apply a command and record it, discarding anything that could be redone
+    pub fn execute(&mut self, mut command: Box<dyn EditorCommand>, state: &mut EditorState) {
+        command.apply(state);
+        self.commands.truncate(self.pointer);
+        // The saved state was in the redo branch that was just discarded
+        if self.saved_pointer.map_or(false, |saved| saved > self.pointer) {
+            self.saved_pointer = None;
+        }
+
+        let merged = !self.merge_barrier && self.commands.last_mut()
+            .map_or(false, |last| last.merge(command.as_ref()));
+        self.merge_barrier = false;
+
+        if merged {
+            // The last command now leads somewhere else than the saved state
+            if self.saved_pointer == Some(self.pointer) {
+                self.saved_pointer = None;
+            }
+        } else {
+            self.commands.push(command);
+            if self.commands.len() > self.capacity {
+                self.commands.remove(0);
+                self.saved_pointer = self.saved_pointer.and_then(|saved| saved.checked_sub(1));
+            }
+        }
+
+        self.pointer = self.commands.len();
+    }
+
+    /// Revert the most recent command, returning false if there is nothing to undo
+    pub fn undo(&mut self, state: &mut EditorState) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+
+        self.pointer -= 1;
+        self.commands[self.pointer].revert(state);
+        self.merge_barrier = true;
+        true
+    }
+
+    /// Re-apply the most recently undone command, returning false if there is nothing to redo
+    pub fn redo(&mut self, state: &mut EditorState) -> bool {
+        if !self.can_redo() {
+            return false;
+        }
+
+        self.commands[self.pointer].apply(state);
+        self.pointer += 1;
+        self.merge_barrier = true;
+        true
+    }
+
+    /// Whether there is a command to undo
+    pub fn can_undo(&self) -> bool {
+        self.pointer > 0
+    }
+
+    /// Whether there is a command to redo
+    pub fn can_redo(&self) -> bool {
+        self.pointer < self.commands.len()
+    }
+
+    /// Description of the command that would be undone
+    pub fn undo_description(&self) -> Option<String> {
+        self.pointer.checked_sub(1).map(|i| self.commands[i].description())
+    }
+
+    /// Description of the command that would be redone
+    pub fn redo_description(&self) -> Option<String> {
+        self.commands.get(self.pointer).map(|c| c.description())
+    }
+
+    /// Forget all recorded commands, treating the current state as saved
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.pointer = 0;
+        self.merge_barrier = false;
+        self.saved_pointer = Some(0);
+    }
+
+    /// Remember the current state as the saved one
+    pub fn mark_saved(&mut self) {
+        self.saved_pointer = Some(self.pointer);
+    }
+
+    /// Whether commands were executed, undone or redone since the last save
+    pub fn is_modified(&self) -> bool {
+        self.saved_pointer != Some(self.pointer)
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+/// Creates a new entity
+pub struct CreateEntityCommand {
+    /// Handle the entity is created with, kept across undo/redo
+    pub handle: EntityHandle,
+    /// Name of the new entity
+    pub name: String,
+    /// Editor type of the new entity
+    pub entity_type: EntityType,
+    /// Initial transform
+    pub transform: EntityTransform,
+    /// Parent of the new entity
+    pub parent: Option<EntityHandle>,
+}
+
+impl CreateEntityCommand {
+    /// Create a command for a new entity with a fresh handle
+    pub fn new(name: &str, entity_type: EntityType, transform: EntityTransform, parent: Option<EntityHandle>) -> Self {
+        Self {
+            handle: EntityHandle::generate(),
+            name: name.to_string(),
+            entity_type,
+            transform,
+            parent,
+        }
+    }
+}
+
+impl EditorCommand for CreateEntityCommand {
+    fn description(&self) -> String {
+        format!("Create '{}'", self.name)
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        spawn_entity_with_handle(state.ecs, self.handle, &self.name, self.entity_type, self.transform.to_component());
+        if self.parent.is_some() {
+            state.ecs.set_parent(&self.handle, self.parent);
+        }
+
+        state.entity_types.insert(self.handle, self.entity_type);
+        state.selection.set(Some(self.handle));
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        state.ecs.destroy_entity(&self.handle);
+        state.entity_types.remove(&self.handle);
+
+        if state.selection.remove(&self.handle) && state.selection.is_empty() {
+            state.selection.set(self.parent);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Snapshot of a deleted entity, enough to bring it back
+struct DeletedEntity {
+    data: EntityData,
+    entity_type: Option<EntityType>,
+}
+
+/// Deletes entities together with all of their children
+pub struct DeleteEntityCommand {
+    /// Roots of the deleted subtrees
+    pub handles: Vec<EntityHandle>,
+    /// Snapshots taken when the command was applied, parents first
+    deleted: Vec<DeletedEntity>,
+}
+
+impl DeleteEntityCommand {
+    /// Create a command that deletes `handle` and its children
+    pub fn new(handle: EntityHandle) -> Self {
+        Self::new_many(vec![handle])
+    }
+
+    /// Create a command that deletes several entities and their children
+    pub fn new_many(handles: Vec<EntityHandle>) -> Self {
+        Self {
+            handles,
+            deleted: Vec::new(),
+        }
+    }
+}
+
+impl EditorCommand for DeleteEntityCommand {
+    fn description(&self) -> String {
+        if self.handles.len() > 1 {
+            return format!("Delete {} Entities", self.handles.len());
+        }
+        match self.deleted.first().and_then(|d| d.data.name.as_ref()) {
+            Some(name) => format!("Delete '{}'", name.name),
+            None => "Delete Entity".to_string(),
+        }
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        // Collect the subtrees breadth first so parents are restored before their children,
+        // skipping roots that are inside another deleted subtree
+        let mut subtree: Vec<EntityHandle> = self.handles.iter()
+            .filter(|handle| !self.handles.iter().any(|other| other != *handle && state.ecs.is_ancestor(other, handle)))
+            .copied()
+            .collect();
+        let mut i = 0;
+        while i < subtree.len() {
+            subtree.extend(state.ecs.children(&subtree[i]));
+            i += 1;
+        }
+
+        self.deleted.clear();
+        for handle in subtree {
+            if let Some(data) = EntityData::capture(state.ecs, &handle) {
+                self.deleted.push(DeletedEntity {
+                    data,
+                    entity_type: state.entity_types.remove(&handle),
+                });
+                state.ecs.destroy_entity(&handle);
+                state.selection.remove(&handle);
+            }
+        }
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        for deleted in &self.deleted {
+            let handle = deleted.data.spawn(state.ecs);
+            if let Some(entity_type) = deleted.entity_type {
+                state.entity_types.insert(handle, entity_type);
+            }
+        }
+
+        state.selection.set_many(self.handles.iter().copied(), self.handles.first().copied());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Spawns copies of entity snapshots, from a prefab or the clipboard
+pub struct SpawnEntitiesCommand {
+    /// Shown in the Edit menu
+    description: String,
+    /// Root of each spawned subtree, selected after spawning
+    roots: Vec<EntityHandle>,
+    /// Entities to spawn, parents first, with handles fixed so redo recreates the same ones
+    entities: Vec<EntityData>,
+    /// Editor type of each entity in `entities`
+    entity_types: Vec<EntityType>,
+}
+
+impl SpawnEntitiesCommand {
+    /// Create a command that instantiates `prefab`, optionally at `position`
+    pub fn instantiate(prefab: &Prefab, position: Option<Vec3>) -> Self {
+        let entities = prefab.instance(position);
+        let entity_types = entities.iter().map(default_entity_type).collect();
+        Self {
+            description: format!("Instantiate '{}'", prefab.name),
+            roots: entities.first().map(|data| data.handle).into_iter().collect(),
+            entities,
+            entity_types,
+        }
+    }
+
+    /// Create a command that spawns several subtrees as one step
+    ///
+    /// Each subtree lists its root first, paired with the editor type of every entity.
+    pub fn new(description: &str, subtrees: Vec<Vec<(EntityData, EntityType)>>) -> Self {
+        let roots = subtrees.iter().filter_map(|subtree| subtree.first().map(|(data, _)| data.handle)).collect();
+        let (entities, entity_types) = subtrees.into_iter().flatten().unzip();
+        Self {
+            description: description.to_string(),
+            roots,
+            entities,
+            entity_types,
+        }
+    }
+
+    /// Handles of the spawned roots
+    pub fn roots(&self) -> &[EntityHandle] {
+        &self.roots
+    }
+}
+
+impl EditorCommand for SpawnEntitiesCommand {
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        for (data, entity_type) in self.entities.iter().zip(&self.entity_types) {
+            let handle = data.spawn(state.ecs);
+            state.entity_types.insert(handle, *entity_type);
+        }
+
+        state.selection.set_many(self.roots.iter().copied(), self.roots.first().copied());
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        for data in &self.entities {
+            state.ecs.destroy_entity(&data.handle);
+            state.entity_types.remove(&data.handle);
+            state.selection.remove(&data.handle);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Editor type for an entity that doesn't have one yet, based on its components
+pub fn default_entity_type(data: &EntityData) -> EntityType {
+    if data.camera.is_some() {
+        EntityType::Camera
+    } else if let Some(light) = &data.light {
+        EntityType::Light(light.kind)
+    } else if data.text.is_some() {
+        EntityType::Text
+    } else if data.audio_source.is_some() {
+        EntityType::AudioSource
+    } else if let Some(shape) = data.renderable.as_ref().and_then(RenderableDescriptor::primitive_shape) {
+        EntityType::Primitive(shape)
+    } else {
+        EntityType::GameObject
+    }
+}
+
+/// Renames an entity
+pub struct RenameEntityCommand {
+    /// Entity being renamed
+    pub handle: EntityHandle,
+    /// Name before the change
+    pub old_name: String,
+    /// Name after the change
+    pub new_name: String,
+}
+
+impl RenameEntityCommand {
+    /// Create a new rename command
+    pub fn new(handle: EntityHandle, old_name: &str, new_name: &str) -> Self {
+        Self {
+            handle,
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+        }
+    }
+
+    fn set_name(&self, state: &mut EditorState, name: &str) {
+        state.ecs.insert_component(&self.handle, NameComponent::new(name));
+    }
+}
+
+impl EditorCommand for RenameEntityCommand {
+    fn description(&self) -> String {
+        format!("Rename '{}' to '{}'", self.old_name, self.new_name)
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        self.set_name(state, &self.new_name);
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        self.set_name(state, &self.old_name);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Moves an entity under a new parent, or to the root with `None`
+pub struct ReparentEntityCommand {
+    /// Entity being moved
+    pub handle: EntityHandle,
+    /// Parent before the move
+    pub old_parent: Option<EntityHandle>,
+    /// Parent after the move
+    pub new_parent: Option<EntityHandle>,
+    /// Order of the entity's old siblings, including itself, restored on undo
+    pub old_siblings: Vec<EntityHandle>,
+    /// Keep the entity where it is in the world instead of keeping its local transform
+    pub keep_world: bool,
+}
+
+impl ReparentEntityCommand {
+    /// Create a reparent command, returning `None` if the move would create a cycle
+    /// or doesn't change the parent
+    pub fn new(ecs: &EcsManager, handle: EntityHandle, new_parent: Option<EntityHandle>) -> Option<Self> {
+        let old_parent = ecs.get_parent(&handle);
+        if old_parent == new_parent || new_parent.map_or(false, |parent| ecs.is_ancestor(&handle, &parent)) {
+            return None;
+        }
+
+        Some(Self {
+            handle,
+            old_parent,
+            new_parent,
+            old_siblings: ecs.siblings(old_parent),
+            keep_world: false,
+        })
+    }
+
+    /// Keep the entity in place in the world, adjusting its local transform to the new parent
+    pub fn keeping_world(mut self) -> Self {
+        self.keep_world = true;
+        self
+    }
+
+    fn set_parent(&self, state: &mut EditorState, parent: Option<EntityHandle>) {
+        if self.keep_world {
+            state.set_parent_keep_world(self.handle, parent);
+        } else {
+            state.ecs.set_parent(&self.handle, parent);
+        }
+    }
+}
+
+impl EditorCommand for ReparentEntityCommand {
+    fn description(&self) -> String {
+        "Reparent".to_string()
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        self.set_parent(state, self.new_parent);
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        self.set_parent(state, self.old_parent);
+        state.ecs.set_sibling_order(&self.old_siblings);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Moves entities under a new empty parent at their center, without moving them in the world
+pub struct GroupEntitiesCommand {
+    /// The empty parent, kept across undo/redo
+    pub group: EntityHandle,
+    /// Entities moved into the group
+    pub members: Vec<EntityHandle>,
+    /// Parent of the group: the members' parent if they share one, otherwise the root
+    pub parent: Option<EntityHandle>,
+    /// Transform of the group, relative to its parent
+    pub transform: EntityTransform,
+    /// Parent of each member before grouping
+    old_parents: Vec<Option<EntityHandle>>,
+    /// Sibling orders the members were taken from, restored on undo
+    old_siblings: Vec<Vec<EntityHandle>>,
+}
+
+impl GroupEntitiesCommand {
+    /// Name of new groups
+    pub const NAME: &'static str = "Group";
+
+    /// Create a command that groups `handles`, returning `None` if there are none
+    ///
+    /// Entities inside another grouped entity stay with their parent.
+    pub fn new(ecs: &EcsManager, handles: &[EntityHandle]) -> Option<Self> {
+        let members: Vec<EntityHandle> = handles.iter()
+            .filter(|handle| ecs.get_entity(handle).is_some())
+            .filter(|handle| !handles.iter().any(|other| other != *handle && ecs.is_ancestor(other, handle)))
+            .copied()
+            .collect();
+        let first = members.first()?;
+
+        let positions: Vec<Vec3> = members.iter()
+            .filter_map(|handle| ecs.world_transform(handle))
+            .map(|matrix| matrix.w_axis.truncate())
+            .collect();
+        let center = positions.iter().copied().sum::<Vec3>() / positions.len().max(1) as f32;
+
+        let first_parent = ecs.get_parent(first);
+        let parent = first_parent.filter(|_| members.iter().all(|handle| ecs.get_parent(handle) == first_parent));
+        let local_center = match parent.and_then(|parent| ecs.world_transform(&parent)) {
+            Some(parent_matrix) => parent_matrix.inverse().transform_point3(center),
+            None => center,
+        };
+
+        Some(Self {
+            group: EntityHandle::generate(),
+            members,
+            parent,
+            transform: EntityTransform::new(local_center.to_array(), [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]),
+            old_parents: Vec::new(),
+            old_siblings: Vec::new(),
+        })
+    }
+}
+
+impl EditorCommand for GroupEntitiesCommand {
+    fn description(&self) -> String {
+        format!("Group {} Entities", self.members.len())
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        self.old_parents = self.members.iter().map(|handle| state.ecs.get_parent(handle)).collect();
+        let mut parents = Vec::new();
+        for parent in &self.old_parents {
+            if !parents.contains(parent) {
+                parents.push(*parent);
+            }
+        }
+        self.old_siblings = parents.into_iter().map(|parent| state.ecs.siblings(parent)).collect();
+
+        spawn_entity_with_handle(state.ecs, self.group, Self::NAME, EntityType::GameObject, self.transform.to_component());
+        if self.parent.is_some() {
+            state.ecs.set_parent(&self.group, self.parent);
+        }
+        state.entity_types.insert(self.group, EntityType::GameObject);
+
+        for handle in &self.members {
+            state.set_parent_keep_world(*handle, Some(self.group));
+        }
+        state.selection.set(Some(self.group));
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        for (handle, parent) in self.members.iter().zip(&self.old_parents).rev() {
+            state.set_parent_keep_world(*handle, *parent);
+        }
+        for siblings in &self.old_siblings {
+            state.ecs.set_sibling_order(siblings);
+        }
+
+        state.ecs.destroy_entity(&self.group);
+        state.entity_types.remove(&self.group);
+        state.selection.set_many(self.members.iter().copied(), self.members.first().copied());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A group taken apart by `UngroupEntitiesCommand`
+struct UngroupedEntity {
+    /// The deleted group
+    group: DeletedEntity,
+    /// Its children, first to last
+    children: Vec<EntityHandle>,
+    /// The group and its siblings before it was deleted, first to last
+    siblings: Vec<EntityHandle>,
+}
+
+/// Moves the children of groups to the groups' parents and deletes the groups,
+/// without moving the children in the world
+pub struct UngroupEntitiesCommand {
+    /// Groups being taken apart
+    pub groups: Vec<EntityHandle>,
+    /// Snapshots taken when the command was applied
+    ungrouped: Vec<UngroupedEntity>,
+}
+
+impl UngroupEntitiesCommand {
+    /// Create a command that ungroups the entities among `handles` with children,
+    /// returning `None` if none have any
+    pub fn new(ecs: &EcsManager, handles: &[EntityHandle]) -> Option<Self> {
+        let groups: Vec<EntityHandle> = handles.iter()
+            .filter(|handle| !ecs.children(handle).is_empty())
+            .copied()
+            .collect();
+        (!groups.is_empty()).then_some(Self { groups, ungrouped: Vec::new() })
+    }
+}
+
+impl EditorCommand for UngroupEntitiesCommand {
+    fn description(&self) -> String {
+        match self.groups.len() {
+            1 => "Ungroup".to_string(),
+            count => format!("Ungroup {} Entities", count),
+        }
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        self.ungrouped.clear();
+        let mut released = Vec::new();
+        for group in &self.groups {
+            let parent = state.ecs.get_parent(group);
+            let siblings = state.ecs.siblings(parent);
+            let children = state.ecs.children(group);
+            for child in &children {
+                state.set_parent_keep_world(*child, parent);
+            }
+
+            let Some(data) = EntityData::capture(state.ecs, group) else {
+                continue;
+            };
+            self.ungrouped.push(UngroupedEntity {
+                group: DeletedEntity { data, entity_type: state.entity_types.remove(group) },
+                children: children.clone(),
+                siblings,
+            });
+            state.ecs.destroy_entity(group);
+            released.extend(children);
+        }
+        state.selection.set_many(released.iter().copied(), released.first().copied());
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        for ungrouped in self.ungrouped.iter().rev() {
+            let group = ungrouped.group.data.spawn(state.ecs);
+            if let Some(entity_type) = ungrouped.group.entity_type {
+                state.entity_types.insert(group, entity_type);
+            }
+
+            for child in &ungrouped.children {
+                state.set_parent_keep_world(*child, Some(group));
+            }
+            state.ecs.set_sibling_order(&ungrouped.children);
+            state.ecs.set_sibling_order(&ungrouped.siblings);
+        }
+        state.selection.set_many(self.groups.iter().copied(), self.groups.first().copied());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Changes the order of the children of one parent
+pub struct ReorderSiblingsCommand {
+    /// Siblings before the change, first to last
+    pub before: Vec<EntityHandle>,
+    /// Siblings after the change, first to last
+    pub after: Vec<EntityHandle>,
+}
+
+impl ReorderSiblingsCommand {
+    /// Create a command that moves an entity `offset` places among its siblings,
+    /// returning `None` if it's already first or last
+    pub fn new(ecs: &EcsManager, handle: EntityHandle, offset: isize) -> Option<Self> {
+        let after = ecs.moved_sibling_order(&handle, offset)?;
+        Some(Self {
+            before: ecs.siblings(ecs.get_parent(&handle)),
+            after,
+        })
+    }
+}
+
+impl EditorCommand for ReorderSiblingsCommand {
+    fn description(&self) -> String {
+        "Reorder".to_string()
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        state.ecs.set_sibling_order(&self.after);
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        state.ecs.set_sibling_order(&self.before);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Which part of a transform an edit touched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformField {
+    /// Position only
+    Position,
+    /// Rotation only
+    Rotation,
+    /// Scale only
+    Scale,
+    /// More than one part at once (e.g. a reset)
+    All,
+}
+
+impl TransformField {
+    /// Work out which field changed between two transforms, or `None` if they match
+    pub fn between(before: &EntityTransform, after: &EntityTransform) -> Option<Self> {
+        let changed = [
+            (before.position != after.position, TransformField::Position),
+            (before.rotation != after.rotation, TransformField::Rotation),
+            (before.scale != after.scale, TransformField::Scale),
+        ];
+
+        let mut fields = changed.iter().filter(|(changed, _)| *changed).map(|(_, field)| *field);
+        match (fields.next(), fields.next()) {
+            (None, _) => None,
+            (Some(field), None) => Some(field),
+            (Some(_), Some(_)) => Some(TransformField::All),
+        }
+    }
+}
+
+/// The transform of one entity before and after an edit
+#[derive(Clone)]
+pub struct TransformChange {
+    /// Entity being transformed
+    pub handle: EntityHandle,
+    /// Transform before the edit
+    pub before: EntityTransform,
+    /// Transform after the edit
+    pub after: EntityTransform,
+}
+
+/// Changes the transform of one or more entities, from the inspector or a scene view gizmo
+pub struct TransformCommand {
+    /// Entities being transformed
+    pub changes: Vec<TransformChange>,
+    /// Which part of the transform changed
+    pub field: TransformField,
+    /// When the most recent edit folded into this command happened
+    pub edited_at: Instant,
+}
+
+impl TransformCommand {
+    /// Create a transform command, returning `None` if nothing changed
+    pub fn new(handle: EntityHandle, before: EntityTransform, after: EntityTransform) -> Option<Self> {
+        Self::new_many(vec![TransformChange { handle, before, after }])
+    }
+
+    /// Create a command that transforms several entities as one step, returning `None` if nothing changed
+    pub fn new_many(changes: Vec<TransformChange>) -> Option<Self> {
+        let mut field = None;
+        let mut changed = Vec::with_capacity(changes.len());
+        for change in changes {
+            let Some(change_field) = TransformField::between(&change.before, &change.after) else {
+                continue;
+            };
+            field = match field {
+                Some(field) if field != change_field => Some(TransformField::All),
+                _ => Some(change_field),
+            };
+            changed.push(change);
+        }
+
+        Some(Self {
+            changes: changed,
+            field: field?,
+            edited_at: Instant::now(),
+        })
+    }
+}
+
+impl EditorCommand for TransformCommand {
+    fn description(&self) -> String {
+        let verb = match self.field {
+            TransformField::Position => "Move",
+            TransformField::Rotation => "Rotate",
+            TransformField::Scale => "Scale",
+            TransformField::All => "Transform",
+        };
+        match self.changes.len() {
+            1 => verb.to_string(),
+            count => format!("{} {} Entities", verb, count),
+        }
+    }
+
+    fn apply(&mut self, state: &mut EditorState) {
+        for change in &self.changes {
+            state.set_transform(change.handle, &change.after);
+        }
+    }
+
+    fn revert(&mut self, state: &mut EditorState) {
+        for change in &self.changes {
+            state.set_transform(change.handle, &change.before);
+        }
+    }
+
+    fn merge(&mut self, next: &dyn EditorCommand) -> bool {
+        let next = match next.as_any().downcast_ref::<TransformCommand>() {
+            Some(next) => next,
+            None => return false,
+        };
+
+        let same_entities = next.changes.len() == self.changes.len()
+            && next.changes.iter().zip(&self.changes).all(|(a, b)| a.handle == b.handle);
+        if !same_entities
+            || next.field != self.field
+            || self.field == TransformField::All
+            || next.edited_at.duration_since(self.edited_at) > COALESCE_WINDOW {
+            return false;
+        }
+
+        for (change, next) in self.changes.iter_mut().zip(&next.changes) {
+            change.after = next.after.clone();
+        }
+        self.edited_at = next.edited_at;
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Mat4, Quat};
+    use crate::ecs::Transform3DComponent;
+
+    fn spawn(ecs: &mut EcsManager, name: &str, position: Vec3, rotation: Quat, scale: Vec3) -> EntityHandle {
+        ecs.create_entity((NameComponent::new(name), Transform3DComponent::new(position, rotation, scale)))
+    }
+
+    /// A rotated, scaled parent with two children, and one entity at the root
+    fn scene() -> (EcsManager, EntityHandle, Vec<EntityHandle>) {
+        let mut ecs = EcsManager::new();
+        let parent = spawn(&mut ecs, "Parent", Vec3::new(1.0, 2.0, 3.0), Quat::from_rotation_y(0.7), Vec3::splat(2.0));
+        let a = spawn(&mut ecs, "A", Vec3::new(1.0, 0.0, 0.0), Quat::from_rotation_x(0.3), Vec3::new(1.0, 2.0, 1.0));
+        let b = spawn(&mut ecs, "B", Vec3::new(-2.0, 1.0, 0.5), Quat::IDENTITY, Vec3::ONE);
+        let c = spawn(&mut ecs, "C", Vec3::new(4.0, -1.0, 2.0), Quat::from_rotation_z(-1.1), Vec3::splat(0.5));
+        ecs.set_parent(&a, Some(parent));
+        ecs.set_parent(&b, Some(parent));
+        (ecs, parent, vec![a, b, c])
+    }
+
+    fn world_transforms(ecs: &EcsManager, handles: &[EntityHandle]) -> Vec<Mat4> {
+        handles.iter().map(|handle| ecs.world_transform(handle).unwrap()).collect()
+    }
+
+    fn assert_same_world(ecs: &EcsManager, handles: &[EntityHandle], expected: &[Mat4]) {
+        for (actual, expected) in world_transforms(ecs, handles).iter().zip(expected) {
+            assert!(actual.abs_diff_eq(*expected, 1e-5), "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn grouping_keeps_world_transforms() {
+        let (mut ecs, parent, members) = scene();
+        let before = world_transforms(&ecs, &members);
+        let mut entity_types = HashMap::new();
+        let mut selection = Selection::new();
+        let mut state = EditorState { ecs: &mut ecs, entity_types: &mut entity_types, selection: &mut selection };
+
+        let mut command = GroupEntitiesCommand::new(state.ecs, &members).unwrap();
+        assert_eq!(command.parent, None);
+        command.apply(&mut state);
+        for member in &members {
+            assert_eq!(state.ecs.get_parent(member), Some(command.group));
+        }
+        assert_same_world(state.ecs, &members, &before);
+
+        command.revert(&mut state);
+        assert!(state.ecs.get_entity(&command.group).is_none());
+        assert_eq!(state.ecs.get_parent(&members[0]), Some(parent));
+        assert_eq!(state.ecs.get_parent(&members[2]), None);
+        assert_same_world(state.ecs, &members, &before);
+    }
+
+    #[test]
+    fn group_under_shared_parent_is_centered_on_members() {
+        let (mut ecs, parent, members) = scene();
+        let members = &members[..2];
+        let before = world_transforms(&ecs, members);
+        let mut entity_types = HashMap::new();
+        let mut selection = Selection::new();
+        let mut state = EditorState { ecs: &mut ecs, entity_types: &mut entity_types, selection: &mut selection };
+
+        let mut command = GroupEntitiesCommand::new(state.ecs, members).unwrap();
+        command.apply(&mut state);
+        assert_eq!(state.ecs.get_parent(&command.group), Some(parent));
+        let center = (before[0].w_axis + before[1].w_axis).truncate() / 2.0;
+        let group_position = state.ecs.world_transform(&command.group).unwrap().w_axis.truncate();
+        assert!(group_position.abs_diff_eq(center, 1e-5));
+        assert_same_world(state.ecs, members, &before);
+    }
+
+    #[test]
+    fn ungrouping_keeps_world_transforms() {
+        let (mut ecs, parent, members) = scene();
+        let children = &members[..2];
+        let before = world_transforms(&ecs, children);
+        let mut entity_types = HashMap::new();
+        let mut selection = Selection::new();
+        let mut state = EditorState { ecs: &mut ecs, entity_types: &mut entity_types, selection: &mut selection };
+
+        let mut command = UngroupEntitiesCommand::new(state.ecs, &[parent]).unwrap();
+        command.apply(&mut state);
+        assert!(state.ecs.get_entity(&parent).is_none());
+        for child in children {
+            assert_eq!(state.ecs.get_parent(child), None);
+        }
+        assert_same_world(state.ecs, children, &before);
+
+        command.revert(&mut state);
+        assert_eq!(state.ecs.children(&parent), children.to_vec());
+        assert_same_world(state.ecs, children, &before);
+    }
+}
@@ -1,20 +1,45 @@
-use egui::{Context, Ui, ScrollArea, RichText, Color32, Vec2, Frame, Rect, Stroke};
+use egui::{Context, Ui, ScrollArea, RichText, Color32, Vec2, Frame, Rect};
+use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
-use crate::ui::editor::ui_components::{EntityComponent, ComponentType, EntityTransform};
-use std::sync::{Arc, Mutex};
+use crate::ui::editor::clipboard::CopiedComponent;
+use crate::ui::editor::ui_components::{EntityTransform, Selection};
+use crate::ui::editor::component_registry::ComponentRegistry;
+use crate::ecs::{EcsManager, EntityHandle, CameraComponent, Transform3DComponent};
+use crate::ui::editor::history::{EditorCommand, RenameEntityCommand, ReparentEntityCommand, TransformChange, TransformCommand};
+
+/// Clipboard name of the transform, which isn't a registered component
+const TRANSFORM_COMPONENT_NAME: &str = "Transform";
+
+/// Transform values as copied by the inspector
+#[derive(Serialize, Deserialize)]
+struct TransformValues {
+    position: [f32; 3],
+    rotation: [f32; 3],
+    scale: [f32; 3],
+}
 
 /// Inspector panel for editing entity properties
 pub struct InspectorPanel {
     /// Component expanded states
     pub component_expanded: HashMap<String, bool>,
-    /// Entity transforms - shared between inspector and scene view
-    pub entity_transforms: Arc<Mutex<HashMap<u32, EntityTransform>>>,
+    /// Transform last shown, so typed Euler angles survive the round trip through the component's quaternion
+    shown_transform: Option<(EntityHandle, EntityTransform)>,
     /// Show add component menu
     pub show_add_component_menu: bool,
     /// Add component search text
     pub add_component_search: String,
-    /// Dirty flag to track changes
+    /// Set when the inspector changed the world, cleared by the editor
     pub dirty: bool,
+    /// Name being edited in the header
+    pub name_buffer: String,
+    /// Whether the name field has keyboard focus
+    pub name_edit_focused: bool,
+    /// Commands queued this frame, executed by the editor
+    pub pending_commands: Vec<Box<dyn EditorCommand>>,
+    /// Component types the inspector can show and add
+    pub component_registry: ComponentRegistry,
+    /// Component values copied from a component header menu
+    pub component_clipboard: Option<CopiedComponent>,
 }
 
 impl InspectorPanel {
@@ -22,38 +47,58 @@ impl InspectorPanel {
     pub fn new() -> Self {
         Self {
             component_expanded: HashMap::new(),
-            entity_transforms: Arc::new(Mutex::new(HashMap::new())),
+            shown_transform: None,
             show_add_component_menu: false,
             add_component_search: String::new(),
             dirty: false,
+            name_buffer: String::new(),
+            name_edit_focused: false,
+            pending_commands: Vec::new(),
+            component_registry: ComponentRegistry::with_builtin(),
+            component_clipboard: None,
         }
     }
     
-    /// Set entity transforms
-    pub fn set_entity_transforms(&mut self, entity_transforms: Arc<Mutex<HashMap<u32, EntityTransform>>>) {
-        self.entity_transforms = entity_transforms;
-    }
-    
-    /// Get entity transforms
-    pub fn get_entity_transforms(&self) -> Arc<Mutex<HashMap<u32, EntityTransform>>> {
-        self.entity_transforms.clone()
-    }
-    
     /// Render the inspector panel
-    pub fn render(&mut self, ui: &mut Ui, selected_entity: Option<u32>, entity_names: &HashMap<u32, String>, log_info: &mut dyn FnMut(&str)) {
+    pub fn render(&mut self, ui: &mut Ui, ecs: &mut EcsManager, selection: &Selection, entity_names: &HashMap<EntityHandle, String>, log_info: &mut dyn FnMut(&str)) {
         ui.vertical(|ui| {
             ui.heading("Inspector");
             ui.separator();
             
-            if let Some(entity_id) = selected_entity {
+            if selection.len() > 1 {
+                self.render_multiple_selection(ui, ecs, selection, log_info);
+            } else if let Some(entity_id) = selection.primary() {
                 if let Some(name) = entity_names.get(&entity_id) {
                     // Entity header
                     ui.horizontal(|ui| {
-                        ui.heading(name);
+                        let mut active = ecs.is_active(&entity_id);
+                        if ui.checkbox(&mut active, "").on_hover_text("Active").changed() {
+                            ecs.set_active(&entity_id, active);
+                            self.dirty = true;
+                            log_info(&format!("{} '{}'", if active { "Activated" } else { "Deactivated" }, name));
+                        }
+                        
+                        if !self.name_edit_focused {
+                            self.name_buffer = name.clone();
+                        }
+                        
+                        let name_response = ui.add(egui::TextEdit::singleline(&mut self.name_buffer)
+                            .font(egui::TextStyle::Heading)
+                            .desired_width(ui.available_width() - 120.0));
+                        self.name_edit_focused = name_response.has_focus();
+                        
+                        if name_response.lost_focus() {
+                            let new_name = self.name_buffer.trim();
+                            if !new_name.is_empty() && new_name != name.as_str() {
+                                self.pending_commands.push(Box::new(RenameEntityCommand::new(entity_id, name, new_name)));
+                                log_info(&format!("Renamed '{}' to '{}'", name, new_name));
+                            }
+                        }
+                        
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             if ui.button("Add Component").clicked() {
                                 self.show_add_component_menu = true;
-                                log_info(&format!("Add component menu opened for entity {}", entity_id));
+                                log_info(&format!("Add component menu opened for entity {}", entity_id.short_id()));
                             }
                         });
                     });
@@ -62,21 +107,30 @@ impl InspectorPanel {
                     
                     // Render add component menu if opened
                     if self.show_add_component_menu {
-                        self.render_add_component_menu(ui, entity_id, log_info);
+                        self.render_add_component_menu(ui, ecs, entity_id, log_info);
                     }
                     
-                    // Always show Transform component
-                    self.render_transform_component(ui, entity_id, log_info);
+                    self.render_parent_field(ui, ecs, entity_id, entity_names, log_info);
+                    ui.separator();
                     
-                    // Render other components
-                    let components = self.get_entity_components(entity_id);
+                    let is_camera = ecs.get_component::<CameraComponent>(&entity_id).is_some();
                     
-                    for component in components {
-                        self.render_component(ui, &component, entity_id, log_info);
-                    }
+                    // Always show Transform component
+                    self.render_transform_component(ui, ecs, entity_id, &[], is_camera, log_info);
                     
-                    // Reset dirty flag
-                    self.dirty = false;
+                    // Render the components the entity actually has
+                    if let Some(entity) = ecs.get_entity(&entity_id) {
+                        let present: Vec<usize> = (0..self.component_registry.registrations().len())
+                            .filter(|&i| self.component_registry.registrations()[i].is_present(ecs.world(), entity))
+                            .collect();
+                        
+                        for i in present {
+                            if self.render_component(ui, i, ecs, entity, log_info) {
+                                self.component_registry.registrations()[i].remove(ecs.world_mut(), entity);
+                                self.dirty = true;
+                            }
+                        }
+                    }
                 } else {
                     ui.label("Entity name not found");
                 }
@@ -86,78 +140,129 @@ impl InspectorPanel {
         });
     }
     
-    /// Get components for an entity
-    fn get_entity_components(&self, entity_id: u32) -> Vec<EntityComponent> {
-        // For demo, return some mock components based on entity ID
-        // In a real implementation, this would query the ECS
-        let mut components = Vec::new();
+    /// Render the entity's parent with a picker to move it under another entity
+    ///
+    /// The entity keeps its place in the world. Picking one of its own descendants
+    /// is rejected with a warning.
+    fn render_parent_field(&mut self, ui: &mut Ui, ecs: &EcsManager, entity_id: EntityHandle, entity_names: &HashMap<EntityHandle, String>, log_info: &mut dyn FnMut(&str)) {
+        let current = ecs.get_parent(&entity_id);
+        let mut chosen = current;
+        
+        ui.horizontal(|ui| {
+            ui.label("Parent");
+            
+            let mut candidates: Vec<(EntityHandle, &String)> = entity_names.iter()
+                .filter(|(handle, _)| **handle != entity_id)
+                .map(|(handle, name)| (*handle, name))
+                .collect();
+            candidates.sort_by(|a, b| a.1.cmp(b.1).then_with(|| a.0.short_id().cmp(&b.0.short_id())));
+            
+            let selected_text = current
+                .and_then(|parent| entity_names.get(&parent).cloned())
+                .unwrap_or_else(|| "None".to_string());
+            egui::ComboBox::from_id_source("inspector_parent")
+                .selected_text(selected_text)
+                .width(ui.available_width() - 30.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut chosen, None, "None");
+                    for (handle, name) in candidates {
+                        ui.selectable_value(&mut chosen, Some(handle), name.as_str());
+                    }
+                });
+            
+            if ui.add_enabled(current.is_some(), egui::Button::new("✖").small())
+                .on_hover_text("Make this a root entity")
+                .clicked() {
+                chosen = None;
+            }
+        });
         
-        // Even IDs get a Camera component
-        if entity_id % 2 == 0 {
-            components.push(EntityComponent {
-                name: "Camera".to_string(),
-                component_type: ComponentType::Camera,
-                removable: true,
-            });
+        if chosen == current {
+            return;
         }
         
-        // IDs divisible by 3 get a Light component
-        if entity_id % 3 == 0 {
-            components.push(EntityComponent {
-                name: "Light".to_string(),
-                component_type: ComponentType::Light,
-                removable: true,
-            });
+        let name = entity_names.get(&entity_id).cloned().unwrap_or_default();
+        if let Some(parent) = chosen {
+            if ecs.is_ancestor(&entity_id, &parent) {
+                let parent_name = entity_names.get(&parent).cloned().unwrap_or_default();
+                log::warn!("Can't make '{}' a child of '{}', which is one of its own children", name, parent_name);
+                return;
+            }
         }
         
-        // IDs divisible by 5 get a SpriteRenderer component
-        if entity_id % 5 == 0 {
-            components.push(EntityComponent {
-                name: "Sprite Renderer".to_string(),
-                component_type: ComponentType::SpriteRenderer,
-                removable: true,
-            });
+        if let Some(command) = ReparentEntityCommand::new(ecs, entity_id, chosen) {
+            self.pending_commands.push(Box::new(command.keeping_world()));
+            match chosen.and_then(|parent| entity_names.get(&parent)) {
+                Some(parent_name) => log_info(&format!("Moved '{}' to be a child of '{}'", name, parent_name)),
+                None => log_info(&format!("Moved '{}' to the root", name)),
+            }
         }
+    }
+    
+    /// Render the shared transform of several selected entities
+    ///
+    /// Shows the primary entity's values, edits move every selected entity by the same amount.
+    fn render_multiple_selection(&mut self, ui: &mut Ui, ecs: &mut EcsManager, selection: &Selection, log_info: &mut dyn FnMut(&str)) {
+        let Some(primary) = selection.primary() else {
+            return;
+        };
         
-        components
+        ui.label(RichText::new(format!("{} objects selected", selection.len())).heading());
+        ui.separator();
+        
+        let others: Vec<EntityHandle> = selection.iter().copied().filter(|id| *id != primary).collect();
+        self.render_transform_component(ui, ecs, primary, &others, false, log_info);
     }
     
-    /// Render a component in the inspector
-    fn render_component(&mut self, ui: &mut Ui, component: &EntityComponent, _entity_id: u32, log_info: &mut dyn FnMut(&str)) {
-        let component_id = format!("component_{}", component.name);
+    /// Render a registered component the entity has
+    ///
+    /// Returns true if the component should be removed.
+    fn render_component(&mut self, ui: &mut Ui, index: usize, ecs: &mut EcsManager, entity: hecs::Entity, log_info: &mut dyn FnMut(&str)) -> bool {
+        let registration = &self.component_registry.registrations()[index];
+        let component_id = format!("component_{}", registration.name);
         let is_expanded = *self.component_expanded.entry(component_id.clone()).or_insert(true);
+        let can_paste = self.clipboard_holds(registration.name);
+        let mut remove = false;
+        let mut copy = false;
+        let mut paste = false;
         
         // Unity-style component header
         let header_color = Color32::from_rgb(65, 65, 65);
-        let header_hovered = Color32::from_rgb(75, 75, 75);
         
         Frame::none()
             .fill(header_color)
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    // Component enabled checkbox
-                    let mut is_active = true;
-                    ui.checkbox(&mut is_active, "");
+                    if let Some(mut enabled) = registration.is_enabled(ecs.world(), entity) {
+                        if ui.checkbox(&mut enabled, "").on_hover_text("Enabled").changed() {
+                            registration.set_enabled(ecs.world(), entity, enabled);
+                            self.dirty = true;
+                        }
+                    }
                     
                     // Component title that can be clicked to expand/collapse
                     let title_resp = ui.add(egui::Label::new(
-                        RichText::new(&component.name).strong()).sense(egui::Sense::click()));
+                        RichText::new(registration.name).strong()).sense(egui::Sense::click()));
                     
                     if title_resp.clicked() {
-                        *self.component_expanded.get_mut(&component_id).unwrap() = !is_expanded;
+                        self.component_expanded.insert(component_id.clone(), !is_expanded);
                     }
                     
-                    // Component menu button
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if component.removable {
-                            if ui.button("✕").clicked() {
-                                log_info(&format!("Remove {} component clicked", component.name));
-                                // In a real implementation, this would remove the component
-                            }
+                        if ui.button("✕").on_hover_text("Remove component").clicked() {
+                            remove = true;
                         }
-                        
-                        if ui.button("⋮").clicked() {
-                            log_info(&format!("{} component menu clicked", component.name));
+                        if registration.can_copy() {
+                            ui.menu_button("⋮", |ui| {
+                                if ui.button("Copy Component").clicked() {
+                                    copy = true;
+                                    ui.close_menu();
+                                }
+                                if ui.add_enabled(can_paste, egui::Button::new("Paste Component Values")).clicked() {
+                                    paste = true;
+                                    ui.close_menu();
+                                }
+                            });
                         }
                     });
                 });
@@ -169,30 +274,128 @@ impl InspectorPanel {
                 .fill(Color32::from_rgb(50, 50, 50))
                 .inner_margin(egui::style::Margin::symmetric(10.0, 5.0))
                 .show(ui, |ui| {
-                    match component.component_type {
-                        ComponentType::Camera => self.render_camera_component(ui),
-                        ComponentType::Light => self.render_light_component(ui),
-                        ComponentType::SpriteRenderer => self.render_sprite_renderer_component(ui),
-                        ComponentType::Rigidbody2D => self.render_rigidbody2d_component(ui),
-                        ComponentType::BoxCollider2D => self.render_box_collider2d_component(ui),
-                        ComponentType::LuaScript => self.render_lua_script_component(ui),
-                        ComponentType::AudioSource => self.render_audio_source_component(ui),
-                        ComponentType::AudioListener => self.render_audio_listener_component(ui),
-                        ComponentType::Transform => {
-                            // Handled separately
-                        },
+                    // Component fields edit the world directly, compare the values to notice changes
+                    let before = registration.copy(ecs.world(), entity);
+                    ui.push_id(&component_id, |ui| {
+                        registration.draw(ui, ecs.world_mut(), entity);
+                    });
+                    if before.is_some() && registration.copy(ecs.world(), entity) != before {
+                        self.dirty = true;
                     }
                 });
         }
+        
+        if copy {
+            if let Some(values) = registration.copy(ecs.world(), entity) {
+                self.component_clipboard = Some(CopiedComponent { name: registration.name.to_string(), values });
+                log_info(&format!("Copied {} component", registration.name));
+            }
+        }
+        if paste {
+            if let Some(copied) = &self.component_clipboard {
+                match registration.paste(ecs.world_mut(), entity, &copied.values) {
+                    Ok(()) => {
+                        self.dirty = true;
+                        log_info(&format!("Pasted {} component values", registration.name));
+                    }
+                    Err(e) => log::error!("Failed to paste {} component values: {}", registration.name, e),
+                }
+            }
+        }
+        if remove {
+            log_info(&format!("Removed {} component", registration.name));
+        }
+        remove
+    }
+    
+    /// Whether the clipboard holds values of the named component
+    fn clipboard_holds(&self, name: &str) -> bool {
+        self.component_clipboard.as_ref().map_or(false, |copied| copied.name == name)
+    }
+    
+    /// The transform of an entity as the inspector shows it
+    ///
+    /// Reuses the last shown transform while the component still matches it,
+    /// so Euler angles aren't re-derived from the quaternion on every frame.
+    fn current_transform(&self, ecs: &EcsManager, entity_id: EntityHandle) -> Option<EntityTransform> {
+        let component = ecs.get_component::<Transform3DComponent>(&entity_id)?;
+        if let Some((handle, shown)) = &self.shown_transform {
+            let shown_component = shown.to_component();
+            if *handle == entity_id
+                && shown_component.position.abs_diff_eq(component.position, 1e-5)
+                && shown_component.rotation.abs_diff_eq(component.rotation, 1e-5)
+                && shown_component.scale.abs_diff_eq(component.scale, 1e-5)
+            {
+                return Some(shown.clone());
+            }
+        }
+        Some(EntityTransform::from_component(&component))
+    }
+    
+    /// Copy the transform shown in the inspector
+    fn copy_transform(&mut self, ecs: &EcsManager, entity_id: EntityHandle) -> bool {
+        let Some(transform) = self.current_transform(ecs, entity_id) else {
+            return false;
+        };
+        let values = TransformValues {
+            position: transform.position,
+            rotation: transform.rotation,
+            scale: transform.scale,
+        };
+        
+        match ron::to_string(&values) {
+            Ok(values) => {
+                self.component_clipboard = Some(CopiedComponent { name: TRANSFORM_COMPONENT_NAME.to_string(), values });
+                true
+            }
+            Err(_) => false,
+        }
+    }
+    
+    /// Paste the copied transform onto every selected entity, as one undoable edit
+    fn paste_transform(&mut self, ecs: &mut EcsManager, entity_id: EntityHandle, others: &[EntityHandle]) -> bool {
+        let Some(copied) = self.component_clipboard.as_ref().filter(|copied| copied.name == TRANSFORM_COMPONENT_NAME) else {
+            return false;
+        };
+        let values: TransformValues = match ron::from_str(&copied.values) {
+            Ok(values) => values,
+            Err(e) => {
+                log::error!("Failed to paste transform values: {}", e);
+                return false;
+            }
+        };
+        
+        let mut changes = Vec::new();
+        for id in std::iter::once(&entity_id).chain(others) {
+            if let Some(before) = EntityTransform::of(ecs, id) {
+                let after = EntityTransform::new(values.position, values.rotation, values.scale);
+                after.write_to(ecs, id);
+                changes.push(TransformChange {
+                    handle: *id,
+                    before,
+                    after,
+                });
+            }
+        }
+        
+        changes.sort_by_key(|change| change.handle.uuid);
+        match TransformCommand::new_many(changes) {
+            Some(command) => {
+                self.pending_commands.push(Box::new(command));
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
     }
     
     /// Render transform component
-    fn render_transform_component(&mut self, ui: &mut Ui, entity_id: u32, log_info: &mut dyn FnMut(&str)) {
-        // Get timestamp saat ini
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs_f64();
+    ///
+    /// Edits are applied to `others` relative to their own transforms.
+    fn render_transform_component(&mut self, ui: &mut Ui, ecs: &mut EcsManager, entity_id: EntityHandle, others: &[EntityHandle], is_camera: bool, log_info: &mut dyn FnMut(&str)) {
+        let can_paste = self.clipboard_holds(TRANSFORM_COMPONENT_NAME);
+        let mut copy = false;
+        let mut paste = false;
         
         // Unity-style component header
         Frame::none()
@@ -209,13 +412,27 @@ impl InspectorPanel {
                     ui.label(RichText::new("Transform").strong());
                     
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("⋮").clicked() {
-                            log_info("Transform component menu clicked");
-                        }
+                        ui.menu_button("⋮", |ui| {
+                            if ui.button("Copy Component").clicked() {
+                                copy = true;
+                                ui.close_menu();
+                            }
+                            if ui.add_enabled(can_paste, egui::Button::new("Paste Component Values")).clicked() {
+                                paste = true;
+                                ui.close_menu();
+                            }
+                        });
                     });
                 });
             });
         
+        if copy && self.copy_transform(ecs, entity_id) {
+            log_info("Copied Transform component");
+        }
+        if paste && self.paste_transform(ecs, entity_id, others) {
+            log_info(&format!("Pasted Transform values to {} entities", others.len() + 1));
+        }
+        
         let component_id = "component_Transform".to_string();
         let is_expanded = *self.component_expanded.entry(component_id).or_insert(true);
         
@@ -224,20 +441,11 @@ impl InspectorPanel {
                 .fill(Color32::from_rgb(50, 50, 50))
                 .inner_margin(egui::style::Margin::symmetric(10.0, 5.0))
                 .show(ui, |ui| {
-                    // Ambil transformasi dari shared state setiap kali render
-                    let mut transform = {
-                        let transforms = self.entity_transforms.lock().unwrap();
-                        transforms.get(&entity_id).cloned().unwrap_or_else(|| {
-                            EntityTransform {
-                                position: [0.0, 0.0, 0.0],
-                                rotation: [0.0, 0.0, 0.0],
-                                scale: [1.0, 1.0, 1.0],
-                                last_update: current_time,
-                            }
-                        })
-                    };
+                    let mut transform = self.current_transform(ecs, entity_id).unwrap_or_default();
                     
+                    let before = transform.clone();
                     let mut changed = false;
+                    let mut reset = false;
                     let available_width = ui.available_width();
                     
                     // Position row with xyz inputs
@@ -261,12 +469,6 @@ impl InspectorPanel {
                             
                             if x_response.changed() {
                                 changed = true;
-                                // Update position langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.position[0] = transform.position[0];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -284,12 +486,6 @@ impl InspectorPanel {
                             
                             if y_response.changed() {
                                 changed = true;
-                                // Update position langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.position[1] = transform.position[1];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -307,12 +503,6 @@ impl InspectorPanel {
                             
                             if z_response.changed() {
                                 changed = true;
-                                // Update position langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.position[2] = transform.position[2];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                     });
@@ -337,12 +527,6 @@ impl InspectorPanel {
                             
                             if x_response.changed() {
                                 changed = true;
-                                // Update rotation langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.rotation[0] = transform.rotation[0];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -359,12 +543,6 @@ impl InspectorPanel {
                             
                             if y_response.changed() {
                                 changed = true;
-                                // Update rotation langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.rotation[1] = transform.rotation[1];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -381,12 +559,6 @@ impl InspectorPanel {
                             
                             if z_response.changed() {
                                 changed = true;
-                                // Update rotation langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.rotation[2] = transform.rotation[2];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                     });
@@ -411,12 +583,6 @@ impl InspectorPanel {
                             
                             if x_response.changed() {
                                 changed = true;
-                                // Update scale langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.scale[0] = transform.scale[0];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -433,12 +599,6 @@ impl InspectorPanel {
                             
                             if y_response.changed() {
                                 changed = true;
-                                // Update scale langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.scale[1] = transform.scale[1];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                         
@@ -455,12 +615,6 @@ impl InspectorPanel {
                             
                             if z_response.changed() {
                                 changed = true;
-                                // Update scale langsung
-                                let mut transforms = self.entity_transforms.lock().unwrap();
-                                if let Some(t) = transforms.get_mut(&entity_id) {
-                                    t.scale[2] = transform.scale[2];
-                                    t.last_update = current_time;
-                                }
                             }
                         });
                     });
@@ -471,472 +625,61 @@ impl InspectorPanel {
                         if ui.button("Reset").clicked() {
                             let mut default_transform = EntityTransform::default();
                             // Set Z ke -10 untuk kamera
-                            if entity_id == 1 {
+                            if is_camera {
                                 default_transform.position[2] = -10.0;
                             }
-                            default_transform.last_update = current_time;
-                            
-                            // Update transform di shared state langsung saat reset
-                            let mut transforms = self.entity_transforms.lock().unwrap();
-                            transforms.insert(entity_id, default_transform.clone());
                             transform = default_transform;
                             
                             changed = true;
+                            reset = true;
                             log_info("Transform reset to default");
                         }
                     });
                     
                     if changed {
                         self.dirty = true;
-                        log_info(&format!("Updated transform for entity {}", entity_id));
-                    }
-                });
-        }
-    }
-    
-    /// Render camera component
-    fn render_camera_component(&self, ui: &mut Ui) {
-        let mut clear_flags = 0;
-        let clear_flag_options = ["Skybox", "Solid Color", "Depth Only", "Don't Clear"];
-        
-        let mut background_color = [0.39, 0.58, 0.93, 1.0];
-        let mut projection = 0;
-        let proj_options = ["Perspective", "Orthographic"];
-        let mut fov = 60.0;
-        let mut near_clip = 0.3;
-        let mut far_clip = 1000.0;
-        let mut orthographic_size = 5.0;
-        let mut hdr = true;
-        let mut allow_msaa = true;
-        let available_width = ui.available_width();
-        
-        // Define field layout function with fixed widths
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.add_sized([120.0, 20.0], egui::Label::new(label));
-                let content_width = available_width - 120.0;
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(content_width, 20.0),
-                    egui::Layout::right_to_left(egui::Align::Center),
-                    |ui| { content(ui); }
-                );
-            });
-        };
-        
-        field_layout(ui, "Clear Flags", Box::new(|ui| {
-            let mut tmp_clear_flags = clear_flags;
-            egui::ComboBox::from_id_source("clear_flags")
-                .selected_text(clear_flag_options[tmp_clear_flags])
-                .show_ui(ui, |ui| {
-                    for (i, &flag) in clear_flag_options.iter().enumerate() {
-                        if ui.selectable_label(tmp_clear_flags == i, flag).clicked() {
-                            tmp_clear_flags = i;
-                        }
-                    }
-                });
-            clear_flags = tmp_clear_flags;
-        }));
-        
-        field_layout(ui, "Background", Box::new(|ui| {
-            ui.color_edit_button_rgba_unmultiplied(&mut background_color);
-        }));
-        
-        // Projection ComboBox
-        {
-            // Clone into a temporary to avoid mutable borrowing issues
-            let mut tmp_projection = projection;
-            field_layout(ui, "Projection", Box::new(move |ui| {
-                egui::ComboBox::from_id_source("projection")
-                    .selected_text(proj_options[tmp_projection])
-                    .show_ui(ui, |ui| {
-                        for (i, &option) in proj_options.iter().enumerate() {
-                            if ui.selectable_label(tmp_projection == i, option).clicked() {
-                                tmp_projection = i;
-                            }
-                        }
-                    });
-            }));
-            projection = tmp_projection;
-        }
-        
-        // Render appropriate fields based on projection type
-        if projection == 0 {
-            field_layout(ui, "Field of View", Box::new(|ui| {
-                ui.add(egui::Slider::new(&mut fov, 1.0..=179.0).suffix("°").fixed_decimals(1));
-            }));
-        } else {
-            field_layout(ui, "Size", Box::new(|ui| {
-                ui.add(egui::DragValue::new(&mut orthographic_size).speed(0.1).fixed_decimals(2));
-            }));
-        }
-        
-        field_layout(ui, "Clipping Planes", Box::new(|ui| {
-            ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Near");
-                    ui.add(egui::DragValue::new(&mut near_clip).speed(0.01).fixed_decimals(2));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Far");
-                    ui.add(egui::DragValue::new(&mut far_clip).speed(1.0).fixed_decimals(1));
-                });
-            });
-        }));
-        
-        field_layout(ui, "HDR", Box::new(|ui| {
-            ui.checkbox(&mut hdr, "");
-        }));
-        
-        field_layout(ui, "Allow MSAA", Box::new(|ui| {
-            ui.checkbox(&mut allow_msaa, "");
-        }));
-    }
-    
-    /// Render light component
-    fn render_light_component(&self, ui: &mut Ui) {
-        let mut light_type = 0;
-        let light_types = ["Directional", "Point", "Spot", "Area"];
-        
-        let mut color: [f32; 3] = [1.0, 1.0, 1.0];
-        let mut intensity = 1.0;
-        let mut range = 10.0;
-        let mut shadow_enabled = true;
-        let mut shadow_resolution = 1;
-        let shadow_resolutions = ["Low", "Medium", "High", "Very High"];
-        let available_width = ui.available_width();
-        
-        // Define field layout function with fixed widths
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.add_sized([120.0, 20.0], egui::Label::new(label));
-                let content_width = available_width - 120.0;
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(content_width, 20.0),
-                    egui::Layout::right_to_left(egui::Align::Center),
-                    |ui| { content(ui); }
-                );
-            });
-        };
-        
-        // Light Type ComboBox
-        {
-            let mut tmp_light_type = light_type;
-            field_layout(ui, "Type", Box::new(move |ui| {
-                egui::ComboBox::from_id_source("light_type")
-                    .selected_text(light_types[tmp_light_type])
-                    .show_ui(ui, |ui| {
-                        for (i, &light_name) in light_types.iter().enumerate() {
-                            if ui.selectable_label(tmp_light_type == i, light_name).clicked() {
-                                tmp_light_type = i;
-                            }
-                        }
-                    });
-            }));
-            light_type = tmp_light_type;
-        }
-        
-        field_layout(ui, "Color", Box::new(|ui| {
-            ui.color_edit_button_rgb(&mut color);
-        }));
-        
-        field_layout(ui, "Intensity", Box::new(|ui| {
-            ui.add(egui::Slider::new(&mut intensity, 0.0..=10.0).fixed_decimals(2));
-        }));
-        
-        // Range slider for non-directional lights
-        if light_type != 0 {
-            field_layout(ui, "Range", Box::new(|ui| {
-                ui.add(egui::Slider::new(&mut range, 1.0..=100.0).logarithmic(true).fixed_decimals(1));
-            }));
-        }
-        
-        // Shadow toggles
-        {
-            let mut tmp_shadow_enabled = shadow_enabled;
-            field_layout(ui, "Shadows", Box::new(move |ui| {
-                ui.checkbox(&mut tmp_shadow_enabled, "");
-            }));
-            shadow_enabled = tmp_shadow_enabled;
-        }
-        
-        // Shadow quality settings if shadows are enabled
-        if shadow_enabled {
-            let mut tmp_shadow_resolution = shadow_resolution;
-            field_layout(ui, "Shadow Quality", Box::new(|ui| {
-                egui::ComboBox::from_id_source("shadow_resolution")
-                    .selected_text(shadow_resolutions[tmp_shadow_resolution])
-                    .show_ui(ui, |ui| {
-                        for (i, &res_name) in shadow_resolutions.iter().enumerate() {
-                            if ui.selectable_label(tmp_shadow_resolution == i, res_name).clicked() {
-                                tmp_shadow_resolution = i;
+                        transform.write_to(ecs, &entity_id);
+                        
+                        let mut changes = vec![TransformChange {
+                            handle: entity_id,
+                            before: before.clone(),
+                            after: transform.clone(),
+                        }];
+                        
+                        for id in others {
+                            if let Some(mut other) = EntityTransform::of(ecs, id) {
+                                let other_before = other.clone();
+                                if reset {
+                                    other = EntityTransform::default();
+                                } else {
+                                    other.apply_delta(&before, &transform);
+                                }
+                                other.write_to(ecs, id);
+                                changes.push(TransformChange {
+                                    handle: *id,
+                                    before: other_before,
+                                    after: other,
+                                });
                             }
                         }
-                    });
-            }));
-            shadow_resolution = tmp_shadow_resolution;
-        }
-    }
-    
-    /// Render sprite renderer component
-    fn render_sprite_renderer_component(&self, ui: &mut Ui) {
-        let mut color = [1.0, 1.0, 1.0, 1.0];
-        let mut sorting_layer = 0;
-        let sorting_layers = ["Default", "Background", "Foreground", "UI"];
-        let mut order_in_layer = 0;
-        let mut material = "Default Sprite".to_string();
-        let mut flip_x = false;
-        let mut flip_y = false;
-        let available_width = ui.available_width();
-        
-        // Define field layout function with fixed widths
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.add_sized([120.0, 20.0], egui::Label::new(label));
-                let content_width = available_width - 120.0;
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(content_width, 20.0),
-                    egui::Layout::right_to_left(egui::Align::Center),
-                    |ui| { content(ui); }
-                );
-            });
-        };
-        
-        field_layout(ui, "Sprite", Box::new(|ui| {
-            ui.horizontal(|ui| {
-                // Mock sprite preview
-                let rect = ui.allocate_space(egui::Vec2::new(40.0, 40.0)).1;
-                ui.painter().rect_filled(rect, 3.0, Color32::DARK_GRAY);
-                ui.painter().rect_stroke(rect, 3.0, Stroke::new(1.0, Color32::WHITE));
-                
-                if ui.button("Select").clicked() {
-                    // Would open sprite selector
-                }
-            });
-        }));
-        
-        field_layout(ui, "Color", Box::new(|ui| {
-            ui.color_edit_button_rgba_unmultiplied(&mut color);
-        }));
-        
-        field_layout(ui, "Material", Box::new(|ui| {
-            let text_edit = egui::TextEdit::singleline(&mut material)
-                .desired_width(ui.available_width() - 30.0);
-            ui.add(text_edit);
-        }));
-        
-        field_layout(ui, "Sorting Layer", Box::new(|ui| {
-            let mut tmp_sorting_layer = sorting_layer;
-            egui::ComboBox::from_id_source("sorting_layer")
-                .selected_text(sorting_layers[tmp_sorting_layer])
-                .show_ui(ui, |ui| {
-                    for (i, &layer_name) in sorting_layers.iter().enumerate() {
-                        if ui.selectable_label(tmp_sorting_layer == i, layer_name).clicked() {
-                            tmp_sorting_layer = i;
-                        }
-                    }
-                });
-            sorting_layer = tmp_sorting_layer;
-        }));
-        
-        field_layout(ui, "Order in Layer", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut order_in_layer).speed(1));
-        }));
-        
-        field_layout(ui, "Flip", Box::new(|ui| {
-            ui.checkbox(&mut flip_x, "X");
-            ui.add_space(10.0);
-            ui.checkbox(&mut flip_y, "Y");
-        }));
-    }
-    
-    /// Render rigidbody2d component
-    fn render_rigidbody2d_component(&self, ui: &mut Ui) {
-        let mut body_type = 0;
-        let body_types = ["Dynamic", "Kinematic", "Static"];
-        let mut mass = 1.0;
-        let mut linear_drag = 0.0;
-        let mut angular_drag = 0.05;
-        let mut gravity_scale = 1.0;
-        let mut freeze_rotation = false;
-        let available_width = ui.available_width();
-        
-        // Define field layout function with fixed widths
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.add_sized([120.0, 20.0], egui::Label::new(label));
-                let content_width = available_width - 120.0;
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(content_width, 20.0),
-                    egui::Layout::right_to_left(egui::Align::Center),
-                    |ui| { content(ui); }
-                );
-            });
-        };
-        
-        field_layout(ui, "Body Type", Box::new(|ui| {
-            let mut tmp_body_type = body_type;
-            egui::ComboBox::from_id_source("body_type")
-                .selected_text(body_types[tmp_body_type])
-                .show_ui(ui, |ui| {
-                    for (i, &type_name) in body_types.iter().enumerate() {
-                        if ui.selectable_label(tmp_body_type == i, type_name).clicked() {
-                            tmp_body_type = i;
+                        
+                        changes.sort_by_key(|change| change.handle.uuid);
+                        if let Some(command) = TransformCommand::new_many(changes) {
+                            self.pending_commands.push(Box::new(command));
                         }
+                        log_info(&format!("Updated transform for entity {}", entity_id.short_id()));
                     }
+                    self.shown_transform = Some((entity_id, transform));
                 });
-            body_type = tmp_body_type;
-        }));
-        
-        field_layout(ui, "Mass", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut mass).speed(0.1).clamp_range(0.0001..=1000.0));
-        }));
-        
-        field_layout(ui, "Linear Drag", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut linear_drag).speed(0.01).clamp_range(0.0..=1000.0));
-        }));
-        
-        field_layout(ui, "Angular Drag", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut angular_drag).speed(0.01).clamp_range(0.0..=1000.0));
-        }));
-        
-        field_layout(ui, "Gravity Scale", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut gravity_scale).speed(0.1));
-        }));
-        
-        field_layout(ui, "Freeze Rotation", Box::new(|ui| {
-            ui.checkbox(&mut freeze_rotation, "");
-        }));
-    }
-    
-    /// Render box collider2d component
-    fn render_box_collider2d_component(&self, ui: &mut Ui) {
-        let mut offset = [0.0, 0.0];
-        let mut size = [1.0, 1.0];
-        let mut density = 1.0;
-        let mut is_trigger = false;
-        let mut material = "Default".to_string();
-        
-        // Define field layout function
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.label(label);
-                ui.add_space(ui.available_width() * 0.3 - label.len() as f32 * 7.0);
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    content(ui);
-                });
-            });
-        };
-        
-        field_layout(ui, "Material", Box::new(|ui| {
-            ui.text_edit_singleline(&mut material);
-            if ui.button("⋯").clicked() {
-                // Would open material selector
-            }
-        }));
-        
-        field_layout(ui, "Is Trigger", Box::new(|ui| {
-            ui.checkbox(&mut is_trigger, "");
-        }));
-        
-        field_layout(ui, "Offset", Box::new(|ui| {
-            ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label("X");
-                    ui.add(egui::DragValue::new(&mut offset[0]).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Y");
-                    ui.add(egui::DragValue::new(&mut offset[1]).speed(0.1));
-                });
-            });
-        }));
-        
-        field_layout(ui, "Size", Box::new(|ui| {
-            ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label("X");
-                    ui.add(egui::DragValue::new(&mut size[0]).speed(0.1).clamp_range(0.001..=1000.0));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Y");
-                    ui.add(egui::DragValue::new(&mut size[1]).speed(0.1).clamp_range(0.001..=1000.0));
-                });
-            });
-        }));
-        
-        field_layout(ui, "Density", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut density).speed(0.1));
-        }));
+        }
     }
     
-    /// Render lua script component
-    fn render_lua_script_component(&self, ui: &mut Ui) {
-        // Mock script editor with fields
-        let mut script_path = "Scripts/Player.rs".to_string();
-        
-        // Define field layout function
-        let field_layout = |ui: &mut Ui, label: &str, content: Box<dyn FnOnce(&mut Ui)>| {
-            ui.horizontal(|ui| {
-                ui.label(label);
-                ui.add_space(ui.available_width() * 0.3 - label.len() as f32 * 7.0);
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    content(ui);
-                });
-            });
+    /// Render add component menu
+    fn render_add_component_menu(&mut self, ui: &mut Ui, ecs: &mut EcsManager, entity_id: EntityHandle, log_info: &mut dyn FnMut(&str)) {
+        let Some(entity) = ecs.get_entity(&entity_id) else {
+            return;
         };
         
-        field_layout(ui, "Script", Box::new(|ui| {
-            ui.text_edit_singleline(&mut script_path);
-            if ui.button("Edit").clicked() {
-                // Would open script editor
-            }
-        }));
-        
-        ui.separator();
-        ui.label("Public Variables");
-        
-        // Mock public variables
-        let mut speed = 5.0;
-        let mut jump_force = 10.0;
-        let mut health = 100;
-        let mut player_name = "Player".to_string();
-        let mut is_invincible = false;
-        
-        field_layout(ui, "Speed", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut speed).speed(0.1));
-        }));
-        
-        field_layout(ui, "Jump Force", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut jump_force).speed(0.1));
-        }));
-        
-        field_layout(ui, "Health", Box::new(|ui| {
-            ui.add(egui::DragValue::new(&mut health));
-        }));
-        
-        field_layout(ui, "Player Name", Box::new(|ui| {
-            ui.text_edit_singleline(&mut player_name);
-        }));
-        
-        field_layout(ui, "Is Invincible", Box::new(|ui| {
-            ui.checkbox(&mut is_invincible, "");
-        }));
-    }
-    
-    /// Render audio source component
-    fn render_audio_source_component(&self, ui: &mut Ui) {
-        // Mock audio source component
-        ui.label("Audio Source component properties would be shown here");
-    }
-    
-    /// Render audio listener component
-    fn render_audio_listener_component(&self, ui: &mut Ui) {
-        // Mock audio listener component
-        ui.label("Audio Listener component properties would be shown here");
-    }
-    
-    /// Render add component menu
-    fn render_add_component_menu(&mut self, ui: &mut Ui, _entity_id: u32, log_info: &mut dyn FnMut(&str)) {
         // Unity-like add component dropdown
         Frame::none()
             .fill(Color32::from_rgb(60, 60, 60))
@@ -966,72 +709,123 @@ impl InspectorPanel {
                     ui.separator();
                     
                     ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-                        // Common components section
-                        ui.collapsing("Common", |ui| {
-                            if ui.selectable_label(false, "Rigidbody 2D").clicked() {
-                                log_info("Added Rigidbody 2D component");
-                                self.show_add_component_menu = false;
-                            }
-                            
-                            if ui.selectable_label(false, "Box Collider 2D").clicked() {
-                                log_info("Added Box Collider 2D component");
-                                self.show_add_component_menu = false;
-                            }
-                            
-                            if ui.selectable_label(false, "Sprite Renderer").clicked() {
-                                log_info("Added Sprite Renderer component");
-                                self.show_add_component_menu = false;
-                            }
-                        });
+                        let search = self.add_component_search.to_lowercase();
+                        let registry = &self.component_registry;
+                        let mut added = None;
+                        let mut pasted = None;
                         
-                        // Physics section
-                        ui.collapsing("Physics", |ui| {
-                            if ui.selectable_label(false, "Rigidbody").clicked() {
-                                log_info("Added Rigidbody component");
-                                self.show_add_component_menu = false;
-                            }
-                            
-                            if ui.selectable_label(false, "Box Collider").clicked() {
-                                log_info("Added Box Collider component");
-                                self.show_add_component_menu = false;
-                            }
+                        // A copied component the entity doesn't have yet can be added with its values
+                        let copied = self.component_clipboard.as_ref().and_then(|copied| {
+                            registry.registrations().iter().position(|registration| {
+                                registration.name == copied.name
+                                    && registration.can_copy()
+                                    && !registration.is_present(ecs.world(), entity)
+                            })
                         });
-                        
-                        // Rendering section
-                        ui.collapsing("Rendering", |ui| {
-                            if ui.selectable_label(false, "Camera").clicked() {
-                                log_info("Added Camera component");
-                                self.show_add_component_menu = false;
-                            }
-                            
-                            if ui.selectable_label(false, "Light").clicked() {
-                                log_info("Added Light component");
-                                self.show_add_component_menu = false;
+                        if let Some(i) = copied {
+                            if ui.selectable_label(false, format!("Paste {}", registry.registrations()[i].name)).clicked() {
+                                pasted = Some(i);
                             }
-                        });
+                            ui.separator();
+                        }
                         
-                        // Audio section
-                        ui.collapsing("Audio", |ui| {
-                            if ui.selectable_label(false, "Audio Source").clicked() {
-                                log_info("Added Audio Source component");
-                                self.show_add_component_menu = false;
+                        let searching = !search.is_empty();
+                        let mut any_available = false;
+                        for category in registry.categories() {
+                            let available: Vec<usize> = registry.registrations().iter().enumerate()
+                                .filter(|(_, registration)| registration.category == category && registration.can_add())
+                                .filter(|(_, registration)| !registration.is_present(ecs.world(), entity))
+                                .filter(|(_, registration)| !searching || registration.name.to_lowercase().contains(&search))
+                                .map(|(i, _)| i)
+                                .collect();
+                            
+                            if available.is_empty() {
+                                continue;
                             }
+                            any_available = true;
                             
-                            if ui.selectable_label(false, "Audio Listener").clicked() {
-                                log_info("Added Audio Listener component");
-                                self.show_add_component_menu = false;
+                            // Sections with matches open while searching, without changing
+                            // whether they're open in the unfiltered tree
+                            egui::CollapsingHeader::new(category)
+                                .id_source((category, searching))
+                                .open(searching.then_some(true))
+                                .show(ui, |ui| {
+                                    for i in available {
+                                        if ui.selectable_label(false, registry.registrations()[i].name).clicked() {
+                                            added = Some(i);
+                                        }
+                                    }
+                                });
+                        }
+                        
+                        if searching && !any_available {
+                            ui.label(RichText::new(format!("No components match \"{}\"", self.add_component_search)).weak());
+                        }
+                        
+                        if let Some(i) = added {
+                            let registration = &registry.registrations()[i];
+                            if registration.add(ecs.world_mut(), entity) {
+                                self.dirty = true;
+                                log_info(&format!("Added {} component to entity {}", registration.name, entity_id.short_id()));
                             }
-                        });
+                            self.show_add_component_menu = false;
+                        }
                         
-                        // Scripts section
-                        ui.collapsing("Scripts", |ui| {
-                            if ui.selectable_label(false, "Lua Script").clicked() {
-                                log_info("Added Lua Script component");
-                                self.show_add_component_menu = false;
+                        if let (Some(i), Some(copied)) = (pasted, &self.component_clipboard) {
+                            let registration = &registry.registrations()[i];
+                            match registration.paste(ecs.world_mut(), entity, &copied.values) {
+                                Ok(()) => {
+                                    self.dirty = true;
+                                    log_info(&format!("Pasted {} component to entity {}", registration.name, entity_id.short_id()));
+                                }
+                                Err(e) => log::error!("Failed to paste {} component: {}", registration.name, e),
                             }
-                        });
+                            self.show_add_component_menu = false;
+                        }
                     });
                 });
             });
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3};
+    use crate::ecs::NameComponent;
+    use crate::ui::editor::history::EditorState;
+
+    /// The transform a scene view query sees for an entity
+    fn queried_transform(ecs: &EcsManager, handle: EntityHandle) -> Transform3DComponent {
+        ecs.query::<&Transform3DComponent>().iter()
+            .find(|(queried, _)| *queried == handle)
+            .map(|(_, transform)| transform.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn inspector_edit_is_visible_to_query() {
+        let mut ecs = EcsManager::new();
+        let source = ecs.create_entity((
+            NameComponent::new("Source"),
+            Transform3DComponent::new(Vec3::new(1.0, 2.0, 3.0), Quat::from_rotation_y(0.5), Vec3::splat(2.0)),
+        ));
+        let target = ecs.create_entity((NameComponent::new("Target"), Transform3DComponent::new(Vec3::ZERO, Quat::IDENTITY, Vec3::ONE)));
+
+        let mut inspector = InspectorPanel::new();
+        assert!(inspector.copy_transform(&ecs, source));
+        assert!(inspector.paste_transform(&mut ecs, target, &[]));
+
+        let queried = queried_transform(&ecs, target);
+        assert!(queried.position.abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-5));
+        assert!(queried.rotation.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-5));
+        assert!(queried.scale.abs_diff_eq(Vec3::splat(2.0), 1e-5));
+
+        // Undoing the queued edit writes the same component back
+        let mut command = inspector.pending_commands.pop().unwrap();
+        let mut entity_types = HashMap::new();
+        let mut selection = Selection::new();
+        command.revert(&mut EditorState { ecs: &mut ecs, entity_types: &mut entity_types, selection: &mut selection });
+        assert!(queried_transform(&ecs, target).position.abs_diff_eq(Vec3::ZERO, 1e-5));
+    }
+}
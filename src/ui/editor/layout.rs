@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use crate::utils::config::{config_path, load_config, save_config};
+
+/// File the layout is saved to, in the editor's config directory
+pub const LAYOUT_FILE_NAME: &str = "editor_layout.ron";
+
+/// Which editor panels are shown and how big they are, kept across restarts
+///
+/// Fields missing from a saved layout take their default, so layouts saved by
+/// older versions still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorLayout {
+    pub show_hierarchy: bool,
+    pub show_inspector: bool,
+    /// Project files tab of the bottom panel
+    pub show_project: bool,
+    /// Console tab of the bottom panel
+    pub show_console: bool,
+    /// Width of the hierarchy panel, in points
+    pub hierarchy_width: f32,
+    /// Width of the inspector panel, in points
+    pub inspector_width: f32,
+    /// Height of the bottom panel, in points
+    pub bottom_height: f32,
+    /// Whether the Game view rather than the Scene view fills the space left by the panels
+    pub show_game_view: bool,
+}
+
+impl Default for EditorLayout {
+    fn default() -> Self {
+        Self {
+            show_hierarchy: true,
+            show_inspector: true,
+            show_project: true,
+            show_console: true,
+            hierarchy_width: 300.0,
+            inspector_width: 300.0,
+            bottom_height: 200.0,
+            show_game_view: false,
+        }
+    }
+}
+
+impl EditorLayout {
+    /// Where the layout is saved, `None` if the OS has no config directory
+    pub fn path() -> Option<PathBuf> {
+        config_path(LAYOUT_FILE_NAME)
+    }
+
+    /// The saved layout, or the default one if there's none or it can't be read
+    pub fn load() -> Self {
+        load_config(LAYOUT_FILE_NAME).unwrap_or_default()
+    }
+
+    /// Save the layout to `path()`, creating its folder
+    pub fn save(&self) -> Result<()> {
+        save_config(LAYOUT_FILE_NAME, self)
+    }
+}
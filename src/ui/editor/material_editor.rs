@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+use egui::{Color32, Pos2, Rect, Sense, Shape, Ui, Vec2};
+use glam::Vec3;
+use log::{error, info};
+use crate::assets::{Handle, MaterialAsset, MaterialKind};
+use crate::rendering::BlendMode;
+use crate::ui::editor::component_registry::field;
+use crate::ui::editor::asset_drag;
+use crate::ui::editor::ui_components::ProjectFileType;
+
+/// Side of the material preview, in points
+const PREVIEW_SIZE: f32 = 96.0;
+
+/// Rings and segments of the preview sphere's mesh
+const PREVIEW_RESOLUTION: usize = 24;
+
+/// Inspector fields to pick a material file and edit the material behind `material`
+///
+/// Edits replace the asset behind the handle, so every entity using the material
+/// picks them up; "Save" writes them to `path`. Returns the new path when another
+/// file is picked, the caller applies it with `set_material_path`.
+pub fn material_fields(
+    ui: &mut Ui,
+    id_source: &str,
+    path: Option<&PathBuf>,
+    material: Option<&Handle<MaterialAsset>>,
+) -> Option<Option<PathBuf>> {
+    let mut picked = None;
+    let current = path.map_or_else(String::new, |path| path.to_string_lossy().into_owned());
+    field(ui, "Material", |ui| {
+        if let Some(text) = committed_text_field(ui, id_source, &current, "Materials/material.mat") {
+            picked = Some((!text.is_empty()).then(|| PathBuf::from(text)));
+        }
+        if let Some(dropped) = asset_drag::path_drop_target(ui, ProjectFileType::Material) {
+            picked = Some(Some(dropped));
+        }
+    });
+
+    if let (Some(material), None) = (material, &picked) {
+        ui.indent(id_source, |ui| edit_material(ui, id_source, path, material));
+    }
+    picked
+}
+
+/// Fields of a material, with a live preview
+fn edit_material(ui: &mut Ui, id_source: &str, path: Option<&PathBuf>, material: &Handle<MaterialAsset>) {
+    let original = material.get();
+    let mut edited = (*original).clone();
+
+    field(ui, "Type", |ui| {
+        egui::ComboBox::from_id_source((id_source, "material_kind"))
+            .selected_text(edited.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in MaterialKind::ALL {
+                    ui.selectable_value(&mut edited.kind, kind, kind.label());
+                }
+            });
+    });
+
+    let color_label = match edited.kind {
+        MaterialKind::Lit => "Base Color",
+        MaterialKind::Sprite => "Tint",
+    };
+    field(ui, color_label, |ui| {
+        ui.color_edit_button_rgba_unmultiplied(&mut edited.base_color);
+    });
+
+    let texture = edited.texture.as_ref().map_or_else(String::new, |path| path.to_string_lossy().into_owned());
+    field(ui, "Texture", |ui| {
+        if let Some(text) = committed_text_field(ui, &format!("{}_texture", id_source), &texture, "Relative to the material") {
+            edited.texture = (!text.is_empty()).then(|| PathBuf::from(text));
+        }
+    });
+
+    if edited.kind == MaterialKind::Lit {
+        field(ui, "Metallic", |ui| {
+            ui.add(egui::Slider::new(&mut edited.metallic, 0.0..=1.0));
+        });
+        field(ui, "Roughness", |ui| {
+            ui.add(egui::Slider::new(&mut edited.roughness, 0.0..=1.0));
+        });
+    }
+
+    field(ui, "Blend Mode", |ui| {
+        egui::ComboBox::from_id_source((id_source, "blend_mode"))
+            .selected_text(edited.blend_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in BlendMode::ALL {
+                    ui.selectable_value(&mut edited.blend_mode, mode, mode.label());
+                }
+            });
+    });
+
+    field(ui, "Preview", |ui| draw_preview(ui, &edited));
+
+    field(ui, "", |ui| {
+        if ui.add_enabled(path.is_some(), egui::Button::new("Save Material")).clicked() {
+            if let Some(path) = path {
+                match edited.save(path) {
+                    Ok(()) => info!("Saved material {}", path.display()),
+                    Err(e) => error!("Failed to save material {}: {}", path.display(), e),
+                }
+            }
+        }
+    });
+
+    if edited != *original {
+        material.replace(edited);
+    }
+}
+
+/// Single line text field that only returns its text once editing ends
+///
+/// The text being typed is kept in egui's memory, so half-typed paths aren't loaded.
+fn committed_text_field(ui: &mut Ui, id_source: &str, current: &str, hint: &str) -> Option<String> {
+    let id = ui.make_persistent_id(id_source);
+    let mut text = ui.data_mut(|data| data.get_temp::<String>(id)).unwrap_or_else(|| current.to_string());
+    let response = ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint));
+    if response.lost_focus() {
+        ui.data_mut(|data| data.remove::<String>(id));
+        return (text != current).then_some(text);
+    }
+    if response.has_focus() {
+        ui.data_mut(|data| data.insert_temp(id, text));
+    }
+    None
+}
+
+/// Shaded sphere for lit materials, a tinted quad over a checkerboard for sprites
+///
+/// The preview only shows the colors and settings, not the texture.
+fn draw_preview(ui: &mut Ui, material: &MaterialAsset) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(PREVIEW_SIZE), Sense::hover());
+    let painter = ui.painter_at(rect);
+    match material.kind {
+        MaterialKind::Lit => {
+            painter.rect_filled(rect, 4.0, Color32::from_gray(30));
+            painter.add(preview_sphere(rect, material));
+        }
+        MaterialKind::Sprite => {
+            // The checkerboard shows through transparent tints
+            let cell = PREVIEW_SIZE / 8.0;
+            for y in 0..8 {
+                for x in 0..8 {
+                    let gray = if (x + y) % 2 == 0 { 90 } else { 140 };
+                    let min = rect.min + Vec2::new(x as f32, y as f32) * cell;
+                    painter.rect_filled(Rect::from_min_size(min, Vec2::splat(cell)), 0.0, Color32::from_gray(gray));
+                }
+            }
+            let [r, g, b, a] = material.base_color;
+            let tint = Color32::from_rgba_unmultiplied(to_byte(r), to_byte(g), to_byte(b), to_byte(a));
+            painter.rect_filled(rect.shrink(PREVIEW_SIZE * 0.15), 0.0, tint);
+        }
+    }
+}
+
+/// Sphere lit by one light from the top left, shaded per vertex like the lit pass
+fn preview_sphere(rect: Rect, material: &MaterialAsset) -> Shape {
+    let center = rect.center();
+    let radius = rect.width() * 0.42;
+    let albedo = Vec3::new(material.base_color[0], material.base_color[1], material.base_color[2]);
+    let to_light = Vec3::new(-0.5, 0.6, 0.6).normalize();
+    let smoothness = 1.0 - material.roughness.clamp(0.0, 1.0);
+    let metallic = material.metallic.clamp(0.0, 1.0);
+    // Same curve as the lit shader's highlight
+    let shininess = 2.0 + (122.0 - 2.0) * smoothness * smoothness;
+    let specular_color = Vec3::splat(0.5).lerp(albedo, metallic);
+
+    let shade = |normal: Vec3| {
+        let diffuse = normal.dot(to_light).max(0.0);
+        let half_dir = (to_light + Vec3::Z).normalize();
+        let specular = if diffuse > 0.0 {
+            specular_color * normal.dot(half_dir).max(0.0).powf(shininess) * smoothness * 2.0
+        } else {
+            Vec3::ZERO
+        };
+        let color = albedo * 0.1 + albedo * diffuse * (1.0 - metallic) + specular;
+        Color32::from_rgb(to_byte(color.x), to_byte(color.y), to_byte(color.z))
+    };
+
+    // Rings from the center of the visible half out to its silhouette
+    let mut mesh = egui::Mesh::default();
+    for ring in 0..=PREVIEW_RESOLUTION {
+        let polar = ring as f32 / PREVIEW_RESOLUTION as f32 * std::f32::consts::FRAC_PI_2;
+        for segment in 0..=PREVIEW_RESOLUTION {
+            let azimuth = segment as f32 / PREVIEW_RESOLUTION as f32 * std::f32::consts::TAU;
+            let normal = Vec3::new(polar.sin() * azimuth.cos(), polar.sin() * azimuth.sin(), polar.cos());
+            let position = Pos2::new(center.x + normal.x * radius, center.y - normal.y * radius);
+            mesh.colored_vertex(position, shade(normal));
+        }
+    }
+    let row = PREVIEW_RESOLUTION as u32 + 1;
+    for ring in 0..PREVIEW_RESOLUTION as u32 {
+        for segment in 0..PREVIEW_RESOLUTION as u32 {
+            let a = ring * row + segment;
+            let b = a + row;
+            mesh.add_triangle(a, b, a + 1);
+            mesh.add_triangle(a + 1, b, b + 1);
+        }
+    }
+    Shape::mesh(mesh)
+}
+
+/// Linear 0..1 channel as an 8-bit one, as egui expects
+fn to_byte(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -1,19 +1,33 @@
 pub mod ui_components;
 pub mod hierarchy;
 pub mod inspector;
+pub mod component_registry;
 pub mod project;
 pub mod scene_view;
 pub mod game_view;
 pub mod audio_panel;
 pub mod console;
+pub mod console_command;
+pub mod script_editor;
+pub mod material_editor;
 pub mod editor_ui;
+pub mod history;
+pub mod clipboard;
+pub mod layout;
+pub mod asset_drag;
+pub mod game_input;
 
 pub use ui_components::*;
 pub use hierarchy::HierarchyPanel;
 pub use inspector::InspectorPanel;
+pub use component_registry::{ComponentRegistry, ComponentRegistration};
 pub use project::ProjectPanel;
-pub use scene_view::SceneViewPanel;
+pub use scene_view::{SceneViewPanel, PhysicsDebugInfo, CameraGizmo, LightGizmo};
 pub use game_view::GameViewPanel;
 pub use audio_panel::AudioPanel;
-pub use console::ConsolePanel;
-pub use editor_ui::EditorUI; 
\ No newline at end of file
+pub use console::{ConsolePanel, ConsoleLogger, SharedConsoleLogs, MAX_CONSOLE_LOGS};
+pub use console_command::{ConsoleCommand, ConsoleCommandFn, ConsoleCommandRegistry};
+pub use script_editor::ScriptEditorPanel;
+pub use editor_ui::{EditorUI, SceneAction};
+pub use layout::EditorLayout;
+pub use history::{CommandHistory, EditorCommand, EditorState}; 
\ No newline at end of file
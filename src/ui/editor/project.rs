@@ -1,13 +1,23 @@
-use egui::{Context, Ui, ScrollArea, RichText, Color32};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use egui::{Context, Ui, ScrollArea, RichText, Color32, Sense, Response};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::assets::{MaterialAsset, MATERIAL_EXTENSION};
 use crate::ui::editor::ui_components::{ProjectFile, ProjectFileType, ProjectTab};
+use crate::ui::editor::asset_drag::DraggedAsset;
+
+/// Contents of a script created from the project panel
+const SCRIPT_TEMPLATE: &str = "function on_start(entity)\nend\n\nfunction on_update(entity, dt)\nend\n";
 
 /// Project panel for managing project files
 pub struct ProjectPanel {
     /// Active tab for project panel
     pub project_active_tab: ProjectTab,
-    /// Project files
+    /// Top-level files of the project
     pub project_files: Vec<ProjectFile>,
-    /// Current project path
+    /// Selected folder, relative to the project root
     pub current_project_path: Option<String>,
     /// Show rename dialog
     pub show_rename_dialog: bool,
@@ -15,133 +25,117 @@ pub struct ProjectPanel {
     pub rename_file_path: String,
     /// Rename file new name
     pub rename_file_new_name: String,
+    /// Script double-clicked this frame, opened by the editor
+    pub opened_script: Option<PathBuf>,
+    /// Directory of the open project
+    project_root: Option<PathBuf>,
+    /// Watches the project directory for external changes
+    watcher: Option<RecommendedWatcher>,
+    /// Change notifications from `watcher`
+    changes: Option<Receiver<notify::Result<notify::Event>>>,
+    /// File system errors not yet shown in the console
+    errors: Vec<String>,
 }
 
 impl ProjectPanel {
-    /// Create a new project panel
+    /// Create a project panel with no project open
     pub fn new() -> Self {
-        // Create sample project structure
-        let project_files = vec![
-            ProjectFile {
-                name: "Scripts".to_string(),
-                file_type: ProjectFileType::Folder,
-                path: "/Scripts".to_string(),
-                children: vec![
-                    ProjectFile {
-                        name: "Player.rs".to_string(),
-                        file_type: ProjectFileType::Script,
-                        path: "/Scripts/Player.lua".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Scripts".to_string()),
-                    },
-                    ProjectFile {
-                        name: "Enemy.rs".to_string(),
-                        file_type: ProjectFileType::Script,
-                        path: "/Scripts/Enemy.lua".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Scripts".to_string()),
-                    },
-                ],
-                expanded: false,
-                parent_path: Some("/".to_string()),
-            },
-            ProjectFile {
-                name: "Scenes".to_string(),
-                file_type: ProjectFileType::Folder,
-                path: "/Scenes".to_string(),
-                children: vec![
-                    ProjectFile {
-                        name: "MainScene.scene".to_string(),
-                        file_type: ProjectFileType::Scene,
-                        path: "/Scenes/MainScene.scene".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Scenes".to_string()),
-                    },
-                ],
-                expanded: false,
-                parent_path: Some("/".to_string()),
-            },
-            ProjectFile {
-                name: "Textures".to_string(),
-                file_type: ProjectFileType::Folder,
-                path: "/Textures".to_string(),
-                children: vec![
-                    ProjectFile {
-                        name: "player.png".to_string(),
-                        file_type: ProjectFileType::Texture,
-                        path: "/Textures/player.png".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Textures".to_string()),
-                    },
-                    ProjectFile {
-                        name: "enemy.png".to_string(),
-                        file_type: ProjectFileType::Texture,
-                        path: "/Textures/enemy.png".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Textures".to_string()),
-                    },
-                    ProjectFile {
-                        name: "background.png".to_string(),
-                        file_type: ProjectFileType::Texture,
-                        path: "/Textures/background.png".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Textures".to_string()),
-                    },
-                ],
-                expanded: false,
-                parent_path: Some("/".to_string()),
-            },
-            ProjectFile {
-                name: "Audio".to_string(),
-                file_type: ProjectFileType::Folder,
-                path: "/Audio".to_string(),
-                children: vec![
-                    ProjectFile {
-                        name: "music.mp3".to_string(),
-                        file_type: ProjectFileType::Audio,
-                        path: "/Audio/music.mp3".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Audio".to_string()),
-                    },
-                    ProjectFile {
-                        name: "explosion.wav".to_string(),
-                        file_type: ProjectFileType::Audio,
-                        path: "/Audio/explosion.wav".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Audio".to_string()),
-                    },
-                    ProjectFile {
-                        name: "wind.ogg".to_string(),
-                        file_type: ProjectFileType::Audio,
-                        path: "/Audio/wind.ogg".to_string(),
-                        children: vec![],
-                        expanded: false,
-                        parent_path: Some("/Audio".to_string()),
-                    },
-                ],
-                expanded: false,
-                parent_path: Some("/".to_string()),
-            },
-        ];
-        
         Self {
             project_active_tab: ProjectTab::Files,
-            project_files,
-            current_project_path: Some("/".to_string()),
+            project_files: Vec::new(),
+            current_project_path: None,
             show_rename_dialog: false,
             rename_file_path: String::new(),
             rename_file_new_name: String::new(),
+            opened_script: None,
+            project_root: None,
+            watcher: None,
+            changes: None,
+            errors: Vec::new(),
         }
     }
-    
+
+    /// Open the project in `root`, listing its top-level files and watching it for changes
+    ///
+    /// Folder contents are read when the folder is first expanded.
+    pub fn open_project(&mut self, root: PathBuf) -> io::Result<()> {
+        let project_files = read_folder(&root, "/", &[])?;
+
+        self.project_files = project_files;
+        self.current_project_path = Some("/".to_string());
+        self.watcher = None;
+        self.changes = None;
+
+        // The project still works without a watcher, it just won't notice external changes
+        let (sender, receiver) = mpsc::channel();
+        match notify::recommended_watcher(sender) {
+            Ok(mut watcher) => match watcher.watch(&root, RecursiveMode::Recursive) {
+                Ok(()) => {
+                    self.watcher = Some(watcher);
+                    self.changes = Some(receiver);
+                }
+                Err(e) => self.errors.push(format!("Failed to watch {}: {}", root.display(), e)),
+            },
+            Err(e) => self.errors.push(format!("Failed to watch {}: {}", root.display(), e)),
+        }
+
+        self.project_root = Some(root);
+        Ok(())
+    }
+
+    /// Directory of the open project
+    pub fn project_root(&self) -> Option<&Path> {
+        self.project_root.as_deref()
+    }
+
+    /// Location on disk of a project path
+    pub fn full_path(&self, path: &str) -> Option<PathBuf> {
+        self.project_root.as_ref().map(|root| root.join(path.trim_start_matches('/')))
+    }
+
+    /// Errors from file operations since the last call
+    pub fn take_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Refresh the files if the watcher reported changes to the project directory
+    pub fn poll_changes(&mut self) {
+        let Some(changes) = &self.changes else {
+            return;
+        };
+
+        let mut changed = false;
+        for change in changes.try_iter() {
+            match change {
+                Ok(event) => changed |= !matches!(event.kind, EventKind::Access(_)),
+                Err(e) => self.errors.push(format!("File watcher error: {}", e)),
+            }
+        }
+
+        if changed {
+            self.refresh();
+        }
+    }
+
+    /// Read the project files again, keeping loaded and expanded folders as they were
+    pub fn refresh(&mut self) {
+        let Some(root) = &self.project_root else {
+            return;
+        };
+
+        match read_folder(root, "/", &self.project_files) {
+            Ok(project_files) => self.project_files = project_files,
+            Err(e) => self.errors.push(format!("Failed to read {}: {}", root.display(), e)),
+        }
+
+        // Drop the selection if the selected folder disappeared
+        if let Some(path) = &self.current_project_path {
+            if path != "/" && !self.path_exists(path) {
+                self.current_project_path = Some("/".to_string());
+            }
+        }
+    }
+
     /// Render the project panel
     pub fn render(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
         egui::SidePanel::right("project_panel")
@@ -159,75 +153,105 @@ impl ProjectPanel {
                         self.project_active_tab = ProjectTab::Audio;
                     }
                 });
-                
+
                 ui.separator();
-                
+
                 // This will be handled by the main EditorUI to route to the appropriate panel
             });
-            
+
         // Render rename dialog if needed
         self.render_rename_dialog(ctx, log_info);
     }
-    
+
     /// Render project files tab
     pub fn render_project_files(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
+        if self.project_root.is_none() {
+            ui.heading("Files");
+            ui.separator();
+            ui.label("No project open");
+            return;
+        }
+
         ui.horizontal(|ui| {
             ui.heading("Files");
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let folder = self.current_project_path.clone().unwrap_or_else(|| "/".to_string());
+                if ui.button("Import Asset").clicked() {
+                    if let Some(path) = self.import_asset(folder.clone()) {
+                        log_info(&format!("Imported asset: {}", path));
+                    }
+                }
                 if ui.button("New Folder").clicked() {
-                    if let Some(path) = &self.current_project_path {
-                        self.create_project_folder(path.clone(), "New Folder".to_string());
-                        log_info("Created new folder");
+                    if let Some(path) = self.create_project_folder(folder.clone(), "New Folder".to_string()) {
+                        log_info(&format!("Created folder: {}", path));
+                    }
+                }
+                if ui.button("New Material").clicked() {
+                    let name = format!("NewMaterial.{}", MATERIAL_EXTENSION);
+                    if let Some(path) = self.create_project_file(folder.clone(), name, ProjectFileType::Material) {
+                        log_info(&format!("Created material: {}", path));
                     }
                 }
                 if ui.button("New Script").clicked() {
-                    if let Some(path) = &self.current_project_path {
-                        self.create_project_file(
-                            path.clone(),
-                            "NewScript.rs".to_string(),
-                            ProjectFileType::Script,
-                        );
-                        log_info("Created new script");
+                    if let Some(path) = self.create_project_file(folder, "NewScript.lua".to_string(), ProjectFileType::Script) {
+                        log_info(&format!("Created script: {}", path));
                     }
                 }
             });
         });
-        
+
         ui.separator();
-        
+
         ScrollArea::vertical().show(ui, |ui| {
             // Clone the project files to avoid borrow issues
             let project_files_clone = self.project_files.clone();
-            for (i, file) in project_files_clone.iter().enumerate() {
-                self.render_file(ui, i, file, 0, log_info);
+            for file in &project_files_clone {
+                self.render_file(ui, file, 0, log_info);
             }
         });
     }
-    
+
     /// Render a file in the project panel
-    fn render_file(&mut self, ui: &mut Ui, _file_index: usize, file: &ProjectFile, depth: usize, log_info: &mut dyn FnMut(&str)) {
+    fn render_file(&mut self, ui: &mut Ui, file: &ProjectFile, depth: usize, log_info: &mut dyn FnMut(&str)) {
         // Convert depth to float for UI spacing
         let indent = (depth as f32) * 20.0;
-        
+
         ui.horizontal(|ui| {
             ui.add_space(indent);
-            
+
             if file.file_type == ProjectFileType::Folder {
                 let expanded = file.expanded;
                 let text = if expanded { "▼" } else { "►" };
                 if ui.button(text).clicked() {
-                    // Toggle expanded state
-                    if let Some(file) = find_file_mut(&mut self.project_files, &file.path) {
-                        file.expanded = !file.expanded;
-                    }
+                    self.toggle_folder(&file.path);
                 }
             } else {
                 ui.add_space(15.0);
             }
-            
-            self.render_project_file_icon(ui, file);
-            
-            if ui.label(RichText::new(&file.name).strong()).clicked() {
+
+            let icon = self.render_project_file_icon(ui, file);
+
+            let selected = file.file_type == ProjectFileType::Folder
+                && self.current_project_path.as_deref() == Some(file.path.as_str());
+            let mut text = RichText::new(&file.name).strong();
+            if selected {
+                text = text.underline();
+            }
+            let response = ui.add(egui::Label::new(text).sense(Sense::click_and_drag()));
+
+            // Files can be dragged onto the hierarchy, the scene view and inspector fields
+            if (response.drag_started() || icon.drag_started()) && file.file_type != ProjectFileType::Folder {
+                DraggedAsset::start(ui.ctx(), DraggedAsset {
+                    path: file.path.clone(),
+                    file_type: file.file_type.clone(),
+                });
+            }
+
+            if response.double_clicked() && file.file_type == ProjectFileType::Script {
+                self.opened_script = self.full_path(&file.path);
+            }
+
+            if response.clicked() {
                 // Set current path when clicking on a folder
                 if file.file_type == ProjectFileType::Folder {
                     self.current_project_path = Some(file.path.clone());
@@ -236,11 +260,10 @@ impl ProjectPanel {
                     log_info(&format!("Selected file: {}", file.path));
                 }
             }
-            
+
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 // Context menu - implemented as buttons for simplicity
-                if ui.button("✖").clicked() {
-                    self.delete_project_file(&file.path);
+                if ui.button("✖").clicked() && self.delete_project_file(&file.path) {
                     log_info(&format!("Deleted: {}", file.path));
                 }
                 if ui.button("✏️").clicked() {
@@ -250,7 +273,7 @@ impl ProjectPanel {
                 }
             });
         });
-        
+
         if file.file_type == ProjectFileType::Folder && file.expanded {
             // Clone children to avoid borrow issues
             let children_clone: Vec<ProjectFile> = if let Some(file) = find_file(&self.project_files, &file.path) {
@@ -258,36 +281,65 @@ impl ProjectPanel {
             } else {
                 vec![]
             };
-            
-            for (i, child) in children_clone.iter().enumerate() {
-                self.render_file(ui, i, child, depth + 1, log_info);
+
+            for child in &children_clone {
+                self.render_file(ui, child, depth + 1, log_info);
+            }
+        }
+    }
+
+    /// Expand or collapse a folder, reading its contents the first time it's expanded
+    fn toggle_folder(&mut self, path: &str) {
+        let Some(root) = self.project_root.clone() else {
+            return;
+        };
+        let Some(folder) = find_file_mut(&mut self.project_files, path) else {
+            return;
+        };
+
+        if !folder.expanded && !folder.loaded {
+            match read_folder(&root, path, &[]) {
+                Ok(children) => {
+                    folder.children = children;
+                    folder.loaded = true;
+                }
+                Err(e) => {
+                    self.errors.push(format!("Failed to read {}: {}", path, e));
+                    return;
+                }
             }
         }
+
+        folder.expanded = !folder.expanded;
     }
-    
+
     /// Render a project file icon
-    fn render_project_file_icon(&mut self, ui: &mut Ui, file: &ProjectFile) {
+    fn render_project_file_icon(&mut self, ui: &mut Ui, file: &ProjectFile) -> Response {
         let icon = match file.file_type {
             ProjectFileType::Folder => "📁",
             ProjectFileType::Scene => "🎬",
             ProjectFileType::Script => "📝",
             ProjectFileType::Texture => "🖼️",
             ProjectFileType::Audio => "🔊",
+            ProjectFileType::Prefab => "📦",
+            ProjectFileType::Material => "🎨",
             ProjectFileType::Other => "📄",
         };
-        
+
         let color = match file.file_type {
             ProjectFileType::Folder => Color32::from_rgb(255, 223, 0),
             ProjectFileType::Scene => Color32::from_rgb(0, 191, 255),
             ProjectFileType::Script => Color32::from_rgb(0, 255, 127),
             ProjectFileType::Texture => Color32::from_rgb(255, 105, 180),
             ProjectFileType::Audio => Color32::from_rgb(138, 43, 226),
+            ProjectFileType::Prefab => Color32::from_rgb(100, 149, 237),
+            ProjectFileType::Material => Color32::from_rgb(255, 165, 0),
             ProjectFileType::Other => Color32::WHITE,
         };
-        
-        ui.label(RichText::new(icon).color(color));
+
+        ui.add(egui::Label::new(RichText::new(icon).color(color)).sense(Sense::click_and_drag()))
     }
-    
+
     /// Render rename dialog
     pub fn render_rename_dialog(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
         if self.show_rename_dialog {
@@ -298,378 +350,270 @@ impl ProjectPanel {
                         ui.label("New name:");
                         ui.text_edit_singleline(&mut self.rename_file_new_name);
                     });
-                    
+
                     ui.horizontal(|ui| {
                         if ui.button("Cancel").clicked() {
                             self.show_rename_dialog = false;
                         }
-                        
+
                         if ui.button("Rename").clicked() {
-                            self.rename_project_file();
+                            if self.rename_project_file() {
+                                log_info(&format!("Renamed file to: {}", self.rename_file_new_name));
+                            }
                             self.show_rename_dialog = false;
-                            log_info(&format!("Renamed file to: {}", self.rename_file_new_name));
                         }
                     });
                 });
         }
     }
-    
-    /// Get files in a directory
-    pub fn get_files_in_directory(&self, directory_path: &str) -> Vec<&ProjectFile> {
-        let mut result = Vec::new();
-        
-        for file in &self.project_files {
-            self.collect_files_in_directory(file, directory_path, &mut result);
-        }
-        
-        result
-    }
-    
-    /// Collect files in a directory
-    fn collect_files_in_directory<'a>(
-        &'a self,
-        file: &'a ProjectFile,
-        directory_path: &str,
-        result: &mut Vec<&'a ProjectFile>,
-    ) {
-        if file.path == directory_path && file.file_type == ProjectFileType::Folder {
-            for child in &file.children {
-                result.push(child);
-            }
-        } else if file.file_type == ProjectFileType::Folder {
-            for child in &file.children {
-                self.collect_files_in_directory(child, directory_path, result);
-            }
-        }
-    }
-    
-    /// Create a project folder
-    pub fn create_project_folder(&mut self, parent_path: String, folder_name: String) {
-        // Ensure folder name is unique
-        let folder_name_clone = folder_name.clone();
-        let mut unique_name = folder_name_clone;
-        let mut counter = 1;
-        
-        // Keep incrementing counter until we find a unique name
-        while self.path_exists(&format!("{}/{}", parent_path, unique_name)) {
-            unique_name = format!("{} ({})", folder_name, counter);
-            counter += 1;
-        }
-        
-        // Create the folder path
-        let path = if parent_path == "/" {
-            format!("/{}", unique_name)
-        } else {
-            format!("{}/{}", parent_path, unique_name)
-        };
-        
-        // Create the folder
-        let folder = ProjectFile {
-            name: unique_name,
-            file_type: ProjectFileType::Folder,
-            path: path.clone(),
-            children: Vec::new(),
-            expanded: false,
-            parent_path: Some(parent_path.clone()),
-        };
-        
-        // Add to parent
-        if parent_path == "/" {
-            self.project_files.push(folder);
-        } else {
-            self.add_file_to_parent(&parent_path, folder);
+
+    /// Create a folder in `parent_path`, returning its path
+    ///
+    /// A number is appended to the name if it's already taken.
+    pub fn create_project_folder(&mut self, parent_path: String, folder_name: String) -> Option<String> {
+        let path = child_path(&parent_path, &self.unique_name(&parent_path, &folder_name, false));
+        let full_path = self.full_path(&path)?;
+
+        if let Err(e) = fs::create_dir(&full_path) {
+            self.errors.push(format!("Failed to create folder {}: {}", path, e));
+            return None;
         }
+
+        self.refresh();
+        Some(path)
     }
-    
-    /// Create a project file
+
+    /// Create a file in `parent_path`, returning its path
+    ///
+    /// Scripts start from a template with empty callbacks, materials from the default
+    /// material, other files are empty.
+    /// A number is appended to the name if it's already taken.
     pub fn create_project_file(
         &mut self,
         parent_path: String,
         file_name: String,
         file_type: ProjectFileType,
-    ) {
-        // Ensure file name is unique
-        let file_name_clone = file_name.clone();
-        let mut unique_name = file_name_clone;
-        let mut counter = 1;
-        
-        while self.path_exists(&format!("{}/{}", parent_path, unique_name)) {
-            // For files with extensions, insert counter before extension
-            if unique_name.contains('.') {
-                let file_name_clone2 = file_name.clone();
-                let parts: Vec<&str> = file_name_clone2.split('.').collect();
-                let name = parts[0];
-                let ext = if parts.len() > 1 {
-                    parts[1..].join(".")
-                } else {
-                    String::new()
-                };
-                
-                if !ext.is_empty() {
-                    unique_name = format!("{} ({})", name, counter);
-                    if !ext.is_empty() {
-                        unique_name = format!("{}.{}", unique_name, ext);
-                    }
-                } else {
-                    unique_name = format!("{} ({})", unique_name, counter);
+    ) -> Option<String> {
+        let path = child_path(&parent_path, &self.unique_name(&parent_path, &file_name, true));
+        let full_path = self.full_path(&path)?;
+
+        let contents = match file_type {
+            ProjectFileType::Script => SCRIPT_TEMPLATE.to_string(),
+            ProjectFileType::Material => match MaterialAsset::default().to_ron() {
+                Ok(contents) => contents,
+                Err(e) => {
+                    self.errors.push(format!("Failed to create material {}: {}", path, e));
+                    return None;
                 }
-            } else {
-                unique_name = format!("{} ({})", unique_name, counter);
-            }
-            counter += 1;
-        }
-        
-        // Create the file
-        let path = if parent_path == "/" {
-            format!("/{}", unique_name)
-        } else {
-            format!("{}/{}", parent_path, unique_name)
+            },
+            _ => String::new(),
         };
-        
-        let file = ProjectFile {
-            name: unique_name,
-            file_type,
-            path: path.clone(),
-            children: Vec::new(),
-            expanded: false,
-            parent_path: Some(parent_path.clone()),
+
+        // `create_new` so a file created behind our back is never overwritten
+        let result = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&full_path)
+            .and_then(|mut file| io::Write::write_all(&mut file, contents.as_bytes()));
+        if let Err(e) = result {
+            self.errors.push(format!("Failed to create file {}: {}", path, e));
+            return None;
+        }
+
+        self.refresh();
+        Some(path)
+    }
+
+    /// Copy a file picked in a file dialog into `parent_path`, returning its project path
+    pub fn import_asset(&mut self, parent_path: String) -> Option<String> {
+        let source = rfd::FileDialog::new()
+            .set_title("Import Asset")
+            .pick_file()?;
+        let file_name = source.file_name()?.to_string_lossy().into_owned();
+
+        let path = child_path(&parent_path, &self.unique_name(&parent_path, &file_name, true));
+        let full_path = self.full_path(&path)?;
+
+        if let Err(e) = fs::copy(&source, &full_path) {
+            self.errors.push(format!("Failed to import {}: {}", source.display(), e));
+            return None;
+        }
+
+        self.refresh();
+        Some(path)
+    }
+
+    /// Delete a file, or a folder with everything in it, returns whether it was deleted
+    pub fn delete_project_file(&mut self, path: &str) -> bool {
+        let Some(full_path) = self.full_path(path) else {
+            return false;
         };
-        
-        // Add to parent
-        if parent_path == "/" {
-            self.project_files.push(file);
+
+        let result = if full_path.is_dir() {
+            fs::remove_dir_all(&full_path)
         } else {
-            self.add_file_to_parent(&parent_path, file);
+            fs::remove_file(&full_path)
+        };
+        if let Err(e) = result {
+            self.errors.push(format!("Failed to delete {}: {}", path, e));
+            return false;
         }
+
+        self.refresh();
+        true
     }
-    
-    /// Add a file to its parent
-    fn add_file_to_parent(&mut self, parent_path: &str, file: ProjectFile) {
-        // Cek dulu apakah parent path ada di root
-        for project_file in &mut self.project_files {
-            if project_file.path == parent_path {
-                project_file.children.push(file);
-                return;
-            }
+
+    /// Rename `rename_file_path` to `rename_file_new_name`, returns whether it was renamed
+    pub fn rename_project_file(&mut self) -> bool {
+        let path = self.rename_file_path.clone();
+        let new_name = self.rename_file_new_name.trim().to_string();
+
+        if new_name.is_empty() || new_name.contains(['/', '\\']) || new_name == "." || new_name == ".." {
+            self.errors.push(format!("Invalid file name: {}", new_name));
+            return false;
         }
-        
-        // Jika tidak ada di root, cari di children
-        // Pertama, identifikasi file mana yang berisi parent_path
-        let mut parent_index = None;
-        let mut child_index = None;
-        
-        // Cari indeks file yang mengandung parent_path
-        for (i, project_file) in self.project_files.iter().enumerate() {
-            if let Some((p_idx, c_idx)) = find_folder_path_indices(&project_file.children, parent_path) {
-                parent_index = Some(i);
-                child_index = Some((p_idx, c_idx));
-                break;
-            }
+
+        let parent_path = match path.rsplit_once('/') {
+            Some(("", _)) | None => "/".to_string(),
+            Some((parent, _)) => parent.to_string(),
+        };
+        let new_path = child_path(&parent_path, &new_name);
+        if new_path == path {
+            return false;
         }
-        
-        // Jika ditemukan, tambahkan file ke children
-        if let Some(p_idx) = parent_index {
-            if let Some((parent_idx, child_idx)) = child_index {
-                if parent_idx == 0 {
-                    // Parent langsung ada di children
-                    self.project_files[p_idx].children[child_idx].children.push(file);
-                } else {
-                    // Parent ada di nested children, gunakan indeks yang ditemukan
-                    self.project_files[p_idx].children[parent_idx].children[child_idx].children.push(file);
-                }
-            }
+
+        let (Some(from), Some(to)) = (self.full_path(&path), self.full_path(&new_path)) else {
+            return false;
+        };
+        if to.exists() {
+            self.errors.push(format!("Failed to rename {}: {} already exists", path, new_path));
+            return false;
         }
-    }
-    
-    /// Delete a project file
-    pub fn delete_project_file(&mut self, path: &str) {
-        // Remove from root if it's a root file
-        self.project_files = self.project_files
-            .iter()
-            .filter(|file| file.path != path)
-            .cloned()
-            .collect();
-        
-        // Remove from children
-        for file in &mut self.project_files {
-            file.children = remove_from_children(file.children.clone(), path);
+        if let Err(e) = fs::rename(&from, &to) {
+            self.errors.push(format!("Failed to rename {}: {}", path, e));
+            return false;
         }
-    }
-    
-    /// Rename a project file
-    pub fn rename_project_file(&mut self) {
-        let path_to_rename = self.rename_file_path.clone();
-        let new_name = self.rename_file_new_name.clone();
-        let mut renamed = false;
-        
-        // Try to rename at root level
-        for i in 0..self.project_files.len() {
-            if self.project_files[i].path == path_to_rename {
-                let old_path = self.project_files[i].path.clone();
-                let parent_path = self.project_files[i].parent_path.clone().unwrap_or_else(|| "/".to_string());
-                
-                // Update the path
-                if parent_path == "/" {
-                    self.project_files[i].path = format!("/{}", new_name);
-                } else {
-                    self.project_files[i].path = format!("{}/{}", parent_path, new_name);
-                }
-                
-                // Update name
-                self.project_files[i].name = new_name.clone();
-                
-                // Update children paths
-                let new_path = self.project_files[i].path.clone();
-                
-                // Clone the file to avoid borrow issues
-                let mut file_clone = self.project_files[i].clone();
-                update_children_paths_helper(&mut file_clone, &old_path, &new_path);
-                self.project_files[i] = file_clone;
-                
-                renamed = true;
-                break;
-            }
+
+        // Keep the renamed folder expanded and selected
+        if let Some(file) = find_file_mut(&mut self.project_files, &path) {
+            file.name = new_name;
+            rename_paths(file, &path, &new_path);
         }
-        
-        // If not renamed yet, try in children
-        if !renamed {
-            for i in 0..self.project_files.len() {
-                let (new_children, success) = rename_in_children(
-                    self.project_files[i].children.clone(),
-                    &path_to_rename,
-                    &new_name,
-                );
-                
-                if success {
-                    self.project_files[i].children = new_children;
-                    renamed = true;
-                    break;
+        if let Some(current) = &self.current_project_path {
+            if let Some(rest) = current.strip_prefix(&path) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    self.current_project_path = Some(format!("{}{}", new_path, rest));
                 }
             }
         }
+
+        self.refresh();
+        true
     }
-    
-    /// Update children paths after a parent is renamed
-    fn update_children_paths(&mut self, file: &mut ProjectFile, old_parent: &str, new_parent: &str) {
-        // Create a new vector for updated children
-        let mut updated_children = Vec::new();
-        
-        // Process each child
-        for mut child in std::mem::take(&mut file.children) {
-            // Update the path and parent_path
-            child.path = child.path.replace(old_parent, new_parent);
-            child.parent_path = Some(new_parent.to_string());
-            
-            // Recursively update paths for this child's children
-            update_children_paths_helper(&mut child, old_parent, new_parent);
-            
-            // Add the updated child to our new vector
-            updated_children.push(child);
-        }
-        
-        // Replace the original children with our updated ones
-        file.children = updated_children;
-    }
-    
-    /// Check if a path exists
+
+    /// Check if a path exists on disk
     pub fn path_exists(&self, path: &str) -> bool {
-        // Check in root files
-        for file in &self.project_files {
-            if file.path == path {
-                return true;
-            }
-            
-            // Check in children
-            if self.path_exists_in_children(file, path) {
-                return true;
-            }
-        }
-        
-        false
+        self.full_path(path).map_or(false, |path| path.exists())
     }
-    
-    /// Check if a path exists in children
-    fn path_exists_in_children(&self, folder: &ProjectFile, path: &str) -> bool {
-        for child in &folder.children {
-            if child.path == path {
-                return true;
-            }
-            
-            if child.file_type == ProjectFileType::Folder {
-                if self.path_exists_in_children(child, path) {
-                    return true;
-                }
-            }
+
+    /// `name`, with a number appended if it's already taken in `parent_path`
+    ///
+    /// For files the number goes before the extension.
+    fn unique_name(&self, parent_path: &str, name: &str, is_file: bool) -> String {
+        let (stem, extension) = match name.split_once('.') {
+            Some((stem, extension)) if is_file && !stem.is_empty() => (stem, Some(extension)),
+            _ => (name, None),
+        };
+
+        let mut unique_name = name.to_string();
+        let mut counter = 1;
+        while self.path_exists(&child_path(parent_path, &unique_name)) {
+            unique_name = match extension {
+                Some(extension) => format!("{} ({}).{}", stem, counter, extension),
+                None => format!("{} ({})", stem, counter),
+            };
+            counter += 1;
         }
-        
-        false
+
+        unique_name
     }
 }
 
-/// Helper function to remove a file from children
-pub fn remove_from_children(children: Vec<ProjectFile>, path: &str) -> Vec<ProjectFile> {
-    let mut result = Vec::new();
-    
-    for child in children {
-        if child.path != path {
-            let mut new_child = child.clone();
-            if !new_child.children.is_empty() {
-                new_child.children = remove_from_children(new_child.children, path);
-            }
-            result.push(new_child);
-        }
+/// Project path of `name` inside the folder `parent_path`
+fn child_path(parent_path: &str, name: &str) -> String {
+    if parent_path == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", parent_path, name)
     }
-    
-    result
 }
 
-/// Helper function to rename a file in children
-pub fn rename_in_children(
-    children: Vec<ProjectFile>,
-    path: &str,
-    new_name: &str,
-) -> (Vec<ProjectFile>, bool) {
-    let mut result = Vec::new();
-    let mut renamed = false;
-    
-    for child in children {
-        if child.path == path {
-            let mut new_child = child.clone();
-            let old_path = new_child.path.clone();
-            let parent_path = new_child.parent_path.clone().unwrap_or_else(|| "/".to_string());
-            
-            // Update the path
-            if parent_path == "/" {
-                new_child.path = format!("/{}", new_name);
-            } else {
-                new_child.path = format!("{}/{}", parent_path, new_name);
-            }
-            
-            // Update name
-            new_child.name = new_name.to_string();
-            
-            // Update children paths (manually for now)
-            for grandchild in &mut new_child.children {
-                grandchild.path = grandchild.path.replace(&old_path, &new_child.path);
-                grandchild.parent_path = Some(new_child.path.clone());
-            }
-            
-            result.push(new_child);
-            renamed = true;
+/// Read the contents of the folder `path` in the project at `root`
+///
+/// Subfolders that were loaded in `previous` are read again and keep their
+/// expanded state, the others are left to be read when expanded. Hidden files
+/// are skipped and folders are listed before files.
+fn read_folder(root: &Path, path: &str, previous: &[ProjectFile]) -> io::Result<Vec<ProjectFile>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(root.join(path.trim_start_matches('/')))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let file_path = child_path(path, &name);
+        let is_dir = entry.file_type()?.is_dir();
+        let file_type = if is_dir {
+            ProjectFileType::Folder
         } else {
-            let mut new_child = child.clone();
-            if !new_child.children.is_empty() {
-                let (new_children, success) = rename_in_children(new_child.children, path, new_name);
-                new_child.children = new_children;
-                if success {
-                    renamed = true;
-                }
+            Path::new(&name).extension()
+                .map_or(ProjectFileType::Other, |extension| ProjectFileType::from_extension(&extension.to_string_lossy()))
+        };
+
+        let mut file = ProjectFile {
+            name,
+            file_type,
+            path: file_path.clone(),
+            children: Vec::new(),
+            expanded: false,
+            loaded: false,
+            parent_path: Some(path.to_string()),
+        };
+
+        if let Some(old) = previous.iter().find(|old| old.path == file_path && old.loaded && is_dir) {
+            // A folder that can't be read any more just shows up collapsed
+            if let Ok(children) = read_folder(root, &file_path, &old.children) {
+                file.children = children;
+                file.loaded = true;
+                file.expanded = old.expanded;
             }
-            result.push(new_child);
         }
+
+        files.push(file);
+    }
+
+    files.sort_by(|a, b| {
+        (b.file_type == ProjectFileType::Folder).cmp(&(a.file_type == ProjectFileType::Folder))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    Ok(files)
+}
+
+/// Move a file and everything in it from `old_path` to `new_path`
+fn rename_paths(file: &mut ProjectFile, old_path: &str, new_path: &str) {
+    file.path = format!("{}{}", new_path, &file.path[old_path.len()..]);
+    if let Some(parent_path) = &file.parent_path {
+        if let Some(rest) = parent_path.strip_prefix(old_path) {
+            file.parent_path = Some(format!("{}{}", new_path, rest));
+        }
+    }
+
+    for child in &mut file.children {
+        rename_paths(child, old_path, new_path);
     }
-    
-    (result, renamed)
 }
 
 /// Helper function to find a file in a project files vector by path
@@ -678,12 +622,12 @@ fn find_file<'a>(files: &'a [ProjectFile], path: &str) -> Option<&'a ProjectFile
         if file.path == path {
             return Some(file);
         }
-        
+
         if let Some(found) = find_file(&file.children, path) {
             return Some(found);
         }
     }
-    
+
     None
 }
 
@@ -693,54 +637,11 @@ fn find_file_mut<'a>(files: &'a mut [ProjectFile], path: &str) -> Option<&'a mut
         if file.path == path {
             return Some(file);
         }
-        
+
         if let Some(found) = find_file_mut(&mut file.children, path) {
             return Some(found);
         }
     }
-    
-    None
-}
-
-/// Helper function to update children paths without self reference
-pub fn update_children_paths_helper(file: &mut ProjectFile, old_parent: &str, new_parent: &str) {
-    // Create a new vector for updated children
-    let mut updated_children = Vec::new();
-    
-    // Process each child
-    for mut child in std::mem::take(&mut file.children) {
-        // Update the path and parent_path
-        child.path = child.path.replace(old_parent, new_parent);
-        child.parent_path = Some(new_parent.to_string());
-        
-        // Recursively update paths for this child's children
-        update_children_paths_helper(&mut child, old_parent, new_parent);
-        
-        // Add the updated child to our new vector
-        updated_children.push(child);
-    }
-    
-    // Replace the original children with our updated ones
-    file.children = updated_children;
-}
 
-/// Helper function to find folder path indices
-pub fn find_folder_path_indices(children: &[ProjectFile], path: &str) -> Option<(usize, usize)> {
-    // Cek langsung di children
-    for (i, child) in children.iter().enumerate() {
-        if child.path == path && child.file_type == ProjectFileType::Folder {
-            return Some((0, i)); // 0 berarti parent langsung
-        }
-        
-        // Cek di children dari children
-        if child.file_type == ProjectFileType::Folder {
-            for (j, grandchild) in child.children.iter().enumerate() {
-                if grandchild.path == path && grandchild.file_type == ProjectFileType::Folder {
-                    return Some((i, j)); // i adalah indeks parent, j adalah indeks child
-                }
-            }
-        }
-    }
-    
     None
-} 
\ No newline at end of file
+}
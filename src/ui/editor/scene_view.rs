@@ -1,7 +1,14 @@
 use egui::{Context, Ui, Color32, Vec2, Rect, Pos2, Stroke};
-use crate::ui::editor::ui_components::{SceneViewTool, EntityTransform};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use crate::ui::editor::ui_components::{SceneViewTool, EntityTransform, Selection};
+use crate::ui::editor::hierarchy::EntityType;
+use crate::ui::editor::history::{EditorCommand, TransformChange, TransformCommand};
+use crate::ui::editor::asset_drag::{self, AssetDrop};
+use crate::ecs::{EcsManager, EntityHandle, Transform3DComponent, WorldShape};
+use crate::rendering::Renderer;
+use crate::utils::config::EditorSettings;
+use crate::utils::math;
+use crate::utils::tween::{Easing, Tween};
+use std::collections::{HashMap, HashSet};
 
 /// Scene view panel for editing scenes
 pub struct SceneViewPanel {
@@ -9,28 +16,140 @@ pub struct SceneViewPanel {
     pub scene_view_size: [f32; 2],
     /// The current scene view tool
     pub scene_view_tool: SceneViewTool,
-    /// Entity transforms - shared between scene view and inspector
-    pub entity_transforms: Arc<Mutex<HashMap<u32, EntityTransform>>>,
-    /// The currently selected entity
-    pub selected_entity: Option<u32>,
+    /// The selected entities
+    pub selection: Selection,
+    /// Where the current box selection drag started
+    pub box_select_start: Option<Pos2>,
     /// Entity names
-    pub entity_names: HashMap<u32, String>,
+    pub entity_names: HashMap<EntityHandle, String>,
+    /// Entity types (for choosing how to draw each entity)
+    pub entity_types: HashMap<EntityHandle, EntityType>,
+    /// Entities switched off themselves or by a parent, they aren't drawn or picked
+    pub inactive_entities: HashSet<EntityHandle>,
     /// Show grid in scene view
     pub show_grid: bool,
     /// Camera position
     pub camera_position: [f32; 2],
     /// Camera zoom
     pub camera_zoom: f32,
-    /// Last mouse position for panning
-    pub last_mouse_pos: Option<Pos2>,
-    /// Active gizmo axis
+    /// Active gizmo axis, `None` transforms along every axis
     pub active_axis: Option<GizmoAxis>,
-    /// Dirty flag to track changes
+    /// Gizmo handle under the pointer, or being dragged
+    pub gizmo_handle: Option<GizmoHandle>,
+    /// Set when a gizmo drag moved entities this frame
     pub dirty: bool,
+    /// Commands queued this frame, executed by the editor
+    pub pending_commands: Vec<Box<dyn EditorCommand>>,
+    /// Renderer drawing the scene into an offscreen texture, if the GPU backend is available
+    pub renderer: Option<Renderer>,
+    /// Camera move in progress after focusing on the selection
+    pub camera_animation: Option<CameraAnimation>,
+    /// Position snap increment in world units
+    pub grid_snap: f32,
+    /// World units between major grid lines
+    pub grid_size: f32,
+    /// Smallest zoom of the camera
+    pub min_zoom: f32,
+    /// Largest zoom of the camera
+    pub max_zoom: f32,
+    /// Rotation snap increment in degrees
+    pub angle_snap: f32,
+    /// Scale snap increment
+    pub scale_snap: f32,
+    /// Snap gizmo drags without holding Ctrl, moved entities go to the nearest grid cell
+    pub snap_enabled: bool,
+    /// Show the renderer's frame stats above the camera info
+    pub show_stats: bool,
+    /// Draw colliders and velocities on top of the scene
+    pub show_physics_debug: bool,
+    /// Live physics data drawn by the physics overlay, set by the editor while it's shown
+    pub physics_debug: Vec<PhysicsDebugInfo>,
+    /// View volumes of the selected cameras, set by the editor every frame
+    pub camera_gizmos: Vec<CameraGizmo>,
+    /// Ranges of the selected point lights, set by the editor every frame
+    pub light_gizmos: Vec<LightGizmo>,
+    /// Range dragged with a light gizmo this frame, written to the light by the editor
+    pub light_range_edit: Option<(EntityHandle, f32)>,
+    /// Light whose range handle is being dragged
+    light_range_drag: Option<EntityHandle>,
+    /// Whether the last mouse press landed in the scene view, enabling its tool shortcuts
+    has_focus: bool,
+    /// The dragged transform before snapping, so small movements add up while snapping
+    drag_unsnapped: Option<EntityTransform>,
+    /// Project asset dropped this frame, handled by the editor
+    pub asset_drop: Option<AssetDrop>,
 }
 
+/// Physics state of one entity, in world space
+#[derive(Debug, Clone)]
+pub struct PhysicsDebugInfo {
+    /// Position of the entity
+    pub position: math::Vec2,
+    /// Collider shape, as the physics system sees it
+    pub collider: Option<WorldShape>,
+    /// Whether the collider is a trigger
+    pub is_trigger: bool,
+    /// Velocity of dynamic bodies, in units per second
+    pub velocity: Option<math::Vec2>,
+}
+
+/// What a selected camera sees, drawn over the scene
+#[derive(Debug, Clone, Copy)]
+pub struct CameraGizmo {
+    /// World space corners of the view volume, see `CameraComponent::frustum_corners`
+    pub corners: [math::Vec3; 8],
+    pub is_orthographic: bool,
+}
+
+/// Reach of a selected point light, drawn over the scene with a handle to resize it
+#[derive(Debug, Clone, Copy)]
+pub struct LightGizmo {
+    pub handle: EntityHandle,
+    /// World position of the light
+    pub position: math::Vec2,
+    /// Distance at which the light has faded out, see `LightComponent::range`
+    pub range: f32,
+}
+
+/// Smooth camera move between two framings
+#[derive(Clone)]
+pub struct CameraAnimation {
+    position: Tween<math::Vec2>,
+    zoom: Tween<f32>,
+}
+
+/// How long focusing on the selection or framing the scene takes, in seconds
+pub const FOCUS_DURATION: f32 = 0.2;
+
+/// Fraction of the viewport the focused selection or framed scene should fill
+pub const FOCUS_FILL: f32 = 0.5;
+
+/// Scene units to pixels at zoom 1
+const PIXELS_PER_UNIT: f32 = 50.0;
+
+/// Size of an entity with scale 1, in pixels at zoom 1
+const ENTITY_BASE_SIZE: f32 = 30.0;
+
+/// Growth of an entity's drawn size per unit of Z, a simple stand-in for perspective
+const PERSPECTIVE_PER_Z: f32 = 0.02;
+
+/// Smallest perspective factor, so far away entities stay visible
+const MIN_PERSPECTIVE: f32 = 0.1;
+
+/// Zoom change per point scrolled, as an exponent so scrolling back and forth returns to the same zoom
+const SCROLL_ZOOM_RATE: f32 = 0.001;
+
+/// Pointer distance in pixels within which a gizmo handle counts as hovered
+const GIZMO_HIT_TOLERANCE: f32 = 6.0;
+
+/// Size of the square handle on a light's range circle, in pixels
+const LIGHT_HANDLE_SIZE: f32 = 8.0;
+
+/// Smallest range a light can be dragged to
+const MIN_LIGHT_RANGE: f32 = 0.01;
+
 /// Gizmo axis for transformation
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GizmoAxis {
     /// X axis (red)
     X,
@@ -40,78 +159,206 @@ pub enum GizmoAxis {
     Z,
 }
 
+/// Part of a transform gizmo that can be grabbed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GizmoHandle {
+    /// Arrow, ring or scale handle of one axis
+    Axis(GizmoAxis),
+    /// Uniform scale box in the middle of the scale gizmo
+    Center,
+}
+
 impl SceneViewPanel {
     /// Create a new scene view panel
     pub fn new() -> Self {
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs_f64();
-            
-        let mut transforms = HashMap::new();
-        transforms.insert(1, EntityTransform {
-            position: [0.0, 0.0, -10.0],
-            rotation: [0.0, 0.0, 0.0],
-            scale: [1.0, 1.0, 1.0],
-            last_update: current_time,
-        });
-        transforms.insert(2, EntityTransform {
-            position: [5.0, 10.0, 0.0],
-            rotation: [45.0, 0.0, 0.0],
-            scale: [1.0, 1.0, 1.0],
-            last_update: current_time,
-        });
-        transforms.insert(3, EntityTransform {
-            position: [0.0, 0.0, 0.0],
-            rotation: [0.0, 0.0, 0.0],
-            scale: [1.0, 1.0, 1.0],
-            last_update: current_time,
-        });
-        transforms.insert(4, EntityTransform {
-            position: [0.0, -2.0, 0.0],
-            rotation: [0.0, 0.0, 0.0],
-            scale: [20.0, 1.0, 1.0],
-            last_update: current_time,
-        });
-        transforms.insert(5, EntityTransform {
-            position: [0.0, 0.0, 0.0],
-            rotation: [0.0, 0.0, 0.0],
-            scale: [1.0, 1.0, 1.0],
-            last_update: current_time,
-        });
-        
         Self {
             scene_view_size: [0.0, 0.0],
             scene_view_tool: SceneViewTool::Select,
-            entity_transforms: Arc::new(Mutex::new(transforms)),
-            selected_entity: None,
+            selection: Selection::new(),
+            box_select_start: None,
             entity_names: HashMap::new(),
+            entity_types: HashMap::new(),
+            inactive_entities: HashSet::new(),
             show_grid: true,
             camera_position: [0.0, 0.0],
             camera_zoom: 1.0,
-            last_mouse_pos: None,
             active_axis: None,
+            gizmo_handle: None,
             dirty: false,
+            pending_commands: Vec::new(),
+            renderer: None,
+            camera_animation: None,
+            grid_snap: 0.25,
+            grid_size: 1.0,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            angle_snap: 15.0,
+            scale_snap: 0.1,
+            snap_enabled: false,
+            show_stats: false,
+            show_physics_debug: false,
+            physics_debug: Vec::new(),
+            camera_gizmos: Vec::new(),
+            light_gizmos: Vec::new(),
+            light_range_edit: None,
+            light_range_drag: None,
+            has_focus: true,
+            drag_unsnapped: None,
+            asset_drop: None,
         }
     }
     
-    /// Get entity transforms
-    pub fn get_entity_transforms(&self) -> Arc<Mutex<HashMap<u32, EntityTransform>>> {
-        self.entity_transforms.clone()
-    }
-    
     /// Set entity names reference
-    pub fn set_entity_names(&mut self, entity_names: HashMap<u32, String>) {
+    pub fn set_entity_names(&mut self, entity_names: HashMap<EntityHandle, String>) {
         self.entity_names = entity_names;
     }
     
-    /// Set selected entity
-    pub fn set_selected_entity(&mut self, entity_id: Option<u32>) {
-        self.selected_entity = entity_id;
+    /// Set the physics data drawn by the physics overlay
+    pub fn set_physics_debug(&mut self, physics_debug: Vec<PhysicsDebugInfo>) {
+        self.physics_debug = physics_debug;
+    }
+    
+    /// Set the view volumes of the selected cameras
+    pub fn set_camera_gizmos(&mut self, camera_gizmos: Vec<CameraGizmo>) {
+        self.camera_gizmos = camera_gizmos;
+    }
+    
+    /// Set the ranges of the selected point lights
+    pub fn set_light_gizmos(&mut self, light_gizmos: Vec<LightGizmo>) {
+        self.light_gizmos = light_gizmos;
+    }
+    
+    /// Take the grid, snap and zoom preferences from the editor settings
+    pub fn apply_settings(&mut self, settings: &EditorSettings) {
+        self.show_grid = settings.show_grid;
+        self.grid_size = settings.grid_size;
+        self.snap_enabled = settings.snap_to_grid;
+        self.grid_snap = settings.grid_snap;
+        self.angle_snap = settings.angle_snap;
+        self.scale_snap = settings.scale_snap;
+        self.min_zoom = settings.min_zoom;
+        self.max_zoom = settings.max_zoom;
+        self.camera_zoom = self.camera_zoom.clamp(self.min_zoom, self.max_zoom);
+    }
+    
+    /// Set entity types reference
+    pub fn set_entity_types(&mut self, entity_types: HashMap<EntityHandle, EntityType>) {
+        self.entity_types = entity_types;
+    }
+    
+    /// Set the entities that are inactive in the hierarchy
+    pub fn set_inactive_entities(&mut self, inactive_entities: HashSet<EntityHandle>) {
+        self.inactive_entities = inactive_entities;
+    }
+    
+    /// Set the selected entities
+    pub fn set_selection(&mut self, selection: &Selection) {
+        self.selection = selection.clone();
+    }
+    
+    /// Move the camera to frame the selected entities
+    ///
+    /// Does nothing when nothing is selected. The move is animated by `animate_camera`.
+    pub fn focus_selection(&mut self, ecs: &EcsManager) {
+        let (min, max) = {
+            let mut bounds: Option<([f32; 2], [f32; 2])> = None;
+            for transform in self.selection.iter().filter_map(|id| EntityTransform::of(ecs, id)) {
+                let (min, max) = entity_world_bounds(&transform);
+                bounds = Some(match bounds {
+                    Some((lo, hi)) => ([lo[0].min(min[0]), lo[1].min(min[1])], [hi[0].max(max[0]), hi[1].max(max[1])]),
+                    None => (min, max),
+                });
+            }
+            match bounds {
+                Some(bounds) => bounds,
+                None => return,
+            }
+        };
+        
+        self.frame_bounds(min, max);
+    }
+    
+    /// Move the camera to frame every renderable in the scene
+    ///
+    /// Returns false and leaves the camera alone if the scene has nothing to frame.
+    /// The move is animated by `animate_camera` like `focus_selection`.
+    pub fn frame_all(&mut self, ecs: &EcsManager) -> bool {
+        let Some(bounds) = ecs.scene_bounds() else {
+            return false;
+        };
+        self.frame_bounds([bounds.min.x, bounds.min.y], [bounds.max.x, bounds.max.y]);
+        true
+    }
+    
+    /// Animate the camera so the world space rectangle from `min` to `max` fills the view
+    fn frame_bounds(&mut self, min: [f32; 2], max: [f32; 2]) {
+        let size = [(max[0] - min[0]).max(0.01), (max[1] - min[1]).max(0.01)];
+        let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        
+        let [width, height] = self.scene_view_size;
+        let zoom = if width > 0.0 && height > 0.0 {
+            (width * FOCUS_FILL / (size[0] * PIXELS_PER_UNIT))
+                .min(height * FOCUS_FILL / (size[1] * PIXELS_PER_UNIT))
+                .clamp(self.min_zoom, self.max_zoom)
+        } else {
+            self.camera_zoom
+        };
+        
+        // Entities are drawn at their position plus the camera position, see `draw_entity`
+        self.camera_animation = Some(CameraAnimation {
+            position: Tween::new(
+                math::Vec2::from(self.camera_position),
+                math::Vec2::new(-center[0], -center[1]),
+                FOCUS_DURATION,
+                Easing::EaseInOutQuad,
+            ),
+            zoom: Tween::new(self.camera_zoom, zoom, FOCUS_DURATION, Easing::EaseInOutQuad),
+        });
+    }
+    
+    /// Pan the camera so the scene follows the pointer, `delta` is in screen pixels
+    ///
+    /// The world under the cursor moves exactly as far as the pointer at any zoom.
+    pub fn pan(&mut self, delta: Vec2) {
+        let pixels_per_unit = PIXELS_PER_UNIT * self.camera_zoom;
+        self.camera_position[0] += delta.x / pixels_per_unit;
+        self.camera_position[1] -= delta.y / pixels_per_unit;
+    }
+    
+    /// Multiply the zoom by `factor`, keeping the world point under `anchor` in place
+    pub fn zoom_at(&mut self, factor: f32, anchor: Pos2, rect: Rect) {
+        let old_zoom = self.camera_zoom;
+        self.camera_zoom = (old_zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        
+        // Screen = center + (world + camera) * PIXELS_PER_UNIT * zoom, solved for the camera
+        let offset = anchor - rect.center();
+        let change = 1.0 / (PIXELS_PER_UNIT * self.camera_zoom) - 1.0 / (PIXELS_PER_UNIT * old_zoom);
+        self.camera_position[0] += offset.x * change;
+        self.camera_position[1] -= offset.y * change;
+    }
+    
+    /// Advance the focus animation, returning true while it's running
+    pub fn animate_camera(&mut self, delta_time: f32) -> bool {
+        let Some(animation) = &mut self.camera_animation else {
+            return false;
+        };
+        
+        self.camera_position = animation.position.update(delta_time).to_array();
+        self.camera_zoom = animation.zoom.update(delta_time);
+        
+        if animation.position.is_finished() {
+            self.camera_animation = None;
+        }
+        true
+    }
+    
+    /// Whether the scene view was the last panel clicked, as opposed to e.g. the hierarchy
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
     }
     
     /// Render the scene view
-    pub fn render(&mut self, ui: &mut Ui, log_info: &mut dyn FnMut(&str)) {
+    pub fn render(&mut self, ui: &mut Ui, ecs: &mut EcsManager, log_info: &mut dyn FnMut(&str)) {
         // Reset dirty flag di awal
         self.dirty = false;
         
@@ -126,122 +373,152 @@ impl SceneViewPanel {
         
         let rect = response.rect;
         
-        // Draw background
-        painter.rect_filled(rect, 0.0, Color32::from_rgb(40, 40, 40));
+        // Keyboard shortcuts only act on the scene view after clicking into it
+        if ui.input(|i| i.pointer.any_pressed()) {
+            self.has_focus = response.hovered();
+        }
+        
+        // Draw the rendered frame, falling back to a flat background without a renderer
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let rendered = self.renderer.as_mut().and_then(|renderer| {
+            renderer.render_to_texture(
+                (rect.width() * pixels_per_point).round() as u32,
+                (rect.height() * pixels_per_point).round() as u32,
+            )
+        });
+        match rendered {
+            Some(texture_id) => {
+                let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                painter.image(texture_id, rect, uv, Color32::WHITE);
+            }
+            None => painter.rect_filled(rect, 0.0, Color32::from_rgb(40, 40, 40)),
+        }
         
         // Draw grid if enabled
         if self.show_grid {
             self.draw_unity_style_grid(ui, rect);
         }
         
-        // Handle camera movement with middle mouse button
-        if response.dragged_by(egui::PointerButton::Middle) {
-            if let Some(_mouse_pos) = self.last_mouse_pos {
-                let delta = response.drag_delta();
-                self.camera_position[0] -= delta.x * 0.01 / self.camera_zoom;
-                self.camera_position[1] += delta.y * 0.01 / self.camera_zoom;
-            }
-            self.last_mouse_pos = response.hover_pos();
-        } else {
-            self.last_mouse_pos = None;
+        let modifiers = ui.input(|i| i.modifiers);
+        
+        // Pan with the middle mouse button or Alt + left mouse
+        let panning = response.dragged_by(egui::PointerButton::Middle)
+            || (response.dragged_by(egui::PointerButton::Primary) && modifiers.alt);
+        if panning {
+            self.camera_animation = None;
+            self.pan(response.drag_delta());
         }
         
-        // Handle zooming with scroll
-        if response.hovered() {
-            let scroll_delta = ui.input(|i| i.scroll_delta.y);
-            if scroll_delta != 0.0 {
-                self.camera_zoom *= (1.0 + scroll_delta * 0.001).max(0.1).min(10.0);
+        // Zoom around the cursor with the scroll wheel, Ctrl+scroll or a trackpad pinch
+        if let Some(pointer) = response.hover_pos() {
+            let factor = ui.input(|i| zoom_factor(i.scroll_delta.y, i.zoom_delta()));
+            if factor != 1.0 {
+                self.camera_animation = None;
+                self.zoom_at(factor, pointer, rect);
                 log_info(&format!("Camera zoom: {:.2}", self.camera_zoom));
             }
         }
         
-        // Draw the scene contents - read the shared entity transforms
-        {
-            self.draw_mock_scene(ui, rect);
-        }
+        // Draw the scene contents from the entities' transform components
+        self.draw_mock_scene(ui, rect, ecs);
         
-        // Handle click selection
+        // Handle click selection, Ctrl+click toggles
         if response.clicked() {
             let click_pos = response.interact_pointer_pos.unwrap();
-            let mut entity_clicked = false;
             
             // Check if clicked on an entity
-            {
-                let transforms = self.entity_transforms.lock().unwrap();
-                for (&id, _) in transforms.iter() {
-                    if self.is_point_in_entity(click_pos, rect, id) {
-                        self.selected_entity = Some(id);
-                        entity_clicked = true;
-                        if let Some(name) = self.entity_names.get(&id) {
-                            log_info(&format!("Selected entity: {}", name));
-                        }
-                        break;
+            match self.pick_entity(ecs, click_pos, rect) {
+                Some(id) => {
+                    if modifiers.command {
+                        self.selection.toggle(id);
+                    } else {
+                        self.selection.set(Some(id));
+                    }
+                    if let Some(name) = self.entity_names.get(&id) {
+                        log_info(&format!("Selected entity: {}", name));
                     }
                 }
+                // If clicked on empty space, deselect
+                None if !modifiers.command => self.selection.clear(),
+                None => {}
             }
-            
-            // If clicked on empty space, deselect
-            if !entity_clicked {
-                self.selected_entity = None;
-            }
+        }
+        
+        // A grabbed light range takes the drag from the tools and box selection
+        let range_dragged = !panning && self.handle_light_range_drag(ui, &response, rect);
+        
+        if self.scene_view_tool == SceneViewTool::Select && !panning && !range_dragged {
+            self.handle_box_select(ui, ecs, &response, rect, modifiers.command || modifiers.shift, log_info);
         }
         
         // Handle transformation tools
-        if let Some(entity_id) = self.selected_entity {
+        if let Some(entity_id) = self.selection.primary() {
+            // Transforms of the whole selection, so it moves together and undoes as one step
+            let before: HashMap<EntityHandle, EntityTransform> = self.selection.iter()
+                .filter_map(|id| EntityTransform::of(ecs, id).map(|t| (*id, t)))
+                .collect();
+            
             // Draw transform gizmo based on current tool
             match self.scene_view_tool {
-                SceneViewTool::Move => self.draw_move_gizmo(ui, rect, entity_id),
-                SceneViewTool::Rotate => self.draw_rotate_gizmo(ui, rect, entity_id),
-                SceneViewTool::Scale => self.draw_scale_gizmo(ui, rect, entity_id),
+                SceneViewTool::Move => self.draw_move_gizmo(ui, rect, before.get(&entity_id)),
+                SceneViewTool::Rotate => self.draw_rotate_gizmo(ui, rect, before.get(&entity_id)),
+                SceneViewTool::Scale => self.draw_scale_gizmo(ui, rect, before.get(&entity_id)),
                 _ => {}
             }
             
+            // Left drags drive the tools, other buttons and Alt pan the camera
+            let tool_dragged = response.dragged_by(egui::PointerButton::Primary) && !panning && !range_dragged;
+            
+            // Drag the unsnapped transform, it is snapped again after the tools below
+            let mut dragged = if tool_dragged {
+                before.get(&entity_id).map(|transform| self.drag_unsnapped.get_or_insert_with(|| transform.clone()).clone())
+            } else {
+                self.drag_unsnapped = None;
+                None
+            };
+            
             // Handle dragging for transform tools
-            if response.dragged() && self.scene_view_tool == SceneViewTool::Move {
-                if let Some(entity_id) = self.selected_entity {
-                    let mut transforms = self.entity_transforms.lock().unwrap();
-                    if let Some(transform) = transforms.get_mut(&entity_id) {
-                        let delta = response.drag_delta();
-                        let mut position_changed = false;
-                        
-                        // Apply transformation based on active axis
-                        match self.active_axis {
-                            Some(GizmoAxis::X) => {
-                                transform.position[0] += delta.x * 0.01 / self.camera_zoom;
-                                position_changed = true;
-                                log_info(&format!("Moving {} along X axis", entity_id));
-                            }
-                            Some(GizmoAxis::Y) => {
-                                transform.position[1] -= delta.y * 0.01 / self.camera_zoom;
-                                position_changed = true;
-                                log_info(&format!("Moving {} along Y axis", entity_id));
-                            }
-                            Some(GizmoAxis::Z) => {
-                                // Z axis movement would depend on the view projection
-                                transform.position[2] += (delta.x - delta.y) * 0.005 / self.camera_zoom;
-                                position_changed = true;
-                                log_info(&format!("Moving {} along Z axis", entity_id));
-                            }
-                            None => {
-                                // Move in all axes
-                                transform.position[0] += delta.x * 0.01 / self.camera_zoom;
-                                transform.position[1] -= delta.y * 0.01 / self.camera_zoom;
-                                position_changed = true;
-                            }
+            if self.scene_view_tool == SceneViewTool::Move {
+                if let Some(transform) = dragged.as_mut() {
+                    let delta = response.drag_delta();
+                    let mut position_changed = false;
+                    
+                    // Apply transformation based on active axis
+                    match self.active_axis {
+                        Some(GizmoAxis::X) => {
+                            transform.position[0] += delta.x * 0.01 / self.camera_zoom;
+                            position_changed = true;
+                            log_info(&format!("Moving {} along X axis", entity_id.short_id()));
                         }
-                        
-                        // Set dirty flag if position changed
-                        if position_changed {
-                            self.dirty = true;
+                        Some(GizmoAxis::Y) => {
+                            transform.position[1] -= delta.y * 0.01 / self.camera_zoom;
+                            position_changed = true;
+                            log_info(&format!("Moving {} along Y axis", entity_id.short_id()));
+                        }
+                        Some(GizmoAxis::Z) => {
+                            // Z axis movement would depend on the view projection
+                            transform.position[2] += (delta.x - delta.y) * 0.005 / self.camera_zoom;
+                            position_changed = true;
+                            log_info(&format!("Moving {} along Z axis", entity_id.short_id()));
+                        }
+                        None => {
+                            // Move in all axes
+                            transform.position[0] += delta.x * 0.01 / self.camera_zoom;
+                            transform.position[1] -= delta.y * 0.01 / self.camera_zoom;
+                            position_changed = true;
                         }
                     }
+                    
+                    // Set dirty flag if position changed
+                    if position_changed {
+                        self.dirty = true;
+                    }
                 }
             }
             
             // Handle scale
-            if response.dragged() && self.scene_view_tool == SceneViewTool::Scale {
-                let mut transforms = self.entity_transforms.lock().unwrap();
-                if let Some(transform) = transforms.get_mut(&entity_id) {
+            if self.scene_view_tool == SceneViewTool::Scale {
+                if let Some(transform) = dragged.as_mut() {
                     let delta = response.drag_delta();
                     let mut scale_changed = false;
                     
@@ -251,19 +528,19 @@ impl SceneViewPanel {
                             transform.scale[0] += delta.x * 0.01;
                             if transform.scale[0] < 0.1 { transform.scale[0] = 0.1; }
                             scale_changed = true;
-                            log_info(&format!("Scaling {} along X axis", entity_id));
+                            log_info(&format!("Scaling {} along X axis", entity_id.short_id()));
                         }
                         Some(GizmoAxis::Y) => {
                             transform.scale[1] -= delta.y * 0.01;
                             if transform.scale[1] < 0.1 { transform.scale[1] = 0.1; }
                             scale_changed = true;
-                            log_info(&format!("Scaling {} along Y axis", entity_id));
+                            log_info(&format!("Scaling {} along Y axis", entity_id.short_id()));
                         }
                         Some(GizmoAxis::Z) => {
                             transform.scale[2] += (delta.x - delta.y) * 0.005;
                             if transform.scale[2] < 0.1 { transform.scale[2] = 0.1; }
                             scale_changed = true;
-                            log_info(&format!("Scaling {} along Z axis", entity_id));
+                            log_info(&format!("Scaling {} along Z axis", entity_id.short_id()));
                         }
                         None => {
                             // Uniform scale
@@ -289,69 +566,248 @@ impl SceneViewPanel {
             }
             
             // Handle rotation
-            if response.dragged() && self.scene_view_tool == SceneViewTool::Rotate {
-                let mut transforms = self.entity_transforms.lock().unwrap();
-                if let Some(transform) = transforms.get_mut(&entity_id) {
+            if self.scene_view_tool == SceneViewTool::Rotate {
+                if let Some(transform) = dragged.as_mut() {
                     let delta = response.drag_delta();
                     let mut rotation_changed = false;
                     
-                    // Apply rotation based on active axis or general rotation
+                    // Turn the rotation incrementally, Euler angles would gimbal lock
                     match self.active_axis {
                         Some(GizmoAxis::X) => {
-                            transform.rotation[0] += delta.y * 0.5;
+                            transform.rotate_axis(math::Vec3::X, delta.y * 0.5);
                             rotation_changed = true;
-                            log_info(&format!("Rotating {} around X axis", entity_id));
+                            log_info(&format!("Rotating {} around X axis", entity_id.short_id()));
                         }
                         Some(GizmoAxis::Y) => {
-                            transform.rotation[1] += delta.x * 0.5;
+                            transform.rotate_axis(math::Vec3::Y, delta.x * 0.5);
                             rotation_changed = true;
-                            log_info(&format!("Rotating {} around Y axis", entity_id));
+                            log_info(&format!("Rotating {} around Y axis", entity_id.short_id()));
                         }
                         Some(GizmoAxis::Z) => {
-                            transform.rotation[2] += (delta.x - delta.y) * 0.5;
+                            transform.rotate_axis(math::Vec3::Z, (delta.x - delta.y) * 0.5);
                             rotation_changed = true;
-                            log_info(&format!("Rotating {} around Z axis", entity_id));
+                            log_info(&format!("Rotating {} around Z axis", entity_id.short_id()));
                         }
                         None => {
                             // General rotation based on mouse movement
-                            transform.rotation[1] += delta.x * 0.5;
-                            transform.rotation[0] += delta.y * 0.5;
+                            transform.rotate_axis(math::Vec3::Y, delta.x * 0.5);
+                            transform.rotate_axis(math::Vec3::X, delta.y * 0.5);
                             rotation_changed = true;
                         }
                     }
                     
-                    // Normalize angles to 0-360 degrees
-                    for i in 0..3 {
-                        while transform.rotation[i] >= 360.0 {
-                            transform.rotation[i] -= 360.0;
-                        }
-                        while transform.rotation[i] < 0.0 {
-                            transform.rotation[i] += 360.0;
-                        }
-                    }
-                    
                     // Set dirty flag if rotation changed
                     if rotation_changed {
                         self.dirty = true;
                     }
                 }
             }
+            
+            // Snap while Ctrl is held, or always with the snap toggle on
+            if let Some(transform) = dragged.as_mut() {
+                let snapping = self.snap_enabled != ui.input(|i| i.modifiers.command);
+                self.drag_unsnapped = Some(transform.clone());
+                if snapping {
+                    if let Some(snapped) = self.snap_transform(transform) {
+                        log_info(&format!("Snapped {} to {}", entity_id.short_id(), snapped));
+                    }
+                }
+            }
+            
+            // Apply the gizmo drag to the whole selection and record it so it can be undone
+            if let (Some(primary_before), Some(primary_after)) = (before.get(&entity_id), dragged) {
+                let mut changes = Vec::new();
+                for (id, before) in &before {
+                    let after = if *id == entity_id {
+                        primary_after.clone()
+                    } else {
+                        let mut after = before.clone();
+                        after.apply_delta(primary_before, &primary_after);
+                        after
+                    };
+                    after.write_to(ecs, id);
+                    changes.push(TransformChange {
+                        handle: *id,
+                        before: before.clone(),
+                        after,
+                    });
+                }
+                changes.sort_by_key(|change| change.handle.uuid);
+                
+                if let Some(command) = TransformCommand::new_many(changes) {
+                    self.pending_commands.push(Box::new(command));
+                }
+            }
         }
         
         // Draw Unity-style viewport overlay
-        self.draw_viewport_overlay(ui, rect);
+        self.draw_viewport_overlay(ui, rect, ecs);
+        
+        // Project assets are dropped on the entity under the pointer, prefabs are placed at it
+        let pointer = ui.input(|i| i.pointer.hover_pos());
+        let target = pointer.and_then(|pos| self.pick_entity(ecs, pos, rect));
+        self.asset_drop = asset_drag::drop_target(ui, rect, |file_type| asset_drag::entity_accepts(ecs, target, file_type))
+            .map(|asset| AssetDrop {
+                asset,
+                target,
+                position: pointer.map(|pos| self.screen_to_world(rect, pos).extend(0.0)),
+            });
+    }
+    
+    /// Resize a light's range by dragging its range circle or handle
+    ///
+    /// Snaps to the position snap increment like the move tool. Returns whether a
+    /// range is being dragged.
+    fn handle_light_range_drag(&mut self, ui: &Ui, response: &egui::Response, rect: Rect) -> bool {
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            let origin = ui.input(|i| i.pointer.press_origin());
+            self.light_range_drag = origin
+                .and_then(|point| self.hit_light_gizmo(point, rect))
+                .map(|gizmo| gizmo.handle);
+        }
+        if !response.dragged_by(egui::PointerButton::Primary) {
+            self.light_range_drag = None;
+        }
+        
+        let Some(gizmo) = self.light_range_drag
+            .and_then(|handle| self.light_gizmos.iter().find(|gizmo| gizmo.handle == handle).copied())
+        else {
+            self.light_range_drag = None;
+            return false;
+        };
+        
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let mut range = (self.screen_to_world(rect, pointer) - gizmo.position).length();
+            let snapping = self.snap_enabled != ui.input(|i| i.modifiers.command);
+            if snapping && self.grid_snap > 0.0 {
+                range = (range / self.grid_snap).round() * self.grid_snap;
+            }
+            let range = range.max(MIN_LIGHT_RANGE);
+            if range != gizmo.range {
+                self.light_range_edit = Some((gizmo.handle, range));
+                self.dirty = true;
+            }
+        }
+        true
+    }
+    
+    /// The light gizmo whose range circle or handle is under `point`, the closest if several are
+    fn hit_light_gizmo(&self, point: Pos2, rect: Rect) -> Option<&LightGizmo> {
+        self.light_gizmos.iter()
+            .filter_map(|gizmo| {
+                let (center, radius) = self.light_gizmo_circle(rect, gizmo);
+                hit_light_range(point, center, radius).map(|distance| (gizmo, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(gizmo, _)| gizmo)
+    }
+    
+    /// Screen center and radius of a light's range circle
+    fn light_gizmo_circle(&self, rect: Rect, gizmo: &LightGizmo) -> (Pos2, f32) {
+        (self.world_to_screen(rect, gizmo.position), gizmo.range * PIXELS_PER_UNIT * self.camera_zoom)
+    }
+    
+    /// Round the part of the transform the current tool edits to the snap increments
+    ///
+    /// With the snap toggle on, positions go to the nearest grid cell, holding Ctrl
+    /// instead snaps them to the finer position snap increment.
+    /// Returns a description of the snapped value, or `None` for tools that don't snap.
+    fn snap_transform(&self, transform: &mut EntityTransform) -> Option<String> {
+        let snap = |value: f32, step: f32| if step > 0.0 { (value / step).round() * step } else { value };
+        
+        let description = match self.scene_view_tool {
+            SceneViewTool::Move => {
+                let step = if self.snap_enabled { self.grid_size } else { self.grid_snap };
+                for value in &mut transform.position {
+                    *value = snap(*value, step);
+                }
+                let [x, y, z] = transform.position;
+                format!("position ({:.3}, {:.3}, {:.3})", x, y, z)
+            }
+            SceneViewTool::Rotate => {
+                for value in &mut transform.rotation {
+                    *value = snap(*value, self.angle_snap).rem_euclid(360.0);
+                }
+                let [x, y, z] = transform.rotation;
+                format!("rotation ({:.1}°, {:.1}°, {:.1}°)", x, y, z)
+            }
+            SceneViewTool::Scale => {
+                for value in &mut transform.scale {
+                    *value = snap(*value, self.scale_snap).max(0.1);
+                }
+                let [x, y, z] = transform.scale;
+                format!("scale ({:.2}, {:.2}, {:.2})", x, y, z)
+            }
+            _ => return None,
+        };
+        
+        Some(description)
+    }
+    
+    /// Drag a rectangle with the select tool to select every entity it touches
+    fn handle_box_select(&mut self, ui: &mut Ui, ecs: &EcsManager, response: &egui::Response, rect: Rect, additive: bool, log_info: &mut dyn FnMut(&str)) {
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            self.box_select_start = ui.input(|i| i.pointer.press_origin());
+        }
+        
+        let Some(start) = self.box_select_start else {
+            return;
+        };
+        let current = ui.input(|i| i.pointer.interact_pos()).unwrap_or(start);
+        let selection_rect = Rect::from_two_pos(start, current);
+        
+        if response.drag_released() {
+            self.box_select_start = None;
+            
+            let hits: Vec<EntityHandle> = entity_transforms(ecs).into_iter()
+                .filter(|(id, _)| !self.inactive_entities.contains(id))
+                .filter(|(_, transform)| self.entity_screen_rect(rect, transform).intersects(selection_rect))
+                .map(|(id, _)| id)
+                .collect();
+            
+            if additive {
+                for id in hits {
+                    self.selection.add(id);
+                }
+            } else {
+                self.selection.set_many(hits, None);
+            }
+            
+            log_info(&format!("Selected {} entities", self.selection.len()));
+        } else {
+            let painter = ui.painter();
+            painter.rect_filled(selection_rect, 0.0, Color32::from_rgba_unmultiplied(44, 93, 135, 60));
+            painter.rect_stroke(selection_rect, 0.0, Stroke::new(1.0, Color32::from_rgb(44, 93, 135)));
+        }
+    }
+    
+    /// Screen space bounding box of an entity, including its rotation
+    fn entity_screen_rect(&self, rect: Rect, transform: &EntityTransform) -> Rect {
+        let scale_factor = 50.0;
+        let center = Pos2::new(
+            rect.center().x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom,
+            rect.center().y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom,
+        );
+        
+        let base_size = ENTITY_BASE_SIZE * self.camera_zoom * perspective_scale(transform.position[2]);
+        let half_size = math::Vec2::new(base_size * transform.scale[0].abs(), base_size * transform.scale[1].abs()) / 2.0;
+        let extents = math::rotated_extents(half_size, transform.rotation[2].to_radians());
+        
+        Rect::from_center_size(center, Vec2::new(extents.x, extents.y) * 2.0)
     }
     
     /// Draw Unity-style grid
     pub fn draw_unity_style_grid(&self, ui: &mut Ui, rect: Rect) {
         let painter = ui.painter();
         
-        let grid_size = 50.0 * self.camera_zoom;
+        let pixels_per_unit = PIXELS_PER_UNIT * self.camera_zoom;
+        let grid_size = pixels_per_unit * self.grid_size;
         let grid_color_major = Color32::from_rgb(80, 80, 80);
         let grid_color_minor = Color32::from_rgb(60, 60, 60);
         
-        let center_x = rect.center().x + self.camera_position[0] * 50.0 * self.camera_zoom;
-        let center_y = rect.center().y - self.camera_position[1] * 50.0 * self.camera_zoom;
+        // Screen position of the world origin
+        let center_x = rect.center().x + self.camera_position[0] * pixels_per_unit;
+        let center_y = rect.center().y - self.camera_position[1] * pixels_per_unit;
         
         // Calculate grid boundaries
         let left = rect.left();
@@ -359,289 +815,162 @@ impl SceneViewPanel {
         let top = rect.top();
         let bottom = rect.bottom();
         
-        // Calculate grid starting positions (aligned to grid_size)
-        let start_x = center_x - (((center_x - left) / grid_size).floor() * grid_size);
-        let start_y = center_y - (((center_y - top) / grid_size).floor() * grid_size);
-        
-        // Draw minor grid lines
+        // Lines are indexed from the origin, every 5th minor line is a major one and line 0 is an axis.
+        // Positions are computed from the index so they don't drift at fractional zoom levels.
         let minor_grid_size = grid_size / 5.0;
-        let minor_start_x = center_x - (((center_x - left) / minor_grid_size).floor() * minor_grid_size);
-        let minor_start_y = center_y - (((center_y - top) / minor_grid_size).floor() * minor_grid_size);
-        
-        // Draw minor grid lines
-        for x in (minor_start_x as i32..=right as i32).step_by(minor_grid_size as usize) {
-            let x = x as f32;
-            if (x - center_x).abs() % grid_size < 1.0 {
-                continue; // Skip where major lines will be
-            }
-            painter.line_segment(
-                [Pos2::new(x, top), Pos2::new(x, bottom)],
-                Stroke::new(1.0, grid_color_minor),
-            );
-        }
         
-        for y in (minor_start_y as i32..=bottom as i32).step_by(minor_grid_size as usize) {
-            let y = y as f32;
-            if (y - center_y).abs() % grid_size < 1.0 {
-                continue; // Skip where major lines will be
-            }
-            painter.line_segment(
-                [Pos2::new(left, y), Pos2::new(right, y)],
-                Stroke::new(1.0, grid_color_minor),
-            );
+        let first_x = ((left - center_x) / minor_grid_size).ceil() as i64;
+        let last_x = ((right - center_x) / minor_grid_size).floor() as i64;
+        for index in first_x..=last_x {
+            let x = center_x + index as f32 * minor_grid_size;
+            let stroke = match index {
+                0 => Stroke::new(2.0, Color32::from_rgb(150, 20, 20)),
+                _ if index % 5 == 0 => Stroke::new(1.0, grid_color_major),
+                _ => Stroke::new(1.0, grid_color_minor),
+            };
+            painter.line_segment([Pos2::new(x, top), Pos2::new(x, bottom)], stroke);
         }
         
-        // Draw major vertical lines
-        for x in (start_x as i32..=right as i32).step_by(grid_size as usize) {
-            let x = x as f32;
-            let is_center = (x - center_x).abs() < 1.0;
-            let color = if is_center { Color32::from_rgb(150, 20, 20) } else { grid_color_major };
-            let stroke_width = if is_center { 2.0 } else { 1.0 };
-            
-            painter.line_segment(
-                [Pos2::new(x, top), Pos2::new(x, bottom)],
-                Stroke::new(stroke_width, color),
-            );
+        let first_y = ((top - center_y) / minor_grid_size).ceil() as i64;
+        let last_y = ((bottom - center_y) / minor_grid_size).floor() as i64;
+        for index in first_y..=last_y {
+            let y = center_y + index as f32 * minor_grid_size;
+            let stroke = match index {
+                0 => Stroke::new(2.0, Color32::from_rgb(20, 150, 20)),
+                _ if index % 5 == 0 => Stroke::new(1.0, grid_color_major),
+                _ => Stroke::new(1.0, grid_color_minor),
+            };
+            painter.line_segment([Pos2::new(left, y), Pos2::new(right, y)], stroke);
         }
-        
-        // Draw major horizontal lines
-        for y in (start_y as i32..=bottom as i32).step_by(grid_size as usize) {
-            let y = y as f32;
-            let is_center = (y - center_y).abs() < 1.0;
-            let color = if is_center { Color32::from_rgb(20, 150, 20) } else { grid_color_major };
-            let stroke_width = if is_center { 2.0 } else { 1.0 };
-            
-            painter.line_segment(
-                [Pos2::new(left, y), Pos2::new(right, y)],
-                Stroke::new(stroke_width, color),
-            );
+    }
+    
+    /// Screen position of an entity's gizmo
+    fn gizmo_center(&self, rect: Rect, transform: Option<&EntityTransform>) -> Option<Pos2> {
+        let transform = transform?;
+        Some(Pos2::new(
+            rect.center().x + (transform.position[0] + self.camera_position[0]) * PIXELS_PER_UNIT * self.camera_zoom,
+            rect.center().y - (transform.position[1] + self.camera_position[1]) * PIXELS_PER_UNIT * self.camera_zoom,
+        ))
+    }
+    
+    /// Track the gizmo handle under the pointer and return the one to highlight
+    ///
+    /// The grabbed handle is kept while a button is held, so the drag sticks to
+    /// its axis when the pointer moves off it.
+    fn update_gizmo_handle(&mut self, ui: &Ui, hovered: Option<GizmoHandle>) -> Option<GizmoHandle> {
+        if !ui.input(|i| i.pointer.any_down()) {
+            self.gizmo_handle = hovered;
+            self.active_axis = match hovered {
+                Some(GizmoHandle::Axis(axis)) => Some(axis),
+                // The center and empty space drive every axis at once
+                Some(GizmoHandle::Center) | None => None,
+            };
         }
+        self.gizmo_handle
     }
     
     /// Draw move gizmo
-    fn draw_move_gizmo(&mut self, ui: &mut Ui, rect: Rect, entity_id: u32) {
-        let painter = ui.painter();
+    fn draw_move_gizmo(&mut self, ui: &mut Ui, rect: Rect, transform: Option<&EntityTransform>) {
+        let Some(center) = self.gizmo_center(rect, transform) else {
+            return;
+        };
         
-        if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
-            let center_x = rect.center().x + (transform.position[0] + self.camera_position[0]) * 50.0 * self.camera_zoom;
-            let center_y = rect.center().y - (transform.position[1] + self.camera_position[1]) * 50.0 * self.camera_zoom;
-            
-            let axis_length = 50.0 * self.camera_zoom;
-            let axis_width = 3.0;
-            let arrow_size = 10.0 * self.camera_zoom;
-            
-            // X axis (red)
-            let x_end = center_x + axis_length;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(x_end, center_y)],
-                Stroke::new(axis_width, Color32::from_rgb(255, 0, 0)),
-            );
-            
-            // X axis arrowhead
-            let x_arrow = [
-                Pos2::new(x_end, center_y),
-                Pos2::new(x_end - arrow_size, center_y - arrow_size/2.0),
-                Pos2::new(x_end - arrow_size, center_y + arrow_size/2.0),
-            ];
-            painter.add(egui::Shape::convex_polygon(
-                x_arrow.to_vec(),
-                Color32::from_rgb(255, 0, 0),
-                Stroke::NONE,
-            ));
-            
-            // Y axis (green)
-            let y_end = center_y - axis_length;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(center_x, y_end)],
-                Stroke::new(axis_width, Color32::from_rgb(0, 255, 0)),
-            );
+        let axis_length = 50.0 * self.camera_zoom;
+        let arrow_size = 10.0 * self.camera_zoom;
+        
+        let hovered = ui.input(|i| i.pointer.hover_pos())
+            .and_then(|pointer| hit_move_gizmo(pointer, center, axis_length))
+            .map(GizmoHandle::Axis);
+        let highlighted = self.update_gizmo_handle(ui, hovered);
+        
+        let painter = ui.painter();
+        for (axis, end) in gizmo_axis_ends(center, axis_length) {
+            let is_highlighted = highlighted == Some(GizmoHandle::Axis(axis));
+            let color = gizmo_axis_color(axis, is_highlighted);
+            let width = if is_highlighted { 5.0 } else { 3.0 };
+            painter.line_segment([center, end], Stroke::new(width, color));
             
-            // Y axis arrowhead
-            let y_arrow = [
-                Pos2::new(center_x, y_end),
-                Pos2::new(center_x - arrow_size/2.0, y_end + arrow_size),
-                Pos2::new(center_x + arrow_size/2.0, y_end + arrow_size),
-            ];
+            // Arrowheads on the screen axes, Z is drawn without one
+            let direction = match axis {
+                GizmoAxis::X => Vec2::X,
+                GizmoAxis::Y => -Vec2::Y,
+                GizmoAxis::Z => continue,
+            };
+            let side = direction.rot90() * arrow_size / 2.0;
+            let base = end - direction * arrow_size;
             painter.add(egui::Shape::convex_polygon(
-                y_arrow.to_vec(),
-                Color32::from_rgb(0, 255, 0),
+                vec![end, base + side, base - side],
+                color,
                 Stroke::NONE,
             ));
-            
-            // Z axis (blue) - shorter for perspective
-            let z_length = axis_length * 0.7;
-            let z_end_x = center_x + z_length * 0.7;
-            let z_end_y = center_y - z_length * 0.7;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(z_end_x, z_end_y)],
-                Stroke::new(axis_width, Color32::from_rgb(0, 0, 255)),
-            );
-            
-            // Check if mouse is hovering over any axis
-            if let Some(mouse_pos) = ui.input(|i| i.pointer.hover_pos()) {
-                // Check X axis
-                let x_axis_rect = Rect::from_min_max(
-                    Pos2::new(center_x, center_y - axis_width),
-                    Pos2::new(x_end, center_y + axis_width),
-                );
-                if x_axis_rect.contains(mouse_pos) {
-                    self.active_axis = Some(GizmoAxis::X);
-                    painter.line_segment(
-                        [Pos2::new(center_x, center_y), Pos2::new(x_end, center_y)],
-                        Stroke::new(axis_width + 2.0, Color32::from_rgb(255, 100, 100)),
-                    );
-                }
-                // Check Y axis
-                let y_axis_rect = Rect::from_min_max(
-                    Pos2::new(center_x - axis_width, y_end),
-                    Pos2::new(center_x + axis_width, center_y),
-                );
-                if y_axis_rect.contains(mouse_pos) {
-                    self.active_axis = Some(GizmoAxis::Y);
-                    painter.line_segment(
-                        [Pos2::new(center_x, center_y), Pos2::new(center_x, y_end)],
-                        Stroke::new(axis_width + 2.0, Color32::from_rgb(100, 255, 100)),
-                    );
-                }
-                // Check Z axis
-                let dist_to_z = mouse_pos.distance(Pos2::new(
-                    center_x + (mouse_pos.x - center_x) * 0.5,
-                    center_y + (mouse_pos.y - center_y) * 0.5,
-                ));
-                if dist_to_z < 5.0 {
-                    self.active_axis = Some(GizmoAxis::Z);
-                    painter.line_segment(
-                        [Pos2::new(center_x, center_y), Pos2::new(z_end_x, z_end_y)],
-                        Stroke::new(axis_width + 2.0, Color32::from_rgb(100, 100, 255)),
-                    );
-                }
-            }
         }
     }
     
     /// Draw rotate gizmo
-    fn draw_rotate_gizmo(&mut self, ui: &mut Ui, rect: Rect, entity_id: u32) {
-        let painter = ui.painter();
+    fn draw_rotate_gizmo(&mut self, ui: &mut Ui, rect: Rect, transform: Option<&EntityTransform>) {
+        let Some(center) = self.gizmo_center(rect, transform) else {
+            return;
+        };
         
-        if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
-            let center_x = rect.center().x + (transform.position[0] + self.camera_position[0]) * 50.0 * self.camera_zoom;
-            let center_y = rect.center().y - (transform.position[1] + self.camera_position[1]) * 50.0 * self.camera_zoom;
-            
-            let radius = 40.0 * self.camera_zoom;
-            
-            // X rotation circle (red)
-            painter.circle_stroke(
-                Pos2::new(center_x, center_y),
-                radius,
-                Stroke::new(2.0, Color32::from_rgb(255, 0, 0)),
-            );
-            
-            // Y rotation circle (green) - drawn as an ellipse to simulate perspective
-            let points_y = (0..=32).map(|i| {
-                let angle = i as f32 * 2.0 * std::f32::consts::PI / 32.0;
-                let x = center_x + radius * angle.cos() * 0.5;
-                let y = center_y + radius * angle.sin();
-                Pos2::new(x, y)
-            }).collect::<Vec<_>>();
-            
-            for i in 0..points_y.len()-1 {
-                painter.line_segment(
-                    [points_y[i], points_y[i+1]],
-                    Stroke::new(2.0, Color32::from_rgb(0, 255, 0)),
-                );
-            }
+        let radius = 40.0 * self.camera_zoom;
+        
+        let hovered = ui.input(|i| i.pointer.hover_pos())
+            .and_then(|pointer| hit_rotate_gizmo(pointer, center, radius))
+            .map(GizmoHandle::Axis);
+        let highlighted = self.update_gizmo_handle(ui, hovered);
+        
+        let painter = ui.painter();
+        for (axis, radii) in gizmo_ring_radii(radius) {
+            let is_highlighted = highlighted == Some(GizmoHandle::Axis(axis));
+            let stroke = Stroke::new(if is_highlighted { 4.0 } else { 2.0 }, gizmo_axis_color(axis, is_highlighted));
             
-            // Z rotation circle (blue) - drawn as an ellipse to simulate perspective
-            let points_z = (0..=32).map(|i| {
+            // Y and Z are drawn as ellipses to simulate perspective
+            let points: Vec<Pos2> = (0..=32).map(|i| {
                 let angle = i as f32 * 2.0 * std::f32::consts::PI / 32.0;
-                let x = center_x + radius * angle.cos();
-                let y = center_y + radius * angle.sin() * 0.5;
-                Pos2::new(x, y)
-            }).collect::<Vec<_>>();
-            
-            for i in 0..points_z.len()-1 {
-                painter.line_segment(
-                    [points_z[i], points_z[i+1]],
-                    Stroke::new(2.0, Color32::from_rgb(0, 0, 255)),
-                );
-            }
+                center + Vec2::new(radii.x * angle.cos(), radii.y * angle.sin())
+            }).collect();
+            painter.add(egui::Shape::line(points, stroke));
         }
     }
     
     /// Draw scale gizmo
-    fn draw_scale_gizmo(&mut self, ui: &mut Ui, rect: Rect, entity_id: u32) {
-        let painter = ui.painter();
+    fn draw_scale_gizmo(&mut self, ui: &mut Ui, rect: Rect, transform: Option<&EntityTransform>) {
+        let Some(center) = self.gizmo_center(rect, transform) else {
+            return;
+        };
         
-        if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
-            let center_x = rect.center().x + (transform.position[0] + self.camera_position[0]) * 50.0 * self.camera_zoom;
-            let center_y = rect.center().y - (transform.position[1] + self.camera_position[1]) * 50.0 * self.camera_zoom;
-            
-            let axis_length = 40.0 * self.camera_zoom;
-            let box_size = 10.0 * self.camera_zoom;
-            
-            // X axis (red)
-            let x_end = center_x + axis_length;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(x_end - box_size/2.0, center_y)],
-                Stroke::new(2.0, Color32::from_rgb(255, 0, 0)),
-            );
+        let axis_length = 40.0 * self.camera_zoom;
+        let box_size = 10.0 * self.camera_zoom;
+        
+        let hovered = ui.input(|i| i.pointer.hover_pos())
+            .and_then(|pointer| hit_scale_gizmo(pointer, center, axis_length, box_size));
+        let highlighted = self.update_gizmo_handle(ui, hovered);
+        
+        let painter = ui.painter();
+        for (axis, end) in gizmo_axis_ends(center, axis_length) {
+            let is_highlighted = highlighted == Some(GizmoHandle::Axis(axis));
+            let color = gizmo_axis_color(axis, is_highlighted);
             
-            // X handle
-            painter.rect_filled(
-                Rect::from_center_size(
-                    Pos2::new(x_end, center_y),
-                    Vec2::new(box_size, box_size)
-                ),
-                0.0, Color32::from_rgb(255, 0, 0)
-            );
+            // Stop the line at the edge of the handle
+            let line_end = end - (end - center).normalized() * box_size / 2.0;
+            painter.line_segment([center, line_end], Stroke::new(if is_highlighted { 4.0 } else { 2.0 }, color));
             
-            // Y axis (green)
-            let y_end = center_y - axis_length;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(center_x, y_end + box_size/2.0)],
-                Stroke::new(2.0, Color32::from_rgb(0, 255, 0)),
-            );
-            
-            // Y handle
-            painter.rect_filled(
-                Rect::from_center_size(
-                    Pos2::new(center_x, y_end),
-                    Vec2::new(box_size, box_size)
-                ),
-                0.0, Color32::from_rgb(0, 255, 0)
-            );
-            
-            // Z axis (blue) - shorter for perspective
-            let z_length = axis_length * 0.7;
-            let z_end_x = center_x + z_length * 0.7;
-            let z_end_y = center_y - z_length * 0.7;
-            painter.line_segment(
-                [Pos2::new(center_x, center_y), Pos2::new(z_end_x - box_size/2.0, z_end_y + box_size/2.0)],
-                Stroke::new(2.0, Color32::from_rgb(0, 0, 255)),
-            );
-            
-            // Z handle
-            painter.rect_filled(
-                Rect::from_center_size(
-                    Pos2::new(z_end_x, z_end_y),
-                    Vec2::new(box_size, box_size)
-                ),
-                0.0, Color32::from_rgb(0, 0, 255)
-            );
-            
-            // Center uniform scale handle
-            painter.rect_filled(
-                Rect::from_center_size(
-                    Pos2::new(center_x, center_y),
-                    Vec2::new(box_size, box_size)
-                ),
-                0.0, Color32::from_rgb(255, 255, 0)
-            );
+            let handle_size = if is_highlighted { box_size * 1.3 } else { box_size };
+            painter.rect_filled(Rect::from_center_size(end, Vec2::splat(handle_size)), 0.0, color);
         }
+        
+        // Center uniform scale handle
+        let center_highlighted = highlighted == Some(GizmoHandle::Center);
+        let (center_size, center_color) = if center_highlighted {
+            (box_size * 1.3, Color32::from_rgb(255, 255, 160))
+        } else {
+            (box_size, Color32::from_rgb(255, 255, 0))
+        };
+        painter.rect_filled(Rect::from_center_size(center, Vec2::splat(center_size)), 0.0, center_color);
     }
     
     /// Draw viewport overlay with orientation gizmo and info
-    fn draw_viewport_overlay(&self, ui: &mut Ui, rect: Rect) {
+    fn draw_viewport_overlay(&self, ui: &mut Ui, rect: Rect, ecs: &EcsManager) {
         let painter = ui.painter();
         
         // Draw orientation gizmo in the top right corner
@@ -694,12 +1023,12 @@ impl SceneViewPanel {
         );
         
         // Camera info text at bottom left
-        if let Some(entity_id) = self.selected_entity {
-            if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
+        if let Some(entity_id) = self.selection.primary() {
+            if let Some(transform) = EntityTransform::of(ecs, &entity_id) {
                 // Create a persistent string for the entity name to avoid temporary value drop
                 let entity_name_str = match self.entity_names.get(&entity_id) {
                     Some(name) => name.clone(),
-                    None => format!("Entity {}", entity_id),
+                    None => format!("Entity {}", entity_id.short_id()),
                 };
                 
                 painter.text(
@@ -731,20 +1060,20 @@ impl SceneViewPanel {
     }
     
     /// Draw a mock scene for visualization
-    pub fn draw_mock_scene(&self, ui: &mut Ui, rect: Rect) {
-        // Salin transformasi entitas dari shared state
-        let entity_transforms = {
-            self.entity_transforms.lock().unwrap().clone()
-        };
+    pub fn draw_mock_scene(&self, ui: &mut Ui, rect: Rect, ecs: &EcsManager) {
+        let entity_transforms: HashMap<EntityHandle, EntityTransform> = entity_transforms(ecs).into_iter().collect();
         
         // Dapatkan semua entity ID dari transforms dan urutkan berdasarkan Z
         let mut entities_with_z = Vec::new();
         for (&id, transform) in entity_transforms.iter() {
-            entities_with_z.push((id, transform.position[2]));
+            if !self.inactive_entities.contains(&id) {
+                entities_with_z.push((id, transform.position[2]));
+            }
         }
         
         // Urutkan berdasarkan z-position (yang lebih jauh/kecil duluan)
-        entities_with_z.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        // Ties are broken by UUID so the draw order matches `pick_entity`
+        entities_with_z.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.uuid.cmp(&b.0.uuid)));
         
         // Gambar grid terlebih dahulu jika opsi grid diaktifkan
         if self.show_grid {
@@ -754,23 +1083,27 @@ impl SceneViewPanel {
         // Gambar setiap entitas berdasarkan urutan Z (dari belakang ke depan)
         // Ini membuat entitas yang lebih dekat (Z lebih besar) menimpa entitas yang lebih jauh
         for (entity_id, _) in entities_with_z {
-            // Debug print untuk membantu pelacakan render order
-            if let Some(name) = self.entity_names.get(&entity_id) {
-                if let Some(transform) = entity_transforms.get(&entity_id) {
-                    println!("Rendering: {} (ID: {}) at Z: {:.3}", name, entity_id, transform.position[2]);
-                }
+            if let Some(transform) = entity_transforms.get(&entity_id) {
+                self.draw_entity(ui, rect, entity_id, transform);
             }
-            
-            self.draw_entity(ui, rect, entity_id);
+        }
+        
+        self.draw_camera_gizmos(ui, rect);
+        self.draw_light_gizmos(ui, rect);
+        
+        if self.show_physics_debug {
+            self.draw_physics_debug(ui, rect);
         }
         
         // Draw manipulator pada objek yang dipilih jika ada
-        if let Some(entity_id) = self.selected_entity {
-            self.draw_manipulator(ui, rect, entity_id);
+        if let Some(entity_id) = self.selection.primary() {
+            if let Some(transform) = entity_transforms.get(&entity_id) {
+                self.draw_manipulator(ui, rect, transform);
+            }
         }
         
         // Tampilkan informasi scene view di pojok kiri bawah
-        self.draw_scene_info(ui, rect);
+        self.draw_scene_info(ui, rect, &entity_transforms);
     }
     
     /// Draw a grid in the scene view
@@ -778,7 +1111,7 @@ impl SceneViewPanel {
         let painter = ui.painter();
         
         // Definisikan parameter grid
-        let grid_size = 50.0; // Ukuran grid dalam pixel
+        let grid_size = PIXELS_PER_UNIT * self.grid_size; // Ukuran grid dalam pixel
         let grid_color = Color32::from_rgba_premultiplied(80, 80, 80, 180);
         let axis_color_x = Color32::from_rgba_premultiplied(200, 80, 80, 180); // Merah untuk sumbu X
         let axis_color_z = Color32::from_rgba_premultiplied(80, 80, 200, 180); // Biru untuk sumbu Z
@@ -788,8 +1121,8 @@ impl SceneViewPanel {
         
         // Gambar grid dengan memperhitungkan camera position dan zoom
         let scaled_grid_size = grid_size * self.camera_zoom;
-        let offset_x = (self.camera_position[0] * scaled_grid_size) % scaled_grid_size;
-        let offset_y = (self.camera_position[1] * scaled_grid_size) % scaled_grid_size;
+        let offset_x = (self.camera_position[0] * PIXELS_PER_UNIT * self.camera_zoom) % scaled_grid_size;
+        let offset_y = (self.camera_position[1] * PIXELS_PER_UNIT * self.camera_zoom) % scaled_grid_size;
         
         // Gambar grid horizontal
         for i in -50..50 {
@@ -829,7 +1162,7 @@ impl SceneViewPanel {
     }
     
     /// Draw a manipulator gizmo for the selected entity
-    fn draw_manipulator(&self, ui: &mut Ui, rect: Rect, entity_id: u32) {
+    fn draw_manipulator(&self, ui: &mut Ui, rect: Rect, transform: &EntityTransform) {
         // Hanya tampilkan manipulator jika mode Select tidak aktif
         if self.scene_view_tool == SceneViewTool::Select {
             return;
@@ -837,119 +1170,116 @@ impl SceneViewPanel {
         
         let painter = ui.painter();
         
-        let transforms = self.entity_transforms.lock().unwrap();
-        if let Some(transform) = transforms.get(&entity_id) {
-            // Konversi posisi entity ke screen space
-            let scale_factor = 50.0;
-            let center_x = rect.center().x;
-            let center_y = rect.center().y;
-            
-            let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
-            let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
-            
-            // Gambar gizmo berdasarkan tool yang aktif
-            match self.scene_view_tool {
-                SceneViewTool::Move => {
-                    // Gambar arrow untuk Move gizmo
-                    let arrow_length = 30.0 * self.camera_zoom;
-                    let arrow_width = 5.0 * self.camera_zoom;
-                    
-                    // X-axis arrow (red)
-                    painter.line_segment(
-                        [Pos2::new(pos_x, pos_y), Pos2::new(pos_x + arrow_length, pos_y)],
-                        Stroke::new(arrow_width, Color32::RED),
-                    );
-                    
-                    // Y-axis arrow (green)
-                    painter.line_segment(
-                        [Pos2::new(pos_x, pos_y), Pos2::new(pos_x, pos_y - arrow_length)],
-                        Stroke::new(arrow_width, Color32::GREEN),
-                    );
-                },
-                SceneViewTool::Rotate => {
-                    // Gambar rotation gizmo
-                    let radius = 25.0 * self.camera_zoom;
-                    painter.circle_stroke(
+        // Konversi posisi entity ke screen space
+        let scale_factor = 50.0;
+        let center_x = rect.center().x;
+        let center_y = rect.center().y;
+        
+        let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
+        let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
+        
+        // Gambar gizmo berdasarkan tool yang aktif
+        match self.scene_view_tool {
+            SceneViewTool::Move => {
+                // Gambar arrow untuk Move gizmo
+                let arrow_length = 30.0 * self.camera_zoom;
+                let arrow_width = 5.0 * self.camera_zoom;
+                
+                // X-axis arrow (red)
+                painter.line_segment(
+                    [Pos2::new(pos_x, pos_y), Pos2::new(pos_x + arrow_length, pos_y)],
+                    Stroke::new(arrow_width, Color32::RED),
+                );
+                
+                // Y-axis arrow (green)
+                painter.line_segment(
+                    [Pos2::new(pos_x, pos_y), Pos2::new(pos_x, pos_y - arrow_length)],
+                    Stroke::new(arrow_width, Color32::GREEN),
+                );
+            },
+            SceneViewTool::Rotate => {
+                // Gambar rotation gizmo
+                let radius = 25.0 * self.camera_zoom;
+                painter.circle_stroke(
+                    Pos2::new(pos_x, pos_y),
+                    radius,
+                    Stroke::new(2.0, Color32::YELLOW),
+                );
+                
+                // Tambahkan indikator untuk rotation saat ini
+                let rotation_rad = transform.rotation[2].to_radians();
+                let indicator_x = pos_x + radius * rotation_rad.cos();
+                let indicator_y = pos_y - radius * rotation_rad.sin();
+                
+                painter.circle_filled(
+                    Pos2::new(indicator_x, indicator_y),
+                    5.0 * self.camera_zoom,
+                    Color32::YELLOW,
+                );
+            },
+            SceneViewTool::Scale => {
+                // Gambar scale gizmo
+                let size = 20.0 * self.camera_zoom;
+                
+                // Gambar uniform scale box
+                painter.rect_stroke(
+                    Rect::from_center_size(
                         Pos2::new(pos_x, pos_y),
-                        radius,
-                        Stroke::new(2.0, Color32::YELLOW),
-                    );
-                    
-                    // Tambahkan indikator untuk rotation saat ini
-                    let rotation_rad = transform.rotation[2].to_radians();
-                    let indicator_x = pos_x + radius * rotation_rad.cos();
-                    let indicator_y = pos_y - radius * rotation_rad.sin();
-                    
-                    painter.circle_filled(
-                        Pos2::new(indicator_x, indicator_y),
-                        5.0 * self.camera_zoom,
-                        Color32::YELLOW,
-                    );
-                },
-                SceneViewTool::Scale => {
-                    // Gambar scale gizmo
-                    let size = 20.0 * self.camera_zoom;
-                    
-                    // Gambar uniform scale box
-                    painter.rect_stroke(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x, pos_y),
-                            Vec2::new(size, size),
-                        ),
-                        0.0,
-                        Stroke::new(2.0, Color32::LIGHT_BLUE),
-                    );
-                    
-                    // Tambahkan corner handles
-                    let corner_size = 5.0 * self.camera_zoom;
-                    
-                    // Top-right corner
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x + size/2.0, pos_y - size/2.0),
-                            Vec2::new(corner_size, corner_size),
-                        ),
-                        0.0,
-                        Color32::WHITE,
-                    );
-                    
-                    // Bottom-right corner
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x + size/2.0, pos_y + size/2.0),
-                            Vec2::new(corner_size, corner_size),
-                        ),
-                        0.0,
-                        Color32::WHITE,
-                    );
-                    
-                    // Bottom-left corner
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x - size/2.0, pos_y + size/2.0),
-                            Vec2::new(corner_size, corner_size),
-                        ),
-                        0.0,
-                        Color32::WHITE,
-                    );
-                    
-                    // Top-left corner
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x - size/2.0, pos_y - size/2.0),
-                            Vec2::new(corner_size, corner_size),
-                        ),
-                        0.0,
-                        Color32::WHITE,
-                    );
-                },
-                _ => {}
-            }
+                        Vec2::new(size, size),
+                    ),
+                    0.0,
+                    Stroke::new(2.0, Color32::LIGHT_BLUE),
+                );
+                
+                // Tambahkan corner handles
+                let corner_size = 5.0 * self.camera_zoom;
+                
+                // Top-right corner
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        Pos2::new(pos_x + size/2.0, pos_y - size/2.0),
+                        Vec2::new(corner_size, corner_size),
+                    ),
+                    0.0,
+                    Color32::WHITE,
+                );
+                
+                // Bottom-right corner
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        Pos2::new(pos_x + size/2.0, pos_y + size/2.0),
+                        Vec2::new(corner_size, corner_size),
+                    ),
+                    0.0,
+                    Color32::WHITE,
+                );
+                
+                // Bottom-left corner
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        Pos2::new(pos_x - size/2.0, pos_y + size/2.0),
+                        Vec2::new(corner_size, corner_size),
+                    ),
+                    0.0,
+                    Color32::WHITE,
+                );
+                
+                // Top-left corner
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        Pos2::new(pos_x - size/2.0, pos_y - size/2.0),
+                        Vec2::new(corner_size, corner_size),
+                    ),
+                    0.0,
+                    Color32::WHITE,
+                );
+            },
+            _ => {}
         }
     }
     
     /// Draw scene information in the bottom left corner
-    fn draw_scene_info(&self, ui: &mut Ui, rect: Rect) {
+    fn draw_scene_info(&self, ui: &mut Ui, rect: Rect, entity_transforms: &HashMap<EntityHandle, EntityTransform>) {
         let painter = ui.painter();
         
         // Tampilkan informasi kamera
@@ -962,13 +1292,15 @@ impl SceneViewPanel {
         
         // Tambahkan informasi entity yang dipilih
         let mut selection_text = String::from("No entity selected");
-        if let Some(entity_id) = self.selected_entity {
+        if self.selection.len() > 1 {
+            selection_text = format!("{} entities selected", self.selection.len());
+        } else if let Some(entity_id) = self.selection.primary() {
             if let Some(name) = self.entity_names.get(&entity_id) {
-                if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
+                if let Some(transform) = entity_transforms.get(&entity_id) {
                     selection_text = format!(
                         "Selected: {} (ID: {}) | Position: ({:.2}, {:.2}, {:.2})", 
                         name, 
-                        entity_id,
+                        entity_id.short_id(),
                         transform.position[0],
                         transform.position[1],
                         transform.position[2]
@@ -1007,6 +1339,35 @@ impl SceneViewPanel {
             Color32::WHITE,
         );
         
+        // Frame stats, averaged so the numbers are readable
+        if let Some(renderer) = self.renderer.as_ref().filter(|_| self.show_stats) {
+            let stats_text = renderer.average_stats().to_string();
+            let stats_galley = ui.painter().layout_no_wrap(
+                stats_text.clone(),
+                font_id.clone(),
+                Color32::WHITE,
+            );
+            
+            let stats_rect = Rect::from_min_size(
+                Pos2::new(rect.min.x + padding, info_rect.min.y - stats_galley.rect.height() - padding * 3.0),
+                Vec2::new(stats_galley.rect.width() + padding * 2.0, stats_galley.rect.height() + padding * 2.0),
+            );
+            
+            painter.rect_filled(
+                stats_rect,
+                3.0,
+                Color32::from_rgba_premultiplied(0, 0, 0, 180),
+            );
+            
+            painter.text(
+                Pos2::new(stats_rect.min.x + padding, stats_rect.min.y + padding),
+                egui::Align2::LEFT_TOP,
+                stats_text,
+                font_id.clone(),
+                Color32::WHITE,
+            );
+        }
+        
         // Selection info
         let selection_galley = ui.painter().layout_no_wrap(
             selection_text.clone(),
@@ -1034,394 +1395,310 @@ impl SceneViewPanel {
         );
     }
     
-    /// Handle input for the scene view panel
-    fn handle_input(&mut self, ui: &mut Ui, rect: Rect) -> bool {
-        let mut response = ui.interact(rect, ui.id().with("scene_view"), egui::Sense::click_and_drag());
-        let mut scene_changed = false;
-        
-        let camera_speed = 0.05;
-        let zoom_speed = 0.1;
-        
-        // Handle panning with middle mouse or Alt + left mouse
-        if ui.input(|i| i.pointer.middle_down()) || 
-           (ui.input(|i| i.pointer.primary_down()) && ui.input(|i| i.modifiers.alt)) {
-            let delta = ui.input(|i| i.pointer.delta());
-            self.camera_position[0] -= delta.x * camera_speed / self.camera_zoom;
-            self.camera_position[1] += delta.y * camera_speed / self.camera_zoom;
-            scene_changed = true;
-        }
+    /// Screen position of a point in the scene
+    fn world_to_screen(&self, rect: Rect, position: math::Vec2) -> Pos2 {
+        let pixels_per_unit = PIXELS_PER_UNIT * self.camera_zoom;
+        Pos2::new(
+            rect.center().x + (position.x + self.camera_position[0]) * pixels_per_unit,
+            rect.center().y - (position.y + self.camera_position[1]) * pixels_per_unit,
+        )
+    }
+    
+    /// Point in the scene's XY plane under a screen position
+    fn screen_to_world(&self, rect: Rect, position: Pos2) -> math::Vec2 {
+        let pixels_per_unit = PIXELS_PER_UNIT * self.camera_zoom;
+        math::Vec2::new(
+            (position.x - rect.center().x) / pixels_per_unit - self.camera_position[0],
+            (rect.center().y - position.y) / pixels_per_unit - self.camera_position[1],
+        )
+    }
+    
+    /// Draw the view volumes of the selected cameras
+    ///
+    /// Corners are flattened onto the scene's XY plane, so a camera looking down
+    /// the Z axis shows its near and far rectangles. An orthographic camera's
+    /// rectangles overlap on exactly the area it renders.
+    fn draw_camera_gizmos(&self, ui: &mut Ui, rect: Rect) {
+        let painter = ui.painter_at(rect);
+        let stroke = Stroke::new(1.0, Color32::from_rgb(0, 150, 255));
+        let faint = Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 150, 255, 110));
         
-        // Handle zooming with scroll wheel
-        let scroll_delta = ui.input(|i| i.scroll_delta.y);
-        if scroll_delta != 0.0 {
-            let old_zoom = self.camera_zoom;
-            self.camera_zoom *= 1.0 + scroll_delta * zoom_speed * 0.01;
-            self.camera_zoom = self.camera_zoom.clamp(0.1, 10.0);
+        for gizmo in &self.camera_gizmos {
+            let corners = gizmo.corners.map(|corner| self.world_to_screen(rect, corner.truncate()));
+            let (near, far) = corners.split_at(4);
             
-            // Adjust zoom around cursor position
-            if ui.input(|i| i.pointer.has_pointer()) {
-                if let Some(mouse_pos) = ui.input(|i| i.pointer.hover_pos()) {
-                    if rect.contains(mouse_pos) {
-                        let center_x = rect.center().x;
-                        let center_y = rect.center().y;
-                        let dx = (mouse_pos.x - center_x) / old_zoom;
-                        let dy = (mouse_pos.y - center_y) / old_zoom;
-                        
-                        let zoom_ratio = 1.0 - self.camera_zoom / old_zoom;
-                        self.camera_position[0] += dx * zoom_ratio;
-                        self.camera_position[1] -= dy * zoom_ratio;
-                    }
-                }
+            // The near plane of an orthographic camera is as big as what it renders
+            let near_stroke = if gizmo.is_orthographic { stroke } else { faint };
+            for (near_corner, far_corner) in near.iter().zip(far) {
+                painter.line_segment([*near_corner, *far_corner], faint);
             }
-            scene_changed = true;
+            painter.add(egui::Shape::closed_line(near.to_vec(), near_stroke));
+            painter.add(egui::Shape::closed_line(far.to_vec(), stroke));
         }
+    }
+    
+    /// Draw the range circles of the selected point lights with their resize handles
+    ///
+    /// The circle being dragged, or under the pointer, is highlighted.
+    fn draw_light_gizmos(&self, ui: &mut Ui, rect: Rect) {
+        let painter = ui.painter_at(rect);
+        let hovered = ui.input(|i| i.pointer.hover_pos())
+            .and_then(|pointer| self.hit_light_gizmo(pointer, rect))
+            .map(|gizmo| gizmo.handle);
         
-        // Handle selection with left click
-        if response.clicked() {
-            // Find which entity was clicked
-            let mut clicked_entity = None;
-            let mut closest_z = std::f32::NEG_INFINITY;
+        for gizmo in &self.light_gizmos {
+            let highlighted = self.light_range_drag.or(hovered) == Some(gizmo.handle);
+            let color = if highlighted {
+                Color32::from_rgb(255, 230, 120)
+            } else {
+                Color32::from_rgba_unmultiplied(255, 200, 0, 160)
+            };
             
-            if let Some(mouse_pos) = ui.input(|i| i.pointer.interact_pos()) {
-                // Get all entities from transforms
-                let transforms = self.entity_transforms.lock().unwrap();
+            let (center, radius) = self.light_gizmo_circle(rect, gizmo);
+            painter.circle_stroke(center, radius, Stroke::new(if highlighted { 2.0 } else { 1.0 }, color));
+            painter.rect_filled(
+                Rect::from_center_size(center + Vec2::new(radius, 0.0), Vec2::splat(LIGHT_HANDLE_SIZE)),
+                0.0,
+                color,
+            );
+        }
+    }
+    
+    /// Draw collider outlines and velocity arrows
+    ///
+    /// Solid colliders are green and triggers yellow.
+    fn draw_physics_debug(&self, ui: &mut Ui, rect: Rect) {
+        let painter = ui.painter_at(rect);
+        let pixels_per_unit = PIXELS_PER_UNIT * self.camera_zoom;
+        
+        for info in &self.physics_debug {
+            if let Some(shape) = &info.collider {
+                let color = if info.is_trigger {
+                    Color32::from_rgb(255, 220, 0)
+                } else {
+                    Color32::from_rgb(0, 255, 0)
+                };
+                let stroke = Stroke::new(1.5, color);
                 
-                // Convert all entities to screen space and check for click
-                for (&id, transform) in transforms.iter() {
-                    let scale_factor = 50.0;
-                    let center_x = rect.center().x;
-                    let center_y = rect.center().y;
-                    
-                    let z_offset = transform.position[2];
-                    let perspective_scale = 1.0 + z_offset * 0.02;
-                    let perspective_zoom = if perspective_scale <= 0.1 { 0.1 } else { perspective_scale };
-                    
-                    let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
-                    let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
-                    
-                    let base_size = 30.0 * self.camera_zoom * perspective_zoom;
-                    let size_x = base_size * transform.scale[0];
-                    let size_y = base_size * transform.scale[1];
-                    
-                    // Custom hit testing based on entity type
-                    let is_hit = match id {
-                        1 => { // Camera
-                            let camera_width = 20.0 * self.camera_zoom * perspective_zoom;
-                            let camera_height = 12.0 * self.camera_zoom * perspective_zoom;
-                            
-                            let camera_rect = Rect::from_center_size(
-                                Pos2::new(pos_x, pos_y),
-                                Vec2::new(camera_width + 10.0, camera_height + 10.0),
-                            );
-                            
-                            camera_rect.contains(mouse_pos)
-                        },
-                        2 => { // Light
-                            let light_radius = 15.0 * self.camera_zoom * perspective_zoom;
-                            let distance = ((pos_x - mouse_pos.x).powi(2) + (pos_y - mouse_pos.y).powi(2)).sqrt();
-                            
-                            distance <= light_radius
-                        },
-                        4 => { // Background - dengan bounding box yang lebih besar
-                            let bg_rect = Rect::from_center_size(
-                                Pos2::new(pos_x, pos_y),
-                                Vec2::new(size_x, size_y),
-                            );
-                            
-                            bg_rect.contains(mouse_pos)
-                        },
-                        _ => { // Default untuk entity lain (termasuk Player)
-                            let entity_rect = Rect::from_center_size(
-                                Pos2::new(pos_x, pos_y),
-                                Vec2::new(size_x, size_y),
-                            );
-                            
-                            entity_rect.contains(mouse_pos)
-                        }
-                    };
-                    
-                    // Jika entity terklik dan memiliki Z lebih besar dari entity yang sudah dipilih,
-                    // update entity yang dipilih (memilih objek yang lebih depan)
-                    if is_hit && transform.position[2] > closest_z {
-                        clicked_entity = Some(id);
-                        closest_z = transform.position[2];
+                match shape {
+                    WorldShape::Polygon { vertices, .. } => {
+                        let points = vertices.iter().map(|vertex| self.world_to_screen(rect, *vertex)).collect();
+                        painter.add(egui::Shape::closed_line(points, stroke));
+                    }
+                    WorldShape::Circle { center, radius } => {
+                        painter.circle_stroke(self.world_to_screen(rect, *center), radius * pixels_per_unit, stroke);
                     }
                 }
             }
             
-            // Update selected entity
-            if self.selected_entity != clicked_entity {
-                println!("Selected entity changed from {:?} to {:?}", self.selected_entity, clicked_entity);
-                self.selected_entity = clicked_entity;
-                scene_changed = true;
+            if let Some(velocity) = info.velocity.filter(|velocity| *velocity != math::Vec2::ZERO) {
+                let origin = self.world_to_screen(rect, info.position);
+                let vector = Vec2::new(velocity.x, -velocity.y) * pixels_per_unit;
+                painter.arrow(origin, vector, Stroke::new(1.5, Color32::from_rgb(0, 200, 255)));
             }
         }
-        
-        // Handle transform tool operations based on selected entity and current tool
-        if let Some(entity_id) = self.selected_entity {
-            // Handle transform tools based on scene_view_tool
-            match self.scene_view_tool {
-                SceneViewTool::Move => {
-                    // Handle moving the entity when dragging
-                    if response.dragged() && !ui.input(|i| i.modifiers.alt) {
-                        let delta = ui.input(|i| i.pointer.delta());
-                        let scale_factor = 50.0 * self.camera_zoom;
-                        
-                        let mut transforms = self.entity_transforms.lock().unwrap();
-                        if let Some(transform) = transforms.get_mut(&entity_id) {
-                            // Update transform position based on delta
-                            transform.position[0] += delta.x / scale_factor;
-                            transform.position[1] -= delta.y / scale_factor;
-                            
-                            // Update timestamp
-                            transform.last_update = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs_f64();
-                            
-                            scene_changed = true;
-                        }
-                    }
-                },
-                SceneViewTool::Rotate => {
-                    // Handle rotating the entity when dragging
-                    if response.dragged() && !ui.input(|i| i.modifiers.alt) {
-                        let delta = ui.input(|i| i.pointer.delta());
-                        
-                        let mut transforms = self.entity_transforms.lock().unwrap();
-                        if let Some(transform) = transforms.get_mut(&entity_id) {
-                            // Update rotation based on horizontal movement for Z rotation
-                            transform.rotation[2] += delta.x * 0.5;
-                            
-                            // Wrap rotation to keep it in 0-360 range
-                            while transform.rotation[2] < 0.0 {
-                                transform.rotation[2] += 360.0;
-                            }
-                            while transform.rotation[2] >= 360.0 {
-                                transform.rotation[2] -= 360.0;
-                            }
-                            
-                            // Update timestamp
-                            transform.last_update = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs_f64();
-                            
-                            scene_changed = true;
-                        }
-                    }
-                },
-                SceneViewTool::Scale => {
-                    // Handle scaling the entity when dragging
-                    if response.dragged() && !ui.input(|i| i.modifiers.alt) {
-                        let delta = ui.input(|i| i.pointer.delta());
-                        
-                        let mut transforms = self.entity_transforms.lock().unwrap();
-                        if let Some(transform) = transforms.get_mut(&entity_id) {
-                            // Scale proportionally using diagonal movement
-                            let scale_delta = (delta.x + delta.y) * 0.01;
-                            transform.scale[0] = (transform.scale[0] + scale_delta).max(0.1);
-                            transform.scale[1] = (transform.scale[1] + scale_delta).max(0.1);
-                            
-                            // Update timestamp
-                            transform.last_update = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs_f64();
-                            
-                            scene_changed = true;
-                        }
-                    }
-                },
-                _ => {}
-            }
-        }
-        
-        scene_changed
     }
     
     /// Draw an entity with proper transformation
-    fn draw_entity(&self, ui: &mut Ui, rect: Rect, entity_id: u32) {
+    fn draw_entity(&self, ui: &mut Ui, rect: Rect, entity_id: EntityHandle, transform: &EntityTransform) {
         let painter = ui.painter();
         
-        let transforms = self.entity_transforms.lock().unwrap();
-        if let Some(transform) = transforms.get(&entity_id) {
-            // Kita perlu mengkonversi koordinat 3D ke koordinat layar 2D
-            // Posisi di scene view adalah:
-            // X screen = center_x + (position.x + camera_position.x) * scale_factor * camera_zoom
-            // Y screen = center_y - (position.y + camera_position.y) * scale_factor * camera_zoom
-            let scale_factor = 50.0; // Faktor konversi dari unit game ke pixel
-            let center_x = rect.center().x;
-            let center_y = rect.center().y;
-            
-            // Perspektif sederhana: benda yang lebih jauh (z lebih negatif) akan tampak lebih kecil
-            let z_offset = transform.position[2];
-            let perspective_scale = 1.0 + z_offset * 0.02;  // Buat efek perspektif lebih terlihat
-            let perspective_zoom = if perspective_scale <= 0.1 { 0.1 } else { perspective_scale };
-            
-            let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
-            let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
-            
-            // Gunakan nilai skala dari transform untuk menentukan ukuran entitas
-            let base_size = 30.0 * self.camera_zoom * perspective_zoom;
-            let size_x = base_size * transform.scale[0];
-            let size_y = base_size * transform.scale[1];
-            
-            let entity_rect = Rect::from_center_size(
-                Pos2::new(pos_x, pos_y),
-                Vec2::new(size_x, size_y),
-            );
-            
-            // Debug untuk Background (entity_id 4)
-            if entity_id == 4 {
-                let debug_info = format!("Background Transform: pos={:?}, scale={:?}, screen_pos=({:.1},{:.1}), size=({:.1},{:.1})", 
-                                        transform.position, transform.scale, pos_x, pos_y, size_x, size_y);
-                println!("{}", debug_info);
-            }
-            
-            // Warna dasar entitas
-            let color = match entity_id {
-                1 => Color32::from_rgb(0, 150, 255), // Camera
-                2 => Color32::from_rgb(255, 200, 0), // Light
-                3 => Color32::from_rgb(0, 200, 0),   // Player
-                4 => Color32::from_rgb(150, 75, 0),  // Ground/Background
-                _ => Color32::WHITE,
-            };
-            
-            // Jika entitas dipilih, gambar highlight di sekitarnya
-            let is_selected = Some(entity_id) == self.selected_entity;
-            
-            // Hitung rotasi dari nilai transform
-            let rotation_radians = transform.rotation[2].to_radians();
-            
-            // Draw different shapes based on entity type
-            match entity_id {
-                1 => { // Camera
-                    // Camera icon - skala berdasarkan Z position
-                    let camera_width = 20.0 * self.camera_zoom * perspective_zoom;
-                    let camera_height = 12.0 * self.camera_zoom * perspective_zoom;
+        // Kita perlu mengkonversi koordinat 3D ke koordinat layar 2D
+        // Posisi di scene view adalah:
+        // X screen = center_x + (position.x + camera_position.x) * scale_factor * camera_zoom
+        // Y screen = center_y - (position.y + camera_position.y) * scale_factor * camera_zoom
+        let scale_factor = 50.0; // Faktor konversi dari unit game ke pixel
+        let center_x = rect.center().x;
+        let center_y = rect.center().y;
+        
+        // Perspektif sederhana: benda yang lebih jauh (z lebih negatif) akan tampak lebih kecil
+        let perspective_zoom = perspective_scale(transform.position[2]);
+        
+        let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
+        let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
+        
+        // Gunakan nilai skala dari transform untuk menentukan ukuran entitas
+        let base_size = 30.0 * self.camera_zoom * perspective_zoom;
+        let size_x = base_size * transform.scale[0];
+        let size_y = base_size * transform.scale[1];
+        
+        let entity_rect = Rect::from_center_size(
+            Pos2::new(pos_x, pos_y),
+            Vec2::new(size_x, size_y),
+        );
+        
+        // Warna dasar entitas
+        let entity_type = self.entity_types.get(&entity_id).copied().unwrap_or(EntityType::GameObject);
+        let color = match entity_type {
+            EntityType::Camera => Color32::from_rgb(0, 150, 255),
+            EntityType::Light(_) => Color32::from_rgb(255, 200, 0),
+            EntityType::GameObject | EntityType::Primitive(_) => Color32::from_rgb(0, 200, 0),
+            EntityType::Sprite => Color32::from_rgb(150, 75, 0),
+            _ => Color32::WHITE,
+        };
+        
+        // Jika entitas dipilih, gambar highlight di sekitarnya
+        let is_selected = self.selection.contains(&entity_id);
+        
+        // Hitung rotasi dari nilai transform
+        let rotation_radians = transform.rotation[2].to_radians();
+        
+        // Draw different shapes based on entity type
+        match entity_type {
+            EntityType::Camera => {
+                // Camera icon - skala berdasarkan Z position
+                let camera_width = 20.0 * self.camera_zoom * perspective_zoom;
+                let camera_height = 12.0 * self.camera_zoom * perspective_zoom;
+                
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        Pos2::new(pos_x, pos_y),
+                        Vec2::new(camera_width, camera_height),
+                    ),
+                    3.0,
+                    color,
+                );
+                
+                painter.circle_filled(
+                    Pos2::new(pos_x + 10.0 * self.camera_zoom * perspective_zoom, pos_y),
+                    4.0 * self.camera_zoom * perspective_zoom,
+                    color,
+                );
+                
+                // Draw frustum lines
+                let line_length = 30.0 * self.camera_zoom * perspective_zoom;
+                let line_width = 1.0;
+                painter.line_segment(
+                    [Pos2::new(pos_x, pos_y), 
+                     Pos2::new(pos_x - line_length, pos_y + line_length * 0.8)],
+                    Stroke::new(line_width, color),
+                );
+                painter.line_segment(
+                    [Pos2::new(pos_x, pos_y), 
+                     Pos2::new(pos_x - line_length, pos_y - line_length * 0.8)],
+                    Stroke::new(line_width, color),
+                );
+                
+                // Highlight jika dipilih
+                if is_selected {
+                    let camera_rect = Rect::from_center_size(
+                        Pos2::new(pos_x, pos_y),
+                        Vec2::new(camera_width + 8.0, camera_height + 8.0),
+                    );
                     
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            Pos2::new(pos_x, pos_y),
-                            Vec2::new(camera_width, camera_height),
-                        ),
+                    painter.rect_stroke(
+                        camera_rect,
                         3.0,
-                        color,
+                        Stroke::new(2.0, Color32::YELLOW),
                     );
                     
-                    painter.circle_filled(
-                        Pos2::new(pos_x + 10.0 * self.camera_zoom * perspective_zoom, pos_y),
-                        4.0 * self.camera_zoom * perspective_zoom,
-                        color,
+                    // Tambahkan pulsing glow effect
+                    let time = ui.input(|i| i.time);
+                    let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
+                    let glow_color = Color32::from_rgba_premultiplied(
+                        255, 255, 0, (glow_alpha * 100.0) as u8
                     );
                     
-                    // Draw frustum lines
-                    let line_length = 30.0 * self.camera_zoom * perspective_zoom;
-                    let line_width = 1.0;
-                    painter.line_segment(
-                        [Pos2::new(pos_x, pos_y), 
-                         Pos2::new(pos_x - line_length, pos_y + line_length * 0.8)],
-                        Stroke::new(line_width, color),
+                    painter.rect_stroke(
+                        camera_rect.expand(4.0),
+                        3.0,
+                        Stroke::new(1.0, glow_color),
                     );
+                }
+            },
+            EntityType::Light(_) => {
+                // Light icon
+                let light_radius = 10.0 * self.camera_zoom * perspective_zoom;
+                painter.circle_filled(
+                    Pos2::new(pos_x, pos_y),
+                    light_radius,
+                    color,
+                );
+                
+                // Light rays - rotasi sesuai dengan transform.rotation[1] (Y)
+                let ray_count = 8;
+                let ray_length = 20.0 * self.camera_zoom * perspective_zoom;
+                let y_rotation_radians = transform.rotation[1].to_radians();
+                
+                for i in 0..ray_count {
+                    let angle = i as f32 * std::f32::consts::PI * 2.0 / ray_count as f32 + y_rotation_radians;
+                    let dx = angle.cos() * ray_length;
+                    let dy = angle.sin() * ray_length;
+                    
                     painter.line_segment(
                         [Pos2::new(pos_x, pos_y), 
-                         Pos2::new(pos_x - line_length, pos_y - line_length * 0.8)],
-                        Stroke::new(line_width, color),
+                         Pos2::new(pos_x + dx, pos_y + dy)],
+                        Stroke::new(2.0, color),
                     );
-                    
-                    // Highlight jika dipilih
-                    if is_selected {
-                        let camera_rect = Rect::from_center_size(
-                            Pos2::new(pos_x, pos_y),
-                            Vec2::new(camera_width + 8.0, camera_height + 8.0),
-                        );
-                        
-                        painter.rect_stroke(
-                            camera_rect,
-                            3.0,
-                            Stroke::new(2.0, Color32::YELLOW),
-                        );
-                        
-                        // Tambahkan pulsing glow effect
-                        let time = ui.input(|i| i.time);
-                        let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
-                        let glow_color = Color32::from_rgba_premultiplied(
-                            255, 255, 0, (glow_alpha * 100.0) as u8
-                        );
-                        
-                        painter.rect_stroke(
-                            camera_rect.expand(4.0),
-                            3.0,
-                            Stroke::new(1.0, glow_color),
-                        );
-                    }
-                },
-                2 => { // Directional Light
-                    // Light icon
-                    let light_radius = 10.0 * self.camera_zoom * perspective_zoom;
-                    painter.circle_filled(
+                }
+                
+                // Draw direction arrow for light direction
+                let arrow_length = 30.0 * self.camera_zoom * perspective_zoom;
+                let arrow_dir_x = y_rotation_radians.sin() * arrow_length;
+                let arrow_dir_y = y_rotation_radians.cos() * arrow_length;
+                
+                // Gambar panah utama
+                painter.line_segment(
+                    [Pos2::new(pos_x, pos_y), 
+                     Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y)],
+                    Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                );
+                
+                // Arrow head
+                let head_size = 8.0 * self.camera_zoom * perspective_zoom;
+                let head_angle = 0.5; // ~30 degrees in radians
+                let head1_x = pos_x + arrow_dir_x - head_size * (y_rotation_radians + std::f32::consts::PI - head_angle).cos();
+                let head1_y = pos_y - arrow_dir_y - head_size * (y_rotation_radians + std::f32::consts::PI - head_angle).sin();
+                let head2_x = pos_x + arrow_dir_x - head_size * (y_rotation_radians + std::f32::consts::PI + head_angle).cos();
+                let head2_y = pos_y - arrow_dir_y - head_size * (y_rotation_radians + std::f32::consts::PI + head_angle).sin();
+                
+                painter.line_segment(
+                    [Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y), 
+                     Pos2::new(head1_x, head1_y)],
+                    Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                );
+                
+                painter.line_segment(
+                    [Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y), 
+                     Pos2::new(head2_x, head2_y)],
+                    Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                );
+                
+                // Highlight jika dipilih
+                if is_selected {
+                    // Lingkaran glow di sekitar light source
+                    painter.circle_stroke(
                         Pos2::new(pos_x, pos_y),
-                        light_radius,
-                        color,
-                    );
-                    
-                    // Light rays - rotasi sesuai dengan transform.rotation[1] (Y)
-                    let ray_count = 8;
-                    let ray_length = 20.0 * self.camera_zoom * perspective_zoom;
-                    let y_rotation_radians = transform.rotation[1].to_radians();
-                    
-                    for i in 0..ray_count {
-                        let angle = i as f32 * std::f32::consts::PI * 2.0 / ray_count as f32 + y_rotation_radians;
-                        let dx = angle.cos() * ray_length;
-                        let dy = angle.sin() * ray_length;
-                        
-                        painter.line_segment(
-                            [Pos2::new(pos_x, pos_y), 
-                             Pos2::new(pos_x + dx, pos_y + dy)],
-                            Stroke::new(2.0, color),
-                        );
-                    }
-                    
-                    // Draw direction arrow for light direction
-                    let arrow_length = 30.0 * self.camera_zoom * perspective_zoom;
-                    let arrow_dir_x = y_rotation_radians.sin() * arrow_length;
-                    let arrow_dir_y = y_rotation_radians.cos() * arrow_length;
-                    
-                    // Gambar panah utama
-                    painter.line_segment(
-                        [Pos2::new(pos_x, pos_y), 
-                         Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y)],
-                        Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                        light_radius + 8.0,
+                        Stroke::new(2.0, Color32::YELLOW),
                     );
                     
-                    // Arrow head
-                    let head_size = 8.0 * self.camera_zoom * perspective_zoom;
-                    let head_angle = 0.5; // ~30 degrees in radians
-                    let head1_x = pos_x + arrow_dir_x - head_size * (y_rotation_radians + std::f32::consts::PI - head_angle).cos();
-                    let head1_y = pos_y - arrow_dir_y - head_size * (y_rotation_radians + std::f32::consts::PI - head_angle).sin();
-                    let head2_x = pos_x + arrow_dir_x - head_size * (y_rotation_radians + std::f32::consts::PI + head_angle).cos();
-                    let head2_y = pos_y - arrow_dir_y - head_size * (y_rotation_radians + std::f32::consts::PI + head_angle).sin();
-                    
-                    painter.line_segment(
-                        [Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y), 
-                         Pos2::new(head1_x, head1_y)],
-                        Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                    // Tambahkan pulsing glow effect
+                    let time = ui.input(|i| i.time);
+                    let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
+                    let glow_color = Color32::from_rgba_premultiplied(
+                        255, 255, 0, (glow_alpha * 100.0) as u8
                     );
                     
-                    painter.line_segment(
-                        [Pos2::new(pos_x + arrow_dir_x, pos_y - arrow_dir_y), 
-                         Pos2::new(head2_x, head2_y)],
-                        Stroke::new(3.0, Color32::from_rgb(255, 160, 0)),
+                    painter.circle_stroke(
+                        Pos2::new(pos_x, pos_y),
+                        light_radius + 16.0,
+                        Stroke::new(1.0, glow_color),
                     );
+                }
+            },
+            EntityType::GameObject | EntityType::Primitive(_) => {
+                // GameObject (cube) - dengan rotasi
+                if rotation_radians.abs() < 0.001 {
+                    // Jika tidak ada rotasi, gambar kotak biasa
+                    painter.rect_filled(entity_rect, 0.0, color);
                     
-                    // Highlight jika dipilih
+                    // Draw outline if selected
                     if is_selected {
-                        // Lingkaran glow di sekitar light source
-                        painter.circle_stroke(
-                            Pos2::new(pos_x, pos_y),
-                            light_radius + 8.0,
-                            Stroke::new(2.0, Color32::YELLOW),
+                        painter.rect_stroke(
+                            entity_rect.expand(4.0), 
+                            0.0, 
+                            Stroke::new(2.0, Color32::YELLOW)
                         );
                         
                         // Tambahkan pulsing glow effect
@@ -1431,257 +1708,462 @@ impl SceneViewPanel {
                             255, 255, 0, (glow_alpha * 100.0) as u8
                         );
                         
-                        painter.circle_stroke(
-                            Pos2::new(pos_x, pos_y),
-                            light_radius + 16.0,
-                            Stroke::new(1.0, glow_color),
+                        painter.rect_stroke(
+                            entity_rect.expand(8.0), 
+                            2.0,
+                            Stroke::new(1.0, glow_color)
                         );
                     }
-                },
-                3 => { // Player
-                    // Player (cube) - dengan rotasi
-                    if rotation_radians.abs() < 0.001 {
-                        // Jika tidak ada rotasi, gambar kotak biasa
-                        painter.rect_filled(entity_rect, 0.0, color);
-                        
-                        // Draw outline if selected
-                        if is_selected {
-                            painter.rect_stroke(
-                                entity_rect.expand(4.0), 
-                                0.0, 
-                                Stroke::new(2.0, Color32::YELLOW)
-                            );
-                            
-                            // Tambahkan pulsing glow effect
-                            let time = ui.input(|i| i.time);
-                            let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
-                            let glow_color = Color32::from_rgba_premultiplied(
-                                255, 255, 0, (glow_alpha * 100.0) as u8
-                            );
-                            
-                            painter.rect_stroke(
-                                entity_rect.expand(8.0), 
-                                2.0,
-                                Stroke::new(1.0, glow_color)
-                            );
-                        }
-                    } else {
-                        // Jika ada rotasi, gambar bentuk yang diputar
-                        let half_width = size_x / 2.0;
-                        let half_height = size_y / 2.0;
-                        
-                        // Buat 4 titik sudut kotak
-                        let points = [
-                            rotate_point(-half_width, -half_height, rotation_radians, pos_x, pos_y),
-                            rotate_point(half_width, -half_height, rotation_radians, pos_x, pos_y),
-                            rotate_point(half_width, half_height, rotation_radians, pos_x, pos_y),
-                            rotate_point(-half_width, half_height, rotation_radians, pos_x, pos_y),
-                        ];
-                        
-                        // Gambar poligon
-                        painter.add(egui::Shape::convex_polygon(
-                            points.to_vec(),
-                            color,
-                            Stroke::NONE,
-                        ));
-                        
-                        // Outline untuk objek yang dipilih
-                        if is_selected {
-                            for i in 0..4 {
-                                let j = (i + 1) % 4;
-                                painter.line_segment(
-                                    [points[i], points[j]],
-                                    Stroke::new(2.0, Color32::YELLOW),
-                                );
-                            }
-                            
-                            // Tambahkan pulsing glow effect
-                            let time = ui.input(|i| i.time);
-                            let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
-                            let glow_color = Color32::from_rgba_premultiplied(
-                                255, 255, 0, (glow_alpha * 100.0) as u8
-                            );
-                            
-                            // Gambar garis glow pada sisi luar
-                            for i in 0..4 {
-                                let j = (i + 1) % 4;
-                                painter.line_segment(
-                                    [points[i], points[j]],
-                                    Stroke::new(4.0, glow_color),
-                                );
-                            }
-                        }
-                    }
-                },
-                4 => { // Background
-                    // Background - panjang dan tipis
-                    // Khusus untuk background, kita gambar dengan cara khusus agar terlihat jelas
-                    let bg_width = size_x;
-                    let bg_height = size_y;
+                } else {
+                    // Jika ada rotasi, gambar bentuk yang diputar
+                    let half_width = size_x / 2.0;
+                    let half_height = size_y / 2.0;
                     
-                    let bg_rect = Rect::from_center_size(
-                        Pos2::new(pos_x, pos_y),
-                        Vec2::new(bg_width, bg_height),
-                    );
+                    // Buat 4 titik sudut kotak
+                    let points = [
+                        rotate_point(-half_width, -half_height, rotation_radians, pos_x, pos_y),
+                        rotate_point(half_width, -half_height, rotation_radians, pos_x, pos_y),
+                        rotate_point(half_width, half_height, rotation_radians, pos_x, pos_y),
+                        rotate_point(-half_width, half_height, rotation_radians, pos_x, pos_y),
+                    ];
                     
-                    // Gambar background dengan warna coklat
-                    painter.rect_filled(bg_rect, 0.0, color);
+                    // Gambar poligon
+                    painter.add(egui::Shape::convex_polygon(
+                        points.to_vec(),
+                        color,
+                        Stroke::NONE,
+                    ));
                     
-                    // Draw outline if selected
+                    // Outline untuk objek yang dipilih
                     if is_selected {
-                        // Gambar outline tebal kuning
-                        painter.rect_stroke(
-                            bg_rect, 
-                            0.0, 
-                            Stroke::new(3.0, Color32::YELLOW)
-                        );
+                        for i in 0..4 {
+                            let j = (i + 1) % 4;
+                            painter.line_segment(
+                                [points[i], points[j]],
+                                Stroke::new(2.0, Color32::YELLOW),
+                            );
+                        }
                         
-                        // Tambahkan pulsing glow effect yang lebih jelas
+                        // Tambahkan pulsing glow effect
                         let time = ui.input(|i| i.time);
                         let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
                         let glow_color = Color32::from_rgba_premultiplied(
                             255, 255, 0, (glow_alpha * 100.0) as u8
                         );
                         
-                        painter.rect_stroke(
-                            bg_rect.expand(8.0), 
-                            2.0,
-                            Stroke::new(2.0, glow_color)
-                        );
-                    }
-                },
-                _ => { // Default
-                    // Default shape
-                    painter.rect_filled(entity_rect, 0.0, color);
-                    
-                    // Highlight jika dipilih
-                    if is_selected {
-                        painter.rect_stroke(
-                            entity_rect.expand(4.0),
-                            0.0,
-                            Stroke::new(2.0, Color32::YELLOW)
-                        );
+                        // Gambar garis glow pada sisi luar
+                        for i in 0..4 {
+                            let j = (i + 1) % 4;
+                            painter.line_segment(
+                                [points[i], points[j]],
+                                Stroke::new(4.0, glow_color),
+                            );
+                        }
                     }
                 }
-            }
-            
-            // Draw entity name label if selected or hovered
-            if is_selected {
-                if let Some(name) = self.entity_names.get(&entity_id) {
-                    // Tambahkan background untuk text agar lebih mudah dibaca
-                    let font_id = egui::FontId::proportional(14.0);
-                    let text_galley = ui.painter().layout_no_wrap(
-                        name.clone(),
-                        font_id.clone(),
-                        Color32::WHITE,
-                    );
-                    let label_width = text_galley.rect.width() + 10.0;
-                    let label_height = text_galley.rect.height() + 6.0;
-                    let label_pos = Pos2::new(pos_x, pos_y - size_y/2.0 - 20.0);
-                    
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            label_pos,
-                            Vec2::new(label_width, label_height),
-                        ),
-                        3.0,
-                        Color32::from_rgba_premultiplied(0, 0, 0, 180),
-                    );
-                    
-                    painter.text(
-                        label_pos,
-                        egui::Align2::CENTER_CENTER,
-                        name,
-                        font_id.clone(),
-                        Color32::WHITE,
+            },
+            EntityType::Sprite => {
+                // Sprite - panjang dan tipis
+                // Khusus untuk background, kita gambar dengan cara khusus agar terlihat jelas
+                let bg_width = size_x;
+                let bg_height = size_y;
+                
+                let bg_rect = Rect::from_center_size(
+                    Pos2::new(pos_x, pos_y),
+                    Vec2::new(bg_width, bg_height),
+                );
+                
+                // Gambar background dengan warna coklat
+                painter.rect_filled(bg_rect, 0.0, color);
+                
+                // Draw outline if selected
+                if is_selected {
+                    // Gambar outline tebal kuning
+                    painter.rect_stroke(
+                        bg_rect, 
+                        0.0, 
+                        Stroke::new(3.0, Color32::YELLOW)
                     );
                     
-                    // Tambahkan posisi z pada label dengan font id baru
-                    let z_font_id = egui::FontId::proportional(14.0);
-                    let z_text = format!("z: {:.3}", transform.position[2]);
-                    let z_text_galley = ui.painter().layout_no_wrap(
-                        z_text.clone(),
-                        z_font_id.clone(),
-                        Color32::LIGHT_GRAY,
+                    // Tambahkan pulsing glow effect yang lebih jelas
+                    let time = ui.input(|i| i.time);
+                    let glow_alpha = ((time * 2.0).sin() * 0.5 + 0.5) as f32 * 0.7 + 0.3;
+                    let glow_color = Color32::from_rgba_premultiplied(
+                        255, 255, 0, (glow_alpha * 100.0) as u8
                     );
-                    let z_label_width = z_text_galley.rect.width() + 10.0;
-                    let z_label_pos = Pos2::new(pos_x, pos_y - size_y/2.0 - 40.0);
                     
-                    painter.rect_filled(
-                        Rect::from_center_size(
-                            z_label_pos,
-                            Vec2::new(z_label_width, label_height),
-                        ),
-                        3.0,
-                        Color32::from_rgba_premultiplied(0, 0, 0, 180),
+                    painter.rect_stroke(
+                        bg_rect.expand(8.0), 
+                        2.0,
+                        Stroke::new(2.0, glow_color)
                     );
-                    
-                    painter.text(
-                        z_label_pos,
-                        egui::Align2::CENTER_CENTER,
-                        z_text,
-                        z_font_id,
-                        Color32::LIGHT_GRAY,
+                }
+            },
+            _ => { // Default
+                // Default shape
+                painter.rect_filled(entity_rect, 0.0, color);
+                
+                // Highlight jika dipilih
+                if is_selected {
+                    painter.rect_stroke(
+                        entity_rect.expand(4.0),
+                        0.0,
+                        Stroke::new(2.0, Color32::YELLOW)
                     );
                 }
             }
         }
-    }
-    
-    /// Check if a point is inside an entity
-    pub fn is_point_in_entity(&self, point: Pos2, rect: Rect, entity_id: u32) -> bool {
-        if let Some(transform) = self.entity_transforms.lock().unwrap().get(&entity_id) {
-            let scale_factor = 50.0;
-            let center_x = rect.center().x;
-            let center_y = rect.center().y;
-            
-            let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
-            let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
-            
-            let base_size = 30.0 * self.camera_zoom;
-            let entity_size_x = base_size * transform.scale[0];
-            let entity_size_y = base_size * transform.scale[1];
-            
-            let rotation_radians = transform.rotation[2].to_radians();
-            
-            // Jika ada rotasi, perlu pengecekan yang lebih kompleks
-            if rotation_radians.abs() > 0.001 {
-                // Rotasi titik point relatif terhadap pusat entity
-                let dx = point.x - pos_x;
-                let dy = point.y - pos_y;
+        
+        // Draw entity name label if selected or hovered
+        if is_selected {
+            if let Some(name) = self.entity_names.get(&entity_id) {
+                // Tambahkan background untuk text agar lebih mudah dibaca
+                let font_id = egui::FontId::proportional(14.0);
+                let text_galley = ui.painter().layout_no_wrap(
+                    name.clone(),
+                    font_id.clone(),
+                    Color32::WHITE,
+                );
+                let label_width = text_galley.rect.width() + 10.0;
+                let label_height = text_galley.rect.height() + 6.0;
+                let label_pos = Pos2::new(pos_x, pos_y - size_y/2.0 - 20.0);
                 
-                // Rotasi balik untuk mengembalikan ke koordinat lokal
-                let cos_rot = rotation_radians.cos();
-                let sin_rot = rotation_radians.sin();
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        label_pos,
+                        Vec2::new(label_width, label_height),
+                    ),
+                    3.0,
+                    Color32::from_rgba_premultiplied(0, 0, 0, 180),
+                );
                 
-                let rotated_x = dx * cos_rot + dy * sin_rot;
-                let rotated_y = -dx * sin_rot + dy * cos_rot;
+                painter.text(
+                    label_pos,
+                    egui::Align2::CENTER_CENTER,
+                    name,
+                    font_id.clone(),
+                    Color32::WHITE,
+                );
                 
-                // Cek apakah titik yang dirotasi ada di dalam kotak
-                return rotated_x.abs() <= entity_size_x / 2.0 && rotated_y.abs() <= entity_size_y / 2.0;
-            } else {
-                // Tanpa rotasi, gunakan Rect biasa
-                let entity_rect = Rect::from_center_size(
-                    Pos2::new(pos_x, pos_y),
-                    Vec2::new(entity_size_x, entity_size_y),
+                // Tambahkan posisi z pada label dengan font id baru
+                let z_font_id = egui::FontId::proportional(14.0);
+                let z_text = format!("z: {:.3}", transform.position[2]);
+                let z_text_galley = ui.painter().layout_no_wrap(
+                    z_text.clone(),
+                    z_font_id.clone(),
+                    Color32::LIGHT_GRAY,
+                );
+                let z_label_width = z_text_galley.rect.width() + 10.0;
+                let z_label_pos = Pos2::new(pos_x, pos_y - size_y/2.0 - 40.0);
+                
+                painter.rect_filled(
+                    Rect::from_center_size(
+                        z_label_pos,
+                        Vec2::new(z_label_width, label_height),
+                    ),
+                    3.0,
+                    Color32::from_rgba_premultiplied(0, 0, 0, 180),
                 );
                 
-                return entity_rect.contains(point);
+                painter.text(
+                    z_label_pos,
+                    egui::Align2::CENTER_CENTER,
+                    z_text,
+                    z_font_id,
+                    Color32::LIGHT_GRAY,
+                );
             }
         }
+    }
+    
+    /// Check if a point is inside an entity
+    pub fn is_point_in_entity(&self, point: Pos2, rect: Rect, transform: &EntityTransform) -> bool {
+        let scale_factor = 50.0;
+        let center_x = rect.center().x;
+        let center_y = rect.center().y;
+        
+        let pos_x = center_x + (transform.position[0] + self.camera_position[0]) * scale_factor * self.camera_zoom;
+        let pos_y = center_y - (transform.position[1] + self.camera_position[1]) * scale_factor * self.camera_zoom;
+        
+        // Same size as `draw_entity`, so clicks line up with what's drawn
+        let base_size = ENTITY_BASE_SIZE * self.camera_zoom * perspective_scale(transform.position[2]);
+        let entity_size_x = base_size * transform.scale[0].abs();
+        let entity_size_y = base_size * transform.scale[1].abs();
         
-        false
+        let rotation_radians = transform.rotation[2].to_radians();
+        
+        // Jika ada rotasi, perlu pengecekan yang lebih kompleks
+        if rotation_radians.abs() > 0.001 {
+            // Rotasi titik point relatif terhadap pusat entity
+            let dx = point.x - pos_x;
+            let dy = point.y - pos_y;
+            
+            // Rotasi balik untuk mengembalikan ke koordinat lokal
+            let local = math::rotate_2d(math::Vec2::new(dx, dy), -rotation_radians);
+            
+            // Cek apakah titik yang dirotasi ada di dalam kotak
+            local.x.abs() <= entity_size_x / 2.0 && local.y.abs() <= entity_size_y / 2.0
+        } else {
+            // Tanpa rotasi, gunakan Rect biasa
+            let entity_rect = Rect::from_center_size(
+                Pos2::new(pos_x, pos_y),
+                Vec2::new(entity_size_x, entity_size_y),
+            );
+            
+            entity_rect.contains(point)
+        }
     }
     
+    /// The front-most entity under a point, the one with the largest Z
+    ///
+    /// Entities at the same Z are ordered by UUID, the same order they're drawn in.
+    pub fn pick_entity(&self, ecs: &EcsManager, point: Pos2, rect: Rect) -> Option<EntityHandle> {
+        entity_transforms(ecs).into_iter()
+            .filter(|(id, _)| !self.inactive_entities.contains(id))
+            .filter(|(_, transform)| self.is_point_in_entity(point, rect, transform))
+            .max_by(|a, b| a.1.position[2].total_cmp(&b.1.position[2]).then(a.0.uuid.cmp(&b.0.uuid)))
+            .map(|(id, _)| id)
+    }
+}
+
+/// Every entity with a `Transform3DComponent`, with its transform
+fn entity_transforms(ecs: &EcsManager) -> Vec<(EntityHandle, EntityTransform)> {
+    ecs.query::<&Transform3DComponent>().iter()
+        .map(|(handle, transform)| (handle, EntityTransform::from_component(transform)))
+        .collect()
+}
+
+/// Zoom multiplier for a frame's scroll and pinch input
+///
+/// Mouse wheels and trackpads both scroll, trackpad pinches and Ctrl+scroll arrive
+/// as `zoom_delta` instead, so each device zooms by the same amount for the same motion.
+fn zoom_factor(scroll_delta: f32, zoom_delta: f32) -> f32 {
+    (scroll_delta * SCROLL_ZOOM_RATE).exp() * zoom_delta
+}
+
+/// Drawn size of an entity at depth `z` relative to its size at Z 0
+fn perspective_scale(z: f32) -> f32 {
+    (1.0 + z * PERSPECTIVE_PER_Z).max(MIN_PERSPECTIVE)
 }
 
 /// Rotate a point around the origin
 fn rotate_point(x: f32, y: f32, angle: f32, center_x: f32, center_y: f32) -> Pos2 {
-    let cos_a = angle.cos();
-    let sin_a = angle.sin();
+    let rotated = math::rotate_2d(math::Vec2::new(x, y), angle);
+    Pos2::new(center_x + rotated.x, center_y + rotated.y)
+} 
+/// World space bounds of an entity as drawn in the scene view, including its rotation
+fn entity_world_bounds(transform: &EntityTransform) -> ([f32; 2], [f32; 2]) {
+    let half_size = math::Vec2::new(transform.scale[0].abs(), transform.scale[1].abs())
+        * ENTITY_BASE_SIZE / PIXELS_PER_UNIT / 2.0;
+    let extents = math::rotated_extents(half_size, transform.rotation[2].to_radians());
+    
+    let [x, y, _] = transform.position;
+    ([x - extents.x, y - extents.y], [x + extents.x, y + extents.y])
+}
+
+/// Screen end points of the X, Y and Z gizmo axes, Z is drawn diagonally and shorter for perspective
+fn gizmo_axis_ends(center: Pos2, axis_length: f32) -> [(GizmoAxis, Pos2); 3] {
+    let z_offset = axis_length * 0.7 * 0.7;
+    [
+        (GizmoAxis::X, center + Vec2::new(axis_length, 0.0)),
+        (GizmoAxis::Y, center - Vec2::new(0.0, axis_length)),
+        (GizmoAxis::Z, center + Vec2::new(z_offset, -z_offset)),
+    ]
+}
+
+/// Radii of the X, Y and Z rotation rings, Y and Z are flattened for perspective
+fn gizmo_ring_radii(radius: f32) -> [(GizmoAxis, Vec2); 3] {
+    [
+        (GizmoAxis::X, Vec2::splat(radius)),
+        (GizmoAxis::Y, Vec2::new(radius * 0.5, radius)),
+        (GizmoAxis::Z, Vec2::new(radius, radius * 0.5)),
+    ]
+}
+
+/// Color of a gizmo axis, lighter when highlighted
+fn gizmo_axis_color(axis: GizmoAxis, highlighted: bool) -> Color32 {
+    match (axis, highlighted) {
+        (GizmoAxis::X, false) => Color32::from_rgb(255, 0, 0),
+        (GizmoAxis::X, true) => Color32::from_rgb(255, 100, 100),
+        (GizmoAxis::Y, false) => Color32::from_rgb(0, 255, 0),
+        (GizmoAxis::Y, true) => Color32::from_rgb(100, 255, 100),
+        (GizmoAxis::Z, false) => Color32::from_rgb(0, 0, 255),
+        (GizmoAxis::Z, true) => Color32::from_rgb(100, 100, 255),
+    }
+}
+
+/// Distance from `point` to the segment from `a` to `b`
+fn distance_to_segment(point: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.length_sq();
+    if length_sq <= f32::EPSILON {
+        return point.distance(a);
+    }
     
-    let rotated_x = x * cos_a - y * sin_a;
-    let rotated_y = x * sin_a + y * cos_a;
+    let t = ((point - a).dot(ab) / length_sq).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// Approximate distance from `point` to the outline of an axis-aligned ellipse
+///
+/// Measured along the line from the center through `point`, which is exact for circles.
+fn distance_to_ellipse(point: Pos2, center: Pos2, radii: Vec2) -> f32 {
+    let offset = point - center;
+    let normalized = Vec2::new(offset.x / radii.x, offset.y / radii.y).length();
+    if normalized <= f32::EPSILON {
+        return radii.min_elem();
+    }
     
-    Pos2::new(center_x + rotated_x, center_y + rotated_y)
-} 
\ No newline at end of file
+    offset.length() * (1.0 - 1.0 / normalized).abs()
+}
+
+/// The axis line closest to `point`, if one is within `GIZMO_HIT_TOLERANCE`
+fn nearest_gizmo_axis(point: Pos2, center: Pos2, axis_length: f32) -> Option<GizmoAxis> {
+    gizmo_axis_ends(center, axis_length).into_iter()
+        .map(|(axis, end)| (axis, distance_to_segment(point, center, end)))
+        .filter(|(_, distance)| *distance <= GIZMO_HIT_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(axis, _)| axis)
+}
+
+/// Move gizmo axis under `point`
+///
+/// The axes all meet at the center, so the center itself moves freely and returns `None`.
+fn hit_move_gizmo(point: Pos2, center: Pos2, axis_length: f32) -> Option<GizmoAxis> {
+    if point.distance(center) < GIZMO_HIT_TOLERANCE {
+        return None;
+    }
+    nearest_gizmo_axis(point, center, axis_length)
+}
+
+/// Rotation ring closest to `point`, if one is within `GIZMO_HIT_TOLERANCE`
+fn hit_rotate_gizmo(point: Pos2, center: Pos2, radius: f32) -> Option<GizmoAxis> {
+    gizmo_ring_radii(radius).into_iter()
+        .map(|(axis, radii)| (axis, distance_to_ellipse(point, center, radii)))
+        .filter(|(_, distance)| *distance <= GIZMO_HIT_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(axis, _)| axis)
+}
+
+/// Pointer distance to a light's range circle or handle, `None` if neither is within reach
+///
+/// The handle sits on the right of the circle.
+fn hit_light_range(point: Pos2, center: Pos2, radius: f32) -> Option<f32> {
+    let handle = Rect::from_center_size(center + Vec2::new(radius, 0.0), Vec2::splat(LIGHT_HANDLE_SIZE)).expand(2.0);
+    if handle.contains(point) {
+        return Some(0.0);
+    }
+    let distance = (point.distance(center) - radius).abs();
+    (distance <= GIZMO_HIT_TOLERANCE).then_some(distance)
+}
+
+/// Scale gizmo handle under `point`: the center box, an axis box or an axis line
+fn hit_scale_gizmo(point: Pos2, center: Pos2, axis_length: f32, box_size: f32) -> Option<GizmoHandle> {
+    let handle_rect = |position: Pos2| Rect::from_center_size(position, Vec2::splat(box_size)).expand(2.0);
+    
+    if handle_rect(center).contains(point) {
+        return Some(GizmoHandle::Center);
+    }
+    
+    gizmo_axis_ends(center, axis_length).into_iter()
+        .find(|(_, end)| handle_rect(*end).contains(point))
+        .map(|(axis, _)| axis)
+        .or_else(|| nearest_gizmo_axis(point, center, axis_length))
+        .map(GizmoHandle::Axis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTER: Pos2 = Pos2::new(100.0, 100.0);
+
+    /// Point on an axis aligned ellipse around `CENTER` at `degrees` counter-clockwise from the right
+    fn on_ring(radii: Vec2, degrees: f32) -> Pos2 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        CENTER + Vec2::new(radii.x * cos, -radii.y * sin)
+    }
+
+    #[test]
+    fn rotate_gizmo_picks_the_ring_under_the_pointer() {
+        let radius = 40.0;
+        assert_eq!(hit_rotate_gizmo(on_ring(Vec2::splat(radius), 45.0), CENTER, radius), Some(GizmoAxis::X));
+        assert_eq!(hit_rotate_gizmo(on_ring(Vec2::new(radius * 0.5, radius), 45.0), CENTER, radius), Some(GizmoAxis::Y));
+        assert_eq!(hit_rotate_gizmo(on_ring(Vec2::new(radius, radius * 0.5), -45.0), CENTER, radius), Some(GizmoAxis::Z));
+    }
+
+    #[test]
+    fn rotate_gizmo_tolerates_a_near_miss() {
+        let radius = 40.0;
+        let near = on_ring(Vec2::splat(radius + GIZMO_HIT_TOLERANCE - 1.0), 45.0);
+        assert_eq!(hit_rotate_gizmo(near, CENTER, radius), Some(GizmoAxis::X));
+        let far = on_ring(Vec2::splat(radius + GIZMO_HIT_TOLERANCE + 2.0), 45.0);
+        assert_eq!(hit_rotate_gizmo(far, CENTER, radius), None);
+        assert_eq!(hit_rotate_gizmo(CENTER, CENTER, radius), None);
+    }
+
+    #[test]
+    fn scale_gizmo_picks_the_center_and_axis_boxes() {
+        let (axis_length, box_size) = (40.0, 10.0);
+        assert_eq!(hit_scale_gizmo(CENTER, CENTER, axis_length, box_size), Some(GizmoHandle::Center));
+        assert_eq!(hit_scale_gizmo(CENTER + Vec2::splat(6.0), CENTER, axis_length, box_size), Some(GizmoHandle::Center));
+        for (axis, end) in gizmo_axis_ends(CENTER, axis_length) {
+            assert_eq!(hit_scale_gizmo(end, CENTER, axis_length, box_size), Some(GizmoHandle::Axis(axis)));
+        }
+    }
+
+    #[test]
+    fn scale_gizmo_picks_axis_lines_and_misses_empty_space() {
+        let (axis_length, box_size) = (40.0, 10.0);
+        let on_x = CENTER + Vec2::new(20.0, 3.0);
+        assert_eq!(hit_scale_gizmo(on_x, CENTER, axis_length, box_size), Some(GizmoHandle::Axis(GizmoAxis::X)));
+        let on_y = CENTER + Vec2::new(-3.0, -25.0);
+        assert_eq!(hit_scale_gizmo(on_y, CENTER, axis_length, box_size), Some(GizmoHandle::Axis(GizmoAxis::Y)));
+        assert_eq!(hit_scale_gizmo(CENTER + Vec2::new(-30.0, 30.0), CENTER, axis_length, box_size), None);
+        assert_eq!(hit_scale_gizmo(CENTER + Vec2::new(60.0, 0.0), CENTER, axis_length, box_size), None);
+    }
+
+    /// Scene view panel over an 800x600 rect, entities at the origin are drawn at its center
+    fn panel() -> (SceneViewPanel, Rect) {
+        (SceneViewPanel::new(), Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 600.0)))
+    }
+
+    fn spawn_at(ecs: &mut EcsManager, position: [f32; 3]) -> EntityHandle {
+        ecs.create_entity((Transform3DComponent::new(position.into(), glam::Quat::IDENTITY, glam::Vec3::ONE),))
+    }
+
+    #[test]
+    fn pick_entity_picks_the_front_most_of_stacked_entities() {
+        let (panel, rect) = panel();
+        let mut ecs = EcsManager::new();
+        spawn_at(&mut ecs, [0.0, 0.0, 0.0]);
+        let front = spawn_at(&mut ecs, [0.1, 0.0, 2.0]);
+        spawn_at(&mut ecs, [0.0, 0.1, 1.0]);
+
+        assert_eq!(panel.pick_entity(&ecs, rect.center(), rect), Some(front));
+    }
+
+    #[test]
+    fn pick_entity_skips_inactive_entities_and_empty_space() {
+        let (mut panel, rect) = panel();
+        let mut ecs = EcsManager::new();
+        let back = spawn_at(&mut ecs, [0.0, 0.0, 0.0]);
+        let front = spawn_at(&mut ecs, [0.0, 0.0, 5.0]);
+        panel.set_inactive_entities(HashSet::from([front]));
+
+        assert_eq!(panel.pick_entity(&ecs, rect.center(), rect), Some(back));
+        assert_eq!(panel.pick_entity(&ecs, rect.center() + Vec2::new(300.0, 0.0), rect), None);
+    }
+
+    #[test]
+    fn pick_entity_breaks_ties_the_same_way_every_time() {
+        let (panel, rect) = panel();
+        let mut ecs = EcsManager::new();
+        let handles = [spawn_at(&mut ecs, [0.0, 0.0, 1.0]), spawn_at(&mut ecs, [0.0, 0.0, 1.0])];
+        let expected = handles.iter().max_by_key(|handle| handle.uuid).copied();
+
+        for _ in 0..3 {
+            assert_eq!(panel.pick_entity(&ecs, rect.center(), rect), expected);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use egui::{Color32, Context, Id, RichText, ScrollArea, TextStyle};
+use crate::scripting::{ScriptSyntaxError, check_syntax};
+
+/// What to do once the user answers the unsaved changes prompt
+#[derive(Debug, Clone, PartialEq)]
+enum PendingAction {
+    Close,
+    Open(PathBuf),
+}
+
+/// Window for editing Lua scripts
+///
+/// Edits one script at a time. The script is checked for syntax errors as it
+/// changes and saved with Ctrl+S; the script system reloads it once saved.
+pub struct ScriptEditorPanel {
+    /// Whether the window is shown
+    pub open: bool,
+    /// File being edited
+    path: Option<PathBuf>,
+    text: String,
+    /// Text as last loaded or saved
+    saved_text: String,
+    /// Syntax error in `text`, if any
+    syntax_error: Option<ScriptSyntaxError>,
+    /// Action waiting for the user to save or discard unsaved changes
+    pending: Option<PendingAction>,
+    /// Errors not yet shown in the console
+    errors: Vec<String>,
+}
+
+impl ScriptEditorPanel {
+    /// Create a script editor with no script open
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            path: None,
+            text: String::new(),
+            saved_text: String::new(),
+            syntax_error: None,
+            pending: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Ask the editor to open a script, from UI code that can't reach the panel
+    pub fn request_open(ctx: &Context, path: &Path) {
+        ctx.data_mut(|data| data.insert_temp(Self::request_id(), path.to_path_buf()));
+    }
+
+    /// The script last passed to `request_open`, if any
+    pub fn take_open_request(ctx: &Context) -> Option<PathBuf> {
+        ctx.data_mut(|data| {
+            let path = data.get_temp::<PathBuf>(Self::request_id());
+            data.remove::<PathBuf>(Self::request_id());
+            path
+        })
+    }
+
+    fn request_id() -> Id {
+        Id::new("script_editor_open_request")
+    }
+
+    /// The script being edited
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Whether the script has changes that aren't saved
+    pub fn is_dirty(&self) -> bool {
+        self.text != self.saved_text
+    }
+
+    /// Open a script, asking first if the current one has unsaved changes
+    pub fn open_script(&mut self, path: PathBuf, log_info: &mut dyn FnMut(&str)) {
+        self.open = true;
+        if self.path.as_deref() == Some(path.as_path()) {
+            return;
+        }
+        if self.is_dirty() {
+            self.pending = Some(PendingAction::Open(path));
+            return;
+        }
+        self.load(path, log_info);
+    }
+
+    /// Save the script to its file
+    pub fn save(&mut self, log_info: &mut dyn FnMut(&str)) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+        match fs::write(path, &self.text) {
+            Ok(()) => {
+                self.saved_text = self.text.clone();
+                log_info(&format!("Saved script: {}", path.display()));
+                true
+            }
+            Err(e) => {
+                self.errors.push(format!("Failed to save {}: {}", path.display(), e));
+                false
+            }
+        }
+    }
+
+    /// Take the errors reported since the last call
+    pub fn take_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Render the editor window and the unsaved changes prompt
+    pub fn render(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        if !self.open {
+            return;
+        }
+
+        let title = match &self.path {
+            Some(path) => {
+                let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                if self.is_dirty() { format!("{} *", name) } else { name }
+            }
+            None => "Script Editor".to_string(),
+        };
+
+        let mut open = true;
+        egui::Window::new(RichText::new(title))
+            .id(Id::new("script_editor_window"))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let can_save = self.path.is_some() && self.is_dirty();
+                    if ui.add_enabled(can_save, egui::Button::new("Save")).on_hover_text("Ctrl+S").clicked() {
+                        self.save(log_info);
+                    }
+                    if ui.add_enabled(self.path.is_some(), egui::Button::new("Revert")).clicked() {
+                        self.text = self.saved_text.clone();
+                        self.check();
+                    }
+                    if let Some(path) = &self.path {
+                        ui.label(RichText::new(path.display().to_string()).weak());
+                    }
+                });
+
+                ui.separator();
+
+                // The status bar goes first so it keeps its space below the text
+                egui::TopBottomPanel::bottom("script_editor_status")
+                    .show_inside(ui, |ui| self.render_status(ui));
+
+                self.render_text(ui, log_info);
+            });
+
+        // Closing with the window's X goes through the same prompt as everything else
+        if !open {
+            if self.is_dirty() {
+                self.pending = Some(PendingAction::Close);
+            } else {
+                self.close();
+            }
+        }
+
+        self.render_unsaved_prompt(ctx, log_info);
+    }
+
+    /// Text area with line numbers
+    fn render_text(&mut self, ui: &mut egui::Ui, log_info: &mut dyn FnMut(&str)) {
+        let text_id = Id::new("script_editor_text");
+
+        // Ctrl+S saves the script while it's being edited, rather than the scene
+        let focused = ui.memory(|memory| memory.has_focus(text_id));
+        if focused && ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::S)) {
+            self.save(log_info);
+        }
+
+        let line_count = self.text.split('\n').count();
+        let error_line = self.syntax_error.as_ref().and_then(|error| error.line);
+
+        ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                // Line numbers, one monospace label per line so they line up with the text
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing.y = 0.0;
+                    // Match the text edit's top margin
+                    ui.add_space(2.0);
+                    let width = line_count.to_string().len();
+                    for line in 1..=line_count {
+                        let mut number = RichText::new(format!("{:>width$}", line, width = width))
+                            .text_style(TextStyle::Monospace);
+                        number = if Some(line) == error_line {
+                            number.color(Color32::from_rgb(255, 90, 90)).strong()
+                        } else {
+                            number.weak()
+                        };
+                        ui.label(number);
+                    }
+                });
+
+                let response = ui.add(
+                    egui::TextEdit::multiline(&mut self.text)
+                        .id(text_id)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(line_count.max(20))
+                        .lock_focus(true)
+                        .interactive(self.path.is_some()),
+                );
+                if response.changed() {
+                    self.check();
+                }
+            });
+        });
+    }
+
+    /// Status bar with the syntax check result
+    fn render_status(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            match (&self.path, &self.syntax_error) {
+                (None, _) => {
+                    ui.label(RichText::new("No script open").weak());
+                }
+                (Some(_), Some(error)) => {
+                    ui.label(RichText::new(format!("⚠ {}", error.message)).color(Color32::from_rgb(255, 90, 90)));
+                }
+                (Some(_), None) => {
+                    ui.label(RichText::new("✔ No syntax errors").color(Color32::from_rgb(0, 200, 100)));
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if self.is_dirty() {
+                    ui.label("Modified");
+                }
+            });
+        });
+    }
+
+    /// Ask whether to save changes before closing or switching scripts
+    fn render_unsaved_prompt(&mut self, ctx: &Context, log_info: &mut dyn FnMut(&str)) {
+        let Some(action) = self.pending.clone() else {
+            return;
+        };
+
+        let name = self.path.as_ref().map_or_else(String::new, |path| path.display().to_string());
+        let mut answer = None;
+        egui::Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Save changes to {}?", name));
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button("Don't Save").clicked() {
+                        answer = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending = None;
+                    }
+                });
+            });
+
+        let Some(save) = answer else {
+            return;
+        };
+        // Keep the script open if it couldn't be saved
+        if save && !self.save(log_info) {
+            self.pending = None;
+            return;
+        }
+
+        self.pending = None;
+        match action {
+            PendingAction::Close => self.close(),
+            PendingAction::Open(path) => self.load(path, log_info),
+        }
+    }
+
+    /// Replace the edited script with the file at `path`
+    fn load(&mut self, path: PathBuf, log_info: &mut dyn FnMut(&str)) {
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                log_info(&format!("Opened script: {}", path.display()));
+                self.text = text.clone();
+                self.saved_text = text;
+                self.path = Some(path);
+                self.check();
+            }
+            Err(e) => self.errors.push(format!("Failed to open {}: {}", path.display(), e)),
+        }
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        self.path = None;
+        self.text.clear();
+        self.saved_text.clear();
+        self.syntax_error = None;
+    }
+
+    /// Check the text for syntax errors
+    fn check(&mut self) {
+        let name = self.path.as_ref()
+            .and_then(|path| path.file_name())
+            .map_or_else(|| "script".to_string(), |name| name.to_string_lossy().into_owned());
+        self.syntax_error = check_syntax(&self.text, &name).err();
+    }
+}
+
+impl Default for ScriptEditorPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::HashSet;
 use egui::{Color32, RichText};
+use crate::utils::math::{self, Mat4, Quat, Vec3};
 use crate::audio::AudioCategory;
+use crate::assets::MATERIAL_EXTENSION;
+use crate::ecs::{EcsManager, EntityHandle, Transform3DComponent, PREFAB_EXTENSION};
 
 /// Console log level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +18,8 @@ pub enum LogLevel {
 pub struct ConsoleLog {
     pub timestamp: String,
     pub level: LogLevel,
+    /// Module that logged the message through the `log` crate, `None` for editor messages
+    pub target: Option<String>,
     pub message: String,
 }
 
@@ -33,63 +39,63 @@ pub enum ProjectFileType {
     Script,
     Texture,
     Audio,
+    Prefab,
+    Material,
     Other,
 }
 
+impl ProjectFileType {
+    /// Classify a file by its extension
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_ascii_lowercase().as_str() {
+            "scene" => ProjectFileType::Scene,
+            "lua" => ProjectFileType::Script,
+            "png" | "jpg" | "jpeg" => ProjectFileType::Texture,
+            "mp3" | "wav" | "ogg" => ProjectFileType::Audio,
+            extension if extension == PREFAB_EXTENSION => ProjectFileType::Prefab,
+            extension if extension == MATERIAL_EXTENSION => ProjectFileType::Material,
+            _ => ProjectFileType::Other,
+        }
+    }
+}
+
 /// Project file
 #[derive(Debug, Clone)]
 pub struct ProjectFile {
     pub name: String,
     pub file_type: ProjectFileType,
+    /// Path relative to the project root, starting with `/`
     pub path: String,
     pub children: Vec<ProjectFile>,
     pub expanded: bool,
+    /// Whether the children of a folder have been read from disk
+    pub loaded: bool,
     pub parent_path: Option<String>,
 }
 
-/// Component type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ComponentType {
-    Transform,
-    Camera,
-    Light,
-    SpriteRenderer,
-    Rigidbody2D,
-    BoxCollider2D,
-    LuaScript,
-    AudioSource,
-    AudioListener,
-}
-
-/// Entity component
-#[derive(Debug, Clone)]
-pub struct EntityComponent {
-    pub name: String,
-    pub component_type: ComponentType,
-    pub removable: bool,
-}
-
-/// Entity transform component
+/// Editor view of an entity's `Transform3DComponent`
+///
+/// The rotation is kept as a quaternion, `rotation` holds the Euler angles the
+/// inspector shows. Editing those angles directly changes the quaternion, while
+/// a rotation that isn't touched is used as is rather than converted back and forth.
+/// Edits only reach the entity once written back with `write_to`.
 #[derive(Clone, Debug)]
 pub struct EntityTransform {
     /// Position in 3D space [x, y, z]
     pub position: [f32; 3],
-    /// Rotation in degrees [x, y, z]
+    /// Rotation in degrees [x, y, z], see `orientation`
     pub rotation: [f32; 3],
     /// Scale in 3D space [x, y, z]
     pub scale: [f32; 3],
-    /// Last update timestamp - untuk mendeteksi perubahan
-    pub last_update: f64,
+    /// Rotation of the entity while `rotation` is still `orientation_euler`
+    orientation: Quat,
+    /// Euler angles `orientation` was last set along with
+    orientation_euler: [f32; 3],
 }
 
 impl Default for EntityTransform {
     fn default() -> Self {
-        Self {
-            position: [0.0, 0.0, 0.0],
-            rotation: [0.0, 0.0, 0.0],
-            scale: [1.0, 1.0, 1.0],
-            last_update: 0.0,
-        }
+        Self::new([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0])
     }
 }
 
@@ -100,7 +106,21 @@ impl EntityTransform {
             position,
             rotation,
             scale,
-            last_update: 0.0,
+            orientation: math::quat_from_euler_degrees(rotation),
+            orientation_euler: rotation,
+        }
+    }
+    
+    /// Create a transform with a quaternion rotation
+    pub fn with_orientation(position: [f32; 3], orientation: Quat, scale: [f32; 3]) -> Self {
+        let orientation = orientation.normalize();
+        let rotation = math::euler_degrees_from_quat(orientation);
+        Self {
+            position,
+            rotation,
+            scale,
+            orientation,
+            orientation_euler: rotation,
         }
     }
     
@@ -112,7 +132,6 @@ impl EntityTransform {
     /// Set position vector
     pub fn set_position(&mut self, position: [f32; 3]) {
         self.position = position;
-        self.update_timestamp();
     }
     
     /// Get rotation in degrees
@@ -123,7 +142,32 @@ impl EntityTransform {
     /// Set rotation in degrees
     pub fn set_rotation(&mut self, rotation: [f32; 3]) {
         self.rotation = rotation;
-        self.update_timestamp();
+    }
+    
+    /// Rotation of the entity
+    ///
+    /// Computed from the Euler angles when they were changed since the
+    /// quaternion was last set.
+    pub fn orientation(&self) -> Quat {
+        if self.rotation == self.orientation_euler {
+            self.orientation
+        } else {
+            math::quat_from_euler_degrees(self.rotation)
+        }
+    }
+    
+    /// Set the rotation from a quaternion, updating the Euler angles to match
+    pub fn set_orientation(&mut self, orientation: Quat) {
+        self.orientation = orientation.normalize();
+        self.rotation = math::euler_degrees_from_quat(self.orientation);
+        self.orientation_euler = self.rotation;
+    }
+    
+    /// Rotate by `degrees` around a world space axis
+    pub fn rotate_axis(&mut self, axis: Vec3, degrees: f32) {
+        if let Some(axis) = axis.try_normalize() {
+            self.set_orientation(Quat::from_axis_angle(axis, degrees.to_radians()) * self.orientation());
+        }
     }
     
     /// Get scale vector
@@ -134,22 +178,66 @@ impl EntityTransform {
     /// Set scale vector
     pub fn set_scale(&mut self, scale: [f32; 3]) {
         self.scale = scale;
-        self.update_timestamp();
     }
     
-    /// Update timestamp to current time
-    fn update_timestamp(&mut self) {
-        self.last_update = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs_f64();
+    /// Offset this transform by the change from `before` to `after`
+    ///
+    /// Used to apply one edit to several selected entities at once. Components
+    /// that didn't change are left untouched. A rotation is applied as the same
+    /// turn in world space, not by adding Euler angles.
+    pub fn apply_delta(&mut self, before: &EntityTransform, after: &EntityTransform) {
+        if after.rotation != before.rotation {
+            let turn = after.orientation() * before.orientation().inverse();
+            self.set_orientation(turn * self.orientation());
+        }
+        
+        for i in 0..3 {
+            let position = after.position[i] - before.position[i];
+            if position != 0.0 {
+                self.position[i] += position;
+            }
+            
+            let scale = after.scale[i] - before.scale[i];
+            if scale != 0.0 {
+                self.scale[i] = (self.scale[i] + scale).max(0.1);
+            }
+        }
+    }
+    
+    /// The transform of an entity, `None` if it has no `Transform3DComponent`
+    pub fn of(ecs: &EcsManager, handle: &EntityHandle) -> Option<Self> {
+        ecs.get_component::<Transform3DComponent>(handle).map(|component| Self::from_component(&component))
+    }
+    
+    /// Write the transform to an entity's `Transform3DComponent`, returning false if the entity is gone
+    pub fn write_to(&self, ecs: &mut EcsManager, handle: &EntityHandle) -> bool {
+        ecs.insert_component(handle, self.to_component())
+    }
+    
+    /// Create an editor transform from an ECS transform component
+    pub fn from_component(component: &Transform3DComponent) -> Self {
+        Self::with_orientation(component.position.to_array(), component.rotation, component.scale.to_array())
+    }
+    
+    /// Convert the editor transform to an ECS transform component
+    pub fn to_component(&self) -> Transform3DComponent {
+        Transform3DComponent::new(Vec3::from(self.position), self.orientation(), Vec3::from(self.scale))
+    }
+    
+    /// Get the transformation matrix
+    pub fn to_matrix(&self) -> Mat4 {
+        math::compose(
+            Vec3::from(self.position),
+            self.orientation(),
+            Vec3::from(self.scale),
+        )
     }
 }
 
 /// Hierarchy item for representing entity hierarchies
 #[derive(Debug, Clone)]
 pub struct HierarchyItem {
-    pub id: u32,
+    pub id: EntityHandle,
     pub name: String,
     pub children: Vec<HierarchyItem>,
 }
@@ -199,6 +287,91 @@ pub mod helpers {
     pub fn get_log_text(log: &ConsoleLog) -> RichText {
         let color = get_log_color(log.level);
         let prefix = get_log_prefix(log.level);
-        RichText::new(format!("[{}] [{}] {}", log.timestamp, prefix, log.message)).color(color)
+        let text = match &log.target {
+            Some(target) => format!("[{}] [{}] {}: {}", log.timestamp, prefix, target, log.message),
+            None => format!("[{}] [{}] {}", log.timestamp, prefix, log.message),
+        };
+        RichText::new(text).color(color)
+    }
+} 
+/// The selected entities, with the most recently selected one as the primary
+///
+/// The primary entity is the one the inspector shows and the gizmo is drawn on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    entities: HashSet<EntityHandle>,
+    primary: Option<EntityHandle>,
+}
+
+impl Selection {
+    /// Create an empty selection
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Replace the selection with a single entity, or clear it with `None`
+    pub fn set(&mut self, handle: Option<EntityHandle>) {
+        self.entities.clear();
+        self.entities.extend(handle);
+        self.primary = handle;
+    }
+    
+    /// Replace the selection with several entities
+    pub fn set_many(&mut self, handles: impl IntoIterator<Item = EntityHandle>, primary: Option<EntityHandle>) {
+        self.entities = handles.into_iter().collect();
+        self.primary = primary.filter(|p| self.entities.contains(p))
+            .or_else(|| self.entities.iter().next().copied());
+    }
+    
+    /// Add an entity and make it the primary
+    pub fn add(&mut self, handle: EntityHandle) {
+        self.entities.insert(handle);
+        self.primary = Some(handle);
+    }
+    
+    /// Remove an entity, returning true if it was selected
+    pub fn remove(&mut self, handle: &EntityHandle) -> bool {
+        let removed = self.entities.remove(handle);
+        if self.primary == Some(*handle) {
+            self.primary = self.entities.iter().next().copied();
+        }
+        removed
     }
-} 
\ No newline at end of file
+    
+    /// Add the entity if it isn't selected, remove it if it is
+    pub fn toggle(&mut self, handle: EntityHandle) {
+        if !self.remove(&handle) {
+            self.add(handle);
+        }
+    }
+    
+    /// Deselect everything
+    pub fn clear(&mut self) {
+        self.set(None);
+    }
+    
+    /// Whether the entity is selected
+    pub fn contains(&self, handle: &EntityHandle) -> bool {
+        self.entities.contains(handle)
+    }
+    
+    /// The primary selected entity
+    pub fn primary(&self) -> Option<EntityHandle> {
+        self.primary
+    }
+    
+    /// Number of selected entities
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+    
+    /// Whether nothing is selected
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+    
+    /// Iterate the selected entities
+    pub fn iter(&self) -> impl Iterator<Item = &EntityHandle> {
+        self.entities.iter()
+    }
+}
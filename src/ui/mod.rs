@@ -3,5 +3,5 @@ mod window;
 mod editor;
 
 pub use app::MirageApp;
-pub use window::WindowManager;
+pub use window::{FullscreenMode, WindowManager};
 pub use editor::*; 
\ No newline at end of file
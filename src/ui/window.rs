@@ -1,9 +1,24 @@
 use winit::{
+    dpi::PhysicalSize,
     event::{Event, WindowEvent, KeyboardInput, ElementState, VirtualKeyCode, MouseButton},
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    monitor::{MonitorHandle, VideoMode},
+    window::{Fullscreen, Window, WindowBuilder},
 };
-use log::{info, debug};
+use log::{info, debug, error, warn};
+use crate::rendering::Renderer;
+
+/// How the window covers the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullscreenMode {
+    /// A regular window with decorations
+    #[default]
+    Windowed,
+    /// A borderless window covering the monitor, at the monitor's resolution
+    Borderless,
+    /// The monitor switches to a video mode matching the resolution
+    Exclusive,
+}
 
 pub struct KeyboardState {
     pub keys_pressed: Vec<VirtualKeyCode>,
@@ -83,6 +98,14 @@ pub struct WindowManager {
     mouse_state: MouseState,
     window_size: (u32, u32),
     should_close: bool,
+    fullscreen: FullscreenMode,
+    /// Size asked for with `set_resolution`, used by windowed and exclusive mode
+    resolution: (u32, u32),
+    vsync: bool,
+    /// Set when vsync changed since the last `apply_to_renderer`
+    vsync_changed: bool,
+    /// Size last passed to the renderer by `apply_to_renderer`
+    renderer_size: Option<(u32, u32)>,
 }
 
 impl WindowManager {
@@ -97,14 +120,20 @@ impl WindowManager {
             .expect("Failed to create window");
         
         info!("Window created: {}x{}", width, height);
+        let size = window.inner_size();
         
         Self {
             event_loop: Some(event_loop),
             window: Some(window),
             keyboard_state: KeyboardState::new(),
             mouse_state: MouseState::new(),
-            window_size: (width, height),
+            window_size: (size.width, size.height),
             should_close: false,
+            fullscreen: FullscreenMode::Windowed,
+            resolution: (size.width, size.height),
+            vsync: true,
+            vsync_changed: false,
+            renderer_size: None,
         }
     }
     
@@ -132,6 +161,118 @@ impl WindowManager {
         self.should_close = true;
     }
     
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.fullscreen
+    }
+    
+    /// Switch between windowed and fullscreen
+    ///
+    /// Exclusive fullscreen uses the monitor's video mode matching the resolution,
+    /// or its largest one. Without any video mode it falls back to borderless with a
+    /// warning. Returns the mode actually used.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) -> FullscreenMode {
+        let Some(window) = &self.window else {
+            return self.fullscreen;
+        };
+        
+        let mode = match mode {
+            FullscreenMode::Windowed => {
+                window.set_fullscreen(None);
+                window.set_inner_size(PhysicalSize::new(self.resolution.0, self.resolution.1));
+                FullscreenMode::Windowed
+            }
+            FullscreenMode::Borderless => {
+                window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+                FullscreenMode::Borderless
+            }
+            FullscreenMode::Exclusive => {
+                match window.current_monitor().and_then(|monitor| select_video_mode(&monitor, self.resolution)) {
+                    Some(video_mode) => {
+                        let size = video_mode.size();
+                        info!("Exclusive fullscreen at {}x{}, {} mHz", size.width, size.height, video_mode.refresh_rate_millihertz());
+                        window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+                        FullscreenMode::Exclusive
+                    }
+                    None => {
+                        warn!("The monitor reports no video modes, using borderless fullscreen instead of exclusive");
+                        window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+                        FullscreenMode::Borderless
+                    }
+                }
+            }
+        };
+        self.fullscreen = mode;
+        mode
+    }
+    
+    /// Go fullscreen borderless from a window, or back to the window from any fullscreen mode
+    pub fn toggle_fullscreen(&mut self) -> FullscreenMode {
+        match self.fullscreen {
+            FullscreenMode::Windowed => self.set_fullscreen(FullscreenMode::Borderless),
+            FullscreenMode::Borderless | FullscreenMode::Exclusive => self.set_fullscreen(FullscreenMode::Windowed),
+        }
+    }
+    
+    /// Set the size of the window in physical pixels
+    ///
+    /// In exclusive fullscreen the video mode closest to it is picked again. Borderless
+    /// fullscreen always has the monitor's size, the resolution applies once windowed.
+    /// The renderer follows with the next `apply_to_renderer`.
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            warn!("Ignoring invalid resolution {}x{}", width, height);
+            return;
+        }
+        self.resolution = (width, height);
+        match self.fullscreen {
+            FullscreenMode::Windowed => {
+                if let Some(window) = &self.window {
+                    window.set_inner_size(PhysicalSize::new(width, height));
+                }
+            }
+            FullscreenMode::Exclusive => {
+                self.set_fullscreen(FullscreenMode::Exclusive);
+            }
+            FullscreenMode::Borderless => {}
+        }
+    }
+    
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+    
+    /// Wait for the display's vertical blank before presenting, applied by `apply_to_renderer`
+    pub fn set_vsync(&mut self, vsync: bool) {
+        if vsync != self.vsync {
+            self.vsync = vsync;
+            self.vsync_changed = true;
+        }
+    }
+    
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
+    }
+    
+    /// Resize the renderer to the window and apply a vsync change, if there was any since the last call
+    ///
+    /// Call it once per frame before rendering, so size changes from resizing,
+    /// `set_resolution` and `set_fullscreen` reach the renderer.
+    pub fn apply_to_renderer(&mut self, renderer: &mut Renderer) {
+        if self.renderer_size != Some(self.window_size) {
+            let (width, height) = self.window_size;
+            if let Err(e) = renderer.resize(width, height) {
+                error!("Failed to resize renderer: {}", e);
+            }
+            self.renderer_size = Some(self.window_size);
+        }
+        if self.vsync_changed {
+            renderer.set_vsync(self.vsync);
+            self.vsync_changed = false;
+        }
+    }
+    
     pub fn run<F>(mut self, mut callback: F) 
     where 
         F: FnMut(&mut Self, &Event<()>) + 'static
@@ -152,6 +293,10 @@ impl WindowManager {
                     self.window_size = (physical_size.width, physical_size.height);
                 }
                 
+                Event::WindowEvent { event: WindowEvent::ScaleFactorChanged { ref new_inner_size, .. }, .. } => {
+                    self.window_size = (new_inner_size.width, new_inner_size.height);
+                }
+                
                 Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } => {
                     self.handle_keyboard_input(input);
                 }
@@ -198,6 +343,9 @@ impl WindowManager {
             match input.state {
                 ElementState::Pressed => {
                     if !self.keyboard_state.keys_down.contains(&key_code) {
+                        if key_code == VirtualKeyCode::F11 {
+                            self.toggle_fullscreen();
+                        }
                         self.keyboard_state.keys_pressed.push(key_code);
                         self.keyboard_state.keys_down.push(key_code);
                     }
@@ -228,4 +376,23 @@ impl WindowManager {
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+/// The monitor's video mode of exactly `resolution` with the highest refresh rate, or else its largest one
+fn select_video_mode(monitor: &MonitorHandle, resolution: (u32, u32)) -> Option<VideoMode> {
+    let (width, height) = resolution;
+    let modes: Vec<VideoMode> = monitor.video_modes().collect();
+    let exact = modes.iter()
+        .filter(|mode| mode.size() == PhysicalSize::new(width, height))
+        .max_by_key(|mode| (mode.refresh_rate_millihertz(), mode.bit_depth()));
+    match exact {
+        Some(mode) => Some(mode.clone()),
+        None => {
+            let largest = modes.into_iter()
+                .max_by_key(|mode| (mode.size().width * mode.size().height, mode.refresh_rate_millihertz(), mode.bit_depth()))?;
+            let size = largest.size();
+            warn!("The monitor has no {}x{} video mode, using {}x{}", width, height, size.width, size.height);
+            Some(largest)
+        }
+    }
+}
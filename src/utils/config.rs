@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use log::warn;
+use serde::{Serialize, Deserialize};
+
+/// Name of the editor's folder in the OS config directory
+pub const CONFIG_DIRECTORY: &str = "mirage-engine";
+
+/// File the editor settings are saved to, in `CONFIG_DIRECTORY`
+pub const SETTINGS_FILE_NAME: &str = "editor_settings.ron";
+
+/// How many projects `EditorSettings::recent_projects` remembers
+pub const MAX_RECENT_PROJECTS: usize = 10;
+
+/// How many scenes `EditorSettings::recent_scenes` remembers
+pub const MAX_RECENT_SCENES: usize = 10;
+
+/// Where a config file named `file_name` is kept, `None` if the OS has no config directory
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|directory| directory.join(CONFIG_DIRECTORY).join(file_name))
+}
+
+/// Read a RON config file, `None` if it doesn't exist or can't be read
+///
+/// Failures other than a missing file are logged.
+pub fn load_config<T: for<'de> Deserialize<'de>>(file_name: &str) -> Option<T> {
+    let path = config_path(file_name)?;
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        // Nothing saved yet
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    ron::from_str(&source)
+        .map_err(|e| warn!("Failed to parse {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Write `value` to a RON config file, creating the config directory
+pub fn save_config<T: Serialize>(file_name: &str, value: &T) -> Result<()> {
+    let path = config_path(file_name).ok_or_else(|| anyhow::anyhow!("No config directory to save {} in", file_name))?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(&path, ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?)?;
+    Ok(())
+}
+
+/// Color scheme of the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// User preferences of the editor, kept across restarts
+///
+/// Fields missing from a saved file take their default, so settings saved by
+/// older versions still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorSettings {
+    /// Whether the scene view draws its grid
+    pub show_grid: bool,
+    /// World units between the scene view's grid lines
+    pub grid_size: f32,
+    /// Snap moved entities to the grid cells without holding Ctrl
+    pub snap_to_grid: bool,
+    /// Position snap increment in world units
+    pub grid_snap: f32,
+    /// Rotation snap increment in degrees
+    pub angle_snap: f32,
+    /// Scale snap increment
+    pub scale_snap: f32,
+    /// Smallest zoom of the scene view camera
+    pub min_zoom: f32,
+    /// Largest zoom of the scene view camera
+    pub max_zoom: f32,
+    pub theme: ThemeMode,
+    /// Seconds between autosaves of a scene with unsaved changes to the recovery file, 0 turns them off
+    pub autosave_interval: f32,
+    /// Projects opened in the editor, most recent first
+    pub recent_projects: Vec<PathBuf>,
+    /// Scenes opened or saved in the editor, most recent first
+    pub recent_scenes: Vec<PathBuf>,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            show_grid: true,
+            grid_size: 1.0,
+            snap_to_grid: false,
+            grid_snap: 0.25,
+            angle_snap: 15.0,
+            scale_snap: 0.1,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            theme: ThemeMode::Dark,
+            autosave_interval: 300.0,
+            recent_projects: Vec::new(),
+            recent_scenes: Vec::new(),
+        }
+    }
+}
+
+impl EditorSettings {
+    /// The saved settings, or the defaults if there are none or they can't be read
+    ///
+    /// Values that make no sense, like a negative grid, are replaced by their defaults.
+    pub fn load() -> Self {
+        let mut settings: Self = load_config(SETTINGS_FILE_NAME).unwrap_or_default();
+        settings.sanitize();
+        settings
+    }
+
+    /// Save the settings to their file in the config directory
+    pub fn save(&self) -> Result<()> {
+        save_config(SETTINGS_FILE_NAME, self)
+    }
+
+    /// Put `path` at the top of `recent_projects`
+    pub fn add_recent_project(&mut self, path: &Path) {
+        add_recent(&mut self.recent_projects, path, MAX_RECENT_PROJECTS);
+    }
+
+    /// Put `path` at the top of `recent_scenes`
+    pub fn add_recent_scene(&mut self, path: &Path) {
+        add_recent(&mut self.recent_scenes, path, MAX_RECENT_SCENES);
+    }
+
+    /// Forget the recent projects and scenes
+    pub fn clear_recent(&mut self) {
+        self.recent_projects.clear();
+        self.recent_scenes.clear();
+    }
+
+    /// Replace values out of their range with the defaults
+    pub fn sanitize(&mut self) {
+        let defaults = Self::default();
+        let positive = |value: f32, default: f32| if value.is_finite() && value > 0.0 { value } else { default };
+
+        self.grid_size = positive(self.grid_size, defaults.grid_size);
+        self.grid_snap = positive(self.grid_snap, defaults.grid_snap);
+        self.angle_snap = positive(self.angle_snap, defaults.angle_snap);
+        self.scale_snap = positive(self.scale_snap, defaults.scale_snap);
+        self.min_zoom = positive(self.min_zoom, defaults.min_zoom);
+        self.max_zoom = positive(self.max_zoom, defaults.max_zoom);
+        if self.min_zoom > self.max_zoom {
+            self.min_zoom = defaults.min_zoom;
+            self.max_zoom = defaults.max_zoom;
+        }
+        if !self.autosave_interval.is_finite() || self.autosave_interval < 0.0 {
+            self.autosave_interval = defaults.autosave_interval;
+        }
+        self.recent_projects.truncate(MAX_RECENT_PROJECTS);
+        self.recent_scenes.truncate(MAX_RECENT_SCENES);
+    }
+}
+
+/// Move `path` to the front of a most-recent-first list, keeping at most `max` entries
+fn add_recent(list: &mut Vec<PathBuf>, path: &Path, max: usize) {
+    list.retain(|entry| entry != path);
+    list.insert(0, path.to_path_buf());
+    list.truncate(max);
+}
@@ -0,0 +1,389 @@
+pub use glam::{EulerRot, Mat4, Quat, Vec2, Vec3, Vec4};
+
+/// Order the editor's Euler angles are applied in
+pub const EULER_ORDER: EulerRot = EulerRot::XYZ;
+
+/// Matrix that scales, then rotates, then translates
+///
+/// Every transform in the engine is composed through this function.
+pub fn compose(position: Vec3, rotation: Quat, scale: Vec3) -> Mat4 {
+    Mat4::from_scale_rotation_translation(scale, rotation, position)
+}
+
+/// Matrix of a 2D transform, rotating by `rotation` radians around the Z axis
+pub fn compose_2d(position: Vec2, rotation: f32, scale: Vec2) -> Mat4 {
+    compose(position.extend(0.0), Quat::from_rotation_z(rotation), scale.extend(1.0))
+}
+
+/// Rotation from Euler angles in degrees, as shown in the editor
+pub fn quat_from_euler_degrees(degrees: [f32; 3]) -> Quat {
+    Quat::from_euler(
+        EULER_ORDER,
+        degrees[0].to_radians(),
+        degrees[1].to_radians(),
+        degrees[2].to_radians(),
+    )
+}
+
+/// Euler angles in degrees of a rotation, the inverse of `quat_from_euler_degrees`
+pub fn euler_degrees_from_quat(rotation: Quat) -> [f32; 3] {
+    let (x, y, z) = rotation.to_euler(EULER_ORDER);
+    [x.to_degrees(), y.to_degrees(), z.to_degrees()]
+}
+
+/// Rotate `point` counter-clockwise around the origin by `angle` radians
+pub fn rotate_2d(point: Vec2, angle: f32) -> Vec2 {
+    Vec2::from_angle(angle).rotate(point)
+}
+
+/// Half size of the axis aligned box around a box of half size `half_size` rotated by `angle` radians
+pub fn rotated_extents(half_size: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    Vec2::new(
+        half_size.x * cos + half_size.y * sin,
+        half_size.x * sin + half_size.y * cos,
+    )
+}
+
+/// Convex hull of the points, counter-clockwise and without collinear points
+///
+/// Returns fewer than 3 points if all the points lie on one line.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Monotone chain: lower hull left to right, then upper hull right to left
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &point in &sorted {
+            while hull.len() >= start + 2 && (hull[hull.len() - 1] - hull[hull.len() - 2]).perp_dot(point - hull[hull.len() - 2]) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point starts the other half
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// Whether a polygon is convex with counter-clockwise vertices and a non-zero area
+pub fn is_convex_polygon(vertices: &[Vec2]) -> bool {
+    if vertices.len() < 3 {
+        return false;
+    }
+    let turns_left = (0..vertices.len()).all(|i| {
+        let (a, b, c) = (vertices[i], vertices[(i + 1) % vertices.len()], vertices[(i + 2) % vertices.len()]);
+        (b - a).perp_dot(c - b) >= 0.0
+    });
+    let doubled_area: f32 = (0..vertices.len())
+        .map(|i| vertices[i].perp_dot(vertices[(i + 1) % vertices.len()]))
+        .sum();
+    turns_left && doubled_area > 0.0
+}
+
+/// Half line from `origin` along `direction`, which is kept normalized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Create a ray, `direction` doesn't need to be normalized
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction: direction.normalize_or_zero() }
+    }
+
+    /// Ray between two points, starting at `from`
+    pub fn between(from: Vec3, to: Vec3) -> Self {
+        Self::new(from, to - from)
+    }
+
+    /// The point `distance` along the ray
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing the points, `None` without points
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |bounds, point| {
+            Self::new(bounds.min.min(point), bounds.max.max(point))
+        }))
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// The eight corners
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Axis aligned box around this box transformed by `matrix`
+    pub fn transformed(&self, matrix: Mat4) -> Aabb {
+        Self::from_points(self.corners().map(|corner| matrix.transform_point3(corner)))
+            .expect("a box has corners")
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Distance along the ray to where it enters the box, 0 if it starts inside
+    ///
+    /// Returns `None` if the ray misses the box or the box is behind it.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let mut near = 0.0_f32;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            let origin = ray.origin[axis];
+            let direction = ray.direction[axis];
+            let (min, max) = (self.min[axis], self.max[axis]);
+
+            // Parallel to the slab, the origin has to be between its planes
+            if direction.abs() < f32::EPSILON {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = 1.0 / direction;
+            let (t0, t1) = ((min - origin) * inverse, (max - origin) * inverse);
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+}
+
+/// Plane of the points `p` where `normal.dot(p) + distance` is 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Plane from the `(a, b, c, d)` of `ax + by + cz + d = 0`, normalized
+    pub fn from_coefficients(coefficients: Vec4) -> Self {
+        let length = coefficients.truncate().length();
+        let coefficients = if length > 0.0 { coefficients / length } else { coefficients };
+        Self { normal: coefficients.truncate(), distance: coefficients.w }
+    }
+
+    /// Distance from the plane, positive on the side the normal points to
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// Volume a camera sees, bounded by six planes facing inwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Frustum of a view-projection matrix with a 0 to 1 depth range
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let [row0, row1, row2, row3] = [0, 1, 2, 3].map(|i| view_projection.row(i));
+        Self {
+            planes: [
+                Plane::from_coefficients(row3 + row0),
+                Plane::from_coefficients(row3 - row0),
+                Plane::from_coefficients(row3 + row1),
+                Plane::from_coefficients(row3 - row1),
+                Plane::from_coefficients(row2),
+                Plane::from_coefficients(row3 - row2),
+            ],
+        }
+    }
+
+    /// Whether a point is inside the frustum or on its boundary
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Whether a box might be inside the frustum
+    ///
+    /// A box is only rejected when it's entirely behind one plane, so some boxes
+    /// near the frustum's edges are reported as intersecting when they aren't.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the normal
+            let corner = Vec3::select(plane.normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_rotation(a: Quat, b: Quat) -> bool {
+        // q and -q are the same rotation
+        a.dot(b).abs() > 1.0 - 1e-5
+    }
+
+    #[test]
+    fn euler_degrees_round_trip() {
+        for degrees in [[0.0, 0.0, 0.0], [30.0, -45.0, 60.0], [-170.0, 80.0, 10.0], [90.0, 0.0, -90.0], [12.5, -75.0, 179.0]] {
+            let back = euler_degrees_from_quat(quat_from_euler_degrees(degrees));
+            for (expected, actual) in degrees.iter().zip(back) {
+                assert!((expected - actual).abs() < 1e-2, "{degrees:?} came back as {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn euler_angles_apply_in_xyz_order() {
+        let rotation = quat_from_euler_degrees([10.0, 20.0, 30.0]);
+        let expected = Quat::from_rotation_x(10f32.to_radians())
+            * Quat::from_rotation_y(20f32.to_radians())
+            * Quat::from_rotation_z(30f32.to_radians());
+        assert!(same_rotation(rotation, expected));
+    }
+
+    #[test]
+    fn single_axis_rotations_turn_the_right_way() {
+        assert!((quat_from_euler_degrees([90.0, 0.0, 0.0]) * Vec3::Y).abs_diff_eq(Vec3::Z, 1e-6));
+        assert!((quat_from_euler_degrees([0.0, 90.0, 0.0]) * Vec3::Z).abs_diff_eq(Vec3::X, 1e-6));
+        assert!((quat_from_euler_degrees([0.0, 0.0, 90.0]) * Vec3::X).abs_diff_eq(Vec3::Y, 1e-6));
+    }
+
+    #[test]
+    fn gimbal_lock_keeps_the_rotation() {
+        // At 90 degrees around Y, X and Z turn around the same axis and the angles aren't unique
+        let rotation = quat_from_euler_degrees([30.0, 90.0, 20.0]);
+        let back = quat_from_euler_degrees(euler_degrees_from_quat(rotation));
+        assert!(same_rotation(rotation, back));
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0))
+    }
+
+    #[test]
+    fn ray_hits_the_near_face() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        let distance = unit_box().intersect_ray(&ray).unwrap();
+        assert!((distance - 4.0).abs() < 1e-6);
+        assert!(ray.at(distance).abs_diff_eq(Vec3::new(0.0, 0.0, 1.0), 1e-6));
+
+        let diagonal = Ray::between(Vec3::splat(3.0), Vec3::ZERO);
+        let distance = unit_box().intersect_ray(&diagonal).unwrap();
+        assert!(diagonal.at(distance).abs_diff_eq(Vec3::ONE, 1e-5));
+    }
+
+    #[test]
+    fn ray_misses_boxes_beside_and_behind_it() {
+        assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(3.0, 0.0, 5.0), Vec3::NEG_Z)), None);
+        assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::Z)), None);
+        assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(1.0, 0.0, -1.0))), None);
+    }
+
+    #[test]
+    fn ray_parallel_to_a_slab_hits_only_between_its_planes() {
+        assert!(unit_box().intersect_ray(&Ray::new(Vec3::new(-5.0, 0.5, 0.0), Vec3::X)).is_some());
+        assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(-5.0, 1.5, 0.0), Vec3::X)), None);
+        assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(-5.0, 0.0, -1.5), Vec3::X)), None);
+    }
+
+    #[test]
+    fn ray_starting_inside_hits_at_zero() {
+        for direction in [Vec3::X, Vec3::NEG_Y, Vec3::new(1.0, 2.0, -3.0)] {
+            assert_eq!(unit_box().intersect_ray(&Ray::new(Vec3::new(0.2, -0.3, 0.5), direction)), Some(0.0));
+        }
+    }
+
+    /// Camera at the origin looking down -Z, seeing from 1 to 100 units away with a 90 degree field of view
+    fn camera_frustum() -> Frustum {
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 1.0, 100.0);
+        Frustum::from_view_projection(projection)
+    }
+
+    #[test]
+    fn frustum_keeps_boxes_in_view() {
+        let frustum = camera_frustum();
+        assert!(frustum.intersects_aabb(&Aabb::new(Vec3::new(-1.0, -1.0, -11.0), Vec3::new(1.0, 1.0, -9.0))));
+        // Straddling the left plane, which at 10 units away is at x = -10
+        assert!(frustum.intersects_aabb(&Aabb::new(Vec3::new(-12.0, -1.0, -11.0), Vec3::new(-9.0, 1.0, -9.0))));
+        // Bigger than the frustum
+        assert!(frustum.intersects_aabb(&Aabb::new(Vec3::splat(-500.0), Vec3::splat(500.0))));
+    }
+
+    #[test]
+    fn frustum_rejects_boxes_outside_one_plane() {
+        let frustum = camera_frustum();
+        let outside = [
+            // Behind the camera
+            Aabb::new(Vec3::new(-1.0, -1.0, 2.0), Vec3::new(1.0, 1.0, 4.0)),
+            // Closer than the near plane
+            Aabb::new(Vec3::new(-0.1, -0.1, -0.5), Vec3::new(0.1, 0.1, -0.2)),
+            // Past the far plane
+            Aabb::new(Vec3::new(-1.0, -1.0, -120.0), Vec3::new(1.0, 1.0, -101.0)),
+            // Left, right, below and above
+            Aabb::new(Vec3::new(-14.0, -1.0, -11.0), Vec3::new(-12.0, 1.0, -9.0)),
+            Aabb::new(Vec3::new(12.0, -1.0, -11.0), Vec3::new(14.0, 1.0, -9.0)),
+            Aabb::new(Vec3::new(-1.0, -14.0, -11.0), Vec3::new(1.0, -12.0, -9.0)),
+            Aabb::new(Vec3::new(-1.0, 12.0, -11.0), Vec3::new(1.0, 14.0, -9.0)),
+        ];
+        for aabb in outside {
+            assert!(!frustum.intersects_aabb(&aabb), "{:?}", aabb);
+        }
+    }
+
+    #[test]
+    fn frustum_contains_points_between_its_planes() {
+        let frustum = camera_frustum();
+        assert!(frustum.contains(Vec3::new(0.0, 0.0, -50.0)));
+        assert!(frustum.contains(Vec3::new(9.0, -9.0, -10.0)));
+        assert!(!frustum.contains(Vec3::new(11.0, 0.0, -10.0)));
+        assert!(!frustum.contains(Vec3::new(0.0, 0.0, -0.5)));
+    }
+}
@@ -1 +1,3 @@
-// Modul untuk utils
\ No newline at end of file
+pub mod config;
+pub mod math;
+pub mod tween;
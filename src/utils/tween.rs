@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use crate::utils::math::{Vec2, Vec3, Vec4};
+
+/// Values that can be interpolated by a `Tween`
+pub trait Lerp: Copy {
+    /// The value a fraction `t` of the way from `self` to `end`
+    fn lerp(self, end: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, end: Self, t: f32) -> Self {
+        self + (end - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, end: Self, t: f32) -> Self {
+        Vec2::lerp(self, end, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(self, end: Self, t: f32) -> Self {
+        Vec3::lerp(self, end, t)
+    }
+}
+
+/// RGBA colors, as used by sprites and lights
+impl Lerp for Vec4 {
+    fn lerp(self, end: Self, t: f32) -> Self {
+        Vec4::lerp(self, end, t)
+    }
+}
+
+impl Lerp for wgpu::Color {
+    fn lerp(self, end: Self, t: f32) -> Self {
+        let t = t as f64;
+        wgpu::Color {
+            r: self.r + (end.r - self.r) * t,
+            g: self.g + (end.g - self.g) * t,
+            b: self.b + (end.b - self.b) * t,
+            a: self.a + (end.a - self.a) * t,
+        }
+    }
+}
+
+/// Curve mapping the linear progress of a tween to the interpolation factor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseInQuad,
+    EaseOutQuad,
+    EaseInOutQuad,
+    EaseInCubic,
+    EaseOutCubic,
+    EaseInOutCubic,
+    /// Bounces off the end value like a dropped ball
+    Bounce,
+}
+
+impl Easing {
+    /// Ease `t`, clamped to 0..=1; 0 and 1 map exactly to 0 and 1
+    pub fn apply(self, t: f32) -> f32 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        if t >= 1.0 {
+            return 1.0;
+        }
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::EaseInCubic => t * t * t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Bounce => bounce_out(t),
+        }
+    }
+}
+
+/// Standard ease-out bounce with four bounces
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// What a tween does once it reaches the end value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepeatMode {
+    /// Stop at the end value
+    #[default]
+    Once,
+    /// Jump back to the start value and play again
+    Loop,
+    /// Play backwards to the start value, then forwards again
+    PingPong,
+}
+
+/// Drives a value from `start` to `end` over `duration` seconds
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    start: T,
+    end: T,
+    duration: f32,
+    easing: Easing,
+    repeat: RepeatMode,
+    /// Seconds since the start, wrapped for repeating tweens
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Create a tween that plays once
+    pub fn new(start: T, end: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            start,
+            end,
+            duration,
+            easing,
+            repeat: RepeatMode::Once,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Set what happens when the tween reaches the end value
+    pub fn with_repeat(mut self, repeat: RepeatMode) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Advance by `delta_time` seconds and return the new value
+    pub fn update(&mut self, delta_time: f32) -> T {
+        self.elapsed += delta_time;
+
+        // Keep repeating tweens within one cycle so precision doesn't degrade over time
+        if self.duration > 0.0 {
+            match self.repeat {
+                RepeatMode::Once => self.elapsed = self.elapsed.min(self.duration),
+                RepeatMode::Loop => self.elapsed %= self.duration,
+                RepeatMode::PingPong => self.elapsed %= self.duration * 2.0,
+            }
+        }
+
+        self.value()
+    }
+
+    /// The current value
+    pub fn value(&self) -> T {
+        let t = self.easing.apply(self.progress());
+        if t == 0.0 {
+            self.start
+        } else if t == 1.0 {
+            self.end
+        } else {
+            self.start.lerp(self.end, t)
+        }
+    }
+
+    /// Linear progress from the start (0) to the end value (1), before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        let t = self.elapsed / self.duration;
+        match self.repeat {
+            RepeatMode::PingPong if t > 1.0 => 2.0 - t,
+            _ => t.min(1.0),
+        }
+    }
+
+    /// Whether a tween that plays once has reached the end, repeating tweens never finish
+    pub fn is_finished(&self) -> bool {
+        self.repeat == RepeatMode::Once && self.elapsed >= self.duration
+    }
+
+    /// Go back to the start value
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+}
+
+/// Identifies a tween in a `TweenManager`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TweenId(u32);
+
+/// Owns tweens of one value type and advances them together
+pub struct TweenManager<T> {
+    tweens: HashMap<TweenId, Tween<T>>,
+    next_id: u32,
+}
+
+impl<T: Lerp> TweenManager<T> {
+    pub fn new() -> Self {
+        Self {
+            tweens: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Start managing a tween
+    pub fn add(&mut self, tween: Tween<T>) -> TweenId {
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+        self.tweens.insert(id, tween);
+        id
+    }
+
+    /// Stop managing a tween, returning it
+    pub fn remove(&mut self, id: TweenId) -> Option<Tween<T>> {
+        self.tweens.remove(&id)
+    }
+
+    pub fn get(&self, id: TweenId) -> Option<&Tween<T>> {
+        self.tweens.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: TweenId) -> Option<&mut Tween<T>> {
+        self.tweens.get_mut(&id)
+    }
+
+    /// Current value of a tween
+    pub fn value(&self, id: TweenId) -> Option<T> {
+        self.tweens.get(&id).map(Tween::value)
+    }
+
+    /// Advance every tween by `delta_time` seconds
+    pub fn update(&mut self, delta_time: f32) {
+        for tween in self.tweens.values_mut() {
+            tween.update(delta_time);
+        }
+    }
+
+    /// Remove the tweens that finished, returning their final values
+    pub fn take_finished(&mut self) -> Vec<(TweenId, T)> {
+        let finished: Vec<TweenId> = self.tweens.iter()
+            .filter(|(_, tween)| tween.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+
+        finished.into_iter()
+            .filter_map(|id| self.tweens.remove(&id).map(|tween| (id, tween.value())))
+            .collect()
+    }
+
+    /// Number of tweens being managed
+    pub fn len(&self) -> usize {
+        self.tweens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tweens.is_empty()
+    }
+
+    /// Remove every tween
+    pub fn clear(&mut self) {
+        self.tweens.clear();
+    }
+}
+
+impl<T: Lerp> Default for TweenManager<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 8] = [
+        Easing::Linear,
+        Easing::EaseInQuad,
+        Easing::EaseOutQuad,
+        Easing::EaseInOutQuad,
+        Easing::EaseInCubic,
+        Easing::EaseOutCubic,
+        Easing::EaseInOutCubic,
+        Easing::Bounce,
+    ];
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+            assert_eq!(easing.apply(-0.5), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(2.0), 1.0, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn easings_are_continuous_at_the_boundaries() {
+        for easing in EASINGS {
+            assert!(easing.apply(1e-4).abs() < 1e-3, "{easing:?}");
+            assert!((easing.apply(1.0 - 1e-4) - 1.0).abs() < 1e-3, "{easing:?}");
+        }
+        // The halves of the in-out curves meet in the middle
+        for easing in [Easing::EaseInOutQuad, Easing::EaseInOutCubic] {
+            assert!((easing.apply(0.5 - 1e-4) - easing.apply(0.5 + 1e-4)).abs() < 1e-3, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn tween_lands_exactly_on_its_end_value() {
+        let mut tween = Tween::new(0.1, 0.7, 0.3, Easing::EaseOutCubic);
+        for _ in 0..5 {
+            tween.update(0.1);
+        }
+        assert_eq!(tween.value(), 0.7);
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn ping_pong_returns_to_the_start() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0, Easing::Linear).with_repeat(RepeatMode::PingPong);
+        assert_eq!(tween.update(0.5), 5.0);
+        assert_eq!(tween.update(1.0), 5.0);
+        assert_eq!(tween.update(0.5), 0.0);
+        assert!(!tween.is_finished());
+    }
+}